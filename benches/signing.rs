@@ -0,0 +1,163 @@
+//! Throughput of the signing hot path and of bulk address derivation.
+//!
+//! Run with `cargo bench --bench signing`.
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+use flow_wallet::node::RawTransaction;
+use flow_wallet::wallet::Signer;
+use flow_wallet::wallet::chain::{
+    Chain, LITECOIN, SighashType, TRON, UtxoAddressType, UtxoChain, UtxoInput, UtxoTxBuilder,
+};
+use flow_wallet::wallet::key_source::{Bip44Path, KeySource, MnemonicKeySource};
+use flow_wallet::wallet::signer::LocalSigner;
+
+const INPUTS: usize = 1_000;
+const ADDRESSES: usize = 10_000;
+
+fn signer(seed: u32) -> LocalSigner {
+    let mut secret = [1u8; 32];
+    secret[28..].copy_from_slice(&seed.to_be_bytes());
+    LocalSigner::from_bytes(secret).expect("valid scalar")
+}
+
+const LITECOIN_SEGWIT: UtxoChain = LITECOIN.with_address_type(UtxoAddressType::P2wpkh);
+
+/// Consolidation of `INPUTS` P2WPKH coins owned by `pubkey` into one output.
+fn utxo_transaction(pubkey: &[u8]) -> RawTransaction {
+    let address = LITECOIN_SEGWIT.address_from_pubkey(pubkey).unwrap();
+    let script_pubkey = LITECOIN_SEGWIT.script_pubkey(&address).unwrap();
+    let builder = (0..INPUTS as u32).fold(UtxoTxBuilder::new(&LITECOIN_SEGWIT), |b, i| {
+        let mut prev_hash = [0u8; 32];
+        prev_hash[..4].copy_from_slice(&i.to_be_bytes());
+        b.input(UtxoInput {
+            prev_hash,
+            output_index: 0,
+            value: 10_000,
+            script_pubkey: script_pubkey.clone(),
+            sequence: 0xffff_ffff,
+            sighash: SighashType::All,
+        })
+    });
+    builder
+        .pay(&address, INPUTS as u64 * 9_000)
+        .unwrap()
+        .build()
+        .unwrap()
+        .into_raw()
+}
+
+fn sign_utxo_transaction(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime");
+    let signer = signer(0);
+    let pubkey = signer.public_key();
+    let skeleton = utxo_transaction(&pubkey);
+
+    let mut group = c.benchmark_group("utxo_1k_inputs");
+    group.throughput(Throughput::Elements(INPUTS as u64));
+    group.sample_size(10);
+
+    group.bench_function("prepare", |b| {
+        b.iter(|| LITECOIN_SEGWIT.prepare_transaction(&skeleton).unwrap())
+    });
+
+    group.bench_function("sign_and_finalize", |b| {
+        b.iter(|| {
+            let payloads = LITECOIN_SEGWIT.prepare_transaction(&skeleton).unwrap();
+            let signatures = runtime.block_on(async {
+                let mut signatures = Vec::with_capacity(payloads.len());
+                for payload in &payloads {
+                    signatures.push(signer.sign_prehash(payload).await.unwrap());
+                }
+                signatures
+            });
+            LITECOIN_SEGWIT
+                .finalize_transaction(&skeleton, &signatures, &pubkey)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn scan_addresses(c: &mut Criterion) {
+    let pubkeys: Vec<Vec<u8>> = (0..ADDRESSES as u32)
+        .map(|i| signer(i).public_key())
+        .collect();
+    let addresses: Vec<String> = pubkeys
+        .iter()
+        .map(|pk| TRON.address_from_pubkey(pk).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("scan_10k_addresses");
+    group.throughput(Throughput::Elements(ADDRESSES as u64));
+    group.sample_size(10);
+
+    group.bench_function("derive_tron", |b| {
+        b.iter(|| {
+            for pk in &pubkeys {
+                TRON.address_from_pubkey(pk).unwrap();
+            }
+        })
+    });
+    group.bench_function("derive_litecoin", |b| {
+        b.iter(|| {
+            for pk in &pubkeys {
+                LITECOIN.address_from_pubkey(pk).unwrap();
+            }
+        })
+    });
+    group.bench_function("validate_tron", |b| {
+        b.iter(|| {
+            for address in &addresses {
+                TRON.validate_address(address).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+/// An onboarding batch of `ADDRESSES` Tron deposit addresses from one seed:
+/// each full path from the seed, against [`MnemonicKeySource::derive_addresses`].
+fn derive_deposit_addresses(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime");
+    let source = MnemonicKeySource::random(None);
+    let root = Bip44Path::for_chain(&TRON);
+
+    let mut group = c.benchmark_group("derive_10k_addresses");
+    group.throughput(Throughput::Elements(ADDRESSES as u64));
+    group.sample_size(10);
+
+    group.bench_function("per_path", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                for index in 0..ADDRESSES as u32 {
+                    let signer = source
+                        .derive_signer_at(&root.external(index))
+                        .await
+                        .unwrap();
+                    TRON.address_from_pubkey(&signer.public_key()).unwrap();
+                }
+            })
+        })
+    });
+    group.bench_function("bulk", |b| {
+        b.iter(|| {
+            source
+                .derive_addresses(&TRON, 0, 0..ADDRESSES as u32)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    sign_utxo_transaction,
+    scan_addresses,
+    derive_deposit_addresses
+);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+use flow_wallet::node::{ReadProvider, network::prelude::*, utils::format_units};
+use flow_wallet::wallet::chain::{Chain, ETHEREUM};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let provider = EthProvider::new("https://ethereum-rpc.publicnode.com");
+
+    let balance: String = provider
+        .get_balance("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+        .await?;
+
+    let params = ETHEREUM.params();
+    println!(
+        "Balance: {} {}",
+        format_units(&balance, params.decimals),
+        params.symbol
+    );
+
+    Ok(())
+}
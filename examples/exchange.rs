@@ -0,0 +1,154 @@
+//! Exchange-style deposit / withdrawal flow on the Tron Nile testnet.
+//!
+//! - allocates one deposit address per customer from a single mnemonic
+//! - tracks incoming deposits until they confirm
+//! - sweeps confirmed deposits into the hot wallet, leaving a fee reserve behind
+//! - whitelists withdrawal addresses with ownership proofs
+//! - pays out withdrawals as a validated batch under the hot wallet's spend
+//!   limits, with an audit trail
+//!
+//! Runs read-only with a throwaway mnemonic unless `EXCHANGE_MNEMONIC` is set.
+
+use std::env;
+use std::sync::Arc;
+
+use flow_wallet::monitor::confirmations::ConfirmationPolicy;
+use flow_wallet::monitor::deposits::{DepositEvent, DepositTracker};
+use flow_wallet::node::network::tron::TronProvider;
+use flow_wallet::node::utils::format_units;
+use flow_wallet::node::{ProviderHealth, ReadProvider};
+use flow_wallet::wallet::Wallet;
+use flow_wallet::wallet::audit::MemoryAuditLog;
+use flow_wallet::wallet::chain::{Chain, TRON_NILE};
+use flow_wallet::wallet::key_source::{KeySource, MnemonicKeySource};
+use flow_wallet::wallet::ownership::OwnershipVerifier;
+use flow_wallet::wallet::payout::{PayoutBatch, PayoutLimits};
+use flow_wallet::wallet::policy::SpendLimits;
+
+const CUSTOMERS: u32 = 3;
+/// Deposits below this many Sun are left on the deposit address.
+const SWEEP_THRESHOLD: u64 = 2_000_000;
+/// Sun kept on each deposit address to pay for the sweep's bandwidth.
+const SWEEP_RESERVE: u64 = 1_000_000;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (source, live) = match env::var("EXCHANGE_MNEMONIC") {
+        Ok(phrase) => (MnemonicKeySource::new(&phrase, None)?, true),
+        Err(_) => (MnemonicKeySource::random(None), false),
+    };
+    let provider = Arc::new(TronProvider::nile());
+    let audit = Arc::new(MemoryAuditLog::new());
+
+    let ProviderHealth {
+        block_height,
+        latency,
+        sync,
+        ..
+    } = provider.health().await?;
+    println!("Nile at block {} ({:?}, {:?})", block_height, latency, sync);
+
+    // Hot wallet: account 0, index 0. Deposit addresses: account 1, one index per customer.
+    // Whatever reaches its key, it signs no more than 5 TRX at once or 50 TRX a day.
+    let hot = Wallet::new(source.derive_signer("m/44'/195'/0'/0/0").await?, TRON_NILE)
+        .with_audit_log(audit.clone())
+        .with_spend_policy(Arc::new(
+            SpendLimits::new()
+                .per_transaction(5_000_000)
+                .daily(50_000_000),
+        ));
+    let hot_address = hot.address()?;
+    println!("Hot wallet: {}", hot_address);
+
+    // 1. Deposit allocation and tracking
+    let mut deposits = Vec::new();
+    let mut tracker = DepositTracker::new(
+        provider.clone(),
+        TRON_NILE.id(),
+        ConfirmationPolicy::recommended(),
+    );
+    for customer in 0..CUSTOMERS {
+        let path = format!("m/44'/195'/1'/0/{}", customer);
+        let deposit = Wallet::new(source.derive_signer(&path).await?, TRON_NILE)
+            .with_audit_log(audit.clone());
+        tracker = tracker.watch(&deposit.address()?);
+        deposits.push(deposit);
+    }
+    // A service polls on an interval (`DepositTracker::run`); once is enough here.
+    let mut confirmed = Vec::new();
+    for event in tracker.poll().await? {
+        match event {
+            DepositEvent::Detected(d) => println!(
+                "  deposit {} to {}: {}/{} confirmations",
+                d.tx_hash, d.address, d.confirmations, d.required
+            ),
+            DepositEvent::Confirmed(d) => {
+                println!("  deposit {} to {} confirmed", d.tx_hash, d.address);
+                confirmed.push(d.address);
+            }
+            DepositEvent::Reverted(d) => println!("  deposit {} reverted", d.tx_hash),
+            DepositEvent::Error(e) => println!("  deposit poll failed: {}", e),
+        }
+    }
+
+    // 2. Sweeping confirmed deposits, less a reserve for fees
+    for (customer, deposit) in deposits.iter().enumerate() {
+        let address = deposit.address()?;
+        let balance: u64 = provider.get_balance(&address).await?.parse().unwrap_or(0);
+        println!(
+            "customer #{} deposit {} balance {} {}",
+            customer,
+            address,
+            format_units(&balance.to_string(), TRON_NILE.decimals()),
+            TRON_NILE.symbol()
+        );
+
+        if balance >= SWEEP_THRESHOLD && confirmed.contains(&address) && live {
+            let amount = balance - SWEEP_RESERVE;
+            match deposit.send_coins(&*provider, &hot_address, amount).await {
+                Ok(hash) => println!(
+                    "  swept -> {}",
+                    TRON_NILE.explorer_tx_url(&hash).unwrap_or(hash)
+                ),
+                Err(e) => println!("  sweep failed: {}", e),
+            }
+        }
+    }
+
+    // 3. Withdrawal address whitelisting: the customer proves control of the address.
+    let verifier = OwnershipVerifier::new();
+    let customer_wallet = Wallet::new(source.derive_signer("m/44'/195'/2'/0/0").await?, TRON_NILE);
+    let withdrawal_address = customer_wallet.address()?;
+    let challenge = verifier.issue(300);
+    let proof = customer_wallet.prove_ownership(&challenge).await?;
+    verifier.verify(&TRON_NILE, &withdrawal_address, &challenge, &proof)?;
+    println!("Whitelisted withdrawal address {}", withdrawal_address);
+
+    // 4. Withdrawals as one batch with limits
+    let csv = format!(
+        "address,amount,memo\n{},1000000,withdrawal-1\n",
+        withdrawal_address
+    );
+    let batch = PayoutBatch::from_csv(&csv)?.with_limits(PayoutLimits {
+        max_amount_per_tx: Some(5_000_000),
+        max_total: Some(50_000_000),
+        ..Default::default()
+    });
+    batch.validate(&TRON_NILE)?;
+    println!("Withdrawal batch total: {} Sun", batch.total());
+
+    if live {
+        let report = batch.execute(&hot, &*provider).await?;
+        for outcome in &report.outcomes {
+            println!(
+                "  {} {} -> {:?} {:?}",
+                outcome.address, outcome.amount, outcome.tx_hashes, outcome.error
+            );
+        }
+    } else {
+        println!("Dry run: set EXCHANGE_MNEMONIC to sweep and pay out on Nile.");
+    }
+
+    println!("Audit trail: {} record(s)", audit.records().len());
+    Ok(())
+}
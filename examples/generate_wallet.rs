@@ -1,11 +1,6 @@
 use flow_wallet::wallet::Wallet;
-use flow_wallet::wallet::chain::{TRON, UtxoChain};
-use flow_wallet::wallet::key_source::{KeySource, MnemonicKeySource};
-
-const LTC_TESTNET: UtxoChain = UtxoChain {
-    name: "litecoin_testnet",
-    p2pkh_prefix: 0x6f, // Testnet prefix
-};
+use flow_wallet::wallet::chain::{LITECOIN_TESTNET as LTC_TESTNET, TRON};
+use flow_wallet::wallet::key_source::{Bip44Path, KeySource, MnemonicKeySource};
 
 #[tokio::main]
 async fn main() {
@@ -23,7 +18,7 @@ async fn main() {
     // 2. Derive Tron Address (Shasta)
     // Path: m/44'/195'/0'/0/0
     let signer_tron = source
-        .derive_signer("m/44'/195'/0'/0/0")
+        .derive_signer(&Bip44Path::for_chain(&TRON).to_string())
         .await
         .expect("derive tron");
     let wallet_tron = Wallet::new(signer_tron, TRON);
@@ -35,9 +30,9 @@ async fn main() {
     println!("--------------------------------------------------");
 
     // 3. Derive Litecoin Address (Testnet)
-    // Path: m/44'/1'/0'/0/0 (Coin type 1 is often used for testnets)
+    // Path: m/44'/1'/0'/0/0 (coin type 1 is shared by all testnets)
     let signer_ltc = source
-        .derive_signer("m/44'/1'/0'/0/0")
+        .derive_signer(&Bip44Path::for_chain(&LTC_TESTNET).to_string())
         .await
         .expect("derive ltc");
     let wallet_ltc = Wallet::new(signer_ltc, LTC_TESTNET);
@@ -1,4 +1,5 @@
-use flow_wallet::node::{Provider, network::prelude::*, utils::format_units};
+use flow_wallet::node::{ReadProvider, network::prelude::*, utils::format_units};
+use flow_wallet::wallet::chain::{Chain, LITECOIN};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -8,9 +9,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .get_balance("ltc1qp7cnlxmz8wgc93g0m020ckru2s55t25y3wunf6")
         .await?;
 
+    let params = LITECOIN.params();
     println!(
-        "Balance: {} LTC",
-        format_units(&balance, provider.get_decimals())
+        "Balance: {} {}",
+        format_units(&balance, params.decimals),
+        params.symbol
     );
 
     Ok(())
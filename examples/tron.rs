@@ -1,5 +1,6 @@
 use flow_wallet::node::utils::format_units;
-use flow_wallet::node::{Provider, network::prelude::*};
+use flow_wallet::node::{ReadProvider, network::prelude::*};
+use flow_wallet::wallet::chain::{Chain, TRON};
 
 #[tokio::main]
 async fn main() {
@@ -19,9 +20,14 @@ async fn main() {
 
     // get balance
     if let Ok(balance) = tron_provider.get_balance(ADDRESS).await {
+        let params = TRON.params();
         println!(
-            "Balance: {} TRX",
-            format_units(&balance, tron_provider.get_decimals())
+            "Balance: {} {}",
+            format_units(&balance, params.decimals),
+            params.symbol
         );
     }
+    if let Some(url) = TRON.explorer_address_url(ADDRESS) {
+        println!("Explorer: {}", url);
+    }
 }
@@ -0,0 +1,73 @@
+//! Command lines of the form `<command> --name value ...`.
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Args {
+    pub command: String,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or("missing command")?;
+        let mut options = HashMap::new();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument {:?}", arg))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("--{} needs a value", name))?;
+            if options.insert(name.to_string(), value).is_some() {
+                return Err(format!("--{} is given twice", name));
+            }
+        }
+        Ok(Self { command, options })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn require(&self, name: &str) -> Result<&str, String> {
+        self.get(name)
+            .ok_or_else(|| format!("{} needs --{}", self.command, name))
+    }
+
+    /// Refuse options `command` does not take, so a typo is not ignored.
+    pub fn allow(&self, names: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .keys()
+            .find(|name| !names.contains(&name.as_str()))
+        {
+            Some(name) => Err(format!("{} does not take --{}", self.command, name)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        Args::parse(line.split(' ').map(str::to_string))
+    }
+
+    #[test]
+    fn parses_a_command_and_its_options() {
+        let args = parse("address --chain tron --path m/44'/195'/0'/0/3").unwrap();
+        assert_eq!(args.command, "address");
+        assert_eq!(args.get("path"), Some("m/44'/195'/0'/0/3"));
+        assert_eq!(args.require("chain"), Ok("tron"));
+        assert!(args.require("keystore").is_err());
+        assert!(args.allow(&["chain", "path"]).is_ok());
+        assert!(args.allow(&["chain"]).is_err());
+
+        assert!(parse("balance tron").is_err());
+        assert!(parse("balance --chain").is_err());
+        assert!(parse("balance --chain tron --chain ethereum").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! `flow-wallet-cli`: the library's wallet operations from a shell, with keys
+//! kept in an encrypted keystore file (`cli` feature).
+//!
+//! The keystore password is read from `FLOW_WALLET_PASSWORD` or, when that is
+//! unset, from standard input; a BIP-39 passphrase from
+//! `FLOW_WALLET_PASSPHRASE`. Chains are the built-in presets (`tron`,
+//! `litecoin`, `ethereum`, ...), reached through their public endpoint
+//! unless `--rpc` names another.
+
+mod args;
+
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flow_wallet::monitor::{MonitorEvent, MonitorFilter, TransactionMonitor};
+use flow_wallet::node::Provider;
+use flow_wallet::node::network::prelude::{EthProvider, LtcProvider, TronProvider};
+use flow_wallet::node::utils::{format_units, parse_units};
+use flow_wallet::wallet::chain::{AnyChain, Chain};
+use flow_wallet::wallet::key_source::{
+    Bip44Path, KeySource, MnemonicKeySource, MnemonicOptions, WordCount,
+};
+use flow_wallet::wallet::keystore::{Keystore, KeystoreKind, ScryptParams};
+use flow_wallet::wallet::{Signer, Wallet};
+
+use args::Args;
+
+const USAGE: &str = "\
+usage: flow-wallet-cli <command> [--option value ...]
+
+commands:
+  generate      --keystore FILE [--words 12|15|18|21|24]
+  address       --chain ID --keystore FILE [--path PATH]
+  balance       --chain ID (--address ADDR | --keystore FILE [--path PATH]) [--rpc URL]
+  send          --chain ID --keystore FILE --to ADDR --amount COINS [--path PATH] [--rpc URL]
+  monitor       --chain ID --address ADDR [--interval SECONDS] [--rpc URL]
+  sign-message  --chain ID --keystore FILE --message TEXT [--path PATH]
+
+PATH defaults to the chain's first BIP-44 receiving address. Private-key
+keystores have a single key and take no --path.";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+    if let Err(e) = runtime.block_on(run(&args)) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: &Args) -> Result<()> {
+    match args.command.as_str() {
+        "generate" => generate(args),
+        "address" => address(args).await,
+        "balance" => balance(args).await,
+        "send" => send(args).await,
+        "monitor" => monitor(args).await,
+        "sign-message" => sign_message(args).await,
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("unknown command {:?}\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn generate(args: &Args) -> Result<()> {
+    args.allow(&["keystore", "words"])?;
+    let path = args.require("keystore")?;
+    if Path::new(path).exists() {
+        return Err(format!("{} already exists", path).into());
+    }
+    let words = match args.get("words").unwrap_or("12") {
+        "12" => WordCount::Twelve,
+        "15" => WordCount::Fifteen,
+        "18" => WordCount::Eighteen,
+        "21" => WordCount::TwentyOne,
+        "24" => WordCount::TwentyFour,
+        other => return Err(format!("--words must be 12, 15, 18, 21 or 24, not {}", other).into()),
+    };
+    let source = MnemonicKeySource::generate(&MnemonicOptions::new().with_words(words), None)?;
+    let password = password()?;
+    Keystore::from_mnemonic(&source, &password, ScryptParams::default())?.save(path)?;
+    println!("Recovery phrase (write it down, it is not shown again):");
+    println!("{}", source.phrase());
+    println!("Keystore saved to {}", path);
+    Ok(())
+}
+
+async fn address(args: &Args) -> Result<()> {
+    args.allow(&["chain", "keystore", "path"])?;
+    let chain = chain(args)?;
+    let wallet = wallet(args, chain).await?;
+    println!("{}", wallet.address()?);
+    Ok(())
+}
+
+async fn balance(args: &Args) -> Result<()> {
+    args.allow(&["chain", "address", "keystore", "path", "rpc"])?;
+    let chain = chain(args)?;
+    let address = match args.get("address") {
+        Some(address) => {
+            chain.validate_address(address)?;
+            address.to_string()
+        }
+        None => wallet(args, chain).await?.address()?,
+    };
+    let balance = provider(args, &chain)?.get_balance(&address).await?;
+    println!(
+        "{} {}",
+        format_units(&balance, chain.decimals()),
+        chain.symbol()
+    );
+    Ok(())
+}
+
+async fn send(args: &Args) -> Result<()> {
+    args.allow(&["chain", "keystore", "path", "to", "amount", "rpc"])?;
+    let chain = chain(args)?;
+    let amount = parse_units(args.require("amount")?, chain.decimals())?;
+    let amount = u64::try_from(amount).map_err(|_| "--amount is too large")?;
+    let provider = provider(args, &chain)?;
+    let wallet = wallet(args, chain).await?;
+    let tx_hash = wallet
+        .send_coins(provider.as_ref(), args.require("to")?, amount)
+        .await?;
+    println!("{}", tx_hash);
+    if let Some(url) = chain.explorer_tx_url(&tx_hash) {
+        println!("{}", url);
+    }
+    Ok(())
+}
+
+async fn monitor(args: &Args) -> Result<()> {
+    args.allow(&["chain", "address", "interval", "rpc"])?;
+    let chain = chain(args)?;
+    let address = args.require("address")?;
+    chain.validate_address(address)?;
+    let interval = match args.get("interval") {
+        Some(seconds) => Duration::from_secs(seconds.parse().map_err(|_| "bad --interval")?),
+        None => Duration::from_secs(10),
+    };
+    let monitor =
+        TransactionMonitor::new(provider(args, &chain)?, chain.id(), MonitorFilter::new())
+            .watch(address)
+            .with_explorer(&chain)
+            .with_decoder(chain);
+    eprintln!("Watching {} on {}; Ctrl-C to stop", address, chain.id());
+    loop {
+        match monitor.poll().await {
+            Ok(events) => events.iter().for_each(|event| print_event(&chain, event)),
+            Err(e) => eprintln!("poll failed: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn print_event(chain: &AnyChain, event: &MonitorEvent) {
+    let MonitorEvent::NewTransaction(monitored) = event else {
+        return;
+    };
+    let transfer = &monitored.transfer;
+    let value = match &transfer.token {
+        Some(token) => format!("{} of token {}", transfer.value, token),
+        None => format!(
+            "{} {}",
+            format_units(&transfer.value, chain.decimals()),
+            chain.symbol()
+        ),
+    };
+    println!(
+        "{:?} {} {} -> {} {}",
+        monitored.direction,
+        value,
+        transfer.from,
+        transfer.to,
+        monitored
+            .explorer_url
+            .as_deref()
+            .unwrap_or(&monitored.transaction.hash)
+    );
+}
+
+async fn sign_message(args: &Args) -> Result<()> {
+    args.allow(&["chain", "keystore", "path", "message"])?;
+    let chain = chain(args)?;
+    let wallet = wallet(args, chain).await?;
+    println!(
+        "{}",
+        wallet
+            .sign_message(args.require("message")?.as_bytes())
+            .await?
+    );
+    Ok(())
+}
+
+fn chain(args: &Args) -> Result<AnyChain> {
+    let id = args.require("chain")?;
+    Ok(AnyChain::from_id(id).ok_or_else(|| format!("unknown chain {:?}", id))?)
+}
+
+fn provider(args: &Args, chain: &AnyChain) -> Result<Arc<dyn Provider>> {
+    let Some(url) = args.get("rpc") else {
+        return chain
+            .default_provider()
+            .ok_or_else(|| format!("{} has no public endpoint; pass --rpc", chain.id()).into());
+    };
+    Ok(match chain {
+        AnyChain::Utxo(_) => Arc::new(LtcProvider::with_url(url.to_string())),
+        AnyChain::Tvm(_) => Arc::new(TronProvider::with_url(url.to_string())),
+        AnyChain::Evm(_) => Arc::new(EthProvider::new(url)),
+    })
+}
+
+/// The wallet of the keystore's key at `--path`.
+async fn wallet(args: &Args, chain: AnyChain) -> Result<Wallet<AnyChain, Box<dyn Signer>>> {
+    let keystore = Keystore::load(args.require("keystore")?)?;
+    let password = password()?;
+    let signer: Box<dyn Signer> = match keystore.kind {
+        KeystoreKind::Mnemonic => {
+            let passphrase = std::env::var("FLOW_WALLET_PASSPHRASE").ok();
+            let source = keystore.to_mnemonic(&password, passphrase.as_deref())?;
+            match args.get("path") {
+                Some(path) => source.derive_signer(path).await?,
+                None => {
+                    source
+                        .derive_signer_at(&Bip44Path::for_chain(&chain))
+                        .await?
+                }
+            }
+        }
+        KeystoreKind::PrivateKey if args.get("path").is_some() => {
+            return Err("a private-key keystore has no derivation paths".into());
+        }
+        KeystoreKind::PrivateKey => Box::new(keystore.to_signer(&password)?),
+        KeystoreKind::Backup => return Err("backup keystores hold no signing key".into()),
+    };
+    Ok(Wallet::new(signer, chain))
+}
+
+fn password() -> Result<String> {
+    if let Ok(password) = std::env::var("FLOW_WALLET_PASSWORD") {
+        return Ok(password);
+    }
+    eprint!("Keystore password: ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
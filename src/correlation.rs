@@ -0,0 +1,107 @@
+use std::fmt;
+use std::future::Future;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Header used by providers to forward the active correlation id.
+pub const DEFAULT_CORRELATION_HEADER: &str = "X-Correlation-Id";
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// Identifier tying together every provider call, signature and audit entry of one send.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Generate a random 128-bit id.
+    pub fn new() -> Self {
+        let mut bytes = [0u8; 16];
+        rand::rng().fill_bytes(&mut bytes);
+        Self(hex::encode(bytes))
+    }
+
+    /// The id of the send currently executing on this task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|id| id.clone()).ok()
+    }
+
+    /// Run `fut` with this id as the current correlation id.
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<String> for CorrelationId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl From<&str> for CorrelationId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Attach the current correlation id (if any) to an outgoing request.
+#[cfg(feature = "network")]
+pub(crate) fn tag_request(
+    request: reqwest::RequestBuilder,
+    header: Option<&str>,
+) -> reqwest::RequestBuilder {
+    match (header, CorrelationId::current()) {
+        (Some(header), Some(id)) => request.header(header, id.as_str()),
+        _ => request,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn current_is_scoped_to_the_future() {
+        assert_eq!(CorrelationId::current(), None);
+        let id = CorrelationId::from("abc");
+        let seen = id.clone().scope(async { CorrelationId::current() }).await;
+        assert_eq!(seen, Some(id));
+        assert_eq!(CorrelationId::current(), None);
+    }
+
+    #[cfg(feature = "network")]
+    #[tokio::test]
+    async fn requests_are_tagged_inside_scope() {
+        let client = reqwest::Client::new();
+        let build = || {
+            tag_request(
+                client.get("http://localhost/"),
+                Some(DEFAULT_CORRELATION_HEADER),
+            )
+            .build()
+            .unwrap()
+        };
+
+        assert!(build().headers().get(DEFAULT_CORRELATION_HEADER).is_none());
+        let request = CorrelationId::from("wd-1").scope(async { build() }).await;
+        assert_eq!(request.headers()[DEFAULT_CORRELATION_HEADER], "wd-1");
+    }
+}
@@ -1,8 +1,27 @@
 use thiserror::Error;
 
-use crate::node::NodeError;
-use crate::wallet::chain::ChainError;
+use crate::node::resolver::ResolverError;
+use crate::node::{AmountError, NodeError};
+use crate::persist::PersistError;
+use crate::wallet::approval::ApprovalError;
+use crate::wallet::chain::{ChainError, Network, RegistryError};
+use crate::wallet::export::ExportError;
+use crate::wallet::fee_bump::FeeBumpError;
+use crate::wallet::hooks::HookError;
+use crate::wallet::idempotency::IdempotencyError;
 use crate::wallet::key_source::KeySourceError;
+use crate::wallet::keystore::KeystoreError;
+#[cfg(feature = "network")]
+use crate::wallet::nft::NftError;
+#[cfg(feature = "network")]
+use crate::wallet::permissions::PermissionError;
+use crate::wallet::policy::PolicyError;
+use crate::wallet::screening::ScreeningError;
+use crate::wallet::signer::SignerError;
+#[cfg(feature = "network")]
+use crate::wallet::staking::StakingError;
+#[cfg(feature = "network")]
+use crate::wallet::token::TokenError;
 
 #[derive(Debug, Error)]
 pub enum WalletError {
@@ -12,9 +31,98 @@ pub enum WalletError {
     #[error("Key source error: {0}")]
     KeySource(#[from] KeySourceError),
 
+    #[error("Keystore error: {0}")]
+    Keystore(#[from] KeystoreError),
+
     #[error("Node error: {0}")]
     Node(#[from] NodeError),
 
-    #[error("Signing failed")]
-    SigningFailed,
+    #[error("Signing failed ({context}): {source}")]
+    SigningFailed {
+        /// What was being signed.
+        context: String,
+        #[source]
+        source: SignerError,
+    },
+
+    #[error("Signer is tagged for {signer} but {chain} is a {network} chain")]
+    NetworkMismatch {
+        signer: Network,
+        chain: String,
+        network: Network,
+    },
+
+    #[error("Fee payer error: {0}")]
+    FeePayer(String),
+
+    #[error("Fee bump failed: {0}")]
+    FeeBump(#[from] FeeBumpError),
+
+    #[error("Name resolution failed: {0}")]
+    Resolver(#[from] ResolverError),
+
+    #[error("Chain registry error: {0}")]
+    Registry(#[from] RegistryError),
+
+    #[cfg(feature = "network")]
+    #[error("Token error: {0}")]
+    Token(#[from] TokenError),
+
+    #[cfg(feature = "network")]
+    #[error("Staking error: {0}")]
+    Staking(#[from] StakingError),
+
+    #[cfg(feature = "network")]
+    #[error("NFT error: {0}")]
+    Nft(#[from] NftError),
+
+    #[cfg(feature = "network")]
+    #[error("Permission error: {0}")]
+    Permission(#[from] PermissionError),
+
+    #[error("Persistence error: {0}")]
+    Persist(#[from] PersistError),
+
+    #[error("History export failed: {0}")]
+    Export(#[from] ExportError),
+
+    #[error("Approval failed: {0}")]
+    Approval(#[from] ApprovalError),
+
+    #[error("Spend policy refused the transfer: {0}")]
+    Policy(#[from] PolicyError),
+
+    #[error("Hook error: {0}")]
+    Hook(#[from] HookError),
+
+    #[error("Idempotency error: {0}")]
+    Idempotency(#[from] IdempotencyError),
+
+    #[error("Screening failed: {0}")]
+    Screening(#[from] ScreeningError),
+
+    #[error("Amount error: {0}")]
+    Amount(#[from] AmountError),
+
+    #[error("No provider is configured for chain {0}")]
+    NoProvider(String),
+
+    #[error("Shutting down; no new sends are accepted")]
+    ShuttingDown,
+
+    #[error("Wallet is watch-only; build and export the transaction to sign it elsewhere")]
+    WatchOnly,
+
+    #[error("Amount is below the chain's minimum transfer of {minimum} base units")]
+    AmountTooSmall { minimum: u64 },
+
+    #[error("Transaction {0} failed on chain")]
+    TransactionFailed(String),
+
+    #[error("Transaction {tx_hash} has {confirmations}/{required} confirmations after timeout")]
+    ConfirmationTimeout {
+        tx_hash: String,
+        confirmations: u64,
+        required: u64,
+    },
 }
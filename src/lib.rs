@@ -1,5 +1,29 @@
+//! Multi-chain wallet: keys, signing, chains and the node providers that
+//! read from and broadcast to them.
+//!
+//! The `network` feature (on by default) brings in the HTTP stack: the
+//! providers under `node::network`, name resolvers and MPC transports.
+//! Without it, [`wallet`], its chains and key sources and the `node` traits
+//! and types still build, with no HTTP client in the dependency tree, for an
+//! air-gapped signer:
+//!
+//! ```sh
+//! cargo build --no-default-features
+//! ```
+
+pub mod correlation;
 pub mod error;
+pub mod metrics;
+pub mod monitor;
 pub mod node;
+pub mod persist;
+pub mod shutdown;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod time;
 pub mod wallet;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use error::WalletError;
@@ -0,0 +1,317 @@
+//! Counters and timings for dashboards and alerts.
+//!
+//! Providers, wallets and monitors given a [`Metrics`] through their
+//! `with_metrics` builders report each request, signature, broadcast and
+//! poll to it. Implement the trait to feed an existing metrics library, or
+//! use [`MemoryMetrics`], which keeps the figures in process and renders
+//! them in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the buckets [`MemoryMetrics`] sorts durations into, in
+/// seconds.
+pub const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Receiver of the figures the crate produces. Every method does nothing
+/// by default, so an implementation records only what it needs.
+pub trait Metrics: Send + Sync {
+    /// A provider named `provider` (`tron`, `ltc`, `json-rpc`) sent a
+    /// request to `endpoint` (scheme and host only) and got `status` back
+    /// after `latency`; `None` if no response arrived at all.
+    fn provider_request(
+        &self,
+        provider: &str,
+        endpoint: &str,
+        status: Option<u16>,
+        latency: Duration,
+    ) {
+        let _ = (provider, endpoint, status, latency);
+    }
+
+    /// A wallet on `chain` asked its signer for one signature, which took
+    /// `latency` and succeeded if `ok`. Signatures asked for together, as
+    /// for the inputs of one UTXO transaction, each report the batch's latency.
+    fn signature(&self, chain: &str, latency: Duration, ok: bool) {
+        let _ = (chain, latency, ok);
+    }
+
+    /// A wallet on `chain` broadcast a transaction, which the network
+    /// accepted if `ok`.
+    fn broadcast(&self, chain: &str, ok: bool) {
+        let _ = (chain, ok);
+    }
+
+    /// A poll of `monitor` (`transactions`, `deposits`) on `chain` took
+    /// `latency` and succeeded if `ok`. `lag` is the age of the monitor's
+    /// view of the chain: the time since its last successful poll started.
+    fn monitor_poll(&self, monitor: &str, chain: &str, latency: Duration, lag: Duration, ok: bool) {
+        let _ = (monitor, chain, latency, lag, ok);
+    }
+}
+
+/// Counts of observations per bucket of [`DURATION_BUCKETS`], plus one for
+/// anything longer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    pub buckets: [u64; DURATION_BUCKETS.len() + 1],
+    pub count: u64,
+    /// Total of the observations, in seconds.
+    pub sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct Series {
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, f64>,
+    histograms: BTreeMap<String, Histogram>,
+}
+
+/// [`Metrics`] kept in memory, e.g. to serve on a `/metrics` endpoint.
+///
+/// Series are named as in Prometheus, `name{label="value",...}`:
+/// `flow_wallet_provider_requests_total{provider,endpoint,status}`,
+/// `flow_wallet_provider_request_seconds{provider,endpoint}`,
+/// `flow_wallet_signatures_total{chain,result}`,
+/// `flow_wallet_signing_seconds{chain}`,
+/// `flow_wallet_broadcasts_total{chain,result}`,
+/// `flow_wallet_monitor_polls_total{monitor,chain,result}`,
+/// `flow_wallet_monitor_poll_seconds{monitor,chain}` and the gauge
+/// `flow_wallet_monitor_lag_seconds{monitor,chain}`.
+#[derive(Default)]
+pub struct MemoryMetrics {
+    series: Mutex<Series>,
+}
+
+impl MemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value of the counter `series`, 0 if it was never incremented.
+    pub fn counter(&self, series: &str) -> u64 {
+        let series = self.series.lock().unwrap().counters.get(series).copied();
+        series.unwrap_or(0)
+    }
+
+    /// Last value of the gauge `series`.
+    pub fn gauge(&self, series: &str) -> Option<f64> {
+        self.series.lock().unwrap().gauges.get(series).copied()
+    }
+
+    /// Observations of the histogram `series` so far.
+    pub fn histogram(&self, series: &str) -> Option<Histogram> {
+        self.series.lock().unwrap().histograms.get(series).cloned()
+    }
+
+    /// Everything recorded, in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap();
+        let mut out = String::new();
+        for (name, value) in &series.counters {
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        for (name, value) in &series.gauges {
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        for (name, histogram) in &series.histograms {
+            let (metric, labels) = match name.split_once('{') {
+                Some((metric, labels)) => (metric, labels.trim_end_matches('}')),
+                None => (name.as_str(), ""),
+            };
+            let separator = if labels.is_empty() { "" } else { "," };
+            let mut cumulative = 0;
+            for (i, count) in histogram.buckets.iter().enumerate() {
+                cumulative += count;
+                let bound = DURATION_BUCKETS
+                    .get(i)
+                    .map_or("+Inf".to_string(), |b| b.to_string());
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{}{}le=\"{}\"}} {}",
+                    metric, labels, separator, bound, cumulative
+                );
+            }
+            let labels = if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{}}}", labels)
+            };
+            let _ = writeln!(out, "{}_sum{} {}", metric, labels, histogram.sum);
+            let _ = writeln!(out, "{}_count{} {}", metric, labels, histogram.count);
+        }
+        out
+    }
+
+    fn count(&self, name: &str, labels: &[(&str, &str)]) {
+        let mut series = self.series.lock().unwrap();
+        *series
+            .counters
+            .entry(series_name(name, labels))
+            .or_default() += 1;
+    }
+
+    fn observe(&self, name: &str, labels: &[(&str, &str)], duration: Duration) {
+        let mut series = self.series.lock().unwrap();
+        series
+            .histograms
+            .entry(series_name(name, labels))
+            .or_default()
+            .observe(duration);
+    }
+}
+
+fn result(ok: bool) -> &'static str {
+    if ok { "ok" } else { "error" }
+}
+
+/// `name{label="value",...}`, with values escaped as Prometheus expects.
+fn series_name(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(label, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", label, value)
+        })
+        .collect();
+    format!("{}{{{}}}", name, labels.join(","))
+}
+
+impl Metrics for MemoryMetrics {
+    fn provider_request(
+        &self,
+        provider: &str,
+        endpoint: &str,
+        status: Option<u16>,
+        latency: Duration,
+    ) {
+        let status = status.map_or("none".to_string(), |s| s.to_string());
+        self.count(
+            "flow_wallet_provider_requests_total",
+            &[
+                ("provider", provider),
+                ("endpoint", endpoint),
+                ("status", &status),
+            ],
+        );
+        self.observe(
+            "flow_wallet_provider_request_seconds",
+            &[("provider", provider), ("endpoint", endpoint)],
+            latency,
+        );
+    }
+
+    fn signature(&self, chain: &str, latency: Duration, ok: bool) {
+        self.count(
+            "flow_wallet_signatures_total",
+            &[("chain", chain), ("result", result(ok))],
+        );
+        self.observe("flow_wallet_signing_seconds", &[("chain", chain)], latency);
+    }
+
+    fn broadcast(&self, chain: &str, ok: bool) {
+        self.count(
+            "flow_wallet_broadcasts_total",
+            &[("chain", chain), ("result", result(ok))],
+        );
+    }
+
+    fn monitor_poll(&self, monitor: &str, chain: &str, latency: Duration, lag: Duration, ok: bool) {
+        let labels = [("monitor", monitor), ("chain", chain)];
+        self.count(
+            "flow_wallet_monitor_polls_total",
+            &[labels[0], labels[1], ("result", result(ok))],
+        );
+        self.observe("flow_wallet_monitor_poll_seconds", &labels, latency);
+        self.series.lock().unwrap().gauges.insert(
+            series_name("flow_wallet_monitor_lag_seconds", &labels),
+            lag.as_secs_f64(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_metrics_count_time_and_render() {
+        let metrics = MemoryMetrics::new();
+        metrics.provider_request(
+            "tron",
+            "https://api.trongrid.io",
+            Some(200),
+            Duration::from_millis(30),
+        );
+        metrics.provider_request(
+            "tron",
+            "https://api.trongrid.io",
+            None,
+            Duration::from_secs(20),
+        );
+        metrics.signature("tron", Duration::from_millis(2), true);
+        metrics.broadcast("tron", false);
+        metrics.monitor_poll(
+            "deposits",
+            "tron",
+            Duration::from_millis(80),
+            Duration::from_secs(3),
+            true,
+        );
+
+        assert_eq!(
+            metrics.counter(
+                "flow_wallet_provider_requests_total{provider=\"tron\",endpoint=\"https://api.trongrid.io\",status=\"none\"}"
+            ),
+            1
+        );
+        assert_eq!(
+            metrics.counter("flow_wallet_broadcasts_total{chain=\"tron\",result=\"error\"}"),
+            1
+        );
+        let latency = metrics
+            .histogram(
+                "flow_wallet_provider_request_seconds{provider=\"tron\",endpoint=\"https://api.trongrid.io\"}",
+            )
+            .unwrap();
+        assert_eq!(latency.count, 2);
+        assert_eq!(latency.buckets[3], 1);
+        assert_eq!(latency.buckets[DURATION_BUCKETS.len()], 1);
+        assert_eq!(
+            metrics.gauge("flow_wallet_monitor_lag_seconds{monitor=\"deposits\",chain=\"tron\"}"),
+            Some(3.0)
+        );
+
+        let text = metrics.render();
+        assert!(
+            text.contains("flow_wallet_signing_seconds_bucket{chain=\"tron\",le=\"0.005\"} 1\n")
+        );
+        assert!(text.contains("flow_wallet_signing_seconds_count{chain=\"tron\"} 1\n"));
+        assert!(text.contains(
+            "flow_wallet_provider_request_seconds_bucket{provider=\"tron\",endpoint=\"https://api.trongrid.io\",le=\"+Inf\"} 2\n"
+        ));
+    }
+}
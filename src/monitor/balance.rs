@@ -0,0 +1,339 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+#[cfg(feature = "network")]
+use crate::node::network::tron::TronProvider;
+#[cfg(feature = "network")]
+use crate::node::token::TokenPreset;
+use crate::node::{NodeError, ReadProvider};
+use crate::persist::Persist;
+use crate::shutdown::Shutdown;
+
+/// Anything that can report an address's balance of one asset, in base units.
+#[async_trait]
+pub trait BalanceSource: Send + Sync {
+    async fn balance(&self, address: &str) -> Result<u128, NodeError>;
+}
+
+/// Native coin balance as reported by [`ReadProvider::get_balance`].
+pub struct NativeBalance(pub Arc<dyn ReadProvider>);
+
+#[async_trait]
+impl BalanceSource for NativeBalance {
+    async fn balance(&self, address: &str) -> Result<u128, NodeError> {
+        let raw = self.0.get_balance(address).await?;
+        raw.parse()
+            .map_err(|_| NodeError::Parse(format!("invalid balance {:?}", raw)))
+    }
+}
+
+/// TRC-20 token balance.
+#[cfg(feature = "network")]
+pub struct Trc20Balance {
+    pub provider: Arc<TronProvider>,
+    pub token: TokenPreset,
+}
+
+#[cfg(feature = "network")]
+#[async_trait]
+impl BalanceSource for Trc20Balance {
+    async fn balance(&self, address: &str) -> Result<u128, NodeError> {
+        self.provider
+            .trc20_balance(address, self.token.contract)
+            .await
+    }
+}
+
+/// Change detected between two consecutive snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalanceEvent {
+    /// One watched address's balance of `asset` moved.
+    Changed {
+        label: String,
+        address: String,
+        asset: String,
+        previous: u128,
+        current: u128,
+    },
+    /// The sum of `asset` across all watched addresses moved.
+    TotalChanged {
+        asset: String,
+        previous: u128,
+        current: u128,
+    },
+    /// A balance could not be read this round; the previous value is kept.
+    Failed {
+        label: String,
+        asset: String,
+        error: String,
+    },
+}
+
+impl BalanceEvent {
+    /// Signed change for `Changed` and `TotalChanged` events.
+    pub fn delta(&self) -> Option<i128> {
+        match self {
+            BalanceEvent::Changed {
+                previous, current, ..
+            }
+            | BalanceEvent::TotalChanged {
+                previous, current, ..
+            } => Some(*current as i128 - *previous as i128),
+            BalanceEvent::Failed { .. } => None,
+        }
+    }
+}
+
+/// Last known balance of one watched target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceReading {
+    pub label: String,
+    pub address: String,
+    pub asset: String,
+    pub balance: u128,
+}
+
+/// A [`BalanceWatcher`]'s baseline, so a restarted watcher reports what moved
+/// while it was down instead of starting a fresh baseline.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceCheckpoint {
+    pub readings: Vec<BalanceReading>,
+}
+
+impl Persist for BalanceCheckpoint {
+    const KIND: &'static str = "balance_checkpoint";
+    const VERSION: u16 = 1;
+}
+
+struct Target {
+    label: String,
+    address: String,
+    asset: String,
+    source: Arc<dyn BalanceSource>,
+}
+
+/// Periodically snapshots balances across wallets and emits deltas.
+///
+/// Meant for dashboards that track totals; use a transaction monitor when
+/// individual transfers matter. The first snapshot establishes a baseline and
+/// emits no `Changed` events.
+pub struct BalanceWatcher {
+    targets: Vec<Target>,
+    interval: Duration,
+    /// Last known balance per target index.
+    snapshot: Mutex<HashMap<usize, u128>>,
+    events: broadcast::Sender<BalanceEvent>,
+}
+
+impl BalanceWatcher {
+    pub fn new(interval: Duration) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            targets: Vec::new(),
+            interval,
+            snapshot: Mutex::new(HashMap::new()),
+            events,
+        }
+    }
+
+    /// Watch `address`'s balance of `asset`, labelled for reporting (e.g. `"hot"`).
+    pub fn watch(
+        mut self,
+        label: &str,
+        address: &str,
+        asset: &str,
+        source: Arc<dyn BalanceSource>,
+    ) -> Self {
+        self.targets.push(Target {
+            label: label.to_string(),
+            address: address.to_string(),
+            asset: asset.to_string(),
+            source,
+        });
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BalanceEvent> {
+        self.events.subscribe()
+    }
+
+    /// Last known total per asset.
+    pub fn totals(&self) -> BTreeMap<String, u128> {
+        let snapshot = self.snapshot.lock().unwrap();
+        Self::sum(&self.targets, &snapshot)
+    }
+
+    pub fn checkpoint(&self) -> BalanceCheckpoint {
+        let snapshot = self.snapshot.lock().unwrap();
+        let readings = self
+            .targets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, target)| {
+                Some(BalanceReading {
+                    label: target.label.clone(),
+                    address: target.address.clone(),
+                    asset: target.asset.clone(),
+                    balance: *snapshot.get(&index)?,
+                })
+            })
+            .collect();
+        BalanceCheckpoint { readings }
+    }
+
+    /// Load balances from `checkpoint` for targets with the same label, address
+    /// and asset. Readings for targets no longer watched are dropped.
+    pub fn restore(&self, checkpoint: &BalanceCheckpoint) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        for reading in &checkpoint.readings {
+            if let Some(index) = self.targets.iter().position(|t| {
+                t.label == reading.label && t.address == reading.address && t.asset == reading.asset
+            }) {
+                snapshot.insert(index, reading.balance);
+            }
+        }
+    }
+
+    /// Take one snapshot, publish and return the events it produced.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "balance_poll", skip_all, fields(targets = self.targets.len()))
+    )]
+    pub async fn poll(&self) -> Vec<BalanceEvent> {
+        let mut readings = Vec::with_capacity(self.targets.len());
+        for target in &self.targets {
+            readings.push(target.source.balance(&target.address).await);
+        }
+
+        let mut events = Vec::new();
+        let mut snapshot = self.snapshot.lock().unwrap();
+        let before = Self::sum(&self.targets, &snapshot);
+        let baseline = snapshot.is_empty();
+
+        for (index, (target, reading)) in self.targets.iter().zip(readings).enumerate() {
+            match reading {
+                Ok(current) => {
+                    if let Some(previous) = snapshot.insert(index, current)
+                        && previous != current
+                    {
+                        events.push(BalanceEvent::Changed {
+                            label: target.label.clone(),
+                            address: target.address.clone(),
+                            asset: target.asset.clone(),
+                            previous,
+                            current,
+                        });
+                    }
+                }
+                Err(e) => events.push(BalanceEvent::Failed {
+                    label: target.label.clone(),
+                    asset: target.asset.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        if !baseline {
+            for (asset, current) in Self::sum(&self.targets, &snapshot) {
+                let previous = before.get(&asset).copied().unwrap_or(0);
+                if previous != current {
+                    events.push(BalanceEvent::TotalChanged {
+                        asset,
+                        previous,
+                        current,
+                    });
+                }
+            }
+        }
+        drop(snapshot);
+
+        for event in &events {
+            // No subscribers is fine; the caller still gets the events.
+            let _ = self.events.send(event.clone());
+        }
+        events
+    }
+
+    /// Poll at the configured interval until `shutdown` is triggered.
+    pub async fn run(&self, shutdown: &Shutdown) {
+        while !shutdown.is_triggered() {
+            self.poll().await;
+            if shutdown.sleep(self.interval).await {
+                break;
+            }
+        }
+    }
+
+    fn sum(targets: &[Target], snapshot: &HashMap<usize, u128>) -> BTreeMap<String, u128> {
+        let mut totals = BTreeMap::new();
+        for (index, balance) in snapshot {
+            *totals.entry(targets[*index].asset.clone()).or_insert(0) += balance;
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Scripted(Mutex<HashMap<String, u128>>);
+
+    impl Scripted {
+        fn set(&self, address: &str, amount: u128) {
+            self.0.lock().unwrap().insert(address.to_string(), amount);
+        }
+    }
+
+    #[async_trait]
+    impl BalanceSource for Scripted {
+        async fn balance(&self, address: &str) -> Result<u128, NodeError> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(address)
+                .copied()
+                .ok_or_else(|| NodeError::Network("down".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_deltas_and_totals_after_baseline() {
+        let source = Arc::new(Scripted::default());
+        source.set("a", 100);
+        source.set("b", 50);
+        let watcher = BalanceWatcher::new(Duration::from_secs(60))
+            .watch("hot", "a", "TRX", source.clone())
+            .watch("cold", "b", "TRX", source.clone());
+        let mut rx = watcher.subscribe();
+
+        assert!(watcher.poll().await.is_empty());
+        assert_eq!(watcher.totals()["TRX"], 150);
+
+        source.set("a", 70);
+        let events = watcher.poll().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].delta(), Some(-30));
+        assert_eq!(
+            events[1],
+            BalanceEvent::TotalChanged {
+                asset: "TRX".into(),
+                previous: 150,
+                current: 120
+            }
+        );
+        assert_eq!(rx.recv().await.unwrap(), events[0]);
+
+        // A failed read keeps the last value instead of reporting a drop to zero.
+        source.0.lock().unwrap().remove("b");
+        let events = watcher.poll().await;
+        assert!(matches!(&events[..], [BalanceEvent::Failed { label, .. }] if label == "cold"));
+        assert_eq!(watcher.totals()["TRX"], 120);
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::{Bound, RangeBounds};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::node::Transaction;
+
+/// Asset key for a chain's native coin (TRX, LTC, ETH, ...).
+pub const NATIVE: &str = "native";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    chain: String,
+    asset: String,
+    min: Bound<u128>,
+    max: Bound<u128>,
+    confirmations: u64,
+}
+
+impl Rule {
+    fn matches(&self, chain: &str, asset: &str, amount: u128) -> bool {
+        self.chain == chain
+            && self.asset.eq_ignore_ascii_case(asset)
+            && (self.min.as_ref(), self.max.as_ref()).contains(&amount)
+    }
+}
+
+/// Required confirmations by (chain, asset, amount range).
+///
+/// Rules are checked in the order they were added and the first match wins,
+/// so list narrow ranges before broad ones. Amounts are in base units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    rules: Vec<Rule>,
+    default: u64,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl ConfirmationPolicy {
+    /// Policy requiring `default` confirmations wherever no rule matches.
+    pub fn new(default: u64) -> Self {
+        Self {
+            rules: Vec::new(),
+            default,
+        }
+    }
+
+    /// Conservative starting point: 1 for small USDT and 19 for large TRX on Tron, 6 on Litecoin.
+    pub fn recommended() -> Self {
+        Self::new(1)
+            .rule("tron", "USDT", ..1_000_000_000, 1)
+            .rule("tron", "USDT", .., 19)
+            .rule("tron", NATIVE, 100_000_000_000.., 19)
+            .rule("litecoin", NATIVE, .., 6)
+    }
+
+    /// Require `confirmations` for `asset` on `chain` when the amount falls in `amounts`.
+    pub fn rule(
+        mut self,
+        chain: &str,
+        asset: &str,
+        amounts: impl RangeBounds<u128>,
+        confirmations: u64,
+    ) -> Self {
+        self.rules.push(Rule {
+            chain: chain.to_string(),
+            asset: asset.to_string(),
+            min: amounts.start_bound().cloned(),
+            max: amounts.end_bound().cloned(),
+            confirmations,
+        });
+        self
+    }
+
+    pub fn required(&self, chain: &str, asset: &str, amount: u128) -> u64 {
+        self.rules
+            .iter()
+            .find(|r| r.matches(chain, asset, amount))
+            .map(|r| r.confirmations)
+            .unwrap_or(self.default)
+    }
+
+    pub fn is_final(&self, chain: &str, asset: &str, amount: u128, confirmations: u64) -> bool {
+        confirmations >= self.required(chain, asset, amount)
+    }
+}
+
+/// How [`Wallet::send_and_wait`](crate::wallet::Wallet::send_and_wait) polls for confirmations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitOptions {
+    pub poll_interval: Duration,
+    /// Give up after this long; the transaction may still confirm later.
+    pub timeout: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+/// A transaction that reached the confirmations its policy required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub confirmations: u64,
+}
+
+/// What [`Wallet::send_and_confirm`](crate::wallet::Wallet::send_and_confirm)
+/// reports once a send is confirmed deeply enough.
+#[derive(Debug, Clone, Serialize)]
+pub struct Receipt {
+    pub tx_hash: String,
+    pub block_number: u64,
+    /// Block time as reported by the provider.
+    pub timestamp: u64,
+    pub confirmations: u64,
+    /// Fee paid in the chain's base unit, if the provider exposes it.
+    pub fee: Option<u64>,
+    /// The provider's view of the transaction.
+    pub transaction: Transaction,
+}
+
+/// Confirmations of a transaction mined in `block` when the tip is `tip`; 0 while unmined.
+pub fn confirmations(tip: u64, block: u64) -> u64 {
+    if block == 0 || block > tip {
+        0
+    } else {
+        tip - block + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let policy = ConfirmationPolicy::recommended();
+        assert_eq!(policy.required("tron", "usdt", 50_000_000), 1);
+        assert_eq!(policy.required("tron", "USDT", 5_000_000_000), 19);
+        assert_eq!(policy.required("tron", NATIVE, 1_000_000), 1);
+        assert_eq!(policy.required("tron", NATIVE, 200_000_000_000), 19);
+        assert_eq!(policy.required("litecoin", NATIVE, 1), 6);
+        assert!(policy.is_final("litecoin", NATIVE, 1, 6));
+        assert!(!policy.is_final("litecoin", NATIVE, 1, 5));
+    }
+
+    #[test]
+    fn counts_confirmations_from_tip() {
+        assert_eq!(confirmations(100, 100), 1);
+        assert_eq!(confirmations(100, 81), 20);
+        assert_eq!(confirmations(100, 0), 0);
+    }
+}
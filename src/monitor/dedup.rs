@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::node::Transaction;
+use crate::wallet::crypto::at_rest::AtRestCipher;
+use crate::wallet::crypto::memory::SecureBuffer;
+
+/// Identity of an on-chain event: one transaction, or one log within it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EventKey {
+    pub chain: String,
+    pub tx_hash: String,
+    /// Position of the log (token transfer) within the transaction, if any.
+    pub log_index: Option<u32>,
+}
+
+impl EventKey {
+    pub fn new(chain: &str, tx_hash: &str, log_index: Option<u32>) -> Self {
+        Self {
+            chain: chain.to_string(),
+            // Explorers disagree on hash casing and `0x` prefixes.
+            tx_hash: tx_hash.trim_start_matches("0x").to_ascii_lowercase(),
+            log_index,
+        }
+    }
+
+    /// Key of a transaction's native transfer.
+    pub fn for_transaction(chain: &str, tx: &Transaction) -> Self {
+        Self::new(chain, &tx.hash, None)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split('\t');
+        let chain = parts.next()?;
+        let tx_hash = parts.next()?;
+        let log_index = match parts.next()? {
+            "-" => None,
+            n => Some(n.parse().ok()?),
+        };
+        Some(Self::new(chain, tx_hash, log_index))
+    }
+}
+
+impl fmt::Display for EventKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.log_index {
+            Some(index) => write!(f, "{}\t{}\t{}", self.chain, self.tx_hash, index),
+            None => write!(f, "{}\t{}\t-", self.chain, self.tx_hash),
+        }
+    }
+}
+
+/// Set of events already reported, shared by monitors so overlapping pages,
+/// provider failover and restarts never report the same event twice.
+pub trait SeenStore: Send + Sync {
+    /// Record `key`; returns `false` if it was already present.
+    fn insert(&self, key: &EventKey) -> std::io::Result<bool>;
+    fn contains(&self, key: &EventKey) -> bool;
+}
+
+/// In-process seen-set; forgets everything on restart.
+#[derive(Default)]
+pub struct MemorySeenStore {
+    keys: Mutex<HashSet<EventKey>>,
+}
+
+impl MemorySeenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SeenStore for MemorySeenStore {
+    fn insert(&self, key: &EventKey) -> std::io::Result<bool> {
+        Ok(self.keys.lock().unwrap().insert(key.clone()))
+    }
+
+    fn contains(&self, key: &EventKey) -> bool {
+        self.keys.lock().unwrap().contains(key)
+    }
+}
+
+/// Seen-set persisted as an append-only file, one tab-separated key per line.
+///
+/// Stores opened with [`open_encrypted`](Self::open_encrypted) write each line
+/// sealed with AES-256-GCM instead, so the file does not reveal which
+/// transactions the operator tracks.
+pub struct FileSeenStore {
+    keys: Mutex<HashSet<EventKey>>,
+    file: Mutex<File>,
+    cipher: Option<AtRestCipher>,
+}
+
+impl FileSeenStore {
+    /// Open (or create) the store at `path`, loading previously seen keys.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::open_with(path.as_ref(), None)
+    }
+
+    /// Open (or create) an encrypted store at `path` with a 32-byte `key`.
+    /// Fails with `InvalidData` if existing entries do not decrypt under `key`.
+    pub fn open_encrypted(path: impl AsRef<Path>, key: &SecureBuffer) -> std::io::Result<Self> {
+        let cipher = AtRestCipher::new(key)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        Self::open_with(path.as_ref(), Some(cipher))
+    }
+
+    fn open_with(path: &Path, cipher: Option<AtRestCipher>) -> std::io::Result<Self> {
+        let mut keys = HashSet::new();
+        if path.exists() {
+            let lines = BufReader::new(File::open(path)?)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()?;
+            let last = lines.len().saturating_sub(1);
+            for (i, line) in lines.iter().enumerate() {
+                let key = match &cipher {
+                    Some(cipher) => match hex::decode(line).ok().map(|b| cipher.open(&b)) {
+                        Some(Ok(plain)) => EventKey::parse(&String::from_utf8_lossy(&plain)),
+                        // Only the last line can be torn; anything else is the wrong key.
+                        _ if i == last => None,
+                        _ => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!("seen-set entry {} does not decrypt", i + 1),
+                            ));
+                        }
+                    },
+                    // A torn final line from a crash is skipped, not fatal.
+                    None => EventKey::parse(line),
+                };
+                if let Some(key) = key {
+                    keys.insert(key);
+                }
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            keys: Mutex::new(keys),
+            file: Mutex::new(file),
+            cipher,
+        })
+    }
+}
+
+impl SeenStore for FileSeenStore {
+    fn insert(&self, key: &EventKey) -> std::io::Result<bool> {
+        let mut keys = self.keys.lock().unwrap();
+        if keys.contains(key) {
+            return Ok(false);
+        }
+        let mut file = self.file.lock().unwrap();
+        match &self.cipher {
+            Some(cipher) => writeln!(
+                file,
+                "{}",
+                hex::encode(cipher.seal(key.to_string().as_bytes()))
+            )?,
+            None => writeln!(file, "{}", key)?,
+        }
+        file.sync_data()?;
+        keys.insert(key.clone());
+        Ok(true)
+    }
+
+    fn contains(&self, key: &EventKey) -> bool {
+        self.keys.lock().unwrap().contains(key)
+    }
+}
+
+/// Drop repeated entries (same chain, hash and log) from a history listing, keeping the first.
+pub fn dedup_transactions(chain: &str, transactions: Vec<Transaction>) -> Vec<Transaction> {
+    let mut seen = HashSet::new();
+    transactions
+        .into_iter()
+        .filter(|tx| seen.insert(EventKey::for_transaction(chain, tx)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("flow-wallet-seen-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key = EventKey::new("tron", "0xABCD", Some(2));
+
+        {
+            let store = FileSeenStore::open(&path).unwrap();
+            assert!(store.insert(&key).unwrap());
+            assert!(
+                !store
+                    .insert(&EventKey::new("tron", "abcd", Some(2)))
+                    .unwrap()
+            );
+            assert!(store.insert(&EventKey::new("tron", "abcd", None)).unwrap());
+        }
+
+        let reopened = FileSeenStore::open(&path).unwrap();
+        assert!(reopened.contains(&key));
+        assert!(!reopened.insert(&key).unwrap());
+        assert!(!reopened.contains(&EventKey::new("litecoin", "abcd", Some(2))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_store_hides_keys_and_rejects_wrong_key() {
+        let path =
+            std::env::temp_dir().join(format!("flow-wallet-seen-enc-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key = SecureBuffer::new(vec![9u8; 32]);
+        let event = EventKey::new("tron", "abcd", None);
+
+        {
+            let store = FileSeenStore::open_encrypted(&path, &key).unwrap();
+            assert!(store.insert(&event).unwrap());
+            assert!(
+                store
+                    .insert(&EventKey::new("tron", "ef01", Some(0)))
+                    .unwrap()
+            );
+        }
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("abcd"));
+
+        let reopened = FileSeenStore::open_encrypted(&path, &key).unwrap();
+        assert!(reopened.contains(&event));
+        let wrong = SecureBuffer::new(vec![1u8; 32]);
+        assert!(FileSeenStore::open_encrypted(&path, &wrong).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,425 @@
+use crate::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::metrics::Metrics;
+use crate::monitor::confirmations::{ConfirmationPolicy, NATIVE, confirmations};
+use crate::monitor::dedup::{EventKey, MemorySeenStore, SeenStore};
+use crate::monitor::{MonitorError, PollMetrics};
+use crate::node::ReadProvider;
+use crate::persist::Persist;
+use crate::shutdown::Shutdown;
+
+/// An incoming native-coin transfer to a watched address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub tx_hash: String,
+    pub address: String,
+    pub from: String,
+    /// Amount in base units.
+    pub amount: u128,
+    pub block_number: u64,
+    pub confirmations: u64,
+    /// Confirmations the policy requires for this deposit.
+    pub required: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepositEvent {
+    /// First sighting, before the policy's confirmation threshold.
+    Detected(Deposit),
+    /// Reached the required confirmations; reported once per [`SeenStore`].
+    Confirmed(Deposit),
+    /// A detected deposit left the chain before confirming, e.g. in a reorg.
+    /// It is reported as `Detected` again if it is mined anew.
+    Reverted(Deposit),
+    /// A poll of [`run`](DepositTracker::run) failed; the tracker carries on
+    /// after its interval.
+    Error(String),
+}
+
+/// Deposits a [`DepositTracker`] has reported as detected but not yet confirmed.
+///
+/// Restoring it after a restart keeps `Detected` from firing a second time for
+/// the same deposit. Confirmed deposits live in the tracker's [`SeenStore`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositCheckpoint {
+    pub chain: String,
+    pub pending: Vec<Deposit>,
+}
+
+impl Persist for DepositCheckpoint {
+    const KIND: &'static str = "deposit_checkpoint";
+    const VERSION: u16 = 1;
+}
+
+/// Polls watched addresses for incoming native-coin transfers and reports them as they
+/// are seen and once they reach the confirmations the [`ConfirmationPolicy`] asks for.
+pub struct DepositTracker {
+    provider: Arc<dyn ReadProvider>,
+    chain: String,
+    policy: ConfirmationPolicy,
+    addresses: Vec<String>,
+    pending: Mutex<HashMap<String, Deposit>>,
+    confirmed: Arc<dyn SeenStore>,
+    events: broadcast::Sender<DepositEvent>,
+    metrics: PollMetrics,
+}
+
+impl DepositTracker {
+    /// Track deposits on `chain` (a `Chain::id`, used to look up policy rules).
+    pub fn new(provider: Arc<dyn ReadProvider>, chain: &str, policy: ConfirmationPolicy) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            provider,
+            chain: chain.to_string(),
+            policy,
+            addresses: Vec::new(),
+            pending: Mutex::new(HashMap::new()),
+            confirmed: Arc::new(MemorySeenStore::new()),
+            events,
+            metrics: PollMetrics::default(),
+        }
+    }
+
+    pub fn watch(mut self, address: &str) -> Self {
+        self.add_address(address);
+        self
+    }
+
+    pub(crate) fn add_address(&mut self, address: &str) {
+        self.addresses.push(address.to_string());
+    }
+
+    pub(crate) fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    /// Remember confirmed deposits in `store`, e.g. a [`FileSeenStore`](crate::monitor::FileSeenStore)
+    /// so a restarted process does not report them again.
+    pub fn with_seen_store(mut self, store: Arc<dyn SeenStore>) -> Self {
+        self.confirmed = store;
+        self
+    }
+
+    /// Report every poll, its latency and how stale the tracker is to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = PollMetrics::new(metrics);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DepositEvent> {
+        self.events.subscribe()
+    }
+
+    /// Deposits seen but not yet final.
+    pub fn pending(&self) -> Vec<Deposit> {
+        self.pending.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn checkpoint(&self) -> DepositCheckpoint {
+        let mut pending = self.pending();
+        pending.sort_by(|a, b| a.tx_hash.cmp(&b.tx_hash));
+        DepositCheckpoint {
+            chain: self.chain.clone(),
+            pending,
+        }
+    }
+
+    /// Resume from a checkpoint taken on the same chain; others are ignored.
+    pub fn restore(&self, checkpoint: DepositCheckpoint) {
+        if checkpoint.chain != self.chain {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        for deposit in checkpoint.pending {
+            pending.insert(deposit.tx_hash.clone(), deposit);
+        }
+    }
+
+    /// Check every watched address once and publish the resulting events.
+    ///
+    /// A pending deposit missing from the listings is looked up by hash: if the
+    /// provider no longer has it mined it is reported as reverted. Providers
+    /// that cannot look transactions up leave it pending.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "deposit_poll",
+            skip_all,
+            fields(chain = %self.chain, addresses = self.addresses.len()),
+            err
+        )
+    )]
+    pub async fn poll(&self) -> Result<Vec<DepositEvent>, MonitorError> {
+        let started = Instant::now();
+        let result = self.scan().await;
+        self.metrics
+            .record("deposits", &self.chain, started, result.is_ok());
+        result
+    }
+
+    async fn scan(&self) -> Result<Vec<DepositEvent>, MonitorError> {
+        let tip = self.provider.get_block_number().await?;
+        let mut events = Vec::new();
+        let mut listed = HashSet::new();
+
+        for address in &self.addresses {
+            for tx in self.provider.get_transactions(address).await? {
+                // Native coin only; token transfers are not deposits of it.
+                if tx.to != *address || tx.is_failed() || tx.token().is_some() {
+                    continue;
+                }
+                let key = EventKey::for_transaction(&self.chain, &tx);
+                listed.insert(key.clone());
+                if self.confirmed.contains(&key) {
+                    continue;
+                }
+                let Ok(amount) = tx.value.parse::<u128>() else {
+                    continue;
+                };
+
+                let deposit = Deposit {
+                    confirmations: confirmations(tip, tx.block_number),
+                    required: self.policy.required(&self.chain, NATIVE, amount),
+                    tx_hash: tx.hash,
+                    address: address.clone(),
+                    from: tx.from,
+                    amount,
+                    block_number: tx.block_number,
+                };
+                self.record(deposit, &mut events)?;
+            }
+        }
+
+        let missing: Vec<Deposit> = self
+            .pending
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|d| !listed.contains(&EventKey::new(&self.chain, &d.tx_hash, None)))
+            .cloned()
+            .collect();
+        for mut deposit in missing {
+            match self.provider.get_transaction(&deposit.tx_hash).await {
+                // Still mined, just no longer on the listing's first page.
+                Ok(Some(tx)) if tx.block_number > 0 && !tx.is_failed() => {
+                    deposit.block_number = tx.block_number;
+                    deposit.confirmations = confirmations(tip, tx.block_number);
+                    self.record(deposit, &mut events)?;
+                }
+                Ok(_) => {
+                    self.pending.lock().unwrap().remove(&deposit.tx_hash);
+                    events.push(DepositEvent::Reverted(deposit));
+                }
+                Err(_) => {}
+            }
+        }
+
+        for event in &events {
+            let _ = self.events.send(event.clone());
+        }
+        Ok(events)
+    }
+
+    /// Update `deposit`'s pending entry, confirming it once it is final.
+    fn record(&self, deposit: Deposit, events: &mut Vec<DepositEvent>) -> Result<(), MonitorError> {
+        let key = EventKey::new(&self.chain, &deposit.tx_hash, None);
+        let mut pending = self.pending.lock().unwrap();
+        let first_sighting = !pending.contains_key(&deposit.tx_hash);
+        if deposit.confirmations >= deposit.required {
+            pending.remove(&deposit.tx_hash);
+            // Recorded before reporting: a crash may lose an event, never repeat one.
+            if self.confirmed.insert(&key)? {
+                events.push(DepositEvent::Confirmed(deposit));
+            }
+        } else {
+            pending.insert(deposit.tx_hash.clone(), deposit.clone());
+            if first_sighting {
+                events.push(DepositEvent::Detected(deposit));
+            }
+        }
+        Ok(())
+    }
+
+    /// Poll every `interval` until `shutdown` is triggered, publishing
+    /// errors as [`DepositEvent::Error`] and retrying.
+    pub async fn run(&self, interval: Duration, shutdown: &Shutdown) {
+        while !shutdown.is_triggered() {
+            if let Err(e) = self.poll().await {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(chain = %self.chain, error = %e, "deposit tracker: poll failed");
+                let _ = self.events.send(DepositEvent::Error(e.to_string()));
+            }
+            if shutdown.sleep(interval).await {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, ReadProvider, Transaction};
+
+    struct Chain {
+        tip: AtomicU64,
+        txs: Mutex<Vec<Transaction>>,
+    }
+
+    impl Chain {
+        fn new(tip: u64, txs: Vec<Transaction>) -> Arc<Self> {
+            Arc::new(Self {
+                tip: AtomicU64::new(tip),
+                txs: Mutex::new(txs),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ReadProvider for Chain {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(self.txs.lock().unwrap().clone())
+        }
+        async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+            let txs = self.txs.lock().unwrap();
+            Ok(txs.iter().find(|tx| tx.hash == hash).cloned())
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(self.tip.load(Ordering::SeqCst))
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    fn incoming(hash: &str, value: &str, block_number: u64) -> Transaction {
+        Transaction {
+            hash: hash.into(),
+            from: "sender".into(),
+            to: "deposit".into(),
+            value: value.into(),
+            block_number,
+            timestamp: 0,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn confirms_per_policy_once() {
+        let provider = Chain::new(
+            10,
+            vec![
+                incoming("small", "100", 10),
+                incoming("large", "900000", 10),
+            ],
+        );
+        let policy = ConfirmationPolicy::new(1).rule("litecoin", NATIVE, 1_000.., 6);
+        let tracker = DepositTracker::new(provider.clone(), "litecoin", policy).watch("deposit");
+
+        let events = tracker.poll().await.unwrap();
+        assert!(matches!(&events[0], DepositEvent::Confirmed(d) if d.tx_hash == "small"));
+        assert!(matches!(&events[1], DepositEvent::Detected(d) if d.required == 6));
+
+        provider.tip.store(14, Ordering::SeqCst);
+        assert!(tracker.poll().await.unwrap().is_empty());
+
+        // A restarted tracker resumed from a checkpoint does not re-detect.
+        let bytes = crate::persist::to_bytes(&tracker.checkpoint()).unwrap();
+        let restarted = DepositTracker::new(provider.clone(), "litecoin", tracker.policy.clone())
+            .watch("deposit");
+        restarted.restore(crate::persist::from_bytes(&bytes).unwrap());
+        assert_eq!(restarted.pending(), tracker.pending());
+        let events = restarted.poll().await.unwrap();
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, DepositEvent::Detected(_)))
+        );
+
+        provider.tip.store(15, Ordering::SeqCst);
+        let events = tracker.poll().await.unwrap();
+        assert!(matches!(&events[..], [DepositEvent::Confirmed(d)] if d.confirmations == 6));
+        assert!(tracker.poll().await.unwrap().is_empty());
+        assert!(tracker.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn shared_store_suppresses_repeats_across_trackers() {
+        // The same deposit listed twice, as overlapping pages would.
+        let provider = Chain::new(
+            10,
+            vec![incoming("dup", "100", 10), incoming("0xDUP", "100", 10)],
+        );
+        let store: Arc<dyn SeenStore> = Arc::new(MemorySeenStore::new());
+
+        let first = DepositTracker::new(provider.clone(), "tron", ConfirmationPolicy::new(1))
+            .watch("deposit")
+            .with_seen_store(store.clone());
+        assert_eq!(first.poll().await.unwrap().len(), 1);
+
+        // A new tracker (e.g. after a restart) sharing the store stays quiet.
+        let restarted = DepositTracker::new(provider, "tron", ConfirmationPolicy::new(1))
+            .watch("deposit")
+            .with_seen_store(store);
+        assert!(restarted.poll().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_deposits_dropped_by_a_reorg() {
+        let provider = Chain::new(10, vec![incoming("a", "100", 10), incoming("b", "100", 9)]);
+        let tracker = DepositTracker::new(provider.clone(), "litecoin", ConfirmationPolicy::new(6))
+            .watch("deposit");
+        assert_eq!(tracker.poll().await.unwrap().len(), 2);
+
+        // The reorg drops "a" and moves "b" to a later block.
+        *provider.txs.lock().unwrap() = vec![incoming("b", "100", 11)];
+        provider.tip.store(11, Ordering::SeqCst);
+        let events = tracker.poll().await.unwrap();
+        assert!(matches!(&events[..], [DepositEvent::Reverted(d)] if d.tx_hash == "a"));
+        assert_eq!(tracker.pending()[0].confirmations, 1);
+
+        // Re-mined later, it is a fresh sighting.
+        provider.txs.lock().unwrap().push(incoming("a", "100", 12));
+        provider.tip.store(12, Ordering::SeqCst);
+        let events = tracker.poll().await.unwrap();
+        assert!(matches!(&events[..], [DepositEvent::Detected(d)] if d.tx_hash == "a"));
+    }
+
+    #[tokio::test]
+    async fn run_publishes_failed_polls() {
+        let provider = crate::testing::MockProvider::new(8);
+        provider.fail_next(
+            crate::testing::MockCall::GetBlockNumber,
+            NodeError::Network("down".into()),
+        );
+        let tracker =
+            DepositTracker::new(Arc::new(provider), "litecoin", ConfirmationPolicy::new(1))
+                .watch("deposit");
+        let mut events = tracker.subscribe();
+
+        let shutdown = Shutdown::new();
+        let stop = shutdown.clone();
+        let run = tracker.run(Duration::from_millis(10), &shutdown);
+        let first = async {
+            let event = events.recv().await.unwrap();
+            stop.trigger();
+            event
+        };
+        let ((), event) = tokio::join!(run, first);
+        assert!(matches!(event, DepositEvent::Error(e) if e.contains("down")));
+    }
+}
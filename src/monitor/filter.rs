@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+
+use crate::node::{Transaction, TxDetails};
+
+/// Which way a transfer moves relative to a watched address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+/// One value movement within a transaction: the native transfer, or a token
+/// transfer decoded into its [`details`](Transaction::details).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    /// Amount in base units of the coin or token, as a decimal string.
+    pub value: String,
+    /// Token contract, `None` for the native coin.
+    pub token: Option<String>,
+}
+
+impl Transfer {
+    /// Every movement in `tx`: decoded token transfers first, then the native
+    /// transfer unless it is a zero-value contract call carrying tokens.
+    pub fn all(tx: &Transaction) -> Vec<Transfer> {
+        let mut transfers: Vec<Transfer> = match &tx.details {
+            Some(TxDetails::Tvm(details)) => details
+                .token_transfers
+                .iter()
+                .map(|t| Transfer {
+                    from: t.from.clone(),
+                    to: t.to.clone(),
+                    value: t.value.clone(),
+                    token: Some(t.token.clone()),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if transfers.is_empty() || !tx.value.trim_start_matches('0').is_empty() {
+            transfers.push(Transfer {
+                from: tx.from.clone(),
+                to: tx.to.clone(),
+                value: tx.value.clone(),
+                token: None,
+            });
+        }
+        transfers
+    }
+}
+
+/// EVM hex addresses compare case-insensitively; base58 and bech32 exactly.
+fn same_address(a: &str, b: &str) -> bool {
+    if a.starts_with("0x") && b.starts_with("0x") {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+/// Which transactions a [`TransactionMonitor`](crate::monitor::TransactionMonitor) reports.
+///
+/// A transaction passes when any of its [`Transfer`]s does. The default lets
+/// everything through; each builder call narrows it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MonitorFilter {
+    direction: Option<Direction>,
+    min_value: u128,
+    allowed_senders: Vec<String>,
+    denied_senders: Vec<String>,
+    token_contracts: Vec<String>,
+}
+
+impl MonitorFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only transfers moving this way relative to the watched address.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Drop transfers below `value` base units (dust).
+    pub fn min_value(mut self, value: u128) -> Self {
+        self.min_value = value;
+        self
+    }
+
+    /// Only transfers from `address`; may be called repeatedly.
+    pub fn allow_sender(mut self, address: &str) -> Self {
+        self.allowed_senders.push(address.to_string());
+        self
+    }
+
+    /// Never transfers from `address`, even if allowed.
+    pub fn deny_sender(mut self, address: &str) -> Self {
+        self.denied_senders.push(address.to_string());
+        self
+    }
+
+    /// Only transfers of the token at `contract` (e.g. a TRC-20); native
+    /// transfers no longer pass. Needs transactions with decoded details.
+    pub fn token_contract(mut self, contract: &str) -> Self {
+        self.token_contracts.push(contract.to_string());
+        self
+    }
+
+    /// The first transfer of `tx` that passes, with its direction relative to `watched`.
+    pub fn matching(&self, watched: &str, tx: &Transaction) -> Option<(Direction, Transfer)> {
+        Transfer::all(tx).into_iter().find_map(|transfer| {
+            let direction = self.matching_transfer(watched, &transfer)?;
+            Some((direction, transfer))
+        })
+    }
+
+    /// Direction of `transfer` relative to `watched`, if it passes.
+    pub fn matching_transfer(&self, watched: &str, transfer: &Transfer) -> Option<Direction> {
+        let direction = self.direction_of(watched, transfer)?;
+        self.accepts(transfer).then_some(direction)
+    }
+
+    fn direction_of(&self, watched: &str, transfer: &Transfer) -> Option<Direction> {
+        let incoming = same_address(&transfer.to, watched);
+        let outgoing = same_address(&transfer.from, watched);
+        match self.direction {
+            Some(Direction::Incoming) if incoming => Some(Direction::Incoming),
+            Some(Direction::Outgoing) if outgoing => Some(Direction::Outgoing),
+            None if incoming => Some(Direction::Incoming),
+            None if outgoing => Some(Direction::Outgoing),
+            _ => None,
+        }
+    }
+
+    fn accepts(&self, transfer: &Transfer) -> bool {
+        let listed = |list: &[String]| list.iter().any(|a| same_address(a, &transfer.from));
+        let token_ok = match &transfer.token {
+            Some(token) => {
+                self.token_contracts.is_empty()
+                    || self.token_contracts.iter().any(|c| same_address(c, token))
+            }
+            None => self.token_contracts.is_empty(),
+        };
+        token_ok
+            && transfer.value.parse::<u128>().unwrap_or(0) >= self.min_value
+            && (self.allowed_senders.is_empty() || listed(&self.allowed_senders))
+            && !listed(&self.denied_senders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::details::{TokenTransfer, TvmTxDetails};
+
+    const USDT: &str = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+    fn tx(from: &str, to: &str, value: &str) -> Transaction {
+        Transaction {
+            hash: "h".into(),
+            from: from.into(),
+            to: to.into(),
+            value: value.into(),
+            block_number: 1,
+            timestamp: 0,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        }
+    }
+
+    #[test]
+    fn filters_dust_direction_senders_and_tokens() {
+        let dust = MonitorFilter::new().min_value(1_000);
+        assert!(dust.matching("me", &tx("a", "me", "999")).is_none());
+        assert_eq!(
+            dust.matching("me", &tx("me", "b", "1000")).unwrap().0,
+            Direction::Outgoing
+        );
+
+        let incoming = MonitorFilter::new()
+            .direction(Direction::Incoming)
+            .deny_sender("spam");
+        assert!(incoming.matching("me", &tx("me", "b", "5")).is_none());
+        assert!(incoming.matching("me", &tx("spam", "me", "5")).is_none());
+        assert!(incoming.matching("me", &tx("a", "me", "5")).is_some());
+        let allowed = MonitorFilter::new().allow_sender("0xAbC");
+        assert!(
+            allowed
+                .matching("0xme", &tx("0xabc", "0xME", "1"))
+                .is_some()
+        );
+
+        // A TRC-20 transfer: the native leg is a zero-value contract call.
+        let mut call = tx("a", USDT, "0");
+        call.details = Some(TxDetails::Tvm(TvmTxDetails {
+            token_transfers: vec![TokenTransfer {
+                token: USDT.into(),
+                from: "a".into(),
+                to: "me".into(),
+                value: "2500000".into(),
+            }],
+            ..Default::default()
+        }));
+        let usdt = MonitorFilter::new().token_contract(USDT);
+        let (direction, transfer) = usdt.matching("me", &call).unwrap();
+        assert_eq!(direction, Direction::Incoming);
+        assert_eq!(transfer.value, "2500000");
+        assert!(usdt.matching("me", &tx("a", "me", "5")).is_none());
+    }
+}
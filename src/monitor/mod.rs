@@ -0,0 +1,72 @@
+pub mod balance;
+pub mod confirmations;
+pub mod dedup;
+pub mod deposits;
+pub mod filter;
+pub mod sharded;
+pub mod sink;
+pub mod transactions;
+pub mod watchdog;
+
+use crate::time::Instant;
+use std::sync::{Arc, Mutex};
+
+use thiserror::Error;
+
+use crate::metrics::Metrics;
+use crate::node::NodeError;
+
+pub use balance::{BalanceCheckpoint, BalanceEvent, BalanceReading, BalanceSource, BalanceWatcher};
+pub use confirmations::{Confirmation, ConfirmationPolicy, WaitOptions};
+pub use dedup::{EventKey, FileSeenStore, MemorySeenStore, SeenStore};
+pub use deposits::{Deposit, DepositCheckpoint, DepositEvent, DepositTracker};
+pub use filter::{Direction, MonitorFilter, Transfer};
+pub use sharded::ShardedDepositTracker;
+#[cfg(feature = "network")]
+pub use sink::WebhookSink;
+pub use sink::{EventSink, Notification, NotificationKind, SendMetadataSource};
+pub use transactions::{
+    AddressCursor, BlockCursor, MonitorEvent, MonitorState, MonitorStream, MonitoredTransaction,
+    TransactionMonitor,
+};
+pub use watchdog::{BumpReason, PendingTransaction, SendWatchdog, WatchdogEvent};
+
+#[derive(Debug, Error)]
+pub enum MonitorError {
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[error("seen-set store: {0}")]
+    Store(#[from] std::io::Error),
+    #[error("event sink: {0}")]
+    Sink(String),
+}
+
+/// Reports a monitor's polls to its [`Metrics`], if it was given one.
+#[derive(Default)]
+pub(crate) struct PollMetrics {
+    metrics: Option<Arc<dyn Metrics>>,
+    /// Start of the last successful poll, or of the first poll until one succeeds.
+    fresh_since: Mutex<Option<Instant>>,
+}
+
+impl PollMetrics {
+    pub(crate) fn new(metrics: Arc<dyn Metrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            fresh_since: Mutex::new(None),
+        }
+    }
+
+    /// Record a poll of `monitor` on `chain` begun at `started`.
+    pub(crate) fn record(&self, monitor: &str, chain: &str, started: Instant, ok: bool) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let mut fresh_since = self.fresh_since.lock().unwrap();
+        if ok || fresh_since.is_none() {
+            *fresh_since = Some(started);
+        }
+        let lag = fresh_since.unwrap_or(started).elapsed();
+        metrics.monitor_poll(monitor, chain, started.elapsed(), lag, ok);
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+
+use crate::metrics::Metrics;
+use crate::monitor::MonitorError;
+use crate::monitor::confirmations::ConfirmationPolicy;
+use crate::monitor::dedup::{MemorySeenStore, SeenStore};
+use crate::monitor::deposits::{DepositCheckpoint, DepositEvent, DepositTracker};
+use crate::node::{Provider, RateBudget, RateLimitedProvider};
+use crate::shutdown::Shutdown;
+
+/// [`DepositTracker`] for address sets too large for one polling loop:
+/// addresses are split across `shards` trackers polled concurrently, all
+/// drawing on one [`RateBudget`] for the provider so that together they stay
+/// within its limits.
+///
+/// An address always lands in the same shard (by hash), and all shards share
+/// one [`SeenStore`], so confirmed deposits are reported once whichever shard
+/// sees them. [`checkpoint`](Self::checkpoint) merges the shards into one
+/// [`DepositCheckpoint`], which [`restore`](Self::restore) routes back by
+/// address, even into a tracker with a different shard count.
+pub struct ShardedDepositTracker {
+    shards: Vec<DepositTracker>,
+    chain: String,
+    events: broadcast::Sender<DepositEvent>,
+}
+
+impl ShardedDepositTracker {
+    /// `shards` trackers on `chain` sharing a budget of `requests_per_second`
+    /// to `provider`.
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        chain: &str,
+        policy: ConfirmationPolicy,
+        shards: usize,
+        requests_per_second: u32,
+    ) -> Self {
+        let budget = Arc::new(RateBudget::per_second(requests_per_second));
+        let provider: Arc<dyn Provider> = Arc::new(RateLimitedProvider::new(provider, budget));
+        let store: Arc<dyn SeenStore> = Arc::new(MemorySeenStore::new());
+        let (events, _) = broadcast::channel(1024);
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| {
+                    DepositTracker::new(provider.clone(), chain, policy.clone())
+                        .with_seen_store(store.clone())
+                })
+                .collect(),
+            chain: chain.to_string(),
+            events,
+        }
+    }
+
+    pub fn watch(mut self, address: &str) -> Self {
+        let shard = self.shard_of(address);
+        self.shards[shard].add_address(address);
+        self
+    }
+
+    pub fn watch_all<'a>(self, addresses: impl IntoIterator<Item = &'a str>) -> Self {
+        addresses.into_iter().fold(self, Self::watch)
+    }
+
+    /// Remember confirmed deposits in `store` instead of in memory.
+    pub fn with_seen_store(mut self, store: Arc<dyn SeenStore>) -> Self {
+        self.shards = self
+            .shards
+            .into_iter()
+            .map(|shard| shard.with_seen_store(store.clone()))
+            .collect();
+        self
+    }
+
+    /// Report every shard's polls to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.shards = self
+            .shards
+            .into_iter()
+            .map(|shard| shard.with_metrics(metrics.clone()))
+            .collect();
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DepositEvent> {
+        self.events.subscribe()
+    }
+
+    /// Number of addresses in each shard.
+    pub fn shard_sizes(&self) -> Vec<usize> {
+        self.shards.iter().map(|s| s.addresses().len()).collect()
+    }
+
+    /// Poll every shard once, concurrently, and publish their events. Each
+    /// shard succeeds or fails on its own; results are in shard order.
+    pub async fn poll(&self) -> Vec<Result<Vec<DepositEvent>, MonitorError>> {
+        let results = join_all(self.shards.iter().map(DepositTracker::poll)).await;
+        for event in results.iter().flatten().flatten() {
+            let _ = self.events.send(event.clone());
+        }
+        results
+    }
+
+    /// Pending deposits of every shard, sorted by hash.
+    pub fn checkpoint(&self) -> DepositCheckpoint {
+        let mut pending: Vec<_> = self.shards.iter().flat_map(|s| s.pending()).collect();
+        pending.sort_by(|a, b| a.tx_hash.cmp(&b.tx_hash));
+        DepositCheckpoint {
+            chain: self.chain.clone(),
+            pending,
+        }
+    }
+
+    /// Hand each pending deposit of `checkpoint` to the shard watching its
+    /// address. A shard must never hold another's deposits: it would find
+    /// them missing from its own listings and look them up as reorged.
+    pub fn restore(&self, checkpoint: DepositCheckpoint) {
+        let mut split: Vec<Vec<_>> = vec![Vec::new(); self.shards.len()];
+        for deposit in checkpoint.pending {
+            split[self.shard_of(&deposit.address)].push(deposit);
+        }
+        for (shard, pending) in self.shards.iter().zip(split) {
+            shard.restore(DepositCheckpoint {
+                chain: checkpoint.chain.clone(),
+                pending,
+            });
+        }
+    }
+
+    /// Poll every `interval` until `shutdown` is triggered, publishing each
+    /// failed shard as a [`DepositEvent::Error`] and retrying.
+    pub async fn run(&self, interval: Duration, shutdown: &Shutdown) {
+        while !shutdown.is_triggered() {
+            for (shard, result) in self.poll().await.into_iter().enumerate() {
+                if let Err(e) = result {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(chain = %self.chain, shard, error = %e, "deposit tracker: poll failed");
+                    let _ = self
+                        .events
+                        .send(DepositEvent::Error(format!("shard {}: {}", shard, e)));
+                }
+            }
+            if shutdown.sleep(interval).await {
+                break;
+            }
+        }
+    }
+
+    fn shard_of(&self, address: &str) -> usize {
+        let digest = Sha256::digest(address.as_bytes());
+        let hash = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"));
+        (hash % self.shards.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, RawTransaction, ReadProvider, Transaction, TransactionProvider};
+
+    /// One incoming transfer to every address, mined at block 10 of 10.
+    struct Exchange {
+        lookups: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Exchange {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![Transaction {
+                hash: format!("tx-{}", address),
+                from: "customer".into(),
+                to: address.into(),
+                value: "100".into(),
+                block_number: 10,
+                timestamp: 0,
+                status: "SUCCESS".into(),
+                raw: None,
+                details: None,
+            }])
+        }
+        async fn get_transaction(&self, _: &str) -> Result<Option<Transaction>, NodeError> {
+            *self.lookups.lock().unwrap() += 1;
+            Ok(None)
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(10)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Exchange {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn shards_addresses_and_checkpoints_across_reshards() {
+        let provider = Arc::new(Exchange {
+            lookups: Mutex::new(0),
+        });
+        let addresses: Vec<String> = (0..40).map(|i| format!("addr{}", i)).collect();
+        let policy = ConfirmationPolicy::new(6);
+        let tracker =
+            ShardedDepositTracker::new(provider.clone(), "tron", policy.clone(), 4, 10_000)
+                .watch_all(addresses.iter().map(String::as_str));
+        let sizes = tracker.shard_sizes();
+        assert_eq!(sizes.iter().sum::<usize>(), 40);
+        assert!(sizes.iter().all(|&n| n > 0));
+
+        let detected: usize = tracker
+            .poll()
+            .await
+            .into_iter()
+            .map(|r| r.unwrap().len())
+            .sum();
+        assert_eq!(detected, 40);
+        let checkpoint = tracker.checkpoint();
+        assert_eq!(checkpoint.pending.len(), 40);
+
+        // Restored into a different shard count, nothing is re-detected and
+        // no shard mistakes another's deposits for reorged ones.
+        let resharded = ShardedDepositTracker::new(provider.clone(), "tron", policy, 7, 10_000)
+            .watch_all(addresses.iter().map(String::as_str));
+        resharded.restore(checkpoint.clone());
+        let results = resharded.poll().await;
+        assert!(results.into_iter().all(|r| r.unwrap().is_empty()));
+        assert_eq!(*provider.lookups.lock().unwrap(), 0);
+        assert_eq!(resharded.checkpoint(), checkpoint);
+    }
+}
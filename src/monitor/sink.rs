@@ -0,0 +1,367 @@
+//! Delivering a [`TransactionMonitor`](super::TransactionMonitor)'s events
+//! outside the process, alongside its broadcast channel.
+//!
+//! A [`WebhookSink`] (feature `network`) POSTs each poll's events to a URL
+//! as `{"events": [...]}`, every entry a [`Notification`]. With a secret,
+//! requests carry `x-signature-timestamp`, in unix seconds, and
+//! `x-signature`, the hex HMAC-SHA256 under the secret of `{timestamp}\n`
+//! followed by the body, so the receiver can refuse requests it did not
+//! expect or that are replayed late.
+//!
+//! Given a [`SendMetadataSource`], e.g. the wallet's
+//! [`MemoryAuditLog`](crate::wallet::audit::MemoryAuditLog), notifications
+//! of the wallet's own sends carry the travel-rule metadata they were made
+//! with.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::monitor::filter::{Direction, Transfer};
+use crate::monitor::{MonitorError, MonitorEvent, MonitoredTransaction};
+use crate::node::ContractEvent;
+use crate::wallet::options::TravelRuleMetadata;
+
+#[cfg(feature = "network")]
+pub use webhook::{DEFAULT_MAX_BATCH, WebhookSink};
+
+/// What a [`Notification`] reports, after the [`MonitorEvent`] variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    NewTransaction,
+    Confirmed,
+    Reverted,
+    ContractEvent,
+}
+
+/// A [`MonitorEvent`] flattened for consumers in other processes: the same
+/// fields whatever the kind, those that do not apply left `null`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    /// The monitor's chain id.
+    pub chain: String,
+    pub tx_hash: String,
+    pub block_number: u64,
+    /// The watched address the transaction was reported under.
+    pub address: Option<String>,
+    pub direction: Option<Direction>,
+    pub transfer: Option<Transfer>,
+    pub display_value: Option<String>,
+    pub explorer_url: Option<String>,
+    /// Blocks on top of the transaction, for `confirmed`.
+    pub confirmations: Option<u64>,
+    /// The log, for `contract_event`.
+    pub event: Option<ContractEvent>,
+    /// What the send was made with, for sends a [`SendMetadataSource`] knows.
+    #[serde(default)]
+    pub metadata: Option<TravelRuleMetadata>,
+}
+
+impl Notification {
+    /// `event` as seen on `chain`; `None` for [`MonitorEvent::Error`], which
+    /// concerns the monitor rather than the chain.
+    pub fn from_event(chain: &str, event: &MonitorEvent) -> Option<Self> {
+        let notification = match event {
+            MonitorEvent::NewTransaction(monitored) => {
+                Self::transaction(NotificationKind::NewTransaction, chain, monitored)
+            }
+            MonitorEvent::Confirmed {
+                transaction,
+                confirmations,
+            } => Self {
+                confirmations: Some(*confirmations),
+                ..Self::transaction(NotificationKind::Confirmed, chain, transaction)
+            },
+            MonitorEvent::Reverted(monitored) => {
+                Self::transaction(NotificationKind::Reverted, chain, monitored)
+            }
+            MonitorEvent::ContractEvent(log) => Self {
+                kind: NotificationKind::ContractEvent,
+                chain: chain.to_string(),
+                tx_hash: log.transaction.clone(),
+                block_number: log.block_number,
+                address: None,
+                direction: None,
+                transfer: None,
+                display_value: None,
+                explorer_url: None,
+                confirmations: None,
+                event: Some(log.clone()),
+                metadata: None,
+            },
+            MonitorEvent::Error(_) => return None,
+        };
+        Some(notification)
+    }
+
+    fn transaction(kind: NotificationKind, chain: &str, monitored: &MonitoredTransaction) -> Self {
+        Self {
+            kind,
+            chain: chain.to_string(),
+            tx_hash: monitored.transaction.hash.clone(),
+            block_number: monitored.transaction.block_number,
+            address: Some(monitored.address.clone()),
+            direction: Some(monitored.direction),
+            transfer: Some(monitored.transfer.clone()),
+            display_value: monitored.display_value.clone(),
+            explorer_url: monitored.explorer_url.clone(),
+            confirmations: None,
+            event: None,
+            metadata: None,
+        }
+    }
+}
+
+/// Where a [`TransactionMonitor`](super::TransactionMonitor) looks up the
+/// compliance metadata of the sends it reports, to pass on to its sinks.
+pub trait SendMetadataSource: Send + Sync {
+    /// The metadata transaction `tx_hash` was sent with, if it was sent
+    /// with any.
+    fn metadata(&self, tx_hash: &str) -> Option<TravelRuleMetadata>;
+}
+
+/// Somewhere a [`TransactionMonitor`](super::TransactionMonitor) hands the
+/// events of each poll, once they are on its broadcast channel and before
+/// its state is saved.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Take one poll's `notifications`, never empty. An error fails the poll,
+    /// and the monitor hands the same notifications to this sink again,
+    /// ahead of newer ones, on the next poll (kept in its state file across
+    /// restarts), so each is delivered at least once.
+    async fn deliver(&self, notifications: &[Notification]) -> Result<(), MonitorError>;
+}
+
+#[cfg(feature = "network")]
+mod webhook {
+    use async_trait::async_trait;
+    use hmac::{Hmac, Mac};
+    use reqwest::Client;
+    use serde::Serialize;
+    use sha2::Sha256;
+
+    use super::{EventSink, Notification};
+    use crate::monitor::MonitorError;
+    use crate::node::network::http::{default_client, fetch};
+    use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+    use crate::wallet::crypto::memory::SecureBuffer;
+
+    /// Most notifications a [`WebhookSink`] sends in one request by default.
+    pub const DEFAULT_MAX_BATCH: usize = 100;
+
+    #[derive(Serialize)]
+    struct Batch<'a> {
+        events: &'a [Notification],
+    }
+
+    /// [`EventSink`] POSTing notifications to a webhook, as the
+    /// [module docs](super) describe. Failed requests (no response, a 5xx
+    /// or a 429) are retried with doubling delays; other statuses are the
+    /// receiver refusing the batch and are not.
+    pub struct WebhookSink {
+        url: String,
+        client: Client,
+        secret: Option<SecureBuffer>,
+        max_batch: usize,
+        retries: u32,
+        backoff: Duration,
+    }
+
+    impl WebhookSink {
+        pub fn new(url: &str) -> Self {
+            Self {
+                url: url.to_string(),
+                client: default_client(),
+                secret: None,
+                max_batch: DEFAULT_MAX_BATCH,
+                retries: 3,
+                backoff: Duration::from_millis(500),
+            }
+        }
+
+        /// Sign every request with `secret`, shared with the receiver.
+        pub fn with_secret(mut self, secret: &[u8]) -> Self {
+            self.secret = Some(SecureBuffer::new(secret.to_vec()));
+            self
+        }
+
+        /// Send at most `max_batch` notifications per request; a poll with
+        /// more is sent in several, in order.
+        pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+            self.max_batch = max_batch.max(1);
+            self
+        }
+
+        /// Retry a failed request up to `retries` times, waiting `backoff`
+        /// before the first retry and twice as long before each next one.
+        pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+            self.retries = retries;
+            self.backoff = backoff;
+            self
+        }
+
+        pub fn with_client(mut self, client: Client) -> Self {
+            self.client = client;
+            self
+        }
+
+        fn signature(&self, body: &[u8]) -> Option<(String, String)> {
+            let secret = self.secret.as_ref()?;
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+                .to_string();
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("any key length");
+            mac.update(timestamp.as_bytes());
+            mac.update(b"\n");
+            mac.update(body);
+            Some((timestamp, hex::encode(mac.finalize().into_bytes())))
+        }
+
+        /// POST `body` once; `Err((retry, reason))` if it was not accepted.
+        async fn post(&self, body: &[u8]) -> Result<(), (bool, String)> {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+            if let Some((timestamp, signature)) = self.signature(body) {
+                request = request
+                    .header("x-signature-timestamp", timestamp)
+                    .header("x-signature", signature);
+            }
+            let status = match fetch(request).await {
+                Ok(response) => response.status(),
+                Err(e) => return Err((true, e.to_string())),
+            };
+            if status.is_success() {
+                return Ok(());
+            }
+            let retry = status.is_server_error() || status.as_u16() == 429;
+            Err((retry, format!("webhook answered {}", status)))
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for WebhookSink {
+        async fn deliver(&self, notifications: &[Notification]) -> Result<(), MonitorError> {
+            for events in notifications.chunks(self.max_batch) {
+                let body = serde_json::to_vec(&Batch { events })
+                    .map_err(|e| MonitorError::Sink(e.to_string()))?;
+                let mut delay = self.backoff;
+                let mut attempt = 0;
+                loop {
+                    match self.post(&body).await {
+                        Ok(()) => break,
+                        Err((true, _)) if attempt < self.retries => {
+                            tokio::time::sleep(delay).await;
+                            delay *= 2;
+                            attempt += 1;
+                        }
+                        Err((_, reason)) => return Err(MonitorError::Sink(reason)),
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl std::fmt::Debug for WebhookSink {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("WebhookSink")
+                .field("url", &self.url)
+                .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+                .field("max_batch", &self.max_batch)
+                .field("retries", &self.retries)
+                .finish()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "network"))]
+mod tests {
+    use super::*;
+    use crate::node::Transaction;
+    use crate::test_utils::http::{self, Reply};
+    use crate::time::Duration;
+
+    /// Answers one request per connection with each of `statuses` in turn,
+    /// and returns the webhook URL and the requests it read.
+    async fn serve(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let replies = statuses.into_iter().map(|s| Reply::new(s, "")).collect();
+        let (url, server) = http::serve(replies).await;
+        (format!("{}/hooks/deposits", url), server)
+    }
+
+    fn deposit(hash: &str) -> MonitorEvent {
+        MonitorEvent::NewTransaction(Box::new(MonitoredTransaction {
+            address: "TWatched".to_string(),
+            direction: Direction::Incoming,
+            transfer: Transfer {
+                from: "TSender".to_string(),
+                to: "TWatched".to_string(),
+                value: "1000000".to_string(),
+                token: None,
+            },
+            transaction: Transaction {
+                hash: hash.to_string(),
+                from: "TSender".to_string(),
+                to: "TWatched".to_string(),
+                value: "1000000".to_string(),
+                block_number: 7,
+                timestamp: 1,
+                status: "SUCCESS".to_string(),
+                raw: None,
+                details: None,
+            },
+            display_value: None,
+            explorer_url: None,
+        }))
+    }
+
+    #[tokio::test]
+    async fn webhook_batches_signs_and_retries_server_errors() {
+        use hmac::{Hmac, Mac};
+
+        let (url, server) = serve(vec![500, 200, 200]).await;
+        let sink = WebhookSink::new(&url)
+            .with_secret(b"shared")
+            .with_max_batch(2)
+            .with_retries(1, Duration::from_millis(10));
+        let notifications: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|hash| Notification::from_event("tron", &deposit(hash)).unwrap())
+            .collect();
+        assert!(Notification::from_event("tron", &MonitorEvent::Error("x".into())).is_none());
+        sink.deliver(&notifications).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0], requests[1], "the failed batch is sent again");
+        let (head, body) = requests[2].split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|l| l.strip_prefix(&format!("{}: ", name)))
+                .unwrap()
+                .to_string()
+        };
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"shared").unwrap();
+        mac.update(format!("{}\n{}", header("x-signature-timestamp"), body).as_bytes());
+        assert_eq!(
+            header("x-signature"),
+            hex::encode(mac.finalize().into_bytes())
+        );
+
+        let batch: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(batch["events"].as_array().unwrap().len(), 1);
+        assert_eq!(batch["events"][0]["kind"], "new_transaction");
+        assert_eq!(batch["events"][0]["tx_hash"], "c");
+        assert_eq!(batch["events"][0]["direction"], "Incoming");
+
+        let (url, server) = serve(vec![400]).await;
+        let refused = WebhookSink::new(&url).deliver(&notifications).await;
+        assert!(matches!(refused, Err(MonitorError::Sink(_))));
+        assert_eq!(server.await.unwrap().len(), 1, "a 4xx is not retried");
+    }
+}
@@ -0,0 +1,1617 @@
+use crate::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::pin::{Pin, pin};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures_util::StreamExt;
+use futures_util::future::{self, Either};
+use futures_util::stream::{BoxStream, Stream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::metrics::Metrics;
+use crate::monitor::dedup::{EventKey, MemorySeenStore, SeenStore};
+use crate::monitor::filter::{Direction, MonitorFilter, Transfer};
+use crate::monitor::sink::{EventSink, Notification, SendMetadataSource};
+use crate::monitor::{MonitorError, PollMetrics};
+use crate::node::details::{TokenTransfer, TvmTxDetails};
+use crate::node::token::TokenMetadataCache;
+use crate::node::utils::AmountFormatter;
+use crate::node::{
+    ChainInfo, ContractEvent, ContractEventSource, LogFilter, LogSource, ReadProvider, Transaction,
+    TransactionStream, TxDetails, TxQuery, TxStatus,
+};
+use crate::persist::{self, Persist};
+use crate::shutdown::Shutdown;
+use crate::wallet::chain::{Chain, Explorer};
+
+/// Pages of history a [`TransactionMonitor`] reads per address in one poll.
+pub const MAX_PAGES_PER_POLL: usize = 20;
+/// Blocks of token logs a [`TransactionMonitor`] reads per token in one poll,
+/// within the range most nodes allow for `eth_getLogs`.
+pub const MAX_LOG_BLOCKS_PER_POLL: u64 = 2_000;
+
+type Decoder = Box<dyn Fn(&Transaction) -> Option<TxDetails> + Send + Sync>;
+
+/// A transaction touching a watched address that passed the monitor's filter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitoredTransaction {
+    /// The watched address it was listed under.
+    pub address: String,
+    pub direction: Direction,
+    /// The transfer that matched the filter.
+    pub transfer: Transfer,
+    pub transaction: Transaction,
+    /// The transfer's value for people, e.g. `12.5 USDT`, when the monitor
+    /// was given [token metadata](TransactionMonitor::with_token_metadata).
+    #[serde(default)]
+    pub display_value: Option<String>,
+    /// Explorer page of the transaction, when the monitor was given
+    /// [one](TransactionMonitor::with_explorer).
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    /// First sighting of a matching transaction; reported once per [`SeenStore`].
+    NewTransaction(Box<MonitoredTransaction>),
+    /// First sighting of an event from a contract registered with
+    /// [`TransactionMonitor::watch_events`].
+    ContractEvent(ContractEvent),
+    /// A reported transaction reached the confirmations asked for with
+    /// [`TransactionMonitor::with_confirmations`].
+    Confirmed {
+        transaction: Box<MonitoredTransaction>,
+        confirmations: u64,
+    },
+    /// A reported transaction failed, or left the chain before confirming.
+    Reverted(Box<MonitoredTransaction>),
+    /// A poll failed; the monitor carries on after its interval.
+    Error(String),
+}
+
+/// How far a [`TransactionMonitor`] has read one address's history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressCursor {
+    pub address: String,
+    /// Newest block timestamp processed; older entries are not looked at again.
+    pub last_timestamp: u64,
+    /// Transactions processed at exactly `last_timestamp`.
+    pub hashes: BTreeSet<String>,
+}
+
+/// Last block whose logs a [`TransactionMonitor`] has read for one token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCursor {
+    pub token: String,
+    pub last_block: u64,
+}
+
+/// Snapshot of a [`TransactionMonitor`]'s progress, from which a restarted
+/// process resumes without reporting the same transactions again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorState {
+    pub chain: String,
+    pub cursors: Vec<AddressCursor>,
+    /// Progress through watched contract events, keyed `contract/event`
+    /// (`contract/transfers` for token watches).
+    #[serde(default)]
+    pub event_cursors: Vec<AddressCursor>,
+    #[serde(default)]
+    pub log_cursors: Vec<BlockCursor>,
+    /// Notifications each sink has yet to take, in the order the sinks were
+    /// added; retried ahead of new ones on the next poll.
+    #[serde(default)]
+    pub undelivered: Vec<Vec<Notification>>,
+}
+
+impl Persist for MonitorState {
+    const KIND: &'static str = "monitor_state";
+    const VERSION: u16 = 1;
+}
+
+impl AddressCursor {
+    /// Whether `tx` was handled by an earlier poll. Unmined entries carry no
+    /// timestamp and are left to the seen store.
+    fn covers(&self, tx: &Transaction) -> bool {
+        self.covers_at(tx.timestamp, &tx.hash)
+    }
+
+    fn advance(&mut self, tx: &Transaction) {
+        self.advance_to(tx.timestamp, &tx.hash);
+    }
+
+    fn covers_at(&self, timestamp: u64, id: &str) -> bool {
+        timestamp != 0
+            && (timestamp < self.last_timestamp
+                || (timestamp == self.last_timestamp && self.hashes.contains(id)))
+    }
+
+    fn advance_to(&mut self, timestamp: u64, id: &str) {
+        if timestamp > self.last_timestamp {
+            self.last_timestamp = timestamp;
+            self.hashes.clear();
+        }
+        if timestamp != 0 && timestamp == self.last_timestamp {
+            self.hashes.insert(id.to_string());
+        }
+    }
+}
+
+/// A contract event subscription: `event` logs from `contract`, read from
+/// `source`, or the token's `Transfer`s to and from watched addresses.
+struct EventWatch {
+    source: Arc<dyn ContractEventSource>,
+    contract: String,
+    event: String,
+    transfers: bool,
+}
+
+impl EventWatch {
+    fn key(&self) -> String {
+        match self.transfers {
+            true => format!("{}/transfers", self.contract),
+            false => format!("{}/{}", self.contract, self.event),
+        }
+    }
+}
+
+/// ERC-20 `Transfer` logs of `token`, read from `source`.
+struct TokenLogWatch {
+    source: Arc<dyn LogSource>,
+    token: String,
+}
+
+/// Polls watched addresses and reports each new transaction that passes its
+/// [`MonitorFilter`], in either direction and for any asset the filter allows.
+/// Given a [`TransactionStream`], it follows the addresses through that
+/// instead and polls only to catch up.
+///
+/// For incoming payments that must reach a confirmation threshold, see
+/// [`DepositTracker`](crate::monitor::DepositTracker).
+pub struct TransactionMonitor {
+    provider: Arc<dyn ReadProvider>,
+    chain: String,
+    filter: MonitorFilter,
+    addresses: Vec<String>,
+    event_watches: Vec<EventWatch>,
+    token_watches: Vec<TokenLogWatch>,
+    history: bool,
+    stream: Option<Arc<dyn TransactionStream>>,
+    confirmations: Option<u64>,
+    unconfirmed: Mutex<Vec<MonitoredTransaction>>,
+    decoder: Option<Decoder>,
+    explorer: Option<Explorer>,
+    token_metadata: Option<(Arc<TokenMetadataCache>, Arc<dyn AmountFormatter>)>,
+    seen: Arc<dyn SeenStore>,
+    cursors: Mutex<HashMap<String, AddressCursor>>,
+    event_cursors: Mutex<HashMap<String, AddressCursor>>,
+    log_cursors: Mutex<HashMap<String, u64>>,
+    state_file: Option<PathBuf>,
+    events: broadcast::Sender<MonitorEvent>,
+    sinks: Vec<Arc<dyn EventSink>>,
+    send_metadata: Option<Arc<dyn SendMetadataSource>>,
+    undelivered: Mutex<Vec<Vec<Notification>>>,
+    metrics: PollMetrics,
+}
+
+impl TransactionMonitor {
+    /// Monitor on `chain` (a `Chain::id`, used for deduplication keys).
+    pub fn new(provider: Arc<dyn ReadProvider>, chain: &str, filter: MonitorFilter) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            provider,
+            chain: chain.to_string(),
+            filter,
+            addresses: Vec::new(),
+            event_watches: Vec::new(),
+            token_watches: Vec::new(),
+            history: true,
+            stream: None,
+            confirmations: None,
+            unconfirmed: Mutex::new(Vec::new()),
+            decoder: None,
+            explorer: None,
+            token_metadata: None,
+            seen: Arc::new(MemorySeenStore::new()),
+            cursors: Mutex::new(HashMap::new()),
+            event_cursors: Mutex::new(HashMap::new()),
+            log_cursors: Mutex::new(HashMap::new()),
+            state_file: None,
+            events,
+            sinks: Vec::new(),
+            send_metadata: None,
+            undelivered: Mutex::new(Vec::new()),
+            metrics: PollMetrics::default(),
+        }
+    }
+
+    /// Monitor picking up where `state` left off; the chain is taken from it.
+    pub fn resume(
+        provider: Arc<dyn ReadProvider>,
+        filter: MonitorFilter,
+        state: MonitorState,
+    ) -> Self {
+        let monitor = Self::new(provider, &state.chain, filter);
+        monitor.restore(state);
+        monitor
+    }
+
+    pub fn watch(mut self, address: &str) -> Self {
+        self.addresses.push(address.to_string());
+        self
+    }
+
+    /// Also report `event` logs (e.g. `Deposit`) emitted by `contract`, as
+    /// [`MonitorEvent::ContractEvent`]s. The filter does not apply to them.
+    pub fn watch_events(
+        mut self,
+        source: Arc<dyn ContractEventSource>,
+        contract: &str,
+        event: &str,
+    ) -> Self {
+        self.event_watches.push(EventWatch {
+            source,
+            contract: contract.to_string(),
+            event: event.to_string(),
+            transfers: false,
+        });
+        self
+    }
+
+    /// Also report transfers of the TRC-20 at `contract` to or from watched
+    /// addresses, found through the contract's `Transfer` events rather than
+    /// address history, as [`MonitorEvent::NewTransaction`]s carrying the
+    /// token transfer in their details. Reading starts at the current time
+    /// on the first poll: a popular token's full history is millions of
+    /// events.
+    pub fn watch_token_events(
+        mut self,
+        source: Arc<dyn ContractEventSource>,
+        contract: &str,
+    ) -> Self {
+        self.event_watches.push(EventWatch {
+            source,
+            contract: contract.to_string(),
+            event: "Transfer".to_string(),
+            transfers: true,
+        });
+        self
+    }
+
+    /// Also report transfers of the ERC-20 at `token` to or from watched
+    /// addresses, found through its `Transfer` logs rather than address
+    /// history. Reading starts at the chain tip on the first poll.
+    pub fn watch_token_logs(mut self, source: Arc<dyn LogSource>, token: &str) -> Self {
+        self.token_watches.push(TokenLogWatch {
+            source,
+            token: token.to_string(),
+        });
+        self
+    }
+
+    /// Do not read address history, for providers without an address index
+    /// (plain EVM JSON-RPC) whose transfers arrive through
+    /// [`watch_token_logs`](Self::watch_token_logs).
+    pub fn without_history(mut self) -> Self {
+        self.history = false;
+        self
+    }
+
+    /// Have [`run`](Self::run) learn of watched addresses' transactions from
+    /// `stream` as blocks arrive, e.g. a
+    /// [`TronStreamProvider`](crate::node::network::tron_ws::TronStreamProvider)
+    /// (feature `tron-ws`), rather than by polling their history.
+    pub fn with_stream(mut self, stream: Arc<dyn TransactionStream>) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    /// Follow every reported transaction until it has `confirmations`
+    /// blocks on top, then report it again as [`MonitorEvent::Confirmed`],
+    /// or as [`MonitorEvent::Reverted`] should it fail or disappear first.
+    /// Transactions still waiting are kept in memory only, and not followed
+    /// further after a restart.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = Some(confirmations);
+        self
+    }
+
+    /// Fill in [`MonitoredTransaction::display_value`], looking token
+    /// symbols and decimals up in `tokens` and rendering with `formatter`.
+    pub fn with_token_metadata(
+        mut self,
+        tokens: Arc<TokenMetadataCache>,
+        formatter: Arc<dyn AmountFormatter>,
+    ) -> Self {
+        self.token_metadata = Some((tokens, formatter));
+        self
+    }
+
+    /// Decode transaction details with `chain` before filtering, which token
+    /// filters (e.g. TRC-20 contracts on Tron) rely on.
+    pub fn with_decoder<C: Chain + 'static>(mut self, chain: C) -> Self {
+        self.decoder = Some(Box::new(move |tx| {
+            chain.decode_provider_tx(tx).ok().flatten()
+        }));
+        self
+    }
+
+    /// Link reported transactions to `chain`'s explorer in
+    /// [`MonitoredTransaction::explorer_url`].
+    pub fn with_explorer(mut self, chain: &(impl Chain + ?Sized)) -> Self {
+        self.explorer = chain.explorer();
+        self
+    }
+
+    /// Report every poll, its latency and how stale the monitor is to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = PollMetrics::new(metrics);
+        self
+    }
+
+    /// Remember reported transactions in `store`, e.g. a
+    /// [`FileSeenStore`](crate::monitor::FileSeenStore) that outlives the process.
+    pub fn with_seen_store(mut self, store: Arc<dyn SeenStore>) -> Self {
+        self.seen = store;
+        self
+    }
+
+    /// Resume from `path` if it holds a saved state for this chain, and save
+    /// the state there after every poll.
+    pub fn with_state_file(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(state) = persist::load::<MonitorState>(&path)? {
+            self.restore(state);
+        }
+        self.state_file = Some(path);
+        Ok(self)
+    }
+
+    /// Also hand each poll's events to `sink`, e.g. a
+    /// [`WebhookSink`](crate::monitor::WebhookSink) for services in other
+    /// processes. A delivery the sink gives up on fails the poll, and is
+    /// handed to it again on the next one.
+    pub fn with_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Attach to each notification for the sinks the travel-rule metadata
+    /// `source` has for its transaction, e.g. the
+    /// [audit log](crate::wallet::audit::MemoryAuditLog) of the wallet that
+    /// sent it.
+    pub fn with_send_metadata(mut self, source: Arc<dyn SendMetadataSource>) -> Self {
+        self.send_metadata = Some(source);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitorEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn state(&self) -> MonitorState {
+        let mut cursors: Vec<_> = self.cursors.lock().unwrap().values().cloned().collect();
+        cursors.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut event_cursors: Vec<_> = self
+            .event_cursors
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        event_cursors.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut log_cursors: Vec<_> = self
+            .log_cursors
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(token, &last_block)| BlockCursor {
+                token: token.clone(),
+                last_block,
+            })
+            .collect();
+        log_cursors.sort_by(|a, b| a.token.cmp(&b.token));
+        MonitorState {
+            chain: self.chain.clone(),
+            cursors,
+            event_cursors,
+            log_cursors,
+            undelivered: self.undelivered.lock().unwrap().clone(),
+        }
+    }
+
+    /// Continue from `state`; a state taken on another chain is ignored.
+    pub fn restore(&self, state: MonitorState) {
+        if state.chain != self.chain {
+            return;
+        }
+        let mut cursors = self.cursors.lock().unwrap();
+        for cursor in state.cursors {
+            cursors.insert(cursor.address.clone(), cursor);
+        }
+        let mut event_cursors = self.event_cursors.lock().unwrap();
+        for cursor in state.event_cursors {
+            event_cursors.insert(cursor.address.clone(), cursor);
+        }
+        let mut log_cursors = self.log_cursors.lock().unwrap();
+        for cursor in state.log_cursors {
+            log_cursors.insert(cursor.token, cursor.last_block);
+        }
+        *self.undelivered.lock().unwrap() = state.undelivered;
+    }
+
+    /// Check every watched address and contract event once and publish the
+    /// resulting events.
+    ///
+    /// History is read oldest first from the address's cursor, following
+    /// provider pages so busy addresses are not cut off at the first page. At
+    /// most [`MAX_PAGES_PER_POLL`] pages are read per address; the rest is
+    /// picked up by the next poll. Events found before an error are still
+    /// published, since they are already marked as seen.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "monitor_poll",
+            skip_all,
+            fields(chain = %self.chain, addresses = self.addresses.len()),
+            err
+        )
+    )]
+    pub async fn poll(&self) -> Result<Vec<MonitorEvent>, MonitorError> {
+        self.poll_sources(self.history).await
+    }
+
+    async fn poll_sources(&self, history: bool) -> Result<Vec<MonitorEvent>, MonitorError> {
+        let started = Instant::now();
+        let result = self.scan_sources(history).await;
+        self.metrics
+            .record("transactions", &self.chain, started, result.is_ok());
+        result
+    }
+
+    async fn scan_sources(&self, history: bool) -> Result<Vec<MonitorEvent>, MonitorError> {
+        let mut events = Vec::new();
+        let mut scanned = match history {
+            true => self.scan(&mut events).await,
+            false => Ok(()),
+        };
+        if scanned.is_ok() {
+            scanned = self.scan_events(&mut events).await;
+        }
+        if scanned.is_ok() {
+            scanned = self.scan_token_logs(&mut events).await;
+        }
+        self.check_confirmations(&mut events).await;
+        self.publish(&mut events).await?;
+        scanned.map(|()| events)
+    }
+
+    async fn publish(&self, events: &mut [MonitorEvent]) -> Result<(), MonitorError> {
+        self.render_values(events).await;
+        for event in events.iter() {
+            if let (Some(_), MonitorEvent::NewTransaction(monitored)) = (self.confirmations, event)
+            {
+                self.unconfirmed.lock().unwrap().push((**monitored).clone());
+            }
+            let _ = self.events.send(event.clone());
+        }
+        let delivered = self.deliver(events).await;
+        if let Some(path) = &self.state_file {
+            persist::save(path, &self.state())?;
+        }
+        delivered
+    }
+
+    /// Hand `events` to every sink, after whatever it failed to take before.
+    /// A sink that fails keeps the lot queued for the next poll; the others
+    /// still get theirs, and the first error is returned.
+    async fn deliver(&self, events: &[MonitorEvent]) -> Result<(), MonitorError> {
+        let notifications: Vec<_> = events
+            .iter()
+            .filter_map(|event| Notification::from_event(&self.chain, event))
+            .map(|mut notification| {
+                if let Some(source) = &self.send_metadata {
+                    notification.metadata = source.metadata(&notification.tx_hash);
+                }
+                notification
+            })
+            .collect();
+        let mut failed = None;
+        for (i, sink) in self.sinks.iter().enumerate() {
+            let mut batch = self.take_undelivered(i);
+            batch.extend(notifications.iter().cloned());
+            if batch.is_empty() {
+                continue;
+            }
+            if let Err(e) = sink.deliver(&batch).await {
+                self.undelivered.lock().unwrap()[i] = batch;
+                failed.get_or_insert(e);
+            }
+        }
+        failed.map_or(Ok(()), Err)
+    }
+
+    /// What sink `index` has yet to take, leaving its queue empty.
+    fn take_undelivered(&self, index: usize) -> Vec<Notification> {
+        let mut undelivered = self.undelivered.lock().unwrap();
+        undelivered.resize_with(self.sinks.len(), Vec::new);
+        std::mem::take(&mut undelivered[index])
+    }
+
+    /// Subscribe to every watched address, catch up on what happened before
+    /// with a poll, then report transactions as `stream` delivers them until
+    /// any subscription ends or `shutdown` is triggered. Contract events and
+    /// token logs are still polled every `interval`.
+    async fn follow(
+        &self,
+        stream: &dyn TransactionStream,
+        interval: Duration,
+        shutdown: &Shutdown,
+    ) -> Result<(), MonitorError> {
+        let mut subscriptions = Vec::new();
+        for address in &self.addresses {
+            let tagged = address.clone();
+            let ended = address.clone();
+            let subscription = stream
+                .subscribe_address(address)
+                .await?
+                .map(move |tx| (tagged.clone(), Some(tx)))
+                .chain(futures_util::stream::once(async move { (ended, None) }));
+            subscriptions.push(subscription.boxed());
+        }
+        let mut merged = futures_util::stream::select_all(subscriptions);
+        self.poll().await?;
+
+        let mut stopped = std::pin::pin!(shutdown.triggered());
+        let mut due = tokio::time::Instant::now() + interval;
+        loop {
+            let next = std::pin::pin!(tokio::time::timeout_at(due, merged.next()));
+            match future::select(stopped.as_mut(), next).await {
+                Either::Left(_) => return Ok(()),
+                Either::Right((Err(_), _)) => {
+                    self.poll_sources(false).await?;
+                    due += interval;
+                }
+                Either::Right((Ok(None | Some((_, None))), _)) => return Ok(()),
+                Either::Right((Ok(Some((_, Some(Err(e))))), _)) => return Err(e.into()),
+                Either::Right((Ok(Some((address, Some(Ok(tx))))), _)) => {
+                    self.receive(&address, tx).await?;
+                }
+            }
+        }
+    }
+
+    /// Report `streamed`, a transaction of `address` just delivered by a
+    /// stream, and move the address's cursor past it.
+    async fn receive(&self, address: &str, streamed: Transaction) -> Result<(), MonitorError> {
+        // The provider's record carries the receipt status, and the form
+        // the decoder and later polls expect.
+        let tx = match self.provider.get_transaction(&streamed.hash).await {
+            Ok(Some(tx)) => tx,
+            _ => streamed,
+        };
+        self.cursors
+            .lock()
+            .unwrap()
+            .entry(address.to_string())
+            .or_insert_with(|| AddressCursor {
+                address: address.to_string(),
+                ..Default::default()
+            })
+            .advance(&tx);
+        let mut events: Vec<_> = self.check(address, tx)?.into_iter().collect();
+        self.publish(&mut events).await
+    }
+
+    async fn scan(&self, events: &mut Vec<MonitorEvent>) -> Result<(), MonitorError> {
+        for address in &self.addresses {
+            let mut cursor = self
+                .cursors
+                .lock()
+                .unwrap()
+                .get(address)
+                .cloned()
+                .unwrap_or_else(|| AddressCursor {
+                    address: address.clone(),
+                    ..Default::default()
+                });
+            let mut query = TxQuery::new().oldest_first();
+            if cursor.last_timestamp > 0 {
+                query = query.since(cursor.last_timestamp);
+            }
+
+            for _ in 0..MAX_PAGES_PER_POLL {
+                let page = self.provider.get_transactions_page(address, &query).await?;
+                let mut transactions = page.transactions;
+                // Oldest first, so the cursor only moves past what has been handled.
+                transactions.sort_by_key(|tx| tx.timestamp);
+                for tx in transactions {
+                    if cursor.covers(&tx) {
+                        continue;
+                    }
+                    cursor.advance(&tx);
+                    if let Some(event) = self.check(address, tx)? {
+                        events.push(event);
+                    }
+                }
+                self.cursors
+                    .lock()
+                    .unwrap()
+                    .insert(address.clone(), cursor.clone());
+                match page.next {
+                    Some(next) => query.cursor = Some(next),
+                    None => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn scan_events(&self, events: &mut Vec<MonitorEvent>) -> Result<(), MonitorError> {
+        for watch in &self.event_watches {
+            let key = watch.key();
+            let known = self.event_cursors.lock().unwrap().get(&key).cloned();
+            let mut cursor = match known {
+                Some(cursor) => cursor,
+                None if watch.transfers => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis() as u64;
+                    self.event_cursors.lock().unwrap().insert(
+                        key.clone(),
+                        AddressCursor {
+                            address: key,
+                            last_timestamp: now,
+                            ..Default::default()
+                        },
+                    );
+                    continue;
+                }
+                None => AddressCursor {
+                    address: key.clone(),
+                    ..Default::default()
+                },
+            };
+            let mut query = TxQuery::new().oldest_first();
+            if cursor.last_timestamp > 0 {
+                query = query.since(cursor.last_timestamp);
+            }
+
+            for _ in 0..MAX_PAGES_PER_POLL {
+                let page = watch
+                    .source
+                    .contract_events(&watch.contract, &watch.event, &query)
+                    .await?;
+                let mut logs = page.events;
+                logs.sort_by_key(|log| log.timestamp);
+                for log in logs {
+                    let id = format!("{}:{}", log.transaction, log.log_index);
+                    if cursor.covers_at(log.timestamp, &id) {
+                        continue;
+                    }
+                    cursor.advance_to(log.timestamp, &id);
+                    if watch.transfers {
+                        events.extend(self.check_token_event(&log)?);
+                        continue;
+                    }
+                    let seen_key =
+                        EventKey::new(&self.chain, &log.transaction, Some(log.log_index));
+                    if self.seen.insert(&seen_key)? {
+                        events.push(MonitorEvent::ContractEvent(log));
+                    }
+                }
+                self.event_cursors
+                    .lock()
+                    .unwrap()
+                    .insert(key.clone(), cursor.clone());
+                match page.next {
+                    Some(next) => query.cursor = Some(next),
+                    None => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn scan_token_logs(&self, events: &mut Vec<MonitorEvent>) -> Result<(), MonitorError> {
+        if self.addresses.is_empty() {
+            return Ok(());
+        }
+        for watch in &self.token_watches {
+            let tip = watch.source.block_number().await?;
+            let last = self.log_cursors.lock().unwrap().get(&watch.token).copied();
+            let Some(last) = last else {
+                self.log_cursors
+                    .lock()
+                    .unwrap()
+                    .insert(watch.token.clone(), tip);
+                continue;
+            };
+            if last >= tip {
+                continue;
+            }
+            let to = tip.min(last + MAX_LOG_BLOCKS_PER_POLL);
+
+            let mut logs = Vec::new();
+            for incoming in [true, false] {
+                let filter = LogFilter::erc20_transfers(&watch.token, &self.addresses, incoming)
+                    .from_block(last + 1)
+                    .to_block(to);
+                logs.extend(watch.source.logs(&filter).await?);
+            }
+            logs.sort_by_key(|log| (log.block_number, log.log_index));
+            logs.dedup_by(|a, b| {
+                a.transaction_hash == b.transaction_hash && a.log_index == b.log_index
+            });
+
+            for log in logs {
+                let Some(transfer) = log.erc20_transfer() else {
+                    continue;
+                };
+                let Some((address, direction)) = self.addresses.iter().find_map(|address| {
+                    let direction = self.filter.matching_transfer(address, &transfer)?;
+                    Some((address, direction))
+                }) else {
+                    continue;
+                };
+                let key = EventKey::new(&self.chain, &log.transaction_hash, Some(log.log_index));
+                if !self.seen.insert(&key)? {
+                    continue;
+                }
+                let transaction = Transaction {
+                    hash: log.transaction_hash.clone(),
+                    from: transfer.from.clone(),
+                    to: log.address.clone(),
+                    value: "0".to_string(),
+                    block_number: log.block_number,
+                    timestamp: 0,
+                    status: "SUCCESS".to_string(),
+                    raw: serde_json::to_value(&log).ok(),
+                    details: None,
+                };
+                events.push(MonitorEvent::NewTransaction(Box::new(self.monitored(
+                    address,
+                    direction,
+                    transfer,
+                    transaction,
+                ))));
+            }
+            self.log_cursors
+                .lock()
+                .unwrap()
+                .insert(watch.token.clone(), to);
+        }
+        Ok(())
+    }
+
+    /// Look up every reported transaction still short of its confirmations
+    /// and add an event for each that has since confirmed or reverted. A
+    /// lookup that fails leaves the transaction for the next poll.
+    async fn check_confirmations(&self, events: &mut Vec<MonitorEvent>) {
+        let Some(required) = self.confirmations else {
+            return;
+        };
+        let waiting = std::mem::take(&mut *self.unconfirmed.lock().unwrap());
+        let mut still_waiting = Vec::new();
+        for monitored in waiting {
+            let hash = &monitored.transaction.hash;
+            match self.provider.get_transaction_status(hash).await {
+                Ok(TxStatus::Confirmed { confirmations, .. }) if confirmations >= required => {
+                    events.push(MonitorEvent::Confirmed {
+                        transaction: Box::new(monitored),
+                        confirmations,
+                    });
+                }
+                Ok(TxStatus::NotFound | TxStatus::Failed { .. }) => {
+                    events.push(MonitorEvent::Reverted(Box::new(monitored)));
+                }
+                _ => still_waiting.push(monitored),
+            }
+        }
+        self.unconfirmed.lock().unwrap().extend(still_waiting);
+    }
+
+    async fn render_values(&self, events: &mut [MonitorEvent]) {
+        let Some((tokens, formatter)) = &self.token_metadata else {
+            return;
+        };
+        let mut native = None;
+        for event in events {
+            let MonitorEvent::NewTransaction(monitored) = event else {
+                continue;
+            };
+            let transfer = &monitored.transfer;
+            monitored.display_value =
+                match &transfer.token {
+                    Some(contract) => tokens.get(contract).await.ok().map(|metadata| {
+                        metadata.format_amount(&transfer.value, formatter.as_ref())
+                    }),
+                    None => {
+                        if native.is_none() {
+                            native = Some(self.provider.chain_info().await.unwrap_or_else(|_| {
+                                ChainInfo::with_decimals(self.provider.get_decimals())
+                            }));
+                        }
+                        native
+                            .as_ref()
+                            .map(|info| info.format_amount(&transfer.value, formatter.as_ref()))
+                    }
+                };
+        }
+    }
+
+    /// Event for a token `Transfer` event if it is new, involves a watched
+    /// address and passes the filter.
+    fn check_token_event(&self, log: &ContractEvent) -> Result<Option<MonitorEvent>, MonitorError> {
+        let Some(transfer) = log.token_transfer() else {
+            return Ok(None);
+        };
+        let Some((address, direction)) = self.addresses.iter().find_map(|address| {
+            let direction = self.filter.matching_transfer(address, &transfer)?;
+            Some((address, direction))
+        }) else {
+            return Ok(None);
+        };
+        let key = EventKey::new(&self.chain, &log.transaction, Some(log.log_index));
+        if !self.seen.insert(&key)? {
+            return Ok(None);
+        }
+        let transaction = Transaction {
+            hash: log.transaction.clone(),
+            from: transfer.from.clone(),
+            to: log.contract.clone(),
+            value: "0".to_string(),
+            block_number: log.block_number,
+            timestamp: log.timestamp,
+            status: "SUCCESS".to_string(),
+            raw: log.raw.clone(),
+            details: Some(TxDetails::Tvm(TvmTxDetails {
+                contract_type: "TriggerSmartContract".to_string(),
+                contract_address: Some(log.contract.clone()),
+                token_transfers: vec![TokenTransfer {
+                    token: log.contract.clone(),
+                    from: transfer.from.clone(),
+                    to: transfer.to.clone(),
+                    value: transfer.value.clone(),
+                }],
+                ..Default::default()
+            })),
+        };
+        Ok(Some(MonitorEvent::NewTransaction(Box::new(
+            self.monitored(address, direction, transfer, transaction),
+        ))))
+    }
+
+    /// Event for `tx` if it is new and passes the filter.
+    fn check(
+        &self,
+        address: &str,
+        mut tx: Transaction,
+    ) -> Result<Option<MonitorEvent>, MonitorError> {
+        if tx.is_failed() {
+            return Ok(None);
+        }
+        let key = EventKey::for_transaction(&self.chain, &tx);
+        if self.seen.contains(&key) {
+            return Ok(None);
+        }
+        if tx.details.is_none()
+            && let Some(decode) = &self.decoder
+        {
+            tx.details = decode(&tx);
+        }
+        let Some((direction, transfer)) = self.filter.matching(address, &tx) else {
+            return Ok(None);
+        };
+        if !self.seen.insert(&key)? {
+            return Ok(None);
+        }
+        Ok(Some(MonitorEvent::NewTransaction(Box::new(
+            self.monitored(address, direction, transfer, tx),
+        ))))
+    }
+
+    fn monitored(
+        &self,
+        address: &str,
+        direction: Direction,
+        transfer: Transfer,
+        transaction: Transaction,
+    ) -> MonitoredTransaction {
+        MonitoredTransaction {
+            address: address.to_string(),
+            direction,
+            transfer,
+            explorer_url: self
+                .explorer
+                .map(|explorer| explorer.tx_url(&transaction.hash)),
+            transaction,
+            display_value: None,
+        }
+    }
+
+    /// Poll every `interval` until `shutdown` is triggered, publishing
+    /// errors as [`MonitorEvent::Error`] and retrying.
+    ///
+    /// With a [stream](Self::with_stream), watched addresses are followed
+    /// through it instead; when it drops, the monitor waits `interval`,
+    /// subscribes again and polls once to pick up what it missed.
+    pub async fn run(&self, interval: Duration, shutdown: &Shutdown) {
+        while !shutdown.is_triggered() {
+            let result = match &self.stream {
+                Some(stream) if self.history && !self.addresses.is_empty() => {
+                    self.follow(stream.as_ref(), interval, shutdown).await
+                }
+                _ => self.poll().await.map(drop),
+            };
+            if let Err(e) = result {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(chain = %self.chain, error = %e, "transaction monitor: poll failed");
+                let _ = self.events.send(MonitorEvent::Error(e.to_string()));
+            }
+            if shutdown.sleep(interval).await {
+                break;
+            }
+        }
+    }
+
+    /// [`run`](Self::run) the monitor on a task of its own and return its
+    /// events as a [`Stream`], which ends once the monitor is stopped through
+    /// the returned [`MonitorStream`].
+    pub fn stream(self: Arc<Self>, interval: Duration) -> MonitorStream {
+        let shutdown = Shutdown::new();
+        let receiver = self.subscribe();
+        let guard = shutdown.enter();
+        let stopped = shutdown.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            self.run(interval, &stopped).await;
+        });
+
+        let events = futures_util::stream::unfold(
+            (receiver, shutdown.clone()),
+            |(mut receiver, shutdown)| async move {
+                let received = {
+                    let received = pin!(receiver.recv());
+                    let finished = pin!(shutdown.drained());
+                    match future::select(received, finished).await {
+                        Either::Left((received, _)) => Some(received),
+                        Either::Right(_) => None,
+                    }
+                };
+                let event = match received {
+                    Some(Ok(event)) => event,
+                    Some(Err(RecvError::Lagged(missed))) => MonitorEvent::Error(format!(
+                        "stream fell behind and missed {} events",
+                        missed
+                    )),
+                    Some(Err(RecvError::Closed)) => return None,
+                    // The task is done: hand out what it published last.
+                    None => receiver.try_recv().ok()?,
+                };
+                Some((event, (receiver, shutdown)))
+            },
+        );
+        MonitorStream {
+            events: events.boxed(),
+            shutdown,
+        }
+    }
+}
+
+/// Events of a [`TransactionMonitor`] running on its own task; see
+/// [`TransactionMonitor::stream`]. Dropping it stops the monitor.
+pub struct MonitorStream {
+    events: BoxStream<'static, MonitorEvent>,
+    shutdown: Shutdown,
+}
+
+impl MonitorStream {
+    /// Token that stops the monitor once triggered, e.g. to tie it to the
+    /// rest of the process's shutdown.
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Stop the monitor and wait for the poll in progress to finish. The
+    /// stream still yields what that poll reports, then ends.
+    pub async fn stop(&self) {
+        self.shutdown.shutdown().await;
+    }
+}
+
+impl Stream for MonitorStream {
+    type Item = MonitorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<MonitorEvent>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for MonitorStream {
+    fn drop(&mut self) {
+        self.shutdown.trigger();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::token::{TokenMetadata, TokenMetadataSource};
+    use crate::node::utils::AmountStyle;
+    use crate::node::{NodeError, TxPage};
+
+    struct History(Mutex<Vec<Transaction>>);
+
+    #[async_trait]
+    impl ReadProvider for History {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    struct NoTokens;
+
+    #[async_trait]
+    impl TokenMetadataSource for NoTokens {
+        async fn token_metadata(&self, _: &str) -> Result<TokenMetadata, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    fn transfer(hash: &str, from: &str, to: &str, value: &str) -> Transaction {
+        Transaction {
+            hash: hash.into(),
+            from: from.into(),
+            to: to.into(),
+            value: value.into(),
+            block_number: 1,
+            timestamp: 0,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_new_matching_transactions_once() {
+        let provider = Arc::new(History(Mutex::new(vec![
+            transfer("dust", "x", "hot", "1"),
+            transfer("in", "x", "hot", "5000"),
+            transfer("out", "hot", "y", "7000"),
+        ])));
+        let filter = MonitorFilter::new()
+            .direction(Direction::Incoming)
+            .min_value(1_000);
+        let monitor = TransactionMonitor::new(provider.clone(), "tron", filter)
+            .watch("hot")
+            .with_token_metadata(
+                Arc::new(TokenMetadataCache::new(Arc::new(NoTokens))),
+                Arc::new(AmountStyle::english()),
+            )
+            .with_explorer(&crate::wallet::chain::TRON);
+        let mut rx = monitor.subscribe();
+
+        let events = monitor.poll().await.unwrap();
+        assert!(matches!(
+            &events[..],
+            [MonitorEvent::NewTransaction(m)]
+                if m.transaction.hash == "in" && m.display_value.as_deref() == Some("0.005")
+                    && m.explorer_url.as_deref() == Some("https://tronscan.org/#/transaction/in")
+        ));
+        assert!(matches!(
+            rx.recv().await.unwrap(),
+            MonitorEvent::NewTransaction(m) if m.direction == Direction::Incoming
+        ));
+
+        provider
+            .0
+            .lock()
+            .unwrap()
+            .push(transfer("in2", "z", "hot", "1000"));
+        let events = monitor.poll().await.unwrap();
+        assert!(matches!(
+            &events[..],
+            [MonitorEvent::NewTransaction(m)] if m.transaction.hash == "in2"
+        ));
+    }
+
+    /// Sink recording each delivery, failing while `down` is set.
+    #[derive(Default)]
+    struct Recorder {
+        down: std::sync::atomic::AtomicBool,
+        delivered: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl EventSink for Recorder {
+        async fn deliver(&self, notifications: &[Notification]) -> Result<(), MonitorError> {
+            if self.down.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(MonitorError::Sink("down".to_string()));
+            }
+            let hashes = notifications.iter().map(|n| n.tx_hash.clone()).collect();
+            self.delivered.lock().unwrap().push(hashes);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_what_a_failing_sink_missed_for_the_next_poll() {
+        let provider = Arc::new(History(Mutex::new(vec![transfer("a", "x", "hot", "5")])));
+        let (flaky, steady) = (Arc::new(Recorder::default()), Arc::new(Recorder::default()));
+        flaky.down.store(true, std::sync::atomic::Ordering::SeqCst);
+        let monitor = TransactionMonitor::new(provider.clone(), "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_sink(flaky.clone())
+            .with_sink(steady.clone());
+
+        assert!(monitor.poll().await.is_err());
+        assert_eq!(
+            *steady.delivered.lock().unwrap(),
+            vec![vec!["a".to_string()]]
+        );
+        assert_eq!(monitor.state().undelivered.len(), 2);
+
+        flaky.down.store(false, std::sync::atomic::Ordering::SeqCst);
+        provider
+            .0
+            .lock()
+            .unwrap()
+            .push(transfer("b", "x", "hot", "5"));
+        monitor.poll().await.unwrap();
+        assert_eq!(
+            *flaky.delivered.lock().unwrap(),
+            vec![vec!["a".to_string(), "b".to_string()]]
+        );
+        assert_eq!(steady.delivered.lock().unwrap()[1], vec!["b".to_string()]);
+        assert!(monitor.state().undelivered.iter().all(Vec::is_empty));
+    }
+
+    #[tokio::test]
+    async fn notifications_carry_the_metadata_of_the_wallets_sends() {
+        use crate::wallet::audit::{AuditKind, AuditRecord, AuditSink, MemoryAuditLog};
+        use crate::wallet::options::TravelRuleMetadata;
+
+        #[derive(Default)]
+        struct Collect(Mutex<Vec<Notification>>);
+
+        #[async_trait]
+        impl EventSink for Collect {
+            async fn deliver(&self, notifications: &[Notification]) -> Result<(), MonitorError> {
+                self.0.lock().unwrap().extend_from_slice(notifications);
+                Ok(())
+            }
+        }
+
+        let metadata = TravelRuleMetadata {
+            reference: Some("wd-7".into()),
+            ..Default::default()
+        };
+        let log = Arc::new(MemoryAuditLog::new());
+        let mut record = AuditRecord::new(
+            AuditKind::SendBroadcast {
+                tx_hash: "out".into(),
+            },
+            "tron",
+            "hot",
+            "x",
+            5,
+        );
+        record.metadata = Some(metadata.clone());
+        log.record(&record);
+
+        let provider = Arc::new(History(Mutex::new(vec![
+            transfer("out", "hot", "x", "5"),
+            transfer("in", "x", "hot", "5"),
+        ])));
+        let sink = Arc::new(Collect::default());
+        let monitor = TransactionMonitor::new(provider, "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_sink(sink.clone())
+            .with_send_metadata(log);
+        monitor.poll().await.unwrap();
+
+        let delivered = sink.0.lock().unwrap();
+        let metadata_of = |hash: &str| {
+            delivered
+                .iter()
+                .find(|n| n.tx_hash == hash)
+                .map(|n| n.metadata.clone())
+                .unwrap()
+        };
+        assert_eq!(metadata_of("out"), Some(metadata));
+        assert_eq!(metadata_of("in"), None);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_a_state_file_without_repeats() {
+        let mut first = transfer("a", "x", "hot", "5");
+        first.timestamp = 100;
+        let mut second = transfer("b", "x", "hot", "5");
+        second.timestamp = 200;
+        let provider = Arc::new(History(Mutex::new(vec![second.clone(), first])));
+        let path = std::env::temp_dir().join(format!("monitor-state-{}", rand::random::<u64>()));
+
+        let monitor = TransactionMonitor::new(provider.clone(), "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_state_file(&path)
+            .unwrap();
+        assert_eq!(monitor.poll().await.unwrap().len(), 2);
+        drop(monitor);
+
+        // Restarted with a fresh in-memory seen store: only the newcomer, which
+        // shares the last timestamp, is reported.
+        let mut third = transfer("c", "x", "hot", "5");
+        third.timestamp = 200;
+        provider.0.lock().unwrap().push(third);
+        let restarted = TransactionMonitor::new(provider, "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_state_file(&path)
+            .unwrap();
+        let events = restarted.poll().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            &events[..],
+            [MonitorEvent::NewTransaction(m)] if m.transaction.hash == "c"
+        ));
+        assert_eq!(restarted.state().cursors[0].hashes.len(), 2);
+    }
+
+    /// Lists one transaction per page; `get_transactions` sees only the first.
+    struct Paged(Vec<Transaction>);
+
+    #[async_trait]
+    impl ReadProvider for Paged {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(self.0[..1].to_vec())
+        }
+        async fn get_transactions_page(
+            &self,
+            _: &str,
+            query: &TxQuery,
+        ) -> Result<TxPage, NodeError> {
+            let index: usize = query.cursor.as_deref().map_or(0, |c| c.parse().unwrap());
+            Ok(TxPage {
+                transactions: vec![self.0[index].clone()],
+                next: (index + 1 < self.0.len()).then(|| (index + 1).to_string()),
+            })
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_pages_past_the_first() {
+        let history = (1..=3)
+            .map(|i| {
+                let mut tx = transfer(&i.to_string(), "x", "hot", "5");
+                tx.timestamp = i * 100;
+                tx
+            })
+            .collect();
+        let monitor =
+            TransactionMonitor::new(Arc::new(Paged(history)), "tron", MonitorFilter::new())
+                .watch("hot");
+        let hashes: Vec<_> = monitor
+            .poll()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                MonitorEvent::NewTransaction(m) => Some(m.transaction.hash),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hashes, ["1", "2", "3"]);
+        assert_eq!(monitor.state().cursors[0].last_timestamp, 300);
+    }
+
+    /// Delivers its transaction once per subscription, then ends.
+    struct Pushed(Transaction);
+
+    #[async_trait]
+    impl TransactionStream for Pushed {
+        async fn subscribe_address(
+            &self,
+            _: &str,
+        ) -> Result<crate::node::AddressStream, NodeError> {
+            Ok(futures_util::stream::iter([Ok(self.0.clone())]).boxed())
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_a_stream_after_catching_up() {
+        let mut old = transfer("old", "x", "hot", "5");
+        old.timestamp = 100;
+        let mut new = transfer("new", "x", "hot", "5");
+        new.timestamp = 200;
+        let provider = Arc::new(History(Mutex::new(vec![old])));
+        let monitor = TransactionMonitor::new(provider, "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_stream(Arc::new(Pushed(new)));
+        let mut rx = monitor.subscribe();
+
+        let stream = monitor.stream.clone().unwrap();
+        monitor
+            .follow(stream.as_ref(), Duration::from_secs(3600), &Shutdown::new())
+            .await
+            .unwrap();
+        let mut hashes = Vec::new();
+        while let Ok(MonitorEvent::NewTransaction(m)) = rx.try_recv() {
+            hashes.push(m.transaction.hash);
+        }
+        assert_eq!(hashes, ["old", "new"]);
+        assert_eq!(monitor.state().cursors[0].last_timestamp, 200);
+    }
+
+    /// History whose transactions all have five confirmations, except
+    /// `gone`, which the node has forgotten.
+    struct Settled(Vec<Transaction>);
+
+    #[async_trait]
+    impl ReadProvider for Settled {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(self.0.clone())
+        }
+        async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+            Ok(match hash {
+                "gone" => TxStatus::NotFound,
+                _ => TxStatus::Confirmed {
+                    block_number: 1,
+                    confirmations: 5,
+                },
+            })
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(6)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_confirmations_until_stopped() {
+        let provider = Arc::new(Settled(vec![
+            transfer("kept", "x", "hot", "5"),
+            transfer("gone", "x", "hot", "5"),
+        ]));
+        let monitor = TransactionMonitor::new(provider, "tron", MonitorFilter::new())
+            .watch("hot")
+            .with_confirmations(3);
+        let mut events = Arc::new(monitor).stream(Duration::from_millis(10));
+
+        let mut seen = Vec::new();
+        while seen.len() < 4 {
+            let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+                .await
+                .unwrap()
+                .unwrap();
+            seen.push(match event {
+                MonitorEvent::NewTransaction(m) => format!("new {}", m.transaction.hash),
+                MonitorEvent::Confirmed {
+                    transaction,
+                    confirmations,
+                } => format!(
+                    "confirmed {} at {}",
+                    transaction.transaction.hash, confirmations
+                ),
+                MonitorEvent::Reverted(m) => format!("reverted {}", m.transaction.hash),
+                other => panic!("unexpected {:?}", other),
+            });
+        }
+        assert_eq!(
+            seen,
+            [
+                "new kept",
+                "new gone",
+                "confirmed kept at 5",
+                "reverted gone"
+            ]
+        );
+
+        events.stop().await;
+        assert!(events.shutdown_handle().is_triggered());
+        let rest = tokio::time::timeout(Duration::from_secs(5), events.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert!(rest.is_empty());
+    }
+
+    struct Logs(Vec<ContractEvent>);
+
+    #[async_trait]
+    impl ContractEventSource for Logs {
+        async fn contract_events(
+            &self,
+            contract: &str,
+            event: &str,
+            range: &TxQuery,
+        ) -> Result<crate::node::EventPage, NodeError> {
+            Ok(crate::node::EventPage {
+                events: self
+                    .0
+                    .iter()
+                    .filter(|log| log.contract == contract && log.name == event)
+                    .filter(|log| range.since.is_none_or(|since| log.timestamp >= since))
+                    .cloned()
+                    .collect(),
+                next: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_trc20_transfers_from_contract_events() {
+        // Mined after the first poll starts the watch at the current time.
+        let later = u64::MAX / 2;
+        let transfer = |tx: &str, from: &str, to: &str| ContractEvent {
+            contract: "Ttoken".into(),
+            name: "Transfer".into(),
+            transaction: tx.into(),
+            log_index: 1,
+            block_number: 7,
+            timestamp: later,
+            params: [("from", from), ("to", to), ("value", "2500000")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            raw: None,
+        };
+        let source = Arc::new(Logs(vec![
+            transfer("in", "Tcustomer", "Thot"),
+            transfer("other", "Tcustomer", "Tsomeone"),
+        ]));
+        let monitor = TransactionMonitor::new(
+            Arc::new(History(Mutex::new(vec![]))),
+            "tron",
+            MonitorFilter::new(),
+        )
+        .watch("Thot")
+        .watch_token_events(source, "Ttoken");
+
+        assert!(monitor.poll().await.unwrap().is_empty());
+        let events = monitor.poll().await.unwrap();
+        let [MonitorEvent::NewTransaction(m)] = &events[..] else {
+            panic!("expected one transfer, got {:?}", events);
+        };
+        assert_eq!(m.direction, Direction::Incoming);
+        assert_eq!(m.transfer.token.as_deref(), Some("Ttoken"));
+        assert!(matches!(
+            &m.transaction.details,
+            Some(TxDetails::Tvm(d)) if d.token_transfers[0].value == "2500000"
+        ));
+        assert!(monitor.poll().await.unwrap().is_empty());
+        assert_eq!(monitor.state().event_cursors[0].address, "Ttoken/transfers");
+    }
+
+    #[tokio::test]
+    async fn reports_contract_events_once() {
+        let log = |tx: &str, name: &str| ContractEvent {
+            contract: "Tdeposits".into(),
+            name: name.into(),
+            transaction: tx.into(),
+            log_index: 0,
+            block_number: 1,
+            timestamp: 100,
+            params: Default::default(),
+            raw: None,
+        };
+        let source = Arc::new(Logs(vec![log("a", "Deposit"), log("b", "Withdrawal")]));
+        let monitor = TransactionMonitor::new(
+            Arc::new(History(Mutex::new(vec![]))),
+            "tron",
+            MonitorFilter::new(),
+        )
+        .watch_events(source, "Tdeposits", "Deposit");
+
+        let events = monitor.poll().await.unwrap();
+        assert!(matches!(
+            &events[..],
+            [MonitorEvent::ContractEvent(e)] if e.transaction == "a"
+        ));
+        assert!(monitor.poll().await.unwrap().is_empty());
+        assert_eq!(
+            monitor.state().event_cursors[0].address,
+            "Tdeposits/Deposit"
+        );
+    }
+
+    /// Serves `logs` matching the filter's contract and block range.
+    struct Blocks {
+        tip: Mutex<u64>,
+        logs: Mutex<Vec<crate::node::Log>>,
+    }
+
+    #[async_trait]
+    impl LogSource for Blocks {
+        async fn block_number(&self) -> Result<u64, NodeError> {
+            Ok(*self.tip.lock().unwrap())
+        }
+        async fn logs(&self, filter: &LogFilter) -> Result<Vec<crate::node::Log>, NodeError> {
+            let range = filter.from_block.unwrap()..=filter.to_block.unwrap();
+            Ok(self
+                .logs
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|log| filter.addresses.contains(&log.address))
+                .filter(|log| range.contains(&log.block_number))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_erc20_transfers_from_logs() {
+        let token = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+        let hot = "0x00000000000000000000000000000000000000aa";
+        let source = Arc::new(Blocks {
+            tip: Mutex::new(100),
+            logs: Mutex::new(vec![]),
+        });
+        let monitor = TransactionMonitor::new(
+            Arc::new(History(Mutex::new(vec![]))),
+            "ethereum",
+            MonitorFilter::new().direction(Direction::Incoming),
+        )
+        .watch(hot)
+        .watch_token_logs(source.clone(), token)
+        .without_history();
+
+        // The first poll only records where to start.
+        assert!(monitor.poll().await.unwrap().is_empty());
+        source.logs.lock().unwrap().push(crate::node::Log {
+            address: token.into(),
+            topics: vec![
+                crate::node::events::ERC20_TRANSFER_TOPIC.into(),
+                crate::node::events::address_topic("0x00000000000000000000000000000000000000bb")
+                    .unwrap(),
+                crate::node::events::address_topic(hot).unwrap(),
+            ],
+            data: format!("0x{:064x}", 2_500_000),
+            block_number: 101,
+            transaction_hash: "0xfeed".into(),
+            log_index: 3,
+            removed: false,
+        });
+        *source.tip.lock().unwrap() = 101;
+
+        let events = monitor.poll().await.unwrap();
+        assert!(matches!(
+            &events[..],
+            [MonitorEvent::NewTransaction(m)]
+                if m.transfer.value == "2500000" && m.transfer.token.as_deref() == Some(token)
+        ));
+        assert!(monitor.poll().await.unwrap().is_empty());
+        assert_eq!(monitor.state().log_cursors[0].last_block, 101);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::time::{Duration, Instant};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::node::{Provider, RawTransaction, ReadProvider, TransactionProvider, TxStatus};
+use crate::shutdown::Shutdown;
+
+/// Why a [`SendWatchdog`] gave up on a transaction getting through as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BumpReason {
+    /// The network still did not know it after every rebroadcast.
+    NotAccepted,
+    /// It has been pending longer than the stuck threshold.
+    Stuck,
+}
+
+/// What a [`SendWatchdog`] saw or did about one transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// The network knows the transaction; it is watched until it is mined.
+    Seen { hash: String },
+    /// Mined; no longer watched.
+    Confirmed { hash: String, block_number: u64 },
+    /// Mined but failed; no longer watched.
+    Failed { hash: String, status: String },
+    /// The signed transaction was sent again; `error` is set if the provider
+    /// refused it.
+    Rebroadcast {
+        hash: String,
+        attempt: u32,
+        error: Option<String>,
+    },
+    /// Needs replacing with a higher fee; no longer watched.
+    NeedsFeeBump { hash: String, reason: BumpReason },
+    /// The provider now serves another chain than the transaction was sent on,
+    /// so it was not rebroadcast; no longer watched.
+    WrongChain {
+        hash: String,
+        expected: String,
+        actual: String,
+    },
+    /// The status could not be read this round.
+    CheckFailed { hash: String, error: String },
+}
+
+/// A transaction a [`SendWatchdog`] is watching, as needed to watch it again
+/// elsewhere (see [`Backup`](crate::wallet::backup::Backup)).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    pub chain: String,
+    pub hash: String,
+    pub signed: RawTransaction,
+}
+
+struct Watched {
+    chain: String,
+    signed: RawTransaction,
+    first_broadcast: Instant,
+    last_broadcast: Instant,
+    rebroadcasts: u32,
+    seen: bool,
+}
+
+enum Action {
+    Report(WatchdogEvent),
+    Rebroadcast {
+        chain: String,
+        signed: RawTransaction,
+    },
+    Wait,
+}
+
+/// Watches broadcast transactions until they are mined, covering the gap
+/// between a provider accepting a broadcast and the network holding on to it.
+///
+/// A transaction the provider does not know `rebroadcast_after` its last
+/// broadcast is sent again, up to `max_rebroadcasts` times; one still unknown
+/// after that, or still pending `stuck_after` its first broadcast, is flagged
+/// with [`WatchdogEvent::NeedsFeeBump`]. Rebroadcasting sends the same signed
+/// bytes, which only ever land on the chain they were signed for.
+pub struct SendWatchdog {
+    provider: Arc<dyn Provider>,
+    interval: Duration,
+    rebroadcast_after: Duration,
+    max_rebroadcasts: u32,
+    stuck_after: Duration,
+    watched: Mutex<BTreeMap<String, Watched>>,
+    events: broadcast::Sender<WatchdogEvent>,
+}
+
+impl SendWatchdog {
+    /// Watchdog checking every 15 seconds, rebroadcasting after 2 minutes (up
+    /// to 3 times) and flagging transactions pending for over 30 minutes.
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            provider,
+            interval: Duration::from_secs(15),
+            rebroadcast_after: Duration::from_secs(120),
+            max_rebroadcasts: 3,
+            stuck_after: Duration::from_secs(30 * 60),
+            watched: Mutex::new(BTreeMap::new()),
+            events,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_rebroadcast_after(mut self, delay: Duration) -> Self {
+        self.rebroadcast_after = delay;
+        self
+    }
+
+    pub fn with_max_rebroadcasts(mut self, attempts: u32) -> Self {
+        self.max_rebroadcasts = attempts;
+        self
+    }
+
+    pub fn with_stuck_after(mut self, delay: Duration) -> Self {
+        self.stuck_after = delay;
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchdogEvent> {
+        self.events.subscribe()
+    }
+
+    /// Start watching `hash`, just broadcast on `chain` as `signed`.
+    pub fn track(&self, chain: &str, hash: &str, signed: RawTransaction) {
+        let now = Instant::now();
+        self.watched.lock().unwrap().insert(
+            hash.to_string(),
+            Watched {
+                chain: chain.to_string(),
+                signed,
+                first_broadcast: now,
+                last_broadcast: now,
+                rebroadcasts: 0,
+                seen: false,
+            },
+        );
+    }
+
+    /// Hashes still being watched.
+    pub fn pending(&self) -> Vec<String> {
+        self.watched.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Everything still being watched, for handing over to another process
+    /// that resumes with [`track`](Self::track).
+    pub fn pending_transactions(&self) -> Vec<PendingTransaction> {
+        self.watched
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(hash, watched)| PendingTransaction {
+                chain: watched.chain.clone(),
+                hash: hash.clone(),
+                signed: watched.signed.clone(),
+            })
+            .collect()
+    }
+
+    /// Check every watched transaction once, publish and return what happened.
+    pub async fn poll(&self) -> Vec<WatchdogEvent> {
+        let mut events = Vec::new();
+        for hash in self.pending() {
+            if let Some(event) = self.check(&hash).await {
+                events.push(event);
+            }
+        }
+        for event in &events {
+            // No subscribers is fine; the caller still gets the events.
+            let _ = self.events.send(event.clone());
+        }
+        events
+    }
+
+    /// Poll at the configured interval until `shutdown` is triggered.
+    pub async fn run(&self, shutdown: &Shutdown) {
+        while !shutdown.is_triggered() {
+            self.poll().await;
+            if shutdown.sleep(self.interval).await {
+                break;
+            }
+        }
+    }
+
+    async fn check(&self, hash: &str) -> Option<WatchdogEvent> {
+        let status = match self.provider.get_transaction_status(hash).await {
+            Ok(status) => status,
+            Err(e) => {
+                return Some(WatchdogEvent::CheckFailed {
+                    hash: hash.to_string(),
+                    error: e.to_string(),
+                });
+            }
+        };
+        match self.decide(hash, status)? {
+            Action::Report(event) => Some(event),
+            Action::Rebroadcast { chain, signed } => {
+                Some(self.rebroadcast(hash, &chain, &signed).await)
+            }
+            Action::Wait => None,
+        }
+    }
+
+    fn decide(&self, hash: &str, status: TxStatus) -> Option<Action> {
+        let mut watched = self.watched.lock().unwrap();
+        let entry = watched.get_mut(hash)?;
+        let hash = hash.to_string();
+        let action = match status {
+            TxStatus::Confirmed { block_number, .. } => {
+                watched.remove(&hash);
+                Action::Report(WatchdogEvent::Confirmed { hash, block_number })
+            }
+            TxStatus::Failed { status, .. } => {
+                watched.remove(&hash);
+                Action::Report(WatchdogEvent::Failed { hash, status })
+            }
+            TxStatus::Pending if entry.first_broadcast.elapsed() >= self.stuck_after => {
+                watched.remove(&hash);
+                Action::Report(WatchdogEvent::NeedsFeeBump {
+                    hash,
+                    reason: BumpReason::Stuck,
+                })
+            }
+            TxStatus::Pending if !entry.seen => {
+                entry.seen = true;
+                Action::Report(WatchdogEvent::Seen { hash })
+            }
+            TxStatus::Pending => Action::Wait,
+            TxStatus::NotFound if entry.last_broadcast.elapsed() < self.rebroadcast_after => {
+                Action::Wait
+            }
+            TxStatus::NotFound if entry.rebroadcasts >= self.max_rebroadcasts => {
+                watched.remove(&hash);
+                Action::Report(WatchdogEvent::NeedsFeeBump {
+                    hash,
+                    reason: BumpReason::NotAccepted,
+                })
+            }
+            TxStatus::NotFound => Action::Rebroadcast {
+                chain: entry.chain.clone(),
+                signed: entry.signed.clone(),
+            },
+        };
+        Some(action)
+    }
+
+    async fn rebroadcast(&self, hash: &str, chain: &str, signed: &RawTransaction) -> WatchdogEvent {
+        if let Ok(info) = self.provider.chain_info().await
+            && let Some(actual) = info.chain_id
+            && actual != chain
+        {
+            self.watched.lock().unwrap().remove(hash);
+            return WatchdogEvent::WrongChain {
+                hash: hash.to_string(),
+                expected: chain.to_string(),
+                actual,
+            };
+        }
+        let error = self
+            .provider
+            .broadcast_transaction(signed)
+            .await
+            .err()
+            .map(|e| e.to_string());
+        let mut watched = self.watched.lock().unwrap();
+        let attempt = match watched.get_mut(hash) {
+            Some(entry) => {
+                entry.rebroadcasts += 1;
+                entry.last_broadcast = Instant::now();
+                entry.rebroadcasts
+            }
+            None => 0,
+        };
+        WatchdogEvent::Rebroadcast {
+            hash: hash.to_string(),
+            attempt,
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, Transaction};
+
+    /// Reports scripted statuses and counts broadcasts.
+    #[derive(Default)]
+    struct Mempool {
+        statuses: Mutex<HashMap<String, TxStatus>>,
+        broadcasts: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Mempool {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+            Ok(self
+                .statuses
+                .lock()
+                .unwrap()
+                .get(hash)
+                .cloned()
+                .unwrap_or(TxStatus::NotFound))
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Mempool {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            *self.broadcasts.lock().unwrap() += 1;
+            Ok("dropped".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn rebroadcasts_until_seen_then_flags() {
+        let mempool = Arc::new(Mempool::default());
+        let watchdog = SendWatchdog::new(mempool.clone())
+            .with_rebroadcast_after(Duration::ZERO)
+            .with_max_rebroadcasts(2);
+        let signed = RawTransaction::Bytes(vec![1, 2, 3]);
+        watchdog.track("litecoin", "lost", signed.clone());
+        watchdog.track("litecoin", "kept", signed);
+        mempool
+            .statuses
+            .lock()
+            .unwrap()
+            .insert("kept".into(), TxStatus::Pending);
+
+        let events = watchdog.poll().await;
+        assert!(events.contains(&WatchdogEvent::Seen {
+            hash: "kept".into()
+        }));
+        assert!(events.contains(&WatchdogEvent::Rebroadcast {
+            hash: "lost".into(),
+            attempt: 1,
+            error: None,
+        }));
+        watchdog.poll().await;
+        assert_eq!(
+            watchdog.poll().await,
+            vec![WatchdogEvent::NeedsFeeBump {
+                hash: "lost".into(),
+                reason: BumpReason::NotAccepted,
+            }]
+        );
+        assert_eq!(*mempool.broadcasts.lock().unwrap(), 2);
+
+        mempool.statuses.lock().unwrap().insert(
+            "kept".into(),
+            TxStatus::Confirmed {
+                block_number: 7,
+                confirmations: 1,
+            },
+        );
+        assert_eq!(
+            watchdog.poll().await,
+            vec![WatchdogEvent::Confirmed {
+                hash: "kept".into(),
+                block_number: 7,
+            }]
+        );
+        assert!(watchdog.pending().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::node::NodeError;
+use crate::node::utils::{format_units, parse_units};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AmountError {
+    #[error("cannot combine amounts with {left} and {right} decimals")]
+    DecimalsMismatch { left: u32, right: u32 },
+    #[error("amount overflow")]
+    Overflow,
+    #[error("amount underflow: {0} is less than {1}")]
+    Underflow(Amount, Amount),
+}
+
+/// A quantity of some coin or token: an integer of base units together with
+/// the decimals that make it a human amount.
+///
+/// Arithmetic is checked and refuses to mix amounts of different decimals,
+/// so a 6-decimal USDT balance cannot be added to an 18-decimal one and a
+/// subtraction cannot wrap around. Amounts of different decimals do not
+/// compare either: `partial_cmp` returns `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Amount {
+    value: u128,
+    decimals: u32,
+}
+
+impl Amount {
+    /// `value` base units (Sun, litoshi, wei, ...).
+    pub const fn new(value: u128, decimals: u32) -> Self {
+        Self { value, decimals }
+    }
+
+    pub const fn zero(decimals: u32) -> Self {
+        Self::new(0, decimals)
+    }
+
+    /// Human amount such as `"1.5"`; see [`parse_units`].
+    pub fn parse(value: &str, decimals: u32) -> Result<Self, NodeError> {
+        Ok(Self::new(parse_units(value, decimals)?, decimals))
+    }
+
+    /// Base-unit integer as providers report it, e.g. a balance of `"1500000"`.
+    pub fn from_base_units(value: &str, decimals: u32) -> Result<Self, NodeError> {
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| NodeError::Parse(format!("invalid base-unit amount {:?}", value)))?;
+        Ok(Self::new(value, decimals))
+    }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.value == 0
+    }
+
+    /// Base units as `u64`, for the send APIs; fails rather than truncate.
+    pub fn to_u64(&self) -> Result<u64, AmountError> {
+        u64::try_from(self.value).map_err(|_| AmountError::Overflow)
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        let value = self
+            .value
+            .checked_add(other.value)
+            .ok_or(AmountError::Overflow)?;
+        Ok(Self::new(value, self.decimals))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        let value = self
+            .value
+            .checked_sub(other.value)
+            .ok_or(AmountError::Underflow(self, other))?;
+        Ok(Self::new(value, self.decimals))
+    }
+
+    fn same_decimals(&self, other: &Self) -> Result<(), AmountError> {
+        if self.decimals == other.decimals {
+            Ok(())
+        } else {
+            Err(AmountError::DecimalsMismatch {
+                left: self.decimals,
+                right: other.decimals,
+            })
+        }
+    }
+}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.same_decimals(other).ok()?;
+        Some(self.value.cmp(&other.value))
+    }
+}
+
+/// Human amount with every decimal place, as [`format_units`] writes it.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_units(&self.value.to_string(), self.decimals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_arithmetic_keeps_units_apart() {
+        let balance = Amount::parse("10.5", 6).unwrap();
+        let send = Amount::parse("0.25", 6).unwrap();
+        assert_eq!(balance.checked_sub(send).unwrap().to_string(), "10.250000");
+        assert_eq!(balance.checked_add(send).unwrap().value(), 10_750_000);
+        assert!(send < balance);
+        assert!(matches!(
+            send.checked_sub(balance),
+            Err(AmountError::Underflow(..))
+        ));
+
+        let wei = Amount::parse("0.25", 18).unwrap();
+        assert_eq!(send.partial_cmp(&wei), None);
+        assert_eq!(
+            send.checked_add(wei),
+            Err(AmountError::DecimalsMismatch { left: 6, right: 18 })
+        );
+        assert_eq!(
+            Amount::new(u128::MAX, 0).checked_add(Amount::new(1, 0)),
+            Err(AmountError::Overflow)
+        );
+        assert!(Amount::new(u64::MAX as u128 + 1, 0).to_u64().is_err());
+        assert!(Amount::from_base_units("1.5", 6).is_err());
+    }
+}
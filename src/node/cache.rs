@@ -0,0 +1,556 @@
+use crate::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+
+use crate::monitor::dedup::EventKey;
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeHistory, FeeOptions, NodeError, Provider, ProviderHealth,
+    RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionDetail,
+    TransactionProvider, TxPage, TxQuery, TxSimulation, TxStatus,
+};
+use crate::persist::{self, Persist};
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+/// Hit and miss counts of a [`TxCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Size-capped LRU of mined transactions keyed by (chain, txid), shared by
+/// every [`CachedProvider`] that should answer from it.
+///
+/// Only transactions with a block number and a final status are kept: those
+/// no longer change (short of a reorg), while pending ones must be re-read to
+/// see them confirm.
+pub struct TxCache {
+    entries: Mutex<LruCache<EventKey, Transaction>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// On-disk form of a [`TxCache`], least recently used first.
+#[derive(Serialize, Deserialize)]
+struct TxCacheSnapshot {
+    entries: Vec<(String, Transaction)>,
+}
+
+impl Persist for TxCacheSnapshot {
+    const KIND: &'static str = "tx_cache";
+    const VERSION: u16 = 1;
+}
+
+impl TxCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache previously written by [`save`](Self::save), or an empty one if
+    /// `path` does not exist. Entries beyond `capacity` are dropped oldest first.
+    pub fn load(path: impl AsRef<Path>, capacity: NonZeroUsize) -> std::io::Result<Self> {
+        let cache = Self::new(capacity);
+        if let Some(snapshot) = persist::load::<TxCacheSnapshot>(path)? {
+            let mut entries = cache.entries.lock().unwrap();
+            for (chain, tx) in snapshot.entries {
+                entries.put(EventKey::for_transaction(&chain, &tx), tx);
+            }
+        }
+        Ok(cache)
+    }
+
+    /// Write all entries to `path`, replacing it atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let snapshot = TxCacheSnapshot {
+            entries: self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .map(|(key, tx)| (key.chain.clone(), tx.clone()))
+                .collect(),
+        };
+        persist::save(path, &snapshot)
+    }
+
+    pub fn get(&self, chain: &str, hash: &str) -> Option<Transaction> {
+        let found = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&EventKey::new(chain, hash, None))
+            .cloned();
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Remember `tx` if it is mined and final; returns whether it was cached.
+    pub fn insert(&self, chain: &str, tx: &Transaction) -> bool {
+        let cacheable =
+            tx.block_number > 0 && !matches!(tx.status.as_str(), "PENDING" | "UNKNOWN" | "");
+        if cacheable {
+            self.entries
+                .lock()
+                .unwrap()
+                .put(EventKey::for_transaction(chain, tx), tx.clone());
+        }
+        cacheable
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// How long a [`CachedProvider`] reuses answers that change as the chain
+/// grows; `None` asks every time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheTtls {
+    pub balance: Option<Duration>,
+    pub block_number: Option<Duration>,
+    pub transactions: Option<Duration>,
+}
+
+/// Short-lived answers of a [`CachedProvider`], with when they were read.
+#[derive(Default)]
+struct FreshAnswers {
+    balances: HashMap<String, (Instant, String)>,
+    block_number: Option<(Instant, u64)>,
+    transactions: HashMap<String, (Instant, Vec<Transaction>)>,
+}
+
+/// `entry`'s value if it was read less than `ttl` ago.
+fn fresh<T: Clone>(entry: Option<&(Instant, T)>, ttl: Option<Duration>) -> Option<T> {
+    let (read, value) = entry?;
+    (read.elapsed() < ttl?).then(|| value.clone())
+}
+
+/// Provider answering [`get_transaction`](ReadProvider::get_transaction) from a
+/// shared [`TxCache`] before asking `inner`.
+///
+/// History listings pass through but feed the cache, so a monitor's poll warms
+/// it for the confirmation tracker and the UI. With [`with_ttls`](Self::with_ttls)
+/// balances, the tip height and history listings are also reused for a while.
+pub struct CachedProvider {
+    inner: Arc<dyn Provider>,
+    chain: String,
+    cache: Arc<TxCache>,
+    ttls: CacheTtls,
+    fresh: Mutex<FreshAnswers>,
+}
+
+impl CachedProvider {
+    /// Cache `inner`'s lookups under `chain` (a `Chain::id`).
+    pub fn new(inner: Arc<dyn Provider>, chain: &str, cache: Arc<TxCache>) -> Self {
+        Self {
+            inner,
+            chain: chain.to_string(),
+            cache,
+            ttls: CacheTtls::default(),
+            fresh: Mutex::new(FreshAnswers::default()),
+        }
+    }
+
+    /// Reuse `get_balance`, `get_block_number` and `get_transactions`
+    /// answers for as long as `ttls` says. A broadcast through this provider
+    /// drops them all, since it may have changed any of them.
+    pub fn with_ttls(mut self, ttls: CacheTtls) -> Self {
+        self.ttls = ttls;
+        self
+    }
+
+    pub fn cache(&self) -> &Arc<TxCache> {
+        &self.cache
+    }
+
+    /// Forget the balance and history kept for `address`, e.g. once a
+    /// deposit to it was announced.
+    pub fn invalidate(&self, address: &str) {
+        let mut fresh = self.fresh.lock().unwrap();
+        fresh.balances.remove(address);
+        fresh.transactions.remove(address);
+    }
+
+    /// Forget every answer kept under [`with_ttls`](Self::with_ttls).
+    pub fn invalidate_all(&self) {
+        *self.fresh.lock().unwrap() = FreshAnswers::default();
+    }
+}
+
+#[async_trait]
+impl ReadProvider for CachedProvider {
+    fn get_decimals(&self) -> u32 {
+        self.inner.get_decimals()
+    }
+
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        self.inner.chain_info().await
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        let ttl = self.ttls.transactions;
+        if let Some(transactions) = fresh(self.fresh.lock().unwrap().transactions.get(address), ttl)
+        {
+            return Ok(transactions);
+        }
+        let read = Instant::now();
+        let transactions = self.inner.get_transactions(address).await?;
+        for tx in &transactions {
+            self.cache.insert(&self.chain, tx);
+        }
+        if ttl.is_some() {
+            let mut fresh = self.fresh.lock().unwrap();
+            let entry = (read, transactions.clone());
+            fresh.transactions.insert(address.to_string(), entry);
+        }
+        Ok(transactions)
+    }
+
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        let page = self.inner.get_transactions_page(address, query).await?;
+        for tx in &page.transactions {
+            self.cache.insert(&self.chain, tx);
+        }
+        Ok(page)
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        if let Some(tx) = self.cache.get(&self.chain, hash) {
+            return Ok(Some(tx));
+        }
+        let tx = self.inner.get_transaction(hash).await?;
+        if let Some(tx) = &tx {
+            self.cache.insert(&self.chain, tx);
+        }
+        Ok(tx)
+    }
+
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        self.inner.get_transaction_status(hash).await
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        let detail = self.inner.get_transaction_detail(hash).await?;
+        if let Some(detail) = &detail {
+            self.cache.insert(&self.chain, &detail.transaction);
+        }
+        Ok(detail)
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        let ttl = self.ttls.block_number;
+        if let Some(number) = fresh(self.fresh.lock().unwrap().block_number.as_ref(), ttl) {
+            return Ok(number);
+        }
+        let read = Instant::now();
+        let number = self.inner.get_block_number().await?;
+        if ttl.is_some() {
+            self.fresh.lock().unwrap().block_number = Some((read, number));
+        }
+        Ok(number)
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        let ttl = self.ttls.balance;
+        if let Some(balance) = fresh(self.fresh.lock().unwrap().balances.get(address), ttl) {
+            return Ok(balance);
+        }
+        let read = Instant::now();
+        let balance = self.inner.get_balance(address).await?;
+        if ttl.is_some() {
+            let mut fresh = self.fresh.lock().unwrap();
+            fresh
+                .balances
+                .insert(address.to_string(), (read, balance.clone()));
+        }
+        Ok(balance)
+    }
+
+    /// Not reused under [`with_ttls`](Self::with_ttls): a past balance is
+    /// asked for by point in time, rarely the same one twice.
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        self.inner.get_balance_at(address, at).await
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        self.inner.get_token_balance(address, contract).await
+    }
+
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        self.inner.get_token_balances(address).await
+    }
+
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        self.inner.get_balances_bulk(addresses).await
+    }
+
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.inner.list_unspent(address).await
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        self.inner.health().await
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for CachedProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner.create_transaction(from, to, amount).await
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        let tx_hash = self.inner.broadcast_transaction(raw_tx).await?;
+        self.invalidate_all();
+        Ok(tx_hash)
+    }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        self.inner.estimate_fee(from, to, amount).await
+    }
+
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        self.inner.check_resources(from, to, amount).await
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        self.inner.fee_history(n_blocks).await
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        self.inner.discard_transaction(raw_tx).await
+    }
+
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_transaction_with_coins(from, to, amount, fees, selection)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_batch_transaction(from, outputs, fees)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        self.inner.simulate_transaction(raw_tx).await
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        self.inner.max_sendable(from, to, fees).await
+    }
+
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_sweep_transaction(from, to, amount, fees)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lookups(AtomicU64);
+
+    #[async_trait]
+    impl ReadProvider for Lookups {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(Transaction {
+                hash: hash.to_string(),
+                from: "a".into(),
+                to: "b".into(),
+                value: "1".into(),
+                block_number: if hash == "pending" { 0 } else { 7 },
+                timestamp: 0,
+                status: "SUCCESS".into(),
+                raw: None,
+                details: None,
+            }))
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(7)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Lookups {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_repeat_lookups_and_persists() {
+        let inner = Arc::new(Lookups(AtomicU64::new(0)));
+        let cache = Arc::new(TxCache::new(NonZeroUsize::new(2).unwrap()));
+        let provider = CachedProvider::new(inner.clone(), "tron", cache.clone());
+
+        provider.get_transaction("AA").await.unwrap();
+        provider.get_transaction("0xaa").await.unwrap();
+        provider.get_transaction("pending").await.unwrap();
+        provider.get_transaction("pending").await.unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3, "pending is never cached");
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3 });
+
+        provider.get_transaction("bb").await.unwrap();
+        provider.get_transaction("cc").await.unwrap();
+        assert_eq!(cache.len(), 2, "capped, least recently used evicted");
+        assert!(cache.get("tron", "aa").is_none());
+
+        let path = std::env::temp_dir().join(format!("tx-cache-{}", rand::random::<u64>()));
+        cache.save(&path).unwrap();
+        let restored = TxCache::load(&path, NonZeroUsize::new(1).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(restored.get("tron", "cc").is_some(), "most recent survives");
+        assert!(restored.get("tron", "bb").is_none());
+    }
+
+    #[tokio::test]
+    async fn reuses_answers_until_they_expire_or_are_invalidated() {
+        use crate::testing::{MockCall, MockProvider};
+
+        let inner = Arc::new(MockProvider::new(6));
+        inner.set_balance("TA", 5);
+        let cache = Arc::new(TxCache::new(NonZeroUsize::new(8).unwrap()));
+        let provider = CachedProvider::new(inner.clone(), "tron", cache).with_ttls(CacheTtls {
+            balance: Some(Duration::from_secs(60)),
+            block_number: Some(Duration::from_millis(20)),
+            transactions: None,
+        });
+
+        assert_eq!(provider.get_balance("TA").await.unwrap(), "5");
+        inner.set_balance("TA", 6);
+        assert_eq!(provider.get_balance("TA").await.unwrap(), "5");
+        provider.invalidate("TA");
+        assert_eq!(provider.get_balance("TA").await.unwrap(), "6");
+        assert_eq!(inner.calls(MockCall::GetBalance), 2);
+
+        provider.get_block_number().await.unwrap();
+        provider.get_block_number().await.unwrap();
+        assert_eq!(inner.calls(MockCall::GetBlockNumber), 1);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        provider.get_block_number().await.unwrap();
+        assert_eq!(inner.calls(MockCall::GetBlockNumber), 2);
+
+        provider.get_transactions("TA").await.unwrap();
+        provider.get_transactions("TA").await.unwrap();
+        assert_eq!(
+            inner.calls(MockCall::GetTransactions),
+            2,
+            "no TTL, no reuse"
+        );
+    }
+
+    #[tokio::test]
+    async fn forwards_every_read_to_the_inner_provider() {
+        use crate::test_utils::{DetailedProvider, assert_forwards_reads};
+
+        let cache = Arc::new(TxCache::new(NonZeroUsize::new(8).unwrap()));
+        let provider = CachedProvider::new(Arc::new(DetailedProvider), "tron", cache.clone());
+        assert_forwards_reads(&provider).await;
+        assert!(cache.get("tron", "tx").is_some(), "details feed the cache");
+    }
+}
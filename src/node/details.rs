@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Chain-specific detail attached to a [`Transaction`](super::Transaction) by
+/// [`Chain::decode_provider_tx`](crate::wallet::chain::Chain::decode_provider_tx).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "chain", rename_all = "snake_case")]
+pub enum TxDetails {
+    Tvm(TvmTxDetails),
+    Utxo(UtxoTxDetails),
+}
+
+/// Decoded view of a TVM (Tron) transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TvmTxDetails {
+    /// Contract type of the first contract, e.g. `TransferContract` or `TriggerSmartContract`.
+    pub contract_type: String,
+    /// Called contract for smart-contract invocations.
+    pub contract_address: Option<String>,
+    /// Total fee burned, in Sun.
+    pub fee: u64,
+    pub energy_usage: Option<u64>,
+    /// TRC-20 transfers, from event logs when present, otherwise from `transfer` call data.
+    pub token_transfers: Vec<TokenTransfer>,
+}
+
+/// A fungible-token movement decoded from a contract call or `Transfer` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenTransfer {
+    pub token: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the token's base unit, as a decimal string.
+    pub value: String,
+}
+
+/// Decoded view of a UTXO transaction, as far as the provider exposed it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UtxoTxDetails {
+    pub inputs: Vec<UtxoIo>,
+    pub outputs: Vec<UtxoIo>,
+    pub fees: Option<u64>,
+}
+
+/// One input or output of a UTXO transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UtxoIo {
+    /// Position within the transaction's inputs or outputs.
+    pub index: u32,
+    pub addresses: Vec<String>,
+    pub value: u64,
+    /// Hex-encoded script (scriptSig for inputs, scriptPubKey for outputs).
+    pub script: Option<String>,
+    pub script_type: Option<String>,
+}
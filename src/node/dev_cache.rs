@@ -0,0 +1,449 @@
+//! Record-and-replay of provider reads for examples and local development.
+//!
+//! A [`DevCacheProvider`] stores every successful read under a directory, one
+//! JSON file per distinct request, and answers the same request from disk on
+//! later runs. Chain state is frozen at the first run; delete the file (or the
+//! whole directory) to fetch it again. Sends and fee-bearing transaction
+//! building always go to the wrapped provider.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeHistory, FeeOptions, NodeError, Provider, ProviderHealth,
+    RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionDetail,
+    TransactionProvider, TxPage, TxQuery, TxSimulation, TxStatus,
+};
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+/// Provider serving reads from responses recorded under `dir`, asking
+/// `inner` (and recording the answer) only on a miss.
+///
+/// Failed reads are not recorded, so a run without connectivity still
+/// replays whatever earlier runs saw and fails only on what they did not.
+pub struct DevCacheProvider {
+    inner: Arc<dyn Provider>,
+    dir: PathBuf,
+}
+
+impl DevCacheProvider {
+    /// Record under `dir`, which is created on first write. Give each
+    /// endpoint its own directory: requests are keyed by method and
+    /// arguments only.
+    pub fn new(inner: Arc<dyn Provider>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File holding the response to `method` called with `args`.
+    fn entry(&self, method: &str, args: &Value) -> PathBuf {
+        let key = Sha256::digest(json!([method, args]).to_string());
+        self.dir
+            .join(format!("{}-{}.json", method, hex::encode(&key[..8])))
+    }
+
+    async fn cached<T, F>(&self, method: &str, args: Value, fetch: F) -> Result<T, NodeError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, NodeError>>,
+    {
+        if let Some(value) = self.replay(method, &args) {
+            return Ok(value);
+        }
+        let value = fetch.await?;
+        self.record(method, args, &value);
+        Ok(value)
+    }
+
+    /// The recorded response to `method` with `args`. An unreadable or
+    /// stale-shaped entry counts as missing, to be refetched and overwritten.
+    fn replay<T: DeserializeOwned>(&self, method: &str, args: &Value) -> Option<T> {
+        let bytes = std::fs::read(self.entry(method, args)).ok()?;
+        let Recorded { response, .. } = serde_json::from_slice(&bytes).ok()?;
+        serde_json::from_value(response).ok()
+    }
+
+    /// Record `value` as the response to `method` with `args`. Best effort;
+    /// the caller still gets the live answer.
+    fn record<T: Serialize>(&self, method: &str, args: Value, value: &T) {
+        let path = self.entry(method, &args);
+        if let Ok(response) = serde_json::to_value(value) {
+            let recorded = Recorded {
+                method: method.to_string(),
+                args,
+                response,
+            };
+            let _ = write(&path, &recorded);
+        }
+    }
+}
+
+/// On-disk entry; the request is kept so recordings can be read and edited.
+#[derive(Serialize, serde::Deserialize)]
+struct Recorded {
+    method: String,
+    args: Value,
+    response: Value,
+}
+
+fn write(path: &Path, recorded: &Recorded) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(recorded)?)?;
+    std::fs::rename(tmp, path)
+}
+
+#[async_trait]
+impl ReadProvider for DevCacheProvider {
+    fn get_decimals(&self) -> u32 {
+        self.inner.get_decimals()
+    }
+
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        self.cached("chain_info", json!([]), self.inner.chain_info())
+            .await
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        self.cached(
+            "get_transactions",
+            json!([address]),
+            self.inner.get_transactions(address),
+        )
+        .await
+    }
+
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        self.inner.get_transactions_page(address, query).await
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        self.cached(
+            "get_transaction",
+            json!([hash]),
+            self.inner.get_transaction(hash),
+        )
+        .await
+    }
+
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        self.cached(
+            "get_transaction_status",
+            json!([hash]),
+            self.inner.get_transaction_status(hash),
+        )
+        .await
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        self.cached(
+            "get_transaction_detail",
+            json!([hash]),
+            self.inner.get_transaction_detail(hash),
+        )
+        .await
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        self.cached("get_block_number", json!([]), self.inner.get_block_number())
+            .await
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        self.cached(
+            "get_balance",
+            json!([address]),
+            self.inner.get_balance(address),
+        )
+        .await
+    }
+
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        let at_key = match at {
+            BalanceAt::Block(block) => json!({ "block": block }),
+            BalanceAt::Timestamp(timestamp) => json!({ "timestamp": timestamp }),
+        };
+        self.cached(
+            "get_balance_at",
+            json!([address, at_key]),
+            self.inner.get_balance_at(address, at),
+        )
+        .await
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        self.cached(
+            "get_token_balance",
+            json!([address, contract]),
+            self.inner.get_token_balance(address, contract),
+        )
+        .await
+    }
+
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        self.cached(
+            "get_token_balances",
+            json!([address]),
+            self.inner.get_token_balances(address),
+        )
+        .await
+    }
+
+    /// Replays the balances [`get_balance`](ReadProvider::get_balance)
+    /// recorded and asks `inner` for the rest in one batch.
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        let recorded: Vec<Option<String>> = addresses
+            .iter()
+            .map(|address| self.replay("get_balance", &json!([address])))
+            .collect();
+        let missing: Vec<String> = addresses
+            .iter()
+            .zip(&recorded)
+            .filter(|(_, balance)| balance.is_none())
+            .map(|(address, _)| address.clone())
+            .collect();
+        let mut fetched = self.inner.get_balances_bulk(&missing).await.into_iter();
+        let mut missing = missing.iter();
+        recorded
+            .into_iter()
+            .map(|balance| match balance {
+                Some(balance) => Ok(balance),
+                None => {
+                    let address = missing.next().expect("one fetch per miss");
+                    let balance = fetched.next().unwrap_or_else(|| {
+                        Err(NodeError::Api("no balance in the bulk answer".into()))
+                    })?;
+                    self.record("get_balance", json!([address]), &balance);
+                    Ok(balance)
+                }
+            })
+            .collect()
+    }
+
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.inner.list_unspent(address).await
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        self.inner.health().await
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for DevCacheProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner.create_transaction(from, to, amount).await
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        self.inner.broadcast_transaction(raw_tx).await
+    }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        self.cached(
+            "estimate_fee",
+            json!([from, to, amount]),
+            self.inner.estimate_fee(from, to, amount),
+        )
+        .await
+    }
+
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        self.inner.check_resources(from, to, amount).await
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        self.cached(
+            "fee_history",
+            json!([n_blocks]),
+            self.inner.fee_history(n_blocks),
+        )
+        .await
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        self.inner.discard_transaction(raw_tx).await
+    }
+
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_transaction_with_coins(from, to, amount, fees, selection)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_batch_transaction(from, outputs, fees)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        self.inner.simulate_transaction(raw_tx).await
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        self.inner.max_sendable(from, to, fees).await
+    }
+
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.inner
+            .create_sweep_transaction(from, to, amount, fees)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    /// Counts balance lookups; fails them once `offline` is set.
+    #[derive(Default)]
+    struct Remote {
+        calls: AtomicU64,
+        offline: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Remote {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(7)
+        }
+        async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+            if self.offline.load(Ordering::SeqCst) {
+                return Err(NodeError::Network("offline".into()));
+            }
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("{}00", address.len()))
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Remote {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_reads_across_runs() {
+        let dir = std::env::temp_dir().join(format!("dev-cache-{}", rand::random::<u64>()));
+        let remote = Arc::new(Remote::default());
+
+        let first = DevCacheProvider::new(remote.clone(), &dir);
+        assert_eq!(first.get_balance("abc").await.unwrap(), "300");
+        assert_eq!(first.get_balance("abc").await.unwrap(), "300");
+        assert_eq!(remote.calls.load(Ordering::SeqCst), 1);
+
+        // A later run with no connectivity replays what was recorded.
+        remote.offline.store(true, Ordering::SeqCst);
+        let second = DevCacheProvider::new(remote.clone(), &dir);
+        assert_eq!(second.get_balance("abc").await.unwrap(), "300");
+        assert!(second.get_balance("abcd").await.is_err());
+
+        // Bulk lookups replay the same recordings.
+        let balances = second
+            .get_balances_bulk(&["abc".into(), "abcd".into()])
+            .await;
+        assert_eq!(balances[0].as_deref().unwrap(), "300");
+        assert!(balances[1].is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn forwards_every_read_to_the_inner_provider() {
+        use crate::test_utils::{DetailedProvider, assert_forwards_reads};
+
+        let dir = std::env::temp_dir().join(format!("dev-cache-{}", rand::random::<u64>()));
+        let provider = DevCacheProvider::new(Arc::new(DetailedProvider), &dir);
+        assert_forwards_reads(&provider).await;
+        // And again from the recordings.
+        assert_forwards_reads(&provider).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
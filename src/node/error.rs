@@ -1,9 +1,108 @@
-#[derive(Debug, thiserror::Error)]
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum NodeError {
     #[error("Network error: {0}")]
     Network(String),
     #[error("Parse error: {0}")]
     Parse(String),
+    /// A response body that did not parse, kept (scrubbed and truncated) for
+    /// debugging against an API that changed under us.
+    #[error("Parse error: {error} (HTTP {status}, body: {body})")]
+    UnexpectedResponse {
+        status: u16,
+        error: String,
+        body: String,
+    },
     #[error("API error: {0}")]
     Api(String),
+    #[error("HTTP status {0}")]
+    Http(u16),
+    /// The endpoint refused the request for exceeding its rate limit (HTTP
+    /// 429), saying when to try again if it did.
+    #[error("Rate limited{}", retry_after.map(|d| format!(", retry after {}s", d.as_secs())).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("RPC error from {method}: {error}")]
+    Rpc { method: String, error: RpcError },
+    /// The sender cannot pay for a send in `resource` (e.g. Tron `energy` or
+    /// `bandwidth`, or the coin burned in their place); amounts are in that
+    /// resource's unit.
+    #[error(
+        "Insufficient {resource}: need {needed}, have {available} ({} short)",
+        needed.saturating_sub(*available)
+    )]
+    InsufficientResources {
+        resource: String,
+        needed: u64,
+        available: u64,
+    },
+}
+
+impl NodeError {
+    /// Whether another attempt, or another endpoint, may succeed: network
+    /// failures, rate limiting and server errors.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NodeError::Network(_) | NodeError::RateLimited { .. } => true,
+            NodeError::Http(status) => *status == 429 || (500..600).contains(status),
+            NodeError::Rpc { error, .. } => error.code == RpcError::LIMIT_EXCEEDED,
+            NodeError::Parse(_)
+            | NodeError::UnexpectedResponse { .. }
+            | NodeError::Api(_)
+            | NodeError::InsufficientResources { .. } => false,
+        }
+    }
+
+    /// Whether the node refused a broadcast because the transaction had
+    /// expired (Tron's `TRANSACTION_EXPIRATION_ERROR`), so building and
+    /// signing it again may succeed where resending cannot.
+    pub fn is_expired_transaction(&self) -> bool {
+        match self {
+            NodeError::Api(message) => {
+                message.contains("TRANSACTION_EXPIRATION_ERROR")
+                    || message.to_ascii_lowercase().contains("transaction expired")
+            }
+            _ => false,
+        }
+    }
+
+    /// How long the endpoint asked to be left alone, for a rate-limited request.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            NodeError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Error object of a JSON-RPC 2.0 response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message} (code {code})")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl RpcError {
+    /// The node does not implement the method.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// Rate limited, as hosted nodes (Infura, Alchemy) report it.
+    pub const LIMIT_EXCEEDED: i64 = -32005;
+
+    /// Read an `error` member, keeping non-conforming ones (a bare string,
+    /// say) as the message.
+    pub fn from_value(value: &serde_json::Value) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_else(|_| Self {
+            code: 0,
+            message: match value {
+                serde_json::Value::String(message) => message.clone(),
+                other => other.to_string(),
+            },
+            data: None,
+        })
+    }
 }
@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+use crate::monitor::Transfer;
+use crate::node::{NodeError, Transaction, TxQuery};
+use crate::wallet::chain::sdk::base58check_encode;
+
+/// `keccak256("Transfer(address,address,uint256)")`, the first topic of every
+/// ERC-20 (and TRC-20) `Transfer` log.
+pub const ERC20_TRANSFER_TOPIC: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// One log a smart contract emitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractEvent {
+    pub contract: String,
+    /// Event name, e.g. `Transfer`.
+    pub name: String,
+    pub transaction: String,
+    /// Position of the log within the transaction.
+    pub log_index: u32,
+    pub block_number: u64,
+    /// Block time, in the provider's unit (milliseconds on Tron).
+    pub timestamp: u64,
+    /// Decoded arguments by parameter name, as the provider renders them.
+    pub params: BTreeMap<String, String>,
+    /// The provider's original entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<serde_json::Value>,
+}
+
+impl ContractEvent {
+    /// The token movement, if this is a TRC-20 (or ERC-20) `Transfer` event.
+    ///
+    /// TronGrid renders the indexed addresses as hex; on a base58 (Tron)
+    /// contract they are turned back into base58, so they compare equal to
+    /// the addresses a wallet derives.
+    pub fn token_transfer(&self) -> Option<Transfer> {
+        if self.name != "Transfer" {
+            return None;
+        }
+        // Some tokens name their parameters `_from`, `_to`, `_value`.
+        let param = |name: &str| {
+            self.params
+                .get(name)
+                .or_else(|| self.params.get(&format!("_{}", name)))
+        };
+        let address = |value: &String| -> Option<String> {
+            if !self.contract.starts_with('T') {
+                return Some(value.clone());
+            }
+            match hex::decode(value.trim_start_matches("0x")) {
+                Ok(body) if body.len() == 20 => {
+                    Some(base58check_encode(&[&[0x41], &body[..]].concat()))
+                }
+                Ok(body) if body.len() == 21 && body[0] == 0x41 => Some(base58check_encode(&body)),
+                Ok(_) => None,
+                Err(_) => Some(value.clone()),
+            }
+        };
+        let value = param("value")?;
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(Transfer {
+            from: address(param("from")?)?,
+            to: address(param("to")?)?,
+            value: value.clone(),
+            token: Some(self.contract.clone()),
+        })
+    }
+}
+
+/// One page of a contract's events.
+#[derive(Debug, Clone, Default)]
+pub struct EventPage {
+    pub events: Vec<ContractEvent>,
+    /// Cursor for the following page; `None` on the last one.
+    pub next: Option<String>,
+}
+
+/// Backend that lists the events a contract emitted.
+#[async_trait]
+pub trait ContractEventSource: Send + Sync {
+    /// Events named `event` emitted by `contract` within `range`'s time bounds.
+    async fn contract_events(
+        &self,
+        contract: &str,
+        event: &str,
+        range: &TxQuery,
+    ) -> Result<EventPage, NodeError>;
+}
+
+/// `eth_getLogs` / `eth_subscribe("logs")` filter.
+///
+/// Each topic position matches any of its listed values; `None` matches anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Contracts whose logs to return; empty for all.
+    pub addresses: Vec<String>,
+    pub topics: Vec<Option<Vec<String>>>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl LogFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(mut self, contract: &str) -> Self {
+        self.addresses.push(contract.to_string());
+        self
+    }
+
+    /// Match topic `position` against any of `values` (`0x`-prefixed, 32 bytes).
+    pub fn topic(mut self, position: usize, values: Vec<String>) -> Self {
+        if self.topics.len() <= position {
+            self.topics.resize(position + 1, None);
+        }
+        self.topics[position] = Some(values);
+        self
+    }
+
+    pub fn from_block(mut self, block: u64) -> Self {
+        self.from_block = Some(block);
+        self
+    }
+
+    pub fn to_block(mut self, block: u64) -> Self {
+        self.to_block = Some(block);
+        self
+    }
+
+    /// `Transfer` logs of the token at `contract` to (`incoming`) or from any of `holders`.
+    pub fn erc20_transfers(contract: &str, holders: &[String], incoming: bool) -> Self {
+        let holders = holders.iter().filter_map(|a| address_topic(a)).collect();
+        Self::new()
+            .address(contract)
+            .topic(0, vec![ERC20_TRANSFER_TOPIC.to_string()])
+            .topic(if incoming { 2 } else { 1 }, holders)
+    }
+
+    /// The filter object JSON-RPC expects.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut filter = serde_json::json!({ "topics": self.topics });
+        match self.addresses.as_slice() {
+            [] => {}
+            [address] => filter["address"] = address.clone().into(),
+            addresses => filter["address"] = addresses.into(),
+        }
+        if let Some(block) = self.from_block {
+            filter["fromBlock"] = format!("{:#x}", block).into();
+        }
+        if let Some(block) = self.to_block {
+            filter["toBlock"] = format!("{:#x}", block).into();
+        }
+        filter
+    }
+}
+
+/// One EVM log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    /// Non-indexed arguments, `0x` hex.
+    pub data: String,
+    pub block_number: u64,
+    pub transaction_hash: String,
+    pub log_index: u32,
+    /// Set on logs a subscription retracts after a reorg.
+    pub removed: bool,
+}
+
+impl Log {
+    /// Parse a JSON-RPC log object.
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, NodeError> {
+        let text = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| NodeError::Parse(format!("log without {}", key)))
+        };
+        let quantity = |key: &str| -> Result<u64, NodeError> {
+            let raw = text(key)?;
+            u64::from_str_radix(raw.trim_start_matches("0x"), 16)
+                .map_err(|_| NodeError::Parse(format!("invalid {} {}", key, raw)))
+        };
+        let topics = value
+            .get("topics")
+            .and_then(|v| v.as_array())
+            .map(|topics| {
+                topics
+                    .iter()
+                    .filter_map(|t| t.as_str().map(str::to_ascii_lowercase))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            address: text("address")?,
+            topics,
+            data: text("data")?,
+            block_number: quantity("blockNumber")?,
+            transaction_hash: text("transactionHash")?,
+            log_index: quantity("logIndex")? as u32,
+            removed: value
+                .get("removed")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        })
+    }
+
+    /// The token movement, if this is an ERC-20 `Transfer` log. ERC-721
+    /// transfers share the topic but index the token id, and are skipped.
+    pub fn erc20_transfer(&self) -> Option<Transfer> {
+        let [topic, from, to] = self.topics.as_slice() else {
+            return None;
+        };
+        if topic != ERC20_TRANSFER_TOPIC {
+            return None;
+        }
+        let topic_address = |topic: &str| {
+            let body = topic.strip_prefix("0x")?;
+            (body.len() == 64).then(|| format!("0x{}", &body[24..]))
+        };
+        let data = hex::decode(self.data.trim_start_matches("0x")).ok()?;
+        if data.len() != 32 || data[..16].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let value = u128::from_be_bytes(data[16..].try_into().ok()?);
+        Some(Transfer {
+            from: topic_address(from)?,
+            to: topic_address(to)?,
+            value: value.to_string(),
+            token: Some(self.address.clone()),
+        })
+    }
+}
+
+/// Topic an indexed `address` argument equal to `address` (`0x` hex) has.
+pub fn address_topic(address: &str) -> Option<String> {
+    let body = address.strip_prefix("0x")?;
+    (body.len() == 40 && body.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| format!("0x{:0>64}", body.to_ascii_lowercase()))
+}
+
+/// Backend that answers `eth_getLogs` queries.
+#[async_trait]
+pub trait LogSource: Send + Sync {
+    async fn block_number(&self) -> Result<u64, NodeError>;
+    async fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>, NodeError>;
+}
+
+/// Transactions of one address as a [`TransactionStream`] delivers them.
+pub type AddressStream = BoxStream<'static, Result<Transaction, NodeError>>;
+
+/// Backend that pushes new transactions as blocks arrive, so a
+/// [`TransactionMonitor`](crate::monitor::TransactionMonitor) need not poll.
+#[async_trait]
+pub trait TransactionStream: Send + Sync {
+    /// Transactions sent from or to `address` in blocks produced from now
+    /// on. The stream ends when the connection does; nothing from before
+    /// the subscription is replayed.
+    async fn subscribe_address(&self, address: &str) -> Result<AddressStream, NodeError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_erc20_transfer_logs() {
+        let log = Log::from_json(&serde_json::json!({
+            "address": "0xdac17f958d2ee523a2206206994597c13d831ec7",
+            "topics": [
+                ERC20_TRANSFER_TOPIC,
+                "0x000000000000000000000000A614F803B6FD780986A42C78EC9C7F77E6DED13C",
+                "0x0000000000000000000000000000000000000000000000000000000000000001",
+            ],
+            "data": "0x00000000000000000000000000000000000000000000000000000000000f4240",
+            "blockNumber": "0x10",
+            "transactionHash": "0xabc",
+            "logIndex": "0x2",
+        }))
+        .unwrap();
+        assert_eq!(log.log_index, 2);
+        let transfer = log.erc20_transfer().unwrap();
+        assert_eq!(transfer.from, "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c");
+        assert_eq!(transfer.value, "1000000");
+
+        let filter = LogFilter::erc20_transfers(&log.address, &[transfer.from], false);
+        assert_eq!(
+            filter.to_json()["topics"][1][0],
+            "0x000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c"
+        );
+    }
+
+    #[test]
+    fn reads_trc20_transfer_events_as_base58() {
+        let event = ContractEvent {
+            contract: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t".into(),
+            name: "Transfer".into(),
+            transaction: "abc".into(),
+            log_index: 0,
+            block_number: 1,
+            timestamp: 1,
+            params: BTreeMap::from([
+                (
+                    "from".to_string(),
+                    "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c".to_string(),
+                ),
+                (
+                    "to".to_string(),
+                    "41a614f803b6fd780986a42c78ec9c7f77e6ded13c".to_string(),
+                ),
+                ("value".to_string(), "5".to_string()),
+            ]),
+            raw: None,
+        };
+        let transfer = event.token_transfer().unwrap();
+        assert_eq!(transfer.from, event.contract);
+        assert_eq!(transfer.to, event.contract);
+        assert_eq!(transfer.value, "5");
+
+        let approval = ContractEvent {
+            name: "Approval".into(),
+            ..event
+        };
+        assert!(approval.token_transfer().is_none());
+    }
+}
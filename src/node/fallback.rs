@@ -0,0 +1,489 @@
+use crate::time::{Duration, Instant};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeHistory, FeeOptions, NodeError, Provider, ProviderHealth,
+    RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionDetail,
+    TransactionProvider, TxPage, TxQuery, TxSimulation, TxStatus,
+};
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+/// What a [`FallbackProvider`] did about a failing endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FallbackEvent {
+    /// Provider `from` failed with a transient error; the call moved on to `to`.
+    FailedOver {
+        from: usize,
+        to: usize,
+        error: String,
+    },
+    /// A health check failed; provider `index` is tried last until one passes.
+    Unhealthy { index: usize, error: String },
+    /// Provider `index` passed a health check again.
+    Healthy { index: usize },
+}
+
+type FallbackListener = Arc<dyn Fn(&FallbackEvent) + Send + Sync>;
+
+/// Provider over an ordered list of endpoints for the same chain, moving on to
+/// the next one when a call fails with a [transient](NodeError::is_transient)
+/// error (network failures, rate limiting and HTTP 5xx).
+///
+/// The endpoint that last answered is tried first, so an outage costs one
+/// failed request rather than one per call. Errors the endpoint itself
+/// reports (a rejected transaction, an unknown address) are returned as is.
+pub struct FallbackProvider<P = Arc<dyn Provider>> {
+    providers: Vec<P>,
+    current: AtomicUsize,
+    health_interval: Option<Duration>,
+    last_health_check: Mutex<Option<Instant>>,
+    unhealthy: Mutex<HashSet<usize>>,
+    listener: Option<FallbackListener>,
+}
+
+impl<P: Provider> FallbackProvider<P> {
+    /// Endpoints in order of preference.
+    pub fn new(providers: Vec<P>) -> Self {
+        Self {
+            providers,
+            current: AtomicUsize::new(0),
+            health_interval: None,
+            last_health_check: Mutex::new(None),
+            unhealthy: Mutex::new(HashSet::new()),
+            listener: None,
+        }
+    }
+
+    /// Probe every endpoint's [`health`](ReadProvider::health) at most once per
+    /// `interval`, before a call, and try endpoints that failed the probe last.
+    pub fn with_health_checks(mut self, interval: Duration) -> Self {
+        self.health_interval = Some(interval);
+        self
+    }
+
+    /// Call `listener` on every failover and health change.
+    pub fn on_event(mut self, listener: impl Fn(&FallbackEvent) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    pub fn providers(&self) -> &[P] {
+        &self.providers
+    }
+
+    /// Index of the endpoint tried first.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Probe every endpoint now and record which ones are unhealthy.
+    pub async fn check_health(&self) {
+        *self.last_health_check.lock().unwrap() = Some(Instant::now());
+        for (index, provider) in self.providers.iter().enumerate() {
+            let result = provider.health().await;
+            let mut unhealthy = self.unhealthy.lock().unwrap();
+            match result {
+                Ok(_) => {
+                    if unhealthy.remove(&index) {
+                        drop(unhealthy);
+                        self.emit(&FallbackEvent::Healthy { index });
+                    }
+                }
+                Err(e) => {
+                    unhealthy.insert(index);
+                    drop(unhealthy);
+                    self.emit(&FallbackEvent::Unhealthy {
+                        index,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Endpoints in the order this call tries them: from the current one
+    /// round the list, with unhealthy ones moved to the end.
+    fn order(&self) -> Vec<usize> {
+        let count = self.providers.len();
+        let start = self.current();
+        let unhealthy = self.unhealthy.lock().unwrap();
+        let (mut healthy, sick): (Vec<usize>, Vec<usize>) = (0..count)
+            .map(|i| (start + i) % count)
+            .partition(|i| !unhealthy.contains(i));
+        healthy.extend(sick);
+        healthy
+    }
+
+    async fn health_check_if_due(&self) {
+        let Some(interval) = self.health_interval else {
+            return;
+        };
+        let due = self
+            .last_health_check
+            .lock()
+            .unwrap()
+            .is_none_or(|at| at.elapsed() >= interval);
+        if due {
+            self.check_health().await;
+        }
+    }
+
+    /// Run `call` against each endpoint in turn until one succeeds or fails
+    /// with an error another endpoint would not fix.
+    async fn call<'a, T, F, Fut>(&'a self, call: F) -> Result<T, NodeError>
+    where
+        F: Fn(&'a P) -> Fut,
+        Fut: Future<Output = Result<T, NodeError>>,
+    {
+        self.health_check_if_due().await;
+        let order = self.order();
+        let mut last_error = None;
+        for (attempt, &index) in order.iter().enumerate() {
+            match call(&self.providers[index]).await {
+                Ok(value) => {
+                    self.current.store(index, Ordering::Relaxed);
+                    return Ok(value);
+                }
+                Err(e) if e.is_transient() => {
+                    if let Some(&next) = order.get(attempt + 1) {
+                        self.emit(&FallbackEvent::FailedOver {
+                            from: index,
+                            to: next,
+                            error: e.to_string(),
+                        });
+                    }
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| NodeError::Network("no providers configured".into())))
+    }
+
+    fn emit(&self, event: &FallbackEvent) {
+        if let Some(listener) = &self.listener {
+            listener(event);
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Provider> ReadProvider for FallbackProvider<P> {
+    fn get_decimals(&self) -> u32 {
+        self.providers
+            .first()
+            .map(|p| p.get_decimals())
+            .unwrap_or(0)
+    }
+
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        self.call(|p| p.chain_info()).await
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        self.call(|p| p.get_transactions(address)).await
+    }
+
+    /// Cursors are endpoint-specific; a page after a failover may restart or skip.
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        self.call(|p| p.get_transactions_page(address, query)).await
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        self.call(|p| p.get_transaction(hash)).await
+    }
+
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        self.call(|p| p.get_transaction_status(hash)).await
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        self.call(|p| p.get_transaction_detail(hash)).await
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        self.call(|p| p.get_block_number()).await
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        self.call(|p| p.get_balance(address)).await
+    }
+
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        self.call(|p| p.get_balance_at(address, at)).await
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        self.call(|p| p.get_token_balance(address, contract)).await
+    }
+
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        self.call(|p| p.get_token_balances(address)).await
+    }
+
+    /// Addresses that failed transiently are retried on the next endpoint.
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        self.health_check_if_due().await;
+        let mut results: Vec<Result<String, NodeError>> = addresses
+            .iter()
+            .map(|_| Err(NodeError::Network("no providers configured".into())))
+            .collect();
+        let mut pending: Vec<usize> = (0..addresses.len()).collect();
+        for index in self.order() {
+            if pending.is_empty() {
+                break;
+            }
+            let batch: Vec<String> = pending.iter().map(|&i| addresses[i].clone()).collect();
+            let answers = self.providers[index].get_balances_bulk(&batch).await;
+            let mut retry = Vec::new();
+            for (i, answer) in pending.into_iter().zip(answers) {
+                if matches!(&answer, Err(e) if e.is_transient()) {
+                    retry.push(i);
+                }
+                results[i] = answer;
+            }
+            if retry.len() < batch.len() {
+                self.current.store(index, Ordering::Relaxed);
+            }
+            pending = retry;
+        }
+        results
+    }
+
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.call(|p| p.list_unspent(address)).await
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        self.call(|p| p.health()).await
+    }
+}
+
+#[async_trait]
+impl<P: Provider> TransactionProvider for FallbackProvider<P> {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.call(|p| p.create_transaction(from, to, amount)).await
+    }
+
+    /// Rebroadcasting a signed transaction elsewhere is safe: it has one hash
+    /// wherever it lands.
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        self.call(|p| p.broadcast_transaction(raw_tx)).await
+    }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        self.call(|p| p.estimate_fee(from, to, amount)).await
+    }
+
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        self.call(|p| p.check_resources(from, to, amount)).await
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        self.call(|p| p.fee_history(n_blocks)).await
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.call(|p| p.create_transaction_with_fees(from, to, amount, fees))
+            .await
+    }
+
+    /// Every provider hears of it, as the one that built `raw_tx` may no
+    /// longer be the current one.
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        for provider in &self.providers {
+            provider.discard_transaction(raw_tx).await;
+        }
+    }
+
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        self.call(|p| p.create_transaction_with_coins(from, to, amount, fees, selection))
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.call(|p| p.create_batch_transaction(from, outputs, fees))
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        self.call(|p| p.simulate_transaction(raw_tx)).await
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        self.call(|p| p.max_sendable(from, to, fees)).await
+    }
+
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.call(|p| p.create_sweep_transaction(from, to, amount, fees))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+
+    use super::*;
+
+    /// Answers with `balance`, or fails with `error` while `failures` lasts.
+    struct Flaky {
+        balance: &'static str,
+        error: NodeError,
+        failures: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(balance: &'static str, error: NodeError, failures: u32) -> Self {
+            Self {
+                balance,
+                error,
+                failures: AtomicU32::new(failures),
+                calls: AtomicU32::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ReadProvider for Flaky {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                Err(self.error.clone())
+            } else {
+                Ok(self.balance.to_string())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Flaky {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_transient_errors_and_sticks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let provider = FallbackProvider::new(vec![
+            Flaky::new("primary", NodeError::Http(503), 1),
+            Flaky::new("backup", NodeError::Http(503), 0),
+        ])
+        .on_event(move |e| sink.lock().unwrap().push(e.clone()));
+
+        assert_eq!(provider.get_balance("a").await.unwrap(), "backup");
+        assert_eq!(provider.current(), 1);
+        // The backup keeps answering; the primary is not retried every call.
+        assert_eq!(provider.get_balance("a").await.unwrap(), "backup");
+        assert_eq!(provider.providers()[0].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![FallbackEvent::FailedOver {
+                from: 0,
+                to: 1,
+                error: "HTTP status 503".into(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn endpoint_errors_are_not_retried_elsewhere() {
+        let provider: FallbackProvider = FallbackProvider::new(vec![
+            Arc::new(Flaky::new(
+                "primary",
+                NodeError::Api("bad address".into()),
+                1,
+            )),
+            Arc::new(Flaky::new(
+                "backup",
+                NodeError::Api("bad address".into()),
+                0,
+            )),
+        ]);
+        assert_eq!(
+            provider.get_balance("a").await,
+            Err(NodeError::Api("bad address".into()))
+        );
+        assert!(NodeError::Network("reset".into()).is_transient());
+        assert!(NodeError::Http(429).is_transient());
+        assert!(!NodeError::Http(404).is_transient());
+    }
+}
@@ -0,0 +1,225 @@
+//! Fee tiers and per-send fee controls shared by all providers.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// How quickly a transaction should confirm, traded against its fee.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FeePriority {
+    Economy,
+    #[default]
+    Normal,
+    Priority,
+}
+
+/// Network fee of a transfer at each priority, in the chain's base unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    pub economy: u64,
+    pub normal: u64,
+    pub priority: u64,
+}
+
+impl FeeEstimate {
+    /// Same fee whatever the priority, e.g. Tron's bandwidth burn.
+    pub fn flat(fee: u64) -> Self {
+        Self {
+            economy: fee,
+            normal: fee,
+            priority: fee,
+        }
+    }
+
+    pub fn at(&self, priority: FeePriority) -> u64 {
+        match priority {
+            FeePriority::Economy => self.economy,
+            FeePriority::Normal => self.normal,
+            FeePriority::Priority => self.priority,
+        }
+    }
+}
+
+/// Fee and ordering controls for one transaction, see
+/// [`TransactionProvider::create_transaction_with_fees`](super::TransactionProvider::create_transaction_with_fees).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeOptions {
+    pub priority: FeePriority,
+    /// Explicit price per unit of the chain's fee resource, overriding `priority`:
+    /// base units per kilobyte on UTXO chains, wei per gas (max fee) on EVM chains.
+    pub fee_rate: Option<u64>,
+    /// Reject the transaction if its fee could exceed this many base units.
+    pub fee_limit: Option<u64>,
+    /// Tip per unit of gas (`maxPriorityFeePerGas`, wei) on EVM chains,
+    /// instead of the one suggested for `priority`.
+    pub priority_fee: Option<u64>,
+    /// Nonce to use instead of the next free one, on chains that order an
+    /// account's transactions by nonce. Reusing the nonce of a pending
+    /// transaction replaces it.
+    pub nonce: Option<u64>,
+}
+
+impl FeeOptions {
+    /// Fail with an API error when `fee` is above [`fee_limit`](Self::fee_limit).
+    pub fn check_limit(&self, fee: u64) -> Result<(), super::NodeError> {
+        match self.fee_limit {
+            Some(limit) if fee > limit => Err(super::NodeError::Api(format!(
+                "fee of {} exceeds fee_limit of {}",
+                fee, limit
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reward percentiles [`TransactionProvider::fee_history`](super::TransactionProvider::fee_history)
+/// asks for: one per [`FeePriority`], economy first.
+pub const FEE_HISTORY_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Weight of the newest block in a smoothed tip. Low enough that one
+/// congested block moves the suggestion by a fraction of its spike.
+pub const FEE_EMA_ALPHA: f64 = 0.3;
+
+/// Fees paid in a run of recent blocks, oldest first, in the chain's fee unit
+/// (wei per gas on EVM chains).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistory {
+    pub oldest_block: u64,
+    /// Base fee of each block, and of the block after the newest as the last entry.
+    pub base_fees: Vec<u128>,
+    /// Fraction of each block's gas limit that was used.
+    pub gas_used_ratios: Vec<f64>,
+    /// Tip at each of [`FEE_HISTORY_PERCENTILES`] within each block.
+    pub rewards: Vec<Vec<u128>>,
+}
+
+impl FeeHistory {
+    /// Base fee the next block will charge.
+    pub fn next_base_fee(&self) -> Option<u128> {
+        self.base_fees.last().copied()
+    }
+
+    /// The higher of the next base fee and the `pct` percentile of recent
+    /// ones, so a brief dip does not leave a transaction underpriced.
+    pub fn base_fee_ceiling(&self, pct: f64) -> Option<u128> {
+        Some(self.next_base_fee()?.max(percentile(&self.base_fees, pct)?))
+    }
+
+    /// Tip for `priority`: each block's tip at the priority's percentile,
+    /// smoothed by an [`Ema`] over the blocks. `None` without rewards.
+    pub fn smoothed_tip(&self, priority: FeePriority) -> Option<u128> {
+        let column = match priority {
+            FeePriority::Economy => 0,
+            FeePriority::Normal => 1,
+            FeePriority::Priority => 2,
+        };
+        let mut ema = Ema::new(FEE_EMA_ALPHA);
+        for block in &self.rewards {
+            ema.update(*block.get(column)?);
+        }
+        ema.value()
+    }
+}
+
+/// Nearest-rank `pct` percentile (0 to 100) of `values`; `None` when empty.
+pub fn percentile(values: &[u128], pct: f64) -> Option<u128> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = (pct.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.saturating_sub(1)])
+}
+
+/// Exponential moving average: each sample moves the average by `alpha` of
+/// the distance to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// `alpha` in `(0, 1]`; higher follows new samples more closely.
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(f64::EPSILON, 1.0),
+            value: None,
+        }
+    }
+
+    /// Fold in `sample` and return the new average.
+    pub fn update(&mut self, sample: u128) -> u128 {
+        let sample = sample as f64;
+        let value = match self.value {
+            Some(value) => value + self.alpha * (sample - value),
+            None => sample,
+        };
+        self.value = Some(value);
+        value.round() as u128
+    }
+
+    pub fn value(&self) -> Option<u128> {
+        self.value.map(|v| v.round() as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_and_limits() {
+        let estimate = FeeEstimate {
+            economy: 1,
+            normal: 2,
+            priority: 3,
+        };
+        assert_eq!(estimate.at(FeePriority::default()), 2);
+        assert_eq!(FeeEstimate::flat(5).at(FeePriority::Economy), 5);
+
+        let options = FeeOptions {
+            fee_limit: Some(10),
+            ..Default::default()
+        };
+        assert!(options.check_limit(10).is_ok());
+        assert!(options.check_limit(11).is_err());
+        assert!(FeeOptions::default().check_limit(u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn history_smooths_spikes() {
+        assert_eq!(percentile(&[5, 1, 4, 2, 3], 50.0), Some(3));
+        assert_eq!(percentile(&[5, 1, 4, 2, 3], 90.0), Some(5));
+        assert_eq!(percentile(&[], 50.0), None);
+
+        let history = FeeHistory {
+            oldest_block: 100,
+            base_fees: vec![30, 40, 35, 20],
+            gas_used_ratios: vec![0.5, 0.9, 0.2],
+            // One block where someone paid a 10x tip.
+            rewards: vec![vec![1, 2, 4], vec![1, 20, 40], vec![1, 2, 4]],
+        };
+        assert_eq!(history.next_base_fee(), Some(20));
+        assert_eq!(history.base_fee_ceiling(90.0), Some(40));
+        // 2, then 2 + 0.3 * 18 = 7.4, then 7.4 - 0.3 * 5.4 = 5.78.
+        assert_eq!(history.smoothed_tip(FeePriority::Normal), Some(6));
+        assert_eq!(history.smoothed_tip(FeePriority::Economy), Some(1));
+        assert_eq!(
+            FeeHistory::default().smoothed_tip(FeePriority::Normal),
+            None
+        );
+    }
+}
+
+/// What a provider expects an unsigned transaction to cost, from a dry run
+/// that does not broadcast it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxSimulation {
+    /// Fee the sender would pay, in the chain's base unit.
+    pub fee: u64,
+    /// Resources the transaction would consume, by name: `bandwidth` and
+    /// `energy` on Tron, `vsize` (bytes) on UTXO chains.
+    pub resources: BTreeMap<String, u64>,
+}
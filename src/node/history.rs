@@ -0,0 +1,211 @@
+use crate::node::{NodeError, ReadProvider, Transaction};
+
+/// Filter and position for [`ReadProvider::get_transactions_page`](super::ReadProvider::get_transactions_page).
+///
+/// Timestamps are in the unit the provider reports in
+/// [`Transaction::timestamp`] (milliseconds on Tron) and both bounds are
+/// inclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxQuery {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Page size; providers clamp it to what their API allows.
+    pub limit: Option<u32>,
+    /// [`TxPage::next`] of the previous page.
+    pub cursor: Option<String>,
+    pub oldest_first: bool,
+}
+
+impl TxQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn since(mut self, timestamp: u64) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    pub fn until(mut self, timestamp: u64) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Continue after the page that returned `cursor`.
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Return the oldest transactions first, so a consumer can stop after any
+    /// page and resume from the last timestamp it handled.
+    pub fn oldest_first(mut self) -> Self {
+        self.oldest_first = true;
+        self
+    }
+
+    /// Whether `tx` falls inside the time range. Transactions without a
+    /// timestamp (0, e.g. unconfirmed ones) always do.
+    pub fn contains(&self, tx: &Transaction) -> bool {
+        tx.timestamp == 0
+            || (self.since.is_none_or(|since| tx.timestamp >= since)
+                && self.until.is_none_or(|until| tx.timestamp <= until))
+    }
+}
+
+/// One page of an address's history.
+#[derive(Debug, Clone, Default)]
+pub struct TxPage {
+    pub transactions: Vec<Transaction>,
+    /// Cursor for the following page; `None` on the last one.
+    pub next: Option<String>,
+}
+
+/// Point in an address's history to read its balance at, for
+/// [`ReadProvider::get_balance_at`](super::ReadProvider::get_balance_at).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceAt {
+    /// After the block of this height.
+    Block(u64),
+    /// At this time, in the unit of [`Transaction::timestamp`].
+    Timestamp(u64),
+}
+
+impl BalanceAt {
+    /// Whether `tx` happened after this point. Unconfirmed transactions
+    /// always did.
+    pub fn precedes(&self, tx: &Transaction) -> bool {
+        match *self {
+            BalanceAt::Block(block) => tx.block_number == 0 || tx.block_number > block,
+            BalanceAt::Timestamp(timestamp) => tx.timestamp == 0 || tx.timestamp > timestamp,
+        }
+    }
+}
+
+/// `address`'s native balance at `at`, worked back from its current balance
+/// by undoing every transaction after that point.
+///
+/// Exact only if the history lists every movement of the coin with `from`
+/// and `to` filled in, and carries the fees of the address's own sends in
+/// [`details`](Transaction::details); fees it does not know are taken as 0.
+pub async fn balance_from_history<P: ReadProvider + ?Sized>(
+    provider: &P,
+    address: &str,
+    at: BalanceAt,
+) -> Result<String, NodeError> {
+    let balance = provider.get_balance(address).await?;
+    let mut balance: i128 = balance
+        .parse()
+        .map_err(|_| NodeError::Parse(format!("balance {}", balance)))?;
+
+    let mut query = match at {
+        BalanceAt::Timestamp(timestamp) => TxQuery::new().since(timestamp.saturating_add(1)),
+        BalanceAt::Block(_) => TxQuery::new(),
+    };
+    loop {
+        let page = provider.get_transactions_page(address, &query).await?;
+        for tx in page.transactions.iter().filter(|tx| at.precedes(tx)) {
+            balance -= movement(tx, address)?;
+        }
+        match page.next {
+            Some(cursor) => query = query.after(cursor),
+            None => break,
+        }
+    }
+
+    if balance < 0 {
+        return Err(NodeError::Api(format!(
+            "history of {} does not account for its balance",
+            address
+        )));
+    }
+    Ok(balance.to_string())
+}
+
+/// Change `tx` made to `address`'s native balance.
+fn movement(tx: &Transaction, address: &str) -> Result<i128, NodeError> {
+    let value: i128 = if tx.is_failed() || tx.token().is_some() {
+        0
+    } else {
+        tx.value
+            .parse()
+            .map_err(|_| NodeError::Parse(format!("value {} of {}", tx.value, tx.hash)))?
+    };
+    let mut change = 0;
+    if tx.to == address {
+        change += value;
+    }
+    if tx.from == address {
+        change -= value + i128::from(tx.fee().unwrap_or(0));
+    }
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+    use crate::testing::MockProvider;
+
+    #[test]
+    fn time_range_is_inclusive_and_keeps_unconfirmed() {
+        let query = TxQuery::new().since(100).until(200);
+        let at = |timestamp| Transaction {
+            hash: String::new(),
+            from: String::new(),
+            to: String::new(),
+            value: "0".into(),
+            block_number: 0,
+            timestamp,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        };
+        assert!(query.contains(&at(100)));
+        assert!(query.contains(&at(200)));
+        assert!(query.contains(&at(0)));
+        assert!(!query.contains(&at(99)));
+        assert!(!query.contains(&at(201)));
+    }
+
+    #[tokio::test]
+    async fn undoes_later_transactions_to_find_a_past_balance() {
+        let provider = MockProvider::new(6);
+        provider.set_balance("TA", 700);
+        let tx = |hash: &str, from: &str, to: &str, value: &str, block_number| Transaction {
+            hash: hash.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            value: value.to_string(),
+            block_number,
+            timestamp: block_number * 3_000,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        };
+        provider.add_transaction(tx("a", "TB", "TA", "1000", 10));
+        provider.add_transaction(tx("b", "TA", "TB", "400", 20));
+        provider.add_transaction(tx("c", "TB", "TA", "100", 30));
+
+        let at = |point| balance_from_history(&provider, "TA", point);
+        assert_eq!(at(BalanceAt::Block(30)).await.unwrap(), "700");
+        assert_eq!(at(BalanceAt::Block(25)).await.unwrap(), "600");
+        assert_eq!(at(BalanceAt::Timestamp(45_000)).await.unwrap(), "1000");
+        assert_eq!(at(BalanceAt::Block(9)).await.unwrap(), "0");
+    }
+
+    #[tokio::test]
+    async fn default_page_is_the_whole_listing() {
+        let page = OfflineProvider
+            .get_transactions_page("T", &TxQuery::new().limit(10))
+            .await
+            .unwrap();
+        assert!(page.transactions.is_empty());
+        assert_eq!(page.next, None);
+    }
+}
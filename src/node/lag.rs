@@ -0,0 +1,512 @@
+use crate::time::{Duration, Instant};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures_util::future::join_all;
+
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeHistory, FeeOptions, NodeError, Provider, ProviderHealth,
+    RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionDetail,
+    TransactionProvider, TxPage, TxQuery, TxSimulation, TxStatus,
+};
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+/// Warning emitted when a provider's view of the chain diverges from its peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LagEvent {
+    /// Provider `index` is `lag` blocks behind the best known height and is excluded.
+    Lagging {
+        index: usize,
+        height: u64,
+        best_height: u64,
+        lag: u64,
+    },
+    /// A previously excluded provider caught up again.
+    Recovered { index: usize, height: u64 },
+    /// Provider `index` failed to report its height and is excluded for this round.
+    Unreachable { index: usize, error: String },
+}
+
+type LagListener = Arc<dyn Fn(&LagEvent) + Send + Sync>;
+
+/// How long [`LagAwareProvider`] trusts the heights it last compared.
+const DEFAULT_TTL: Duration = Duration::from_secs(1);
+
+/// Provider over several backends for the same chain that refuses to serve data
+/// from a backend lagging more than `max_lag` blocks behind the others.
+///
+/// Calls first compare block heights, asked of every backend at once and
+/// reused for a short TTL, so stale explorer data never feeds balance or
+/// confirmation decisions.
+pub struct LagAwareProvider {
+    providers: Vec<Arc<dyn Provider>>,
+    max_lag: u64,
+    excluded: Mutex<HashSet<usize>>,
+    listener: Option<LagListener>,
+    ttl: Duration,
+    /// Fresh providers as of the last refresh, and when it happened.
+    fresh: Mutex<Option<(Instant, Vec<usize>)>>,
+}
+
+impl LagAwareProvider {
+    pub fn new(providers: Vec<Arc<dyn Provider>>, max_lag: u64) -> Self {
+        Self {
+            providers,
+            max_lag,
+            excluded: Mutex::new(HashSet::new()),
+            listener: None,
+            ttl: DEFAULT_TTL,
+            fresh: Mutex::new(None),
+        }
+    }
+
+    /// Reuse compared heights for `ttl` (one second by default) before
+    /// asking the backends again; zero compares them on every call.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Call `listener` whenever a provider starts or stops lagging.
+    pub fn on_event(mut self, listener: impl Fn(&LagEvent) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Query all heights concurrently and return the indices of providers
+    /// within `max_lag` of the best one.
+    pub async fn refresh(&self) -> Result<Vec<usize>, NodeError> {
+        let results = join_all(self.providers.iter().map(|p| p.get_block_number())).await;
+        let mut heights = Vec::with_capacity(results.len());
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(height) => heights.push((index, Some(height))),
+                Err(e) => {
+                    self.emit(&LagEvent::Unreachable {
+                        index,
+                        error: e.to_string(),
+                    });
+                    heights.push((index, None));
+                }
+            }
+        }
+
+        let best_height = heights
+            .iter()
+            .filter_map(|(_, h)| *h)
+            .max()
+            .ok_or_else(|| NodeError::Network("no provider reported a block height".into()))?;
+
+        let mut fresh = Vec::new();
+        let mut excluded = self.excluded.lock().unwrap();
+        for (index, height) in heights {
+            let Some(height) = height else {
+                continue;
+            };
+            let lag = best_height - height;
+            if lag > self.max_lag {
+                if excluded.insert(index) {
+                    self.emit(&LagEvent::Lagging {
+                        index,
+                        height,
+                        best_height,
+                        lag,
+                    });
+                }
+            } else {
+                if excluded.remove(&index) {
+                    self.emit(&LagEvent::Recovered { index, height });
+                }
+                fresh.push(index);
+            }
+        }
+        *self.fresh.lock().unwrap() = Some((Instant::now(), fresh.clone()));
+        Ok(fresh)
+    }
+
+    async fn fresh_provider(&self) -> Result<&Arc<dyn Provider>, NodeError> {
+        let cached = match &*self.fresh.lock().unwrap() {
+            Some((at, fresh)) if at.elapsed() < self.ttl => Some(fresh.clone()),
+            _ => None,
+        };
+        let fresh = match cached {
+            Some(fresh) => fresh,
+            None => self.refresh().await?,
+        };
+        fresh
+            .first()
+            .map(|&i| &self.providers[i])
+            .ok_or_else(|| NodeError::Network("all providers are lagging".into()))
+    }
+
+    fn emit(&self, event: &LagEvent) {
+        if let Some(listener) = &self.listener {
+            listener(event);
+        }
+    }
+}
+
+#[async_trait]
+impl ReadProvider for LagAwareProvider {
+    fn get_decimals(&self) -> u32 {
+        self.providers
+            .first()
+            .map(|p| p.get_decimals())
+            .unwrap_or(0)
+    }
+
+    /// A lagging provider serves the same chain, so this does not wait for a fresh one.
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        match self.providers.first() {
+            Some(provider) => provider.chain_info().await,
+            None => Ok(ChainInfo::with_decimals(0)),
+        }
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        self.fresh_provider().await?.get_transactions(address).await
+    }
+
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_transactions_page(address, query)
+            .await
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        self.fresh_provider().await?.get_transaction(hash).await
+    }
+
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_transaction_status(hash)
+            .await
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_transaction_detail(hash)
+            .await
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        self.fresh_provider().await?.get_block_number().await
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        self.fresh_provider().await?.get_balance(address).await
+    }
+
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_balance_at(address, at)
+            .await
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_token_balance(address, contract)
+            .await
+    }
+
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        self.fresh_provider()
+            .await?
+            .get_token_balances(address)
+            .await
+    }
+
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        match self.fresh_provider().await {
+            Ok(provider) => provider.get_balances_bulk(addresses).await,
+            Err(e) => addresses.iter().map(|_| Err(e.clone())).collect(),
+        }
+    }
+
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.fresh_provider().await?.list_unspent(address).await
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        self.fresh_provider().await?.health().await
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for LagAwareProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.fresh_provider()
+            .await?
+            .create_transaction(from, to, amount)
+            .await
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        self.fresh_provider()
+            .await?
+            .broadcast_transaction(raw_tx)
+            .await
+    }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        self.fresh_provider()
+            .await?
+            .estimate_fee(from, to, amount)
+            .await
+    }
+
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        self.fresh_provider()
+            .await?
+            .check_resources(from, to, amount)
+            .await
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        self.fresh_provider().await?.fee_history(n_blocks).await
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.fresh_provider()
+            .await?
+            .create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+
+    /// Every provider hears of it: the one that built `raw_tx` may have
+    /// fallen behind since.
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        for provider in &self.providers {
+            provider.discard_transaction(raw_tx).await;
+        }
+    }
+
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        self.fresh_provider()
+            .await?
+            .create_transaction_with_coins(from, to, amount, fees, selection)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.fresh_provider()
+            .await?
+            .create_batch_transaction(from, outputs, fees)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        self.fresh_provider()
+            .await?
+            .simulate_transaction(raw_tx)
+            .await
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        self.fresh_provider()
+            .await?
+            .max_sendable(from, to, fees)
+            .await
+    }
+
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.fresh_provider()
+            .await?
+            .create_sweep_transaction(from, to, amount, fees)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct FixedHeight(u64, &'static str);
+
+    #[async_trait]
+    impl ReadProvider for FixedHeight {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(self.0)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok(self.1.to_string())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for FixedHeight {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_provider_is_skipped_and_reported() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let provider = LagAwareProvider::new(
+            vec![
+                Arc::new(FixedHeight(90, "stale")),
+                Arc::new(FixedHeight(100, "fresh")),
+            ],
+            5,
+        )
+        .on_event(move |e| sink.lock().unwrap().push(e.clone()));
+
+        assert_eq!(provider.get_balance("addr").await.unwrap(), "fresh");
+        provider.refresh().await.unwrap();
+        assert_eq!(provider.get_balance("addr").await.unwrap(), "fresh");
+
+        // The warning fires once, not on every call.
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![LagEvent::Lagging {
+                index: 0,
+                height: 90,
+                best_height: 100,
+                lag: 10
+            }]
+        );
+    }
+
+    /// Answers its height only once every other provider has been asked too.
+    struct Gated {
+        gate: Arc<tokio::sync::Barrier>,
+        asked: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Gated {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            self.asked.fetch_add(1, Ordering::SeqCst);
+            self.gate.wait().await;
+            Ok(100)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("1".to_string())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Gated {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            unimplemented!()
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn asks_every_height_at_once_and_reuses_them_for_the_ttl() {
+        let gate = Arc::new(tokio::sync::Barrier::new(2));
+        let gated = || {
+            Arc::new(Gated {
+                gate: gate.clone(),
+                asked: AtomicUsize::new(0),
+            })
+        };
+        let (first, second) = (gated(), gated());
+        let provider = LagAwareProvider::new(vec![first.clone(), second.clone()], 5)
+            .with_ttl(Duration::from_secs(60));
+
+        // Asked one after the other, the first height would never come back.
+        for _ in 0..3 {
+            let balance =
+                tokio::time::timeout(Duration::from_secs(5), provider.get_balance("addr"));
+            assert_eq!(balance.await.unwrap().unwrap(), "1");
+        }
+        assert_eq!(first.asked.load(Ordering::SeqCst), 1);
+        assert_eq!(second.asked.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn forwards_every_read_to_a_fresh_provider() {
+        use crate::test_utils::{DetailedProvider, assert_forwards_reads};
+
+        let provider = LagAwareProvider::new(vec![Arc::new(DetailedProvider)], 5);
+        assert_forwards_reads(&provider).await;
+    }
+}
@@ -1,11 +1,54 @@
+pub mod amount;
+pub mod cache;
+pub mod details;
+#[cfg(feature = "dev-cache")]
+pub mod dev_cache;
 pub mod error;
+pub mod events;
+pub mod fallback;
+pub mod fee;
+pub mod history;
+pub mod lag;
+#[cfg(feature = "network")]
 pub mod network;
+pub mod nft;
+pub mod price;
+pub mod rate;
+pub mod resolver;
+pub mod swap;
+pub mod token;
 pub mod utils;
 
+use crate::time::{Duration, Instant};
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 
-pub use crate::node::error::NodeError;
+use crate::monitor::confirmations::confirmations;
+use crate::node::utils::AmountFormatter;
+use crate::persist::Persist;
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+pub use crate::node::amount::{Amount, AmountError};
+pub use crate::node::cache::{CacheStats, CacheTtls, CachedProvider, TxCache};
+pub use crate::node::details::TxDetails;
+#[cfg(feature = "dev-cache")]
+pub use crate::node::dev_cache::DevCacheProvider;
+pub use crate::node::error::{NodeError, RpcError};
+pub use crate::node::events::{
+    AddressStream, ContractEvent, ContractEventSource, EventPage, Log, LogFilter, LogSource,
+    TransactionStream,
+};
+pub use crate::node::fallback::{FallbackEvent, FallbackProvider};
+pub use crate::node::fee::{
+    Ema, FEE_HISTORY_PERCENTILES, FeeEstimate, FeeHistory, FeeOptions, FeePriority, TxSimulation,
+    percentile,
+};
+pub use crate::node::history::{BalanceAt, TxPage, TxQuery, balance_from_history};
+pub use crate::node::rate::{RateBudget, RateLimitedProvider};
+pub use crate::node::token::{Allowance, Token, TokenBalance};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -16,31 +59,675 @@ pub struct Transaction {
     pub block_number: u64,
     pub timestamp: u64,
     pub status: String, // "SUCCESS", "FAILED"
+    /// The provider's original entry, kept so the chain can decode [`details`](Self::details).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<serde_json::Value>,
+    /// Chain-specific detail, filled in by `Chain::decode_provider_tx`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<TxDetails>,
+}
+
+impl Transaction {
+    /// Whether the chain reports this transaction as failed or reverted.
+    /// Providers use chain-specific codes (e.g. Tron's `REVERT`, `OUT_OF_ENERGY`).
+    pub fn is_failed(&self) -> bool {
+        !matches!(self.status.as_str(), "SUCCESS" | "PENDING" | "UNKNOWN" | "")
+    }
+
+    /// [`value`](Self::value) as an [`Amount`] of `decimals`, usually the
+    /// provider's [`get_decimals`](ReadProvider::get_decimals).
+    pub fn amount(&self, decimals: u32) -> Result<Amount, NodeError> {
+        Amount::from_base_units(&self.value, decimals)
+    }
+
+    /// Contract of the token this transaction moves, `None` for a transfer of
+    /// the native coin. Known once [`details`](Self::details) are filled in:
+    /// by providers that list token transfers, or by the chain's decoding.
+    pub fn token(&self) -> Option<&str> {
+        match self.details.as_ref()? {
+            TxDetails::Tvm(tvm) if !tvm.token_transfers.is_empty() => {
+                tvm.contract_address.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Fee paid in the chain's base unit, when the decoded details carry it.
+    pub fn fee(&self) -> Option<u64> {
+        match self.details.as_ref()? {
+            TxDetails::Tvm(tvm) => Some(tvm.fee),
+            TxDetails::Utxo(utxo) => utxo.fees,
+        }
+    }
+}
+
+/// Where a transaction stands, from [`ReadProvider::get_transaction_status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// The provider does not know the hash (not yet propagated, or dropped).
+    NotFound,
+    /// Known but not yet in a block.
+    Pending,
+    Confirmed {
+        block_number: u64,
+        confirmations: u64,
+    },
+    /// Included but failed or reverted; `status` is the provider's code.
+    Failed { block_number: u64, status: String },
+}
+
+impl TxStatus {
+    /// Status of `tx` when the chain tip is at `tip`.
+    pub fn of(tx: &Transaction, tip: u64) -> Self {
+        if tx.is_failed() {
+            TxStatus::Failed {
+                block_number: tx.block_number,
+                status: tx.status.clone(),
+            }
+        } else if tx.block_number == 0 {
+            TxStatus::Pending
+        } else {
+            TxStatus::Confirmed {
+                block_number: tx.block_number,
+                confirmations: confirmations(tip, tx.block_number),
+            }
+        }
+    }
+}
+
+/// One transaction looked up by hash, with where it stands, from
+/// [`ReadProvider::get_transaction_detail`]. The provider's own record of it is
+/// kept in `transaction.raw`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionDetail {
+    pub transaction: Transaction,
+    pub status: TxStatus,
+    /// Blocks from the one including it to the tip; 0 while pending.
+    pub confirmations: u64,
+    /// Fee paid in the chain's base unit, where the provider reports it.
+    pub fee: Option<u64>,
+    /// Hash of the block including it, where the provider reports it.
+    pub block_hash: Option<String>,
+}
+
+impl TransactionDetail {
+    /// Detail of `transaction` when the chain tip is at `tip`, with the fee
+    /// its decoded details carry.
+    pub fn new(transaction: Transaction, tip: u64, block_hash: Option<String>) -> Self {
+        let status = TxStatus::of(&transaction, tip);
+        Self {
+            confirmations: match status {
+                TxStatus::Confirmed { confirmations, .. } => confirmations,
+                _ => confirmations(tip, transaction.block_number),
+            },
+            fee: transaction.fee(),
+            status,
+            block_hash,
+            transaction,
+        }
+    }
 }
 
+/// Transaction payload handed from a [`Provider`] to a `Chain` and back.
+///
+/// Each chain documents which variant it accepts; REST backends such as
+/// TronGrid and Blockcypher build and accept [`RawTransaction::Json`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RawTransaction {
+    /// Transaction object as returned by a JSON API.
+    Json(serde_json::Value),
+    /// Serialized wire-format transaction.
+    Bytes(#[serde(with = "serde_bytes")] Vec<u8>),
+    /// BIP-174 partially signed Bitcoin transaction, serialized.
+    Psbt(#[serde(with = "serde_bytes")] Vec<u8>),
+}
+
+/// Unsigned transactions are carried to offline signers and back as records.
+impl Persist for RawTransaction {
+    const KIND: &'static str = "raw_tx";
+    const VERSION: u16 = 1;
+}
+
+impl RawTransaction {
+    /// Variant name, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RawTransaction::Json(_) => "json",
+            RawTransaction::Bytes(_) => "bytes",
+            RawTransaction::Psbt(_) => "psbt",
+        }
+    }
+
+    pub fn as_json(&self) -> Option<&serde_json::Value> {
+        match self {
+            RawTransaction::Json(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Value> for RawTransaction {
+    fn from(value: serde_json::Value) -> Self {
+        RawTransaction::Json(value)
+    }
+}
+
+/// How closely a provider follows the chain tip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncStatus {
+    Synced,
+    /// The newest block the provider knows about is this many seconds old.
+    Stale {
+        seconds_behind: u64,
+    },
+    /// The provider does not expose enough data to tell.
+    Unknown,
+}
+
+/// Requests in flight at once in the default [`ReadProvider::get_balances_bulk`].
+pub const BULK_CONCURRENCY: usize = 8;
+
+/// Snapshot returned by [`ReadProvider::health`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    /// Round-trip time of the probe request.
+    pub latency: Duration,
+    pub block_height: u64,
+    pub sync: SyncStatus,
+}
+
+/// What a provider knows about the chain it serves, from [`ReadProvider::chain_info`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    /// [`Chain::id`](crate::wallet::chain::Chain::id) of the chain, when the
+    /// provider can tell which network its endpoint is on.
+    pub chain_id: Option<String>,
+    /// Ticker of the native coin, e.g. `TRX`.
+    pub symbol: Option<String>,
+    /// Decimal places of the native coin, as in [`ReadProvider::get_decimals`].
+    pub decimals: u32,
+    /// Average time between blocks.
+    pub block_time: Option<Duration>,
+}
+
+impl ChainInfo {
+    /// Only the decimals are known.
+    pub fn with_decimals(decimals: u32) -> Self {
+        Self {
+            chain_id: None,
+            symbol: None,
+            decimals,
+            block_time: None,
+        }
+    }
+
+    /// Render `value`, in base units, with this chain's decimals and symbol.
+    pub fn format_amount(&self, value: &str, formatter: &dyn AmountFormatter) -> String {
+        formatter.format_amount(value, self.decimals, self.symbol.as_deref())
+    }
+}
+
+/// What a node or indexer answers about the chain: balances, history,
+/// blocks and unspent outputs. Monitors, watch-only wallets and portfolio
+/// valuation need nothing more.
 #[async_trait]
-pub trait Provider: Send + Sync {
+pub trait ReadProvider: Send + Sync {
+    /// Decimal places of the native coin, in which balances and amounts are given.
     fn get_decimals(&self) -> u32;
 
+    /// Chain id, native symbol, decimals and block time of the chain served.
+    /// The default knows only [`get_decimals`](Self::get_decimals).
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        Ok(ChainInfo::with_decimals(self.get_decimals()))
+    }
+
     /// Get transactions for a specific address
     async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError>;
 
+    /// One page of `address`'s history within `query`'s time range.
+    ///
+    /// [`get_transactions`](Self::get_transactions) may stop at the provider's
+    /// first page; follow [`TxPage::next`] here to see all of it. The default
+    /// filters and orders `get_transactions` and returns it as a single page.
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        let mut transactions: Vec<_> = self
+            .get_transactions(address)
+            .await?
+            .into_iter()
+            .filter(|tx| query.contains(tx))
+            .collect();
+        if query.oldest_first {
+            transactions.sort_by_key(|tx| tx.timestamp);
+        } else {
+            transactions.sort_by_key(|tx| std::cmp::Reverse(tx.timestamp));
+        }
+        Ok(TxPage {
+            transactions,
+            next: None,
+        })
+    }
+
+    /// Look up one transaction by hash; `Ok(None)` when the provider does not know it.
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        let _ = hash;
+        Err(NodeError::Api(
+            "transaction lookup is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Whether `hash` is unknown, pending, confirmed (and how deeply) or failed.
+    /// The default combines [`get_transaction`](Self::get_transaction) with the tip height.
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        let Some(tx) = self.get_transaction(hash).await? else {
+            return Ok(TxStatus::NotFound);
+        };
+        let tip = if tx.block_number > 0 {
+            self.get_block_number().await?
+        } else {
+            0
+        };
+        Ok(TxStatus::of(&tx, tip))
+    }
+
+    /// [`get_transaction`](Self::get_transaction) with its confirmations,
+    /// fee and block hash. The default knows the fee only from decoded
+    /// details and no block hash; providers whose APIs report them fill them in.
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        let Some(tx) = self.get_transaction(hash).await? else {
+            return Ok(None);
+        };
+        let tip = if tx.block_number > 0 {
+            self.get_block_number().await?
+        } else {
+            0
+        };
+        Ok(Some(TransactionDetail::new(tx, tip, None)))
+    }
+
     /// Get the latest block number
     async fn get_block_number(&self) -> Result<u64, NodeError>;
 
     /// Get the balance of an address
     async fn get_balance(&self, address: &str) -> Result<String, NodeError>;
 
+    /// [`get_balance`](Self::get_balance) as an [`Amount`] of the native
+    /// coin's decimals.
+    async fn get_balance_amount(&self, address: &str) -> Result<Amount, NodeError> {
+        Amount::from_base_units(&self.get_balance(address).await?, self.get_decimals())
+    }
+
+    /// Native balance of `address` as it stood at `at`, e.g. for end-of-day
+    /// statements. Providers ask their API where it keeps past balances; the
+    /// default works it out from the history with [`balance_from_history`].
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        balance_from_history(self, address, at).await
+    }
+
+    /// Balance of the token at `contract` (TRC-20, ERC-20) held by `address`,
+    /// in the token's base units.
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        let _ = (address, contract);
+        Err(NodeError::Api(
+            "token balances are not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Every token `address` holds, as far as the provider can tell: an
+    /// indexer lists what the account has received, a plain node only the
+    /// known [`token::PRESETS`] of its network.
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        let _ = address;
+        Err(NodeError::Api(
+            "token balances are not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Balances of many addresses, in input order. Each entry succeeds or fails on
+    /// its own, so one bad address or throttled request does not lose the rest.
+    ///
+    /// The default issues [`BULK_CONCURRENCY`] `get_balance` calls at a time;
+    /// providers with multi-address endpoints batch instead.
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        // Futures are lazy, so collecting them first still bounds what is in flight.
+        let calls: Vec<_> = addresses.iter().map(|a| self.get_balance(a)).collect();
+        futures_util::stream::iter(calls)
+            .buffered(BULK_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    /// Outputs of `address` not yet spent, confirmed or not, on UTXO chains.
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        let _ = address;
+        Err(NodeError::Api(
+            "unspent outputs are not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Probe the provider: latency, current height and sync status where available.
+    /// The default implementation times a `get_block_number` call.
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        let started = Instant::now();
+        let block_height = self.get_block_number().await?;
+        Ok(ProviderHealth {
+            latency: started.elapsed(),
+            block_height,
+            sync: SyncStatus::Unknown,
+        })
+    }
+}
+
+/// Building, pricing and broadcasting transactions, for sends.
+#[async_trait]
+pub trait TransactionProvider: Send + Sync {
     /// Create a raw transaction (unsigned)
-    /// Returns the raw transaction data (hex or JSON string)
     async fn create_transaction(
         &self,
         from: &str,
         to: &str,
         amount: u64,
-    ) -> Result<String, NodeError>;
+    ) -> Result<RawTransaction, NodeError>;
 
     /// Broadcast a signed transaction
     /// Returns the transaction hash
-    async fn broadcast_transaction(&self, raw_tx: &str) -> Result<String, NodeError>;
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError>;
+
+    /// Fee a transfer of `amount` from `from` to `to` would pay at each priority.
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        let _ = (from, to, amount);
+        Err(NodeError::Api(
+            "fee estimation is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Whether `from` can pay for sending `amount` to `to`, failing with
+    /// [`NodeError::InsufficientResources`] before anything is built if not.
+    /// The default has nothing to check.
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        let _ = (from, to, amount);
+        Ok(())
+    }
+
+    /// [`create_transaction_with_fees`](Self::create_transaction_with_fees),
+    /// funded by the inputs `selection` picks from
+    /// [`list_unspent`](Self::list_unspent) rather than by the provider.
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        let _ = (from, to, amount, fees, selection);
+        Err(NodeError::Api(
+            "coin selection is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// One transaction paying each `(address, amount)` of `outputs`, with
+    /// change back to `from`, on chains whose transactions can have several
+    /// recipients (see [`Chain::output_vsize`](crate::wallet::chain::Chain::output_vsize)).
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        let _ = (from, outputs, fees);
+        Err(NodeError::Api(
+            "batch transactions are not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Most `from` can send to `to` in one transaction with the fee at
+    /// `fees` paid, leaving nothing behind; fails with
+    /// [`NodeError::InsufficientResources`] if the fee takes it all.
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        let _ = (from, to, fees);
+        Err(NodeError::Api(
+            "sweeping is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Transaction sending the `amount` [`max_sendable`](Self::max_sendable)
+    /// returned. The default builds an ordinary transfer, which empties
+    /// account-based chains; UTXO providers spend every coin without change.
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+
+    /// Dry-run `raw_tx` without broadcasting it: the fee and resources it
+    /// would consume, failing where the provider can tell the node would
+    /// reject it.
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        let _ = raw_tx;
+        Err(NodeError::Api(
+            "simulation is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// Base fees and tips of the last `n_blocks` blocks, for networks with a
+    /// fee market that reports them (`eth_feeHistory`).
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        let _ = n_blocks;
+        Err(NodeError::Api(
+            "fee history is not supported by this provider".to_string(),
+        ))
+    }
+
+    /// [`create_transaction`](Self::create_transaction) with fee controls.
+    /// Providers whose network has no fee market ignore `fees`.
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        let _ = fees;
+        self.create_transaction(from, to, amount).await
+    }
+
+    /// `raw_tx`, built by this provider, will not be broadcast (signing it
+    /// failed, or the node refused it), so what building it reserved, such
+    /// as an EVM nonce, can go to the next transaction. The default reserves
+    /// nothing.
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        let _ = raw_tx;
+    }
+}
+
+/// A full provider: reads and transacts, as every network provider in
+/// [`network`] does and [`Wallet::send_coins`](crate::wallet::Wallet::send_coins)
+/// needs. There is nothing to implement: any type with both halves is one.
+pub trait Provider: ReadProvider + TransactionProvider {}
+
+impl<P: ReadProvider + TransactionProvider + ?Sized> Provider for P {}
+
+/// Shared providers are providers, so wrappers generic over `P: Provider` also
+/// take an `Arc<dyn Provider>`.
+#[async_trait]
+impl<P: ReadProvider + ?Sized> ReadProvider for Arc<P> {
+    fn get_decimals(&self) -> u32 {
+        (**self).get_decimals()
+    }
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        (**self).chain_info().await
+    }
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        (**self).get_transactions(address).await
+    }
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        (**self).get_transactions_page(address, query).await
+    }
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        (**self).get_transaction(hash).await
+    }
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        (**self).get_transaction_status(hash).await
+    }
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        (**self).get_transaction_detail(hash).await
+    }
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        (**self).get_block_number().await
+    }
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        (**self).get_balance(address).await
+    }
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        (**self).get_balance_at(address, at).await
+    }
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        (**self).get_token_balance(address, contract).await
+    }
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        (**self).get_token_balances(address).await
+    }
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        (**self).get_balances_bulk(addresses).await
+    }
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        (**self).list_unspent(address).await
+    }
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        (**self).health().await
+    }
+}
+
+#[async_trait]
+impl<P: TransactionProvider + ?Sized> TransactionProvider for Arc<P> {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        (**self).create_transaction(from, to, amount).await
+    }
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        (**self).broadcast_transaction(raw_tx).await
+    }
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        (**self).estimate_fee(from, to, amount).await
+    }
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        (**self).check_resources(from, to, amount).await
+    }
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        (**self).fee_history(n_blocks).await
+    }
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        (**self)
+            .create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        (**self).discard_transaction(raw_tx).await
+    }
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        (**self).max_sendable(from, to, fees).await
+    }
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        (**self).simulate_transaction(raw_tx).await
+    }
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        (**self)
+            .create_sweep_transaction(from, to, amount, fees)
+            .await
+    }
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        (**self)
+            .create_transaction_with_coins(from, to, amount, fees, selection)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        (**self).create_batch_transaction(from, outputs, fees).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+
+    #[tokio::test]
+    async fn default_health_reports_height() {
+        let health = OfflineProvider.health().await.expect("health");
+        assert_eq!(health.block_height, 0);
+        assert_eq!(health.sync, SyncStatus::Unknown);
+    }
 }
@@ -0,0 +1,184 @@
+//! Fiat prices from CoinGecko's `/simple/price` (feature `coingecko`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use crate::metrics::Metrics;
+use crate::node::NodeError;
+use crate::node::network::diagnostics::{DiagnosticsSink, read_json};
+use crate::node::network::http::{default_client, rate_limited, send};
+use crate::node::price::PriceProvider;
+
+const COINGECKO_PUBLIC: &str = "https://api.coingecko.com/api/v3";
+const COINGECKO_PRO: &str = "https://pro-api.coingecko.com/api/v3";
+
+/// CoinGecko coin ids of the symbols the crate's chains and token presets use.
+const DEFAULT_COIN_IDS: &[(&str, &str)] = &[
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("LTC", "litecoin"),
+    ("TRX", "tron"),
+    ("USDC", "usd-coin"),
+    ("USDT", "tether"),
+];
+
+/// [`PriceProvider`] over CoinGecko. Symbols are mapped to CoinGecko coin
+/// ids, which are what its API prices; add others with
+/// [`with_coin_id`](Self::with_coin_id).
+pub struct CoinGeckoPrices {
+    client: Client,
+    base_url: String,
+    /// Header and value of the API key, if any.
+    api_key: Option<(&'static str, String)>,
+    coin_ids: HashMap<String, String>,
+    diagnostics: Option<Arc<dyn DiagnosticsSink>>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl Default for CoinGeckoPrices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinGeckoPrices {
+    /// The public API, without a key.
+    pub fn new() -> Self {
+        Self::with_url(COINGECKO_PUBLIC.to_string())
+    }
+
+    pub fn with_url(url: String) -> Self {
+        Self {
+            client: default_client(),
+            base_url: url,
+            api_key: None,
+            coin_ids: DEFAULT_COIN_IDS
+                .iter()
+                .map(|(symbol, id)| (symbol.to_string(), id.to_string()))
+                .collect(),
+            diagnostics: None,
+            metrics: None,
+        }
+    }
+
+    /// The public API with a free demo plan key.
+    pub fn demo(api_key: &str) -> Self {
+        let mut prices = Self::new();
+        prices.api_key = Some(("x-cg-demo-api-key", api_key.to_string()));
+        prices
+    }
+
+    /// The paid API with its key.
+    pub fn pro(api_key: &str) -> Self {
+        let mut prices = Self::with_url(COINGECKO_PRO.to_string());
+        prices.api_key = Some(("x-cg-pro-api-key", api_key.to_string()));
+        prices
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Price `symbol` as the CoinGecko coin `id`, e.g. `("BNB", "binancecoin")`.
+    pub fn with_coin_id(mut self, symbol: &str, id: &str) -> Self {
+        self.coin_ids.insert(symbol.to_uppercase(), id.to_string());
+        self
+    }
+
+    /// Hand responses that fail to parse to `sink` as well as returning
+    /// them in the error.
+    pub fn with_diagnostics(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.diagnostics = Some(sink);
+        self
+    }
+
+    /// Report every request's endpoint, status and latency to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinGeckoPrices {
+    /// One `/simple/price` request for all of `symbols` CoinGecko has an id for.
+    async fn get_prices(
+        &self,
+        symbols: &[&str],
+        fiat: &str,
+    ) -> Result<BTreeMap<String, f64>, NodeError> {
+        let ids: BTreeMap<String, String> = symbols
+            .iter()
+            .filter_map(|symbol| {
+                let symbol = symbol.to_uppercase();
+                let id = self.coin_ids.get(&symbol)?.clone();
+                Some((id, symbol))
+            })
+            .collect();
+        if ids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        // https://docs.coingecko.com/reference/simple-price
+        let fiat = fiat.to_lowercase();
+        let joined = ids.keys().cloned().collect::<Vec<_>>().join(",");
+        let mut request = self
+            .client
+            .get(format!("{}/simple/price", self.base_url))
+            .query(&[("ids", joined.as_str()), ("vs_currencies", fiat.as_str())]);
+        if let Some((header, key)) = &self.api_key {
+            request = request.header(*header, key);
+        }
+        let resp = send(request, "coingecko", self.metrics.as_deref()).await?;
+        if let Some(e) = rate_limited(&resp) {
+            return Err(e);
+        }
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
+        Ok(prices_from_json(&body, &ids, &fiat))
+    }
+}
+
+/// Prices in a `/simple/price` response, `{"tron": {"usd": 0.2}}`, keyed by
+/// the symbols `ids` maps each coin id to.
+fn prices_from_json(
+    body: &serde_json::Value,
+    ids: &BTreeMap<String, String>,
+    fiat: &str,
+) -> BTreeMap<String, f64> {
+    ids.iter()
+        .filter_map(|(id, symbol)| Some((symbol.clone(), body[id][fiat].as_f64()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_simple_prices_back_to_symbols() {
+        let ids: BTreeMap<String, String> = [
+            ("tron".to_string(), "TRX".to_string()),
+            ("tether".to_string(), "USDT".to_string()),
+            ("litecoin".to_string(), "LTC".to_string()),
+        ]
+        .into();
+        let body = serde_json::json!({
+            "tron": { "usd": 0.21 },
+            "tether": { "usd": 1.0, "eur": 0.92 },
+            "litecoin": {},
+        });
+
+        let prices = prices_from_json(&body, &ids, "usd");
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices["TRX"], 0.21);
+        assert_eq!(prices["USDT"], 1.0);
+    }
+}
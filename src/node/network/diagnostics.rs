@@ -0,0 +1,180 @@
+//! Capture of responses a provider could not parse.
+//!
+//! Third-party APIs change shape without notice, and a bare serde message
+//! ("expected value at line 1 column 1") does not say whether the endpoint
+//! returned an HTML error page, a renamed field or a rate-limit notice. HTTP
+//! providers therefore keep the offending body: scrubbed of anything that
+//! looks like a credential, cut to [`MAX_CAPTURED_BODY`] bytes, in the
+//! returned [`NodeError::UnexpectedResponse`] and, if one is configured, in a
+//! [`DiagnosticsSink`].
+
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+
+use crate::node::NodeError;
+use crate::node::network::http::HttpResponse;
+
+/// Longest body kept, in bytes.
+pub const MAX_CAPTURED_BODY: usize = 1024;
+
+/// Names whose values are replaced when a body is captured, matched
+/// case-insensitively anywhere in a key (`api_key`, `X-Api-Key`, ...).
+const SECRET_NAMES: [&str; 7] = [
+    "key",
+    "secret",
+    "password",
+    "passphrase",
+    "private",
+    "authorization",
+    "access_token",
+];
+
+/// A response that failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedResponse {
+    /// Scheme and host only: paths and queries often carry API keys.
+    pub endpoint: String,
+    pub status: u16,
+    /// What the parser reported.
+    pub error: String,
+    /// Scrubbed and truncated body.
+    pub body: String,
+}
+
+/// Receives every [`CapturedResponse`] of the providers it is given to,
+/// e.g. to log them or keep the last few for a support page.
+pub trait DiagnosticsSink: Send + Sync {
+    fn record(&self, response: &CapturedResponse);
+}
+
+/// Read `response` as JSON, capturing the body if it does not parse.
+pub(crate) fn read_json<T: DeserializeOwned>(
+    response: HttpResponse,
+    sink: Option<&dyn DiagnosticsSink>,
+) -> Result<T, NodeError> {
+    let endpoint = origin(response.url());
+    let status = response.status().as_u16();
+    let bytes = response.body();
+    serde_json::from_slice(bytes).map_err(|e| {
+        let captured = CapturedResponse {
+            endpoint,
+            status,
+            error: e.to_string(),
+            body: truncate(scrub(&String::from_utf8_lossy(bytes))),
+        };
+        if let Some(sink) = sink {
+            sink.record(&captured);
+        }
+        NodeError::UnexpectedResponse {
+            status,
+            error: captured.error,
+            body: captured.body,
+        }
+    })
+}
+
+pub(crate) fn origin(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}://{}:{}", url.scheme(), host, port),
+        (Some(host), None) => format!("{}://{}", url.scheme(), host),
+        _ => url.scheme().to_string(),
+    }
+}
+
+/// Replace the value after every secret-looking name, in JSON (`"api_key":
+/// "..."`) as well as in query strings and headers echoed back in error
+/// pages (`apikey=...`, `Authorization: ...`).
+fn scrub(body: &str) -> String {
+    let lower = body.to_ascii_lowercase();
+    let mut out = String::with_capacity(body.len());
+    let mut copied = 0;
+    let mut search = 0;
+    while let Some((at, name)) = SECRET_NAMES
+        .iter()
+        .filter_map(|name| lower[search..].find(name).map(|i| (search + i, name.len())))
+        .min()
+    {
+        let after_name = at + name;
+        let value_start = after_name
+            + body[after_name..]
+                .find(|c: char| !matches!(c, '"' | '\'' | ':' | '=' | ' '))
+                .unwrap_or(body.len() - after_name);
+        let value_end = value_start
+            + body[value_start..]
+                .find(|c: char| {
+                    matches!(c, '"' | '\'' | '&' | ',' | '}' | ']' | '<' | '{' | '[')
+                        || c.is_whitespace()
+                })
+                .unwrap_or(body.len() - value_start);
+        // Only a name followed by a separator introduces a value; `keys` or
+        // `keyword` do not.
+        if value_start > after_name && value_end > value_start {
+            out.push_str(&body[copied..value_start]);
+            out.push_str("[redacted]");
+            copied = value_end;
+            search = value_end;
+        } else {
+            search = after_name;
+        }
+    }
+    out.push_str(&body[copied..]);
+    out
+}
+
+fn truncate(mut body: String) -> String {
+    if body.len() > MAX_CAPTURED_BODY {
+        let mut end = MAX_CAPTURED_BODY;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push_str("...");
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::node::network::http::send;
+    use crate::test_utils::http::{Reply, serve};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<CapturedResponse>>);
+
+    impl DiagnosticsSink for Recorder {
+        fn record(&self, response: &CapturedResponse) {
+            self.0.lock().unwrap().push(response.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn captures_scrubbed_body_of_unparseable_responses() {
+        let body = format!(
+            "<html>upstream echoed apikey=abc123&x=1 \"private_key\": \"deadbeef\"{}</html>",
+            "!".repeat(2000)
+        );
+        let (url, _server) = serve(vec![Reply::new(200, body)]).await;
+        let url = format!("{}/v3/project-secret?apikey=abc", url);
+
+        let recorder = Recorder::default();
+        let response = send(reqwest::Client::new().get(&url), "test", None)
+            .await
+            .unwrap();
+        let error = read_json::<serde_json::Value>(response, Some(&recorder)).unwrap_err();
+        let NodeError::UnexpectedResponse { status, body, .. } = error else {
+            panic!("expected the body to be captured, got {:?}", error);
+        };
+        assert_eq!(status, 200);
+        assert!(body.starts_with(
+            "<html>upstream echoed apikey=[redacted]&x=1 \"private_key\": \"[redacted]\""
+        ));
+        assert_eq!(body.len(), MAX_CAPTURED_BODY + 3);
+
+        let captured = recorder.0.lock().unwrap();
+        assert_eq!(captured[0].body, body);
+        assert!(!captured[0].endpoint.contains("secret"));
+    }
+}
@@ -0,0 +1,842 @@
+use crate::metrics::Metrics;
+use crate::node::events::{Log, LogFilter, LogSource};
+use crate::node::network::diagnostics::DiagnosticsSink;
+use crate::node::network::nonce::NonceManager;
+use crate::node::network::rpc::RpcClient;
+use crate::node::swap::HtlcCall;
+use crate::node::token::{
+    Allowance, TokenMetadata, TokenMetadataSource, TokenPreset, USDT_ETHEREUM, decode_abi_decimals,
+    decode_abi_string,
+};
+use crate::node::{
+    ChainInfo, FEE_HISTORY_PERCENTILES, FeeEstimate, FeeHistory, FeeOptions, FeePriority,
+    NodeError, RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionProvider, token,
+};
+use crate::wallet::chain::sdk::be_bytes_to_decimal;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Selector of `transfer(address,uint256)`.
+const TRANSFER_SELECTOR: &str = "a9059cbb";
+/// Selector of `balanceOf(address)`.
+const BALANCE_OF_SELECTOR: &str = "70a08231";
+/// Selectors of `approve(address,uint256)` and `allowance(address,address)`.
+const APPROVE_SELECTOR: &str = "095ea7b3";
+const ALLOWANCE_SELECTOR: &str = "dd62ed3e";
+/// Selectors of the ERC-20 metadata getters `symbol()`, `name()` and `decimals()`.
+const SYMBOL_SELECTOR: &str = "95d89b41";
+const NAME_SELECTOR: &str = "06fdde03";
+const DECIMALS_SELECTOR: &str = "313ce567";
+/// Tip offered when the node does not implement `eth_maxPriorityFeePerGas` (1 gwei).
+const DEFAULT_PRIORITY_FEE: u128 = 1_000_000_000;
+/// Blocks of `eth_feeHistory` fee suggestions are drawn from.
+const FEE_HISTORY_BLOCKS: u32 = 20;
+/// Calls per JSON-RPC batch; hosted nodes commonly reject larger batches.
+const RPC_BATCH_LIMIT: usize = 100;
+
+/// Ethereum (or any EVM network) over standard JSON-RPC.
+///
+/// Plain JSON-RPC has no per-address index, so [`ReadProvider::get_transactions`]
+/// is not supported; pair with an indexer for history.
+///
+/// Nonces come from a [`NonceManager`], so concurrent sends from one address
+/// get consecutive nonces rather than the same one.
+pub struct EthProvider {
+    rpc: RpcClient,
+    nonces: Arc<NonceManager>,
+}
+
+/// Gas parameters for an EIP-1559 transaction, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthFees {
+    pub gas: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+}
+
+/// What the network currently charges, in wei per gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FeeMarket {
+    /// Base fee of the next block.
+    pub base_fee: u128,
+    /// Base fee `maxFeePerGas` budgets for: at least `base_fee`, higher when
+    /// recent blocks were dearer.
+    pub max_base_fee: u128,
+    /// Tip per [`FeePriority`], economy first.
+    pub tips: [u128; 3],
+}
+
+impl FeeMarket {
+    pub fn tip(&self, priority: FeePriority) -> u128 {
+        match priority {
+            FeePriority::Economy => self.tips[0],
+            FeePriority::Normal => self.tips[1],
+            FeePriority::Priority => self.tips[2],
+        }
+    }
+
+    /// `maxFeePerGas` for `priority`: twice the budgeted base fee plus the
+    /// tip, enough to stay valid through several full blocks.
+    pub fn max_fee(&self, priority: FeePriority) -> u128 {
+        self.max_base_fee * 2 + self.tip(priority)
+    }
+
+    fn from_history(history: &FeeHistory) -> Option<Self> {
+        let tip = |priority| history.smoothed_tip(priority);
+        Some(Self {
+            base_fee: history.next_base_fee()?,
+            max_base_fee: history.base_fee_ceiling(90.0)?,
+            tips: [
+                tip(FeePriority::Economy)?,
+                tip(FeePriority::Normal)?,
+                tip(FeePriority::Priority)?,
+            ],
+        })
+    }
+}
+
+impl EthFees {
+    /// Most the transaction can cost in gas, in wei.
+    pub fn max_cost(&self) -> u128 {
+        self.gas as u128 * self.max_fee_per_gas
+    }
+}
+
+/// EIP-1559 fee caps suggested from recent blocks, in wei per gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    /// Base fee of the next block.
+    pub base_fee: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+}
+
+impl EthProvider {
+    pub fn new(rpc_url: &str) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url),
+            nonces: Arc::default(),
+        }
+    }
+
+    /// Take nonces from `nonces`, shared with other providers sending from
+    /// the same accounts, e.g. ones for other RPC endpoints of the network.
+    pub fn with_nonce_manager(mut self, nonces: Arc<NonceManager>) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
+    /// Send requests through `client`, e.g. one from [`HttpConfig::build`](crate::node::network::HttpConfig::build)
+    /// with other timeouts or a proxy.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.rpc = self.rpc.with_client(client);
+        self
+    }
+
+    /// Header carrying the active [`CorrelationId`](crate::correlation::CorrelationId).
+    /// Pass `None` to stop forwarding correlation ids to the upstream API.
+    pub fn with_correlation_header(mut self, header: Option<&str>) -> Self {
+        self.rpc = self.rpc.with_correlation_header(header);
+        self
+    }
+
+    /// Capture unparseable responses; see [`RpcClient::with_diagnostics`].
+    pub fn with_diagnostics(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.rpc = self.rpc.with_diagnostics(sink);
+        self
+    }
+
+    /// Report request figures to `metrics`; see [`RpcClient::with_metrics`].
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.rpc = self.rpc.with_metrics(metrics);
+        self
+    }
+
+    /// Retry transient failures; see [`RpcClient::with_retries`].
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.rpc = self.rpc.with_retries(retries, delay);
+        self
+    }
+
+    /// EIP-155 chain id reported by the node.
+    pub async fn chain_id(&self) -> Result<u64, NodeError> {
+        let result = self.rpc("eth_chainId", json!([])).await?;
+        Ok(parse_quantity(&result)? as u64)
+    }
+
+    /// Network name matching [`EvmChain::name`](crate::wallet::chain::EvmChain) for known chain ids.
+    pub async fn network(&self) -> Result<Option<&'static str>, NodeError> {
+        Ok(match self.chain_id().await? {
+            1 => Some("ethereum"),
+            11_155_111 => Some("sepolia"),
+            _ => None,
+        })
+    }
+
+    /// USDT contract on this provider's network.
+    pub async fn usdt(&self) -> Result<Option<TokenPreset>, NodeError> {
+        Ok(match self.network().await? {
+            Some("ethereum") => Some(USDT_ETHEREUM),
+            _ => None,
+        })
+    }
+
+    /// Nonce for the next transaction from `address`, counting pending ones.
+    pub async fn get_transaction_count(&self, address: &str) -> Result<u64, NodeError> {
+        let result = self
+            .rpc("eth_getTransactionCount", json!([address, "pending"]))
+            .await?;
+        Ok(parse_quantity(&result)? as u64)
+    }
+
+    /// Gas limit and EIP-1559 fees for `call`. Tips are smoothed over recent
+    /// blocks and `maxFeePerGas` allows for the base fee doubling, so one
+    /// congested block does not swing the fees chosen.
+    pub async fn estimate_fees(&self, call: &Value) -> Result<EthFees, NodeError> {
+        self.estimate_fees_at(call, FeePriority::Normal).await
+    }
+
+    /// [`estimate_fees`](Self::estimate_fees) with the tip scaled for `priority`.
+    pub async fn estimate_fees_at(
+        &self,
+        call: &Value,
+        priority: FeePriority,
+    ) -> Result<EthFees, NodeError> {
+        let gas = self.estimate_gas(call).await?;
+        let market = self.fee_market().await?;
+        Ok(EthFees {
+            gas,
+            max_priority_fee_per_gas: market.tip(priority),
+            max_fee_per_gas: market.max_fee(priority),
+        })
+    }
+
+    /// Fee caps for `priority`, the tip smoothed over recent blocks as in
+    /// [`estimate_fees`](Self::estimate_fees).
+    pub async fn suggest_fees(&self, priority: FeePriority) -> Result<FeeSuggestion, NodeError> {
+        let market = self.fee_market().await?;
+        Ok(FeeSuggestion {
+            base_fee: market.base_fee,
+            max_priority_fee_per_gas: market.tip(priority),
+            max_fee_per_gas: market.max_fee(priority),
+        })
+    }
+
+    async fn estimate_gas(&self, call: &Value) -> Result<u64, NodeError> {
+        Ok(parse_quantity(&self.rpc("eth_estimateGas", json!([call])).await?)? as u64)
+    }
+
+    /// Base fees and tips of the last `n_blocks` blocks, via `eth_feeHistory`.
+    pub async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        let result = self
+            .rpc(
+                "eth_feeHistory",
+                json!([
+                    format!("{:#x}", n_blocks),
+                    "latest",
+                    FEE_HISTORY_PERCENTILES
+                ]),
+            )
+            .await?;
+        let quantities = |value: Option<&Value>| -> Result<Vec<u128>, NodeError> {
+            value
+                .and_then(Value::as_array)
+                .map(|items| items.iter().map(parse_quantity).collect())
+                .unwrap_or(Ok(Vec::new()))
+        };
+        Ok(FeeHistory {
+            oldest_block: parse_quantity(&result["oldestBlock"])? as u64,
+            base_fees: quantities(result.get("baseFeePerGas"))?,
+            gas_used_ratios: result["gasUsedRatio"]
+                .as_array()
+                .map(|ratios| ratios.iter().filter_map(Value::as_f64).collect())
+                .unwrap_or_default(),
+            rewards: result["reward"]
+                .as_array()
+                .map(|blocks| blocks.iter().map(|b| quantities(Some(b))).collect())
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Fees from [`fee_history`](Self::fee_history), or from the latest block
+    /// and `eth_maxPriorityFeePerGas` on nodes without it.
+    pub(crate) async fn fee_market(&self) -> Result<FeeMarket, NodeError> {
+        match self.fee_history(FEE_HISTORY_BLOCKS).await {
+            Ok(history) => {
+                if let Some(market) = FeeMarket::from_history(&history) {
+                    return Ok(market);
+                }
+            }
+            Err(NodeError::Api(_) | NodeError::Rpc { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        let tip = match self.rpc("eth_maxPriorityFeePerGas", json!([])).await {
+            Ok(value) => parse_quantity(&value)?,
+            Err(NodeError::Api(_) | NodeError::Rpc { .. }) => DEFAULT_PRIORITY_FEE,
+            Err(e) => return Err(e),
+        };
+        let block = self
+            .rpc("eth_getBlockByNumber", json!(["latest", false]))
+            .await?;
+        let base_fee = block
+            .get("baseFeePerGas")
+            .map(parse_quantity)
+            .transpose()?
+            .ok_or_else(|| NodeError::Api("node does not report baseFeePerGas".to_string()))?;
+        Ok(FeeMarket {
+            base_fee,
+            max_base_fee: base_fee,
+            tips: [
+                scale_tip(tip, FeePriority::Economy),
+                tip,
+                scale_tip(tip, FeePriority::Priority),
+            ],
+        })
+    }
+
+    /// Logs matching `filter`, via `eth_getLogs`. Nodes cap the block range
+    /// (often at a few thousand blocks) or the result count; narrow the range
+    /// if they refuse.
+    pub async fn get_logs(&self, filter: &LogFilter) -> Result<Vec<Log>, NodeError> {
+        let result = self.rpc("eth_getLogs", json!([filter.to_json()])).await?;
+        result
+            .as_array()
+            .ok_or_else(|| NodeError::Parse("eth_getLogs: expected an array".to_string()))?
+            .iter()
+            .map(Log::from_json)
+            .collect()
+    }
+
+    /// ERC-20 `balanceOf(owner)` in the token's base unit.
+    pub async fn erc20_balance(&self, owner: &str, contract: &str) -> Result<u128, NodeError> {
+        let data = format!("0x{}{}", BALANCE_OF_SELECTOR, abi_address(owner)?);
+        let result = self
+            .rpc(
+                "eth_call",
+                json!([{ "to": contract, "data": data }, "latest"]),
+            )
+            .await?;
+        parse_quantity(&result)
+    }
+
+    /// ERC-20 `allowance(owner, spender)`: what `spender` may move of `owner`'s tokens.
+    pub async fn erc20_allowance(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+    ) -> Result<Allowance, NodeError> {
+        let data = format!(
+            "0x{}{}{}",
+            ALLOWANCE_SELECTOR,
+            abi_address(owner)?,
+            abi_address(spender)?
+        );
+        let result = self
+            .rpc(
+                "eth_call",
+                json!([{ "to": contract, "data": data }, "latest"]),
+            )
+            .await?;
+        Allowance::from_word(&quantity_bytes(&result)?)
+    }
+
+    /// Gas and fees an ERC-20 `approve(spender, amount)` from `owner` would need.
+    pub async fn estimate_erc20_approve(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+        amount: Allowance,
+    ) -> Result<EthFees, NodeError> {
+        let data = format!("0x{}", abi_approve(spender, amount)?);
+        self.estimate_fees(&call_object(owner, contract, 0, &data))
+            .await
+    }
+
+    /// Build an unsigned ERC-20 `approve(spender, amount)` from `owner` paying `fees`.
+    pub async fn create_erc20_approve(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+        amount: Allowance,
+        fees: &EthFees,
+    ) -> Result<RawTransaction, NodeError> {
+        let data = format!("0x{}", abi_approve(spender, amount)?);
+        self.build_transaction(call_object(owner, contract, 0, &data), fees, None)
+            .await
+    }
+
+    /// Gas and fees an ERC-20 `transfer(to, amount)` from `from` would need.
+    pub async fn estimate_erc20_transfer(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: u128,
+    ) -> Result<EthFees, NodeError> {
+        let data = format!("0x{}", abi_transfer(to, amount)?);
+        self.estimate_fees(&call_object(from, contract, 0, &data))
+            .await
+    }
+
+    /// Build an unsigned ERC-20 `transfer(to, amount)` from `from` paying `fees`.
+    pub async fn create_erc20_transfer(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: u128,
+        fees: &EthFees,
+    ) -> Result<RawTransaction, NodeError> {
+        let data = format!("0x{}", abi_transfer(to, amount)?);
+        self.build_transaction(call_object(from, contract, 0, &data), fees, None)
+            .await
+    }
+
+    /// Gas and fees a `HashedTimelock` call from `from` sending `value` would need.
+    pub async fn estimate_htlc_call(
+        &self,
+        from: &str,
+        contract: &str,
+        call: &HtlcCall,
+        value: u128,
+    ) -> Result<EthFees, NodeError> {
+        self.estimate_fees(&call_object(from, contract, value, &call.evm_data()))
+            .await
+    }
+
+    /// Build an unsigned `HashedTimelock` call from `from`; `value` is the
+    /// amount [`HtlcCall::NewContract`] locks and zero otherwise.
+    pub async fn create_htlc_call(
+        &self,
+        from: &str,
+        contract: &str,
+        call: &HtlcCall,
+        value: u128,
+        fees: &EthFees,
+    ) -> Result<RawTransaction, NodeError> {
+        self.build_transaction(
+            call_object(from, contract, value, &call.evm_data()),
+            fees,
+            None,
+        )
+        .await
+    }
+
+    /// Add nonce, chain id and fees to `call`, giving the JSON `EvmChain` signs.
+    /// The nonce is `nonce`, replacing a pending transaction, or the next free one.
+    async fn build_transaction(
+        &self,
+        mut call: Value,
+        fees: &EthFees,
+        nonce: Option<u64>,
+    ) -> Result<RawTransaction, NodeError> {
+        let from = call["from"].as_str().unwrap_or_default().to_string();
+        let chain_id = self.chain_id().await?;
+        let nonce = match nonce {
+            Some(nonce) => {
+                self.nonces.replace(&from, nonce, fees);
+                nonce
+            }
+            None => {
+                let pending = self.get_transaction_count(&from).await?;
+                self.nonces.reserve(&from, pending, fees)
+            }
+        };
+        call["nonce"] = format!("{:#x}", nonce).into();
+        call["chainId"] = format!("{:#x}", chain_id).into();
+        call["maxPriorityFeePerGas"] = format!("{:#x}", fees.max_priority_fee_per_gas).into();
+        call["maxFeePerGas"] = format!("{:#x}", fees.max_fee_per_gas).into();
+        call["gas"] = format!("{:#x}", fees.gas).into();
+        Ok(RawTransaction::Json(call))
+    }
+
+    pub(crate) async fn rpc(&self, method: &str, params: Value) -> Result<Value, NodeError> {
+        self.rpc.call(method, params).await
+    }
+
+    /// One JSON-RPC batch calling `method` once per entry of `params`.
+    async fn rpc_batch(&self, method: &str, params: Vec<Value>) -> Vec<Result<Value, NodeError>> {
+        self.rpc.batch(method, params).await
+    }
+}
+
+#[async_trait]
+impl ReadProvider for EthProvider {
+    fn get_decimals(&self) -> u32 {
+        18
+    }
+
+    /// Asks the node for its chain id; symbol and block time are only known
+    /// for the networks [`network`](EthProvider::network) recognizes.
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        let network = self.network().await?;
+        Ok(ChainInfo {
+            chain_id: network.map(str::to_string),
+            symbol: network.map(|_| "ETH".to_string()),
+            decimals: 18,
+            block_time: network.map(|_| Duration::from_secs(12)),
+        })
+    }
+
+    async fn get_transactions(&self, _address: &str) -> Result<Vec<Transaction>, NodeError> {
+        Err(NodeError::Api(
+            "JSON-RPC does not index transactions by address".to_string(),
+        ))
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        let tx = self.rpc("eth_getTransactionByHash", json!([hash])).await?;
+        if tx.is_null() {
+            return Ok(None);
+        }
+        let (block_number, timestamp, status) = if tx["blockNumber"].is_null() {
+            (0, 0, "PENDING")
+        } else {
+            let receipt = self.rpc("eth_getTransactionReceipt", json!([hash])).await?;
+            let block = self
+                .rpc("eth_getBlockByNumber", json!([tx["blockNumber"], false]))
+                .await?;
+            let status = match receipt["status"].as_str() {
+                Some("0x1") => "SUCCESS",
+                Some("0x0") => "FAILED",
+                _ => "UNKNOWN",
+            };
+            (
+                parse_quantity(&tx["blockNumber"])? as u64,
+                parse_quantity(&block["timestamp"])? as u64,
+                status,
+            )
+        };
+        Ok(Some(Transaction {
+            hash: hash.to_string(),
+            from: tx["from"].as_str().unwrap_or_default().to_string(),
+            // Contract creations have no recipient.
+            to: tx["to"].as_str().unwrap_or_default().to_string(),
+            value: be_bytes_to_decimal(&quantity_bytes(&tx["value"])?),
+            block_number,
+            timestamp,
+            status: status.to_string(),
+            raw: Some(tx),
+            details: None,
+        }))
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        let result = self.rpc("eth_blockNumber", json!([])).await?;
+        Ok(parse_quantity(&result)? as u64)
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        // Balances can exceed u128 in principle; keep full precision in wei.
+        let result = self
+            .rpc("eth_getBalance", json!([address, "latest"]))
+            .await?;
+        Ok(be_bytes_to_decimal(&quantity_bytes(&result)?))
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        Ok(self.erc20_balance(address, contract).await?.to_string())
+    }
+
+    /// Non-zero balances of the known tokens of this network: a node cannot
+    /// list the contracts an account has touched.
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        let Some(network) = self.network().await? else {
+            return Ok(Vec::new());
+        };
+        let mut balances = Vec::new();
+        for preset in token::PRESETS.iter().filter(|t| t.network == network) {
+            let balance = self.erc20_balance(address, preset.contract).await?;
+            if balance > 0 {
+                balances.push(TokenBalance::new(
+                    Some(network),
+                    preset.contract,
+                    balance.to_string(),
+                ));
+            }
+        }
+        Ok(balances)
+    }
+
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        let mut balances = Vec::with_capacity(addresses.len());
+        for batch in addresses.chunks(RPC_BATCH_LIMIT) {
+            let params = batch.iter().map(|a| json!([a, "latest"])).collect();
+            let results = self.rpc_batch("eth_getBalance", params).await;
+            balances.extend(
+                results
+                    .into_iter()
+                    .map(|result| Ok(be_bytes_to_decimal(&quantity_bytes(&result?)?))),
+            );
+        }
+        balances
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for EthProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        // `amount` is in wei.
+        let call = call_object(from, to, amount as u128, "0x");
+        let fees = self.estimate_fees(&call).await?;
+        self.build_transaction(call, &fees, None).await
+    }
+
+    /// Expected cost at the current base fee; the signed `maxFeePerGas` allows for
+    /// the base fee doubling before inclusion.
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        let gas = self
+            .estimate_gas(&call_object(from, to, amount as u128, "0x"))
+            .await? as u128;
+        let market = self.fee_market().await?;
+        let cost = |priority| {
+            u64::try_from(gas * (market.base_fee + market.tip(priority))).unwrap_or(u64::MAX)
+        };
+        Ok(FeeEstimate {
+            economy: cost(FeePriority::Economy),
+            normal: cost(FeePriority::Normal),
+            priority: cost(FeePriority::Priority),
+        })
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        EthProvider::fee_history(self, n_blocks).await
+    }
+
+    /// `fee_rate` sets `maxFeePerGas` and `priority_fee` `maxPriorityFeePerGas`
+    /// in wei; `fee_limit` caps gas × `maxFeePerGas`. A `nonce` in use by a
+    /// pending transaction built here outbids it by the 10% nodes require.
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        let call = call_object(from, to, amount as u128, "0x");
+        let mut eth_fees = self.estimate_fees_at(&call, fees.priority).await?;
+        if let Some(tip) = fees.priority_fee {
+            // Keep the base fee budget the suggestion allowed for.
+            eth_fees.max_fee_per_gas =
+                eth_fees.max_fee_per_gas - eth_fees.max_priority_fee_per_gas + tip as u128;
+            eth_fees.max_priority_fee_per_gas = tip as u128;
+        }
+        if let Some(max_fee) = fees.fee_rate {
+            eth_fees.max_fee_per_gas = max_fee as u128;
+            eth_fees.max_priority_fee_per_gas =
+                eth_fees.max_priority_fee_per_gas.min(max_fee as u128);
+        }
+        if let Some(nonce) = fees.nonce {
+            eth_fees = self.nonces.replacement_fees(from, nonce, &eth_fees);
+        }
+        fees.check_limit(u64::try_from(eth_fees.max_cost()).unwrap_or(u64::MAX))?;
+        self.build_transaction(call, &eth_fees, fees.nonce).await
+    }
+
+    /// Frees the nonce `raw_tx` was built with for the next send.
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        let Some(tx) = raw_tx.as_json() else {
+            return;
+        };
+        if let (Some(from), Ok(nonce)) = (tx["from"].as_str(), parse_quantity(&tx["nonce"])) {
+            self.nonces.release(from, nonce as u64);
+        }
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        let RawTransaction::Bytes(bytes) = raw_tx else {
+            return Err(NodeError::Parse(format!(
+                "expected a serialized transaction, got {}",
+                raw_tx.kind()
+            )));
+        };
+        let result = self
+            .rpc(
+                "eth_sendRawTransaction",
+                json!([format!("0x{}", hex::encode(bytes))]),
+            )
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| NodeError::Parse(format!("unexpected tx hash: {}", result)))
+    }
+}
+
+/// `eth_call` / `eth_estimateGas` transaction object.
+fn call_object(from: &str, to: &str, value: u128, data: &str) -> Value {
+    json!({
+        "from": from,
+        "to": to,
+        "value": format!("{:#x}", value),
+        "data": data,
+    })
+}
+
+/// Big-endian bytes of a `0x` hex quantity or data word.
+fn quantity_bytes(value: &Value) -> Result<Vec<u8>, NodeError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| NodeError::Parse(format!("expected hex string, got {}", value)))?;
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    let padded = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    hex::decode(padded).map_err(|e| NodeError::Parse(e.to_string()))
+}
+
+pub(crate) fn parse_quantity(value: &Value) -> Result<u128, NodeError> {
+    let bytes = quantity_bytes(value)?;
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    if bytes.len() - start > 16 {
+        return Err(NodeError::Parse(format!(
+            "quantity out of range: {}",
+            value
+        )));
+    }
+    Ok(bytes[start..]
+        .iter()
+        .fold(0u128, |acc, &b| (acc << 8) | b as u128))
+}
+
+/// ABI-encode a `0x` address as one 32-byte word (hex).
+pub(crate) fn abi_address(address: &str) -> Result<String, NodeError> {
+    let body = address.strip_prefix("0x").unwrap_or(address);
+    if body.len() != 40 || hex::decode(body).is_err() {
+        return Err(NodeError::Parse(format!("invalid EVM address {}", address)));
+    }
+    Ok(format!("{:0>64}", body.to_ascii_lowercase()))
+}
+
+/// ABI-encode a call to ERC-20 `approve(spender, amount)`.
+fn abi_approve(spender: &str, amount: Allowance) -> Result<String, NodeError> {
+    Ok(format!(
+        "{}{}{}",
+        APPROVE_SELECTOR,
+        abi_address(spender)?,
+        amount.abi_word()
+    ))
+}
+
+/// ABI-encode a call to ERC-20 `transfer(to, amount)`.
+fn abi_transfer(to: &str, amount: u128) -> Result<String, NodeError> {
+    Ok(format!(
+        "{}{}{:064x}",
+        TRANSFER_SELECTOR,
+        abi_address(to)?,
+        amount
+    ))
+}
+
+/// Tip for `priority`: half the suggestion for economy, double for priority.
+fn scale_tip(tip: u128, priority: FeePriority) -> u128 {
+    match priority {
+        FeePriority::Economy => tip / 2,
+        FeePriority::Normal => tip,
+        FeePriority::Priority => tip * 2,
+    }
+}
+
+#[async_trait]
+impl LogSource for EthProvider {
+    async fn block_number(&self) -> Result<u64, NodeError> {
+        self.get_block_number().await
+    }
+
+    async fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>, NodeError> {
+        self.get_logs(filter).await
+    }
+}
+
+#[async_trait]
+impl TokenMetadataSource for EthProvider {
+    async fn token_metadata(&self, contract: &str) -> Result<TokenMetadata, NodeError> {
+        let call = |selector: &str| {
+            let data = format!("0x{}", selector);
+            async move {
+                let result = self
+                    .rpc(
+                        "eth_call",
+                        json!([{ "to": contract, "data": data }, "latest"]),
+                    )
+                    .await?;
+                let hex = result
+                    .as_str()
+                    .ok_or_else(|| NodeError::Parse(format!("eth_call: {}", result)))?;
+                hex::decode(hex.trim_start_matches("0x"))
+                    .map_err(|e| NodeError::Parse(e.to_string()))
+            }
+        };
+        Ok(TokenMetadata {
+            contract: contract.to_string(),
+            symbol: decode_abi_string(&call(SYMBOL_SELECTOR).await?)?,
+            name: decode_abi_string(&call(NAME_SELECTOR).await?)?,
+            decimals: decode_abi_decimals(&call(DECIMALS_SELECTOR).await?)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quantities_and_encodes_transfers() {
+        assert_eq!(parse_quantity(&json!("0x0")).unwrap(), 0);
+        assert_eq!(parse_quantity(&json!("0x5208")).unwrap(), 21_000);
+        assert_eq!(
+            parse_quantity(&json!(format!("0x{:064x}", 1_000_000u64))).unwrap(),
+            1_000_000
+        );
+        assert!(parse_quantity(&json!(format!("0x1{:032x}", 0))).is_err());
+
+        assert_eq!(
+            abi_transfer("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", 1_000_000).unwrap(),
+            "a9059cbb\
+             0000000000000000000000005aaeb6053f3e94c9b9a09f33669435e7ef1beaed\
+             00000000000000000000000000000000000000000000000000000000000f4240"
+        );
+        assert!(abi_address("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t").is_err());
+    }
+
+    #[test]
+    fn fee_market_budgets_for_recent_base_fees() {
+        let history = FeeHistory {
+            oldest_block: 1,
+            base_fees: vec![100, 300, 200],
+            gas_used_ratios: vec![1.0, 0.1],
+            rewards: vec![vec![1, 10, 100], vec![3, 10, 100]],
+        };
+        let market = FeeMarket::from_history(&history).unwrap();
+        assert_eq!(market.base_fee, 200);
+        assert_eq!(market.max_fee(FeePriority::Normal), 2 * 300 + 10);
+        assert_eq!(market.tip(FeePriority::Economy), 2);
+        // Nodes that return no rewards fall back to `eth_maxPriorityFeePerGas`.
+        assert!(
+            FeeMarket::from_history(&FeeHistory {
+                rewards: vec![],
+                ..history
+            })
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Log subscriptions over an EVM node's WebSocket endpoint (feature `eth-ws`).
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::node::events::{Log, LogFilter};
+use crate::node::{NodeError, RpcError};
+
+/// An `eth_subscribe("logs")` subscription: new logs as blocks arrive,
+/// without polling `eth_getLogs`.
+pub struct LogSubscription {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    id: String,
+}
+
+impl LogSubscription {
+    /// Subscribe at `url` (`ws://host:port`) to logs matching `filter`. Its
+    /// block bounds are ignored: a subscription only delivers new logs.
+    pub async fn connect(url: &str, filter: &LogFilter) -> Result<Self, NodeError> {
+        let (mut stream, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let filter = LogFilter {
+            from_block: None,
+            to_block: None,
+            ..filter.clone()
+        };
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_subscribe",
+            "params": ["logs", filter.to_json()],
+        });
+        stream
+            .send(Message::text(request.to_string()))
+            .await
+            .map_err(|e| NodeError::Network(e.to_string()))?;
+
+        loop {
+            let reply = next_json(&mut stream)
+                .await
+                .ok_or_else(|| NodeError::Network("closed before subscribing".to_string()))??;
+            if reply.get("id") != Some(&json!(1)) {
+                continue;
+            }
+            if let Some(error) = reply.get("error") {
+                return Err(NodeError::Rpc {
+                    method: "eth_subscribe".to_string(),
+                    error: RpcError::from_value(error),
+                });
+            }
+            let id = reply
+                .get("result")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| NodeError::Parse("eth_subscribe: no subscription id".to_string()))?
+                .to_string();
+            return Ok(Self { stream, id });
+        }
+    }
+
+    /// Subscription id the node assigned.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Next log, or `None` once the node closes the connection. Logs a reorg
+    /// retracts are delivered again with [`Log::removed`] set.
+    pub async fn next(&mut self) -> Option<Result<Log, NodeError>> {
+        loop {
+            let message = match next_json(&mut self.stream).await? {
+                Ok(message) => message,
+                Err(e) => return Some(Err(e)),
+            };
+            let params = &message["params"];
+            if message["method"] == "eth_subscription" && params["subscription"] == self.id {
+                return Some(Log::from_json(&params["result"]));
+            }
+        }
+    }
+}
+
+/// Next text message, parsed; `None` when the connection closes.
+async fn next_json(
+    stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+) -> Option<Result<Value, NodeError>> {
+    loop {
+        match stream.next().await? {
+            Ok(Message::Text(text)) => {
+                return Some(
+                    serde_json::from_str(text.as_str())
+                        .map_err(|e| NodeError::Parse(e.to_string())),
+                );
+            }
+            Ok(Message::Close(_)) => return None,
+            Ok(_) => continue,
+            Err(e) => return Some(Err(NodeError::Network(e.to_string()))),
+        }
+    }
+}
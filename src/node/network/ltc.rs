@@ -1,15 +1,38 @@
-use crate::node::{NodeError, Provider, Transaction};
+use crate::correlation::{DEFAULT_CORRELATION_HEADER, tag_request};
+use crate::metrics::Metrics;
+use crate::node::network::diagnostics::{DiagnosticsSink, read_json};
+use crate::node::network::http::{HttpResponse, default_client, rate_limited, send};
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeOptions, FeePriority, NodeError, RawTransaction,
+    ReadProvider, Transaction, TransactionDetail, TransactionProvider, TxSimulation,
+};
+use crate::wallet::chain::utxo::DUST_LIMIT;
+use crate::wallet::chain::{
+    Chain, ChangeStrategy, CoinSelection, LITECOIN, SighashType, UnsignedUtxoTx, UtxoInput,
+    UtxoTxBuilder,
+};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 const BLOCKCYPHER_LTC_MAINNET: &str = "https://api.blockcypher.com/v1/ltc/main";
 // TODO: Add Testnet support. BlockCypher does not support LTC testnet.
 // Alternatives: Chain.so V3 (requires API key), Tatum (requires API key), local node.
+/// Addresses per batched request Blockcypher allows without an API token.
+const BLOCKCYPHER_FREE_BATCH: usize = 3;
 
 pub struct LtcProvider {
     client: Client,
     base_url: String,
+    correlation_header: Option<String>,
+    batch_size: usize,
+    diagnostics: Option<Arc<dyn DiagnosticsSink>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    token: Option<String>,
+    skeletons: bool,
+    rate_limit_remaining: Mutex<Option<u64>>,
 }
 
 impl Default for LtcProvider {
@@ -21,17 +44,374 @@ impl Default for LtcProvider {
 impl LtcProvider {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: default_client(),
             base_url: BLOCKCYPHER_LTC_MAINNET.to_string(),
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            batch_size: BLOCKCYPHER_FREE_BATCH,
+            diagnostics: None,
+            metrics: None,
+            token: None,
+            skeletons: false,
+            rate_limit_remaining: Mutex::new(None),
         }
     }
 
     pub fn with_url(url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: default_client(),
             base_url: url,
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            batch_size: BLOCKCYPHER_FREE_BATCH,
+            diagnostics: None,
+            metrics: None,
+            token: None,
+            skeletons: false,
+            rate_limit_remaining: Mutex::new(None),
         }
     }
+
+    /// Send requests through `client`, e.g. one from [`HttpConfig::build`](crate::node::network::HttpConfig::build)
+    /// with other timeouts or a proxy.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Header carrying the active [`CorrelationId`](crate::correlation::CorrelationId).
+    /// Pass `None` to stop forwarding correlation ids to the upstream API.
+    pub fn with_correlation_header(mut self, header: Option<&str>) -> Self {
+        self.correlation_header = header.map(str::to_string);
+        self
+    }
+
+    /// Hand responses that fail to parse to `sink` as well as returning
+    /// them in the error.
+    pub fn with_diagnostics(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.diagnostics = Some(sink);
+        self
+    }
+
+    /// Report every request's endpoint, status and latency to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Send Blockcypher API `token` with every request, for the higher rate
+    /// limits of a paid or registered plan.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Have Blockcypher's `txs/new` build sends rather than building them
+    /// locally from [`unspent_outputs`](Self::unspent_outputs). Every
+    /// skeleton must pay exactly the requested amount to the recipient, with
+    /// anything else back to the sender and the fee within `fee_limit`.
+    pub fn with_skeletons(mut self) -> Self {
+        self.skeletons = true;
+        self
+    }
+
+    /// Requests Blockcypher says are left in the current rate-limit window,
+    /// as of the last response that said; `None` before any did.
+    pub fn rate_limit_remaining(&self) -> Option<u64> {
+        *self.rate_limit_remaining.lock().unwrap()
+    }
+
+    /// Addresses per request in [`get_balances_bulk`](ReadProvider::get_balances_bulk).
+    /// Blockcypher accepts up to 100 with an API token, 3 without.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, 100);
+        self
+    }
+
+    /// One batched `addrs/{a;b;c}/balance` request.
+    async fn balance_batch(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        // https://www.blockcypher.com/dev/bitcoin/#batching
+        let url = format!("{}/addrs/{}/balance", self.base_url, addresses.join(";"));
+        let body: Result<serde_json::Value, NodeError> = async {
+            let resp = self.call(self.get(&url)).await?;
+            if !resp.status().is_success() {
+                return Err(NodeError::Http(resp.status().as_u16()));
+            }
+            read_json(resp, self.diagnostics.as_deref())
+        }
+        .await;
+        match body {
+            Ok(body) => batch_balances(addresses, body),
+            Err(e) => addresses.iter().map(|_| Err(e.clone())).collect(),
+        }
+    }
+
+    /// Confirmed and unconfirmed outputs of `address` not yet spent, for
+    /// [`UtxoTxBuilder`](crate::wallet::chain::UtxoTxBuilder).
+    pub async fn unspent_outputs(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        // https://www.blockcypher.com/dev/bitcoin/#address-endpoint
+        let url = format!(
+            "{}/addrs/{}?unspentOnly=true&includeScript=true",
+            self.base_url, address
+        );
+        let resp = self.call(self.get(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        #[derive(Deserialize)]
+        struct UnspentResponse {
+            #[serde(default)]
+            txrefs: Vec<UnspentRef>,
+            #[serde(default)]
+            unconfirmed_txrefs: Vec<UnspentRef>,
+        }
+        #[derive(Deserialize)]
+        struct UnspentRef {
+            tx_hash: String,
+            tx_output_n: u32,
+            value: u64,
+            script: String,
+        }
+
+        let body: UnspentResponse = read_json(resp, self.diagnostics.as_deref())?;
+
+        body.txrefs
+            .into_iter()
+            .chain(body.unconfirmed_txrefs)
+            .map(|utxo| {
+                let prev_hash = hex::decode(&utxo.tx_hash)
+                    .ok()
+                    .and_then(|h| <[u8; 32]>::try_from(h).ok())
+                    .ok_or_else(|| NodeError::Parse(format!("bad tx_hash {}", utxo.tx_hash)))?;
+                Ok(UtxoInput {
+                    prev_hash,
+                    output_index: utxo.tx_output_n,
+                    value: utxo.value,
+                    script_pubkey: hex::decode(&utxo.script)
+                        .map_err(|e| NodeError::Parse(e.to_string()))?,
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                })
+            })
+            .collect()
+    }
+
+    /// Blockcypher's full record of the transaction `hash`; `None` if it does not know it.
+    async fn transaction_entry(&self, hash: &str) -> Result<Option<serde_json::Value>, NodeError> {
+        let url = format!("{}/txs/{}", self.base_url, hash);
+        let resp = self.call(self.get(&url)).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+        read_json(resp, self.diagnostics.as_deref()).map(Some)
+    }
+
+    /// Broadcast a fully serialized transaction via `txs/push`.
+    async fn push_raw(&self, bytes: &[u8]) -> Result<String, NodeError> {
+        // https://www.blockcypher.com/dev/bitcoin/#push-raw-transaction-endpoint
+        let url = format!("{}/txs/push", self.base_url);
+        let resp = self
+            .call(
+                self.post(&url)
+                    .json(&serde_json::json!({ "tx": hex::encode(bytes) })),
+            )
+            .await?;
+
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
+
+        if let Some(err) = body.get("error") {
+            return Err(NodeError::Api(err.to_string()));
+        }
+        body.pointer("/tx/hash")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| NodeError::Parse(format!("unexpected push response: {}", body)))
+    }
+
+    /// Blockcypher `txs/new` skeleton. `preference` picks one of Blockcypher's fee
+    /// tiers; `fees` sets the absolute fee instead.
+    async fn create_skeleton(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        preference: Option<&'static str>,
+        fees: Option<u64>,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://api.blockcypher.com/v1/ltc/main/txs/new
+        let url = format!("{}/txs/new", self.base_url);
+
+        #[derive(serde::Serialize)]
+        struct CreateTxReq {
+            inputs: Vec<Input>,
+            outputs: Vec<Output>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            preference: Option<&'static str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            fees: Option<u64>,
+        }
+        #[derive(serde::Serialize)]
+        struct Input {
+            addresses: Vec<String>,
+        }
+        #[derive(serde::Serialize)]
+        struct Output {
+            addresses: Vec<String>,
+            value: u64,
+        }
+
+        let req = CreateTxReq {
+            inputs: vec![Input {
+                addresses: vec![from.to_string()],
+            }],
+            outputs: vec![Output {
+                addresses: vec![to.to_string()],
+                value: amount,
+            }],
+            preference,
+            fees,
+        };
+
+        let resp = self.call(self.post(&url).json(&req)).await?;
+
+        // Blockcypher returns a JSON object with "tosign" array.
+        // We return the whole JSON to be processed by the signer.
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
+
+        if let Some(err) = body.get("error") {
+            return Err(NodeError::Api(err.to_string()));
+        }
+
+        Ok(RawTransaction::Json(body))
+    }
+
+    /// Chain summary: tip height and Blockcypher's current fee tiers.
+    async fn chain_info(&self) -> Result<BlockcypherChain, NodeError> {
+        // https://api.blockcypher.com/v1/ltc/main
+        let resp = self.call(self.get(&self.base_url)).await?;
+
+        read_json(resp, self.diagnostics.as_deref())
+    }
+
+    /// Fee rate in litoshi per kilobyte: `fees.fee_rate`, or Blockcypher's
+    /// tier for `fees.priority`.
+    async fn fee_rate(&self, fees: &FeeOptions) -> Result<u64, NodeError> {
+        if let Some(per_kb) = fees.fee_rate {
+            return Ok(per_kb);
+        }
+        let chain = self.chain_info().await?;
+        Ok(match fees.priority {
+            FeePriority::Economy => chain.low_fee_per_kb,
+            FeePriority::Normal => chain.medium_fee_per_kb,
+            FeePriority::Priority => chain.high_fee_per_kb,
+        })
+    }
+
+    /// Every unspent output of `from` paid to `to` in a single output, less the fee.
+    async fn sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<UnsignedUtxoTx, NodeError> {
+        let fee_rate = self.fee_rate(fees).await?;
+        let utxos = self.unspent_outputs(from).await?;
+        let total: u64 = utxos.iter().map(|u| u.value).sum();
+        let mut tx = utxos
+            .into_iter()
+            .fold(UtxoTxBuilder::new(&LITECOIN), UtxoTxBuilder::input)
+            .pay(to, 0)
+            .and_then(|builder| builder.build())
+            .map_err(|e| NodeError::Api(e.to_string()))?;
+        let fee = fee_rate
+            * tx.estimated_vsize()
+                .map_err(|e| NodeError::Api(e.to_string()))?
+            / 1000;
+        fees.check_limit(fee)?;
+        match total.checked_sub(fee) {
+            Some(amount) if amount >= DUST_LIMIT => tx.outputs[0].value = amount,
+            _ => {
+                return Err(NodeError::InsufficientResources {
+                    resource: "LTC".to_string(),
+                    needed: fee + DUST_LIMIT,
+                    available: total,
+                });
+            }
+        }
+        Ok(tx)
+    }
+
+    /// Size of a payment of `amount` from `from` plus change, given its spendable outputs.
+    async fn estimated_transfer_size(&self, from: &str, amount: u64) -> Result<u64, NodeError> {
+        let values = self
+            .unspent_outputs(from)
+            .await?
+            .into_iter()
+            .map(|utxo| utxo.value)
+            .collect();
+        Ok(estimated_size(inputs_needed(values, amount), 2))
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        self.authorize(tag_request(
+            self.client.get(url),
+            self.correlation_header.as_deref(),
+        ))
+    }
+
+    fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        self.authorize(tag_request(
+            self.client.post(url),
+            self.correlation_header.as_deref(),
+        ))
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.query(&[("token", token)]),
+            None => request,
+        }
+    }
+
+    /// Send `request`, noting the rate-limit quota the response reports and
+    /// failing with [`NodeError::RateLimited`] on a 429.
+    async fn call(&self, request: reqwest::RequestBuilder) -> Result<HttpResponse, NodeError> {
+        let resp = send(request, "ltc", self.metrics.as_deref()).await?;
+        if let Some(remaining) = rate_limit_remaining(&resp) {
+            *self.rate_limit_remaining.lock().unwrap() = Some(remaining);
+        }
+        match rate_limited(&resp) {
+            Some(e) => Err(e),
+            None => Ok(resp),
+        }
+    }
+}
+
+/// `ref_balance` of the newest confirmed txref in an `addrs/{a}` response:
+/// the address's balance once that transaction was applied. 0 without one.
+fn ref_balance(body: &serde_json::Value) -> u64 {
+    body["txrefs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .max_by_key(|txref| txref["block_height"].as_i64().unwrap_or(0))
+        .and_then(|txref| txref["ref_balance"].as_u64())
+        .unwrap_or(0)
+}
+
+/// Blockcypher's `X-Ratelimit-Remaining` header.
+fn rate_limit_remaining(response: &HttpResponse) -> Option<u64> {
+    response
+        .headers()
+        .get("x-ratelimit-remaining")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,6 +421,7 @@ struct BlockcypherBalance {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct BlockcypherTxRef {
     tx_hash: String,
     block_height: i64,
@@ -48,72 +429,264 @@ struct BlockcypherTxRef {
     confirmed: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+struct BlockcypherTx {
+    hash: String,
+    block_height: i64,
+    #[serde(default)]
+    inputs: Vec<BlockcypherTxIo>,
+    #[serde(default)]
+    outputs: Vec<BlockcypherTxIo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockcypherTxIo {
+    #[serde(default)]
+    addresses: Option<Vec<String>>,
+    #[serde(default)]
+    value: u64,
+}
+
+impl BlockcypherTxIo {
+    fn address(&self) -> Option<&str> {
+        self.addresses.as_ref()?.first().map(String::as_str)
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct BlockcypherAddressFull {
     // address: String,
     // total_received: u64,
     // total_sent: u64,
     // balance: u64,
-    txrefs: Option<Vec<BlockcypherTxRef>>,
+    txrefs: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Deserialize, Debug)]
 struct BlockcypherChain {
     height: u64,
+    #[serde(default)]
+    low_fee_per_kb: u64,
+    #[serde(default)]
+    medium_fee_per_kb: u64,
+    #[serde(default)]
+    high_fee_per_kb: u64,
+}
+
+/// Map a full Blockcypher transaction to a [`Transaction`]: sent from the first
+/// input's address to the first output paying someone else.
+fn transaction_from_full(entry: serde_json::Value) -> Result<Transaction, NodeError> {
+    let tx: BlockcypherTx =
+        serde_json::from_value(entry.clone()).map_err(|e| NodeError::Parse(e.to_string()))?;
+    let from = tx
+        .inputs
+        .first()
+        .and_then(BlockcypherTxIo::address)
+        .unwrap_or_default()
+        .to_string();
+    let payment = tx
+        .outputs
+        .iter()
+        .find(|o| o.address() != Some(from.as_str()))
+        .or(tx.outputs.first());
+    Ok(Transaction {
+        hash: tx.hash,
+        to: payment
+            .and_then(BlockcypherTxIo::address)
+            .unwrap_or_default()
+            .to_string(),
+        from,
+        value: payment.map(|o| o.value).unwrap_or(0).to_string(),
+        block_number: tx.block_height.max(0) as u64,
+        timestamp: 0,
+        status: if tx.block_height > 0 {
+            "SUCCESS"
+        } else {
+            "PENDING"
+        }
+        .to_string(),
+        raw: Some(entry),
+        details: None,
+    })
+}
+
+/// Detail of a `txs/{hash}` entry, which states its confirmations, fee and
+/// block hash itself.
+fn detail_from_full(entry: serde_json::Value) -> Result<TransactionDetail, NodeError> {
+    let confirmations = entry["confirmations"].as_u64().unwrap_or(0);
+    let fee = entry["fees"].as_u64();
+    let block_hash = entry["block_hash"].as_str().map(str::to_string);
+    let tx = transaction_from_full(entry)?;
+    let tip = match confirmations {
+        0 => 0,
+        n => tx.block_number + n - 1,
+    };
+    let mut detail = TransactionDetail::new(tx, tip, block_hash);
+    detail.fee = fee;
+    Ok(detail)
+}
+
+/// Match a batched balance response (an object for one address, an array
+/// otherwise) back to the requested addresses.
+fn batch_balances(addresses: &[String], body: serde_json::Value) -> Vec<Result<String, NodeError>> {
+    let entries = match body {
+        serde_json::Value::Array(entries) => entries,
+        single => vec![single],
+    };
+    addresses
+        .iter()
+        .map(|address| {
+            let entry = entries
+                .iter()
+                .find(|e| e.get("address").and_then(|a| a.as_str()) == Some(address.as_str()))
+                .ok_or_else(|| {
+                    NodeError::Api(format!("{} missing from batch response", address))
+                })?;
+            if let Some(err) = entry.get("error") {
+                return Err(NodeError::Api(err.to_string()));
+            }
+            entry
+                .get("balance")
+                .and_then(|b| b.as_u64())
+                .map(|b| b.to_string())
+                .ok_or_else(|| NodeError::Parse(format!("no balance for {}", address)))
+        })
+        .collect()
+}
+
+/// Refuse a `txs/new` skeleton unless it pays `amount` to `to` once, sends
+/// every other output back to `from`, and spends no more on fees than
+/// `fees` allows. Blockcypher's word for what it built is not taken.
+fn check_skeleton(
+    raw_tx: &RawTransaction,
+    from: &str,
+    to: &str,
+    amount: u64,
+    fees: &FeeOptions,
+) -> Result<(), NodeError> {
+    let summary = LITECOIN
+        .inspect_transaction(raw_tx)
+        .map_err(|e| NodeError::Api(e.to_string()))?;
+    let mut paid = false;
+    for payment in &summary.payments {
+        if !paid && payment.to == to && payment.amount == amount as u128 {
+            paid = true;
+        } else if payment.to != from {
+            return Err(NodeError::Api(format!(
+                "skeleton pays {} to {}, which is neither {} nor change",
+                payment.amount, payment.to, to
+            )));
+        }
+    }
+    if !paid {
+        return Err(NodeError::Api(format!(
+            "skeleton does not pay {} to {}",
+            amount, to
+        )));
+    }
+    let fee = summary.max_fee.unwrap_or(u128::MAX);
+    fees.check_limit(u64::try_from(fee).unwrap_or(u64::MAX))
+}
+
+/// Approximate size in bytes of a P2PKH transaction.
+fn estimated_size(inputs: usize, outputs: usize) -> u64 {
+    10 + 148 * inputs as u64 + 34 * outputs as u64
+}
+
+/// Inputs a largest-first selection needs to cover `target` (all of them if it can't).
+fn inputs_needed(mut values: Vec<u64>, target: u64) -> usize {
+    values.sort_unstable_by(|a, b| b.cmp(a));
+    let mut total = 0u64;
+    for (i, value) in values.iter().enumerate() {
+        total += value;
+        if total >= target {
+            return i + 1;
+        }
+    }
+    values.len().max(1)
 }
 
 #[async_trait]
-impl Provider for LtcProvider {
+impl ReadProvider for LtcProvider {
     fn get_decimals(&self) -> u32 {
         8
     }
 
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        Ok(ChainInfo {
+            chain_id: (self.base_url == BLOCKCYPHER_LTC_MAINNET).then(|| "litecoin".to_string()),
+            symbol: Some("LTC".to_string()),
+            decimals: 8,
+            block_time: Some(Duration::from_secs(150)),
+        })
+    }
+
     async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
         // https://api.blockcypher.com/v1/ltc/main/addrs/L.../balance
         let url = format!("{}/addrs/{}/balance", self.base_url, address);
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let resp = self.call(self.get(&url)).await?;
 
         if !resp.status().is_success() {
-            return Err(NodeError::Api(format!("Status: {}", resp.status())));
+            return Err(NodeError::Http(resp.status().as_u16()));
         }
 
-        let body: BlockcypherBalance = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let body: BlockcypherBalance = read_json(resp, self.diagnostics.as_deref())?;
 
         Ok(body.balance.to_string())
     }
 
+    /// The balance after the address's last transaction in or below the
+    /// block. Blockcypher filters history by height only, so a time is refused.
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        let BalanceAt::Block(number) = at else {
+            return Err(NodeError::Api(
+                "Blockcypher looks past balances up by block height only".to_string(),
+            ));
+        };
+        // https://www.blockcypher.com/dev/bitcoin/#address-endpoint
+        let url = format!(
+            "{}/addrs/{}?before={}&limit=1",
+            self.base_url,
+            address,
+            number + 1
+        );
+        let resp = self.call(self.get(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
+        Ok(ref_balance(&body).to_string())
+    }
+
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        let mut results = Vec::with_capacity(addresses.len());
+        for batch in addresses.chunks(self.batch_size) {
+            results.extend(self.balance_batch(batch).await);
+        }
+        results
+    }
+
     async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
         // https://api.blockcypher.com/v1/ltc/main/addrs/L...
-        let url = format!("{}/addrs/{}", self.base_url, address);
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+        // includeScript adds each ref's script, which the chain decoder exposes as details.
+        let url = format!("{}/addrs/{}?includeScript=true", self.base_url, address);
+        let resp = self.call(self.get(&url)).await?;
 
         if !resp.status().is_success() {
-            return Err(NodeError::Api(format!("Status: {}", resp.status())));
+            return Err(NodeError::Http(resp.status().as_u16()));
         }
 
-        let body: BlockcypherAddressFull = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let body: BlockcypherAddressFull = read_json(resp, self.diagnostics.as_deref())?;
 
         let txs = body.txrefs.unwrap_or_default();
         let transactions = txs
             .into_iter()
-            .map(|tx| {
-                Transaction {
+            .map(|entry| {
+                let tx: BlockcypherTxRef = serde_json::from_value(entry.clone())
+                    .map_err(|e| NodeError::Parse(e.to_string()))?;
+                Ok(Transaction {
                     hash: tx.tx_hash,
                     from: "".to_string(), // Blockcypher simplified view doesn't easily show from/to without deep dive
                     to: "".to_string(),
@@ -126,106 +699,71 @@ impl Provider for LtcProvider {
                         "PENDING"
                     }
                     .to_string(),
-                }
+                    raw: Some(entry),
+                    details: None,
+                })
             })
-            .collect();
+            .collect::<Result<_, NodeError>>()?;
 
         Ok(transactions)
     }
 
-    async fn get_block_number(&self) -> Result<u64, NodeError> {
-        // https://api.blockcypher.com/v1/ltc/main
-        let url = self.base_url.clone();
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        match self.transaction_entry(hash).await? {
+            Some(entry) => transaction_from_full(entry).map(Some),
+            None => Ok(None),
+        }
+    }
 
-        let body: BlockcypherChain = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        match self.transaction_entry(hash).await? {
+            Some(entry) => detail_from_full(entry).map(Some),
+            None => Ok(None),
+        }
+    }
 
-        Ok(body.height)
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(self.chain_info().await?.height)
     }
 
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.unspent_outputs(address).await
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for LtcProvider {
     async fn create_transaction(
         &self,
         from: &str,
         to: &str,
         amount: u64,
-    ) -> Result<String, NodeError> {
-        // https://api.blockcypher.com/v1/ltc/main/txs/new
-        let url = format!("{}/txs/new", self.base_url);
-
-        #[derive(serde::Serialize)]
-        struct CreateTxReq {
-            inputs: Vec<Input>,
-            outputs: Vec<Output>,
-        }
-        #[derive(serde::Serialize)]
-        struct Input {
-            addresses: Vec<String>,
-        }
-        #[derive(serde::Serialize)]
-        struct Output {
-            addresses: Vec<String>,
-            value: u64,
-        }
-
-        let req = CreateTxReq {
-            inputs: vec![Input {
-                addresses: vec![from.to_string()],
-            }],
-            outputs: vec![Output {
-                addresses: vec![to.to_string()],
-                value: amount,
-            }],
-        };
-
-        let resp = self
-            .client
-            .post(&url)
-            .json(&req)
-            .send()
+    ) -> Result<RawTransaction, NodeError> {
+        self.create_transaction_with_fees(from, to, amount, &FeeOptions::default())
             .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
-
-        // Blockcypher returns a JSON object with "tosign" array.
-        // We return the whole JSON to be processed by the signer.
-        let body: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+    }
 
-        if let Some(err) = body.get("error") {
-            return Err(NodeError::Api(err.to_string()));
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        if let RawTransaction::Bytes(bytes) = raw_tx {
+            return self.push_raw(bytes).await;
         }
 
-        Ok(body.to_string())
-    }
-
-    async fn broadcast_transaction(&self, raw_tx: &str) -> Result<String, NodeError> {
         // https://api.blockcypher.com/v1/ltc/main/txs/send
         let url = format!("{}/txs/send", self.base_url);
 
-        let tx: serde_json::Value =
-            serde_json::from_str(raw_tx).map_err(|e| NodeError::Parse(e.to_string()))?;
+        let tx = raw_tx.as_json().ok_or_else(|| {
+            NodeError::Parse(format!(
+                "expected a JSON transaction, got {}",
+                raw_tx.kind()
+            ))
+        })?;
 
-        let resp = self
-            .client
-            .post(&url)
-            .json(&tx)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let resp = self.call(self.post(&url).json(tx)).await?;
 
-        let body: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
 
         if let Some(err) = body.get("error") {
             return Err(NodeError::Api(err.to_string()));
@@ -241,6 +779,164 @@ impl Provider for LtcProvider {
         // Fallback if structure is different
         Ok("SUCCESS".to_string())
     }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        _to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        let size = self.estimated_transfer_size(from, amount).await?;
+        let chain = self.chain_info().await?;
+        let fee = |per_kb: u64| per_kb * size / 1000;
+        Ok(FeeEstimate {
+            economy: fee(chain.low_fee_per_kb),
+            normal: fee(chain.medium_fee_per_kb),
+            priority: fee(chain.high_fee_per_kb),
+        })
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        if !self.skeletons {
+            return self
+                .create_transaction_with_coins(from, to, amount, fees, &CoinSelection::default())
+                .await;
+        }
+        let raw_tx = match fees.fee_rate {
+            Some(per_kb) => {
+                let size = self.estimated_transfer_size(from, amount).await?;
+                self.create_skeleton(from, to, amount, None, Some(per_kb * size / 1000))
+                    .await?
+            }
+            None => {
+                let preference = match fees.priority {
+                    FeePriority::Economy => "low",
+                    FeePriority::Normal => "medium",
+                    FeePriority::Priority => "high",
+                };
+                self.create_skeleton(from, to, amount, Some(preference), None)
+                    .await?
+            }
+        };
+        check_skeleton(&raw_tx, from, to, amount, fees)?;
+        Ok(raw_tx)
+    }
+
+    /// Built locally from `from`'s unspent outputs, with change back to
+    /// `from`; signing it yields raw bytes for `txs/push`.
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        let fee_rate = self.fee_rate(fees).await?;
+        let utxos = self.unspent_outputs(from).await?;
+        let tx = UtxoTxBuilder::new(&LITECOIN)
+            .pay(to, amount)
+            .and_then(|builder| builder.change(ChangeStrategy::SameAddress))
+            .and_then(|builder| builder.select_coins(&utxos, selection, fee_rate))
+            .and_then(|builder| builder.build())
+            .map_err(|e| NodeError::Api(e.to_string()))?;
+        fees.check_limit(tx.fee().map_err(|e| NodeError::Api(e.to_string()))?)?;
+        Ok(tx.into_raw())
+    }
+
+    /// Built locally like [`create_transaction_with_coins`](Self::create_transaction_with_coins),
+    /// one output per recipient.
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        let fee_rate = self.fee_rate(fees).await?;
+        let utxos = self.unspent_outputs(from).await?;
+        let tx = outputs
+            .iter()
+            .try_fold(UtxoTxBuilder::new(&LITECOIN), |builder, (to, amount)| {
+                builder.pay(to, *amount)
+            })
+            .and_then(|builder| builder.change(ChangeStrategy::SameAddress))
+            .and_then(|builder| builder.select_coins(&utxos, &CoinSelection::default(), fee_rate))
+            .and_then(|builder| builder.build())
+            .map_err(|e| NodeError::Api(e.to_string()))?;
+        fees.check_limit(tx.fee().map_err(|e| NodeError::Api(e.to_string()))?)?;
+        Ok(tx.into_raw())
+    }
+
+    /// Locally built transactions are priced and sized from their inputs;
+    /// Blockcypher skeletons carry the fee and size `txs/new` worked out.
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        let json = raw_tx.as_json().ok_or_else(|| {
+            NodeError::Parse(format!(
+                "expected a JSON transaction, got {}",
+                raw_tx.kind()
+            ))
+        })?;
+        let (fee, vsize) = if json.get("utxo_tx").is_some() {
+            let tx = UnsignedUtxoTx::from_raw(raw_tx).map_err(|e| NodeError::Api(e.to_string()))?;
+            let fee = tx.fee().map_err(|e| NodeError::Api(e.to_string()))?;
+            let vsize = tx
+                .estimated_vsize()
+                .map_err(|e| NodeError::Api(e.to_string()))?;
+            (fee, vsize)
+        } else {
+            let field = |name: &str| {
+                json.pointer(&format!("/tx/{}", name))
+                    .and_then(|v| v.as_u64())
+            };
+            let fee = field("fees")
+                .ok_or_else(|| NodeError::Parse("skeleton has no fees".to_string()))?;
+            let vsize = field("vsize")
+                .or_else(|| field("size"))
+                .ok_or_else(|| NodeError::Parse("skeleton has no size".to_string()))?;
+            (fee, vsize)
+        };
+        Ok(TxSimulation {
+            fee,
+            resources: [("vsize".to_string(), vsize)].into(),
+        })
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        Ok(self.sweep_transaction(from, to, fees).await?.outputs[0].value)
+    }
+
+    /// Spends every unspent output, refusing if they no longer add up to
+    /// `amount` plus the fee.
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        let tx = self.sweep_transaction(from, to, fees).await?;
+        if tx.outputs[0].value != amount {
+            return Err(NodeError::Api(format!(
+                "unspent outputs of {} changed: can now sweep {} rather than {}",
+                from, tx.outputs[0].value, amount
+            )));
+        }
+        Ok(tx.into_raw())
+    }
 }
 
 #[cfg(test)]
@@ -252,4 +948,150 @@ mod tests {
         let provider = LtcProvider::new();
         assert_eq!(provider.base_url, BLOCKCYPHER_LTC_MAINNET);
     }
+
+    #[test]
+    fn matches_batched_balances_to_addresses() {
+        let addresses = vec!["La".to_string(), "Lb".to_string(), "Lc".to_string()];
+        let body = serde_json::json!([
+            { "address": "Lb", "balance": 7 },
+            { "address": "La", "balance": 5 },
+            { "address": "Lc", "error": "Rate limited" },
+        ]);
+        let results = batch_balances(&addresses, body);
+        assert_eq!(results[0], Ok("5".to_string()));
+        assert_eq!(results[1], Ok("7".to_string()));
+        assert!(results[2].is_err());
+
+        let single = batch_balances(
+            &addresses[..1],
+            serde_json::json!({ "address": "La", "balance": 1 }),
+        );
+        assert_eq!(single, vec![Ok("1".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn sends_the_token_and_reports_rate_limiting() {
+        use crate::test_utils::http::{Reply, serve};
+
+        let (url, server) = serve(vec![
+            Reply::new(429, "")
+                .header("Retry-After", "7")
+                .header("X-Ratelimit-Remaining", "0"),
+        ])
+        .await;
+
+        let provider = LtcProvider::with_url(url).with_token("secret");
+        let error = provider.get_balance("La").await.unwrap_err();
+        assert_eq!(
+            error,
+            NodeError::RateLimited {
+                retry_after: Some(Duration::from_secs(7))
+            }
+        );
+        assert!(error.is_transient());
+        assert_eq!(provider.rate_limit_remaining(), Some(0));
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /addrs/La/balance?token=secret "));
+    }
+
+    #[test]
+    fn reads_the_balance_as_of_the_newest_txref() {
+        let body = serde_json::json!({
+            "address": "La",
+            "txrefs": [
+                { "tx_hash": "bb", "block_height": 2_000, "ref_balance": 80_000 },
+                { "tx_hash": "aa", "block_height": 1_500, "ref_balance": 100_000 },
+            ],
+        });
+        assert_eq!(ref_balance(&body), 80_000);
+        assert_eq!(ref_balance(&serde_json::json!({ "address": "La" })), 0);
+    }
+
+    #[test]
+    fn sizes_transfers_from_largest_inputs() {
+        assert_eq!(inputs_needed(vec![10, 50, 30], 60), 2);
+        assert_eq!(inputs_needed(vec![10, 50, 30], 500), 3);
+        assert_eq!(inputs_needed(vec![], 1), 1);
+        assert_eq!(estimated_size(1, 2), 226);
+    }
+
+    #[test]
+    fn refuses_skeletons_that_pay_anyone_else() {
+        let own = "LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc";
+        let p2pkh = |byte: u8| {
+            LITECOIN.address_from_script(
+                &[&[0x76, 0xa9, 0x14][..], &[byte; 20], &[0x88, 0xac]].concat(),
+            )
+        };
+        let (to, attacker) = (p2pkh(1), p2pkh(2));
+        let skeleton = |outputs: serde_json::Value| {
+            RawTransaction::Json(serde_json::json!({
+                "tx": {
+                    "inputs": [{
+                        "prev_hash": "ab".repeat(32),
+                        "output_index": 0,
+                        "output_value": 100_000,
+                        "addresses": [own],
+                    }],
+                    "outputs": outputs,
+                },
+                "tosign": [],
+            }))
+        };
+        let fees = FeeOptions {
+            fee_limit: Some(5_000),
+            ..FeeOptions::default()
+        };
+
+        let honest = skeleton(serde_json::json!([
+            { "value": 50_000, "addresses": [to] },
+            { "value": 48_000, "addresses": [own] },
+        ]));
+        assert_eq!(check_skeleton(&honest, own, &to, 50_000, &fees), Ok(()));
+
+        // Self-consistent, but the change goes to the API's own address.
+        let tampered = skeleton(serde_json::json!([
+            { "value": 50_000, "addresses": [to] },
+            { "value": 48_000, "addresses": [attacker] },
+        ]));
+        assert!(check_skeleton(&tampered, own, &to, 50_000, &fees).is_err());
+
+        let short = skeleton(serde_json::json!([
+            { "value": 40_000, "addresses": [to] },
+            { "value": 58_000, "addresses": [own] },
+        ]));
+        assert!(check_skeleton(&short, own, &to, 50_000, &fees).is_err());
+
+        let greedy = skeleton(serde_json::json!([
+            { "value": 50_000, "addresses": [to] },
+            { "value": 40_000, "addresses": [own] },
+        ]));
+        assert!(check_skeleton(&greedy, own, &to, 50_000, &fees).is_err());
+    }
+
+    #[test]
+    fn reads_confirmations_fee_and_block_from_a_transaction() {
+        let entry = serde_json::json!({
+            "hash": "ab",
+            "block_height": 2_500_000,
+            "block_hash": "00ff",
+            "confirmations": 3,
+            "fees": 2_260,
+            "inputs": [{ "addresses": ["LA"], "output_value": 60_000 }],
+            "outputs": [{ "addresses": ["LB"], "value": 50_000 }],
+        });
+        let detail = detail_from_full(entry).unwrap();
+        assert_eq!(detail.confirmations, 3);
+        assert_eq!(
+            detail.status,
+            crate::node::TxStatus::Confirmed {
+                block_number: 2_500_000,
+                confirmations: 3,
+            }
+        );
+        assert_eq!(detail.fee, Some(2_260));
+        assert_eq!(detail.block_hash.as_deref(), Some("00ff"));
+        assert_eq!(detail.transaction.value, "50000");
+        assert!(detail.transaction.raw.is_some());
+    }
 }
@@ -1,3 +1,21 @@
+#[cfg(feature = "coingecko")]
+pub mod coingecko;
+pub mod diagnostics;
+pub mod eth;
+#[cfg(feature = "eth-ws")]
+pub mod eth_ws;
+pub mod http;
 pub mod ltc;
+pub mod nonce;
 pub mod prelude;
+pub mod rpc;
 pub mod tron;
+#[cfg(feature = "tron-grpc")]
+pub mod tron_grpc;
+#[cfg(feature = "tron-ws")]
+pub mod tron_ws;
+
+pub use diagnostics::{CapturedResponse, DiagnosticsSink};
+pub use http::HttpConfig;
+pub use nonce::NonceManager;
+pub use rpc::RpcClient;
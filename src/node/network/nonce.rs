@@ -0,0 +1,180 @@
+//! Nonces for concurrent EVM sends from one address.
+//!
+//! `eth_getTransactionCount(.., "pending")` only counts what the node has
+//! already seen, so two sends built at the same time would both get the same
+//! nonce and one would be rejected (or replace the other). [`NonceManager`]
+//! remembers the nonces it handed out until the node's count passes them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::node::network::eth::EthFees;
+use crate::time::{Duration, Instant};
+
+/// How long a nonce stays reserved when the node never sees its
+/// transaction, e.g. because signing failed without anyone saying so.
+const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(300);
+
+/// Nodes accept a transaction replacing a pending one only if both of its fee
+/// caps are at least this many percent higher (geth's default price bump).
+const REPLACEMENT_BUMP_PERCENT: u128 = 10;
+
+/// A nonce handed out for a transaction the node has not counted yet.
+#[derive(Debug, Clone, Copy)]
+struct Reservation {
+    at: Instant,
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+}
+
+/// Pending nonces per address, shared by everything building transactions
+/// for the same accounts.
+///
+/// A nonce is reserved when a transaction is built and stays reserved until
+/// the node's pending count passes it. One that is
+/// [`release`](Self::release)d, or never reaches the node within
+/// [`with_stale_after`](Self::with_stale_after), is handed out again so
+/// later transactions are not stuck behind the gap.
+#[derive(Debug)]
+pub struct NonceManager {
+    stale_after: Duration,
+    accounts: Mutex<HashMap<String, BTreeMap<u64, Reservation>>>,
+}
+
+impl Default for NonceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self {
+            stale_after: DEFAULT_STALE_AFTER,
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Hand a nonce out again once its transaction has not reached the node
+    /// for `stale_after` (5 minutes by default).
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Reserve the lowest free nonce of `address` at or above
+    /// `pending_count`, the node's `eth_getTransactionCount(.., "pending")`.
+    pub fn reserve(&self, address: &str, pending_count: u64, fees: &EthFees) -> u64 {
+        let mut accounts = self.accounts.lock().unwrap();
+        let reserved = accounts.entry(address.to_ascii_lowercase()).or_default();
+        // Below the node's count the transactions are known to it; at or above,
+        // one that has not arrived by now never will.
+        reserved.retain(|&nonce, r| nonce >= pending_count && r.at.elapsed() < self.stale_after);
+        let nonce = (pending_count..)
+            .find(|nonce| !reserved.contains_key(nonce))
+            .expect("nonces are unbounded");
+        reserved.insert(nonce, Reservation::new(fees));
+        nonce
+    }
+
+    /// Fees for a transaction reusing `nonce` of `address`: `fees`, raised
+    /// enough to replace the pending transaction this manager built with it.
+    pub fn replacement_fees(&self, address: &str, nonce: u64, fees: &EthFees) -> EthFees {
+        let accounts = self.accounts.lock().unwrap();
+        let Some(pending) = accounts
+            .get(&address.to_ascii_lowercase())
+            .and_then(|reserved| reserved.get(&nonce))
+        else {
+            return *fees;
+        };
+        let bump = |old: u128| old + (old * REPLACEMENT_BUMP_PERCENT).div_ceil(100);
+        EthFees {
+            gas: fees.gas,
+            max_fee_per_gas: fees.max_fee_per_gas.max(bump(pending.max_fee_per_gas)),
+            max_priority_fee_per_gas: fees
+                .max_priority_fee_per_gas
+                .max(bump(pending.max_priority_fee_per_gas)),
+        }
+    }
+
+    /// Record that `nonce` of `address` now belongs to a transaction paying
+    /// `fees`, replacing whatever had it.
+    pub fn replace(&self, address: &str, nonce: u64, fees: &EthFees) {
+        self.accounts
+            .lock()
+            .unwrap()
+            .entry(address.to_ascii_lowercase())
+            .or_default()
+            .insert(nonce, Reservation::new(fees));
+    }
+
+    /// Give `nonce` of `address` back: its transaction will not be sent.
+    pub fn release(&self, address: &str, nonce: u64) {
+        if let Some(reserved) = self
+            .accounts
+            .lock()
+            .unwrap()
+            .get_mut(&address.to_ascii_lowercase())
+        {
+            reserved.remove(&nonce);
+        }
+    }
+}
+
+impl Reservation {
+    fn new(fees: &EthFees) -> Self {
+        Self {
+            at: Instant::now(),
+            max_fee_per_gas: fees.max_fee_per_gas,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const FEES: EthFees = EthFees {
+        gas: 21_000,
+        max_priority_fee_per_gas: 100,
+        max_fee_per_gas: 1_000,
+    };
+
+    #[test]
+    fn concurrent_sends_get_consecutive_nonces_and_gaps_are_refilled() {
+        let nonces = NonceManager::new();
+        assert_eq!(nonces.reserve(FROM, 7, &FEES), 7);
+        assert_eq!(nonces.reserve(&FROM.to_lowercase(), 7, &FEES), 8);
+        assert_eq!(nonces.reserve(FROM, 7, &FEES), 9);
+        nonces.release(FROM, 8);
+        assert_eq!(nonces.reserve(FROM, 8, &FEES), 8);
+        // The node saw everything up to 11 from elsewhere.
+        assert_eq!(nonces.reserve(FROM, 11, &FEES), 11);
+
+        let nonces = NonceManager::new().with_stale_after(Duration::ZERO);
+        assert_eq!(nonces.reserve(FROM, 3, &FEES), 3);
+        assert_eq!(nonces.reserve(FROM, 3, &FEES), 3);
+    }
+
+    #[test]
+    fn replacements_outbid_the_pending_transaction() {
+        let nonces = NonceManager::new();
+        assert_eq!(nonces.replacement_fees(FROM, 0, &FEES), FEES);
+        let nonce = nonces.reserve(FROM, 0, &FEES);
+        let bumped = nonces.replacement_fees(FROM, nonce, &FEES);
+        assert_eq!(bumped.max_fee_per_gas, 1_100);
+        assert_eq!(bumped.max_priority_fee_per_gas, 110);
+        let higher = EthFees {
+            max_fee_per_gas: 5_000,
+            ..FEES
+        };
+        assert_eq!(
+            nonces
+                .replacement_fees(FROM, nonce, &higher)
+                .max_fee_per_gas,
+            5_000
+        );
+    }
+}
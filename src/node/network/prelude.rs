@@ -1,3 +1,8 @@
+#[cfg(feature = "coingecko")]
+pub use crate::node::network::coingecko::*;
 #[allow(ambiguous_glob_reexports)]
+pub use crate::node::network::eth::*;
 pub use crate::node::network::ltc::*;
 pub use crate::node::network::tron::*;
+#[cfg(feature = "tron-grpc")]
+pub use crate::node::network::tron_grpc::*;
@@ -0,0 +1,244 @@
+//! JSON-RPC 2.0 over HTTP, for the providers and clients that speak it.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::correlation::{DEFAULT_CORRELATION_HEADER, tag_request};
+use crate::metrics::Metrics;
+use crate::node::network::diagnostics::{DiagnosticsSink, read_json};
+use crate::node::network::http::{default_client, send};
+use crate::node::{NodeError, RpcError};
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    id: Value,
+    /// `null` for lookups that found nothing.
+    #[serde(default)]
+    result: Value,
+    error: Option<Value>,
+}
+
+/// One JSON-RPC endpoint: request ids, batching, error envelopes and
+/// retries of transient failures.
+///
+/// Errors the node reports come back as [`NodeError::Rpc`]; a failed HTTP
+/// exchange as the matching transport error.
+pub struct RpcClient {
+    client: Client,
+    url: String,
+    correlation_header: Option<String>,
+    next_id: AtomicU64,
+    retries: u32,
+    retry_delay: Duration,
+    diagnostics: Option<Arc<dyn DiagnosticsSink>>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: default_client(),
+            url: url.to_string(),
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            next_id: AtomicU64::new(1),
+            retries: 0,
+            retry_delay: Duration::from_millis(250),
+            diagnostics: None,
+            metrics: None,
+        }
+    }
+
+    /// Send requests through `client`, e.g. one from
+    /// [`HttpConfig::build`](crate::node::network::HttpConfig::build).
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Header carrying the active [`CorrelationId`](crate::correlation::CorrelationId);
+    /// `None` stops forwarding it.
+    pub fn with_correlation_header(mut self, header: Option<&str>) -> Self {
+        self.correlation_header = header.map(str::to_string);
+        self
+    }
+
+    /// Retry [transient](NodeError::is_transient) failures up to `retries`
+    /// times, waiting `delay` and then twice as long each time. Off by
+    /// default: behind a [`FallbackProvider`](crate::node::FallbackProvider),
+    /// moving on to the next endpoint is usually quicker.
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Hand responses that fail to parse to `sink` as well as returning
+    /// them in the error.
+    pub fn with_diagnostics(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.diagnostics = Some(sink);
+        self
+    }
+
+    /// Report every request's endpoint, status and latency to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Call `method` with `params` and return its `result`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "rpc", skip(self, params))
+    )]
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, NodeError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let response: Response = self.post(&request).await?;
+        result(method, response)
+    }
+
+    /// Call `method` once per entry of `params` in a single batch. Results
+    /// are in the order of `params`, whatever order the node answered in.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "rpc_batch", skip(self, params), fields(calls = params.len()))
+    )]
+    pub async fn batch(&self, method: &str, params: Vec<Value>) -> Vec<Result<Value, NodeError>> {
+        let count = params.len() as u64;
+        let first = self.next_id.fetch_add(count, Ordering::Relaxed);
+        let request: Vec<Value> = params
+            .into_iter()
+            .enumerate()
+            .map(|(offset, params)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": first + offset as u64,
+                    "method": method,
+                    "params": params,
+                })
+            })
+            .collect();
+
+        match self.post::<Vec<Response>>(&Value::Array(request)).await {
+            Ok(responses) => batch_results(method, first, count as usize, responses),
+            Err(e) => (0..count).map(|_| Err(e.clone())).collect(),
+        }
+    }
+
+    async fn post<T: for<'de> Deserialize<'de>>(&self, body: &Value) -> Result<T, NodeError> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            match self.post_once(body).await {
+                Err(e) if e.is_transient() && attempt < self.retries => {
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_once<T: for<'de> Deserialize<'de>>(&self, body: &Value) -> Result<T, NodeError> {
+        let response = send(
+            tag_request(
+                self.client.post(&self.url),
+                self.correlation_header.as_deref(),
+            )
+            .json(body),
+            "json-rpc",
+            self.metrics.as_deref(),
+        )
+        .await?;
+        // Rate limiting and server errors fail here; other statuses still
+        // carry a JSON-RPC error body worth reading.
+        let status = NodeError::Http(response.status().as_u16());
+        if status.is_transient() {
+            return Err(status);
+        }
+        read_json(response, self.diagnostics.as_deref())
+    }
+}
+
+fn result(method: &str, response: Response) -> Result<Value, NodeError> {
+    match response.error {
+        Some(error) => Err(NodeError::Rpc {
+            method: method.to_string(),
+            error: RpcError::from_value(&error),
+        }),
+        None => Ok(response.result),
+    }
+}
+
+/// Order batch responses by request id; nodes may answer out of order.
+fn batch_results(
+    method: &str,
+    first: u64,
+    count: usize,
+    responses: Vec<Response>,
+) -> Vec<Result<Value, NodeError>> {
+    let mut slots: Vec<Option<Response>> = (0..count).map(|_| None).collect();
+    for response in responses {
+        if let Some(slot) = response
+            .id
+            .as_u64()
+            .and_then(|id| id.checked_sub(first))
+            .and_then(|offset| slots.get_mut(offset as usize))
+        {
+            *slot = Some(response);
+        }
+    }
+    slots
+        .into_iter()
+        .map(|response| match response {
+            Some(response) => result(method, response),
+            None => Err(NodeError::Parse(format!("{}: missing from batch", method))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_batches_and_types_errors() {
+        let responses = serde_json::from_value(json!([
+            { "id": 11, "error": { "code": -32601, "message": "method not found" } },
+            { "id": 10, "result": "0x1" },
+            { "id": 99, "result": "0x2" },
+        ]))
+        .unwrap();
+        let results = batch_results("eth_getBalance", 10, 3, responses);
+        assert_eq!(results[0], Ok(json!("0x1")));
+        let Err(NodeError::Rpc { method, error }) = &results[1] else {
+            panic!("expected an RPC error, got {:?}", results[1]);
+        };
+        assert_eq!(method, "eth_getBalance");
+        assert_eq!(error.code, RpcError::METHOD_NOT_FOUND);
+        assert!(matches!(results[2], Err(NodeError::Parse(_))));
+
+        assert_eq!(RpcError::from_value(&json!("busy")).message, "busy");
+        let limited = NodeError::Rpc {
+            method: "eth_call".into(),
+            error: RpcError::from_value(&json!({ "code": -32005, "message": "limit" })),
+        };
+        assert!(limited.is_transient());
+    }
+}
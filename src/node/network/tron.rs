@@ -1,15 +1,56 @@
-use crate::node::{NodeError, Provider, Transaction};
-use crate::wallet::crypto::hash::double_sha256;
+use crate::correlation::{DEFAULT_CORRELATION_HEADER, tag_request};
+use crate::metrics::Metrics;
+use crate::node::details::{TokenTransfer, TvmTxDetails};
+use crate::node::events::{ContractEvent, ContractEventSource, EventPage};
+use crate::node::network::diagnostics::{DiagnosticsSink, read_json};
+use crate::node::network::http::{default_client, send};
+use crate::node::nft::{Nft, NftMetadata, gateway_url};
+use crate::node::swap::HtlcCall;
+use crate::node::token::{
+    Allowance, TokenMetadata, TokenMetadataSource, TokenPreset, USDT_TRON, USDT_TRON_NILE,
+    decode_abi_decimals, decode_abi_string,
+};
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeOptions, NodeError, ProviderHealth, RawTransaction,
+    ReadProvider, SyncStatus, TokenBalance, Transaction, TransactionDetail, TransactionProvider,
+    TxDetails, TxPage, TxQuery, TxSimulation,
+};
+use crate::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::wallet::chain::sdk::{base58check_decode, base58check_encode, decimal_to_u256};
+use crate::wallet::chain::tvm::proto::{self, Contract, ContractType, TransactionRaw};
 use async_trait::async_trait;
+use prost::Message;
 use reqwest::Client;
 use serde::Deserialize;
+use std::sync::Arc;
 
 const TRON_GRID_MAINNET: &str = "https://api.trongrid.io";
 const TRON_GRID_NILE: &str = "https://nile.trongrid.io";
+const TRON_GRID_SHASTA: &str = "https://api.shasta.trongrid.io";
+/// Largest `limit` TronGrid's account endpoints accept.
+const TRONGRID_MAX_PAGE: u32 = 200;
+/// Tron produces a block every 3 seconds.
+pub(super) const TRON_BLOCK_TIME: Duration = Duration::from_secs(3);
+/// A Tron tip older than this is considered stale.
+pub(super) const TRON_SYNC_TOLERANCE_SECS: u64 = 30;
+/// Lifetime of locally built transactions, matching java-tron's default.
+pub(super) const TX_EXPIRATION_MS: i64 = 60_000;
+/// Bandwidth of a signed TRX transfer, in bytes.
+const TRX_TRANSFER_BANDWIDTH: u64 = 268;
+/// Bandwidth a signed transaction uses beyond its raw data: the 65-byte
+/// signature and the 64 bytes reserved for its result, with field headers.
+const SIGNED_TX_OVERHEAD: u64 = 134;
+/// Sun burned per byte of bandwidth once free and staked bandwidth run out.
+pub(crate) const SUN_PER_BANDWIDTH_BYTE: u64 = 1_000;
+/// Sun burned when a transfer activates a new account: 1 TRX plus 0.1 TRX of bandwidth.
+const ACCOUNT_CREATION_FEE: u64 = 1_100_000;
 
 pub struct TronProvider {
     client: Client,
     base_url: String,
+    correlation_header: Option<String>,
+    diagnostics: Option<Arc<dyn DiagnosticsSink>>,
+    metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl Default for TronProvider {
@@ -21,27 +62,95 @@ impl Default for TronProvider {
 impl TronProvider {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: default_client(),
             base_url: TRON_GRID_MAINNET.to_string(),
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            diagnostics: None,
+            metrics: None,
         }
     }
 
     pub fn nile() -> Self {
         Self {
-            client: Client::new(),
+            client: default_client(),
             base_url: TRON_GRID_NILE.to_string(),
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            diagnostics: None,
+            metrics: None,
         }
     }
 
+    pub fn shasta() -> Self {
+        Self::with_url(TRON_GRID_SHASTA.to_string())
+    }
+
     pub fn with_url(url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: default_client(),
             base_url: url,
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_string()),
+            diagnostics: None,
+            metrics: None,
+        }
+    }
+
+    /// Send requests through `client`, e.g. one from [`HttpConfig::build`](crate::node::network::HttpConfig::build)
+    /// with other timeouts or a proxy.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Header carrying the active [`CorrelationId`](crate::correlation::CorrelationId).
+    /// Pass `None` to stop forwarding correlation ids to the upstream API.
+    pub fn with_correlation_header(mut self, header: Option<&str>) -> Self {
+        self.correlation_header = header.map(str::to_string);
+        self
+    }
+
+    /// Hand responses that fail to parse to `sink` as well as returning
+    /// them in the error.
+    pub fn with_diagnostics(mut self, sink: Arc<dyn DiagnosticsSink>) -> Self {
+        self.diagnostics = Some(sink);
+        self
+    }
+
+    /// Report every request's endpoint, status and latency to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Network served by this provider, if it is one of the public TronGrid endpoints.
+    pub fn network(&self) -> Option<&'static str> {
+        match self.base_url.as_str() {
+            TRON_GRID_MAINNET => Some("tron"),
+            TRON_GRID_NILE => Some("tron-nile"),
+            TRON_GRID_SHASTA => Some("tron-shasta"),
+            _ => None,
         }
     }
+
+    /// USDT contract on this provider's network.
+    pub fn usdt(&self) -> Option<TokenPreset> {
+        match self.network()? {
+            "tron" => Some(USDT_TRON),
+            "tron-nile" => Some(USDT_TRON_NILE),
+            _ => None,
+        }
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        tag_request(self.client.get(url), self.correlation_header.as_deref())
+    }
+
+    fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        tag_request(self.client.post(url), self.correlation_header.as_deref())
+    }
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct TronGridResponse<T> {
     data: Vec<T>,
     success: bool,
@@ -50,9 +159,12 @@ struct TronGridResponse<T> {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(dead_code)]
 struct TronGridMeta {
     at: Option<u64>,
     page_size: Option<u64>,
+    /// Present when another page follows.
+    fingerprint: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -96,6 +208,26 @@ struct TronTransferValue {
     to_address: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+struct TronBlockResponse {
+    #[serde(rename = "blockID")]
+    block_id: String,
+    block_header: TronBlockHeader,
+}
+
+#[derive(Deserialize, Debug)]
+struct TronBlockHeader {
+    raw_data: TronBlockRawData,
+}
+
+#[derive(Deserialize, Debug)]
+struct TronBlockRawData {
+    number: u64,
+    /// Block production time in milliseconds.
+    #[serde(default)]
+    timestamp: u64,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 enum TronAmount {
@@ -103,47 +235,258 @@ enum TronAmount {
     String(String),
 }
 
-fn tron_hex_to_base58(address_hex: &str) -> Option<String> {
+pub(super) fn tron_hex_to_base58(address_hex: &str) -> Option<String> {
     let trimmed = address_hex.strip_prefix("0x").unwrap_or(address_hex);
     let bytes = hex::decode(trimmed).ok()?;
     if bytes.len() != 21 {
         return None;
     }
 
-    let checksum_full = double_sha256(&bytes);
-    let mut address_bytes = Vec::with_capacity(25);
-    address_bytes.extend_from_slice(&bytes);
-    address_bytes.extend_from_slice(&checksum_full[..4]);
+    Some(base58check_encode(&bytes))
+}
+
+/// Map a TronGrid transaction object to a [`Transaction`], keeping the original as `raw`.
+fn transaction_from_entry(entry: serde_json::Value) -> Result<Transaction, NodeError> {
+    let tx: TronTransaction =
+        serde_json::from_value(entry.clone()).map_err(|e| NodeError::Parse(e.to_string()))?;
+    let (from, to, value) = tx
+        .raw_data
+        .as_ref()
+        .and_then(|raw| raw.contract.first())
+        .and_then(|contract| contract.parameter.as_ref())
+        .and_then(|param| param.value.as_ref())
+        .map(|value| {
+            let amount = match &value.amount {
+                Some(TronAmount::Number(n)) => n.to_string(),
+                Some(TronAmount::String(s)) => s.clone(),
+                None => "0".to_string(),
+            };
+            let owner_hex = value.owner_address.clone().unwrap_or_default();
+            let to_hex = value.to_address.clone().unwrap_or_default();
+            let from = tron_hex_to_base58(&owner_hex).unwrap_or(owner_hex);
+            let to = tron_hex_to_base58(&to_hex).unwrap_or(to_hex);
+            (from, to, amount)
+        })
+        .unwrap_or_else(|| ("".to_string(), "".to_string(), "0".to_string()));
+
+    let status = tx
+        .ret
+        .first()
+        .and_then(|ret| ret.contract_ret.as_deref())
+        .unwrap_or("UNKNOWN")
+        .to_string();
+
+    Ok(Transaction {
+        hash: tx.tx_id,
+        from,
+        to,
+        value,
+        block_number: tx.block_number.unwrap_or(0),
+        timestamp: tx.block_timestamp.unwrap_or(0),
+        status,
+        raw: Some(entry),
+        details: None,
+    })
+}
+
+/// Map a TronGrid `/transactions/trc20` entry to a [`Transaction`] of the
+/// token transfer, keeping the original as `raw`. The listing carries no
+/// block number, so `block_number` is 0 until one is found.
+fn token_transfer_from_entry(entry: serde_json::Value) -> Result<Transaction, NodeError> {
+    #[derive(Deserialize)]
+    struct TokenInfo {
+        address: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Trc20Transfer {
+        transaction_id: String,
+        #[serde(default)]
+        block_timestamp: u64,
+        from: String,
+        to: String,
+        value: String,
+        token_info: TokenInfo,
+    }
 
-    Some(bs58::encode(address_bytes).into_string())
+    let transfer: Trc20Transfer =
+        serde_json::from_value(entry.clone()).map_err(|e| NodeError::Parse(e.to_string()))?;
+    Ok(Transaction {
+        hash: transfer.transaction_id,
+        from: transfer.from.clone(),
+        to: transfer.to.clone(),
+        value: transfer.value.clone(),
+        block_number: 0,
+        timestamp: transfer.block_timestamp,
+        // Only transfers that emitted their event are listed.
+        status: "SUCCESS".to_string(),
+        raw: Some(entry),
+        details: Some(TxDetails::Tvm(TvmTxDetails {
+            contract_type: "TriggerSmartContract".to_string(),
+            contract_address: Some(transfer.token_info.address.clone()),
+            token_transfers: vec![TokenTransfer {
+                token: transfer.token_info.address,
+                from: transfer.from,
+                to: transfer.to,
+                value: transfer.value,
+            }],
+            ..TvmTxDetails::default()
+        })),
+    })
+}
+
+/// `native` and `tokens` in one list, newest first. A token transfer whose
+/// transaction `native` also lists replaces it, taking its block and status.
+fn merge_token_transfers(native: Vec<Transaction>, tokens: Vec<Transaction>) -> Vec<Transaction> {
+    let mut native: Vec<Option<Transaction>> = native.into_iter().map(Some).collect();
+    let mut merged = Vec::with_capacity(native.len() + tokens.len());
+    for mut token in tokens {
+        if let Some(tx) = native
+            .iter_mut()
+            .find(|tx| tx.as_ref().is_some_and(|tx| tx.hash == token.hash))
+            .and_then(Option::take)
+        {
+            token.block_number = tx.block_number;
+            token.status = tx.status;
+        }
+        merged.push(token);
+    }
+    merged.extend(native.into_iter().flatten());
+    merged.sort_by_key(|tx| std::cmp::Reverse(tx.timestamp));
+    merged
+}
+
+/// Map a TronGrid event object to a [`ContractEvent`], keeping the original as `raw`.
+///
+/// TronGrid lists each argument under its position and, when the ABI names it,
+/// under its name too; only the named ones are kept if there are any.
+fn contract_event_from_entry(entry: serde_json::Value) -> Result<ContractEvent, NodeError> {
+    #[derive(Deserialize)]
+    struct TronEvent {
+        transaction_id: String,
+        #[serde(default)]
+        block_number: u64,
+        #[serde(default)]
+        block_timestamp: u64,
+        #[serde(default)]
+        event_index: u32,
+        event_name: String,
+        contract_address: String,
+        #[serde(default)]
+        result: serde_json::Map<String, serde_json::Value>,
+    }
+
+    let event: TronEvent =
+        serde_json::from_value(entry.clone()).map_err(|e| NodeError::Parse(e.to_string()))?;
+    let named = event.result.keys().any(|k| k.parse::<u32>().is_err());
+    let params = event
+        .result
+        .into_iter()
+        .filter(|(k, _)| !named || k.parse::<u32>().is_err())
+        .map(|(k, v)| {
+            let v = match v {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            (k, v)
+        })
+        .collect();
+
+    Ok(ContractEvent {
+        contract: event.contract_address,
+        name: event.event_name,
+        transaction: event.transaction_id,
+        log_index: event.event_index,
+        block_number: event.block_number,
+        timestamp: event.block_timestamp,
+        params,
+        raw: Some(entry),
+    })
 }
 
 #[async_trait]
-impl Provider for TronProvider {
+impl ReadProvider for TronProvider {
     fn get_decimals(&self) -> u32 {
         6
     }
 
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        Ok(ChainInfo {
+            chain_id: self.network().map(str::to_string),
+            symbol: Some("TRX".to_string()),
+            decimals: 6,
+            block_time: Some(TRON_BLOCK_TIME),
+        })
+    }
+
+    /// The latest page of `address`'s transactions merged with the latest
+    /// page of its TRC-20 transfers, newest first. A TRC-20 transfer takes the
+    /// block of its transaction when both pages list it, else its receipt's.
     async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        let native = self
+            .get_transactions_page(address, &TxQuery::new())
+            .await?
+            .transactions;
+        let tokens = self.trc20_transfers(address).await?;
+        let mut merged = merge_token_transfers(native, tokens);
+
+        for tx in merged
+            .iter_mut()
+            .filter(|tx| tx.block_number == 0 && tx.token().is_some())
+        {
+            // Docs: https://developers.tron.network/reference/gettransactioninfobyid
+            let body = serde_json::json!({ "value": tx.hash });
+            let info = self.post_json("gettransactioninfobyid", &body).await?;
+            if let Some(number) = info.get("blockNumber").and_then(|v| v.as_u64()) {
+                tx.block_number = number;
+            }
+            if let Some(result) = info.pointer("/receipt/result").and_then(|v| v.as_str()) {
+                tx.status = result.to_string();
+            }
+        }
+        Ok(merged)
+    }
+
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
         // Fetch account transactions
         // Docs: https://developers.tron.network/reference/get-account-transaction
         let url = format!("{}/v1/accounts/{}/transactions", self.base_url, address);
+        let order = if query.oldest_first {
+            "block_timestamp,asc"
+        } else {
+            "block_timestamp,desc"
+        };
+        let mut params = vec![("order_by", order.to_string())];
+        if let Some(limit) = query.limit {
+            params.push(("limit", limit.clamp(1, TRONGRID_MAX_PAGE).to_string()));
+        }
+        if let Some(since) = query.since {
+            params.push(("min_timestamp", since.to_string()));
+        }
+        if let Some(until) = query.until {
+            params.push(("max_timestamp", until.to_string()));
+        }
+        if let Some(cursor) = &query.cursor {
+            params.push(("fingerprint", cursor.clone()));
+        }
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let resp = send(
+            self.get(&url).query(&params),
+            "tron",
+            self.metrics.as_deref(),
+        )
+        .await?;
 
         if !resp.status().is_success() {
-            return Err(NodeError::Api(format!("Status: {}", resp.status())));
+            return Err(NodeError::Http(resp.status().as_u16()));
         }
 
-        let body: TronGridResponse<TronTransaction> = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let body: TronGridResponse<serde_json::Value> =
+            read_json(resp, self.diagnostics.as_deref())?;
 
         if !body.success {
             return Err(NodeError::Api(
@@ -154,91 +497,56 @@ impl Provider for TronProvider {
         let transactions = body
             .data
             .into_iter()
-            .map(|tx| {
-                let (from, to, value) = tx
-                    .raw_data
-                    .as_ref()
-                    .and_then(|raw| raw.contract.first())
-                    .and_then(|contract| contract.parameter.as_ref())
-                    .and_then(|param| param.value.as_ref())
-                    .map(|value| {
-                        let amount = match &value.amount {
-                            Some(TronAmount::Number(n)) => n.to_string(),
-                            Some(TronAmount::String(s)) => s.clone(),
-                            None => "0".to_string(),
-                        };
-                        let owner_hex = value.owner_address.clone().unwrap_or_default();
-                        let to_hex = value.to_address.clone().unwrap_or_default();
-                        let from = tron_hex_to_base58(&owner_hex).unwrap_or(owner_hex);
-                        let to = tron_hex_to_base58(&to_hex).unwrap_or(to_hex);
-                        (from, to, amount)
-                    })
-                    .unwrap_or_else(|| ("".to_string(), "".to_string(), "0".to_string()));
-
-                let status = tx
-                    .ret
-                    .first()
-                    .and_then(|ret| ret.contract_ret.as_deref())
-                    .unwrap_or("UNKNOWN")
-                    .to_string();
-
-                Transaction {
-                    hash: tx.tx_id,
-                    from,
-                    to,
-                    value,
-                    block_number: tx.block_number.unwrap_or(0),
-                    timestamp: tx.block_timestamp.unwrap_or(0),
-                    status,
-                }
-            })
-            .collect();
+            .map(transaction_from_entry)
+            .collect::<Result<_, NodeError>>()?;
 
-        Ok(transactions)
+        Ok(TxPage {
+            transactions,
+            next: body.meta.and_then(|meta| meta.fingerprint),
+        })
     }
 
-    async fn get_block_number(&self) -> Result<u64, NodeError> {
-        // https://developers.tron.network/reference/get-now-block
-        // But that's wallet/getnowblock (POST).
-        // Let's use wallet/getnowblock
-        let url = format!("{}/wallet/getnowblock", self.base_url);
-        let resp = self
-            .client
-            .post(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
-
-        #[derive(Deserialize)]
-        struct BlockHeader {
-            raw_data: BlockRawData,
-        }
-        #[derive(Deserialize)]
-        struct BlockRawData {
-            number: u64,
-        }
-        #[derive(Deserialize)]
-        struct BlockResponse {
-            block_header: BlockHeader,
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        match self.transaction_with_info(hash).await? {
+            Some((entry, _)) => transaction_from_entry(entry).map(Some),
+            None => Ok(None),
         }
+    }
 
-        let body: BlockResponse = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        let Some((entry, info)) = self.transaction_with_info(hash).await? else {
+            return Ok(None);
+        };
+        let tx = transaction_from_entry(entry)?;
+        if tx.block_number == 0 {
+            return Ok(Some(TransactionDetail::new(tx, 0, None)));
+        }
+        // Docs: https://developers.tron.network/reference/getblock
+        let block = self
+            .post_json(
+                "getblock",
+                &serde_json::json!({ "id_or_num": tx.block_number.to_string(), "detail": false }),
+            )
+            .await?;
+        let block_hash = block["blockID"].as_str().map(str::to_string);
+        let tip = self.get_block_number().await?;
+        let mut detail = TransactionDetail::new(tx, tip, block_hash);
+        // The receipt leaves out a zero fee, as for transfers paid with bandwidth.
+        detail.fee = Some(info["fee"].as_u64().unwrap_or(0));
+        Ok(Some(detail))
+    }
 
-        Ok(body.block_header.raw_data.number)
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(self.now_block().await?.block_header.raw_data.number)
     }
 
     async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
         // Docs: https://developers.tron.network/reference/account-getaccount
         let url = format!("{}/v1/accounts/{}", self.base_url, address);
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let resp = send(self.get(&url), "tron", self.metrics.as_deref()).await?;
 
         #[derive(Deserialize)]
         struct AccountResponse {
@@ -250,10 +558,7 @@ impl Provider for TronProvider {
             balance: Option<u64>,
         }
 
-        let body: AccountResponse = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let body: AccountResponse = read_json(resp, self.diagnostics.as_deref())?;
 
         if !body.success {
             return Err(NodeError::Api(
@@ -270,80 +575,1671 @@ impl Provider for TronProvider {
         }
     }
 
+    /// Read from the node's balance history, which must have
+    /// `storage.balance.history.lookup` enabled. A time is resolved to the
+    /// block of the address's last transaction at or before it.
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        let number = match at {
+            BalanceAt::Block(number) => number,
+            BalanceAt::Timestamp(timestamp) => {
+                let query = TxQuery::new().until(timestamp).limit(1);
+                let page = self.get_transactions_page(address, &query).await?;
+                match page.transactions.first() {
+                    Some(tx) => tx.block_number,
+                    None => return Ok("0".to_string()),
+                }
+            }
+        };
+
+        // Docs: https://developers.tron.network/reference/getblockbynum
+        let block = self
+            .post_json("getblockbynum", &serde_json::json!({ "num": number }))
+            .await?;
+        let hash = block["blockID"]
+            .as_str()
+            .ok_or_else(|| NodeError::Api(format!("block {} not found", number)))?;
+        // Docs: https://developers.tron.network/reference/getaccountbalance
+        let body = serde_json::json!({
+            "account_identifier": { "address": address },
+            "block_identifier": { "hash": hash, "number": number },
+            "visible": true,
+        });
+        let result = self.post_json("getaccountbalance", &body).await?;
+        Ok(result["balance"].as_u64().unwrap_or(0).to_string())
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        Ok(self.trc20_balance(address, contract).await?.to_string())
+    }
+
+    /// Every TRC-20 token TronGrid has indexed for the account.
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        let url = format!("{}/v1/accounts/{}", self.base_url, address);
+        let resp = send(self.get(&url), "tron", self.metrics.as_deref()).await?;
+        let body = read_json(resp, self.diagnostics.as_deref())?;
+        trc20_balances_from_json(&body, self.network())
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        let started = Instant::now();
+        let block = self.now_block().await?.block_header.raw_data;
+        let latency = started.elapsed();
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let seconds_behind = now_ms.saturating_sub(block.timestamp) / 1000;
+        let sync = if block.timestamp == 0 {
+            SyncStatus::Unknown
+        } else if seconds_behind <= TRON_SYNC_TOLERANCE_SECS {
+            SyncStatus::Synced
+        } else {
+            SyncStatus::Stale { seconds_behind }
+        };
+
+        Ok(ProviderHealth {
+            latency,
+            block_height: block.number,
+            sync,
+        })
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for TronProvider {
     async fn create_transaction(
         &self,
         from: &str,
         to: &str,
         amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        // Built locally from the protobuf definitions rather than via
+        // `wallet/createtransaction`, so the node only supplies the reference block.
+        let contract = Contract::pack(
+            ContractType::TransferContract,
+            &proto::TransferContract {
+                owner_address: decode_address(from)?,
+                to_address: decode_address(to)?,
+                amount: i64::try_from(amount)
+                    .map_err(|_| NodeError::Parse(format!("amount {} out of range", amount)))?,
+            },
+        );
+        let raw_data = self.reference_raw_data(contract).await?;
+        let raw_data_hex = hex::encode(raw_data.encode_to_vec());
+
+        Ok(RawTransaction::Json(serde_json::json!({
+            "txID": hex::encode(raw_data.txid()),
+            "raw_data_hex": raw_data_hex,
+            "visible": true,
+        })))
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        // https://developers.tron.network/reference/broadcasthex
+        let url = format!("{}/wallet/broadcasthex", self.base_url);
+
+        let tx = raw_tx.as_json().ok_or_else(|| {
+            NodeError::Parse(format!(
+                "expected a JSON transaction, got {}",
+                raw_tx.kind()
+            ))
+        })?;
+        // Re-serialize from the signed bytes, ignoring any JSON rendering of raw_data.
+        let raw_data_hex = tx
+            .get("raw_data_hex")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse("missing raw_data_hex".to_string()))?;
+        let raw_data = TransactionRaw::decode(
+            hex::decode(raw_data_hex)
+                .map_err(|e| NodeError::Parse(e.to_string()))?
+                .as_slice(),
+        )
+        .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let signature = tx
+            .get("signature")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|sig| {
+                hex::decode(sig.as_str().unwrap_or_default())
+                    .map_err(|e| NodeError::Parse(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let signed = proto::Transaction {
+            raw_data: Some(raw_data),
+            signature,
+        };
+
+        let resp = send(
+            self.post(&url)
+                .json(&serde_json::json!({ "transaction": hex::encode(signed.encode_to_vec()) })),
+            "tron",
+            self.metrics.as_deref(),
+        )
+        .await?;
+        // TronGrid answers API errors with 200; anything else is the gateway.
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
+
+        if let Some(result) = body.get("result")
+            && result.as_bool() == Some(true)
+        {
+            // Return txID if available, or just "SUCCESS"
+            return Ok(body
+                .get("txid")
+                .and_then(|v| v.as_str())
+                .unwrap_or("SUCCESS")
+                .to_string());
+        }
+
+        Err(NodeError::Api(format!("Broadcast failed: {}", body)))
+    }
+
+    /// TRX transfers have no fee market: the fee is the bandwidth burn, plus the
+    /// activation fee when `to` does not exist yet.
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        _amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        if !self.account_exists(to).await? {
+            return Ok(FeeEstimate::flat(ACCOUNT_CREATION_FEE));
+        }
+        let bandwidth = self.account_resources(from).await?.bandwidth;
+        Ok(FeeEstimate::flat(if bandwidth >= TRX_TRANSFER_BANDWIDTH {
+            0
+        } else {
+            TRX_TRANSFER_BANDWIDTH * SUN_PER_BANDWIDTH_BYTE
+        }))
+    }
+
+    /// A TRX transfer is paid in bandwidth, or in TRX burned for it once
+    /// free and staked bandwidth run out; fails when neither is there.
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        let balance: u64 = self.get_balance(from).await?.parse().unwrap_or(0);
+        let burn = self.estimate_fee(from, to, amount).await?.normal;
+        if balance >= amount.saturating_add(burn) {
+            return Ok(());
+        }
+        let bandwidth = self.account_resources(from).await?.bandwidth;
+        if burn > 0 && bandwidth < TRX_TRANSFER_BANDWIDTH && balance >= amount {
+            return Err(NodeError::InsufficientResources {
+                resource: "bandwidth".to_string(),
+                needed: TRX_TRANSFER_BANDWIDTH,
+                available: bandwidth,
+            });
+        }
+        Err(NodeError::InsufficientResources {
+            resource: "TRX".to_string(),
+            needed: amount.saturating_add(burn),
+            available: balance,
+        })
+    }
+
+    /// Bandwidth from the size of the signed transaction; energy from a
+    /// `triggerconstantcontract` dry run of contract calls, which fails if
+    /// the call would revert. The fee is the TRX burned for whatever the
+    /// owner's free and staked resources do not cover, plus activation of a
+    /// new recipient.
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        let raw_data_hex = raw_tx
+            .as_json()
+            .and_then(|tx| tx.get("raw_data_hex"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse("missing raw_data_hex".to_string()))?;
+        let raw_data_bytes =
+            hex::decode(raw_data_hex).map_err(|e| NodeError::Parse(e.to_string()))?;
+        let raw_data = TransactionRaw::decode(raw_data_bytes.as_slice())
+            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let contract = raw_data
+            .contract
+            .first()
+            .ok_or_else(|| NodeError::Parse("transaction has no contract".to_string()))?;
+        let bandwidth = raw_data_bytes.len() as u64 + SIGNED_TX_OVERHEAD;
+
+        let (owner, energy, activation) = match contract.kind() {
+            Some(ContractType::TransferContract) => {
+                let transfer: proto::TransferContract = contract
+                    .unpack()
+                    .ok_or_else(|| NodeError::Parse("invalid TransferContract".to_string()))?;
+                let to = base58check_encode(&transfer.to_address);
+                let activation = if self.account_exists(&to).await? {
+                    0
+                } else {
+                    ACCOUNT_CREATION_FEE
+                };
+                (transfer.owner_address, 0, activation)
+            }
+            Some(ContractType::TriggerSmartContract) => {
+                let call: proto::TriggerSmartContract = contract
+                    .unpack()
+                    .ok_or_else(|| NodeError::Parse("invalid TriggerSmartContract".to_string()))?;
+                // https://developers.tron.network/reference/triggerconstantcontract
+                let body = serde_json::json!({
+                    "owner_address": base58check_encode(&call.owner_address),
+                    "contract_address": base58check_encode(&call.contract_address),
+                    "data": hex::encode(&call.data),
+                    "call_value": call.call_value,
+                    "visible": true,
+                });
+                let result = self.post_json("triggerconstantcontract", &body).await?;
+                if result.pointer("/result/result").and_then(|v| v.as_bool()) != Some(true) {
+                    return Err(NodeError::Api(format!("dry run failed: {}", result)));
+                }
+                let energy = result
+                    .get("energy_used")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                (call.owner_address, energy, 0)
+            }
+            kind => {
+                return Err(NodeError::Api(format!(
+                    "cannot simulate {}",
+                    kind.map(|k| k.as_str_name()).unwrap_or("unknown contract")
+                )));
+            }
+        };
+
+        let available = self.account_resources(&base58check_encode(&owner)).await?;
+        let mut fee = activation;
+        if available.bandwidth < bandwidth {
+            fee += bandwidth * SUN_PER_BANDWIDTH_BYTE;
+        }
+        if energy > available.energy {
+            fee += (energy - available.energy) * self.energy_price().await?;
+        }
+        Ok(TxSimulation {
+            fee,
+            resources: [
+                ("bandwidth".to_string(), bandwidth),
+                ("energy".to_string(), energy),
+            ]
+            .into(),
+        })
+    }
+
+    /// The balance less the TRX burned for bandwidth, or the account creation
+    /// fee if `to` is not activated yet.
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        let balance: u64 = self.get_balance(from).await?.parse().unwrap_or(0);
+        let fee = self
+            .estimate_fee(from, to, balance)
+            .await?
+            .at(fees.priority);
+        match balance.checked_sub(fee) {
+            Some(amount) if amount > 0 => Ok(amount),
+            _ => Err(NodeError::InsufficientResources {
+                resource: "TRX".to_string(),
+                needed: fee.saturating_add(1),
+                available: balance,
+            }),
+        }
+    }
+}
+
+/// Resource types that can be staked for or delegated to an account.
+/// Untyped stakes in node responses are bandwidth, hence the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TronResource {
+    #[default]
+    Bandwidth,
+    Energy,
+    /// Voting power only, without bandwidth or energy (Stake 2.0); cannot
+    /// be delegated.
+    TronPower,
+}
+
+impl TronResource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TronResource::Bandwidth => "BANDWIDTH",
+            TronResource::Energy => "ENERGY",
+            TronResource::TronPower => "TRON_POWER",
+        }
+    }
+}
+
+impl TronProvider {
+    /// Build an unsigned Stake 2.0 `FreezeBalanceV2Contract` transaction
+    /// staking `amount` Sun for `resource`.
+    pub async fn create_freeze_balance(
+        &self,
+        owner: &str,
+        amount: u64,
+        resource: TronResource,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/freezebalancev2-1
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "frozen_balance": amount,
+            "resource": resource.as_str(),
+            "visible": true,
+        });
+        self.post_wallet_api("freezebalancev2", &body).await
+    }
+
+    /// Build an unsigned `UnfreezeBalanceV2Contract` transaction. The `amount`
+    /// Sun stop yielding `resource` at once but only become withdrawable, with
+    /// [`create_withdraw_expire_unfreeze`](Self::create_withdraw_expire_unfreeze),
+    /// after the network's unstaking period.
+    pub async fn create_unfreeze_balance(
+        &self,
+        owner: &str,
+        amount: u64,
+        resource: TronResource,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/unfreezebalancev2-1
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "unfreeze_balance": amount,
+            "resource": resource.as_str(),
+            "visible": true,
+        });
+        self.post_wallet_api("unfreezebalancev2", &body).await
+    }
+
+    /// Build an unsigned `WithdrawExpireUnfreezeContract` transaction moving
+    /// every unstaked amount past its `unlock_time` back to the liquid balance.
+    pub async fn create_withdraw_expire_unfreeze(
+        &self,
+        owner: &str,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/withdrawexpireunfreeze
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "visible": true,
+        });
+        self.post_wallet_api("withdrawexpireunfreeze", &body).await
+    }
+
+    /// Build an unsigned `VoteWitnessContract` transaction. It replaces every
+    /// vote the account cast before.
+    pub async fn create_vote_witness(
+        &self,
+        owner: &str,
+        votes: &[TronVote],
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/votewitnessaccount
+        let votes: Vec<_> = votes
+            .iter()
+            .map(|vote| serde_json::json!({ "vote_address": vote.address, "vote_count": vote.count }))
+            .collect();
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "votes": votes,
+            "visible": true,
+        });
+        self.post_wallet_api("votewitnessaccount", &body).await
+    }
+
+    /// Build an unsigned `AccountPermissionUpdateContract` transaction
+    /// replacing the owner and active permissions of `owner`. It must be
+    /// signed under the current owner permission.
+    pub async fn create_account_permission_update(
+        &self,
+        owner: &str,
+        owner_permission: &TronPermission,
+        actives: &[TronPermission],
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/accountpermissionupdate
+        let permission = |kind: &str, permission: &TronPermission| {
+            let mut json = serde_json::json!({
+                "type": kind,
+                "permission_name": permission.name,
+                "threshold": permission.threshold,
+                "keys": permission
+                    .keys
+                    .iter()
+                    .map(|(address, weight)| serde_json::json!({ "address": address, "weight": weight }))
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(operations) = &permission.operations {
+                json["operations"] = serde_json::Value::String(operations.clone());
+            }
+            json
+        };
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "owner": permission("Owner", owner_permission),
+            "actives": actives
+                .iter()
+                .map(|active| permission("Active", active))
+                .collect::<Vec<_>>(),
+            "visible": true,
+        });
+        self.post_wallet_api("accountpermissionupdate", &body).await
+    }
+
+    /// Build an unsigned Stake 2.0 `DelegateResourceContract` transaction.
+    /// `balance` is the amount of staked TRX (in Sun) whose resources are lent to `receiver`.
+    pub async fn create_delegate_resource(
+        &self,
+        owner: &str,
+        receiver: &str,
+        balance: u64,
+        resource: TronResource,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/delegateresource-1
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "receiver_address": receiver,
+            "balance": balance,
+            "resource": resource.as_str(),
+            "lock": false,
+            "visible": true,
+        });
+        self.post_wallet_api("delegateresource", &body).await
+    }
+
+    /// Build an unsigned `UnDelegateResourceContract` transaction reclaiming delegated resources.
+    pub async fn create_undelegate_resource(
+        &self,
+        owner: &str,
+        receiver: &str,
+        balance: u64,
+        resource: TronResource,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/undelegateresource-1
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "receiver_address": receiver,
+            "balance": balance,
+            "resource": resource.as_str(),
+            "visible": true,
+        });
+        self.post_wallet_api("undelegateresource", &body).await
+    }
+
+    /// TRC-20 `balanceOf(owner)` in the token's base unit.
+    pub async fn trc20_balance(&self, owner: &str, contract: &str) -> Result<u128, NodeError> {
+        let bytes = self
+            .constant_call(owner, contract, "balanceOf(address)", &abi_address(owner)?)
+            .await?;
+        balance_word(&bytes)
+    }
+
+    /// TRC-20 `allowance(owner, spender)`: what `spender` may move of `owner`'s tokens.
+    pub async fn trc20_allowance(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+    ) -> Result<Allowance, NodeError> {
+        let parameter = format!("{}{}", abi_address(owner)?, abi_address(spender)?);
+        let bytes = self
+            .constant_call(owner, contract, "allowance(address,address)", &parameter)
+            .await?;
+        Allowance::from_word(&bytes)
+    }
+
+    /// Energy a TRC-20 `approve` would consume, from a dry run of the call.
+    pub async fn estimate_trc20_approve_energy(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+        amount: Allowance,
+    ) -> Result<u64, NodeError> {
+        self.estimate_energy(
+            owner,
+            contract,
+            "approve(address,uint256)",
+            &abi_approve(spender, amount)?,
+        )
+        .await
+    }
+
+    /// Energy a TRC-20 `transfer` would consume, from a dry run of the call.
+    pub async fn estimate_trc20_transfer_energy(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: u128,
+    ) -> Result<u64, NodeError> {
+        self.estimate_energy(
+            from,
+            contract,
+            "transfer(address,uint256)",
+            &abi_transfer(to, amount)?,
+        )
+        .await
+    }
+
+    /// Energy `owner` calling `selector` (e.g. `"approve(address,uint256)"`)
+    /// on `contract` with the hex-encoded `parameter` would consume, from a
+    /// dry run of the call.
+    pub async fn estimate_energy(
+        &self,
+        owner: &str,
+        contract: &str,
+        selector: &str,
+        parameter: &str,
+    ) -> Result<u64, NodeError> {
+        // https://developers.tron.network/reference/triggerconstantcontract
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "contract_address": contract,
+            "function_selector": selector,
+            "parameter": parameter,
+            "visible": true,
+        });
+        let result = self.post_json("triggerconstantcontract", &body).await?;
+        result
+            .get("energy_used")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| NodeError::Api(format!("{} dry run failed: {}", selector, result)))
+    }
+
+    /// Build an unsigned TRC-20 `transfer(to, amount)` capped at `fee_limit` Sun.
+    pub async fn create_trc20_transfer(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: u128,
+        fee_limit: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.trigger_smart_contract(
+            from,
+            contract,
+            "transfer(address,uint256)",
+            &abi_transfer(to, amount)?,
+            0,
+            fee_limit,
+        )
+        .await
+    }
+
+    /// Build an unsigned TRC-20 `approve(spender, amount)` capped at `fee_limit` Sun.
+    pub async fn create_trc20_approve(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+        amount: Allowance,
+        fee_limit: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.trigger_smart_contract(
+            owner,
+            contract,
+            "approve(address,uint256)",
+            &abi_approve(spender, amount)?,
+            0,
+            fee_limit,
+        )
+        .await
+    }
+
+    /// Build an unsigned `HashedTimelock` call capped at `fee_limit` Sun;
+    /// `call_value` is the Sun [`HtlcCall::NewContract`] locks and zero otherwise.
+    pub async fn create_htlc_call(
+        &self,
+        from: &str,
+        contract: &str,
+        call: &HtlcCall,
+        call_value: u64,
+        fee_limit: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.trigger_smart_contract(
+            from,
+            contract,
+            call.signature(),
+            &call.parameter(),
+            call_value,
+            fee_limit,
+        )
+        .await
+    }
+
+    /// TRC-721 tokens `owner` holds, as far as its latest token transfers on
+    /// TronGrid tell: each token received there that `ownerOf` still
+    /// attributes to `owner`.
+    pub async fn trc721_tokens(&self, owner: &str) -> Result<Vec<Nft>, NodeError> {
+        let mut nfts: Vec<Nft> = Vec::new();
+        for tx in self.trc20_transfers(owner).await? {
+            let (Some(entry), Some(contract)) = (&tx.raw, tx.token()) else {
+                continue;
+            };
+            let info = &entry["token_info"];
+            // TRC-721 transfers are listed with the token id as their value
+            // and no decimals.
+            if tx.to != owner
+                || info["decimals"].as_u64().unwrap_or(0) != 0
+                || nfts
+                    .iter()
+                    .any(|nft| nft.contract == contract && nft.token_id == tx.value)
+            {
+                continue;
+            }
+            // Fungible tokens without decimals have no `ownerOf`.
+            match self.trc721_owner_of(contract, &tx.value).await {
+                Ok(holder) if holder == owner => {}
+                Ok(_) | Err(NodeError::Api(_) | NodeError::Parse(_)) => continue,
+                Err(e) => return Err(e),
+            }
+            let text = |field: &str| info[field].as_str().map(str::to_string);
+            nfts.push(Nft {
+                contract: contract.to_string(),
+                token_id: tx.value.clone(),
+                owner: owner.to_string(),
+                collection: text("name"),
+                symbol: text("symbol"),
+                token_uri: self.trc721_token_uri(contract, &tx.value).await.ok(),
+            });
+        }
+        Ok(nfts)
+    }
+
+    /// TRC-721 `ownerOf(token_id)`.
+    pub async fn trc721_owner_of(
+        &self,
+        contract: &str,
+        token_id: &str,
     ) -> Result<String, NodeError> {
-        // https://developers.tron.network/reference/createtransaction
-        let url = format!("{}/wallet/createtransaction", self.base_url);
+        let bytes = self
+            .constant_call(
+                contract,
+                contract,
+                "ownerOf(uint256)",
+                &abi_token_id(token_id)?,
+            )
+            .await?;
+        if bytes.len() != 32 {
+            return Err(NodeError::Parse(format!(
+                "ownerOf returned {}",
+                hex::encode(&bytes)
+            )));
+        }
+        let mut payload = vec![0x41];
+        payload.extend_from_slice(&bytes[12..]);
+        Ok(base58check_encode(&payload))
+    }
 
-        #[derive(serde::Serialize)]
-        struct CreateTxReq {
-            to_address: String,
-            owner_address: String,
-            amount: u64,
+    /// TRC-721 `tokenURI(token_id)`: where the token's metadata lives.
+    pub async fn trc721_token_uri(
+        &self,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<String, NodeError> {
+        let bytes = self
+            .constant_call(
+                contract,
+                contract,
+                "tokenURI(uint256)",
+                &abi_token_id(token_id)?,
+            )
+            .await?;
+        decode_abi_string(&bytes)
+    }
+
+    /// The metadata document `token_uri` points at: fetched over HTTP(S),
+    /// through [`IPFS_GATEWAY`](crate::node::nft::IPFS_GATEWAY) for `ipfs://`,
+    /// or decoded from a `data:application/json` URI. Fetched without the
+    /// provider's client, so its headers (API keys) stay with TronGrid.
+    pub async fn nft_metadata(&self, token_uri: &str) -> Result<NftMetadata, NodeError> {
+        let parse = |bytes: &[u8]| {
+            serde_json::from_slice(bytes)
+                .map(NftMetadata::from_document)
+                .map_err(|e| NodeError::Parse(format!("NFT metadata: {}", e)))
+        };
+        if let Some(encoded) = token_uri.strip_prefix("data:application/json;base64,") {
+            use base64::Engine;
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| NodeError::Parse(format!("NFT metadata: {}", e)))?;
+            return parse(&bytes);
+        }
+        if let Some(json) = token_uri.strip_prefix("data:application/json,") {
+            return parse(json.as_bytes());
         }
+        let resp = send(
+            default_client().get(gateway_url(token_uri)),
+            "nft-metadata",
+            self.metrics.as_deref(),
+        )
+        .await?;
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+        parse(resp.body())
+    }
 
-        let req = CreateTxReq {
-            to_address: to.to_string(),
-            owner_address: from.to_string(),
-            amount,
+    /// Energy a TRC-721 `transferFrom` would consume, from a dry run of the call.
+    pub async fn estimate_trc721_transfer_energy(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<u64, NodeError> {
+        self.estimate_energy(
+            from,
+            contract,
+            "transferFrom(address,address,uint256)",
+            &abi_transfer_from(from, to, token_id)?,
+        )
+        .await
+    }
+
+    /// Build an unsigned TRC-721 `transferFrom(from, to, token_id)` capped at
+    /// `fee_limit` Sun.
+    pub async fn create_trc721_transfer(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        token_id: &str,
+        fee_limit: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.trigger_smart_contract(
+            from,
+            contract,
+            "transferFrom(address,address,uint256)",
+            &abi_transfer_from(from, to, token_id)?,
+            0,
+            fee_limit,
+        )
+        .await
+    }
+
+    async fn trigger_smart_contract(
+        &self,
+        from: &str,
+        contract: &str,
+        function_selector: &str,
+        parameter: &str,
+        call_value: u64,
+        fee_limit: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        // https://developers.tron.network/reference/triggersmartcontract
+        let body = serde_json::json!({
+            "owner_address": from,
+            "contract_address": contract,
+            "function_selector": function_selector,
+            "parameter": parameter,
+            "fee_limit": fee_limit,
+            "call_value": call_value,
+            "visible": true,
+        });
+        let result = self.post_json("triggersmartcontract", &body).await?;
+        if result.pointer("/result/result").and_then(|v| v.as_bool()) != Some(true) {
+            return Err(NodeError::Api(format!(
+                "triggersmartcontract failed: {}",
+                result
+            )));
+        }
+        result
+            .get("transaction")
+            .cloned()
+            .map(RawTransaction::Json)
+            .ok_or_else(|| NodeError::Parse("missing transaction".to_string()))
+    }
+
+    /// Energy and bandwidth currently available to `address`.
+    pub async fn account_resources(
+        &self,
+        address: &str,
+    ) -> Result<TronAccountResources, NodeError> {
+        // https://developers.tron.network/reference/getaccountresource
+        let body = serde_json::json!({ "address": address, "visible": true });
+        let result = self.post_json("getaccountresource", &body).await?;
+        let field = |name: &str| result.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        Ok(TronAccountResources {
+            energy: field("EnergyLimit").saturating_sub(field("EnergyUsed")),
+            bandwidth: field("freeNetLimit").saturating_sub(field("freeNetUsed"))
+                + field("NetLimit").saturating_sub(field("NetUsed")),
+        })
+    }
+
+    /// Sun of TRX that must be staked, and delegated, to provide `units` of
+    /// `resource` at the network's current ratio of stake to resources.
+    pub async fn stake_for_resource(
+        &self,
+        address: &str,
+        resource: TronResource,
+        units: u64,
+    ) -> Result<u64, NodeError> {
+        // https://developers.tron.network/reference/getaccountresource
+        let body = serde_json::json!({ "address": address, "visible": true });
+        let result = self.post_json("getaccountresource", &body).await?;
+        let field = |name: &str| result.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        let (limit, weight) = match resource {
+            TronResource::Energy => (field("TotalEnergyLimit"), field("TotalEnergyWeight")),
+            TronResource::Bandwidth => (field("TotalNetLimit"), field("TotalNetWeight")),
+            TronResource::TronPower => {
+                return Err(NodeError::Api("TRON_POWER cannot be delegated".to_string()));
+            }
         };
+        stake_for(units, limit, weight)
+            .ok_or_else(|| NodeError::Parse(format!("no network totals for {}", resource.as_str())))
+    }
 
-        let resp = self
-            .client
-            .post(&url)
-            .json(&req)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+    /// Balance, stakes, votes and permissions of `address`; `None` if the
+    /// account has not been activated.
+    pub async fn get_account(&self, address: &str) -> Result<Option<TronAccount>, NodeError> {
+        // https://developers.tron.network/reference/walletgetaccount
+        let body = serde_json::json!({ "address": address, "visible": true });
+        account_from_json(self.post_json("getaccount", &body).await?)
+    }
+
+    /// Whether `address` has been activated on chain.
+    pub async fn account_exists(&self, address: &str) -> Result<bool, NodeError> {
+        // https://developers.tron.network/reference/walletgetaccount
+        let body = serde_json::json!({ "address": address, "visible": true });
+        let result = self.post_json("getaccount", &body).await?;
+        Ok(result.get("address").is_some())
+    }
+
+    /// Current price of one unit of energy, in Sun.
+    pub async fn energy_price(&self) -> Result<u64, NodeError> {
+        // https://developers.tron.network/reference/wallet-getchainparameters
+        let result = self
+            .post_json("getchainparameters", &serde_json::json!({}))
+            .await?;
+        result
+            .get("chainParameter")
+            .and_then(|v| v.as_array())
+            .and_then(|params| {
+                params
+                    .iter()
+                    .find(|p| p.get("key").and_then(|k| k.as_str()) == Some("getEnergyFee"))
+            })
+            .and_then(|p| p.get("value"))
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| NodeError::Parse("getEnergyFee not found".to_string()))
+    }
+
+    /// Events named `event` (e.g. `Transfer`) that `contract` emitted, one page
+    /// at a time. `range` bounds block timestamps in milliseconds and carries
+    /// the page cursor and order, as for account history.
+    pub async fn get_contract_events(
+        &self,
+        contract: &str,
+        event: &str,
+        range: &TxQuery,
+    ) -> Result<EventPage, NodeError> {
+        // https://developers.tron.network/reference/events-by-contract-address
+        let url = format!("{}/v1/contracts/{}/events", self.base_url, contract);
+        let order = if range.oldest_first {
+            "block_timestamp,asc"
+        } else {
+            "block_timestamp,desc"
+        };
+        let mut params = vec![
+            ("event_name", event.to_string()),
+            ("order_by", order.to_string()),
+        ];
+        if let Some(limit) = range.limit {
+            params.push(("limit", limit.clamp(1, TRONGRID_MAX_PAGE).to_string()));
+        }
+        if let Some(since) = range.since {
+            params.push(("min_block_timestamp", since.to_string()));
+        }
+        if let Some(until) = range.until {
+            params.push(("max_block_timestamp", until.to_string()));
+        }
+        if let Some(cursor) = &range.cursor {
+            params.push(("fingerprint", cursor.clone()));
+        }
+
+        let resp = send(
+            self.get(&url).query(&params),
+            "tron",
+            self.metrics.as_deref(),
+        )
+        .await?;
+
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        let body: TronGridResponse<serde_json::Value> =
+            read_json(resp, self.diagnostics.as_deref())?;
+
+        if !body.success {
+            return Err(NodeError::Api(
+                "TronGrid returned success: false".to_string(),
+            ));
+        }
+
+        let events = body
+            .data
+            .into_iter()
+            .map(contract_event_from_entry)
+            .collect::<Result<_, NodeError>>()?;
+
+        Ok(EventPage {
+            events,
+            next: body.meta.and_then(|meta| meta.fingerprint),
+        })
+    }
+
+    /// Unsigned `raw_data` for `contract`, referencing the current block.
+    async fn reference_raw_data(&self, contract: Contract) -> Result<TransactionRaw, NodeError> {
+        let block = self.now_block().await?;
+        let block_id = hex::decode(&block.block_id).map_err(|e| NodeError::Parse(e.to_string()))?;
+        if block_id.len() != 32 {
+            return Err(NodeError::Parse(format!(
+                "invalid blockID {}",
+                block.block_id
+            )));
+        }
+        let header = block.block_header.raw_data;
+        let timestamp = header.timestamp as i64;
+
+        Ok(TransactionRaw {
+            ref_block_bytes: header.number.to_be_bytes()[6..8].to_vec(),
+            ref_block_hash: block_id[8..16].to_vec(),
+            expiration: timestamp + TX_EXPIRATION_MS,
+            contract: vec![contract],
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    /// Latest block header via `wallet/getnowblock`.
+    async fn now_block(&self) -> Result<TronBlockResponse, NodeError> {
+        // https://developers.tron.network/reference/wallet-getnowblock
+        let url = format!("{}/wallet/getnowblock", self.base_url);
+        let resp = send(self.post(&url), "tron", self.metrics.as_deref()).await?;
 
-        // Tron returns the full JSON transaction object. We just return it as string.
-        let body: serde_json::Value = resp
-            .json()
+        let body: TronBlockResponse = read_json(resp, self.diagnostics.as_deref())?;
+
+        Ok(body)
+    }
+
+    /// POST to a `/wallet/*` endpoint that returns an unsigned transaction object.
+    async fn post_wallet_api(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<RawTransaction, NodeError> {
+        self.post_json(endpoint, body)
             .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+            .map(RawTransaction::Json)
+    }
+
+    /// Return data of a read-only call of `selector` on `contract`.
+    async fn constant_call(
+        &self,
+        owner: &str,
+        contract: &str,
+        selector: &str,
+        parameter: &str,
+    ) -> Result<Vec<u8>, NodeError> {
+        // https://developers.tron.network/reference/triggerconstantcontract
+        let body = serde_json::json!({
+            "owner_address": owner,
+            "contract_address": contract,
+            "function_selector": selector,
+            "parameter": parameter,
+            "visible": true,
+        });
+        let result = self.post_json("triggerconstantcontract", &body).await?;
+        let word = result
+            .pointer("/constant_result/0")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse(format!("missing constant_result: {}", result)))?;
+        hex::decode(word).map_err(|e| NodeError::Parse(e.to_string()))
+    }
+
+    /// The latest page of `address`'s TRC-20 transfers, either direction.
+    async fn trc20_transfers(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        // Docs: https://developers.tron.network/reference/trc20-transaction-information-by-account-address
+        let url = format!(
+            "{}/v1/accounts/{}/transactions/trc20",
+            self.base_url, address
+        );
+        let resp = send(
+            self.get(&url)
+                .query(&[("order_by", "block_timestamp,desc")]),
+            "tron",
+            self.metrics.as_deref(),
+        )
+        .await?;
+
+        if !resp.status().is_success() {
+            return Err(NodeError::Http(resp.status().as_u16()));
+        }
+
+        let body: TronGridResponse<serde_json::Value> =
+            read_json(resp, self.diagnostics.as_deref())?;
+
+        if !body.success {
+            return Err(NodeError::Api(
+                "TronGrid returned success: false".to_string(),
+            ));
+        }
+
+        body.data
+            .into_iter()
+            .map(token_transfer_from_entry)
+            .collect()
+    }
+
+    /// `hash`'s transaction, with the block its receipt names merged in, and
+    /// the receipt; `None` if TronGrid does not know it.
+    async fn transaction_with_info(
+        &self,
+        hash: &str,
+    ) -> Result<Option<(serde_json::Value, serde_json::Value)>, NodeError> {
+        // Docs: https://developers.tron.network/reference/gettransactionbyid
+        let body = serde_json::json!({ "value": hash });
+        let mut entry = self.post_json("gettransactionbyid", &body).await?;
+        if entry.get("txID").is_none() {
+            return Ok(None);
+        }
+        // The transaction itself carries no block; its receipt does.
+        let info = self.post_json("gettransactioninfobyid", &body).await?;
+        if let Some(number) = info.get("blockNumber") {
+            entry["blockNumber"] = number.clone();
+        }
+        if let Some(timestamp) = info.get("blockTimeStamp") {
+            entry["block_timestamp"] = timestamp.clone();
+        }
+        Ok(Some((entry, info)))
+    }
+
+    /// POST to a `/wallet/*` endpoint, failing on an `Error` key in the response.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "trongrid", skip(self, body))
+    )]
+    async fn post_json(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, NodeError> {
+        let url = format!("{}/wallet/{}", self.base_url, endpoint);
+        let resp = send(self.post(&url).json(body), "tron", self.metrics.as_deref()).await?;
+
+        let body: serde_json::Value = read_json(resp, self.diagnostics.as_deref())?;
 
         if let Some(err) = body.get("Error") {
             return Err(NodeError::Api(err.to_string()));
         }
 
-        Ok(body.to_string())
+        Ok(body)
     }
+}
 
-    async fn broadcast_transaction(&self, raw_tx: &str) -> Result<String, NodeError> {
-        // https://developers.tron.network/reference/broadcasttransaction
-        let url = format!("{}/wallet/broadcasttransaction", self.base_url);
+#[async_trait]
+impl ContractEventSource for TronProvider {
+    async fn contract_events(
+        &self,
+        contract: &str,
+        event: &str,
+        range: &TxQuery,
+    ) -> Result<EventPage, NodeError> {
+        self.get_contract_events(contract, event, range).await
+    }
+}
 
-        let tx: serde_json::Value =
-            serde_json::from_str(raw_tx).map_err(|e| NodeError::Parse(e.to_string()))?;
+/// Spendable resources of a Tron account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TronAccountResources {
+    pub energy: u64,
+    /// Free plus staked bandwidth, in bytes.
+    pub bandwidth: u64,
+}
 
-        let resp = self
-            .client
-            .post(&url)
-            .json(&tx)
-            .send()
-            .await
-            .map_err(|e| NodeError::Network(e.to_string()))?;
+/// TRX staked for a resource, in Sun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TronStake {
+    pub resource: TronResource,
+    pub amount: u64,
+    /// When a Stake 1.0 freeze can be undone, in milliseconds; Stake 2.0
+    /// stakes have no lock and report `None`.
+    pub expire_time: Option<u64>,
+}
 
-        let body: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| NodeError::Parse(e.to_string()))?;
+/// TRX being unstaked, withdrawable once `unlock_time` (milliseconds) passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TronUnstake {
+    pub resource: TronResource,
+    pub amount: u64,
+    pub unlock_time: u64,
+}
 
-        if let Some(result) = body.get("result")
-            && result.as_bool() == Some(true)
-        {
-            // Return txID if available, or just "SUCCESS"
-            return Ok(body
-                .get("txid")
-                .and_then(|v| v.as_str())
-                .unwrap_or("SUCCESS")
-                .to_string());
-        }
+/// Votes cast for one super representative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TronVote {
+    pub address: String,
+    pub count: u64,
+}
 
-        Err(NodeError::Api(format!("Broadcast failed: {}", body)))
+/// Keys allowed to act for an account and the weight they need together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TronPermission {
+    pub name: String,
+    pub threshold: u64,
+    /// Address and weight of each key.
+    pub keys: Vec<(String, u64)>,
+    /// Hex bitmask of the contract types an active permission may sign, bit
+    /// `n` for contract type `n`; `None` for the owner permission.
+    pub operations: Option<String>,
+}
+
+/// On-chain state of an activated Tron account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TronAccount {
+    pub address: String,
+    /// Liquid balance, in Sun.
+    pub balance: u64,
+    /// Activation time, in milliseconds.
+    pub create_time: u64,
+    /// Stakes held for this account's own use.
+    pub staked: Vec<TronStake>,
+    /// Stakes whose resources are delegated to other accounts.
+    pub delegated: Vec<TronStake>,
+    pub unstaking: Vec<TronUnstake>,
+    pub votes: Vec<TronVote>,
+    pub owner_permission: Option<TronPermission>,
+    pub active_permissions: Vec<TronPermission>,
+}
+
+impl TronAccount {
+    /// Sun staked for `resource`, delegated or not.
+    pub fn staked_for(&self, resource: TronResource) -> u64 {
+        self.staked
+            .iter()
+            .chain(&self.delegated)
+            .filter(|stake| stake.resource == resource)
+            .map(|stake| stake.amount)
+            .sum()
+    }
+
+    /// Liquid, staked, delegated and unstaking Sun together.
+    pub fn total_balance(&self) -> u64 {
+        let staked: u64 = self
+            .staked
+            .iter()
+            .chain(&self.delegated)
+            .map(|stake| stake.amount)
+            .sum();
+        let unstaking: u64 = self.unstaking.iter().map(|u| u.amount).sum();
+        self.balance + staked + unstaking
+    }
+
+    /// Votes available to cast: one per staked TRX.
+    pub fn voting_power(&self) -> u64 {
+        self.staked
+            .iter()
+            .chain(&self.delegated)
+            .map(|stake| stake.amount)
+            .sum::<u64>()
+            / 1_000_000
+    }
+}
+
+#[async_trait]
+impl TokenMetadataSource for TronProvider {
+    /// The contract calls itself: TronGrid requires an owner address but
+    /// constant calls do not need it to hold anything.
+    async fn token_metadata(&self, contract: &str) -> Result<TokenMetadata, NodeError> {
+        let call = |selector| self.constant_call(contract, contract, selector, "");
+        Ok(TokenMetadata {
+            contract: contract.to_string(),
+            symbol: decode_abi_string(&call("symbol()").await?)?,
+            name: decode_abi_string(&call("name()").await?)?,
+            decimals: decode_abi_decimals(&call("decimals()").await?)?,
+        })
+    }
+}
+
+/// The `trc20` list of a `/v1/accounts` response: one single-entry object
+/// of contract to balance per token.
+fn trc20_balances_from_json(
+    body: &serde_json::Value,
+    network: Option<&str>,
+) -> Result<Vec<TokenBalance>, NodeError> {
+    if body.get("success") == Some(&serde_json::Value::Bool(false)) {
+        return Err(NodeError::Api(
+            "TronGrid returned success: false".to_string(),
+        ));
+    }
+    let Some(tokens) = body.pointer("/data/0/trc20").and_then(|v| v.as_array()) else {
+        return Ok(Vec::new());
+    };
+    tokens
+        .iter()
+        .filter_map(|token| token.as_object())
+        .flatten()
+        .map(|(contract, balance)| {
+            let balance = balance
+                .as_str()
+                .ok_or_else(|| NodeError::Parse(format!("trc20 balance of {}", contract)))?;
+            Ok(TokenBalance::new(network, contract, balance.to_string()))
+        })
+        .collect()
+}
+
+/// Sun of stake whose share of `total_limit` units, given `total_weight`
+/// TRX staked network-wide, is at least `units`.
+fn stake_for(units: u64, total_limit: u64, total_weight: u64) -> Option<u64> {
+    if total_limit == 0 || total_weight == 0 {
+        return None;
+    }
+    let sun = (units as u128 * total_weight as u128 * 1_000_000).div_ceil(total_limit as u128);
+    u64::try_from(sun).ok()
+}
+
+/// Read a `wallet/getaccount` response (with `visible: true`), in which
+/// Stake 1.0 freezes and Stake 2.0 stakes sit in different fields.
+fn account_from_json(value: serde_json::Value) -> Result<Option<TronAccount>, NodeError> {
+    #[derive(Deserialize)]
+    struct Raw {
+        address: Option<String>,
+        #[serde(default)]
+        balance: u64,
+        #[serde(default)]
+        create_time: u64,
+        #[serde(default)]
+        frozen: Vec<Frozen>,
+        #[serde(default, rename = "frozenV2")]
+        frozen_v2: Vec<FrozenV2>,
+        #[serde(default, rename = "unfrozenV2")]
+        unfrozen_v2: Vec<UnfrozenV2>,
+        #[serde(default)]
+        account_resource: RawResource,
+        #[serde(default, rename = "delegated_frozenV2_balance_for_bandwidth")]
+        delegated_bandwidth: u64,
+        #[serde(default)]
+        votes: Vec<Vote>,
+        owner_permission: Option<Permission>,
+        #[serde(default)]
+        active_permission: Vec<Permission>,
+    }
+    #[derive(Deserialize, Default)]
+    struct RawResource {
+        frozen_balance_for_energy: Option<Frozen>,
+        #[serde(default, rename = "delegated_frozenV2_balance_for_energy")]
+        delegated_energy: u64,
+    }
+    #[derive(Deserialize)]
+    struct Frozen {
+        frozen_balance: u64,
+        expire_time: u64,
+    }
+    #[derive(Deserialize)]
+    struct FrozenV2 {
+        #[serde(default, rename = "type")]
+        resource: TronResource,
+        #[serde(default)]
+        amount: u64,
+    }
+    #[derive(Deserialize)]
+    struct UnfrozenV2 {
+        #[serde(default, rename = "type")]
+        resource: TronResource,
+        unfreeze_amount: u64,
+        unfreeze_expire_time: u64,
+    }
+    #[derive(Deserialize)]
+    struct Vote {
+        vote_address: String,
+        vote_count: u64,
+    }
+    #[derive(Deserialize)]
+    struct Permission {
+        #[serde(default)]
+        permission_name: String,
+        threshold: u64,
+        #[serde(default)]
+        keys: Vec<Key>,
+        operations: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Key {
+        address: String,
+        weight: u64,
+    }
+
+    let raw: Raw = serde_json::from_value(value).map_err(|e| NodeError::Parse(e.to_string()))?;
+    let Some(address) = raw.address else {
+        return Ok(None);
+    };
+    let v1 = |resource, frozen: &Frozen| TronStake {
+        resource,
+        amount: frozen.frozen_balance,
+        expire_time: Some(frozen.expire_time),
+    };
+    let v2 = |resource, amount| TronStake {
+        resource,
+        amount,
+        expire_time: None,
+    };
+    let staked = raw
+        .frozen
+        .iter()
+        .map(|f| v1(TronResource::Bandwidth, f))
+        .chain(
+            raw.account_resource
+                .frozen_balance_for_energy
+                .iter()
+                .map(|f| v1(TronResource::Energy, f)),
+        )
+        .chain(raw.frozen_v2.iter().map(|f| v2(f.resource, f.amount)))
+        .filter(|stake| stake.amount > 0)
+        .collect();
+    let delegated = [
+        v2(TronResource::Bandwidth, raw.delegated_bandwidth),
+        v2(TronResource::Energy, raw.account_resource.delegated_energy),
+    ]
+    .into_iter()
+    .filter(|stake| stake.amount > 0)
+    .collect();
+    let permission = |p: Permission| TronPermission {
+        name: p.permission_name,
+        threshold: p.threshold,
+        keys: p.keys.into_iter().map(|k| (k.address, k.weight)).collect(),
+        operations: p.operations,
+    };
+    Ok(Some(TronAccount {
+        address,
+        balance: raw.balance,
+        create_time: raw.create_time,
+        staked,
+        delegated,
+        unstaking: raw
+            .unfrozen_v2
+            .into_iter()
+            .map(|u| TronUnstake {
+                resource: u.resource,
+                amount: u.unfreeze_amount,
+                unlock_time: u.unfreeze_expire_time,
+            })
+            .collect(),
+        votes: raw
+            .votes
+            .into_iter()
+            .map(|v| TronVote {
+                address: v.vote_address,
+                count: v.vote_count,
+            })
+            .collect(),
+        owner_permission: raw.owner_permission.map(permission),
+        active_permissions: raw.active_permission.into_iter().map(permission).collect(),
+    }))
+}
+
+/// 21-byte (`0x41`-prefixed) form of a base58 Tron address.
+pub(super) fn decode_address(address: &str) -> Result<Vec<u8>, NodeError> {
+    let payload = base58check_decode(address).map_err(|e| NodeError::Parse(e.to_string()))?;
+    if payload.len() != 21 {
+        return Err(NodeError::Parse(format!(
+            "invalid Tron address {}",
+            address
+        )));
+    }
+    Ok(payload)
+}
+
+/// A `uint256` balance returned by `balanceOf`, if it fits in a `u128`.
+pub(super) fn balance_word(bytes: &[u8]) -> Result<u128, NodeError> {
+    if bytes.len() != 32 || bytes[..16].iter().any(|&b| b != 0) {
+        return Err(NodeError::Parse(format!(
+            "balance out of range: {}",
+            hex::encode(bytes)
+        )));
+    }
+    let mut low = [0u8; 16];
+    low.copy_from_slice(&bytes[16..]);
+    Ok(u128::from_be_bytes(low))
+}
+
+/// ABI-encode a base58 Tron address as one 32-byte word (hex).
+pub(super) fn abi_address(address: &str) -> Result<String, NodeError> {
+    let payload = decode_address(address)?;
+    Ok(format!("{:0>64}", hex::encode(&payload[1..])))
+}
+
+/// ABI-encode the `(address, uint256)` arguments of a TRC-20 `transfer`.
+fn abi_transfer(to: &str, amount: u128) -> Result<String, NodeError> {
+    Ok(format!("{}{:064x}", abi_address(to)?, amount))
+}
+
+/// ABI-encode a decimal TRC-721 token id as one `uint256` word (hex).
+fn abi_token_id(token_id: &str) -> Result<String, NodeError> {
+    decimal_to_u256(token_id)
+        .map(hex::encode)
+        .ok_or_else(|| NodeError::Parse(format!("invalid token id {}", token_id)))
+}
+
+/// ABI-encode the `(address, address, uint256)` arguments of a TRC-721 `transferFrom`.
+fn abi_transfer_from(from: &str, to: &str, token_id: &str) -> Result<String, NodeError> {
+    Ok(format!(
+        "{}{}{}",
+        abi_address(from)?,
+        abi_address(to)?,
+        abi_token_id(token_id)?
+    ))
+}
+
+/// ABI-encode the `(address, uint256)` arguments of a TRC-20 `approve`.
+fn abi_approve(spender: &str, amount: Allowance) -> Result<String, NodeError> {
+    Ok(format!("{}{}", abi_address(spender)?, amount.abi_word()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_stake_from_the_network_ratio() {
+        // 90 billion energy a day shared by 18 billion staked TRX: 5 energy per TRX.
+        assert_eq!(
+            stake_for(65_000, 90_000_000_000, 18_000_000_000),
+            Some(13_000_000_000)
+        );
+        assert_eq!(stake_for(1, 3, 1), Some(333_334));
+        assert_eq!(stake_for(1, 0, 1), None);
+    }
+
+    #[test]
+    fn merges_trc20_transfers_with_native_transactions() {
+        let native = |hash: &str, block_number, timestamp| Transaction {
+            hash: hash.to_string(),
+            from: "TA".to_string(),
+            to: "TB".to_string(),
+            value: "5".to_string(),
+            block_number,
+            timestamp,
+            status: "SUCCESS".to_string(),
+            raw: None,
+            details: None,
+        };
+        let transfer = |hash: &str, timestamp: u64| {
+            token_transfer_from_entry(serde_json::json!({
+                "transaction_id": hash,
+                "token_info": {
+                    "symbol": "USDT",
+                    "address": "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+                    "decimals": 6,
+                    "name": "Tether USD"
+                },
+                "block_timestamp": timestamp,
+                "from": "TA",
+                "to": "TC",
+                "type": "Transfer",
+                "value": "1000000"
+            }))
+            .unwrap()
+        };
+
+        let merged = merge_token_transfers(
+            vec![native("aa", 300, 3_000), native("bb", 100, 1_000)],
+            vec![transfer("bb", 1_000), transfer("cc", 2_000)],
+        );
+        let hashes: Vec<&str> = merged.iter().map(|tx| tx.hash.as_str()).collect();
+        assert_eq!(hashes, ["aa", "cc", "bb"]);
+        assert_eq!(merged[0].token(), None);
+        // The sender's own transaction lends the transfer its block.
+        assert_eq!(
+            merged[2].token(),
+            Some("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t")
+        );
+        assert_eq!((merged[2].to.as_str(), merged[2].block_number), ("TC", 100));
+        assert_eq!(merged[2].value, "1000000");
+        assert_eq!(merged[1].block_number, 0);
+    }
+
+    #[test]
+    fn abi_encodes_transfer_arguments() {
+        assert_eq!(
+            abi_transfer("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", 1_000_000).unwrap(),
+            "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c\
+             00000000000000000000000000000000000000000000000000000000000f4240"
+        );
+        assert!(abi_address("not-an-address").is_err());
+    }
+
+    #[test]
+    fn abi_encodes_nft_token_ids_of_any_width() {
+        use crate::wallet::chain::sdk::be_bytes_to_decimal;
+
+        assert_eq!(abi_token_id("256").unwrap(), format!("{:064x}", 256));
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(abi_token_id(max).unwrap(), "f".repeat(64));
+        assert_eq!(
+            be_bytes_to_decimal(&decimal_to_u256(&max[1..]).unwrap()),
+            &max[1..]
+        );
+        assert!(abi_token_id(&max.replace("935", "936")).is_err());
+        assert!(abi_token_id("12a").is_err() && abi_token_id("").is_err());
+
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let parameter = abi_transfer_from(usdt, usdt, "7").unwrap();
+        assert_eq!(parameter.len(), 3 * 64);
+        assert!(parameter.ends_with("07"));
+    }
+
+    #[tokio::test]
+    async fn chain_info_names_the_public_endpoints() {
+        use crate::node::utils::AmountStyle;
+
+        let info = TronProvider::nile().chain_info().await.unwrap();
+        assert_eq!(info.chain_id.as_deref(), Some("tron-nile"));
+        assert_eq!(
+            info.format_amount("1500000", &AmountStyle::english()),
+            "1.5 TRX"
+        );
+        let custom = TronProvider::with_url("http://127.0.0.1:8090".to_string());
+        assert_eq!(custom.chain_info().await.unwrap().chain_id, None);
+    }
+
+    #[test]
+    fn lists_trc20_balances_labelled_by_preset() {
+        let body = serde_json::json!({
+            "success": true,
+            "data": [{
+                "balance": 1_000_000,
+                "trc20": [
+                    { "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t": "2500000" },
+                    { "TLa2f6VPqDgRE67v1736s7bJ8Ray5wYjU7": "7" }
+                ]
+            }]
+        });
+        let balances = trc20_balances_from_json(&body, Some("tron")).unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].symbol.as_deref(), Some("USDT"));
+        assert_eq!(balances[0].decimals, Some(6));
+        assert_eq!(balances[0].balance, "2500000");
+        assert_eq!(balances[1].symbol, None);
+
+        let unused = serde_json::json!({ "success": true, "data": [] });
+        assert!(
+            trc20_balances_from_json(&unused, Some("tron"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reads_stakes_votes_and_permissions() {
+        let account = account_from_json(serde_json::json!({
+            "address": "TXYZopYRdj2D9XRtbG411XZZ3kM5VkAeBf",
+            "balance": 5_000_000,
+            "create_time": 1_600_000_000_000u64,
+            "frozen": [{ "frozen_balance": 2_000_000, "expire_time": 1_700_000_000_000u64 }],
+            "frozenV2": [
+                { "amount": 10_000_000 },
+                { "type": "ENERGY", "amount": 30_000_000 },
+                { "type": "TRON_POWER" }
+            ],
+            "unfrozenV2": [
+                { "type": "ENERGY", "unfreeze_amount": 1_000_000, "unfreeze_expire_time": 1_800_000_000_000u64 }
+            ],
+            "account_resource": { "delegated_frozenV2_balance_for_energy": 8_000_000 },
+            "votes": [{ "vote_address": "TLyqzVGLV1srkB7dToTAEqgDSfPtXRJZYH", "vote_count": 40 }],
+            "owner_permission": {
+                "permission_name": "owner",
+                "threshold": 2,
+                "keys": [
+                    { "address": "TXYZopYRdj2D9XRtbG411XZZ3kM5VkAeBf", "weight": 1 },
+                    { "address": "TLyqzVGLV1srkB7dToTAEqgDSfPtXRJZYH", "weight": 1 }
+                ]
+            },
+            "active_permission": [
+                { "type": "Active", "id": 2, "permission_name": "active", "threshold": 1, "keys": [] }
+            ]
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(account.staked.len(), 3);
+        assert_eq!(account.staked_for(TronResource::Bandwidth), 12_000_000);
+        assert_eq!(account.staked_for(TronResource::Energy), 38_000_000);
+        assert_eq!(account.staked[0].expire_time, Some(1_700_000_000_000));
+        assert_eq!(account.voting_power(), 50);
+        assert_eq!(account.total_balance(), 56_000_000);
+        assert_eq!(account.votes[0].count, 40);
+        assert_eq!(account.owner_permission.unwrap().keys.len(), 2);
+        assert_eq!(account.active_permissions[0].name, "active");
+
+        // Unactivated accounts come back as an empty object.
+        assert_eq!(account_from_json(serde_json::json!({})).unwrap(), None);
+    }
+
+    #[test]
+    fn parses_trongrid_events() {
+        let event = contract_event_from_entry(serde_json::json!({
+            "block_number": 61_000_000,
+            "block_timestamp": 1_713_000_000_000u64,
+            "contract_address": "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+            "event_index": 1,
+            "event_name": "Transfer",
+            "result": {
+                "0": "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                "1": "0x0000000000000000000000000000000000000001",
+                "2": "1000000",
+                "from": "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                "to": "0x0000000000000000000000000000000000000001",
+                "value": "1000000"
+            },
+            "transaction_id": "ab".repeat(32),
+        }))
+        .unwrap();
+        assert_eq!(event.name, "Transfer");
+        assert_eq!(event.log_index, 1);
+        assert_eq!(
+            event.params.keys().collect::<Vec<_>>(),
+            ["from", "to", "value"]
+        );
+        assert_eq!(event.params["value"], "1000000");
     }
 }
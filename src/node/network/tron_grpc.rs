@@ -0,0 +1,556 @@
+//! Tron provider speaking the `protocol.Wallet` gRPC service of a java-tron full node
+//! (port 50051 by default), for operators running their own nodes, and
+//! optionally `protocol.WalletSolidity` (port 50061) for solidified state.
+
+use crate::correlation::{CorrelationId, DEFAULT_CORRELATION_HEADER};
+use crate::node::network::http::{DEFAULT_CONNECT_TIMEOUT, DEFAULT_REQUEST_TIMEOUT};
+use crate::node::network::tron::{
+    TRON_BLOCK_TIME, TRON_SYNC_TOLERANCE_SECS, TX_EXPIRATION_MS, abi_address, balance_word,
+    decode_address,
+};
+use crate::node::{
+    ChainInfo, NodeError, ProviderHealth, RawTransaction, ReadProvider, SyncStatus, Transaction,
+    TransactionProvider,
+};
+use crate::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::wallet::chain::sdk::keccak256;
+use crate::wallet::chain::tvm::proto::{self, Contract, ContractType, TransactionRaw};
+use async_trait::async_trait;
+use prost::Message;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Channel, Endpoint};
+
+const GET_ACCOUNT: &str = "/protocol.Wallet/GetAccount";
+const GET_NOW_BLOCK: &str = "/protocol.Wallet/GetNowBlock2";
+const GET_BLOCK_BY_NUM: &str = "/protocol.Wallet/GetBlockByNum2";
+const BROADCAST_TRANSACTION: &str = "/protocol.Wallet/BroadcastTransaction";
+const TRIGGER_CONSTANT_CONTRACT: &str = "/protocol.Wallet/TriggerConstantContract";
+const SOLIDITY_GET_ACCOUNT: &str = "/protocol.WalletSolidity/GetAccount";
+const SOLIDITY_GET_NOW_BLOCK: &str = "/protocol.WalletSolidity/GetNowBlock2";
+
+pub struct TronGrpcProvider {
+    channel: Channel,
+    /// `protocol.WalletSolidity` endpoint, if configured.
+    solidity: Option<Channel>,
+    correlation_header: Option<String>,
+}
+
+fn lazy_channel(url: &str) -> Result<Channel, NodeError> {
+    let endpoint = Endpoint::from_shared(url.to_string())
+        .map_err(|e| NodeError::Network(format!("invalid endpoint {}: {}", url, e)))?;
+    Ok(endpoint
+        .timeout(DEFAULT_REQUEST_TIMEOUT)
+        .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+        .connect_lazy())
+}
+
+impl TronGrpcProvider {
+    /// Provider for the node at `url`, e.g. `http://127.0.0.1:50051`.
+    /// The connection is established on first use; calls time out like
+    /// the HTTP providers' do by default.
+    pub fn new(url: &str) -> Result<Self, NodeError> {
+        Ok(Self {
+            channel: lazy_channel(url)?,
+            solidity: None,
+            correlation_header: Some(DEFAULT_CORRELATION_HEADER.to_ascii_lowercase()),
+        })
+    }
+
+    /// Read solidified state from the `WalletSolidity` service at `url`,
+    /// e.g. `http://127.0.0.1:50061`, for
+    /// [`solidified_balance`](Self::solidified_balance) and
+    /// [`solidified_block_number`](Self::solidified_block_number).
+    pub fn with_solidity_node(mut self, url: &str) -> Result<Self, NodeError> {
+        self.solidity = Some(lazy_channel(url)?);
+        Ok(self)
+    }
+
+    /// Metadata key carrying the active [`CorrelationId`]. gRPC keys are lowercase.
+    /// Pass `None` to stop forwarding correlation ids to the node.
+    pub fn with_correlation_header(mut self, header: Option<&str>) -> Self {
+        self.correlation_header = header.map(str::to_ascii_lowercase);
+        self
+    }
+
+    /// Block at `number` as served by `GetBlockByNum2`.
+    pub async fn block_by_num(&self, number: u64) -> Result<TronGrpcBlock, NodeError> {
+        let num = i64::try_from(number)
+            .map_err(|_| NodeError::Parse(format!("block number {} out of range", number)))?;
+        let block: api::BlockExtention = self
+            .unary(GET_BLOCK_BY_NUM, api::NumberMessage { num })
+            .await?;
+        TronGrpcBlock::try_from(block)
+    }
+
+    async fn now_block(&self) -> Result<TronGrpcBlock, NodeError> {
+        let block: api::BlockExtention = self.unary(GET_NOW_BLOCK, api::EmptyMessage {}).await?;
+        TronGrpcBlock::try_from(block)
+    }
+
+    /// Height of the latest solidified block, which can no longer be
+    /// reverted.
+    pub async fn solidified_block_number(&self) -> Result<u64, NodeError> {
+        let block: api::BlockExtention = self
+            .unary_on(
+                self.solidity_channel()?,
+                SOLIDITY_GET_NOW_BLOCK,
+                api::EmptyMessage {},
+            )
+            .await?;
+        Ok(TronGrpcBlock::try_from(block)?.number)
+    }
+
+    /// Balance of `address` in Sun as of the latest solidified block.
+    pub async fn solidified_balance(&self, address: &str) -> Result<String, NodeError> {
+        let account: api::Account = self
+            .unary_on(
+                self.solidity_channel()?,
+                SOLIDITY_GET_ACCOUNT,
+                api::Account {
+                    address: decode_address(address)?,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        Ok(account.balance.max(0).to_string())
+    }
+
+    fn solidity_channel(&self) -> Result<&Channel, NodeError> {
+        self.solidity.as_ref().ok_or_else(|| {
+            NodeError::Api("no solidity node configured; see with_solidity_node".to_string())
+        })
+    }
+
+    /// Return value of `owner` calling `selector` (e.g. `"balanceOf(address)"`)
+    /// on `contract` with the ABI-encoded `parameter`, run by the node
+    /// without a transaction.
+    pub async fn trigger_constant_contract(
+        &self,
+        owner: &str,
+        contract: &str,
+        selector: &str,
+        parameter: &[u8],
+    ) -> Result<Vec<u8>, NodeError> {
+        let data = [&keccak256(selector.as_bytes())[..4], parameter].concat();
+        let call = proto::TriggerSmartContract {
+            owner_address: decode_address(owner)?,
+            contract_address: decode_address(contract)?,
+            call_value: 0,
+            data,
+        };
+        let result: api::TransactionExtention = self.unary(TRIGGER_CONSTANT_CONTRACT, call).await?;
+        constant_result(result, selector)
+    }
+
+    /// TRC-20 `balanceOf(owner)` in the token's base unit.
+    pub async fn trc20_balance(&self, owner: &str, contract: &str) -> Result<u128, NodeError> {
+        let parameter = hex::decode(abi_address(owner)?).expect("hex from abi_address");
+        let bytes = self
+            .trigger_constant_contract(owner, contract, "balanceOf(address)", &parameter)
+            .await?;
+        balance_word(&bytes)
+    }
+
+    /// Unsigned `raw_data` for `contract`, referencing the current block.
+    async fn reference_raw_data(&self, contract: Contract) -> Result<TransactionRaw, NodeError> {
+        let block = self.now_block().await?;
+        let timestamp = block.timestamp as i64;
+        Ok(TransactionRaw {
+            ref_block_bytes: block.number.to_be_bytes()[6..8].to_vec(),
+            ref_block_hash: block.block_id[8..16].to_vec(),
+            expiration: timestamp + TX_EXPIRATION_MS,
+            contract: vec![contract],
+            timestamp,
+            ..Default::default()
+        })
+    }
+
+    async fn unary<Req, Resp>(&self, path: &'static str, message: Req) -> Result<Resp, NodeError>
+    where
+        Req: Message + Send + Sync + 'static,
+        Resp: Message + Default + Send + Sync + 'static,
+    {
+        self.unary_on(&self.channel, path, message).await
+    }
+
+    async fn unary_on<Req, Resp>(
+        &self,
+        channel: &Channel,
+        path: &'static str,
+        message: Req,
+    ) -> Result<Resp, NodeError>
+    where
+        Req: Message + Send + Sync + 'static,
+        Resp: Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| NodeError::Network(e.to_string()))?;
+
+        let mut request = tonic::Request::new(message);
+        if let (Some(header), Some(id)) = (&self.correlation_header, CorrelationId::current())
+            && let (Ok(key), Ok(value)) = (
+                header.parse::<tonic::metadata::MetadataKey<_>>(),
+                MetadataValue::try_from(id.as_str()),
+            )
+        {
+            request.metadata_mut().insert(key, value);
+        }
+
+        grpc.unary(
+            request,
+            PathAndQuery::from_static(path),
+            tonic_prost::ProstCodec::default(),
+        )
+        .await
+        .map(tonic::Response::into_inner)
+        .map_err(status_to_error)
+    }
+}
+
+/// First word of a `TriggerConstantContract` result, or why the call failed.
+fn constant_result(
+    result: api::TransactionExtention,
+    selector: &str,
+) -> Result<Vec<u8>, NodeError> {
+    if let Some(ret) = result.result.filter(|ret| !ret.result) {
+        return Err(NodeError::Api(format!(
+            "{} call failed ({}): {}",
+            selector,
+            ret.code,
+            String::from_utf8_lossy(&ret.message)
+        )));
+    }
+    result
+        .constant_result
+        .into_iter()
+        .next()
+        .ok_or_else(|| NodeError::Parse(format!("{} returned no constant_result", selector)))
+}
+
+/// Header fields of a block fetched over gRPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TronGrpcBlock {
+    pub number: u64,
+    /// Block production time in milliseconds.
+    pub timestamp: u64,
+    pub block_id: [u8; 32],
+}
+
+impl TryFrom<api::BlockExtention> for TronGrpcBlock {
+    type Error = NodeError;
+
+    fn try_from(block: api::BlockExtention) -> Result<Self, NodeError> {
+        let raw = block
+            .block_header
+            .and_then(|h| h.raw_data)
+            .ok_or_else(|| NodeError::Parse("block without header".to_string()))?;
+        let block_id = <[u8; 32]>::try_from(block.blockid.as_slice()).map_err(|_| {
+            NodeError::Parse(format!("invalid blockid of {} bytes", block.blockid.len()))
+        })?;
+        Ok(Self {
+            number: raw.number.max(0) as u64,
+            timestamp: raw.timestamp.max(0) as u64,
+            block_id,
+        })
+    }
+}
+
+fn status_to_error(status: tonic::Status) -> NodeError {
+    match status.code() {
+        tonic::Code::Unavailable | tonic::Code::DeadlineExceeded | tonic::Code::Cancelled => {
+            NodeError::Network(status.message().to_string())
+        }
+        code => NodeError::Api(format!("{:?}: {}", code, status.message())),
+    }
+}
+
+#[async_trait]
+impl ReadProvider for TronGrpcProvider {
+    fn get_decimals(&self) -> u32 {
+        6
+    }
+
+    /// A node does not report which Tron network it is on, so `chain_id` is unset.
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        Ok(ChainInfo {
+            chain_id: None,
+            symbol: Some("TRX".to_string()),
+            decimals: 6,
+            block_time: Some(TRON_BLOCK_TIME),
+        })
+    }
+
+    async fn get_transactions(&self, _address: &str) -> Result<Vec<Transaction>, NodeError> {
+        // Full nodes keep no per-account index; history needs TronGrid or an event service.
+        Err(NodeError::Api(
+            "transaction history is not available over the java-tron gRPC API".to_string(),
+        ))
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(self.now_block().await?.number)
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        let account: api::Account = self
+            .unary(
+                GET_ACCOUNT,
+                api::Account {
+                    address: decode_address(address)?,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        // Unknown accounts come back empty, i.e. with a zero balance.
+        Ok(account.balance.max(0).to_string())
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        Ok(self.trc20_balance(address, contract).await?.to_string())
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        let started = Instant::now();
+        let block = self.now_block().await?;
+        let latency = started.elapsed();
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let seconds_behind = now_ms.saturating_sub(block.timestamp) / 1000;
+        let sync = if block.timestamp == 0 {
+            SyncStatus::Unknown
+        } else if seconds_behind <= TRON_SYNC_TOLERANCE_SECS {
+            SyncStatus::Synced
+        } else {
+            SyncStatus::Stale { seconds_behind }
+        };
+
+        Ok(ProviderHealth {
+            latency,
+            block_height: block.number,
+            sync,
+        })
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for TronGrpcProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        let contract = Contract::pack(
+            ContractType::TransferContract,
+            &proto::TransferContract {
+                owner_address: decode_address(from)?,
+                to_address: decode_address(to)?,
+                amount: i64::try_from(amount)
+                    .map_err(|_| NodeError::Parse(format!("amount {} out of range", amount)))?,
+            },
+        );
+        let raw_data = self.reference_raw_data(contract).await?;
+
+        Ok(RawTransaction::Json(serde_json::json!({
+            "txID": hex::encode(raw_data.txid()),
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+            "visible": true,
+        })))
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        let tx = raw_tx.as_json().ok_or_else(|| {
+            NodeError::Parse(format!(
+                "expected a JSON transaction, got {}",
+                raw_tx.kind()
+            ))
+        })?;
+        let raw_data_hex = tx
+            .get("raw_data_hex")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse("missing raw_data_hex".to_string()))?;
+        let raw_data = TransactionRaw::decode(
+            hex::decode(raw_data_hex)
+                .map_err(|e| NodeError::Parse(e.to_string()))?
+                .as_slice(),
+        )
+        .map_err(|e| NodeError::Parse(e.to_string()))?;
+        let signature = tx
+            .get("signature")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|sig| {
+                hex::decode(sig.as_str().unwrap_or_default())
+                    .map_err(|e| NodeError::Parse(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let tx_id = hex::encode(raw_data.txid());
+
+        let ret: api::Return = self
+            .unary(
+                BROADCAST_TRANSACTION,
+                proto::Transaction {
+                    raw_data: Some(raw_data),
+                    signature,
+                },
+            )
+            .await?;
+
+        if ret.result {
+            Ok(tx_id)
+        } else {
+            // `Return.response_code` 8 is TRANSACTION_EXPIRATION_ERROR.
+            let code = match ret.code {
+                8 => "TRANSACTION_EXPIRATION_ERROR".to_string(),
+                code => code.to_string(),
+            };
+            Err(NodeError::Api(format!(
+                "Broadcast failed ({}): {}",
+                code,
+                String::from_utf8_lossy(&ret.message)
+            )))
+        }
+    }
+}
+
+/// Request and response messages of `api/api.proto` and `core/Tron.proto` used above,
+/// in the shape `prost-build` generates. Unused fields are skipped on decode.
+mod api {
+    use crate::wallet::chain::tvm::proto::Transaction;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct EmptyMessage {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NumberMessage {
+        #[prost(int64, tag = "1")]
+        pub num: i64,
+    }
+
+    /// `protocol.Account`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Account {
+        #[prost(bytes = "vec", tag = "3")]
+        pub address: Vec<u8>,
+        /// Sun.
+        #[prost(int64, tag = "4")]
+        pub balance: i64,
+    }
+
+    /// `protocol.Return`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Return {
+        #[prost(bool, tag = "1")]
+        pub result: bool,
+        /// `Return.response_code`, e.g. 3 for `CONTRACT_VALIDATE_ERROR`.
+        #[prost(int32, tag = "2")]
+        pub code: i32,
+        #[prost(bytes = "vec", tag = "3")]
+        pub message: Vec<u8>,
+    }
+
+    /// `protocol.BlockHeader.raw`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct BlockHeaderRaw {
+        #[prost(int64, tag = "1")]
+        pub timestamp: i64,
+        #[prost(int64, tag = "7")]
+        pub number: i64,
+    }
+
+    /// `protocol.BlockHeader`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct BlockHeader {
+        #[prost(message, optional, tag = "1")]
+        pub raw_data: Option<BlockHeaderRaw>,
+    }
+
+    /// `protocol.TransactionExtention`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TransactionExtention {
+        #[prost(message, optional, tag = "1")]
+        pub transaction: Option<Transaction>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub txid: Vec<u8>,
+        /// Return data of a constant call.
+        #[prost(bytes = "vec", repeated, tag = "3")]
+        pub constant_result: Vec<Vec<u8>>,
+        #[prost(message, optional, tag = "4")]
+        pub result: Option<Return>,
+        #[prost(int64, tag = "5")]
+        pub energy_used: i64,
+    }
+
+    /// `protocol.BlockExtention` (sic).
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct BlockExtention {
+        #[prost(message, repeated, tag = "1")]
+        pub transactions: Vec<TransactionExtention>,
+        #[prost(message, optional, tag = "2")]
+        pub block_header: Option<BlockHeader>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub blockid: Vec<u8>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_block_extention_header() {
+        let block = api::BlockExtention {
+            transactions: vec![],
+            block_header: Some(api::BlockHeader {
+                raw_data: Some(api::BlockHeaderRaw {
+                    timestamp: 1_700_000_000_000,
+                    number: 0x0123_4567,
+                }),
+            }),
+            blockid: [[0, 0, 0, 0, 0x01, 0x23, 0x45, 0x67].as_slice(), &[0xaa; 24]].concat(),
+        };
+        let decoded = api::BlockExtention::decode(block.encode_to_vec().as_slice()).unwrap();
+        let header = TronGrpcBlock::try_from(decoded).unwrap();
+        assert_eq!(header.number, 0x0123_4567);
+        assert_eq!(header.timestamp, 1_700_000_000_000);
+        assert_eq!(header.block_id[8..16], [0xaa; 8]);
+
+        assert!(TronGrpcBlock::try_from(api::BlockExtention::default()).is_err());
+        assert!(TronGrpcProvider::new("not a uri").is_err());
+    }
+
+    #[test]
+    fn reads_constant_results_and_reverts() {
+        let word = [[0u8; 24].as_slice(), &1_500_000u64.to_be_bytes()].concat();
+        let ok = api::TransactionExtention {
+            constant_result: vec![word],
+            result: Some(api::Return {
+                result: true,
+                ..Default::default()
+            }),
+            energy_used: 935,
+            ..Default::default()
+        };
+        let decoded = api::TransactionExtention::decode(ok.encode_to_vec().as_slice()).unwrap();
+        let bytes = constant_result(decoded, "balanceOf(address)").unwrap();
+        assert_eq!(balance_word(&bytes).unwrap(), 1_500_000);
+
+        let reverted = api::TransactionExtention {
+            result: Some(api::Return {
+                result: false,
+                code: 2,
+                message: b"REVERT opcode executed".to_vec(),
+            }),
+            ..Default::default()
+        };
+        let error = constant_result(reverted, "balanceOf(address)").unwrap_err();
+        assert!(error.to_string().contains("REVERT"));
+    }
+}
@@ -0,0 +1,271 @@
+//! New Tron transactions pushed over a node's JSON-RPC WebSocket (feature
+//! `tron-ws`).
+//!
+//! java-tron's JSON-RPC is Ethereum-compatible: where an endpoint serves it
+//! over WebSocket and supports `eth_subscribe("newHeads")`, every new block
+//! is announced as it is produced and read whole over the same connection,
+//! instead of asking TronGrid for an address's history every few seconds.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::node::events::{AddressStream, TransactionStream};
+use crate::node::network::tron::{decode_address, tron_hex_to_base58};
+use crate::node::{NodeError, RpcError, Transaction};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// [`TransactionStream`] over a Tron JSON-RPC WebSocket endpoint, e.g.
+/// `ws://node:8545/jsonrpc`. Each subscription opens its own connection.
+#[derive(Debug, Clone)]
+pub struct TronStreamProvider {
+    url: String,
+}
+
+impl TronStreamProvider {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionStream for TronStreamProvider {
+    async fn subscribe_address(&self, address: &str) -> Result<AddressStream, NodeError> {
+        let watched = format!("0x{}", hex::encode(&decode_address(address)?[1..]));
+        let heads = HeadSubscription::connect(&self.url).await?;
+        let stream = futures_util::stream::unfold(
+            (heads, VecDeque::new()),
+            move |(mut heads, mut ready)| {
+                let watched = watched.clone();
+                async move {
+                    loop {
+                        if let Some(tx) = ready.pop_front() {
+                            return Some((Ok(tx), (heads, ready)));
+                        }
+                        let block = match heads.next_block().await? {
+                            Ok(block) => block,
+                            Err(e) => return Some((Err(e), (heads, ready))),
+                        };
+                        match block_transactions(&block, &watched) {
+                            Ok(found) => ready.extend(found),
+                            Err(e) => return Some((Err(e), (heads, ready))),
+                        }
+                    }
+                }
+            },
+        );
+        Ok(stream.boxed())
+    }
+}
+
+/// An `eth_subscribe("newHeads")` subscription that fetches each announced
+/// block with its transactions.
+struct HeadSubscription {
+    socket: Socket,
+    id: String,
+    next_request: u64,
+    /// Heads announced while waiting for a block.
+    heads: VecDeque<Value>,
+}
+
+impl HeadSubscription {
+    async fn connect(url: &str) -> Result<Self, NodeError> {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| NodeError::Network(e.to_string()))?;
+        let mut subscription = Self {
+            socket,
+            id: String::new(),
+            next_request: 1,
+            heads: VecDeque::new(),
+        };
+        let id = subscription
+            .call("eth_subscribe", json!(["newHeads"]))
+            .await?;
+        subscription.id = id
+            .as_str()
+            .ok_or_else(|| NodeError::Parse("eth_subscribe: no subscription id".to_string()))?
+            .to_string();
+        Ok(subscription)
+    }
+
+    /// Next announced block with full transactions, or `None` once the node
+    /// closes the connection.
+    async fn next_block(&mut self) -> Option<Result<Value, NodeError>> {
+        let head = match self.heads.pop_front() {
+            Some(head) => head,
+            None => loop {
+                match self.next_message().await? {
+                    Ok(message) => {
+                        if let Some(head) = self.notification(message) {
+                            break head;
+                        }
+                    }
+                    Err(e) => return Some(Err(e)),
+                }
+            },
+        };
+        let Some(hash) = head["hash"].as_str() else {
+            return Some(Err(NodeError::Parse("newHeads: head without hash".into())));
+        };
+        Some(
+            self.call("eth_getBlockByHash", json!([hash, true]))
+                .await
+                .and_then(|block| match block {
+                    Value::Null => Err(NodeError::Api(format!("block {} not found", hash))),
+                    block => Ok(block),
+                }),
+        )
+    }
+
+    /// Send a request and wait for its reply, queueing heads announced meanwhile.
+    async fn call(&mut self, method: &str, params: Value) -> Result<Value, NodeError> {
+        let id = self.next_request;
+        self.next_request += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.socket
+            .send(Message::text(request.to_string()))
+            .await
+            .map_err(|e| NodeError::Network(e.to_string()))?;
+
+        loop {
+            let message = self.next_message().await.ok_or_else(|| {
+                NodeError::Network(format!("closed before {} returned", method))
+            })??;
+            if message.get("id") != Some(&json!(id)) {
+                if let Some(head) = self.notification(message) {
+                    self.heads.push_back(head);
+                }
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                return Err(NodeError::Rpc {
+                    method: method.to_string(),
+                    error: RpcError::from_value(error),
+                });
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    /// The head `message` announces, if it belongs to this subscription.
+    fn notification(&self, mut message: Value) -> Option<Value> {
+        let ours = message["method"] == "eth_subscription"
+            && message["params"]["subscription"] == self.id.as_str();
+        ours.then(|| message["params"]["result"].take())
+    }
+
+    /// Next text message, parsed; `None` when the connection closes.
+    async fn next_message(&mut self) -> Option<Result<Value, NodeError>> {
+        loop {
+            match self.socket.next().await? {
+                Ok(Message::Text(text)) => {
+                    return Some(
+                        serde_json::from_str(text.as_str())
+                            .map_err(|e| NodeError::Parse(e.to_string())),
+                    );
+                }
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => continue,
+                Err(e) => return Some(Err(NodeError::Network(e.to_string()))),
+            }
+        }
+    }
+}
+
+fn quantity(value: &Value) -> Option<u128> {
+    u128::from_str_radix(value.as_str()?.strip_prefix("0x")?, 16).ok()
+}
+
+/// Base58 form of a JSON-RPC (`0x` plus 20 bytes) Tron address.
+fn base58(address: &Value) -> String {
+    address
+        .as_str()
+        .and_then(|a| a.strip_prefix("0x"))
+        .and_then(|a| tron_hex_to_base58(&format!("41{}", a)))
+        .unwrap_or_default()
+}
+
+/// Transactions of `block` sent from or to `watched` (`0x` hex). Their
+/// status is `UNKNOWN`: a block does not say whether a contract call
+/// reverted, only its receipt does.
+fn block_transactions(block: &Value, watched: &str) -> Result<Vec<Transaction>, NodeError> {
+    let missing = |field: &str| NodeError::Parse(format!("block without {}", field));
+    let number = quantity(&block["number"]).ok_or_else(|| missing("number"))? as u64;
+    // Seconds in JSON-RPC; milliseconds everywhere else on Tron.
+    let timestamp = quantity(&block["timestamp"]).ok_or_else(|| missing("timestamp"))? as u64;
+    let transactions = block["transactions"]
+        .as_array()
+        .ok_or_else(|| missing("transactions"))?;
+
+    Ok(transactions
+        .iter()
+        .filter(|tx| {
+            [&tx["from"], &tx["to"]]
+                .iter()
+                .any(|a| a.as_str().is_some_and(|a| a.eq_ignore_ascii_case(watched)))
+        })
+        .map(|tx| Transaction {
+            hash: tx["hash"]
+                .as_str()
+                .map(|h| h.trim_start_matches("0x").to_string())
+                .unwrap_or_default(),
+            from: base58(&tx["from"]),
+            to: base58(&tx["to"]),
+            value: quantity(&tx["value"]).unwrap_or(0).to_string(),
+            block_number: number,
+            timestamp: timestamp * 1000,
+            status: "UNKNOWN".to_string(),
+            raw: Some(tx.clone()),
+            details: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_watched_addresses_transactions_from_a_block() {
+        let watched = format!(
+            "0x{}",
+            hex::encode(&decode_address("TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT").unwrap()[1..])
+        );
+        let block = json!({
+            "number": "0x3e8",
+            "timestamp": "0x6553f100",
+            "transactions": [
+                {
+                    "hash": "0xaa",
+                    "from": "0x0000000000000000000000000000000000000001",
+                    "to": watched.to_uppercase().replacen("0X", "0x", 1),
+                    "value": "0xf4240",
+                },
+                {
+                    "hash": "0xbb",
+                    "from": "0x0000000000000000000000000000000000000001",
+                    "to": "0x0000000000000000000000000000000000000002",
+                    "value": "0x1",
+                },
+            ],
+        });
+
+        let found = block_transactions(&block, &watched).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].hash, "aa");
+        assert_eq!(found[0].to, "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT");
+        assert_eq!(found[0].value, "1000000");
+        assert_eq!(found[0].block_number, 1_000);
+        assert_eq!(found[0].timestamp, 1_700_000_000_000);
+        assert!(!found[0].is_failed());
+    }
+}
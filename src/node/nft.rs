@@ -0,0 +1,82 @@
+//! Non-fungible tokens (TRC-721) and the metadata their URIs point at.
+
+use serde::{Deserialize, Serialize};
+
+/// Gateway `ipfs://` token URIs are fetched through.
+pub const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+
+/// One token of an NFT contract, held by `owner`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nft {
+    /// Contract address in the network's native format.
+    pub contract: String,
+    /// Token id, a `uint256` as a decimal string.
+    pub token_id: String,
+    pub owner: String,
+    /// Collection name and symbol, as the indexer reported them.
+    pub collection: Option<String>,
+    pub symbol: Option<String>,
+    /// What `tokenURI` returned, if the contract implements it.
+    pub token_uri: Option<String>,
+}
+
+/// The JSON document a token URI names, by the ERC-721 metadata schema
+/// most TRC-721 collections follow too.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Image URI, `ipfs://` ones already mapped to [`IPFS_GATEWAY`].
+    pub image: Option<String>,
+    /// The whole document, for fields beyond the schema such as `attributes`.
+    pub document: serde_json::Value,
+}
+
+impl NftMetadata {
+    pub fn from_document(document: serde_json::Value) -> Self {
+        let text = |field: &str| document.get(field).and_then(|v| v.as_str());
+        Self {
+            name: text("name").map(str::to_string),
+            description: text("description").map(str::to_string),
+            image: text("image").map(gateway_url),
+            document,
+        }
+    }
+}
+
+/// `uri` as an HTTP(S) URL: `ipfs://` URIs go through [`IPFS_GATEWAY`],
+/// anything else is returned as is.
+pub fn gateway_url(uri: &str) -> String {
+    match uri.strip_prefix("ipfs://") {
+        Some(path) => format!(
+            "{}{}",
+            IPFS_GATEWAY,
+            path.strip_prefix("ipfs/").unwrap_or(path)
+        ),
+        None => uri.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_metadata_through_the_ipfs_gateway() {
+        let metadata = NftMetadata::from_document(serde_json::json!({
+            "name": "Punk #7",
+            "image": "ipfs://ipfs/QmImage",
+            "attributes": [{ "trait_type": "Hat", "value": "Cap" }],
+        }));
+        assert_eq!(metadata.name.as_deref(), Some("Punk #7"));
+        assert_eq!(
+            metadata.image.as_deref(),
+            Some("https://ipfs.io/ipfs/QmImage")
+        );
+        assert_eq!(metadata.document["attributes"][0]["value"], "Cap");
+        assert_eq!(
+            gateway_url("https://example.com/7.json"),
+            "https://example.com/7.json"
+        );
+    }
+}
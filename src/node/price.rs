@@ -0,0 +1,66 @@
+//! Fiat prices of coins and tokens, for valuing balances.
+
+use std::collections::{BTreeMap, HashMap};
+
+use async_trait::async_trait;
+
+use crate::node::NodeError;
+
+/// Source of fiat exchange rates, keyed by ticker symbol (`TRX`, `USDT`).
+///
+/// Symbols are matched case-insensitively and returned upper-case; fiat
+/// currencies are ISO 4217 codes such as `usd` or `EUR`, in either case.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    /// Price of one whole unit of each of `symbols` in `fiat`. Symbols the
+    /// source has no price for are left out rather than failing the call.
+    async fn get_prices(
+        &self,
+        symbols: &[&str],
+        fiat: &str,
+    ) -> Result<BTreeMap<String, f64>, NodeError>;
+
+    /// Price of one whole unit of `symbol` in `fiat`, `None` if unknown.
+    async fn get_price(&self, symbol: &str, fiat: &str) -> Result<Option<f64>, NodeError> {
+        let prices = self.get_prices(&[symbol], fiat).await?;
+        Ok(prices.get(&symbol.to_uppercase()).copied())
+    }
+}
+
+/// Prices set by hand, e.g. from a feed the application already has, or in tests.
+#[derive(Debug, Clone, Default)]
+pub struct StaticPrices {
+    prices: HashMap<(String, String), f64>,
+}
+
+impl StaticPrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price `symbol` at `price` per whole unit in `fiat`.
+    pub fn with_price(mut self, symbol: &str, fiat: &str, price: f64) -> Self {
+        self.prices
+            .insert((symbol.to_uppercase(), fiat.to_uppercase()), price);
+        self
+    }
+}
+
+#[async_trait]
+impl PriceProvider for StaticPrices {
+    async fn get_prices(
+        &self,
+        symbols: &[&str],
+        fiat: &str,
+    ) -> Result<BTreeMap<String, f64>, NodeError> {
+        let fiat = fiat.to_uppercase();
+        Ok(symbols
+            .iter()
+            .map(|symbol| symbol.to_uppercase())
+            .filter_map(|symbol| {
+                let price = *self.prices.get(&(symbol.clone(), fiat.clone()))?;
+                Some((symbol, price))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,259 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::node::{
+    BalanceAt, ChainInfo, FeeEstimate, FeeHistory, FeeOptions, NodeError, Provider, ProviderHealth,
+    RawTransaction, ReadProvider, TokenBalance, Transaction, TransactionDetail,
+    TransactionProvider, TxPage, TxQuery, TxSimulation, TxStatus,
+};
+use crate::wallet::chain::{CoinSelection, UtxoInput};
+
+/// Request budget for one endpoint, shared by everything calling it.
+///
+/// Requests are spaced evenly, `1 / per_second` apart; a caller arriving
+/// early waits for its slot. Share one budget (through [`RateLimitedProvider`]s
+/// over the same endpoint) between all tasks so that together they stay under
+/// the plan's limit instead of each getting the whole of it.
+pub struct RateBudget {
+    spacing: Duration,
+    next: tokio::sync::Mutex<Instant>,
+}
+
+impl RateBudget {
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            spacing: Duration::from_secs(1) / requests.max(1),
+            next: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for the next request slot.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.spacing;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Provider spending one unit of a shared [`RateBudget`] on every request it
+/// sends to `inner`.
+pub struct RateLimitedProvider {
+    inner: Arc<dyn Provider>,
+    budget: Arc<RateBudget>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Arc<dyn Provider>, budget: Arc<RateBudget>) -> Self {
+        Self { inner, budget }
+    }
+
+    pub fn budget(&self) -> &Arc<RateBudget> {
+        &self.budget
+    }
+}
+
+#[async_trait]
+impl ReadProvider for RateLimitedProvider {
+    fn get_decimals(&self) -> u32 {
+        self.inner.get_decimals()
+    }
+
+    async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+        self.budget.acquire().await;
+        self.inner.chain_info().await
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_transactions(address).await
+    }
+
+    async fn get_transactions_page(
+        &self,
+        address: &str,
+        query: &TxQuery,
+    ) -> Result<TxPage, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_transactions_page(address, query).await
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_transaction(hash).await
+    }
+
+    async fn get_transaction_status(&self, hash: &str) -> Result<TxStatus, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_transaction_status(hash).await
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_transaction_detail(hash).await
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_block_number().await
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_balance(address).await
+    }
+
+    async fn get_balance_at(&self, address: &str, at: BalanceAt) -> Result<String, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_balance_at(address, at).await
+    }
+
+    async fn get_token_balance(&self, address: &str, contract: &str) -> Result<String, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_token_balance(address, contract).await
+    }
+
+    async fn get_token_balances(&self, address: &str) -> Result<Vec<TokenBalance>, NodeError> {
+        self.budget.acquire().await;
+        self.inner.get_token_balances(address).await
+    }
+
+    /// One unit for the whole call: providers with multi-address endpoints
+    /// answer it in a single request.
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        self.budget.acquire().await;
+        self.inner.get_balances_bulk(addresses).await
+    }
+
+    async fn list_unspent(&self, address: &str) -> Result<Vec<UtxoInput>, NodeError> {
+        self.budget.acquire().await;
+        self.inner.list_unspent(address).await
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        self.budget.acquire().await;
+        self.inner.health().await
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for RateLimitedProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        self.budget.acquire().await;
+        self.inner.create_transaction(from, to, amount).await
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        self.budget.acquire().await;
+        self.inner.broadcast_transaction(raw_tx).await
+    }
+
+    async fn estimate_fee(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<FeeEstimate, NodeError> {
+        self.budget.acquire().await;
+        self.inner.estimate_fee(from, to, amount).await
+    }
+
+    async fn check_resources(&self, from: &str, to: &str, amount: u64) -> Result<(), NodeError> {
+        self.budget.acquire().await;
+        self.inner.check_resources(from, to, amount).await
+    }
+
+    async fn fee_history(&self, n_blocks: u32) -> Result<FeeHistory, NodeError> {
+        self.budget.acquire().await;
+        self.inner.fee_history(n_blocks).await
+    }
+
+    async fn create_transaction_with_fees(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.budget.acquire().await;
+        self.inner
+            .create_transaction_with_fees(from, to, amount, fees)
+            .await
+    }
+
+    /// Not throttled: nothing is sent upstream.
+    async fn discard_transaction(&self, raw_tx: &RawTransaction) {
+        self.inner.discard_transaction(raw_tx).await
+    }
+
+    async fn create_transaction_with_coins(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+        selection: &CoinSelection,
+    ) -> Result<RawTransaction, NodeError> {
+        self.budget.acquire().await;
+        self.inner
+            .create_transaction_with_coins(from, to, amount, fees, selection)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        from: &str,
+        outputs: &[(String, u64)],
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.budget.acquire().await;
+        self.inner
+            .create_batch_transaction(from, outputs, fees)
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<TxSimulation, NodeError> {
+        self.budget.acquire().await;
+        self.inner.simulate_transaction(raw_tx).await
+    }
+
+    async fn max_sendable(
+        &self,
+        from: &str,
+        to: &str,
+        fees: &FeeOptions,
+    ) -> Result<u64, NodeError> {
+        self.budget.acquire().await;
+        self.inner.max_sendable(from, to, fees).await
+    }
+
+    async fn create_sweep_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        fees: &FeeOptions,
+    ) -> Result<RawTransaction, NodeError> {
+        self.budget.acquire().await;
+        self.inner
+            .create_sweep_transaction(from, to, amount, fees)
+            .await
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+
+use super::{NameResolver, ResolverError, normalize_name};
+use crate::node::NodeError;
+use crate::node::network::RpcClient;
+use crate::wallet::crypto::hash::keccak256;
+
+/// ENS registry, deployed at the same address on mainnet and the public testnets.
+const ENS_REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+/// `resolver(bytes32)`
+const RESOLVER_SELECTOR: &str = "0178b8bf";
+/// `addr(bytes32)`
+const ADDR_SELECTOR: &str = "3b3b57de";
+
+/// Resolves `*.eth` names through an Ethereum JSON-RPC endpoint.
+///
+/// Names go through [`normalize_name`], so only plain ASCII names resolve.
+/// Wrap in a [`CachingResolver`](super::CachingResolver) to avoid a round trip per send.
+pub struct EnsResolver {
+    rpc: RpcClient,
+    registry: String,
+}
+
+impl EnsResolver {
+    pub fn new(rpc_url: &str) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url),
+            registry: ENS_REGISTRY.to_string(),
+        }
+    }
+
+    /// Send requests through `client`, e.g. one from [`HttpConfig::build`](crate::node::network::HttpConfig::build)
+    /// with other timeouts or a proxy.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.rpc = self.rpc.with_client(client);
+        self
+    }
+
+    /// Use a different registry contract (e.g. a local deployment).
+    pub fn with_registry(mut self, registry: &str) -> Self {
+        self.registry = registry.to_string();
+        self
+    }
+
+    /// `eth_call` returning the last 20 bytes of the first result word, `None` if zero.
+    async fn call_address(
+        &self,
+        to: &str,
+        selector: &str,
+        node: &[u8; 32],
+    ) -> Result<Option<String>, ResolverError> {
+        let call = json!({ "to": to, "data": format!("0x{}{}", selector, hex::encode(node)) });
+        let result = self.rpc.call("eth_call", json!([call, "latest"])).await?;
+        let result = result.as_str().unwrap_or_default();
+        let word = hex::decode(result.trim_start_matches("0x"))
+            .map_err(|e| NodeError::Parse(e.to_string()))?;
+        if word.len() < 32 {
+            return Ok(None);
+        }
+
+        let address = &word[12..32];
+        if address.iter().all(|&b| b == 0) {
+            return Ok(None);
+        }
+        Ok(Some(format!("0x{}", hex::encode(address))))
+    }
+}
+
+/// EIP-137 namehash of an already normalised name.
+pub fn namehash(name: &str) -> [u8; 32] {
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(&node);
+        buf[32..].copy_from_slice(&keccak256(label.as_bytes()));
+        node = keccak256(&buf);
+    }
+    node
+}
+
+#[async_trait]
+impl NameResolver for EnsResolver {
+    fn supports(&self, name: &str) -> bool {
+        name.to_ascii_lowercase().ends_with(".eth")
+    }
+
+    async fn resolve(&self, name: &str) -> Result<String, ResolverError> {
+        let name = normalize_name(name)?;
+        let node = namehash(&name);
+
+        let not_found = || ResolverError::NotFound(name.clone());
+        let resolver = self
+            .call_address(&self.registry, RESOLVER_SELECTOR, &node)
+            .await?
+            .ok_or_else(not_found)?;
+        self.call_address(&resolver, ADDR_SELECTOR, &node)
+            .await?
+            .ok_or_else(not_found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namehash_matches_eip137_vectors() {
+        assert_eq!(namehash(""), [0u8; 32]);
+        assert_eq!(
+            hex::encode(namehash("eth")),
+            "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            hex::encode(namehash("foo.eth")),
+            "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(feature = "network")]
+pub mod ens;
+
+use crate::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::node::NodeError;
+
+#[cfg(feature = "network")]
+pub use ens::EnsResolver;
+
+/// Longest name accepted, matching the DNS limit.
+const MAX_NAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResolverError {
+    #[error("invalid name {0:?}")]
+    InvalidName(String),
+    #[error("name {0} has no address record")]
+    NotFound(String),
+    #[error(transparent)]
+    Node(#[from] NodeError),
+}
+
+/// Resolves human-readable names (`alice.eth`) to on-chain addresses.
+#[async_trait]
+pub trait NameResolver: Send + Sync {
+    /// Whether `name` belongs to a namespace this resolver handles.
+    fn supports(&self, name: &str) -> bool;
+
+    async fn resolve(&self, name: &str) -> Result<String, ResolverError>;
+}
+
+/// Lowercase `name` and reject anything outside `[a-z0-9-]` labels.
+///
+/// Non-ASCII names are refused rather than normalised, so look-alike
+/// (homoglyph) names can never silently resolve to an attacker's address.
+pub fn normalize_name(name: &str) -> Result<String, ResolverError> {
+    let invalid = || ResolverError::InvalidName(name.to_string());
+    if name.is_empty() || name.len() > MAX_NAME_LEN || !name.is_ascii() {
+        return Err(invalid());
+    }
+
+    let normalized = name.to_ascii_lowercase();
+    for label in normalized.split('.') {
+        let well_formed = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+        if !well_formed {
+            return Err(invalid());
+        }
+    }
+    Ok(normalized)
+}
+
+struct CacheEntry {
+    address: Option<String>,
+    stored_at: Instant,
+}
+
+/// Wraps a resolver with a bounded TTL cache.
+///
+/// Misses (`NotFound`) are cached too, under a separate TTL, so repeatedly
+/// sending to an unregistered name does not hammer the upstream node.
+pub struct CachingResolver<R: NameResolver> {
+    inner: R,
+    ttl: Duration,
+    negative_ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl<R: NameResolver> CachingResolver<R> {
+    pub fn new(inner: R, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            negative_ttl: ttl,
+            capacity: 10_000,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long an unregistered name is remembered as such.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Maximum number of cached names; the oldest entry is evicted when full.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    fn cached(&self, name: &str) -> Option<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(name)?;
+        let ttl = if entry.address.is_some() {
+            self.ttl
+        } else {
+            self.negative_ttl
+        };
+        (entry.stored_at.elapsed() < ttl).then(|| entry.address.clone())
+    }
+
+    fn store(&self, name: String, address: Option<String>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(&name) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            name,
+            CacheEntry {
+                address,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+}
+
+#[async_trait]
+impl<R: NameResolver> NameResolver for CachingResolver<R> {
+    fn supports(&self, name: &str) -> bool {
+        self.inner.supports(name)
+    }
+
+    async fn resolve(&self, name: &str) -> Result<String, ResolverError> {
+        let name = normalize_name(name)?;
+        if let Some(cached) = self.cached(&name) {
+            return cached.ok_or(ResolverError::NotFound(name));
+        }
+
+        match self.inner.resolve(&name).await {
+            Ok(address) => {
+                self.store(name, Some(address.clone()));
+                Ok(address)
+            }
+            Err(ResolverError::NotFound(_)) => {
+                self.store(name.clone(), None);
+                Err(ResolverError::NotFound(name))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counting {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl NameResolver for Counting {
+        fn supports(&self, name: &str) -> bool {
+            name.ends_with(".test")
+        }
+
+        async fn resolve(&self, name: &str) -> Result<String, ResolverError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match name {
+                "alice.test" => Ok("0xa11ce".to_string()),
+                _ => Err(ResolverError::NotFound(name.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_and_non_ascii_names() {
+        assert_eq!(normalize_name("Alice.ETH").unwrap(), "alice.eth");
+        assert!(normalize_name("alice..eth").is_err());
+        assert!(normalize_name("-alice.eth").is_err());
+        assert!(normalize_name("al ice.eth").is_err());
+        // Cyrillic "а" looks identical to Latin "a".
+        assert!(normalize_name("\u{0430}lice.eth").is_err());
+    }
+
+    #[tokio::test]
+    async fn caches_hits_and_misses() {
+        let resolver = CachingResolver::new(Counting::default(), Duration::from_secs(60));
+
+        assert_eq!(resolver.resolve("ALICE.test").await.unwrap(), "0xa11ce");
+        assert_eq!(resolver.resolve("alice.test").await.unwrap(), "0xa11ce");
+        assert!(resolver.resolve("bob.test").await.is_err());
+        assert_eq!(
+            resolver.resolve("bob.test").await,
+            Err(ResolverError::NotFound("bob.test".into()))
+        );
+        assert_eq!(resolver.inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,152 @@
+//! Calls to a `HashedTimelock` swap contract on EVM chains and Tron.
+//!
+//! The contract locks a payment under `sha256(preimage)` until a timelock:
+//! the receiver takes it with `withdraw(contractId, preimage)`, the sender
+//! takes it back with `refund(contractId)` afterwards. It is the account-chain
+//! counterpart of [`Htlc`](crate::wallet::chain::Htlc); both sides of a swap
+//! use the same secret hash.
+
+use crate::node::NodeError;
+use crate::wallet::chain::sdk::{base58check_decode, keccak256, sha256};
+
+/// One `HashedTimelock` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtlcCall {
+    /// `newContract(address,bytes32,uint256)`; the locked amount is the call value.
+    NewContract {
+        receiver: [u8; 20],
+        hashlock: [u8; 32],
+        /// Unix time, in seconds, the refund opens at.
+        timelock: u64,
+    },
+    /// `withdraw(bytes32,bytes32)`, revealing the preimage.
+    Withdraw {
+        contract_id: [u8; 32],
+        preimage: [u8; 32],
+    },
+    /// `refund(bytes32)`.
+    Refund { contract_id: [u8; 32] },
+}
+
+impl HtlcCall {
+    /// Lock the call value for `receiver` (a `0x` or base58 Tron address).
+    pub fn new_contract(
+        receiver: &str,
+        hashlock: [u8; 32],
+        timelock: u64,
+    ) -> Result<Self, NodeError> {
+        Ok(Self::NewContract {
+            receiver: address_bytes(receiver)?,
+            hashlock,
+            timelock,
+        })
+    }
+
+    /// Function signature, as Tron's `function_selector` expects it.
+    pub fn signature(&self) -> &'static str {
+        match self {
+            Self::NewContract { .. } => "newContract(address,bytes32,uint256)",
+            Self::Withdraw { .. } => "withdraw(bytes32,bytes32)",
+            Self::Refund { .. } => "refund(bytes32)",
+        }
+    }
+
+    /// ABI-encoded arguments (hex, no selector).
+    pub fn parameter(&self) -> String {
+        match self {
+            Self::NewContract {
+                receiver,
+                hashlock,
+                timelock,
+            } => format!(
+                "{:0>64}{}{:064x}",
+                hex::encode(receiver),
+                hex::encode(hashlock),
+                timelock
+            ),
+            Self::Withdraw {
+                contract_id,
+                preimage,
+            } => format!("{}{}", hex::encode(contract_id), hex::encode(preimage)),
+            Self::Refund { contract_id } => hex::encode(contract_id),
+        }
+    }
+
+    /// EVM call data: selector and arguments, `0x`-prefixed.
+    pub fn evm_data(&self) -> String {
+        let selector = &keccak256(self.signature().as_bytes())[..4];
+        format!("0x{}{}", hex::encode(selector), self.parameter())
+    }
+}
+
+/// Id `newContract` assigns: `sha256(sender ‖ receiver ‖ amount ‖ hashlock ‖ timelock)`,
+/// packed as Solidity's `abi.encodePacked` does. Both sides can compute it
+/// without reading the creation logs.
+pub fn contract_id(
+    sender: &str,
+    receiver: &str,
+    amount: u128,
+    hashlock: [u8; 32],
+    timelock: u64,
+) -> Result<[u8; 32], NodeError> {
+    let mut packed = Vec::with_capacity(20 + 20 + 32 + 32 + 32);
+    packed.extend_from_slice(&address_bytes(sender)?);
+    packed.extend_from_slice(&address_bytes(receiver)?);
+    packed.extend_from_slice(&[0; 16]);
+    packed.extend_from_slice(&amount.to_be_bytes());
+    packed.extend_from_slice(&hashlock);
+    packed.extend_from_slice(&[0; 24]);
+    packed.extend_from_slice(&timelock.to_be_bytes());
+    Ok(sha256(&packed))
+}
+
+/// The 20 bytes the EVM sees for a `0x` or base58 Tron address.
+pub fn address_bytes(address: &str) -> Result<[u8; 20], NodeError> {
+    let invalid = || NodeError::Parse(format!("invalid address {}", address));
+    if let Some(body) = address.strip_prefix("0x") {
+        return hex::decode(body)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(invalid);
+    }
+    let payload = base58check_decode(address).map_err(|_| invalid())?;
+    match payload.split_first() {
+        Some((0x41, rest)) => rest.try_into().map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_calls_for_both_chains() {
+        let refund = HtlcCall::Refund {
+            contract_id: [0xab; 32],
+        };
+        // keccak256("refund(bytes32)")[..4]
+        assert_eq!(&refund.evm_data()[..10], "0x7249fbb6");
+        assert_eq!(refund.parameter(), "ab".repeat(32));
+
+        let tron =
+            HtlcCall::new_contract("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", [1; 32], 1_700_000_000)
+                .unwrap();
+        let evm = HtlcCall::new_contract(
+            "0xa614f803b6fd780986a42c78ec9c7f77e6ded13c",
+            [1; 32],
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(tron, evm);
+        assert_eq!(
+            tron.parameter(),
+            format!(
+                "000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c{}{:064x}",
+                "01".repeat(32),
+                1_700_000_000u64
+            )
+        );
+        assert!(address_bytes("0x1234").is_err());
+    }
+}
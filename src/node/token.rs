@@ -0,0 +1,439 @@
+//! Well-known token contracts per network, and metadata of any other.
+
+use crate::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::node::utils::AmountFormatter;
+use crate::node::{NodeError, Transaction, TxDetails};
+
+/// A fungible token deployed at a fixed contract address on one network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenPreset {
+    pub symbol: &'static str,
+    /// Network identifier, e.g. `"tron"` or `"tron-nile"`.
+    pub network: &'static str,
+    /// Contract address in the network's native format.
+    pub contract: &'static str,
+    pub decimals: u32,
+}
+
+pub const USDT_TRON: TokenPreset = TokenPreset {
+    symbol: "USDT",
+    network: "tron",
+    contract: "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+    decimals: 6,
+};
+
+pub const USDT_TRON_NILE: TokenPreset = TokenPreset {
+    symbol: "USDT",
+    network: "tron-nile",
+    contract: "TXYZopYRdj2D9XRtbG411XZZ3kM5VkAeBf",
+    decimals: 6,
+};
+
+pub const USDT_ETHEREUM: TokenPreset = TokenPreset {
+    symbol: "USDT",
+    network: "ethereum",
+    contract: "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+    decimals: 6,
+};
+
+pub const PRESETS: &[TokenPreset] = &[USDT_TRON, USDT_TRON_NILE, USDT_ETHEREUM];
+
+/// A fungible token on one chain, whether one of the [`PRESETS`] or any
+/// other contract, e.g. from [`TokenMetadata`] or configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Token {
+    /// Network identifier, as in [`TokenPreset::network`].
+    pub chain_id: String,
+    /// Contract address in the network's native format.
+    pub contract: String,
+    pub decimals: u32,
+    pub symbol: String,
+}
+
+impl Token {
+    pub fn new(chain_id: &str, contract: &str, decimals: u32, symbol: &str) -> Self {
+        Self {
+            chain_id: chain_id.to_string(),
+            contract: contract.to_string(),
+            decimals,
+            symbol: symbol.to_string(),
+        }
+    }
+
+    /// The token at `metadata`'s contract on `chain_id`.
+    pub fn from_metadata(chain_id: &str, metadata: &TokenMetadata) -> Self {
+        Self::new(
+            chain_id,
+            &metadata.contract,
+            metadata.decimals,
+            &metadata.symbol,
+        )
+    }
+
+    /// Whether `contract` is this token's; hex addresses compare
+    /// case-insensitively, like in [`by_contract`].
+    pub fn is_contract(&self, contract: &str) -> bool {
+        self.contract.eq_ignore_ascii_case(contract)
+    }
+}
+
+/// How much of an owner's tokens a spender may move, as TRC-20 and ERC-20
+/// `approve` grant it and `allowance` reports it, in base units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Allowance {
+    Limited(u128),
+    /// Everything the owner holds, now and later: the `2^256 - 1` many dapps
+    /// ask for. Reported for any allowance beyond `u128` too.
+    Unlimited,
+}
+
+impl Allowance {
+    /// The allowance as a `uint256` ABI word, hex without `0x`.
+    pub fn abi_word(&self) -> String {
+        match self {
+            Allowance::Limited(amount) => format!("{:064x}", amount),
+            Allowance::Unlimited => "f".repeat(64),
+        }
+    }
+
+    /// Decode a big-endian `uint256` of at most 32 bytes.
+    pub fn from_word(bytes: &[u8]) -> Result<Self, NodeError> {
+        if bytes.len() > 32 {
+            return Err(NodeError::Parse(format!(
+                "allowance is not a uint256: {}",
+                hex::encode(bytes)
+            )));
+        }
+        let split = bytes.len().saturating_sub(16);
+        if bytes[..split].iter().any(|&b| b != 0) {
+            return Ok(Allowance::Unlimited);
+        }
+        let mut low = [0u8; 16];
+        low[16 - (bytes.len() - split)..].copy_from_slice(&bytes[split..]);
+        Ok(Allowance::Limited(u128::from_be_bytes(low)))
+    }
+
+    /// Whether the spender may move `amount` base units.
+    pub fn covers(&self, amount: u128) -> bool {
+        match self {
+            Allowance::Limited(allowed) => *allowed >= amount,
+            Allowance::Unlimited => true,
+        }
+    }
+}
+
+impl From<&TokenPreset> for Token {
+    fn from(preset: &TokenPreset) -> Self {
+        Self::new(
+            preset.network,
+            preset.contract,
+            preset.decimals,
+            preset.symbol,
+        )
+    }
+}
+
+impl From<TokenPreset> for Token {
+    fn from(preset: TokenPreset) -> Self {
+        Self::from(&preset)
+    }
+}
+
+/// Look up a preset by symbol (case-insensitive) and network.
+pub fn find(symbol: &str, network: &str) -> Option<&'static TokenPreset> {
+    PRESETS
+        .iter()
+        .find(|t| t.network == network && t.symbol.eq_ignore_ascii_case(symbol))
+}
+
+/// Look up a preset by contract address on `network`. Tron addresses are
+/// compared exactly, hex ones case-insensitively.
+pub fn by_contract(network: &str, contract: &str) -> Option<&'static TokenPreset> {
+    PRESETS
+        .iter()
+        .find(|t| t.network == network && t.contract.eq_ignore_ascii_case(contract))
+}
+
+/// One token held by an address, as [`ReadProvider::get_token_balances`] lists it.
+///
+/// [`ReadProvider::get_token_balances`]: crate::node::ReadProvider::get_token_balances
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenBalance {
+    pub contract: String,
+    /// In the token's base units.
+    pub balance: String,
+    /// Symbol and decimals, when the contract is one of the [`PRESETS`].
+    pub symbol: Option<String>,
+    pub decimals: Option<u32>,
+}
+
+impl TokenBalance {
+    /// A balance of `contract` on `network`, labelled if it is a known preset.
+    pub fn new(network: Option<&str>, contract: &str, balance: String) -> Self {
+        let preset = network.and_then(|network| by_contract(network, contract));
+        Self {
+            contract: contract.to_string(),
+            balance,
+            symbol: preset.map(|t| t.symbol.to_string()),
+            decimals: preset.map(|t| t.decimals),
+        }
+    }
+}
+
+/// Symbol, name and decimals a token contract reports about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub contract: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u32,
+}
+
+impl TokenMetadata {
+    /// `value` base units of this token for people, e.g. `12.5 USDT`.
+    pub fn format_amount(&self, value: &str, formatter: &dyn AmountFormatter) -> String {
+        formatter.format_amount(value, self.decimals, Some(&self.symbol))
+    }
+}
+
+/// Reads [`TokenMetadata`] from the contract: `symbol()`, `name()` and
+/// `decimals()` for TRC-20 and ERC-20 tokens.
+#[async_trait]
+pub trait TokenMetadataSource: Send + Sync {
+    async fn token_metadata(&self, contract: &str) -> Result<TokenMetadata, NodeError>;
+}
+
+/// [`TokenMetadataSource`] behind an in-process cache.
+///
+/// Metadata practically never changes, but a proxy contract can be upgraded
+/// and a lookup can hit a node that has not synced the deployment yet, so
+/// entries expire after a TTL instead of living forever.
+pub struct TokenMetadataCache {
+    source: Arc<dyn TokenMetadataSource>,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, TokenMetadata)>>,
+}
+
+impl TokenMetadataCache {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn new(source: Arc<dyn TokenMetadataSource>) -> Self {
+        Self {
+            source,
+            ttl: Self::DEFAULT_TTL,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Metadata of `contract`, from the cache while it is fresh. Failed
+    /// lookups are not cached.
+    pub async fn get(&self, contract: &str) -> Result<TokenMetadata, NodeError> {
+        let key = cache_key(contract);
+        if let Some((fetched, metadata)) = self.entries.lock().unwrap().get(&key)
+            && fetched.elapsed() < self.ttl
+        {
+            return Ok(metadata.clone());
+        }
+        let metadata = self.source.token_metadata(contract).await?;
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), metadata.clone()));
+        Ok(metadata)
+    }
+
+    /// Drop `contract`'s entry so the next [`get`](Self::get) refetches it.
+    pub fn invalidate(&self, contract: &str) {
+        self.entries.lock().unwrap().remove(&cache_key(contract));
+    }
+
+    /// Each decoded token transfer of `tx` as a human amount, e.g. `12.5
+    /// USDT`. A token whose metadata cannot be read keeps its base units
+    /// and contract address.
+    pub async fn format_token_transfers(
+        &self,
+        tx: &Transaction,
+        formatter: &dyn AmountFormatter,
+    ) -> Vec<String> {
+        let Some(TxDetails::Tvm(details)) = &tx.details else {
+            return Vec::new();
+        };
+        let mut rendered = Vec::with_capacity(details.token_transfers.len());
+        for transfer in &details.token_transfers {
+            rendered.push(match self.get(&transfer.token).await {
+                Ok(metadata) => metadata.format_amount(&transfer.value, formatter),
+                Err(_) => format!("{} {}", transfer.value, transfer.token),
+            });
+        }
+        rendered
+    }
+}
+
+/// Hex addresses differ only in checksum casing; base58 ones are exact.
+fn cache_key(contract: &str) -> String {
+    if contract.starts_with("0x") {
+        contract.to_ascii_lowercase()
+    } else {
+        contract.to_string()
+    }
+}
+
+/// Return value of a `string` getter. Some early tokens (MKR, SAI) return
+/// `bytes32` instead, padded with zeros; both are accepted.
+#[cfg(feature = "network")]
+pub(crate) fn decode_abi_string(data: &[u8]) -> Result<String, NodeError> {
+    let invalid = || NodeError::Parse(format!("invalid ABI string: {}", hex::encode(data)));
+    let bytes = if data.len() == 32 {
+        let end = data.iter().position(|&b| b == 0).unwrap_or(32);
+        &data[..end]
+    } else {
+        let word = |at: usize| -> Result<usize, NodeError> {
+            let word = data.get(at..at + 32).ok_or_else(invalid)?;
+            if word[..24].iter().any(|&b| b != 0) {
+                return Err(invalid());
+            }
+            Ok(u64::from_be_bytes(word[24..].try_into().expect("8 bytes")) as usize)
+        };
+        let offset = word(0)?;
+        let len = word(offset)?;
+        data.get(offset + 32..offset + 32 + len)
+            .ok_or_else(invalid)?
+    };
+    String::from_utf8(bytes.to_vec()).map_err(|_| invalid())
+}
+
+/// Return value of `decimals()`: a `uint8` in one 32-byte word.
+#[cfg(feature = "network")]
+pub(crate) fn decode_abi_decimals(data: &[u8]) -> Result<u32, NodeError> {
+    match data {
+        [zeros @ .., last] if data.len() == 32 && zeros.iter().all(|&b| b == 0) => Ok(*last as u32),
+        _ => Err(NodeError::Parse(format!(
+            "invalid decimals: {}",
+            hex::encode(data)
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::node::details::{TokenTransfer, TvmTxDetails};
+    use crate::node::utils::AmountStyle;
+
+    struct Contract(AtomicU32);
+
+    #[async_trait]
+    impl TokenMetadataSource for Contract {
+        async fn token_metadata(&self, contract: &str) -> Result<TokenMetadata, NodeError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(TokenMetadata {
+                contract: contract.to_string(),
+                symbol: "USDT".to_string(),
+                name: "Tether USD".to_string(),
+                decimals: 6,
+            })
+        }
+    }
+
+    #[test]
+    fn allowances_round_trip_through_abi_words() {
+        let unlimited = hex::decode(Allowance::Unlimited.abi_word()).unwrap();
+        assert_eq!(
+            Allowance::from_word(&unlimited).unwrap(),
+            Allowance::Unlimited
+        );
+        let limited = hex::decode(Allowance::Limited(12_500_000).abi_word()).unwrap();
+        assert_eq!(
+            Allowance::from_word(&limited).unwrap(),
+            Allowance::Limited(12_500_000)
+        );
+        // Nodes trim leading zeros off `eth_call` results at times.
+        assert_eq!(
+            Allowance::from_word(&[0x01, 0x00]).unwrap(),
+            Allowance::Limited(256)
+        );
+        assert!(Allowance::Limited(5).covers(5) && !Allowance::Limited(5).covers(6));
+        assert!(Allowance::from_word(&[0; 33]).is_err());
+    }
+
+    #[tokio::test]
+    async fn caches_metadata_and_renders_transfers() {
+        let source = Arc::new(Contract(AtomicU32::new(0)));
+        let cache = TokenMetadataCache::new(source.clone());
+        let tx = Transaction {
+            hash: "h".into(),
+            from: "a".into(),
+            to: USDT_TRON.contract.into(),
+            value: "0".into(),
+            block_number: 1,
+            timestamp: 0,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: Some(TxDetails::Tvm(TvmTxDetails {
+                token_transfers: vec![TokenTransfer {
+                    token: USDT_TRON.contract.into(),
+                    from: "a".into(),
+                    to: "b".into(),
+                    value: "12500000".into(),
+                }],
+                ..Default::default()
+            })),
+        };
+        let style = AmountStyle::english();
+        assert_eq!(
+            cache.format_token_transfers(&tx, &style).await,
+            ["12.5 USDT"]
+        );
+        cache.get(USDT_TRON.contract).await.unwrap();
+        assert_eq!(source.0.load(Ordering::SeqCst), 1);
+
+        let expired = TokenMetadataCache::new(source.clone()).with_ttl(Duration::ZERO);
+        expired.get("0xAbC").await.unwrap();
+        expired.get("0xabc").await.unwrap();
+        assert_eq!(source.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "network")]
+    fn decodes_string_and_bytes32_getters() {
+        let mut encoded = vec![0u8; 96];
+        encoded[31] = 0x20;
+        encoded[63] = 4;
+        encoded[64..68].copy_from_slice(b"USDT");
+        assert_eq!(decode_abi_string(&encoded).unwrap(), "USDT");
+
+        let mut bytes32 = [0u8; 32];
+        bytes32[..3].copy_from_slice(b"MKR");
+        assert_eq!(decode_abi_string(&bytes32).unwrap(), "MKR");
+
+        let mut decimals = [0u8; 32];
+        decimals[31] = 18;
+        assert_eq!(decode_abi_decimals(&decimals).unwrap(), 18);
+        assert!(decode_abi_decimals(&encoded).is_err());
+    }
+
+    #[test]
+    fn finds_usdt_per_network() {
+        assert_eq!(find("usdt", "tron"), Some(&USDT_TRON));
+        assert_eq!(find("USDT", "ethereum").unwrap().decimals, 6);
+        assert_eq!(find("USDT", "litecoin"), None);
+        assert_eq!(
+            by_contract("ethereum", &USDT_ETHEREUM.contract.to_lowercase()),
+            Some(&USDT_ETHEREUM)
+        );
+        assert_eq!(by_contract("tron-nile", USDT_TRON.contract), None);
+    }
+}
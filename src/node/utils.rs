@@ -1,3 +1,9 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures_util::StreamExt;
+
+use crate::node::{NodeError, ReadProvider};
+
 pub fn format_units(value: &str, decimals: u32) -> String {
     let decimals = decimals as usize;
     if value.is_empty() {
@@ -19,14 +25,205 @@ pub fn format_units(value: &str, decimals: u32) -> String {
         return padded;
     }
 
+    if decimals == 0 {
+        return value.to_string();
+    }
     let split_idx = value.len() - decimals;
     let (integer, fractional) = value.split_at(split_idx);
     format!("{}.{}", integer, fractional)
 }
 
+/// Which side of the number a currency symbol goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPosition {
+    /// `$1.00`, `€ 1,00`
+    Before,
+    /// `1.00 TRX`, `1,00 €`
+    After,
+}
+
+/// Renders base-unit amounts for people. Implement it to plug in an
+/// application's own locale rules; [`AmountStyle`] covers the common ones.
+pub trait AmountFormatter: Send + Sync {
+    /// `value` is a base-unit integer in decimal digits, shifted by `decimals`.
+    fn format_amount(&self, value: &str, decimals: u32, symbol: Option<&str>) -> String;
+}
+
+/// Separator, grouping and symbol conventions for [`AmountFormatter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountStyle {
+    pub decimal_separator: char,
+    /// Separator between groups of three integer digits; `None` for no grouping.
+    pub group_separator: Option<char>,
+    pub symbol_position: SymbolPosition,
+    /// Put a space between the symbol and the number.
+    pub symbol_space: bool,
+    /// Drop zeros at the end of the fraction (`1.500000` becomes `1.5`).
+    pub trim_trailing_zeros: bool,
+}
+
+impl Default for AmountStyle {
+    /// Same digits as [`format_units`]: `.` separator, no grouping, symbol after.
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: None,
+            symbol_position: SymbolPosition::After,
+            symbol_space: true,
+            trim_trailing_zeros: false,
+        }
+    }
+}
+
+impl AmountStyle {
+    /// `1,234.5 TRX`
+    pub fn english() -> Self {
+        Self {
+            group_separator: Some(','),
+            trim_trailing_zeros: true,
+            ..Self::default()
+        }
+    }
+
+    /// `1.234,5 TRX`, as in most of continental Europe.
+    pub fn continental() -> Self {
+        Self {
+            decimal_separator: ',',
+            group_separator: Some('.'),
+            trim_trailing_zeros: true,
+            ..Self::default()
+        }
+    }
+}
+
+impl AmountFormatter for AmountStyle {
+    fn format_amount(&self, value: &str, decimals: u32, symbol: Option<&str>) -> String {
+        let decimals = decimals as usize;
+        let digits = value.trim_start_matches('0');
+        if value.chars().any(|c| !c.is_ascii_digit()) {
+            return value.to_string();
+        }
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (integer, fraction) = padded.split_at(padded.len() - decimals);
+        let fraction = if self.trim_trailing_zeros {
+            fraction.trim_end_matches('0')
+        } else {
+            fraction
+        };
+
+        let mut number = String::with_capacity(padded.len() + padded.len() / 3 + 1);
+        for (i, digit) in integer.chars().enumerate() {
+            if let Some(separator) = self.group_separator
+                && i > 0
+                && (integer.len() - i) % 3 == 0
+            {
+                number.push(separator);
+            }
+            number.push(digit);
+        }
+        if !fraction.is_empty() {
+            number.push(self.decimal_separator);
+            number.push_str(fraction);
+        }
+
+        let space = if self.symbol_space { " " } else { "" };
+        match (symbol, self.symbol_position) {
+            (None, _) => number,
+            (Some(symbol), SymbolPosition::Before) => format!("{}{}{}", symbol, space, number),
+            (Some(symbol), SymbolPosition::After) => format!("{}{}{}", number, space, symbol),
+        }
+    }
+}
+
+/// Inverse of [`format_units`]: `"1.5"` with 6 decimals is `1500000`.
+/// Rejects more fractional digits than `decimals` instead of rounding.
+pub fn parse_units(value: &str, decimals: u32) -> Result<u128, NodeError> {
+    let invalid =
+        |reason: &str| NodeError::Parse(format!("invalid amount {:?}: {}", value, reason));
+    let (integer, fractional) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+    if integer.is_empty() && fractional.is_empty() {
+        return Err(invalid("empty"));
+    }
+    if !integer
+        .chars()
+        .chain(fractional.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid("not a decimal number"));
+    }
+    if fractional.len() > decimals as usize {
+        return Err(invalid("too many decimal places"));
+    }
+
+    let scale = 10u128
+        .checked_pow(decimals)
+        .ok_or_else(|| invalid("too many decimals"))?;
+    let integer: u128 = if integer.is_empty() {
+        0
+    } else {
+        integer.parse().map_err(|_| invalid("out of range"))?
+    };
+    let fractional: u128 = if fractional.is_empty() {
+        0
+    } else {
+        let padded = format!("{:0<width$}", fractional, width = decimals as usize);
+        padded.parse().map_err(|_| invalid("out of range"))?
+    };
+    integer
+        .checked_mul(scale)
+        .and_then(|v| v.checked_add(fractional))
+        .ok_or_else(|| invalid("out of range"))
+}
+
+/// Balances of many addresses, from [`get_balances`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BalanceReport {
+    /// Balance in base units, by address.
+    pub balances: BTreeMap<String, String>,
+    /// Why each address missing from `balances` could not be read.
+    pub errors: BTreeMap<String, NodeError>,
+}
+
+/// Look up the balance of each of `addresses` on `provider`, with at most
+/// `concurrency` requests in flight. A failed lookup is reported against its
+/// address without holding up the rest; repeated addresses are asked once.
+///
+/// Wrap `provider` in a [`RateLimitedProvider`](crate::node::RateLimitedProvider)
+/// to also bound the request rate. Providers with multi-address endpoints
+/// batch through [`ReadProvider::get_balances_bulk`] instead.
+pub async fn get_balances(
+    provider: &dyn ReadProvider,
+    addresses: &[&str],
+    concurrency: usize,
+) -> BalanceReport {
+    let addresses: BTreeSet<&str> = addresses.iter().copied().collect();
+    let calls: Vec<_> = addresses
+        .into_iter()
+        .map(|address| async move { (address, provider.get_balance(address).await) })
+        .collect();
+    let results: Vec<_> = futures_util::stream::iter(calls)
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut report = BalanceReport::default();
+    for (address, result) in results {
+        match result {
+            Ok(balance) => {
+                report.balances.insert(address.to_string(), balance);
+            }
+            Err(e) => {
+                report.errors.insert(address.to_string(), e);
+            }
+        }
+    }
+    report
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{MockCall, MockProvider};
 
     #[test]
     fn test_format_units() {
@@ -39,4 +236,51 @@ mod tests {
         // LTC case (8 decimals)
         assert_eq!(format_units("100000000", 8), "1.00000000");
     }
+
+    #[test]
+    fn test_amount_styles() {
+        let plain = AmountStyle::default();
+        assert_eq!(plain.format_amount("50059810", 6, None), "50.059810");
+        assert_eq!(plain.format_amount("0", 8, Some("LTC")), "0.00000000 LTC");
+
+        let english = AmountStyle::english();
+        assert_eq!(
+            english.format_amount("1234567500000", 6, Some("TRX")),
+            "1,234,567.5 TRX"
+        );
+        assert_eq!(english.format_amount("1000000", 6, None), "1");
+
+        let euro = AmountStyle {
+            symbol_position: SymbolPosition::Before,
+            symbol_space: false,
+            ..AmountStyle::continental()
+        };
+        assert_eq!(euro.format_amount("123456", 2, Some("€")), "€1.234,56");
+        assert_eq!(euro.format_amount("5", 0, None), "5");
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("50.05981", 6).unwrap(), 50_059_810);
+        assert_eq!(parse_units("1", 6).unwrap(), 1_000_000);
+        assert_eq!(parse_units(".5", 6).unwrap(), 500_000);
+        assert!(parse_units("0.0000001", 6).is_err());
+        assert!(parse_units("1,5", 6).is_err());
+        assert!(parse_units("", 6).is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_each_address_balance_or_error() {
+        let provider = MockProvider::new(6);
+        provider.set_balance("TA", 5);
+        provider.set_balance("TC", 7);
+        provider.fail_next(MockCall::GetBalance, NodeError::Http(429));
+
+        let report = get_balances(&provider, &["TA", "TB", "TC", "TA"], 2).await;
+        assert_eq!(provider.calls(MockCall::GetBalance), 3);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors["TA"], NodeError::Http(429));
+        assert_eq!(report.balances["TB"], "0");
+        assert_eq!(report.balances["TC"], "7");
+    }
 }
@@ -0,0 +1,402 @@
+//! Versioned binary records for state that outlives a process or leaves the
+//! machine (unsigned transactions carried to an air-gapped signer, key shares,
+//! monitor checkpoints).
+//!
+//! A record is
+//! `"FW" || 0 || kind length (u8) || kind || version (u16 BE) || created (u64 BE)
+//! || producer length (u8) || producer || CBOR body || HMAC-SHA256 tag`.
+//! The header is checked before the body is touched, so a record of the wrong
+//! kind or from a newer release fails cleanly instead of decoding as garbage,
+//! and the tag catches truncation and bit rot. Records from before the tag was
+//! added (`"FW" || kind length || ...`, no creation time) are still read.
+//!
+//! Encoding is deterministic: the same value, creation time and key always
+//! give the same bytes, so exported artifacts can be diffed and hashed.
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+use std::io;
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use thiserror::Error;
+
+const MAGIC: &[u8; 2] = b"FW";
+/// Where records without a tag had their (never empty) kind length.
+const TAGGED: u8 = 0;
+const TAG_LEN: usize = 32;
+/// Key for records that are only checked for integrity, not authenticity.
+const UNKEYED: &[u8] = b"flow-wallet record";
+
+/// Written into every record to tell which release produced it.
+pub const PRODUCER: &str = concat!("flow-wallet ", env!("CARGO_PKG_VERSION"));
+
+/// A type with a stable binary encoding.
+///
+/// Bump [`VERSION`](Self::VERSION) whenever the serialized shape changes in a
+/// way older readers cannot handle, and teach [`migrate`](Self::migrate) to
+/// lift the old shape; new optional fields marked `#[serde(default)]` do not
+/// need a bump.
+pub trait Persist: Serialize + DeserializeOwned {
+    /// Record tag, unique per type.
+    const KIND: &'static str;
+    /// Current format version; records with a higher version are rejected.
+    const VERSION: u16;
+
+    /// Rewrite the body of a record written at `version` (always below
+    /// [`VERSION`](Self::VERSION)) into the current shape.
+    fn migrate(body: ciborium::Value, version: u16) -> Result<ciborium::Value, PersistError> {
+        let _ = version;
+        Ok(body)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PersistError {
+    #[error("not a flow-wallet record")]
+    Magic,
+    #[error("expected a {expected} record, found {found}")]
+    Kind {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("{kind} record version {found} is newer than supported version {supported}")]
+    Version {
+        kind: &'static str,
+        found: u16,
+        supported: u16,
+    },
+    #[error("record failed its integrity check (corrupted, truncated or wrong key)")]
+    Integrity,
+    #[error("cannot migrate {kind} record from version {from}: {reason}")]
+    Migration {
+        kind: &'static str,
+        from: u16,
+        reason: String,
+    },
+    #[error("encoding failed: {0}")]
+    Encode(String),
+    #[error("decoding failed: {0}")]
+    Decode(String),
+}
+
+/// Header of a record, readable without knowing its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordInfo {
+    pub kind: String,
+    pub version: u16,
+    /// Unix seconds; `None` for records written before creation times were kept.
+    pub created_at: Option<u64>,
+    /// Release that wrote the record, e.g. `"flow-wallet 0.1.0"`.
+    pub producer: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn tag(key: &[u8], bytes: &[u8]) -> [u8; TAG_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(bytes);
+    mac.finalize().into_bytes().into()
+}
+
+/// Encode `value` with its header, stamped with the current time.
+pub fn to_bytes<T: Persist>(value: &T) -> Result<Vec<u8>, PersistError> {
+    to_bytes_at(value, now())
+}
+
+/// Encode `value` with an explicit creation time, for reproducible output.
+pub fn to_bytes_at<T: Persist>(value: &T, created_at: u64) -> Result<Vec<u8>, PersistError> {
+    encode(value, created_at, UNKEYED)
+}
+
+/// Like [`to_bytes_at`], but the tag is keyed so only holders of `key` can
+/// produce a record that [`from_bytes_keyed`] accepts.
+pub fn to_bytes_keyed<T: Persist>(
+    value: &T,
+    created_at: u64,
+    key: &[u8],
+) -> Result<Vec<u8>, PersistError> {
+    encode(value, created_at, key)
+}
+
+fn encode<T: Persist>(value: &T, created_at: u64, key: &[u8]) -> Result<Vec<u8>, PersistError> {
+    let kind = T::KIND.as_bytes();
+    let kind_len = u8::try_from(kind.len())
+        .ok()
+        .filter(|&len| len != TAGGED)
+        .ok_or_else(|| PersistError::Encode("kind must be 1 to 255 bytes".into()))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + 14 + kind.len() + PRODUCER.len());
+    out.extend_from_slice(MAGIC);
+    out.push(TAGGED);
+    out.push(kind_len);
+    out.extend_from_slice(kind);
+    out.extend_from_slice(&T::VERSION.to_be_bytes());
+    out.extend_from_slice(&created_at.to_be_bytes());
+    out.push(PRODUCER.len() as u8);
+    out.extend_from_slice(PRODUCER.as_bytes());
+    ciborium::into_writer(value, &mut out).map_err(|e| PersistError::Encode(e.to_string()))?;
+    let tag = tag(key, &out);
+    out.extend_from_slice(&tag);
+    Ok(out)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], PersistError> {
+    let (head, rest) = bytes
+        .split_at_checked(len)
+        .ok_or_else(|| PersistError::Decode("truncated header".into()))?;
+    *bytes = rest;
+    Ok(head)
+}
+
+/// Split a record into its header and CBOR body, checking the tag with `key`.
+fn parse<'a>(bytes: &'a [u8], key: &[u8]) -> Result<(RecordInfo, &'a [u8]), PersistError> {
+    let mut rest = bytes.strip_prefix(MAGIC).ok_or(PersistError::Magic)?;
+    let tagged = rest.first() == Some(&TAGGED);
+    if tagged {
+        let (signed, expected) = bytes
+            .split_last_chunk::<TAG_LEN>()
+            .ok_or(PersistError::Integrity)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+        mac.update(signed);
+        mac.verify_slice(expected)
+            .map_err(|_| PersistError::Integrity)?;
+        rest = &signed[MAGIC.len() + 1..];
+    }
+
+    let kind_len = take(&mut rest, 1)?[0];
+    let kind = String::from_utf8_lossy(take(&mut rest, kind_len as usize)?).into_owned();
+    let version = u16::from_be_bytes(take(&mut rest, 2)?.try_into().unwrap());
+    let (created_at, producer) = if tagged {
+        let created_at = u64::from_be_bytes(take(&mut rest, 8)?.try_into().unwrap());
+        let producer_len = take(&mut rest, 1)?[0];
+        let producer = String::from_utf8_lossy(take(&mut rest, producer_len as usize)?);
+        (Some(created_at), Some(producer.into_owned()))
+    } else {
+        (None, None)
+    };
+    let info = RecordInfo {
+        kind,
+        version,
+        created_at,
+        producer,
+    };
+    Ok((info, rest))
+}
+
+/// Read the header of a record and check its integrity, without decoding the body.
+pub fn inspect(bytes: &[u8]) -> Result<RecordInfo, PersistError> {
+    parse(bytes, UNKEYED).map(|(info, _)| info)
+}
+
+fn decode<T: Persist>(bytes: &[u8], key: &[u8]) -> Result<(T, RecordInfo), PersistError> {
+    let (info, body) = parse(bytes, key)?;
+    if info.kind != T::KIND {
+        return Err(PersistError::Kind {
+            expected: T::KIND,
+            found: info.kind,
+        });
+    }
+    if info.version > T::VERSION {
+        return Err(PersistError::Version {
+            kind: T::KIND,
+            found: info.version,
+            supported: T::VERSION,
+        });
+    }
+    let decode_error = |e: &dyn std::fmt::Display| PersistError::Decode(e.to_string());
+    let mut body: ciborium::Value = ciborium::from_reader(body).map_err(|e| decode_error(&e))?;
+    if info.version < T::VERSION {
+        body = T::migrate(body, info.version)?;
+    }
+    let value = body.deserialized().map_err(|e| decode_error(&e))?;
+    Ok((value, info))
+}
+
+/// Decode a record written by [`to_bytes`], migrating older versions, along
+/// with its header.
+pub fn from_bytes_with_info<T: Persist>(bytes: &[u8]) -> Result<(T, RecordInfo), PersistError> {
+    decode(bytes, UNKEYED)
+}
+
+/// Decode a record written by [`to_bytes`], with the version it was written at.
+pub fn from_bytes_versioned<T: Persist>(bytes: &[u8]) -> Result<(T, u16), PersistError> {
+    from_bytes_with_info(bytes).map(|(value, info)| (value, info.version))
+}
+
+/// Decode a record written by [`to_bytes`].
+pub fn from_bytes<T: Persist>(bytes: &[u8]) -> Result<T, PersistError> {
+    from_bytes_with_info(bytes).map(|(value, _)| value)
+}
+
+/// Decode a record written by [`to_bytes_keyed`] with the same `key`.
+///
+/// Records without a tag predate keyed records and are refused.
+pub fn from_bytes_keyed<T: Persist>(bytes: &[u8], key: &[u8]) -> Result<T, PersistError> {
+    if bytes.get(MAGIC.len()) != Some(&TAGGED) {
+        return Err(PersistError::Integrity);
+    }
+    decode(bytes, key).map(|(value, _)| value)
+}
+
+/// Write `value` to `path` as a record, replacing any previous file atomically.
+pub fn save<T: Persist>(path: impl AsRef<Path>, value: &T) -> std::io::Result<()> {
+    let bytes = to_bytes(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(tmp, path)
+}
+
+/// Record previously written by [`save`], or `None` if `path` does not exist.
+pub fn load<T: Persist>(path: impl AsRef<Path>) -> std::io::Result<Option<T>> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    from_bytes(&bytes)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::RawTransaction;
+    use crate::node::fee::FeePriority;
+    use serde::Deserialize;
+
+    #[test]
+    fn roundtrips_and_checks_the_header() {
+        let tx = RawTransaction::Json(
+            serde_json::json!({ "txID": "ab", "raw_data": { "expiration": 1 } }),
+        );
+        let bytes = to_bytes(&tx).unwrap();
+        assert_eq!(from_bytes::<RawTransaction>(&bytes).unwrap(), tx);
+
+        let psbt = RawTransaction::Psbt(vec![0x70, 0x73, 0x62, 0x74, 0xff]);
+        let bytes = to_bytes(&psbt).unwrap();
+        // Byte payloads are stored as CBOR byte strings, not integer arrays.
+        let empty = to_bytes(&RawTransaction::Psbt(Vec::new())).unwrap();
+        assert_eq!(bytes.len() - empty.len(), 5);
+        assert_eq!(from_bytes::<RawTransaction>(&bytes).unwrap(), psbt);
+
+        #[derive(Serialize, Deserialize)]
+        struct Other(FeePriority);
+        impl Persist for Other {
+            const KIND: &'static str = "other";
+            const VERSION: u16 = 1;
+        }
+        assert!(matches!(
+            from_bytes::<Other>(&bytes),
+            Err(PersistError::Kind { .. })
+        ));
+        assert!(matches!(
+            from_bytes::<Other>(b"{}"),
+            Err(PersistError::Magic)
+        ));
+
+        #[derive(Serialize, Deserialize)]
+        struct Newer(RawTransaction);
+        impl Persist for Newer {
+            const KIND: &'static str = "raw_tx";
+            const VERSION: u16 = u16::MAX;
+        }
+        let newer = to_bytes(&Newer(psbt.clone())).unwrap();
+        assert!(matches!(
+            from_bytes::<RawTransaction>(&newer),
+            Err(PersistError::Version {
+                found: u16::MAX,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn tags_are_deterministic_and_checked() {
+        let tx = RawTransaction::Bytes(vec![1, 2, 3]);
+        let bytes = to_bytes_at(&tx, 1_700_000_000).unwrap();
+        assert_eq!(to_bytes_at(&tx, 1_700_000_000).unwrap(), bytes);
+        let info = inspect(&bytes).unwrap();
+        assert_eq!(info.created_at, Some(1_700_000_000));
+        assert_eq!(info.producer.as_deref(), Some(PRODUCER));
+
+        let mut flipped = bytes.clone();
+        flipped[bytes.len() - TAG_LEN - 1] ^= 1;
+        assert_eq!(
+            from_bytes::<RawTransaction>(&flipped),
+            Err(PersistError::Integrity)
+        );
+        assert_eq!(
+            from_bytes::<RawTransaction>(&bytes[..bytes.len() - 1]),
+            Err(PersistError::Integrity)
+        );
+
+        let keyed = to_bytes_keyed(&tx, 0, b"backup key").unwrap();
+        assert_eq!(
+            from_bytes_keyed::<RawTransaction>(&keyed, b"backup key").unwrap(),
+            tx
+        );
+        assert_eq!(
+            from_bytes_keyed::<RawTransaction>(&keyed, b"other key"),
+            Err(PersistError::Integrity)
+        );
+        assert_eq!(
+            from_bytes_keyed::<RawTransaction>(&bytes, b"backup key"),
+            Err(PersistError::Integrity)
+        );
+    }
+
+    #[test]
+    fn migrates_untagged_records() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Limits {
+            daily: u64,
+            #[serde(default)]
+            per_send: u64,
+        }
+        impl Persist for Limits {
+            const KIND: &'static str = "limits";
+            const VERSION: u16 = 2;
+
+            // Version 1 stored a single limit as a bare integer.
+            fn migrate(
+                body: ciborium::Value,
+                version: u16,
+            ) -> Result<ciborium::Value, PersistError> {
+                match (version, body) {
+                    (1, ciborium::Value::Integer(daily)) => Ok(ciborium::Value::Map(vec![
+                        ("daily".into(), ciborium::Value::Integer(daily)),
+                        ("per_send".into(), ciborium::Value::Integer(daily)),
+                    ])),
+                    (from, _) => Err(PersistError::Migration {
+                        kind: Self::KIND,
+                        from,
+                        reason: "unexpected body".into(),
+                    }),
+                }
+            }
+        }
+
+        // Header layout used before records carried a tag.
+        let mut legacy = b"FW\x06limits\x00\x01".to_vec();
+        ciborium::into_writer(&500u64, &mut legacy).unwrap();
+        let (limits, info) = from_bytes_with_info::<Limits>(&legacy).unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                daily: 500,
+                per_send: 500
+            }
+        );
+        assert_eq!((info.version, info.created_at), (1, None));
+    }
+}
@@ -0,0 +1,116 @@
+//! Cooperative shutdown shared by monitors and send pipelines.
+//!
+//! Once triggered, long-running loops stop at their next iteration and wallets
+//! refuse to start new sends, while sends already in flight run to completion.
+//! [`Shutdown::drained`] resolves when the last of them has finished.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::Sender::new(false)),
+            in_flight: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// Ask every subsystem holding a clone of this token to stop.
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolve once [`trigger`](Self::trigger) has been called.
+    pub async fn triggered(&self) {
+        let mut rx = self.triggered.subscribe();
+        let _ = rx.wait_for(|t| *t).await;
+    }
+
+    /// Sleep for `duration`, waking early if shutdown is triggered.
+    /// Returns `true` if the caller should stop.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        tokio::time::timeout(duration, self.triggered())
+            .await
+            .is_ok()
+    }
+
+    /// Register a unit of work that must finish before shutdown completes.
+    /// Returns `None` once shutdown has been triggered.
+    pub fn enter(&self) -> Option<WorkGuard> {
+        if self.is_triggered() {
+            return None;
+        }
+        self.in_flight.send_modify(|n| *n += 1);
+        Some(WorkGuard {
+            in_flight: self.in_flight.clone(),
+        })
+    }
+
+    /// Number of units of work currently registered.
+    pub fn in_flight(&self) -> usize {
+        *self.in_flight.borrow()
+    }
+
+    /// Resolve when no registered work is in flight.
+    pub async fn drained(&self) {
+        let mut rx = self.in_flight.subscribe();
+        let _ = rx.wait_for(|n| *n == 0).await;
+    }
+
+    /// Trigger, then wait for in-flight work to finish.
+    pub async fn shutdown(&self) {
+        self.trigger();
+        self.drained().await;
+    }
+}
+
+/// Held while a unit of work is in flight; releases it on drop.
+pub struct WorkGuard {
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        self.in_flight.send_modify(|n| *n -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn refuses_new_work_and_waits_for_in_flight() {
+        let shutdown = Shutdown::new();
+        let guard = shutdown.enter().expect("accepting work");
+        shutdown.trigger();
+        assert!(shutdown.enter().is_none());
+        assert_eq!(shutdown.in_flight(), 1);
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drained().await }
+        });
+        drop(guard);
+        waiter.await.unwrap();
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+}
@@ -0,0 +1,183 @@
+//! Helpers shared by unit tests across modules.
+
+#[cfg(feature = "network")]
+pub mod http;
+
+use async_trait::async_trait;
+
+use crate::node::{
+    BalanceAt, NodeError, ProviderHealth, RawTransaction, ReadProvider, SyncStatus, Transaction,
+    TransactionDetail, TransactionProvider, TxStatus,
+};
+
+/// Provider whose reads return empty data and whose writes always fail.
+pub struct OfflineProvider;
+
+#[async_trait]
+impl ReadProvider for OfflineProvider {
+    fn get_decimals(&self) -> u32 {
+        6
+    }
+
+    async fn get_transactions(&self, _address: &str) -> Result<Vec<Transaction>, NodeError> {
+        Ok(vec![])
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(0)
+    }
+
+    async fn get_balance(&self, _address: &str) -> Result<String, NodeError> {
+        Ok("0".into())
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for OfflineProvider {
+    async fn create_transaction(
+        &self,
+        _from: &str,
+        _to: &str,
+        _amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        Err(NodeError::Network("offline".into()))
+    }
+
+    async fn broadcast_transaction(&self, _raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        Err(NodeError::Network("offline".into()))
+    }
+}
+
+/// Provider whose reads with a trait default answer something the default
+/// would not, so [`assert_forwards_reads`] catches a wrapper that leaves one
+/// to the default instead of asking the provider it wraps.
+pub struct DetailedProvider;
+
+const CONFIRMED: TxStatus = TxStatus::Confirmed {
+    block_number: 90,
+    confirmations: 11,
+};
+
+#[async_trait]
+impl ReadProvider for DetailedProvider {
+    fn get_decimals(&self) -> u32 {
+        6
+    }
+
+    async fn get_transactions(&self, _address: &str) -> Result<Vec<Transaction>, NodeError> {
+        Ok(vec![])
+    }
+
+    async fn get_transaction_status(&self, _hash: &str) -> Result<TxStatus, NodeError> {
+        Ok(CONFIRMED)
+    }
+
+    async fn get_transaction_detail(
+        &self,
+        hash: &str,
+    ) -> Result<Option<TransactionDetail>, NodeError> {
+        Ok(Some(TransactionDetail {
+            transaction: Transaction {
+                hash: hash.to_string(),
+                from: "TA".into(),
+                to: "TB".into(),
+                value: "1".into(),
+                block_number: 90,
+                timestamp: 0,
+                status: "SUCCESS".into(),
+                raw: None,
+                details: None,
+            },
+            status: CONFIRMED,
+            confirmations: 11,
+            fee: Some(5),
+            block_hash: Some("block".into()),
+        }))
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(100)
+    }
+
+    async fn get_balance(&self, _address: &str) -> Result<String, NodeError> {
+        Ok("0".into())
+    }
+
+    async fn get_balance_at(&self, _address: &str, _at: BalanceAt) -> Result<String, NodeError> {
+        Ok("42".into())
+    }
+
+    async fn get_balances_bulk(&self, addresses: &[String]) -> Vec<Result<String, NodeError>> {
+        addresses
+            .iter()
+            .map(|a| Ok(format!("bulk {}", a)))
+            .collect()
+    }
+
+    async fn health(&self) -> Result<ProviderHealth, NodeError> {
+        Ok(ProviderHealth {
+            latency: Default::default(),
+            block_height: 100,
+            sync: SyncStatus::Stale { seconds_behind: 7 },
+        })
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for DetailedProvider {
+    async fn create_transaction(
+        &self,
+        _from: &str,
+        _to: &str,
+        _amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        Err(NodeError::Network("offline".into()))
+    }
+
+    async fn broadcast_transaction(&self, _raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        Err(NodeError::Network("offline".into()))
+    }
+}
+
+/// Check that `provider`, wrapping a [`DetailedProvider`], passes on the
+/// reads the trait has defaults for.
+pub async fn assert_forwards_reads(provider: &dyn ReadProvider) {
+    assert_eq!(
+        provider.get_transaction_status("tx").await.unwrap(),
+        CONFIRMED
+    );
+    let detail = provider
+        .get_transaction_detail("tx")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(detail.fee, Some(5));
+    assert_eq!(detail.block_hash.as_deref(), Some("block"));
+    let at = BalanceAt::Block(50);
+    assert_eq!(provider.get_balance_at("TA", at).await.unwrap(), "42");
+    let balances = provider.get_balances_bulk(&["TA".into()]).await;
+    assert_eq!(balances[0].as_deref().unwrap(), "bulk TA");
+    assert_eq!(
+        provider.health().await.unwrap().sync,
+        SyncStatus::Stale { seconds_behind: 7 }
+    );
+}
+
+/// Unsigned TRON transfer, as a node would build one.
+pub fn tron_transfer() -> RawTransaction {
+    use prost::Message;
+
+    use crate::wallet::chain::tvm::proto::{self, ContractType, TransactionRaw};
+
+    let raw_data = TransactionRaw {
+        contract: vec![proto::Contract::pack(
+            ContractType::TransferContract,
+            &proto::TransferContract::default(),
+        )],
+        ..Default::default()
+    };
+    RawTransaction::Json(serde_json::json!({
+        "txID": hex::encode(raw_data.txid()),
+        "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+    }))
+}
@@ -0,0 +1,418 @@
+//! In-memory stand-ins for a node and a signer, for testing code built on
+//! this crate without TronGrid, Blockcypher or a key.
+//!
+//! [`MockProvider`] answers from state the test scripts and can be told to
+//! fail; [`MockSigner`] signs with a throwaway key and records what it was
+//! asked to sign.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use crate::node::{NodeError, RawTransaction, ReadProvider, Transaction, TransactionProvider};
+use crate::wallet::Signer;
+use crate::wallet::signer::local::LocalSigner;
+use crate::wallet::signer::{SignerError, SigningContext};
+
+/// A [`MockProvider`] method, for [`MockProvider::fail_next`] and
+/// [`MockProvider::calls`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockCall {
+    GetTransactions,
+    GetTransaction,
+    GetBlockNumber,
+    GetBalance,
+    CreateTransaction,
+    BroadcastTransaction,
+}
+
+type CreateFn = dyn Fn(&str, &str, u64) -> Result<RawTransaction, NodeError> + Send + Sync;
+type BroadcastFn = dyn Fn(&RawTransaction) -> Result<String, NodeError> + Send + Sync;
+
+#[derive(Default)]
+struct State {
+    balances: HashMap<String, String>,
+    transactions: Vec<Transaction>,
+    block_number: u64,
+    broadcasts: Vec<RawTransaction>,
+    failures: HashMap<MockCall, VecDeque<NodeError>>,
+    calls: HashMap<MockCall, usize>,
+}
+
+/// Scriptable in-memory [`Provider`].
+///
+/// Balances, history and the tip height are whatever the test sets;
+/// unknown addresses hold `0`. Transactions are built by the
+/// [`on_create`](Self::on_create) closure, and refused until one is given.
+/// Broadcasts succeed, are kept for [`broadcasts`](Self::broadcasts) and
+/// leave the transaction pending until [`confirm`](Self::confirm)ed.
+pub struct MockProvider {
+    decimals: u32,
+    state: Mutex<State>,
+    create: Option<Box<CreateFn>>,
+    broadcast: Option<Box<BroadcastFn>>,
+}
+
+impl MockProvider {
+    pub fn new(decimals: u32) -> Self {
+        Self {
+            decimals,
+            state: Mutex::new(State::default()),
+            create: None,
+            broadcast: None,
+        }
+    }
+
+    /// Build the transactions of sends with `create(from, to, amount)`.
+    pub fn on_create(
+        mut self,
+        create: impl Fn(&str, &str, u64) -> Result<RawTransaction, NodeError> + Send + Sync + 'static,
+    ) -> Self {
+        self.create = Some(Box::new(create));
+        self
+    }
+
+    /// Answer broadcasts with `broadcast(raw_tx)` instead of accepting them
+    /// under the SHA-256 of the transaction.
+    pub fn on_broadcast(
+        mut self,
+        broadcast: impl Fn(&RawTransaction) -> Result<String, NodeError> + Send + Sync + 'static,
+    ) -> Self {
+        self.broadcast = Some(Box::new(broadcast));
+        self
+    }
+
+    /// Set `address`'s balance, in base units.
+    pub fn set_balance(&self, address: &str, balance: impl ToString) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .balances
+            .insert(address.to_string(), balance.to_string());
+    }
+
+    pub fn set_block_number(&self, block_number: u64) {
+        self.state.lock().unwrap().block_number = block_number;
+    }
+
+    /// Add `tx` to the history of its sender and recipient, replacing any
+    /// transaction with the same hash.
+    pub fn add_transaction(&self, tx: Transaction) {
+        let mut state = self.state.lock().unwrap();
+        state.transactions.retain(|known| known.hash != tx.hash);
+        state.transactions.push(tx);
+    }
+
+    /// Mark the transaction `hash` as included in `block_number`. Returns
+    /// whether the provider knew it.
+    pub fn confirm(&self, hash: &str, block_number: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let Some(tx) = state.transactions.iter_mut().find(|tx| tx.hash == hash) else {
+            return false;
+        };
+        tx.block_number = block_number;
+        tx.status = "SUCCESS".to_string();
+        true
+    }
+
+    /// Make the next `call` fail with `error`. Queued failures are used up
+    /// one call at a time, in order.
+    pub fn fail_next(&self, call: MockCall, error: NodeError) {
+        let mut state = self.state.lock().unwrap();
+        state.failures.entry(call).or_default().push_back(error);
+    }
+
+    /// How many times `call` was made, failed or not.
+    pub fn calls(&self, call: MockCall) -> usize {
+        let state = self.state.lock().unwrap();
+        state.calls.get(&call).copied().unwrap_or(0)
+    }
+
+    /// Every transaction broadcast successfully, oldest first.
+    pub fn broadcasts(&self) -> Vec<RawTransaction> {
+        self.state.lock().unwrap().broadcasts.clone()
+    }
+
+    /// Count `call` and take its next scripted failure, if any.
+    fn enter(&self, call: MockCall) -> Result<std::sync::MutexGuard<'_, State>, NodeError> {
+        let mut state = self.state.lock().unwrap();
+        *state.calls.entry(call).or_default() += 1;
+        match state.failures.get_mut(&call).and_then(VecDeque::pop_front) {
+            Some(error) => Err(error),
+            None => Ok(state),
+        }
+    }
+}
+
+#[async_trait]
+impl ReadProvider for MockProvider {
+    fn get_decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+        let state = self.enter(MockCall::GetTransactions)?;
+        Ok(state
+            .transactions
+            .iter()
+            .filter(|tx| tx.from == address || tx.to == address)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+        let state = self.enter(MockCall::GetTransaction)?;
+        Ok(state
+            .transactions
+            .iter()
+            .find(|tx| tx.hash == hash)
+            .cloned())
+    }
+
+    async fn get_block_number(&self) -> Result<u64, NodeError> {
+        Ok(self.enter(MockCall::GetBlockNumber)?.block_number)
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+        let state = self.enter(MockCall::GetBalance)?;
+        Ok(state
+            .balances
+            .get(address)
+            .cloned()
+            .unwrap_or_else(|| "0".to_string()))
+    }
+}
+
+#[async_trait]
+impl TransactionProvider for MockProvider {
+    async fn create_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<RawTransaction, NodeError> {
+        drop(self.enter(MockCall::CreateTransaction)?);
+        match &self.create {
+            Some(create) => create(from, to, amount),
+            None => Err(NodeError::Api("no transaction scripted".to_string())),
+        }
+    }
+
+    async fn broadcast_transaction(&self, raw_tx: &RawTransaction) -> Result<String, NodeError> {
+        drop(self.enter(MockCall::BroadcastTransaction)?);
+        let hash = match &self.broadcast {
+            Some(broadcast) => broadcast(raw_tx)?,
+            None => {
+                let encoded =
+                    serde_json::to_vec(raw_tx).map_err(|e| NodeError::Parse(e.to_string()))?;
+                hex::encode(Sha256::digest(encoded))
+            }
+        };
+        let mut state = self.state.lock().unwrap();
+        state.broadcasts.push(raw_tx.clone());
+        if !state.transactions.iter().any(|tx| tx.hash == hash) {
+            state.transactions.push(Transaction {
+                hash: hash.clone(),
+                from: String::new(),
+                to: String::new(),
+                value: "0".to_string(),
+                block_number: 0,
+                timestamp: 0,
+                status: "PENDING".to_string(),
+                raw: None,
+                details: None,
+            });
+        }
+        Ok(hash)
+    }
+}
+
+/// One request a [`MockSigner`] received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignRequest {
+    /// The message, or the 32-byte digest when `prehashed`.
+    pub payload: Vec<u8>,
+    pub prehashed: bool,
+    /// What the wallet said the signature is for, if it said.
+    pub context: Option<SigningContext>,
+}
+
+/// secp256k1 [`Signer`] that signs with a test key and records every
+/// request; its signatures verify like a [`LocalSigner`]'s. Clones share
+/// the record and the queued failures, so a test can keep one while a
+/// wallet owns another.
+#[derive(Clone)]
+pub struct MockSigner {
+    key: Arc<LocalSigner>,
+    requests: Arc<Mutex<Vec<SignRequest>>>,
+    failures: Arc<Mutex<VecDeque<SignerError>>>,
+}
+
+impl MockSigner {
+    /// A signer for the secret scalar `secret_key`.
+    pub fn new(secret_key: [u8; 32]) -> Self {
+        Self {
+            key: Arc::new(
+                LocalSigner::from_bytes(secret_key).expect("invalid secp256k1 secret key"),
+            ),
+            requests: Arc::default(),
+            failures: Arc::default(),
+        }
+    }
+
+    /// Make the next request fail with `error`, e.g.
+    /// [`SignerError::Rejected`] for a user declining on a device.
+    pub fn fail_next(&self, error: SignerError) {
+        self.failures.lock().unwrap().push_back(error);
+    }
+
+    /// Every request so far, failed ones included, oldest first.
+    pub fn requests(&self) -> Vec<SignRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn record(
+        &self,
+        payload: &[u8],
+        prehashed: bool,
+        context: Option<&SigningContext>,
+    ) -> Result<(), SignerError> {
+        self.requests.lock().unwrap().push(SignRequest {
+            payload: payload.to_vec(),
+            prehashed,
+            context: context.cloned(),
+        });
+        match self.failures.lock().unwrap().pop_front() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for MockSigner {
+    fn default() -> Self {
+        Self::new([1u8; 32])
+    }
+}
+
+#[async_trait]
+impl Signer for MockSigner {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.record(message, false, None)?;
+        self.key.sign(message).await
+    }
+
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.record(prehash, true, None)?;
+        self.key.sign_prehash(prehash).await
+    }
+
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.record(message, false, Some(context))?;
+        self.key.sign(message).await
+    }
+
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.record(prehash, true, Some(context))?;
+        self.key.sign_prehash(prehash).await
+    }
+
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.record(prehash, true, None)?;
+        self.key.sign_recoverable(prehash).await
+    }
+
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.record(prehash, true, Some(context))?;
+        self.key.sign_recoverable(prehash).await
+    }
+
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.record(prehash, true, None)?;
+        self.key.sign_schnorr(prehash, tweak).await
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        self.key.public_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WalletError;
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::LITECOIN;
+    use crate::wallet::chain::utxo::{SighashType, UtxoInput, UtxoTxBuilder};
+
+    const TO: &str = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+
+    #[tokio::test]
+    async fn scripted_sends_fail_then_succeed() {
+        let signer = MockSigner::default();
+        let wallet = Wallet::new(signer.clone(), LITECOIN);
+        let from = wallet.address().unwrap();
+        let provider = MockProvider::new(8).on_create(|from, to, amount| {
+            let tx = UtxoTxBuilder::new(&LITECOIN)
+                .input(UtxoInput {
+                    prev_hash: [7; 32],
+                    output_index: 0,
+                    value: 100_000,
+                    script_pubkey: LITECOIN.script_pubkey(from).unwrap(),
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                })
+                .pay(to, amount)
+                .unwrap()
+                .build()
+                .unwrap();
+            Ok(tx.into_raw())
+        });
+        provider.set_balance(&from, 100_000);
+
+        provider.fail_next(
+            MockCall::BroadcastTransaction,
+            NodeError::Network("connection reset".into()),
+        );
+        assert!(matches!(
+            wallet.send_coins(&provider, TO, 50_000).await,
+            Err(WalletError::Node(NodeError::Network(_)))
+        ));
+        signer.fail_next(SignerError::Rejected("declined".into()));
+        assert!(wallet.send_coins(&provider, TO, 50_000).await.is_err());
+        assert!(provider.broadcasts().is_empty());
+
+        let tx_hash = wallet.send_coins(&provider, TO, 50_000).await.unwrap();
+        assert_eq!(provider.broadcasts().len(), 1);
+        assert_eq!(provider.calls(MockCall::BroadcastTransaction), 2);
+        assert_eq!(signer.requests().len(), 3);
+        assert_eq!(signer.requests()[2].context.as_ref().unwrap().to, TO);
+
+        assert!(provider.confirm(&tx_hash, 10));
+        provider.set_block_number(12);
+        assert_eq!(
+            provider.get_transaction_status(&tx_hash).await.unwrap(),
+            crate::node::TxStatus::Confirmed {
+                block_number: 10,
+                confirmations: 3,
+            }
+        );
+    }
+}
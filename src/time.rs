@@ -0,0 +1,11 @@
+//! Clocks that also work in a browser.
+//!
+//! `std::time::{Instant, SystemTime}` panic on `wasm32-unknown-unknown`,
+//! which has no clock of its own; `web-time` reads the JavaScript ones there
+//! and is the std types everywhere else.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
@@ -0,0 +1,393 @@
+//! Bundler and paymaster JSON-RPC clients, and smart-account sends through them.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use super::{SmartAccount, UserOperation, dummy_signature};
+use crate::node::network::RpcClient;
+use crate::node::network::eth::{EthProvider, abi_address, parse_quantity};
+use crate::node::utils::format_units;
+use crate::node::{FeePriority, NodeError};
+use crate::wallet::chain::{Chain, EvmChain};
+use crate::wallet::options::SendOptions;
+use crate::wallet::signer::SigningContext;
+use crate::wallet::{Signer, Wallet};
+
+/// `getNonce(address,uint192)` on the EntryPoint.
+const GET_NONCE_SELECTOR: &str = "35567e1a";
+
+/// Gas limits a bundler estimated for a user operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserOpGas {
+    pub pre_verification_gas: u128,
+    pub verification_gas_limit: u128,
+    pub call_gas_limit: u128,
+    pub paymaster_verification_gas_limit: Option<u128>,
+    pub paymaster_post_op_gas_limit: Option<u128>,
+}
+
+/// Outcome of an included user operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserOpReceipt {
+    /// Whether the account's call succeeded; the operation is included either way.
+    pub success: bool,
+    /// Bundle transaction the operation was included in.
+    pub transaction_hash: String,
+    /// Wei charged to the account or its paymaster.
+    pub actual_gas_cost: u128,
+}
+
+/// Paymaster fields of a [`UserOperation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymasterData {
+    pub paymaster: String,
+    pub paymaster_data: Vec<u8>,
+    pub verification_gas_limit: Option<u128>,
+    pub post_op_gas_limit: Option<u128>,
+}
+
+impl PaymasterData {
+    fn apply(self, op: &mut UserOperation) {
+        op.paymaster = Some(self.paymaster);
+        op.paymaster_data = self.paymaster_data;
+        if let Some(limit) = self.verification_gas_limit {
+            op.paymaster_verification_gas_limit = limit;
+        }
+        if let Some(limit) = self.post_op_gas_limit {
+            op.paymaster_post_op_gas_limit = limit;
+        }
+    }
+}
+
+/// A service that pays for user operations.
+#[async_trait]
+pub trait Paymaster: Send + Sync {
+    /// Placeholder fields to estimate gas with; defaults to [`sponsor`](Self::sponsor).
+    async fn stub(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+        chain_id: u64,
+    ) -> Result<PaymasterData, NodeError> {
+        self.sponsor(op, entry_point, chain_id).await
+    }
+
+    /// Final fields for `op`, whose gas limits are set. Refusing to sponsor is an error.
+    async fn sponsor(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+        chain_id: u64,
+    ) -> Result<PaymasterData, NodeError>;
+}
+
+/// ERC-7677 paymaster web service (`pm_getPaymasterStubData`, `pm_getPaymasterData`).
+pub struct PaymasterClient {
+    rpc: RpcClient,
+    context: Value,
+}
+
+impl PaymasterClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            rpc: RpcClient::new(url),
+            context: json!({}),
+        }
+    }
+
+    /// Service-specific context sent with every request, e.g. a sponsorship policy id.
+    pub fn with_context(mut self, context: Value) -> Self {
+        self.context = context;
+        self
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        op: &UserOperation,
+        entry_point: &str,
+        chain_id: u64,
+    ) -> Result<PaymasterData, NodeError> {
+        let params = json!([
+            op.to_json(),
+            entry_point,
+            format!("{:#x}", chain_id),
+            self.context
+        ]);
+        let result = self.rpc.call(method, params).await?;
+        let paymaster = result
+            .get("paymaster")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse(format!("{}: no paymaster", method)))?;
+        Ok(PaymasterData {
+            paymaster: paymaster.to_string(),
+            paymaster_data: hex_bytes(result.get("paymasterData"))?,
+            verification_gas_limit: optional_quantity(&result, "paymasterVerificationGasLimit")?,
+            post_op_gas_limit: optional_quantity(&result, "paymasterPostOpGasLimit")?,
+        })
+    }
+}
+
+#[async_trait]
+impl Paymaster for PaymasterClient {
+    async fn stub(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+        chain_id: u64,
+    ) -> Result<PaymasterData, NodeError> {
+        self.request("pm_getPaymasterStubData", op, entry_point, chain_id)
+            .await
+    }
+
+    async fn sponsor(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+        chain_id: u64,
+    ) -> Result<PaymasterData, NodeError> {
+        self.request("pm_getPaymasterData", op, entry_point, chain_id)
+            .await
+    }
+}
+
+/// ERC-4337 bundler endpoint, optionally paired with a [`Paymaster`].
+pub struct BundlerClient {
+    rpc: RpcClient,
+    paymaster: Option<Arc<dyn Paymaster>>,
+}
+
+impl BundlerClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            rpc: RpcClient::new(url),
+            paymaster: None,
+        }
+    }
+
+    /// Have `paymaster` pay for every operation built for this bundler.
+    pub fn with_paymaster(mut self, paymaster: Arc<dyn Paymaster>) -> Self {
+        self.paymaster = Some(paymaster);
+        self
+    }
+
+    pub async fn supported_entry_points(&self) -> Result<Vec<String>, NodeError> {
+        let result = self.rpc.call("eth_supportedEntryPoints", json!([])).await?;
+        serde_json::from_value(result).map_err(|e| NodeError::Parse(e.to_string()))
+    }
+
+    pub async fn estimate_user_operation_gas(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+    ) -> Result<UserOpGas, NodeError> {
+        let result = self
+            .rpc
+            .call(
+                "eth_estimateUserOperationGas",
+                json!([op.to_json(), entry_point]),
+            )
+            .await?;
+        let required = |key: &str| {
+            optional_quantity(&result, key)?
+                .ok_or_else(|| NodeError::Parse(format!("gas estimate without {}", key)))
+        };
+        Ok(UserOpGas {
+            pre_verification_gas: required("preVerificationGas")?,
+            verification_gas_limit: required("verificationGasLimit")?,
+            call_gas_limit: required("callGasLimit")?,
+            paymaster_verification_gas_limit: optional_quantity(
+                &result,
+                "paymasterVerificationGasLimit",
+            )?,
+            paymaster_post_op_gas_limit: optional_quantity(&result, "paymasterPostOpGasLimit")?,
+        })
+    }
+
+    /// Submit a signed operation; returns its hash.
+    pub async fn send_user_operation(
+        &self,
+        op: &UserOperation,
+        entry_point: &str,
+    ) -> Result<String, NodeError> {
+        let result = self
+            .rpc
+            .call("eth_sendUserOperation", json!([op.to_json(), entry_point]))
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| NodeError::Parse(format!("unexpected user operation hash: {}", result)))
+    }
+
+    /// Receipt of an operation, or `None` while it is not yet included.
+    pub async fn get_user_operation_receipt(
+        &self,
+        hash: &str,
+    ) -> Result<Option<UserOpReceipt>, NodeError> {
+        let result = self
+            .rpc
+            .call("eth_getUserOperationReceipt", json!([hash]))
+            .await?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        let transaction_hash = result
+            .pointer("/receipt/transactionHash")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| NodeError::Parse("receipt without transactionHash".to_string()))?;
+        Ok(Some(UserOpReceipt {
+            success: result
+                .get("success")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            transaction_hash: transaction_hash.to_string(),
+            actual_gas_cost: optional_quantity(&result, "actualGasCost")?.unwrap_or(0),
+        }))
+    }
+}
+
+impl<T: Signer> Wallet<EvmChain, T> {
+    /// Unsigned operation making `account` run `call_data`, with its nonce,
+    /// fees, gas limits and (if the bundler has one) paymaster fields filled in.
+    pub async fn build_user_operation(
+        &self,
+        node: &EthProvider,
+        bundler: &BundlerClient,
+        account: &SmartAccount,
+        call_data: Vec<u8>,
+        priority: FeePriority,
+    ) -> Result<UserOperation, crate::WalletError> {
+        let nonce_call = format!(
+            "0x{}{}{:064x}",
+            GET_NONCE_SELECTOR,
+            abi_address(&account.address)?,
+            0
+        );
+        let nonce = parse_quantity(
+            &node
+                .rpc(
+                    "eth_call",
+                    json!([{ "to": account.entry_point, "data": nonce_call }, "latest"]),
+                )
+                .await?,
+        )?;
+        let market = node.fee_market().await?;
+
+        let mut op = UserOperation {
+            sender: account.address.clone(),
+            nonce,
+            call_data,
+            max_fee_per_gas: market.max_fee(priority),
+            max_priority_fee_per_gas: market.tip(priority),
+            signature: dummy_signature(),
+            ..Default::default()
+        };
+        if let Some((factory, factory_data)) = &account.factory {
+            let code = node
+                .rpc("eth_getCode", json!([account.address, "latest"]))
+                .await?;
+            if code.as_str().is_none_or(|code| code == "0x") {
+                op.factory = Some(factory.clone());
+                op.factory_data = factory_data.clone();
+            }
+        }
+
+        if let Some(paymaster) = &bundler.paymaster {
+            paymaster
+                .stub(&op, &account.entry_point, account.chain_id)
+                .await?
+                .apply(&mut op);
+        }
+        let gas = bundler
+            .estimate_user_operation_gas(&op, &account.entry_point)
+            .await?;
+        op.pre_verification_gas = gas.pre_verification_gas;
+        op.verification_gas_limit = gas.verification_gas_limit;
+        op.call_gas_limit = gas.call_gas_limit;
+        if let Some(limit) = gas.paymaster_verification_gas_limit {
+            op.paymaster_verification_gas_limit = limit;
+        }
+        if let Some(limit) = gas.paymaster_post_op_gas_limit {
+            op.paymaster_post_op_gas_limit = limit;
+        }
+        if let Some(paymaster) = &bundler.paymaster {
+            paymaster
+                .sponsor(&op, &account.entry_point, account.chain_id)
+                .await?
+                .apply(&mut op);
+        }
+        Ok(op)
+    }
+
+    /// Send `amount` wei from the smart `account` this wallet's key owns,
+    /// through `bundler`. The send is approved and audited like
+    /// [`send_coins_with_options`](Self::send_coins_with_options); the
+    /// returned hash is the user operation's, not a transaction's.
+    pub async fn send_user_operation(
+        &self,
+        node: &EthProvider,
+        bundler: &BundlerClient,
+        account: &SmartAccount,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> Result<String, crate::WalletError> {
+        let _in_flight = match &self.shutdown {
+            Some(shutdown) => Some(shutdown.enter().ok_or(crate::WalletError::ShuttingDown)?),
+            None => None,
+        };
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(async {
+                let from = &account.address;
+                let to = &self.resolve_recipient(to).await?;
+                self.audited_send(from, to, amount, options, || async {
+                    let call_data = SmartAccount::execute_call(to, amount as u128, &[])?;
+                    let mut op = self
+                        .build_user_operation(
+                            node,
+                            bundler,
+                            account,
+                            call_data,
+                            options.fee_priority,
+                        )
+                        .await?;
+                    let context = SigningContext {
+                        chain: self.chain.id().to_string(),
+                        from: from.clone(),
+                        to: to.clone(),
+                        amount,
+                        summary: options.summary.clone().unwrap_or_else(|| {
+                            format!(
+                                "Send {} on {} to {} from smart account {}",
+                                format_units(&amount.to_string(), 18),
+                                self.chain.id(),
+                                to,
+                                from
+                            )
+                        }),
+                        metadata: options.metadata.clone(),
+                    };
+                    self.sign_user_operation(&mut op, account, Some(&context))
+                        .await?;
+                    Ok(bundler
+                        .send_user_operation(&op, &account.entry_point)
+                        .await?)
+                })
+                .await
+            })
+            .await
+    }
+}
+
+fn optional_quantity(result: &Value, key: &str) -> Result<Option<u128>, NodeError> {
+    result.get(key).map(parse_quantity).transpose()
+}
+
+fn hex_bytes(value: Option<&Value>) -> Result<Vec<u8>, NodeError> {
+    let text = value.and_then(|v| v.as_str()).unwrap_or("0x");
+    hex::decode(text.trim_start_matches("0x")).map_err(|e| NodeError::Parse(e.to_string()))
+}
@@ -0,0 +1,282 @@
+//! ERC-4337 account abstraction: sends from smart contract accounts.
+//!
+//! A smart account does not sign transactions. Its owner key signs a
+//! [`UserOperation`]; a bundler submits it to the `EntryPoint` contract, which
+//! asks the account to validate the signature and then runs its call. The
+//! owner key is an ordinary [`Signer`], so local, MPC and hardware signers all
+//! work, and sends go through the wallet's approval policy and audit log
+//! like [`Wallet::send_coins`] does.
+//!
+//! Only EntryPoint v0.7 and accounts that check an EIP-191 signature of the
+//! operation hash (SimpleAccount and most of its descendants) are supported.
+
+#[cfg(feature = "network")]
+pub mod bundler;
+
+#[cfg(feature = "network")]
+pub use bundler::{
+    BundlerClient, Paymaster, PaymasterClient, PaymasterData, UserOpGas, UserOpReceipt,
+};
+
+use serde_json::{Value, json};
+
+use crate::wallet::chain::sdk::keccak256;
+use crate::wallet::chain::{Chain, ChainError, EvmChain, SignatureFormat};
+use crate::wallet::scheme::{Secp256k1, SignatureScheme};
+use crate::wallet::signer::SigningContext;
+use crate::wallet::{Signer, Wallet};
+
+/// EntryPoint v0.7, deployed at the same address on every EVM network.
+pub const ENTRY_POINT_V07: &str = "0x0000000071727De22E5E9d8BAf0edAc6f37da032";
+
+/// `execute(address,uint256,bytes)`, SimpleAccount's single-call entry.
+const EXECUTE_SELECTOR: [u8; 4] = [0xb6, 0x1d, 0x27, 0xf6];
+
+/// A smart contract account and the network it lives on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartAccount {
+    pub address: String,
+    pub chain_id: u64,
+    pub entry_point: String,
+    /// Factory and its calldata, to deploy the account with its first operation.
+    pub factory: Option<(String, Vec<u8>)>,
+}
+
+impl SmartAccount {
+    pub fn new(address: &str, chain_id: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            chain_id,
+            entry_point: ENTRY_POINT_V07.to_string(),
+            factory: None,
+        }
+    }
+
+    /// Deploy the account through `factory` if it has no code yet.
+    pub fn with_factory(mut self, factory: &str, factory_data: Vec<u8>) -> Self {
+        self.factory = Some((factory.to_string(), factory_data));
+        self
+    }
+
+    /// Calldata making the account call `to` with `value` wei and `data`.
+    pub fn execute_call(to: &str, value: u128, data: &[u8]) -> Result<Vec<u8>, ChainError> {
+        let mut out = EXECUTE_SELECTOR.to_vec();
+        out.extend_from_slice(&word_address(to)?);
+        out.extend_from_slice(&word(value));
+        out.extend_from_slice(&word(0x60));
+        out.extend_from_slice(&word(data.len() as u128));
+        out.extend_from_slice(data);
+        out.resize(out.len() + (32 - data.len() % 32) % 32, 0);
+        Ok(out)
+    }
+}
+
+/// An ERC-4337 v0.7 user operation, in its unpacked RPC form.
+///
+/// Nonces carry a 192-bit key above a 64-bit sequence; keys wider than 64
+/// bits are not supported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserOperation {
+    pub sender: String,
+    pub nonce: u128,
+    pub factory: Option<String>,
+    pub factory_data: Vec<u8>,
+    pub call_data: Vec<u8>,
+    pub call_gas_limit: u128,
+    pub verification_gas_limit: u128,
+    pub pre_verification_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub paymaster: Option<String>,
+    pub paymaster_verification_gas_limit: u128,
+    pub paymaster_post_op_gas_limit: u128,
+    pub paymaster_data: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl UserOperation {
+    /// Hash the account validates a signature over: the packed operation
+    /// bound to `entry_point` and `chain_id`.
+    pub fn hash(&self, entry_point: &str, chain_id: u64) -> Result<[u8; 32], ChainError> {
+        let init_code = match &self.factory {
+            Some(factory) => [address_bytes(factory)?.as_slice(), &self.factory_data].concat(),
+            None => Vec::new(),
+        };
+        let paymaster_and_data = match &self.paymaster {
+            Some(paymaster) => [
+                address_bytes(paymaster)?.as_slice(),
+                &self.paymaster_verification_gas_limit.to_be_bytes(),
+                &self.paymaster_post_op_gas_limit.to_be_bytes(),
+                &self.paymaster_data,
+            ]
+            .concat(),
+            None => Vec::new(),
+        };
+
+        let mut packed = Vec::with_capacity(8 * 32);
+        packed.extend_from_slice(&word_address(&self.sender)?);
+        packed.extend_from_slice(&word(self.nonce));
+        packed.extend_from_slice(&keccak256(&init_code));
+        packed.extend_from_slice(&keccak256(&self.call_data));
+        packed.extend_from_slice(&self.verification_gas_limit.to_be_bytes());
+        packed.extend_from_slice(&self.call_gas_limit.to_be_bytes());
+        packed.extend_from_slice(&word(self.pre_verification_gas));
+        packed.extend_from_slice(&self.max_priority_fee_per_gas.to_be_bytes());
+        packed.extend_from_slice(&self.max_fee_per_gas.to_be_bytes());
+        packed.extend_from_slice(&keccak256(&paymaster_and_data));
+
+        let mut outer = Vec::with_capacity(3 * 32);
+        outer.extend_from_slice(&keccak256(&packed));
+        outer.extend_from_slice(&word_address(entry_point)?);
+        outer.extend_from_slice(&word(chain_id as u128));
+        Ok(keccak256(&outer))
+    }
+
+    /// The operation as `eth_sendUserOperation` and friends take it.
+    pub fn to_json(&self) -> Value {
+        let quantity = |n: u128| format!("{:#x}", n);
+        let bytes = |b: &[u8]| format!("0x{}", hex::encode(b));
+        let mut op = json!({
+            "sender": self.sender,
+            "nonce": quantity(self.nonce),
+            "callData": bytes(&self.call_data),
+            "callGasLimit": quantity(self.call_gas_limit),
+            "verificationGasLimit": quantity(self.verification_gas_limit),
+            "preVerificationGas": quantity(self.pre_verification_gas),
+            "maxFeePerGas": quantity(self.max_fee_per_gas),
+            "maxPriorityFeePerGas": quantity(self.max_priority_fee_per_gas),
+            "signature": bytes(&self.signature),
+        });
+        if let Some(factory) = &self.factory {
+            op["factory"] = factory.clone().into();
+            op["factoryData"] = bytes(&self.factory_data).into();
+        }
+        if let Some(paymaster) = &self.paymaster {
+            op["paymaster"] = paymaster.clone().into();
+            op["paymasterVerificationGasLimit"] =
+                quantity(self.paymaster_verification_gas_limit).into();
+            op["paymasterPostOpGasLimit"] = quantity(self.paymaster_post_op_gas_limit).into();
+            op["paymasterData"] = bytes(&self.paymaster_data).into();
+        }
+        op
+    }
+}
+
+/// A well-formed 65-byte signature for gas estimation; it does not validate.
+pub fn dummy_signature() -> Vec<u8> {
+    let mut signature = vec![0xff; 64];
+    signature.push(0x1c);
+    signature
+}
+
+/// `keccak256("\x19Ethereum Signed Message:\n32" || hash)`, what accounts
+/// recover the owner from.
+fn eth_signed_message_hash(hash: &[u8; 32]) -> [u8; 32] {
+    keccak256(&[b"\x19Ethereum Signed Message:\n32".as_slice(), hash].concat())
+}
+
+impl<T: Signer> Wallet<EvmChain, T> {
+    /// Sign `op` for `account` with this wallet's key, the account's owner.
+    pub async fn sign_user_operation(
+        &self,
+        op: &mut UserOperation,
+        account: &SmartAccount,
+        context: Option<&SigningContext>,
+    ) -> Result<(), crate::WalletError> {
+        self.check_can_sign()?;
+        self.check_network()?;
+        let digest = eth_signed_message_hash(&op.hash(&account.entry_point, account.chain_id)?);
+        let signed = match context {
+            Some(context) => {
+                self.signer
+                    .sign_recoverable_with_context(&digest, context)
+                    .await
+            }
+            None => self.signer.sign_recoverable(&digest).await,
+        };
+        let signature = signed.map_err(|source| crate::WalletError::SigningFailed {
+            context: format!(
+                "user operation of {} on {}",
+                account.address,
+                self.chain.id()
+            ),
+            source,
+        })?;
+        // OpenZeppelin's `ECDSA.recover`, which most accounts use, rejects high-s.
+        let mut signature = Secp256k1::canonicalize(
+            &signature,
+            SignatureFormat::Recoverable,
+            &self.signature_policy(),
+        )?;
+        if let Some(v) = signature.get_mut(64) {
+            *v = 27 + (*v & 1);
+        }
+        op.signature = signature;
+        Ok(())
+    }
+}
+
+fn address_bytes(address: &str) -> Result<[u8; 20], ChainError> {
+    let invalid = || ChainError::InvalidAddress(address.to_string());
+    let body = address.strip_prefix("0x").ok_or_else(invalid)?;
+    hex::decode(body)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(invalid)
+}
+
+fn word_address(address: &str) -> Result<[u8; 32], ChainError> {
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(&address_bytes(address)?);
+    Ok(out)
+}
+
+fn word(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    use super::*;
+    use crate::wallet::chain::{ETHEREUM, evm_address_from_pubkey};
+    use crate::wallet::signer::LocalSigner;
+
+    #[tokio::test]
+    async fn owner_signature_recovers_over_the_eip191_hash() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), ETHEREUM);
+        let account = SmartAccount::new("0x00000000000000000000000000000000000000aa", 1);
+        let mut op = UserOperation {
+            sender: account.address.clone(),
+            call_data: SmartAccount::execute_call(
+                "0x00000000000000000000000000000000000000bb",
+                1_000,
+                &[],
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(op.call_data.len(), 4 + 4 * 32);
+        assert_eq!(&op.call_data[..4], &EXECUTE_SELECTOR);
+
+        wallet
+            .sign_user_operation(&mut op, &account, None)
+            .await
+            .unwrap();
+        let hash = op.hash(&account.entry_point, 1).unwrap();
+        assert_ne!(hash, op.hash(&account.entry_point, 10).unwrap());
+
+        let signature = Signature::from_slice(&op.signature[..64]).unwrap();
+        let recid = RecoveryId::from_byte(op.signature[64] - 27).unwrap();
+        let key =
+            VerifyingKey::recover_from_prehash(&eth_signed_message_hash(&hash), &signature, recid)
+                .unwrap();
+        assert_eq!(
+            evm_address_from_pubkey(&key.to_sec1_bytes()).unwrap(),
+            wallet.address().unwrap()
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Delayed execution of large sends, with a window in which they can be cancelled.
+
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::WalletError;
+use crate::node::Provider;
+use crate::persist::{self, Persist};
+use crate::wallet::approval::{ApprovalError, ApprovalPolicy, ApprovalRequest};
+use crate::wallet::chain::Chain;
+use crate::wallet::{Signer, Wallet};
+
+/// Where a [`DelayedSend`] stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DelayedStatus {
+    /// Waiting for its window to pass.
+    Pending,
+    Cancelled,
+    Executed {
+        tx_hash: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// A send held back by a [`DelayPolicy`]. Only the intent is kept: nothing
+/// is built or signed until it executes, so a cancelled send leaves no
+/// transaction behind that could still be broadcast.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelayedSend {
+    pub id: String,
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the chain's base unit.
+    pub amount: u64,
+    /// Unix seconds.
+    pub queued_at: u64,
+    /// Unix seconds after which [`DelayPolicy::execute_due`] sends it.
+    pub execute_at: u64,
+    pub status: DelayedStatus,
+}
+
+/// Change to the queue of a [`DelayPolicy`], for notifying the people who
+/// may want to cancel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelayEvent {
+    Queued(DelayedSend),
+    Cancelled(DelayedSend),
+    Executed(DelayedSend),
+    Failed(DelayedSend),
+}
+
+/// Queue of a [`DelayPolicy`], as kept in its state file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelayQueue {
+    pub sends: Vec<DelayedSend>,
+}
+
+impl Persist for DelayQueue {
+    const KIND: &'static str = "delay_queue";
+    const VERSION: u16 = 1;
+}
+
+/// Holds sends above an amount for a cancellation window before they go out.
+///
+/// As an [`ApprovalPolicy`] it refuses such a send with
+/// [`ApprovalError::Delayed`] and queues its intent instead. Anyone can
+/// [`cancel`](Self::cancel) it until `execute_at`; after that a worker's
+/// [`execute_due`](Self::execute_due) sends it through the same wallet,
+/// whose other policies and audit log still apply.
+pub struct DelayPolicy {
+    window: Duration,
+    threshold: u64,
+    queue: Mutex<DelayQueue>,
+    /// Ids being executed, which `approve` lets through once.
+    releasing: Mutex<Vec<String>>,
+    state_file: Option<PathBuf>,
+    events: broadcast::Sender<DelayEvent>,
+}
+
+impl DelayPolicy {
+    /// Delay every send by `window`.
+    pub fn new(window: Duration) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            window,
+            threshold: 0,
+            queue: Mutex::new(DelayQueue::default()),
+            releasing: Mutex::new(Vec::new()),
+            state_file: None,
+            events,
+        }
+    }
+
+    /// Only delay sends of more than `amount` base units.
+    pub fn above(mut self, amount: u64) -> Self {
+        self.threshold = amount;
+        self
+    }
+
+    /// Keep the queue in `path`, loading it if the file exists, so queued
+    /// sends survive a restart.
+    pub fn with_state_file(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(queue) = persist::load::<DelayQueue>(&path)? {
+            self.queue = Mutex::new(queue);
+        }
+        self.state_file = Some(path);
+        Ok(self)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DelayEvent> {
+        self.events.subscribe()
+    }
+
+    /// Sends still waiting, oldest first.
+    pub fn pending(&self) -> Vec<DelayedSend> {
+        self.queue
+            .lock()
+            .unwrap()
+            .sends
+            .iter()
+            .filter(|send| send.status == DelayedStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    /// Every send the policy has queued, whatever became of it.
+    pub fn queue(&self) -> DelayQueue {
+        self.queue.lock().unwrap().clone()
+    }
+
+    /// Stop the pending send `id` from executing.
+    pub fn cancel(&self, id: &str) -> Result<DelayedSend, ApprovalError> {
+        let cancelled = self.update(id, |send| {
+            if send.status != DelayedStatus::Pending {
+                return Err(ApprovalError::Denied(format!(
+                    "delayed send {} is no longer pending",
+                    id
+                )));
+            }
+            send.status = DelayedStatus::Cancelled;
+            Ok(())
+        })?;
+        let _ = self.events.send(DelayEvent::Cancelled(cancelled.clone()));
+        Ok(cancelled)
+    }
+
+    /// Send every pending intent of `wallet`'s chain and address whose window
+    /// has passed, one after the other. Returns them with their new status.
+    pub async fn execute_due<C: Chain, S: Signer<C::Scheme>>(
+        &self,
+        wallet: &Wallet<C, S>,
+        provider: &dyn Provider,
+    ) -> Result<Vec<DelayedSend>, WalletError> {
+        let from = wallet.address()?;
+        let now = unix_now();
+        let due: Vec<_> = self
+            .pending()
+            .into_iter()
+            .filter(|send| {
+                send.chain == wallet.chain.id() && send.from == from && send.execute_at <= now
+            })
+            .collect();
+
+        let mut done = Vec::with_capacity(due.len());
+        for send in due {
+            self.releasing.lock().unwrap().push(send.id.clone());
+            let result = wallet.send_coins(provider, &send.to, send.amount).await;
+            self.releasing.lock().unwrap().retain(|id| *id != send.id);
+            let status = match result {
+                Ok(tx_hash) => DelayedStatus::Executed { tx_hash },
+                Err(e) => DelayedStatus::Failed {
+                    error: e.to_string(),
+                },
+            };
+            let send = self.update(&send.id, |send| {
+                send.status = status;
+                Ok(())
+            })?;
+            let _ = self.events.send(match send.status {
+                DelayedStatus::Executed { .. } => DelayEvent::Executed(send.clone()),
+                _ => DelayEvent::Failed(send.clone()),
+            });
+            done.push(send);
+        }
+        Ok(done)
+    }
+
+    /// Apply `change` to send `id` and save the queue.
+    fn update(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut DelayedSend) -> Result<(), ApprovalError>,
+    ) -> Result<DelayedSend, ApprovalError> {
+        let mut queue = self.queue.lock().unwrap();
+        let send = queue
+            .sends
+            .iter_mut()
+            .find(|send| send.id == id)
+            .ok_or_else(|| ApprovalError::Denied(format!("no delayed send {}", id)))?;
+        change(send)?;
+        let send = send.clone();
+        self.save(&queue)?;
+        Ok(send)
+    }
+
+    fn save(&self, queue: &DelayQueue) -> Result<(), ApprovalError> {
+        match &self.state_file {
+            Some(path) => persist::save(path, queue).map_err(|e| {
+                ApprovalError::Storage(format!("cannot save {}: {}", path.display(), e))
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ApprovalPolicy for DelayPolicy {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<(), ApprovalError> {
+        if request.amount <= self.threshold {
+            return Ok(());
+        }
+        let released = {
+            let queue = self.queue.lock().unwrap();
+            let releasing = self.releasing.lock().unwrap();
+            queue.sends.iter().any(|send| {
+                releasing.contains(&send.id)
+                    && send.chain == request.chain
+                    && send.from == request.from
+                    && send.to == request.to
+                    && send.amount == request.amount
+            })
+        };
+        if released {
+            return Ok(());
+        }
+
+        let now = unix_now();
+        let send = DelayedSend {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            chain: request.chain.clone(),
+            from: request.from.clone(),
+            to: request.to.clone(),
+            amount: request.amount,
+            queued_at: now,
+            execute_at: now + self.window.as_secs(),
+            status: DelayedStatus::Pending,
+        };
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.sends.push(send.clone());
+            self.save(&queue)?;
+        }
+        let _ = self.events.send(DelayEvent::Queued(send.clone()));
+        Err(ApprovalError::Delayed {
+            id: send.id,
+            execute_at: send.execute_at,
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+    #[tokio::test]
+    async fn large_sends_wait_out_their_window() {
+        let path = std::env::temp_dir().join(format!("delay-{}", rand::random::<u64>()));
+        let policy = Arc::new(
+            DelayPolicy::new(Duration::from_secs(24 * 60 * 60))
+                .above(1_000)
+                .with_state_file(&path)
+                .unwrap(),
+        );
+        let mut events = policy.subscribe();
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_approval_policy(policy.clone());
+
+        let Err(WalletError::Approval(ApprovalError::Delayed { id, .. })) =
+            wallet.send_coins(&OfflineProvider, TO, 5_000).await
+        else {
+            panic!("expected the send to be delayed");
+        };
+        assert!(matches!(events.recv().await.unwrap(), DelayEvent::Queued(s) if s.id == id));
+        // Not due yet, and small sends are not held.
+        assert!(
+            policy
+                .execute_due(&wallet, &OfflineProvider)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            wallet.send_coins(&OfflineProvider, TO, 1_000).await,
+            Err(WalletError::Node(_))
+        ));
+
+        // The queue survives a restart, and a cancelled send never executes.
+        let restored = DelayPolicy::new(Duration::ZERO)
+            .with_state_file(&path)
+            .unwrap();
+        assert_eq!(restored.pending()[0].id, id);
+        assert_eq!(
+            restored.cancel(&id).unwrap().status,
+            DelayedStatus::Cancelled
+        );
+        assert!(restored.cancel(&id).is_err());
+        assert!(restored.pending().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn due_sends_execute_through_the_wallet() {
+        let policy = Arc::new(DelayPolicy::new(Duration::ZERO));
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_approval_policy(policy.clone());
+        assert!(
+            wallet
+                .send_coins(&OfflineProvider, TO, 5_000)
+                .await
+                .is_err()
+        );
+
+        // Released past the policy; the offline provider then refuses to build it.
+        let done = policy.execute_due(&wallet, &OfflineProvider).await.unwrap();
+        assert_eq!(done.len(), 1);
+        let DelayedStatus::Failed { error } = &done[0].status else {
+            panic!(
+                "expected the offline send to fail, got {:?}",
+                done[0].status
+            );
+        };
+        assert!(!error.contains("delayed"));
+        assert!(policy.pending().is_empty());
+        assert_eq!(policy.queue().sends.len(), 1);
+    }
+}
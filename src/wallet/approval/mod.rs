@@ -0,0 +1,97 @@
+pub mod delay;
+pub mod totp;
+pub mod velocity;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+pub use delay::{DelayEvent, DelayPolicy, DelayedSend, DelayedStatus};
+pub use totp::TotpPolicy;
+pub use velocity::{PolicyEvent, PolicySignal, VelocityPolicy};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ApprovalError {
+    #[error("approval required: {0}")]
+    Required(String),
+    #[error("send denied: {0}")]
+    Denied(String),
+    #[error("approval secret unavailable: {0}")]
+    Secret(String),
+    #[error("approval state unavailable: {0}")]
+    Storage(String),
+    /// Queued by a [`DelayPolicy`] to go out at `execute_at` (Unix seconds)
+    /// unless cancelled.
+    #[error("send delayed as {id} until {execute_at}")]
+    Delayed { id: String, execute_at: u64 },
+}
+
+/// A send awaiting approval, as seen by an [`ApprovalPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalRequest {
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the chain's base unit.
+    pub amount: u64,
+    /// [`SendOptions::approval_code`](crate::wallet::options::SendOptions::approval_code).
+    pub code: Option<String>,
+}
+
+/// Gate consulted before every send of a wallet; see
+/// [`Wallet::with_approval_policy`](crate::wallet::Wallet::with_approval_policy).
+///
+/// It runs after the recipient is resolved and before anything is built or
+/// signed, so a refusal costs nothing on chain.
+#[async_trait]
+pub trait ApprovalPolicy: Send + Sync {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<(), ApprovalError>;
+}
+
+/// Policies that must all approve, checked in order; the first refusal wins.
+pub struct AllOf(pub Vec<Arc<dyn ApprovalPolicy>>);
+
+#[async_trait]
+impl ApprovalPolicy for AllOf {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<(), ApprovalError> {
+        for policy in &self.0 {
+            policy.approve(request).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::WalletError;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::secrets::MemorySecretStore;
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[tokio::test]
+    async fn large_sends_need_a_code() {
+        let store = Arc::new(MemorySecretStore::new());
+        store.insert("ops", "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        let policy = TotpPolicy::new(store, "ops")
+            .with_base32_secret()
+            .above(1_000);
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_approval_policy(Arc::new(policy));
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+        let refused = wallet.send_coins(&OfflineProvider, to, 5_000).await;
+        assert!(matches!(
+            refused,
+            Err(WalletError::Approval(ApprovalError::Required(_)))
+        ));
+        // Under the threshold the send goes ahead (and fails offline).
+        let small = wallet.send_coins(&OfflineProvider, to, 1_000).await;
+        assert!(matches!(small, Err(WalletError::Node(_))));
+    }
+}
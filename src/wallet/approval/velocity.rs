@@ -0,0 +1,259 @@
+//! Per-destination send velocity limits and simple anomaly signals.
+
+use crate::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::wallet::approval::{ApprovalError, ApprovalPolicy, ApprovalRequest};
+
+/// Sends the trailing average is taken over.
+const TRAILING_SENDS: usize = 20;
+/// Sends needed before amounts are compared to the average.
+const MIN_SAMPLES: usize = 3;
+
+/// Why a [`VelocityPolicy`] raised a [`PolicyEvent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicySignal {
+    /// More sends to this destination within the window than allowed.
+    TooManySends { count: usize, limit: usize },
+    /// More value to this destination within the window than allowed.
+    TooMuchValue { total: u128, limit: u128 },
+    /// First send to this destination.
+    NewDestination,
+    /// The amount is more than `factor` times the trailing average.
+    UnusualAmount { average: u64, factor: u64 },
+}
+
+impl fmt::Display for PolicySignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicySignal::TooManySends { count, limit } => {
+                write!(f, "{} sends in the window, limit {}", count, limit)
+            }
+            PolicySignal::TooMuchValue { total, limit } => {
+                write!(f, "{} sent in the window, limit {}", total, limit)
+            }
+            PolicySignal::NewDestination => write!(f, "new destination"),
+            PolicySignal::UnusualAmount { average, factor } => {
+                write!(f, "amount above {}x the average of {}", factor, average)
+            }
+        }
+    }
+}
+
+/// A signal on one send, for review queues. `denied` tells whether the send
+/// was refused or only flagged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyEvent {
+    pub request: ApprovalRequest,
+    pub signal: PolicySignal,
+    pub denied: bool,
+}
+
+#[derive(Default)]
+struct History {
+    /// Approved sends per destination within the window, oldest first.
+    recent: HashMap<String, VecDeque<(Instant, u64)>>,
+    known: HashSet<String>,
+    amounts: VecDeque<u64>,
+}
+
+/// Limits how often and how much a wallet sends to each destination over a
+/// sliding window, and flags new destinations and outsized amounts.
+///
+/// Limits deny the send; anomalies only publish a [`PolicyEvent`] and let it
+/// through. Counters are updated when a send is approved, so sends that later
+/// fail on chain still count against the window.
+pub struct VelocityPolicy {
+    window: Duration,
+    max_sends: Option<usize>,
+    max_value: Option<u128>,
+    anomaly_factor: Option<u64>,
+    flag_new_destinations: bool,
+    history: Mutex<History>,
+    events: broadcast::Sender<PolicyEvent>,
+}
+
+impl VelocityPolicy {
+    /// Policy counting sends per destination over the last `window`; no limits yet.
+    pub fn new(window: Duration) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            window,
+            max_sends: None,
+            max_value: None,
+            anomaly_factor: None,
+            flag_new_destinations: false,
+            history: Mutex::new(History::default()),
+            events,
+        }
+    }
+
+    /// Deny a send if the destination already received `limit` within the window.
+    pub fn max_sends(mut self, limit: usize) -> Self {
+        self.max_sends = Some(limit);
+        self
+    }
+
+    /// Deny a send that would take the destination's total within the window
+    /// above `limit` base units.
+    pub fn max_value(mut self, limit: u128) -> Self {
+        self.max_value = Some(limit);
+        self
+    }
+
+    /// Flag amounts above `factor` times the average of recent sends.
+    pub fn flag_amounts_above_average(mut self, factor: u64) -> Self {
+        self.anomaly_factor = Some(factor);
+        self
+    }
+
+    /// Flag the first send to each destination, except those in `known`.
+    pub fn flag_new_destinations<'a>(mut self, known: impl IntoIterator<Item = &'a str>) -> Self {
+        self.flag_new_destinations = true;
+        let history = self.history.get_mut().unwrap();
+        history.known.extend(known.into_iter().map(str::to_string));
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PolicyEvent> {
+        self.events.subscribe()
+    }
+
+    fn check_at(&self, request: &ApprovalRequest, now: Instant) -> Result<(), ApprovalError> {
+        let mut history = self.history.lock().unwrap();
+        let mut signals = Vec::new();
+
+        let recent = history.recent.entry(request.to.clone()).or_default();
+        while recent
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= self.window)
+        {
+            recent.pop_front();
+        }
+        if let Some(limit) = self.max_sends
+            && recent.len() >= limit
+        {
+            signals.push(PolicySignal::TooManySends {
+                count: recent.len() + 1,
+                limit,
+            });
+        }
+        let total = recent.iter().map(|(_, a)| *a as u128).sum::<u128>() + request.amount as u128;
+        if let Some(limit) = self.max_value
+            && total > limit
+        {
+            signals.push(PolicySignal::TooMuchValue { total, limit });
+        }
+        let denied = !signals.is_empty();
+
+        if self.flag_new_destinations && !history.known.contains(&request.to) {
+            signals.push(PolicySignal::NewDestination);
+        }
+        if let Some(factor) = self.anomaly_factor
+            && history.amounts.len() >= MIN_SAMPLES
+        {
+            let average = history.amounts.iter().sum::<u64>() / history.amounts.len() as u64;
+            if request.amount > average.saturating_mul(factor) {
+                signals.push(PolicySignal::UnusualAmount { average, factor });
+            }
+        }
+
+        if !denied {
+            history
+                .recent
+                .entry(request.to.clone())
+                .or_default()
+                .push_back((now, request.amount));
+            history.known.insert(request.to.clone());
+            history.amounts.push_back(request.amount);
+            if history.amounts.len() > TRAILING_SENDS {
+                history.amounts.pop_front();
+            }
+        }
+        drop(history);
+
+        let reason = signals.first().map(PolicySignal::to_string);
+        for signal in signals {
+            let _ = self.events.send(PolicyEvent {
+                request: request.clone(),
+                signal,
+                denied,
+            });
+        }
+        match reason {
+            Some(reason) if denied => Err(ApprovalError::Denied(format!(
+                "velocity limit for {}: {}",
+                request.to, reason
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ApprovalPolicy for VelocityPolicy {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<(), ApprovalError> {
+        self.check_at(request, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(to: &str, amount: u64) -> ApprovalRequest {
+        ApprovalRequest {
+            chain: "tron".into(),
+            from: "hot".into(),
+            to: to.into(),
+            amount,
+            code: None,
+        }
+    }
+
+    #[test]
+    fn limits_per_destination_and_flags_anomalies() {
+        let policy = VelocityPolicy::new(Duration::from_secs(60))
+            .max_sends(2)
+            .max_value(1_000)
+            .flag_amounts_above_average(2)
+            .flag_new_destinations(["cold"]);
+        let mut events = policy.subscribe();
+        let start = Instant::now();
+
+        assert!(policy.check_at(&send("cold", 100), start).is_ok());
+        assert!(events.try_recv().is_err(), "known destination");
+        assert!(policy.check_at(&send("cold", 100), start).is_ok());
+        assert!(policy.check_at(&send("cold", 100), start).is_err());
+        assert_eq!(
+            events.try_recv().unwrap().signal,
+            PolicySignal::TooManySends { count: 3, limit: 2 }
+        );
+        // The window slides: a minute later the destination is clear again.
+        let later = start + Duration::from_secs(60);
+        assert!(policy.check_at(&send("cold", 900), later).is_ok());
+        assert!(policy.check_at(&send("cold", 200), later).is_err());
+        assert!(matches!(
+            events.try_recv().unwrap().signal,
+            PolicySignal::TooMuchValue { total: 1_100, .. }
+        ));
+
+        // Allowed, but flagged twice for review.
+        assert!(policy.check_at(&send("new", 1_000), later).is_ok());
+        let flags: Vec<_> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(flags.iter().all(|e| !e.denied));
+        assert_eq!(flags[0].signal, PolicySignal::NewDestination);
+        assert_eq!(
+            flags[1].signal,
+            PolicySignal::UnusualAmount {
+                average: 366,
+                factor: 2
+            }
+        );
+    }
+}
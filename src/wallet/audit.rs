@@ -0,0 +1,152 @@
+use crate::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::correlation::CorrelationId;
+use crate::monitor::SendMetadataSource;
+use crate::wallet::options::TravelRuleMetadata;
+
+/// Stage of the send pipeline an audit record refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditKind {
+    SendRequested,
+    /// Screening could not check the recipient and its fail-open mode let the
+    /// send continue.
+    ScreeningBypassed {
+        reason: String,
+    },
+    SendBroadcast {
+        tx_hash: String,
+    },
+    SendFailed {
+        reason: String,
+    },
+}
+
+/// One entry in the audit trail of a send.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub kind: AuditKind,
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// In the chain's base unit, or the token's for a token send.
+    pub amount: u64,
+    /// Contract of the token sent; `None` for the chain's coin.
+    #[serde(default)]
+    pub token: Option<String>,
+    pub timestamp: u64,
+    pub metadata: Option<TravelRuleMetadata>,
+    pub correlation_id: Option<CorrelationId>,
+}
+
+impl AuditRecord {
+    pub fn new(kind: AuditKind, chain: &str, from: &str, to: &str, amount: u64) -> Self {
+        Self {
+            kind,
+            chain: chain.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            token: None,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            metadata: None,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Destination for audit records emitted by the send pipeline.
+/// Implementations must not block for long; they run inline with sends.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord);
+}
+
+/// In-memory audit log, mostly useful for tests and small deployments.
+#[derive(Default)]
+pub struct MemoryAuditLog {
+    records: Mutex<Vec<AuditRecord>>,
+}
+
+impl MemoryAuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl AuditSink for MemoryAuditLog {
+    fn record(&self, record: &AuditRecord) {
+        self.records.lock().unwrap().push(record.clone());
+    }
+}
+
+impl SendMetadataSource for MemoryAuditLog {
+    fn metadata(&self, tx_hash: &str) -> Option<TravelRuleMetadata> {
+        let records = self.records.lock().unwrap();
+        records.iter().rev().find_map(|record| match &record.kind {
+            AuditKind::SendBroadcast { tx_hash: hash } if hash == tx_hash => {
+                record.metadata.clone()
+            }
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::options::{SendOptions, TravelRuleParty};
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[tokio::test]
+    async fn send_records_metadata_in_audit_log() {
+        let log = Arc::new(MemoryAuditLog::new());
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_audit_log(log.clone());
+
+        let metadata = TravelRuleMetadata {
+            originator: TravelRuleParty {
+                name: "Alice".into(),
+                ..Default::default()
+            },
+            beneficiary: TravelRuleParty {
+                name: "Bob".into(),
+                vasp: Some("Example VASP".into()),
+                ..Default::default()
+            },
+            reference: Some("wd-42".into()),
+        };
+        let options = SendOptions {
+            metadata: Some(metadata.clone()),
+            ..Default::default()
+        };
+
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let result = wallet
+            .send_coins_with_options(&OfflineProvider, to, 10, &options)
+            .await;
+        assert!(result.is_err());
+
+        let records = log.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].kind, AuditKind::SendRequested);
+        assert!(matches!(records[1].kind, AuditKind::SendFailed { .. }));
+        assert_eq!(records[1].metadata, Some(metadata));
+        assert_eq!(records[1].to, to);
+        assert!(records[0].correlation_id.is_some());
+        assert_eq!(records[0].correlation_id, records[1].correlation_id);
+    }
+}
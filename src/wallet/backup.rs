@@ -0,0 +1,123 @@
+//! Portable backup of a running wallet service, for moving it between hosts.
+//!
+//! A [`Backup`] gathers what a fresh host needs to carry on where the old one
+//! stopped: the keystores, each still under its own password, the account and
+//! change indices of every chain, address labels, monitor checkpoints and the
+//! transactions still awaiting confirmation. The bundle is encoded as a
+//! [`persist`](crate::persist) record and sealed in a [`Keystore`] of kind
+//! [`KeystoreKind::Backup`], so it gets the same password stretching and
+//! tamper detection as key files.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WalletError;
+use crate::monitor::{BalanceCheckpoint, DepositCheckpoint, MonitorState, PendingTransaction};
+use crate::persist::{self, Persist};
+use crate::wallet::keystore::{Keystore, KeystoreKind, ScryptParams};
+
+/// Account and change position of one chain in a
+/// [`WalletManager`](crate::wallet::manager::WalletManager).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountIndex {
+    pub chain: String,
+    pub account: u32,
+    pub next_change: u32,
+}
+
+/// Everything a wallet service hands over when it moves.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub keystores: Vec<Keystore>,
+    /// Filled in by [`WalletManager::export_backup`](crate::wallet::manager::WalletManager::export_backup).
+    pub accounts: Vec<AccountIndex>,
+    /// Address to label.
+    pub labels: BTreeMap<String, String>,
+    pub monitors: Vec<MonitorState>,
+    pub deposits: Vec<DepositCheckpoint>,
+    #[serde(default)]
+    pub balances: Option<BalanceCheckpoint>,
+    /// From [`SendWatchdog::pending_transactions`](crate::monitor::SendWatchdog::pending_transactions).
+    pub pending: Vec<PendingTransaction>,
+}
+
+impl Persist for Backup {
+    const KIND: &'static str = "wallet_backup";
+    const VERSION: u16 = 1;
+}
+
+impl Backup {
+    /// Encrypt the bundle under `password`.
+    pub fn seal(&self, password: &str, params: ScryptParams) -> Result<Keystore, WalletError> {
+        let bytes = persist::to_bytes(self)?;
+        Ok(Keystore::encrypt(
+            KeystoreKind::Backup,
+            &bytes,
+            password,
+            params,
+        )?)
+    }
+
+    /// Decrypt a bundle sealed with [`seal`](Self::seal).
+    pub fn open(sealed: &Keystore, password: &str) -> Result<Self, WalletError> {
+        let bytes = sealed.decrypt(KeystoreKind::Backup, password)?;
+        Ok(persist::from_bytes(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::RawTransaction;
+    use crate::wallet::chain::{LITECOIN, TRON};
+    use crate::wallet::key_source::MnemonicKeySource;
+    use crate::wallet::manager::WalletManager;
+
+    const FAST: ScryptParams = ScryptParams {
+        log_n: 10,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn moves_indices_and_state_between_managers() {
+        let source = MnemonicKeySource::random(None);
+        let keystore = Keystore::from_mnemonic(&source, "key password", FAST).unwrap();
+        let mut old = WalletManager::new(source);
+        old.register(&TRON).register_account(&LITECOIN, 3);
+        old.set_next_change_index(&LITECOIN, 12).unwrap();
+
+        let mut backup = Backup {
+            keystores: vec![keystore],
+            ..Backup::default()
+        };
+        backup
+            .labels
+            .insert("TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT".into(), "hot".into());
+        backup.pending.push(PendingTransaction {
+            chain: "litecoin".into(),
+            hash: "ab".into(),
+            signed: RawTransaction::Bytes(vec![1, 2]),
+        });
+        let sealed = old.export_backup(backup, "bundle password", FAST).unwrap();
+        let sealed = Keystore::from_json(&sealed.to_json()).unwrap();
+        assert!(Backup::open(&sealed, "wrong").is_err());
+
+        // The new host restores the key from the bundle, then the indices.
+        let restored = Backup::open(&sealed, "bundle password").unwrap();
+        let source = restored.keystores[0]
+            .to_mnemonic("key password", None)
+            .unwrap();
+        let mut new = WalletManager::new(source);
+        new.register(&TRON);
+        assert!(new.import_backup(&sealed, "bundle password").is_err());
+        new.register(&LITECOIN);
+        let restored = new.import_backup(&sealed, "bundle password").unwrap();
+
+        assert_eq!(new.next_change_index(&LITECOIN).unwrap(), 12);
+        assert_eq!(new.path(&LITECOIN, 0).unwrap().account, 3);
+        assert_eq!(restored.labels["TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT"], "hot");
+        assert_eq!(restored.pending[0].hash, "ab");
+    }
+}
@@ -0,0 +1,197 @@
+//! BIP-38 password-protected private keys: the `6P...` strings printed on
+//! paper wallets.
+//!
+//! Only keys encrypted directly are handled, not the EC-multiplied ones a
+//! printer makes from an intermediate code without seeing the key. scrypt
+//! runs at the cost BIP-38 fixes (N = 2^14, r = 8, p = 8), about a second
+//! per key in release builds.
+
+use aes_gcm::aes::Aes256;
+use aes_gcm::aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::wallet::chain::sdk::{base58check_decode, base58check_encode, double_sha256, hash160};
+use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::keystore::KeystoreError;
+use crate::wallet::signer::local::LocalSigner;
+
+const PREFIX: [u8; 2] = [0x01, 0x42];
+const EC_MULTIPLIED_PREFIX: [u8; 2] = [0x01, 0x43];
+/// Flag byte of a directly encrypted key, before the compression bit.
+const FLAG: u8 = 0xc0;
+const COMPRESSED: u8 = 0x20;
+
+/// A private key decrypted from BIP-38.
+pub struct Bip38Key {
+    /// The 32-byte secret scalar.
+    pub secret: SecureBuffer,
+    /// Whether the key's address is of the compressed public key.
+    pub compressed: bool,
+}
+
+impl Bip38Key {
+    pub fn to_signer(&self) -> Result<LocalSigner, KeystoreError> {
+        LocalSigner::from_slice(&self.secret).map_err(|e| KeystoreError::InvalidKey(e.to_string()))
+    }
+}
+
+/// Encrypt `secret_key` under `passphrase` as a `6P...` string. `compressed`
+/// says which of the key's addresses it stands for, as BIP-38 keys commit to
+/// one: that of the compressed public key for keys from modern wallets.
+pub fn encrypt(
+    secret_key: &[u8],
+    compressed: bool,
+    passphrase: &str,
+) -> Result<String, KeystoreError> {
+    // `from_slice` would left-pad a short key; BIP-38 encrypts exactly 32 bytes.
+    if secret_key.len() != 32 {
+        return Err(KeystoreError::InvalidKey(format!(
+            "private key of {} bytes, expected 32",
+            secret_key.len()
+        )));
+    }
+    let signing_key =
+        SigningKey::from_slice(secret_key).map_err(|e| KeystoreError::InvalidKey(e.to_string()))?;
+    let address_hash = address_hash(signing_key.verifying_key(), compressed);
+    let derived = derive(passphrase, &address_hash)?;
+    let cipher = Aes256::new_from_slice(&derived[32..]).expect("derived key is 32 bytes");
+
+    let mut blocks = SecureBuffer::new(vec![0u8; 32]);
+    for (i, byte) in blocks.as_mut().iter_mut().enumerate() {
+        *byte = secret_key[i] ^ derived[i];
+    }
+    for block in blocks.as_mut().chunks_mut(16) {
+        cipher.encrypt_block(block.into());
+    }
+
+    let flag = if compressed { FLAG | COMPRESSED } else { FLAG };
+    let mut payload = Vec::with_capacity(39);
+    payload.extend_from_slice(&PREFIX);
+    payload.push(flag);
+    payload.extend_from_slice(&address_hash);
+    payload.extend_from_slice(&blocks);
+    Ok(base58check_encode(&payload))
+}
+
+/// The signer of a `6P...` key. [`KeystoreError::Decrypt`] if `passphrase`
+/// is wrong.
+pub fn decrypt(encrypted: &str, passphrase: &str) -> Result<LocalSigner, KeystoreError> {
+    decrypt_key(encrypted, passphrase)?.to_signer()
+}
+
+/// [`decrypt`], keeping the secret and which address it stands for, e.g. to
+/// move it into a [`Keystore`](crate::wallet::keystore::Keystore).
+pub fn decrypt_key(encrypted: &str, passphrase: &str) -> Result<Bip38Key, KeystoreError> {
+    let payload = base58check_decode(encrypted.trim())
+        .map_err(|e| KeystoreError::Format(format!("BIP-38 key: {}", e)))?;
+    if payload.len() != 39 {
+        return Err(KeystoreError::Format(format!(
+            "BIP-38 key of {} bytes, expected 39",
+            payload.len()
+        )));
+    }
+    let (prefix, flag) = (&payload[..2], payload[2]);
+    if prefix == EC_MULTIPLIED_PREFIX {
+        return Err(KeystoreError::Unsupported(
+            "EC-multiplied BIP-38 keys".to_string(),
+        ));
+    }
+    if prefix != PREFIX || flag & !COMPRESSED != FLAG {
+        return Err(KeystoreError::Format(
+            "not a BIP-38 encrypted key".to_string(),
+        ));
+    }
+    let compressed = flag & COMPRESSED != 0;
+    let expected_hash: [u8; 4] = payload[3..7].try_into().expect("4 bytes");
+
+    let derived = derive(passphrase, &expected_hash)?;
+    let cipher = Aes256::new_from_slice(&derived[32..]).expect("derived key is 32 bytes");
+    let mut secret = SecureBuffer::new(payload[7..].to_vec());
+    for block in secret.as_mut().chunks_mut(16) {
+        cipher.decrypt_block(block.into());
+    }
+    for (byte, mask) in secret.as_mut().iter_mut().zip(&derived[..32]) {
+        *byte ^= mask;
+    }
+
+    // The address hash is the only check the format has on the passphrase.
+    let signing_key = SigningKey::from_slice(&secret).map_err(|_| KeystoreError::Decrypt)?;
+    if address_hash(signing_key.verifying_key(), compressed) != expected_hash {
+        return Err(KeystoreError::Decrypt);
+    }
+    Ok(Bip38Key { secret, compressed })
+}
+
+/// First four bytes of the double SHA-256 of the key's Bitcoin address.
+fn address_hash(key: &VerifyingKey, compressed: bool) -> [u8; 4] {
+    let mut payload = vec![0x00];
+    payload.extend_from_slice(&hash160(key.to_encoded_point(compressed).as_bytes()));
+    let address = base58check_encode(&payload);
+    double_sha256(address.as_bytes())[..4]
+        .try_into()
+        .expect("4 bytes")
+}
+
+/// scrypt of the NFC-normalized passphrase, salted with the address hash:
+/// an XOR mask for the key, then the AES-256 key.
+fn derive(passphrase: &str, address_hash: &[u8; 4]) -> Result<SecureBuffer, KeystoreError> {
+    let params =
+        scrypt::Params::new(14, 8, 8, 64).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    let passphrase = SecureBuffer::from(passphrase.nfc().collect::<String>());
+    let mut derived = SecureBuffer::new(vec![0u8; 64]);
+    scrypt::scrypt(&passphrase, address_hash, &params, derived.as_mut())
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    Ok(derived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+    use crate::wallet::scheme::Secp256k1;
+
+    #[test]
+    fn matches_the_bip38_test_vectors() {
+        let secret =
+            hex::decode("cbf4b9f70470856bb4f40f80b87edb90865997ffee6df315ab166d713af433a5")
+                .unwrap();
+        let uncompressed = "6PRVWUbkzzsbcVac2qwfssoUJAN1Xhrg6bNk8J7Nzm5H7kxEbn2Nh2ZoGg";
+        let compressed = "6PYNKZ1EAgYgmQfmNVamxyXVWHzK5s6DGhwP4J5o44cvXdoY7sRzhtpUeo";
+        assert_eq!(
+            encrypt(&secret, false, "TestingOneTwoThree").unwrap(),
+            uncompressed
+        );
+        assert_eq!(
+            encrypt(&secret, true, "TestingOneTwoThree").unwrap(),
+            compressed
+        );
+
+        let key = decrypt_key(compressed, "TestingOneTwoThree").unwrap();
+        assert_eq!(&*key.secret, secret.as_slice());
+        assert!(key.compressed);
+        assert_eq!(
+            Signer::<Secp256k1>::public_key(&key.to_signer().unwrap()),
+            Signer::<Secp256k1>::public_key(&LocalSigner::from_slice(&secret).unwrap())
+        );
+
+        assert!(matches!(
+            decrypt(uncompressed, "TestingOneTwoThre3"),
+            Err(KeystoreError::Decrypt)
+        ));
+        assert!(matches!(
+            decrypt("5KN7MzqK5wt2TP1fQCYyHBtDrXdJuXbUzm4A9rKAteGu3Qi5CVR", "x"),
+            Err(KeystoreError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_keys_that_are_not_32_bytes() {
+        for secret in [&[1u8; 24][..], &[1u8; 31], &[1u8; 33]] {
+            assert!(matches!(
+                encrypt(secret, true, "TestingOneTwoThree"),
+                Err(KeystoreError::InvalidKey(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Bounds on what a provider-built transaction may do.
+//!
+//! Chains that sign what a server assembled (Tron's `createtransaction`,
+//! Blockcypher skeletons, node-filled EVM calls) decode the transaction into a
+//! [`TxSummary`] and [`Chain::prepare_transaction_capped`](super::Chain::prepare_transaction_capped)
+//! refuses to produce digests unless it fits the caller's [`TxCaps`].
+
+use super::ChainError;
+
+/// One value movement a transaction makes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxPayment {
+    pub to: String,
+    /// In the base unit of the chain, or of `TxSummary::token`.
+    pub amount: u128,
+}
+
+/// What a transaction would do once signed, as decoded from its signed bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxSummary {
+    pub payments: Vec<TxPayment>,
+    /// Contract whose tokens the payments move; `None` for the native coin.
+    pub token: Option<String>,
+    /// Most the transaction can spend on fees. `None` when the fee is not part
+    /// of the transaction (Tron transfers pay with bandwidth).
+    pub max_fee: Option<u128>,
+}
+
+/// Caller-provided limits a provider-built transaction must stay within.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxCaps {
+    pub max_fee: Option<u128>,
+    /// Exact amount the recipient must receive.
+    pub amount: Option<u128>,
+    /// The only address allowed to receive value, besides `change`.
+    pub recipient: Option<String>,
+    /// Addresses allowed to receive change, usually the sender's.
+    pub change: Vec<String>,
+    /// Token contract the payments must move; `None` requires the native coin.
+    pub token: Option<String>,
+}
+
+impl TxCaps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_fee(mut self, max_fee: u128) -> Self {
+        self.max_fee = Some(max_fee);
+        self
+    }
+
+    pub fn amount(mut self, amount: u128) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    pub fn recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.recipient = Some(recipient.into());
+        self
+    }
+
+    pub fn change(mut self, address: impl Into<String>) -> Self {
+        self.change.push(address.into());
+        self
+    }
+
+    pub fn token(mut self, contract: impl Into<String>) -> Self {
+        self.token = Some(contract.into());
+        self
+    }
+
+    /// Whether `summary` stays within these caps.
+    pub fn check(&self, summary: &TxSummary) -> Result<(), ChainError> {
+        let violation = |reason: String| Err(ChainError::CapViolation(reason));
+
+        if let (Some(max_fee), Some(fee)) = (self.max_fee, summary.max_fee)
+            && fee > max_fee
+        {
+            return violation(format!("fee up to {} exceeds the cap of {}", fee, max_fee));
+        }
+        if self.amount.is_none() && self.recipient.is_none() {
+            return Ok(());
+        }
+
+        let same_token = match (&self.token, &summary.token) {
+            (None, None) => true,
+            (Some(expected), Some(actual)) => same_address(expected, actual),
+            _ => false,
+        };
+        if !same_token {
+            return violation(format!(
+                "moves {} instead of {}",
+                summary.token.as_deref().unwrap_or("the native coin"),
+                self.token.as_deref().unwrap_or("the native coin")
+            ));
+        }
+
+        let mut paid = 0u128;
+        for payment in &summary.payments {
+            if self.change.iter().any(|c| same_address(c, &payment.to)) {
+                continue;
+            }
+            if let Some(recipient) = &self.recipient
+                && !same_address(recipient, &payment.to)
+            {
+                return violation(format!(
+                    "pays {} to unexpected address {}",
+                    payment.amount, payment.to
+                ));
+            }
+            paid = paid.saturating_add(payment.amount);
+        }
+        if let Some(amount) = self.amount
+            && paid != amount
+        {
+            return violation(format!("pays {} instead of {}", paid, amount));
+        }
+        Ok(())
+    }
+}
+
+/// `0x` addresses compare case-insensitively (EIP-55 casing is only a checksum).
+pub(crate) fn same_address(a: &str, b: &str) -> bool {
+    if a.starts_with("0x") && b.starts_with("0x") {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_amount_recipient_fee_and_token() {
+        let summary = TxSummary {
+            payments: vec![
+                TxPayment {
+                    to: "recipient".into(),
+                    amount: 1_000,
+                },
+                TxPayment {
+                    to: "sender".into(),
+                    amount: 400,
+                },
+            ],
+            token: None,
+            max_fee: Some(50),
+        };
+        let caps = TxCaps::new()
+            .recipient("recipient")
+            .amount(1_000)
+            .change("sender")
+            .max_fee(50);
+        assert_eq!(caps.check(&summary), Ok(()));
+
+        let violations = [
+            caps.clone().amount(999),
+            caps.clone().max_fee(49),
+            caps.clone().recipient("someone else"),
+            caps.clone()
+                .token("0xdac17f958d2ee523a2206206994597c13d831ec7"),
+            TxCaps {
+                change: Vec::new(),
+                ..caps.clone()
+            },
+        ];
+        for caps in violations {
+            assert!(matches!(
+                caps.check(&summary),
+                Err(ChainError::CapViolation(_))
+            ));
+        }
+    }
+}
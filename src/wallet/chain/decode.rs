@@ -0,0 +1,68 @@
+//! Structured view of an unsigned transaction, for showing a person what
+//! they are about to sign.
+
+use serde::{Deserialize, Serialize};
+
+/// Fields of a provider-built transaction, from
+/// [`Chain::decode_transaction`](super::Chain::decode_transaction).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "chain", rename_all = "snake_case")]
+pub enum DecodedTransaction {
+    Tvm(DecodedTvmTx),
+    Utxo(DecodedUtxoTx),
+}
+
+/// A TVM (Tron) transaction's first contract and the limits around it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedTvmTx {
+    /// e.g. `TransferContract` or `TriggerSmartContract`.
+    pub contract_type: String,
+    pub owner: Option<String>,
+    /// Recipient of a TRX or TRC-20 transfer or of delegated resources;
+    /// the called contract for other calls.
+    pub to: Option<String>,
+    /// Amount moved, in Sun or, for a TRC-20 transfer, the token's base unit.
+    pub amount: Option<u128>,
+    /// Contract of the token a TRC-20 transfer moves.
+    pub token: Option<String>,
+    /// Hex call data of a smart-contract call.
+    pub data: Option<String>,
+    /// Most Sun the transaction may burn on energy; 0 if it sets no limit.
+    pub fee_limit: u64,
+    pub memo: Option<String>,
+    /// Milliseconds since the epoch after which the network refuses it.
+    pub expiration: i64,
+}
+
+/// A UTXO transaction's inputs and outputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedUtxoTx {
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<DecodedOutput>,
+    /// What the inputs bring in beyond what the outputs pay out; `None` if
+    /// the outputs pay more, which the network would refuse.
+    pub fee: Option<u64>,
+    /// Whether a higher-fee transaction may replace it (BIP-125).
+    pub replaceable: bool,
+    pub lock_time: u32,
+}
+
+/// A coin a UTXO transaction spends.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedInput {
+    pub prev_txid: String,
+    pub output_index: u32,
+    /// Value as the provider reported it.
+    pub value: u64,
+    pub address: String,
+}
+
+/// A payment a UTXO transaction makes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedOutput {
+    /// Address paid, or `script:<hex>` for scripts without one.
+    pub address: String,
+    pub value: u64,
+    /// Data of an `OP_RETURN` output, which pays nobody.
+    pub memo: Option<String>,
+}
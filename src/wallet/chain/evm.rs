@@ -0,0 +1,438 @@
+use serde_json::Value;
+
+use super::sdk::{expect_json, keccak256, parse_recoverable_signature, sec1_uncompressed};
+use super::{
+    Chain, ChainError, ChainFeatures, Explorer, MessageFormat, Network, SignatureFormat, TxPayment,
+    TxSummary,
+};
+use crate::node::RawTransaction;
+use crate::wallet::scheme::Secp256k1;
+
+/// EIP-2718 type byte of an EIP-1559 (dynamic fee) transaction.
+const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// Generic EVM chain (Ethereum and compatible networks), distinguished by EIP-155 chain id.
+///
+/// Providers hand over an unsigned EIP-1559 transaction as [`RawTransaction::Json`]
+/// with `eth_sendTransaction`-style fields (`chainId`, `nonce`, `maxPriorityFeePerGas`,
+/// `maxFeePerGas`, `gas`, `to`, `value`, `data`); quantities are `0x` hex strings.
+/// The signed transaction comes back as [`RawTransaction::Bytes`], ready for
+/// `eth_sendRawTransaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmChain {
+    pub name: &'static str,
+    pub chain_id: u64,
+    pub network: Network,
+    /// Ticker of the native coin, `ETH` on Ethereum and its rollups.
+    pub symbol: &'static str,
+    pub explorer: Option<Explorer>,
+}
+
+pub const ETHEREUM: EvmChain = EvmChain {
+    name: "ethereum",
+    chain_id: 1,
+    network: Network::Mainnet,
+    symbol: "ETH",
+    explorer: Some(Explorer::new(
+        "https://etherscan.io/tx/{}",
+        "https://etherscan.io/address/{}",
+    )),
+};
+
+pub const SEPOLIA: EvmChain = EvmChain {
+    name: "sepolia",
+    chain_id: 11_155_111,
+    network: Network::Testnet,
+    symbol: "ETH",
+    explorer: Some(Explorer::new(
+        "https://sepolia.etherscan.io/tx/{}",
+        "https://sepolia.etherscan.io/address/{}",
+    )),
+};
+
+impl Chain for EvmChain {
+    type Scheme = Secp256k1;
+
+    fn id(&self) -> &'static str {
+        self.name
+    }
+
+    fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Ethereum's 60 for every EVM network, so one key owns the same address everywhere.
+    fn coin_type(&self) -> u32 {
+        60
+    }
+
+    /// The native coin counts in wei, 10^-18 of it.
+    fn decimals(&self) -> u32 {
+        18
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn explorer(&self) -> Option<Explorer> {
+        self.explorer
+    }
+
+    fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+        evm_address_from_pubkey(pubkey_sec1)
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), ChainError> {
+        let invalid = |reason: &str| {
+            ChainError::InvalidAddress(format!("{} ({}): {}", address, self.name, reason))
+        };
+        let body = address
+            .strip_prefix("0x")
+            .ok_or_else(|| invalid("missing 0x prefix"))?;
+        if body.len() != 40 || !body.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid("expected 40 hex characters"));
+        }
+
+        // Single-case addresses carry no checksum; mixed case must match EIP-55.
+        if is_mixed_case(body) && !is_checksum_address(address) {
+            return Err(invalid("bad checksum"));
+        }
+        Ok(())
+    }
+
+    fn prehashed(&self) -> bool {
+        true
+    }
+
+    fn signature_format(&self) -> SignatureFormat {
+        SignatureFormat::Recoverable
+    }
+
+    /// EIP-1559 fees; a pending transaction is replaced by reusing its nonce.
+    fn features(&self) -> ChainFeatures {
+        ChainFeatures {
+            tokens: true,
+            replace_by_fee: true,
+            message_signing: true,
+            fee_market: true,
+            ..ChainFeatures::default()
+        }
+    }
+
+    fn message_format(&self) -> Option<MessageFormat> {
+        Some(MessageFormat::Ethereum)
+    }
+
+    fn prepare_transaction(&self, raw_tx: &RawTransaction) -> Result<Vec<Vec<u8>>, ChainError> {
+        let fields = self.unsigned_fields(expect_json(self.name, raw_tx)?)?;
+        Ok(vec![keccak256(&typed_payload(&fields)).to_vec()])
+    }
+
+    fn finalize_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+        signatures: &[Vec<u8>],
+        pubkey: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        let signature = signatures
+            .first()
+            .ok_or_else(|| ChainError::Other("No signatures provided".to_string()))?;
+
+        let mut fields = self.unsigned_fields(expect_json(self.name, raw_tx)?)?;
+        let sighash = keccak256(&typed_payload(&fields));
+        // Ethereum rejects high-s signatures (EIP-2).
+        let (signature, y_parity) = parse_recoverable_signature(signature, &sighash, pubkey)?;
+
+        let (r, s) = signature.split_bytes();
+        fields.push(rlp_bytes(&[y_parity]));
+        fields.push(rlp_bytes(trim_leading_zeros(&r)));
+        fields.push(rlp_bytes(trim_leading_zeros(&s)));
+        Ok(RawTransaction::Bytes(typed_payload(&fields)))
+    }
+
+    /// Keccak-256 of the signed envelope, as `eth_sendRawTransaction` returns it.
+    fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+        match signed_tx {
+            RawTransaction::Bytes(bytes) => Ok(format!("0x{}", hex::encode(keccak256(bytes)))),
+            other => Err(ChainError::Other(format!(
+                "{} expects a signed transaction, got {}",
+                self.name,
+                other.kind()
+            ))),
+        }
+    }
+
+    /// Understands plain value transfers and ERC-20 `transfer` calls; the fee
+    /// bound is `gas * maxFeePerGas`.
+    fn inspect_transaction(&self, raw_tx: &RawTransaction) -> Result<TxSummary, ChainError> {
+        let tx = expect_json(self.name, raw_tx)?;
+        // Same checks as signing, so the summary describes what would be signed.
+        self.unsigned_fields(tx)?;
+        let to = to_checksum_address(&hex::encode(hex_field(tx, "to")?));
+        let value = quantity_u128(tx, "value")?;
+        let max_fee = quantity_u128(tx, "gas")?
+            .checked_mul(quantity_u128(tx, "maxFeePerGas")?)
+            .ok_or_else(|| ChainError::Other("fee overflows".to_string()))?;
+
+        let data = hex_field(tx, "data").unwrap_or_default();
+        if data.is_empty() {
+            return Ok(TxSummary {
+                payments: vec![TxPayment { to, amount: value }],
+                token: None,
+                max_fee: Some(max_fee),
+            });
+        }
+        match data.as_slice() {
+            [0xa9, 0x05, 0x9c, 0xbb, args @ ..]
+                if args.len() == 64 && value == 0 && args[32..48].iter().all(|&b| b == 0) =>
+            {
+                let mut amount = [0u8; 16];
+                amount.copy_from_slice(&args[48..]);
+                Ok(TxSummary {
+                    payments: vec![TxPayment {
+                        to: to_checksum_address(&hex::encode(&args[12..32])),
+                        amount: u128::from_be_bytes(amount),
+                    }],
+                    token: Some(to),
+                    max_fee: Some(max_fee),
+                })
+            }
+            _ => Err(ChainError::Other(
+                "cannot inspect contract calls other than ERC-20 transfer".to_string(),
+            )),
+        }
+    }
+}
+
+impl EvmChain {
+    /// RLP items of the unsigned EIP-1559 transaction, in signing order.
+    fn unsigned_fields(&self, tx: &Value) -> Result<Vec<Vec<u8>>, ChainError> {
+        let chain_id = quantity(tx, "chainId")?;
+        if chain_id != trim_leading_zeros(&self.chain_id.to_be_bytes()) {
+            return Err(ChainError::Other(format!(
+                "transaction chainId 0x{} does not match {} ({})",
+                hex::encode(&chain_id),
+                self.name,
+                self.chain_id
+            )));
+        }
+
+        let to = hex_field(tx, "to")?;
+        if to.len() != 20 {
+            return Err(ChainError::InvalidAddress(format!(
+                "to: expected 20 bytes, got {}",
+                to.len()
+            )));
+        }
+
+        Ok(vec![
+            rlp_bytes(&chain_id),
+            rlp_bytes(&quantity(tx, "nonce")?),
+            rlp_bytes(&quantity(tx, "maxPriorityFeePerGas")?),
+            rlp_bytes(&quantity(tx, "maxFeePerGas")?),
+            rlp_bytes(&quantity(tx, "gas")?),
+            rlp_bytes(&to),
+            rlp_bytes(&quantity(tx, "value")?),
+            rlp_bytes(&hex_field(tx, "data").unwrap_or_default()),
+            // Empty access list.
+            rlp_list(&[]),
+        ])
+    }
+}
+
+/// Derive an EVM address: last 20 bytes of keccak256 over the uncompressed key, EIP-55 cased.
+pub fn evm_address_from_pubkey(pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+    let uncompressed = sec1_uncompressed(pubkey_sec1)?;
+    let hash = keccak256(&uncompressed[1..]);
+    Ok(to_checksum_address(&hex::encode(&hash[12..])))
+}
+
+/// Whether `address` is `0x` and 40 hex characters in their EIP-55 casing.
+/// All-lowercase or all-uppercase addresses carry no checksum and are not,
+/// though [`validate_address`](Chain::validate_address) accepts them; check
+/// this where an address should have come from a checksummed source.
+pub fn is_checksum_address(address: &str) -> bool {
+    address.len() == 42 && address.starts_with("0x") && to_checksum_address(address) == address
+}
+
+pub(crate) fn is_mixed_case(hex: &str) -> bool {
+    hex.bytes().any(|b| b.is_ascii_lowercase()) && hex.bytes().any(|b| b.is_ascii_uppercase())
+}
+
+/// EIP-55 mixed-case form of a 20-byte hex address (with or without `0x`).
+pub fn to_checksum_address(address: &str) -> String {
+    let lower = address
+        .strip_prefix("0x")
+        .unwrap_or(address)
+        .to_ascii_lowercase();
+    let hash = keccak256(lower.as_bytes());
+
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        if nibble >= 8 {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `0x02 || rlp(fields)`
+fn typed_payload(fields: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = vec![EIP1559_TX_TYPE];
+    out.extend_from_slice(&rlp_list(fields));
+    out
+}
+
+fn hex_field(tx: &Value, name: &str) -> Result<Vec<u8>, ChainError> {
+    let value = tx
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ChainError::Other(format!("Missing {}", name)))?;
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    // Quantities may have an odd number of digits (`0x1`).
+    let padded = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_string()
+    };
+    hex::decode(padded).map_err(|e| ChainError::Other(format!("Invalid hex in {}: {}", name, e)))
+}
+
+/// Big-endian bytes of a hex quantity without leading zeros, as RLP encodes integers.
+fn quantity(tx: &Value, name: &str) -> Result<Vec<u8>, ChainError> {
+    Ok(trim_leading_zeros(&hex_field(tx, name)?).to_vec())
+}
+
+fn quantity_u128(tx: &Value, name: &str) -> Result<u128, ChainError> {
+    let bytes = quantity(tx, name)?;
+    if bytes.len() > 16 {
+        return Err(ChainError::Other(format!("{} out of range", name)));
+    }
+    Ok(bytes.iter().fold(0u128, |acc, &b| (acc << 8) | b as u128))
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn rlp_length_prefix(out: &mut Vec<u8>, len: usize, short_base: u8) {
+    if len <= 55 {
+        out.push(short_base + len as u8);
+    } else {
+        let len_bytes = len.to_be_bytes();
+        let len_bytes = trim_leading_zeros(&len_bytes);
+        out.push(short_base + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+}
+
+fn rlp_bytes(data: &[u8]) -> Vec<u8> {
+    if data.len() == 1 && data[0] < 0x80 {
+        return data.to_vec();
+    }
+    let mut out = Vec::with_capacity(data.len() + 9);
+    rlp_length_prefix(&mut out, data.len(), 0x80);
+    out.extend_from_slice(data);
+    out
+}
+
+/// RLP list of already-encoded items.
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = items.concat();
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    rlp_length_prefix(&mut out, payload.len(), 0xc0);
+    out.extend_from_slice(&payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+    use crate::wallet::chain::{TRON, sdk::base58check_decode};
+    use crate::wallet::signer::LocalSigner;
+
+    #[test]
+    fn checksum_and_validation() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(to_checksum_address(&address.to_lowercase()), address);
+        assert!(ETHEREUM.validate_address(address).is_ok());
+        assert!(ETHEREUM.validate_address(&address.to_lowercase()).is_ok());
+        assert!(is_checksum_address(address));
+        assert!(!is_checksum_address(&address.to_lowercase()));
+        assert!(
+            ETHEREUM
+                .validate_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+                .is_err()
+        );
+        assert!(
+            ETHEREUM
+                .validate_address("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn address_matches_tron_body() {
+        // Tron and EVM share the keccak derivation; only the encoding differs.
+        let pubkey = LocalSigner::from_bytes([1u8; 32]).unwrap().public_key();
+        let evm = ETHEREUM.address_from_pubkey(&pubkey).unwrap();
+        let tron = base58check_decode(&TRON.address_from_pubkey(&pubkey).unwrap()).unwrap();
+        assert_eq!(evm.to_lowercase(), format!("0x{}", hex::encode(&tron[1..])));
+        assert_eq!(evm, "0x1a642f0E3c3aF545E7AcBD38b07251B3990914F1");
+    }
+
+    #[test]
+    fn rlp_vectors() {
+        assert_eq!(hex::encode(rlp_bytes(b"dog")), "83646f67");
+        assert_eq!(
+            hex::encode(rlp_list(&[rlp_bytes(b"cat"), rlp_bytes(b"dog")])),
+            "c88363617483646f67"
+        );
+        assert_eq!(rlp_bytes(&[]), [0x80]);
+        assert_eq!(rlp_bytes(&[0x0f]), [0x0f]);
+        assert_eq!(rlp_list(&[]), [0xc0]);
+    }
+
+    #[tokio::test]
+    async fn signed_transaction_recovers_to_sender() {
+        let signer = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let pubkey = signer.public_key();
+        let raw = RawTransaction::Json(serde_json::json!({
+            "chainId": "0xaa36a7",
+            "nonce": "0x0",
+            "maxPriorityFeePerGas": "0x3b9aca00",
+            "maxFeePerGas": "0x77359400",
+            "gas": "0x5208",
+            "to": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "value": "0xde0b6b3a7640000",
+            "data": "0x",
+        }));
+        assert!(ETHEREUM.prepare_transaction(&raw).is_err());
+
+        let payloads = SEPOLIA.prepare_transaction(&raw).unwrap();
+        let signature = signer.sign_prehash(&payloads[0]).await.unwrap();
+        let RawTransaction::Bytes(signed) = SEPOLIA
+            .finalize_transaction(&raw, &[signature], &pubkey)
+            .unwrap()
+        else {
+            panic!("expected wire bytes");
+        };
+        assert_eq!(signed[0], EIP1559_TX_TYPE);
+
+        // Signing with another key must not produce a transaction for this sender.
+        let other = LocalSigner::from_bytes([2u8; 32]).unwrap();
+        let foreign = other.sign_prehash(&payloads[0]).await.unwrap();
+        assert!(
+            SEPOLIA
+                .finalize_transaction(&raw, &[foreign], &pubkey)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,36 @@
+/// What a chain's protocol offers, for code that shows or hides functionality
+/// per chain (a memo field, a token picker, a "speed up" button).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainFeatures {
+    /// Transfers can carry a free-text note (Tron's `data`, UTXO `OP_RETURN`).
+    pub memos: bool,
+    /// Fungible tokens on contracts (TRC-20, ERC-20).
+    pub tokens: bool,
+    /// A pending transaction can be replaced by one paying a higher fee
+    /// (BIP-125 on UTXO chains, same-nonce replacement on EVM).
+    pub replace_by_fee: bool,
+    /// Native multi-party spending rules: P2SH/P2WSH scripts, Tron account
+    /// permissions. Contract wallets on EVM do not count.
+    pub multisig: bool,
+    /// Keys can sign arbitrary messages, e.g. ownership proofs.
+    pub message_signing: bool,
+    /// Fees are bid per transaction, so a priority choice changes the fee.
+    pub fee_market: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wallet::chain::{BITCOIN_CASH, Chain, ETHEREUM, LITECOIN, TRON};
+
+    #[test]
+    fn features_per_family() {
+        let tron = TRON.features();
+        assert!(tron.tokens && tron.memos && !tron.fee_market && !tron.replace_by_fee);
+        let eth = ETHEREUM.features();
+        assert!(eth.tokens && eth.fee_market && eth.replace_by_fee && !eth.multisig);
+        let ltc = LITECOIN.features();
+        assert!(!ltc.tokens && ltc.replace_by_fee && ltc.multisig);
+        assert!(!BITCOIN_CASH.features().replace_by_fee);
+        assert_eq!(Box::new(ETHEREUM).features(), eth);
+    }
+}
@@ -0,0 +1,118 @@
+//! Signed messages in the form each chain's wallets produce them
+//! (`personal_sign` and its relatives), for proving control of an address
+//! to an exchange or dApp.
+//!
+//! Every scheme hashes the message behind a chain-specific prefix, so a
+//! signed message can never be replayed as a transaction.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+use super::ChainError;
+use super::sdk::{double_sha256, keccak256, write_compact_size};
+
+/// How a chain's wallets sign arbitrary messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// TronWeb's `signMessageV2`: `keccak256("\x19TRON Signed Message:\n" ||
+    /// len || message)`, written as `0x`-prefixed hex of `r || s || v`.
+    /// For 32-byte messages this is also the older `signMessage`.
+    Tron,
+    /// EIP-191 `personal_sign`: `keccak256("\x19Ethereum Signed Message:\n"
+    /// || len || message)`, written as `0x`-prefixed hex of `r || s || v`.
+    Ethereum,
+    /// Bitcoin Core's `signmessage`: double SHA-256 of the compact-size
+    /// prefixed `magic` and message, written as base64 of `header || r || s`
+    /// with a BIP-137 header.
+    Bitcoin {
+        /// E.g. `"Litecoin Signed Message:\n"`.
+        magic: &'static str,
+        /// Sign with the header of native SegWit addresses rather than
+        /// compressed P2PKH ones.
+        segwit: bool,
+    },
+}
+
+impl MessageFormat {
+    /// Digest the signature covers.
+    pub fn digest(&self, message: &[u8]) -> [u8; 32] {
+        match self {
+            MessageFormat::Tron => keccak256(
+                &[
+                    format!("\x19TRON Signed Message:\n{}", message.len()).as_bytes(),
+                    message,
+                ]
+                .concat(),
+            ),
+            MessageFormat::Ethereum => keccak256(
+                &[
+                    format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes(),
+                    message,
+                ]
+                .concat(),
+            ),
+            MessageFormat::Bitcoin { magic, .. } => {
+                let mut data = Vec::with_capacity(magic.len() + message.len() + 10);
+                write_compact_size(&mut data, magic.len() as u64);
+                data.extend_from_slice(magic.as_bytes());
+                write_compact_size(&mut data, message.len() as u64);
+                data.extend_from_slice(message);
+                double_sha256(&data)
+            }
+        }
+    }
+
+    /// Write a low-s signature with its recovery parity the way the chain's
+    /// wallets do.
+    pub fn encode(&self, signature: &Signature, parity: u8) -> String {
+        let rs = signature.to_bytes();
+        match self {
+            MessageFormat::Tron | MessageFormat::Ethereum => {
+                format!("0x{}{}", hex::encode(rs), hex::encode([27 + parity]))
+            }
+            MessageFormat::Bitcoin { segwit, .. } => {
+                let header = if *segwit { 39 } else { 31 } + parity;
+                BASE64.encode([&[header][..], &rs].concat())
+            }
+        }
+    }
+
+    /// Key that signed `message`, as compressed SEC1.
+    pub fn recover(&self, message: &[u8], signature: &str) -> Result<Vec<u8>, ChainError> {
+        let invalid =
+            |reason: &str| ChainError::Other(format!("invalid message signature: {}", reason));
+        let (rs, parity) = match self {
+            MessageFormat::Tron | MessageFormat::Ethereum => {
+                let bytes = hex::decode(signature.trim_start_matches("0x"))
+                    .map_err(|e| invalid(&e.to_string()))?;
+                let [rs @ .., v] = bytes.as_slice() else {
+                    return Err(invalid("empty"));
+                };
+                let parity = match v {
+                    0 | 1 => *v,
+                    27 | 28 => v - 27,
+                    _ => return Err(invalid("bad recovery byte")),
+                };
+                (rs.to_vec(), parity)
+            }
+            MessageFormat::Bitcoin { .. } => {
+                let bytes = BASE64
+                    .decode(signature.trim())
+                    .map_err(|e| invalid(&e.to_string()))?;
+                let [header @ 27..=42, rs @ ..] = bytes.as_slice() else {
+                    return Err(invalid("bad header byte"));
+                };
+                (rs.to_vec(), (header - 27) % 4)
+            }
+        };
+        if rs.len() != 64 {
+            return Err(invalid("expected 65 bytes"));
+        }
+        let signature = Signature::from_slice(&rs).map_err(|e| invalid(&e.to_string()))?;
+        let recid = RecoveryId::from_byte(parity).ok_or_else(|| invalid("bad recovery id"))?;
+        let key = VerifyingKey::recover_from_prehash(&self.digest(message), &signature, recid)
+            .map_err(|_| invalid("no key recovers from it"))?;
+        Ok(key.to_encoded_point(true).as_bytes().to_vec())
+    }
+}
@@ -1,30 +1,438 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod caps;
+pub mod decode;
+pub mod evm;
+pub mod features;
+pub mod message;
+pub mod params;
+pub mod presets;
+pub mod registry;
+pub mod sdk;
 pub mod tvm;
 pub mod utxo;
 
-pub use tvm::{TRON, TvmChain, tvm_address_from_pubkey};
-pub use utxo::{LITECOIN, UtxoChain, utxo_address_from_pubkey};
+pub use caps::{TxCaps, TxPayment, TxSummary};
+pub use decode::{DecodedInput, DecodedOutput, DecodedTransaction, DecodedTvmTx, DecodedUtxoTx};
+pub use evm::{ETHEREUM, EvmChain, SEPOLIA, evm_address_from_pubkey, to_checksum_address};
+pub use features::ChainFeatures;
+pub use message::MessageFormat;
+pub use params::{ChainParams, Explorer};
+pub use presets::AnyChain;
+pub use registry::{ChainDefinition, ChainFamily, ChainRegistry, RegisteredChain, RegistryError};
+pub use tvm::{TRON, TRON_NILE, TRON_SHASTA, TvmChain, tvm_address_from_pubkey};
+pub use utxo::{
+    BITCOIN, BITCOIN_CASH, BITCOIN_TESTNET, ChangeStrategy, CoinSelection, DOGECOIN, Htlc,
+    HtlcPath, HtlcSpend, LITECOIN, LITECOIN_TESTNET, MultisigKind, MultisigSpend, MultisigWallet,
+    OutPoint, SighashType, UnsignedUtxoTx, UtxoAddressType, UtxoChain, UtxoInput, UtxoOutput,
+    UtxoTxBuilder, p2tr_address_from_pubkey, p2wpkh_address_from_pubkey, utxo_address_from_pubkey,
+};
+
+use crate::node::{RawTransaction, Transaction, TxDetails};
+use crate::wallet::scheme::{KeyType, SignatureScheme};
 
 /// Blockchain-specific address derivation contract.
 pub trait Chain: Send + Sync {
+    /// Signature scheme this chain verifies; signers must produce the same one.
+    type Scheme: SignatureScheme;
+
     fn id(&self) -> &'static str;
+    /// Whether this chain moves real value. Wallets refuse to sign for it with
+    /// a signer [tagged](crate::wallet::Signer::network) for the other network.
+    fn network(&self) -> Network;
+    /// SLIP-44 coin type used in this chain's BIP-44 derivation paths.
+    fn coin_type(&self) -> u32;
+    /// Decimal places of the native coin: amounts are in units of 10^-decimals.
+    fn decimals(&self) -> u32;
+    /// Ticker of the native coin, e.g. `TRX`.
+    fn symbol(&self) -> &'static str;
+    /// Block explorer transactions and addresses link to, if the chain has one.
+    fn explorer(&self) -> Option<Explorer> {
+        None
+    }
+    /// Units, ticker, coin type and explorer of the chain together.
+    fn params(&self) -> ChainParams {
+        ChainParams {
+            symbol: self.symbol(),
+            decimals: self.decimals(),
+            coin_type: self.coin_type(),
+            explorer: self.explorer(),
+        }
+    }
+    /// Explorer page of transaction `hash`.
+    fn explorer_tx_url(&self, hash: &str) -> Option<String> {
+        self.explorer().map(|explorer| explorer.tx_url(hash))
+    }
+    /// Explorer page of `address`.
+    fn explorer_address_url(&self, address: &str) -> Option<String> {
+        self.explorer()
+            .map(|explorer| explorer.address_url(address))
+    }
+    /// BIP-43 purpose of the derivation path: 44 unless the address type has its own.
+    fn purpose(&self) -> u32 {
+        44
+    }
     fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError>;
-    fn prepare_transaction(&self, raw_tx: &str) -> Result<Vec<Vec<u8>>, ChainError>;
+    /// Check that `address` is well-formed for this chain (encoding, checksum, version byte).
+    fn validate_address(&self, address: &str) -> Result<(), ChainError>;
+    /// Whether [`prepare_transaction`](Self::prepare_transaction) yields digests to be
+    /// signed as-is rather than messages for the signer to hash.
+    fn prehashed(&self) -> bool {
+        false
+    }
+    /// What [`finalize_transaction`](Self::finalize_transaction) wants from the signer.
+    fn signature_format(&self) -> SignatureFormat {
+        SignatureFormat::Der
+    }
+    /// Canonical form signer output is brought into before it is finalized.
+    fn signature_policy(&self) -> SignaturePolicy {
+        SignaturePolicy::default()
+    }
+    /// What the chain offers beyond plain transfers. Nothing unless the chain says so.
+    fn features(&self) -> ChainFeatures {
+        ChainFeatures::default()
+    }
+    /// Smallest amount a plain transfer may move, in base units: the dust
+    /// threshold below which UTXO nodes refuse to relay an output, or 1 where
+    /// a transfer of nothing is invalid. Zero-value transfers are fine otherwise.
+    fn min_transfer(&self) -> u64 {
+        0
+    }
+    /// Virtual size, in bytes, an output paying `address` adds to a
+    /// transaction, on chains whose transactions can pay several recipients
+    /// at once. `None` where a transaction has a single recipient.
+    fn output_vsize(&self, address: &str) -> Option<u64> {
+        let _ = address;
+        None
+    }
+    /// Extract the payloads to sign from a provider-built transaction.
+    fn prepare_transaction(&self, raw_tx: &RawTransaction) -> Result<Vec<Vec<u8>>, ChainError>;
+    /// Attach `signatures` (one per prepared payload) and return the broadcastable transaction.
+    /// Chains whose accounts can require several keys take further signatures
+    /// of co-signers after those of `pubkey`.
     fn finalize_transaction(
         &self,
-        raw_tx: &str,
+        raw_tx: &RawTransaction,
         signatures: &[Vec<u8>],
         pubkey: &[u8],
-    ) -> Result<String, ChainError>;
+    ) -> Result<RawTransaction, ChainError>;
+
+    /// How the chain's wallets sign arbitrary messages; `None` if the
+    /// wallet cannot sign them for this chain.
+    fn message_format(&self) -> Option<MessageFormat> {
+        None
+    }
+
+    /// Have an unsigned transaction act under account permission
+    /// `permission_id` rather than the owner's, for chains with account-level
+    /// permissions (Tron). Others refuse.
+    fn apply_permission(
+        &self,
+        raw_tx: &RawTransaction,
+        permission_id: i32,
+    ) -> Result<RawTransaction, ChainError> {
+        let _ = (raw_tx, permission_id);
+        Err(ChainError::Other(format!(
+            "{} has no account permissions",
+            self.id()
+        )))
+    }
+
+    /// Attach `memo` to an unsigned transfer, e.g. the deposit memo an
+    /// exchange asks for, on chains whose [`features`](Self::features) have
+    /// `memos`. Others refuse.
+    fn apply_memo(
+        &self,
+        raw_tx: &RawTransaction,
+        memo: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        let _ = (raw_tx, memo);
+        Err(ChainError::Other(format!(
+            "{} transfers cannot carry a memo",
+            self.id()
+        )))
+    }
+
+    /// Mark an unsigned transaction as replaceable by a higher-fee one
+    /// spending the same coins (BIP-125), for chains whose transactions
+    /// must opt in. Others refuse.
+    fn signal_replaceable(&self, raw_tx: &RawTransaction) -> Result<RawTransaction, ChainError> {
+        let _ = raw_tx;
+        Err(ChainError::Other(format!(
+            "{} transactions cannot signal replace-by-fee",
+            self.id()
+        )))
+    }
+
+    /// Have an unsigned transaction stay valid for `lifetime` after it was
+    /// built, for chains whose transactions expire (Tron, after 60 seconds
+    /// by default), so slow signers do not run out the clock. Others refuse.
+    fn extend_expiration(
+        &self,
+        raw_tx: &RawTransaction,
+        lifetime: crate::time::Duration,
+    ) -> Result<RawTransaction, ChainError> {
+        let _ = (raw_tx, lifetime);
+        Err(ChainError::Other(format!(
+            "{} transactions do not expire",
+            self.id()
+        )))
+    }
+
+    /// Decode what a provider-built transaction would do once signed.
+    /// Chains without a decoder refuse, so caps are never waved through.
+    fn inspect_transaction(&self, raw_tx: &RawTransaction) -> Result<TxSummary, ChainError> {
+        let _ = raw_tx;
+        Err(ChainError::Other(format!(
+            "{} cannot inspect transactions",
+            self.id()
+        )))
+    }
+    /// Decode every field of a provider-built transaction a person would
+    /// want to see before signing it, unlike
+    /// [`inspect_transaction`](Self::inspect_transaction), which reads only
+    /// what caps check. Chains without a decoder refuse.
+    fn decode_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<DecodedTransaction, ChainError> {
+        let _ = raw_tx;
+        Err(ChainError::Other(format!(
+            "{} cannot decode transactions",
+            self.id()
+        )))
+    }
+    /// Hash `signed_tx` will be known by once broadcast, worked out locally so
+    /// a send can be looked up on chain when the broadcast itself is lost.
+    fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+        let _ = signed_tx;
+        Err(ChainError::Other(format!(
+            "{} cannot compute transaction hashes",
+            self.id()
+        )))
+    }
+    /// [`prepare_transaction`](Self::prepare_transaction), refusing transactions
+    /// that do not stay within `caps`. For providers that are trusted to build
+    /// transactions but not with what they move.
+    fn prepare_transaction_capped(
+        &self,
+        raw_tx: &RawTransaction,
+        caps: &TxCaps,
+    ) -> Result<Vec<Vec<u8>>, ChainError> {
+        caps.check(&self.inspect_transaction(raw_tx)?)?;
+        self.prepare_transaction(raw_tx)
+    }
+
+    /// Decode chain-specific details from a history entry's [`raw`](Transaction::raw) payload.
+    /// Chains without a decoder, or entries without a payload, yield `Ok(None)`.
+    fn decode_provider_tx(&self, tx: &Transaction) -> Result<Option<TxDetails>, ChainError> {
+        let _ = tx;
+        Ok(None)
+    }
+}
+
+/// Forward every [`Chain`] method of a smart pointer to its target.
+macro_rules! forward_chain {
+    ($pointer:ident) => {
+        impl<C: Chain + ?Sized> Chain for $pointer<C> {
+            type Scheme = C::Scheme;
+
+            fn id(&self) -> &'static str {
+                (**self).id()
+            }
+            fn network(&self) -> Network {
+                (**self).network()
+            }
+            fn coin_type(&self) -> u32 {
+                (**self).coin_type()
+            }
+            fn decimals(&self) -> u32 {
+                (**self).decimals()
+            }
+            fn symbol(&self) -> &'static str {
+                (**self).symbol()
+            }
+            fn explorer(&self) -> Option<Explorer> {
+                (**self).explorer()
+            }
+            fn purpose(&self) -> u32 {
+                (**self).purpose()
+            }
+            fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+                (**self).address_from_pubkey(pubkey_sec1)
+            }
+            fn validate_address(&self, address: &str) -> Result<(), ChainError> {
+                (**self).validate_address(address)
+            }
+            fn prehashed(&self) -> bool {
+                (**self).prehashed()
+            }
+            fn signature_format(&self) -> SignatureFormat {
+                (**self).signature_format()
+            }
+            fn signature_policy(&self) -> SignaturePolicy {
+                (**self).signature_policy()
+            }
+            fn features(&self) -> ChainFeatures {
+                (**self).features()
+            }
+            fn min_transfer(&self) -> u64 {
+                (**self).min_transfer()
+            }
+            fn output_vsize(&self, address: &str) -> Option<u64> {
+                (**self).output_vsize(address)
+            }
+            fn prepare_transaction(
+                &self,
+                raw_tx: &RawTransaction,
+            ) -> Result<Vec<Vec<u8>>, ChainError> {
+                (**self).prepare_transaction(raw_tx)
+            }
+            fn finalize_transaction(
+                &self,
+                raw_tx: &RawTransaction,
+                signatures: &[Vec<u8>],
+                pubkey: &[u8],
+            ) -> Result<RawTransaction, ChainError> {
+                (**self).finalize_transaction(raw_tx, signatures, pubkey)
+            }
+            fn message_format(&self) -> Option<MessageFormat> {
+                (**self).message_format()
+            }
+            fn apply_permission(
+                &self,
+                raw_tx: &RawTransaction,
+                permission_id: i32,
+            ) -> Result<RawTransaction, ChainError> {
+                (**self).apply_permission(raw_tx, permission_id)
+            }
+            fn apply_memo(
+                &self,
+                raw_tx: &RawTransaction,
+                memo: &[u8],
+            ) -> Result<RawTransaction, ChainError> {
+                (**self).apply_memo(raw_tx, memo)
+            }
+            fn signal_replaceable(
+                &self,
+                raw_tx: &RawTransaction,
+            ) -> Result<RawTransaction, ChainError> {
+                (**self).signal_replaceable(raw_tx)
+            }
+            fn extend_expiration(
+                &self,
+                raw_tx: &RawTransaction,
+                lifetime: crate::time::Duration,
+            ) -> Result<RawTransaction, ChainError> {
+                (**self).extend_expiration(raw_tx, lifetime)
+            }
+            fn inspect_transaction(
+                &self,
+                raw_tx: &RawTransaction,
+            ) -> Result<TxSummary, ChainError> {
+                (**self).inspect_transaction(raw_tx)
+            }
+            fn decode_transaction(
+                &self,
+                raw_tx: &RawTransaction,
+            ) -> Result<DecodedTransaction, ChainError> {
+                (**self).decode_transaction(raw_tx)
+            }
+            fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+                (**self).transaction_hash(signed_tx)
+            }
+            fn prepare_transaction_capped(
+                &self,
+                raw_tx: &RawTransaction,
+                caps: &TxCaps,
+            ) -> Result<Vec<Vec<u8>>, ChainError> {
+                (**self).prepare_transaction_capped(raw_tx, caps)
+            }
+            fn decode_provider_tx(
+                &self,
+                tx: &Transaction,
+            ) -> Result<Option<TxDetails>, ChainError> {
+                (**self).decode_provider_tx(tx)
+            }
+        }
+    };
+}
+
+// Chains picked at runtime, e.g. from a [`ChainRegistry`], are used boxed or shared.
+forward_chain!(Box);
+forward_chain!(Arc);
+
+/// Mainnet or testnet. The same seed derives the same keys on both, so this
+/// is what tells a testnet key apart from one that holds real funds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+        })
+    }
+}
+
+/// Encoding of the signatures a chain finalizes transactions with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// DER, from [`Signer::sign`](crate::wallet::Signer::sign) or
+    /// [`Signer::sign_prehash`](crate::wallet::Signer::sign_prehash).
+    Der,
+    /// `r || s || recid` over a prehash, from
+    /// [`Signer::sign_recoverable`](crate::wallet::Signer::sign_recoverable).
+    Recoverable,
+    /// 64-byte BIP-340 signature over a prehash by the key's Taproot output
+    /// key, from [`Signer::sign_schnorr`](crate::wallet::Signer::sign_schnorr)
+    /// with the key's [`taproot_tweak`](sdk::taproot_tweak).
+    Schnorr,
+}
+
+/// What to do with a high-s ECDSA signature. `(r, s)` and `(r, n - s)` both
+/// verify, so a signer's choice between them changes the transaction id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HighS {
+    /// Replace `s` with `n - s`, flipping the recovery id of recoverable signatures.
+    #[default]
+    Normalize,
+    /// Refuse the signature, to catch signers that should only produce low-s.
+    Reject,
+    /// Keep the signature as the signer produced it.
+    Allow,
+}
+
+/// Canonical form ECDSA signatures must be in before a chain finalizes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SignaturePolicy {
+    pub high_s: HighS,
+    /// Refuse DER signatures that are not minimally encoded (BIP-66).
+    pub strict_der: bool,
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum ChainError {
     #[error("invalid public key")]
     InvalidPublicKey,
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
     #[error("other error: {0}")]
     Other(String),
     #[error("derivation failed: {0}")]
     Derivation(String),
+    #[error("transaction exceeds caps: {0}")]
+    CapViolation(String),
+    #[error("non-canonical signature: {0}")]
+    NonCanonicalSignature(String),
+    #[error("expected a {expected} public key, got {actual}")]
+    KeyTypeMismatch { expected: KeyType, actual: KeyType },
 }
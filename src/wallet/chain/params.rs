@@ -0,0 +1,68 @@
+/// Block explorer pages of a chain, as templates with a `{}` where the
+/// transaction hash or address goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explorer {
+    pub tx: &'static str,
+    pub address: &'static str,
+}
+
+impl Explorer {
+    pub const fn new(tx: &'static str, address: &'static str) -> Self {
+        Self { tx, address }
+    }
+
+    pub fn tx_url(&self, hash: &str) -> String {
+        self.tx.replacen("{}", hash, 1)
+    }
+
+    pub fn address_url(&self, address: &str) -> String {
+        self.address.replacen("{}", address, 1)
+    }
+}
+
+/// What a chain's native coin is and how to present it: units, ticker,
+/// derivation and where to look transactions up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainParams {
+    /// Ticker of the native coin, e.g. `LTC`.
+    pub symbol: &'static str,
+    /// Decimal places of the native coin: amounts are in units of 10^-decimals.
+    pub decimals: u32,
+    /// SLIP-44 coin type of the chain's BIP-44 derivation paths.
+    pub coin_type: u32,
+    pub explorer: Option<Explorer>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wallet::chain::{
+        AnyChain, BITCOIN, Chain, ChainParams, ETHEREUM, LITECOIN, TRON_NILE,
+    };
+
+    #[test]
+    fn presets_link_to_their_explorers() {
+        assert_eq!(
+            TRON_NILE.explorer_tx_url("ab12").as_deref(),
+            Some("https://nile.tronscan.org/#/transaction/ab12")
+        );
+        assert_eq!(
+            ETHEREUM
+                .explorer_address_url("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+                .as_deref(),
+            Some("https://etherscan.io/address/0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+        );
+        let ChainParams {
+            symbol,
+            decimals,
+            coin_type,
+            ..
+        } = Box::new(AnyChain::Utxo(LITECOIN)).params();
+        assert_eq!((symbol, decimals, coin_type), ("LTC", 8, 2));
+        assert_eq!(BITCOIN.params().symbol, "BTC");
+        for chain in crate::wallet::chain::presets::PRESETS {
+            let explorer = chain.explorer().unwrap();
+            assert_eq!(explorer.tx.matches("{}").count(), 1);
+            assert_eq!(explorer.address.matches("{}").count(), 1);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Registry of the built-in chains, looked up by [`Chain::id`].
+
+use super::{
+    BITCOIN, BITCOIN_CASH, BITCOIN_TESTNET, Chain, ChainError, ChainFeatures, DOGECOIN, ETHEREUM,
+    EvmChain, Explorer, LITECOIN, LITECOIN_TESTNET, MessageFormat, Network, SEPOLIA,
+    SignatureFormat, SignaturePolicy, TRON, TRON_NILE, TRON_SHASTA, TvmChain, TxSummary, UtxoChain,
+    decode::DecodedTransaction,
+};
+use crate::node::{RawTransaction, Transaction, TxDetails};
+use crate::time::Duration;
+use crate::wallet::scheme::Secp256k1;
+#[cfg(feature = "network")]
+use {
+    crate::node::Provider,
+    crate::node::network::{eth::EthProvider, ltc::LtcProvider, tron::TronProvider},
+    std::sync::Arc,
+};
+
+/// Every built-in chain preset.
+pub const PRESETS: &[AnyChain] = &[
+    AnyChain::Utxo(BITCOIN),
+    AnyChain::Utxo(BITCOIN_TESTNET),
+    AnyChain::Utxo(LITECOIN),
+    AnyChain::Utxo(LITECOIN_TESTNET),
+    AnyChain::Utxo(DOGECOIN),
+    AnyChain::Utxo(BITCOIN_CASH),
+    AnyChain::Tvm(TRON),
+    AnyChain::Tvm(TRON_NILE),
+    AnyChain::Tvm(TRON_SHASTA),
+    AnyChain::Evm(ETHEREUM),
+    AnyChain::Evm(SEPOLIA),
+];
+
+/// One of the built-in chain families, for code that picks the chain at
+/// runtime (from configuration, a database row) rather than at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyChain {
+    Utxo(UtxoChain),
+    Tvm(TvmChain),
+    Evm(EvmChain),
+}
+
+impl AnyChain {
+    /// The preset whose [`id`](Chain::id) is `id`, e.g. `dogecoin` or `tron-nile`.
+    pub fn from_id(id: &str) -> Option<Self> {
+        PRESETS.iter().find(|chain| chain.id() == id).copied()
+    }
+
+    /// Public endpoint the provider for this chain talks to unless given
+    /// another, or `None` where there is no keyless one to default to.
+    pub fn default_endpoint(&self) -> Option<&'static str> {
+        match self.id() {
+            "tron" => Some("https://api.trongrid.io"),
+            "tron-nile" => Some("https://nile.trongrid.io"),
+            "tron-shasta" => Some("https://api.shasta.trongrid.io"),
+            "litecoin" => Some("https://api.blockcypher.com/v1/ltc/main"),
+            "litecoin-testnet" => Some("https://api.blockcypher.com/v1/ltc/test3"),
+            "ethereum" => Some("https://ethereum-rpc.publicnode.com"),
+            "sepolia" => Some("https://ethereum-sepolia-rpc.publicnode.com"),
+            _ => None,
+        }
+    }
+
+    /// Provider for this chain at its [`default_endpoint`](Self::default_endpoint).
+    #[cfg(feature = "network")]
+    pub fn default_provider(&self) -> Option<Arc<dyn Provider>> {
+        let url = self.default_endpoint()?;
+        Some(match self {
+            AnyChain::Tvm(_) => Arc::new(TronProvider::with_url(url.to_string())),
+            AnyChain::Utxo(_) => Arc::new(LtcProvider::with_url(url.to_string())),
+            AnyChain::Evm(_) => Arc::new(EthProvider::new(url)),
+        })
+    }
+}
+
+macro_rules! delegate {
+    ($self:ident, $chain:ident => $call:expr) => {
+        match $self {
+            AnyChain::Utxo($chain) => $call,
+            AnyChain::Tvm($chain) => $call,
+            AnyChain::Evm($chain) => $call,
+        }
+    };
+}
+
+impl Chain for AnyChain {
+    type Scheme = Secp256k1;
+
+    fn id(&self) -> &'static str {
+        delegate!(self, chain => chain.id())
+    }
+
+    fn network(&self) -> Network {
+        delegate!(self, chain => chain.network())
+    }
+
+    fn coin_type(&self) -> u32 {
+        delegate!(self, chain => chain.coin_type())
+    }
+
+    fn decimals(&self) -> u32 {
+        delegate!(self, chain => chain.decimals())
+    }
+
+    fn symbol(&self) -> &'static str {
+        delegate!(self, chain => chain.symbol())
+    }
+
+    fn explorer(&self) -> Option<Explorer> {
+        delegate!(self, chain => chain.explorer())
+    }
+
+    fn purpose(&self) -> u32 {
+        delegate!(self, chain => chain.purpose())
+    }
+
+    fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+        delegate!(self, chain => chain.address_from_pubkey(pubkey_sec1))
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), ChainError> {
+        delegate!(self, chain => chain.validate_address(address))
+    }
+
+    fn prehashed(&self) -> bool {
+        delegate!(self, chain => chain.prehashed())
+    }
+
+    fn signature_format(&self) -> SignatureFormat {
+        delegate!(self, chain => chain.signature_format())
+    }
+
+    fn signature_policy(&self) -> SignaturePolicy {
+        delegate!(self, chain => chain.signature_policy())
+    }
+
+    fn features(&self) -> ChainFeatures {
+        delegate!(self, chain => chain.features())
+    }
+
+    fn min_transfer(&self) -> u64 {
+        delegate!(self, chain => chain.min_transfer())
+    }
+
+    fn output_vsize(&self, address: &str) -> Option<u64> {
+        delegate!(self, chain => chain.output_vsize(address))
+    }
+
+    fn prepare_transaction(&self, raw_tx: &RawTransaction) -> Result<Vec<Vec<u8>>, ChainError> {
+        delegate!(self, chain => chain.prepare_transaction(raw_tx))
+    }
+
+    fn finalize_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+        signatures: &[Vec<u8>],
+        pubkey: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        delegate!(self, chain => chain.finalize_transaction(raw_tx, signatures, pubkey))
+    }
+
+    fn message_format(&self) -> Option<MessageFormat> {
+        delegate!(self, chain => chain.message_format())
+    }
+
+    fn apply_permission(
+        &self,
+        raw_tx: &RawTransaction,
+        permission_id: i32,
+    ) -> Result<RawTransaction, ChainError> {
+        delegate!(self, chain => chain.apply_permission(raw_tx, permission_id))
+    }
+
+    fn apply_memo(
+        &self,
+        raw_tx: &RawTransaction,
+        memo: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        delegate!(self, chain => chain.apply_memo(raw_tx, memo))
+    }
+
+    fn signal_replaceable(&self, raw_tx: &RawTransaction) -> Result<RawTransaction, ChainError> {
+        delegate!(self, chain => chain.signal_replaceable(raw_tx))
+    }
+
+    fn extend_expiration(
+        &self,
+        raw_tx: &RawTransaction,
+        lifetime: Duration,
+    ) -> Result<RawTransaction, ChainError> {
+        delegate!(self, chain => chain.extend_expiration(raw_tx, lifetime))
+    }
+
+    fn inspect_transaction(&self, raw_tx: &RawTransaction) -> Result<TxSummary, ChainError> {
+        delegate!(self, chain => chain.inspect_transaction(raw_tx))
+    }
+
+    fn decode_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<DecodedTransaction, ChainError> {
+        delegate!(self, chain => chain.decode_transaction(raw_tx))
+    }
+
+    fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+        delegate!(self, chain => chain.transaction_hash(signed_tx))
+    }
+
+    fn decode_provider_tx(&self, tx: &Transaction) -> Result<Option<TxDetails>, ChainError> {
+        delegate!(self, chain => chain.decode_provider_tx(tx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::signer::LocalSigner;
+    use crate::wallet::{Signer, Wallet};
+
+    #[test]
+    fn presets_by_id_derive_their_own_addresses() {
+        let ids: Vec<_> = PRESETS.iter().map(|chain| chain.id()).collect();
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len(), "duplicate preset id");
+        assert_eq!(
+            AnyChain::from_id("dogecoin"),
+            Some(AnyChain::Utxo(DOGECOIN))
+        );
+        assert_eq!(AnyChain::from_id("solana"), None);
+        for chain in PRESETS {
+            if chain.network() == Network::Testnet
+                && let Some(url) = chain.default_endpoint()
+            {
+                assert!(
+                    !PRESETS
+                        .iter()
+                        .any(|other| other.network() == Network::Mainnet
+                            && other.default_endpoint() == Some(url))
+                );
+            }
+        }
+
+        // Private key 1: the generator point.
+        let pubkey = LocalSigner::from_bytes({
+            let mut key = [0u8; 32];
+            key[31] = 1;
+            key
+        })
+        .unwrap()
+        .public_key();
+        let address = |id| {
+            AnyChain::from_id(id)
+                .unwrap()
+                .address_from_pubkey(&pubkey)
+                .unwrap()
+        };
+        assert_eq!(
+            address("bitcoin"),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        assert_eq!(
+            address("bitcoin-cash"),
+            "bitcoincash:qp63uahgrxged4z5jswyt5dn5v3lzsem6cy4spdc2h"
+        );
+        assert!(address("dogecoin").starts_with('D'));
+        assert!(address("bitcoin-testnet").starts_with("tb1q"));
+
+        let bch = AnyChain::from_id("bitcoin-cash").unwrap();
+        let wallet = Wallet::new(LocalSigner::from_bytes([3u8; 32]).unwrap(), bch);
+        let own = wallet.address().unwrap();
+        assert_eq!(bch.decimals(), 8);
+        assert!(bch.validate_address(&own).is_ok());
+        assert!(
+            bch.validate_address("1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu")
+                .is_ok()
+        );
+        let AnyChain::Utxo(chain) = bch else {
+            unreachable!()
+        };
+        assert_eq!(
+            chain.address_from_script(&chain.script_pubkey(&own).unwrap()),
+            own
+        );
+    }
+}
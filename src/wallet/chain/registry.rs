@@ -0,0 +1,552 @@
+//! Chains defined in configuration rather than code.
+//!
+//! A [`ChainRegistry`] reads a list of chain definitions from JSON (or TOML,
+//! with the `toml` feature) and hands out [`Chain`]s and providers for them
+//! by name, so a deployment can add a network such as Dash or a private EVM
+//! chain without a rebuild. Every definition is one of the built-in families
+//! ([`UtxoChain`], [`TvmChain`], [`EvmChain`]) with its own parameters:
+//!
+//! ```json
+//! { "chains": [
+//!     { "name": "dash", "family": "utxo", "p2pkh_prefix": 76, "p2sh_prefix": 16,
+//!       "coin_type": 5, "provider_url": "https://api.blockcypher.com/v1/dash/main" },
+//!     { "name": "base", "family": "evm", "chain_id": 8453,
+//!       "provider_url": "https://mainnet.base.org" }
+//! ] }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+#[cfg(feature = "network")]
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    AnyChain, Chain, ChainError, ChainFeatures, EvmChain, Explorer, MessageFormat, Network,
+    SignatureFormat, SignaturePolicy, TvmChain, TxSummary, UtxoAddressType, UtxoChain,
+    decode::DecodedTransaction,
+};
+#[cfg(feature = "network")]
+use crate::node::{
+    Provider,
+    network::prelude::{EthProvider, LtcProvider, TronProvider},
+};
+use crate::node::{RawTransaction, Transaction, TxDetails};
+use crate::time::Duration;
+use crate::wallet::key_source::{Bip44Path, parse_path};
+use crate::wallet::scheme::Secp256k1;
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("cannot read chain config {path}: {reason}")]
+    Io { path: String, reason: String },
+    #[error("invalid chain config: {0}")]
+    Parse(String),
+    #[error("invalid definition of chain {name:?}: {reason}")]
+    InvalidDefinition { name: String, reason: String },
+    #[error("chain {0:?} is defined more than once")]
+    Duplicate(String),
+    #[error("unknown chain {0:?}")]
+    UnknownChain(String),
+    #[error("chain {0:?} has no provider_url")]
+    NoProvider(String),
+}
+
+/// Family-specific parameters of a [`ChainDefinition`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "family", rename_all = "lowercase")]
+pub enum ChainFamily {
+    Utxo {
+        p2pkh_prefix: u8,
+        /// Version byte of base58 P2SH addresses, if the chain has them.
+        #[serde(default)]
+        p2sh_prefix: Option<u8>,
+        /// Bech32 human-readable part, or the CashAddr prefix.
+        #[serde(default)]
+        bech32_hrp: String,
+        #[serde(default = "default_address_type")]
+        address_type: UtxoAddressType,
+    },
+    Tvm {
+        #[serde(default = "default_tvm_prefix")]
+        address_prefix: u8,
+    },
+    Evm {
+        chain_id: u64,
+    },
+}
+
+fn default_address_type() -> UtxoAddressType {
+    UtxoAddressType::P2pkh
+}
+
+fn default_tvm_prefix() -> u8 {
+    0x41
+}
+
+fn default_network() -> Network {
+    Network::Mainnet
+}
+
+/// One chain as it appears in a config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainDefinition {
+    /// Name the chain is looked up by; becomes its [`Chain::id`].
+    pub name: String,
+    #[serde(flatten)]
+    pub family: ChainFamily,
+    /// SLIP-44 coin type; required for UTXO chains, 195 on TVM and 60 on EVM
+    /// chains when unset.
+    #[serde(default)]
+    pub coin_type: Option<u32>,
+    #[serde(default = "default_network")]
+    pub network: Network,
+    /// Decimal places of the native coin; the family's usual count when unset.
+    #[serde(default)]
+    pub decimals: Option<u32>,
+    /// Ticker of the native coin; `TRX` on TVM and `ETH` on EVM chains, and
+    /// the name in upper case on UTXO chains, when unset.
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Explorer page templates, each with a `{}` for the transaction hash or
+    /// address. Both or neither.
+    #[serde(default)]
+    pub explorer_tx_url: Option<String>,
+    #[serde(default)]
+    pub explorer_address_url: Option<String>,
+    /// Path keys are derived at by default, e.g. `m/44'/5'/0'/0/0`; the
+    /// chain's first BIP-44 receiving address when unset.
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    /// Endpoint of the family's provider: a Blockcypher-style API for UTXO
+    /// chains, TronGrid for TVM chains and JSON-RPC for EVM chains.
+    #[serde(default)]
+    pub provider_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    chains: Vec<ChainDefinition>,
+}
+
+/// A chain built from a [`ChainDefinition`]: one of the built-in families with
+/// the configured decimals, derivation path and provider endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredChain {
+    pub chain: AnyChain,
+    pub decimals: u32,
+    pub derivation_path: String,
+    pub provider_url: Option<String>,
+}
+
+impl RegisteredChain {
+    fn from_definition(definition: ChainDefinition) -> Result<Self, RegistryError> {
+        let invalid = |reason: String| RegistryError::InvalidDefinition {
+            name: definition.name.clone(),
+            reason,
+        };
+        if definition.name.is_empty() {
+            return Err(invalid("empty name".into()));
+        }
+        // Chain ids are `&'static str`. Registries are loaded once at startup,
+        // so the names live for the rest of the process anyway.
+        let name = leak(&definition.name);
+        let explorer = match (
+            &definition.explorer_tx_url,
+            &definition.explorer_address_url,
+        ) {
+            (Some(tx), Some(address)) => {
+                if [tx, address].iter().any(|t| t.matches("{}").count() != 1) {
+                    return Err(invalid("explorer URLs need exactly one {}".into()));
+                }
+                Some(Explorer::new(leak(tx), leak(address)))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(invalid(
+                    "explorer_tx_url and explorer_address_url go together".into(),
+                ));
+            }
+        };
+        let symbol = |default: &str| leak(definition.symbol.as_deref().unwrap_or(default));
+        let chain = match &definition.family {
+            ChainFamily::Utxo {
+                p2pkh_prefix,
+                p2sh_prefix,
+                bech32_hrp,
+                address_type,
+            } => {
+                if *address_type != UtxoAddressType::P2pkh && bech32_hrp.is_empty() {
+                    return Err(invalid(format!(
+                        "{:?} addresses need a bech32_hrp",
+                        address_type
+                    )));
+                }
+                let coin_type = definition
+                    .coin_type
+                    .ok_or_else(|| invalid("UTXO chains need a coin_type".into()))?;
+                AnyChain::Utxo(UtxoChain {
+                    name,
+                    p2pkh_prefix: *p2pkh_prefix,
+                    p2sh_prefix: *p2sh_prefix,
+                    bech32_hrp: leak(bech32_hrp),
+                    address_type: *address_type,
+                    coin_type,
+                    network: definition.network,
+                    symbol: symbol(&definition.name.to_uppercase()),
+                    explorer,
+                })
+            }
+            ChainFamily::Tvm { address_prefix } => AnyChain::Tvm(TvmChain {
+                name,
+                address_prefix: *address_prefix,
+                network: definition.network,
+                symbol: symbol("TRX"),
+                explorer,
+            }),
+            ChainFamily::Evm { chain_id } => AnyChain::Evm(EvmChain {
+                name,
+                chain_id: *chain_id,
+                network: definition.network,
+                symbol: symbol("ETH"),
+                explorer,
+            }),
+        };
+        if definition.coin_type.is_some_and(|c| c != chain.coin_type()) {
+            return Err(invalid(format!(
+                "coin_type is fixed at {} for this family",
+                chain.coin_type()
+            )));
+        }
+        let derivation_path = match definition.derivation_path {
+            Some(path) => {
+                parse_path(&path).map_err(|e| invalid(e.to_string()))?;
+                path
+            }
+            None => Bip44Path::for_chain(&chain).to_string(),
+        };
+        Ok(Self {
+            decimals: definition.decimals.unwrap_or_else(|| chain.decimals()),
+            chain,
+            derivation_path,
+            provider_url: definition.provider_url,
+        })
+    }
+}
+
+fn leak(text: &str) -> &'static str {
+    Box::leak(text.to_string().into_boxed_str())
+}
+
+impl Chain for RegisteredChain {
+    type Scheme = Secp256k1;
+
+    fn id(&self) -> &'static str {
+        self.chain.id()
+    }
+
+    fn network(&self) -> Network {
+        self.chain.network()
+    }
+
+    fn coin_type(&self) -> u32 {
+        self.chain.coin_type()
+    }
+
+    fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.chain.symbol()
+    }
+
+    fn explorer(&self) -> Option<Explorer> {
+        self.chain.explorer()
+    }
+
+    fn purpose(&self) -> u32 {
+        self.chain.purpose()
+    }
+
+    fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+        self.chain.address_from_pubkey(pubkey_sec1)
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), ChainError> {
+        self.chain.validate_address(address)
+    }
+
+    fn prehashed(&self) -> bool {
+        self.chain.prehashed()
+    }
+
+    fn signature_format(&self) -> SignatureFormat {
+        self.chain.signature_format()
+    }
+
+    fn signature_policy(&self) -> SignaturePolicy {
+        self.chain.signature_policy()
+    }
+
+    fn features(&self) -> ChainFeatures {
+        self.chain.features()
+    }
+
+    fn min_transfer(&self) -> u64 {
+        self.chain.min_transfer()
+    }
+
+    fn output_vsize(&self, address: &str) -> Option<u64> {
+        self.chain.output_vsize(address)
+    }
+
+    fn prepare_transaction(&self, raw_tx: &RawTransaction) -> Result<Vec<Vec<u8>>, ChainError> {
+        self.chain.prepare_transaction(raw_tx)
+    }
+
+    fn finalize_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+        signatures: &[Vec<u8>],
+        pubkey: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        self.chain.finalize_transaction(raw_tx, signatures, pubkey)
+    }
+
+    fn message_format(&self) -> Option<MessageFormat> {
+        self.chain.message_format()
+    }
+
+    fn apply_permission(
+        &self,
+        raw_tx: &RawTransaction,
+        permission_id: i32,
+    ) -> Result<RawTransaction, ChainError> {
+        self.chain.apply_permission(raw_tx, permission_id)
+    }
+
+    fn apply_memo(
+        &self,
+        raw_tx: &RawTransaction,
+        memo: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        self.chain.apply_memo(raw_tx, memo)
+    }
+
+    fn signal_replaceable(&self, raw_tx: &RawTransaction) -> Result<RawTransaction, ChainError> {
+        self.chain.signal_replaceable(raw_tx)
+    }
+
+    fn extend_expiration(
+        &self,
+        raw_tx: &RawTransaction,
+        lifetime: Duration,
+    ) -> Result<RawTransaction, ChainError> {
+        self.chain.extend_expiration(raw_tx, lifetime)
+    }
+
+    fn inspect_transaction(&self, raw_tx: &RawTransaction) -> Result<TxSummary, ChainError> {
+        self.chain.inspect_transaction(raw_tx)
+    }
+
+    fn decode_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<DecodedTransaction, ChainError> {
+        self.chain.decode_transaction(raw_tx)
+    }
+
+    fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+        self.chain.transaction_hash(signed_tx)
+    }
+
+    fn decode_provider_tx(&self, tx: &Transaction) -> Result<Option<TxDetails>, ChainError> {
+        self.chain.decode_provider_tx(tx)
+    }
+}
+
+/// Chains by name, loaded from configuration.
+#[derive(Debug, Clone, Default)]
+pub struct ChainRegistry {
+    chains: BTreeMap<String, RegisteredChain>,
+}
+
+impl ChainRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a `{ "chains": [...] }` document.
+    pub fn from_json(json: &str) -> Result<Self, RegistryError> {
+        let file: RegistryFile =
+            serde_json::from_str(json).map_err(|e| RegistryError::Parse(e.to_string()))?;
+        Self::from_definitions(file.chains)
+    }
+
+    /// Parse a document of `[[chains]]` tables.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, RegistryError> {
+        let file: RegistryFile =
+            toml::from_str(toml).map_err(|e| RegistryError::Parse(e.to_string()))?;
+        Self::from_definitions(file.chains)
+    }
+
+    /// Read `path`, as TOML if it ends in `.toml` and as JSON otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| RegistryError::Io {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&text),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(RegistryError::Parse(
+                "TOML configs need the `toml` feature".into(),
+            )),
+            _ => Self::from_json(&text),
+        }
+    }
+
+    pub fn from_definitions(
+        definitions: impl IntoIterator<Item = ChainDefinition>,
+    ) -> Result<Self, RegistryError> {
+        let mut registry = Self::new();
+        for definition in definitions {
+            registry.register(definition)?;
+        }
+        Ok(registry)
+    }
+
+    /// Add `definition`, refusing a name that is already taken.
+    pub fn register(&mut self, definition: ChainDefinition) -> Result<(), RegistryError> {
+        if self.chains.contains_key(&definition.name) {
+            return Err(RegistryError::Duplicate(definition.name));
+        }
+        let chain = RegisteredChain::from_definition(definition)?;
+        self.chains.insert(chain.id().to_string(), chain);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredChain> {
+        self.chains.get(name)
+    }
+
+    /// Names of the registered chains, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chains.keys().map(String::as_str)
+    }
+
+    /// The chain named `name`, for a [`Wallet`](crate::wallet::Wallet) whose
+    /// chain is only known at runtime.
+    pub fn chain(&self, name: &str) -> Result<Box<dyn Chain<Scheme = Secp256k1>>, RegistryError> {
+        Ok(Box::new(self.entry(name)?.clone()))
+    }
+
+    /// A provider for the chain named `name`, talking to its `provider_url`.
+    #[cfg(feature = "network")]
+    pub fn provider(&self, name: &str) -> Result<Arc<dyn Provider>, RegistryError> {
+        let entry = self.entry(name)?;
+        let url = entry
+            .provider_url
+            .clone()
+            .ok_or_else(|| RegistryError::NoProvider(name.to_string()))?;
+        Ok(match entry.chain {
+            AnyChain::Utxo(_) => Arc::new(LtcProvider::with_url(url)),
+            AnyChain::Tvm(_) => Arc::new(TronProvider::with_url(url)),
+            AnyChain::Evm(_) => Arc::new(EthProvider::new(&url)),
+        })
+    }
+
+    fn entry(&self, name: &str) -> Result<&RegisteredChain, RegistryError> {
+        self.get(name)
+            .ok_or_else(|| RegistryError::UnknownChain(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Wallet;
+    use crate::wallet::signer::LocalSigner;
+
+    const CONFIG: &str = r#"{ "chains": [
+        { "name": "dash", "family": "utxo", "p2pkh_prefix": 76, "coin_type": 5,
+          "derivation_path": "m/44'/5'/0'/0/7",
+          "provider_url": "https://api.blockcypher.com/v1/dash/main" },
+        { "name": "base", "family": "evm", "chain_id": 8453,
+          "explorer_tx_url": "https://basescan.org/tx/{}",
+          "explorer_address_url": "https://basescan.org/address/{}",
+          "provider_url": "https://mainnet.base.org" },
+        { "name": "tron-private", "family": "tvm", "network": "testnet", "decimals": 8 }
+    ] }"#;
+
+    #[test]
+    fn builds_chains_from_definitions() {
+        let registry = ChainRegistry::from_json(CONFIG).unwrap();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["base", "dash", "tron-private"]
+        );
+
+        let dash = registry.get("dash").unwrap();
+        assert_eq!(dash.derivation_path, "m/44'/5'/0'/0/7");
+        assert_eq!(dash.decimals(), 8);
+        let wallet = Wallet::new(
+            LocalSigner::from_bytes([1u8; 32]).unwrap(),
+            registry.chain("dash").unwrap(),
+        );
+        assert!(wallet.address().unwrap().starts_with('X'));
+
+        let base = registry.get("base").unwrap();
+        assert_eq!(base.derivation_path, "m/44'/60'/0'/0/0");
+        assert_eq!(base.decimals(), 18);
+        assert_eq!(
+            base.explorer_tx_url("0xab").as_deref(),
+            Some("https://basescan.org/tx/0xab")
+        );
+        assert_eq!((base.symbol(), dash.symbol()), ("ETH", "DASH"));
+        let tron = registry.get("tron-private").unwrap();
+        assert_eq!((tron.network(), tron.decimals()), (Network::Testnet, 8));
+
+        assert!(matches!(
+            registry.chain("solana"),
+            Err(RegistryError::UnknownChain(_))
+        ));
+        #[cfg(feature = "network")]
+        {
+            assert!(registry.provider("base").is_ok());
+            assert!(matches!(
+                registry.provider("tron-private"),
+                Err(RegistryError::NoProvider(_))
+            ));
+        }
+
+        let bad_path = r#"{ "chains": [ { "name": "x", "family": "evm", "chain_id": 1,
+            "derivation_path": "m/44'/60'/zero" } ] }"#;
+        assert!(matches!(
+            ChainRegistry::from_json(bad_path),
+            Err(RegistryError::InvalidDefinition { .. })
+        ));
+        let half_explorer = r#"{ "chains": [ { "name": "x", "family": "evm", "chain_id": 1,
+            "explorer_tx_url": "https://example.com/tx/{}" } ] }"#;
+        assert!(matches!(
+            ChainRegistry::from_json(half_explorer),
+            Err(RegistryError::InvalidDefinition { .. })
+        ));
+
+        #[cfg(feature = "toml")]
+        {
+            let registry = ChainRegistry::from_toml(
+                "[[chains]]\nname = \"dogecoin-custom\"\nfamily = \"utxo\"\n\
+                 p2pkh_prefix = 30\ncoin_type = 3\n",
+            )
+            .unwrap();
+            assert_eq!(registry.get("dogecoin-custom").unwrap().coin_type(), 3);
+        }
+    }
+}
@@ -0,0 +1,690 @@
+//! Building blocks for implementing [`Chain`](super::Chain) outside this crate.
+//!
+//! Everything here is what the built-in TVM and UTXO chains use internally, so a
+//! custom chain gets the same encodings and checks without copying code.
+
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+use super::{ChainError, HighS, SignatureFormat, SignaturePolicy};
+use crate::node::RawTransaction;
+use crate::wallet::scheme::KeyType;
+
+pub use crate::wallet::crypto::hash::{double_sha256, hash160, keccak256, sha256, tagged_hash};
+
+/// Encode `payload` as base58 with a 4-byte double-SHA256 checksum.
+pub fn base58check_encode(payload: &[u8]) -> String {
+    let checksum = double_sha256(payload);
+    let mut bytes = Vec::with_capacity(payload.len() + 4);
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&checksum[..4]);
+    bs58::encode(bytes).into_string()
+}
+
+/// Decode a base58check string and return the payload without its checksum.
+pub fn base58check_decode(encoded: &str) -> Result<Vec<u8>, ChainError> {
+    let bytes = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|e| ChainError::InvalidAddress(e.to_string()))?;
+    if bytes.len() < 5 {
+        return Err(ChainError::InvalidAddress("too short".into()));
+    }
+
+    let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+    if double_sha256(payload)[..4] != *checksum {
+        return Err(ChainError::InvalidAddress("bad checksum".into()));
+    }
+    Ok(payload.to_vec())
+}
+
+/// Decode a versioned base58check payload (`version || body`) and check its shape.
+pub fn base58check_decode_versioned(
+    encoded: &str,
+    version: u8,
+    body_len: usize,
+) -> Result<Vec<u8>, ChainError> {
+    let payload = base58check_decode(encoded)?;
+    if payload.len() != body_len + 1 || payload[0] != version {
+        return Err(ChainError::InvalidAddress(format!(
+            "expected version 0x{:02x} with a {}-byte body",
+            version, body_len
+        )));
+    }
+    Ok(payload[1..].to_vec())
+}
+
+/// Parse a SEC1 public key (compressed or uncompressed). An ed25519 key is
+/// reported as [`ChainError::KeyTypeMismatch`] rather than as malformed.
+pub fn parse_sec1(pubkey_sec1: &[u8]) -> Result<VerifyingKey, ChainError> {
+    expect_key_type(pubkey_sec1, KeyType::Secp256k1)?;
+    VerifyingKey::from_sec1_bytes(pubkey_sec1).map_err(|_| ChainError::InvalidPublicKey)
+}
+
+/// Fail with [`ChainError::KeyTypeMismatch`] if `public_key` is recognizably
+/// on a curve other than `expected`.
+pub fn expect_key_type(public_key: &[u8], expected: KeyType) -> Result<(), ChainError> {
+    match KeyType::of_public_key(public_key) {
+        Some(actual) if actual != expected => Err(ChainError::KeyTypeMismatch { expected, actual }),
+        _ => Ok(()),
+    }
+}
+
+/// 33-byte compressed SEC1 encoding of a public key.
+pub fn sec1_compressed(pubkey_sec1: &[u8]) -> Result<[u8; 33], ChainError> {
+    let key = parse_sec1(pubkey_sec1)?;
+    let mut out = [0u8; 33];
+    out.copy_from_slice(key.to_encoded_point(true).as_bytes());
+    Ok(out)
+}
+
+/// BIP-86 tweak of a key used as a Taproot internal key with no script tree:
+/// `tagged_hash("TapTweak", x(P))`.
+pub fn taproot_tweak(pubkey_sec1: &[u8]) -> Result<[u8; 32], ChainError> {
+    let compressed = sec1_compressed(pubkey_sec1)?;
+    Ok(tagged_hash("TapTweak", &compressed[1..]))
+}
+
+/// X-only output key a BIP-86 Taproot output commits to: the key with even y,
+/// plus its [`taproot_tweak`] times the generator.
+pub fn taproot_output_key(pubkey_sec1: &[u8]) -> Result<[u8; 32], ChainError> {
+    use k256::elliptic_curve::PrimeField;
+    use k256::{ProjectivePoint, Scalar};
+
+    let mut even = sec1_compressed(pubkey_sec1)?;
+    even[0] = 0x02;
+    let internal =
+        VerifyingKey::from_sec1_bytes(&even).map_err(|_| ChainError::InvalidPublicKey)?;
+    let tweak = Option::<Scalar>::from(Scalar::from_repr(taproot_tweak(pubkey_sec1)?.into()))
+        .ok_or(ChainError::InvalidPublicKey)?;
+    let output = ProjectivePoint::from(*internal.as_affine()) + ProjectivePoint::GENERATOR * tweak;
+    let output =
+        VerifyingKey::from_affine(output.to_affine()).map_err(|_| ChainError::InvalidPublicKey)?;
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&output.to_encoded_point(true).as_bytes()[1..]);
+    Ok(key)
+}
+
+/// 65-byte uncompressed SEC1 encoding (`0x04 || X || Y`) of a public key.
+pub fn sec1_uncompressed(pubkey_sec1: &[u8]) -> Result<[u8; 65], ChainError> {
+    let key = parse_sec1(pubkey_sec1)?;
+    let encoded = key.to_encoded_point(false);
+    let bytes = encoded.as_bytes();
+    if bytes.len() != 65 || bytes[0] != 0x04 {
+        return Err(ChainError::Derivation(
+            "unexpected uncompressed key format".into(),
+        ));
+    }
+    let mut out = [0u8; 65];
+    out.copy_from_slice(bytes);
+    Ok(out)
+}
+
+/// Parse a DER signature from a [`Signer`](crate::wallet::Signer), normalized to low-s.
+pub fn parse_der_signature(der: &[u8]) -> Result<Signature, ChainError> {
+    let signature = Signature::from_der(der)
+        .map_err(|e| ChainError::Other(format!("Invalid DER signature: {}", e)))?;
+    Ok(signature.normalize_s().unwrap_or(signature))
+}
+
+/// Find the y-parity under which `signature` over `prehash` recovers to `pubkey`.
+pub fn recovery_parity(
+    prehash: &[u8; 32],
+    signature: &Signature,
+    pubkey: &[u8],
+) -> Result<u8, ChainError> {
+    let expected = parse_sec1(pubkey)?;
+    for parity in [false, true] {
+        let recid = RecoveryId::new(parity, false);
+        if VerifyingKey::recover_from_prehash(prehash, signature, recid).ok() == Some(expected) {
+            return Ok(parity as u8);
+        }
+    }
+    Err(ChainError::Other(
+        "signature does not match the signer's public key".to_string(),
+    ))
+}
+
+/// Signature and y-parity from what a signer returned: a 65-byte `r || s || recid`
+/// from [`Signer::sign_recoverable`](crate::wallet::Signer::sign_recoverable),
+/// or DER, whose parity is found by trial recovery. Normalized to low-s either way.
+pub fn parse_recoverable_signature(
+    signature: &[u8],
+    prehash: &[u8; 32],
+    pubkey: &[u8],
+) -> Result<(Signature, u8), ChainError> {
+    if let [rs @ .., recid @ (0 | 1)] = signature
+        && rs.len() == 64
+        && let Ok(parsed) = Signature::from_slice(rs)
+    {
+        // Normalizing s negates the nonce point, flipping its y-parity.
+        let (parsed, parity) = match parsed.normalize_s() {
+            Some(normalized) => (normalized, recid ^ 1),
+            None => (parsed, *recid),
+        };
+        let recovered = VerifyingKey::recover_from_prehash(
+            prehash,
+            &parsed,
+            RecoveryId::new(parity == 1, false),
+        );
+        if recovered.ok() != Some(parse_sec1(pubkey)?) {
+            return Err(ChainError::Other(
+                "signature does not match the signer's public key".to_string(),
+            ));
+        }
+        return Ok((parsed, parity));
+    }
+    let parsed = parse_der_signature(signature)?;
+    let parity = recovery_parity(prehash, &parsed, pubkey)?;
+    Ok((parsed, parity))
+}
+
+/// Compressed SEC1 key that produced the recoverable `r || s || recid`
+/// `signature` of `prehash`, for signatures whose signer is not known up
+/// front, such as a multisig co-signer's.
+pub fn recover_signer(signature: &[u8], prehash: &[u8; 32]) -> Result<Vec<u8>, ChainError> {
+    let [rs @ .., recid @ (0 | 1)] = signature else {
+        return Err(ChainError::Other(
+            "expected a 65-byte recoverable signature".to_string(),
+        ));
+    };
+    let parsed = Signature::from_slice(rs)
+        .map_err(|e| ChainError::Other(format!("invalid signature: {}", e)))?;
+    let key =
+        VerifyingKey::recover_from_prehash(prehash, &parsed, RecoveryId::new(*recid == 1, false))
+            .map_err(|_| ChainError::Other("cannot recover the signer's public key".to_string()))?;
+    Ok(key.to_encoded_point(true).as_bytes().to_vec())
+}
+
+/// Bring a signer's output into the form `policy` requires. Recoverable
+/// signatures stay `r || s || recid`, with the recovery id flipped when `s` is
+/// normalized; Schnorr signatures are not malleable and only checked for
+/// length; anything else is treated as DER.
+pub fn canonicalize_signature(
+    signature: &[u8],
+    format: SignatureFormat,
+    policy: &SignaturePolicy,
+) -> Result<Vec<u8>, ChainError> {
+    if format == SignatureFormat::Schnorr {
+        if signature.len() != 64 {
+            return Err(ChainError::NonCanonicalSignature(format!(
+                "expected a 64-byte Schnorr signature, got {} bytes",
+                signature.len()
+            )));
+        }
+        return Ok(signature.to_vec());
+    }
+    if format == SignatureFormat::Recoverable
+        && let [rs @ .., recid @ (0 | 1)] = signature
+        && rs.len() == 64
+    {
+        let parsed = Signature::from_slice(rs)
+            .map_err(|e| ChainError::NonCanonicalSignature(e.to_string()))?;
+        return Ok(match low_s(parsed, policy.high_s)? {
+            Some(normalized) => [&normalized.to_bytes()[..], &[recid ^ 1]].concat(),
+            None => signature.to_vec(),
+        });
+    }
+    let parsed = Signature::from_der(signature)
+        .map_err(|e| ChainError::NonCanonicalSignature(format!("invalid DER: {}", e)))?;
+    if policy.strict_der && parsed.to_der().as_bytes() != signature {
+        return Err(ChainError::NonCanonicalSignature(
+            "DER is not minimally encoded".to_string(),
+        ));
+    }
+    Ok(match low_s(parsed, policy.high_s)? {
+        Some(normalized) => normalized.to_der().as_bytes().to_vec(),
+        None => signature.to_vec(),
+    })
+}
+
+/// The low-s replacement `high_s` asks for, if `signature` is high-s.
+fn low_s(signature: Signature, high_s: HighS) -> Result<Option<Signature>, ChainError> {
+    match (signature.normalize_s(), high_s) {
+        (None, _) | (Some(_), HighS::Allow) => Ok(None),
+        (Some(normalized), HighS::Normalize) => Ok(Some(normalized)),
+        (Some(_), HighS::Reject) => Err(ChainError::NonCanonicalSignature(
+            "high-s signature".to_string(),
+        )),
+    }
+}
+
+/// Borrow the JSON body of `raw_tx`, or fail naming the chain that expected it.
+pub fn expect_json<'a>(
+    chain: &str,
+    raw_tx: &'a RawTransaction,
+) -> Result<&'a serde_json::Value, ChainError> {
+    raw_tx.as_json().ok_or_else(|| {
+        ChainError::Other(format!(
+            "{} expects a JSON transaction, got {}",
+            chain,
+            raw_tx.kind()
+        ))
+    })
+}
+
+/// Decimal string of a big-endian unsigned integer of any width (e.g. an ABI `uint256`).
+pub fn be_bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut number = bytes.to_vec();
+    let mut digits = Vec::new();
+    while number.iter().any(|&b| b != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let acc = (remainder << 8) | *byte as u32;
+            *byte = (acc / 10) as u8;
+            remainder = acc % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// 32-byte big-endian form of a decimal `uint256`, such as an NFT token id;
+/// `None` if it is not a decimal number or does not fit.
+pub fn decimal_to_u256(decimal: &str) -> Option<[u8; 32]> {
+    if decimal.is_empty() {
+        return None;
+    }
+    let mut number = [0u8; 32];
+    for digit in decimal.bytes() {
+        let mut carry = match digit {
+            b'0'..=b'9' => (digit - b'0') as u32,
+            _ => return None,
+        };
+        for byte in number.iter_mut().rev() {
+            let acc = *byte as u32 * 10 + carry;
+            *byte = acc as u8;
+            carry = acc >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(number)
+}
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// Checksum constants of bech32 (BIP-173, witness v0) and bech32m (BIP-350, v1+).
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for &v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    out.push(0);
+    out.extend(hrp.bytes().map(|b| b & 0x1f));
+    out
+}
+
+/// Regroup `data` from `from`-bit to `to`-bit words (BIP-173 `convertbits`).
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let max = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &value in data {
+        if (value as u32) >> from != 0 {
+            return None;
+        }
+        acc = (acc << from) | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+/// Encode a SegWit address: bech32 for witness v0, bech32m for later versions.
+pub fn segwit_encode(hrp: &str, version: u8, program: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true).unwrap_or_default());
+
+    let constant = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    values.extend_from_slice(&[0; 6]);
+    let polymod = bech32_polymod(&values) ^ constant;
+
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len() + 6);
+    out.push_str(hrp);
+    out.push('1');
+    for d in data {
+        out.push(BECH32_CHARSET[d as usize] as char);
+    }
+    for i in 0..6 {
+        out.push(BECH32_CHARSET[((polymod >> (5 * (5 - i))) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Decode a SegWit address for `hrp` into its witness version and program.
+pub fn segwit_decode(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), ChainError> {
+    let invalid = |reason: &str| ChainError::InvalidAddress(reason.to_string());
+    if address.bytes().any(|b| b.is_ascii_lowercase())
+        && address.bytes().any(|b| b.is_ascii_uppercase())
+    {
+        return Err(invalid("mixed case"));
+    }
+    let address = address.to_ascii_lowercase();
+    let (prefix, data) = address
+        .rsplit_once('1')
+        .ok_or_else(|| invalid("missing separator"))?;
+    if prefix != hrp {
+        return Err(invalid("wrong human-readable part"));
+    }
+    if data.len() < 7 {
+        return Err(invalid("too short"));
+    }
+    let data = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&x| x == c).map(|p| p as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| invalid("invalid character"))?;
+
+    let version = data[0];
+    let constant = if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    if bech32_polymod(&values) != constant {
+        return Err(invalid("bad checksum"));
+    }
+
+    let program = convert_bits(&data[1..data.len() - 6], 5, 8, false)
+        .ok_or_else(|| invalid("invalid padding"))?;
+    if version > 16 || !(2..=40).contains(&program.len()) {
+        return Err(invalid("invalid witness program"));
+    }
+    if version == 0 && program.len() != 20 && program.len() != 32 {
+        return Err(invalid("invalid v0 program length"));
+    }
+    Ok((version, program))
+}
+
+/// CashAddr type of a pay-to-pubkey-hash address.
+pub const CASHADDR_P2PKH: u8 = 0;
+/// CashAddr type of a pay-to-script-hash address.
+pub const CASHADDR_P2SH: u8 = 1;
+
+/// BCH-code checksum of CashAddr, over 5-bit values.
+fn cashaddr_polymod(values: &[u8]) -> u64 {
+    const GEN: [u64; 5] = [
+        0x98_f2bc_8e61,
+        0x79_b76d_99e2,
+        0xf3_3e5f_b3c4,
+        0xae_2eab_e2a8,
+        0x1e_4f43_e470,
+    ];
+    let mut chk = 1u64;
+    for &v in values {
+        let top = chk >> 35;
+        chk = ((chk & 0x07_ffff_ffff) << 5) ^ v as u64;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk ^ 1
+}
+
+fn cashaddr_checksum_input(prefix: &str, data: &[u8]) -> Vec<u8> {
+    let mut values: Vec<u8> = prefix.bytes().map(|b| b & 0x1f).collect();
+    values.push(0);
+    values.extend_from_slice(data);
+    values
+}
+
+/// Encode a 20-byte hash as a CashAddr (Bitcoin Cash) address of `kind`
+/// ([`CASHADDR_P2PKH`] or [`CASHADDR_P2SH`]), e.g. `bitcoincash:qp...`.
+pub fn cashaddr_encode(prefix: &str, kind: u8, hash: &[u8; 20]) -> String {
+    let mut payload = vec![kind << 3];
+    payload.extend_from_slice(hash);
+    let data = convert_bits(&payload, 8, 5, true).unwrap_or_default();
+
+    let mut values = cashaddr_checksum_input(prefix, &data);
+    values.extend_from_slice(&[0; 8]);
+    let polymod = cashaddr_polymod(&values);
+
+    let mut out = String::with_capacity(prefix.len() + 1 + data.len() + 8);
+    out.push_str(prefix);
+    out.push(':');
+    for d in data {
+        out.push(BECH32_CHARSET[d as usize] as char);
+    }
+    for i in 0..8 {
+        out.push(BECH32_CHARSET[((polymod >> (5 * (7 - i))) & 31) as usize] as char);
+    }
+    out
+}
+
+/// Decode a CashAddr address, with or without its `prefix:`, into its type
+/// and 20-byte hash.
+pub fn cashaddr_decode(prefix: &str, address: &str) -> Result<(u8, [u8; 20]), ChainError> {
+    let invalid = |reason: &str| ChainError::InvalidAddress(reason.to_string());
+    if address.bytes().any(|b| b.is_ascii_lowercase())
+        && address.bytes().any(|b| b.is_ascii_uppercase())
+    {
+        return Err(invalid("mixed case"));
+    }
+    let address = address.to_ascii_lowercase();
+    let data = match address.split_once(':') {
+        Some((given, data)) if given == prefix => data,
+        Some(_) => return Err(invalid("wrong prefix")),
+        None => address.as_str(),
+    };
+    let data = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&x| x == c).map(|p| p as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| invalid("invalid character"))?;
+    if data.len() <= 8 || cashaddr_polymod(&cashaddr_checksum_input(prefix, &data)) != 0 {
+        return Err(invalid("bad checksum"));
+    }
+
+    let payload = convert_bits(&data[..data.len() - 8], 5, 8, false)
+        .ok_or_else(|| invalid("invalid padding"))?;
+    match payload.as_slice() {
+        // Size bits 0: a 160-bit hash.
+        [version, hash @ ..] if version & 0x87 == 0 && hash.len() == 20 => {
+            let mut out = [0u8; 20];
+            out.copy_from_slice(hash);
+            Ok((version >> 3, out))
+        }
+        _ => Err(invalid("unsupported CashAddr version")),
+    }
+}
+
+/// Append a Bitcoin-style CompactSize (varint) length prefix.
+pub fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Read a CompactSize at `*pos`, advancing past it.
+pub fn read_compact_size(bytes: &[u8], pos: &mut usize) -> Result<u64, ChainError> {
+    let truncated = || ChainError::Other("truncated CompactSize".to_string());
+    let width = match *bytes.get(*pos).ok_or_else(truncated)? {
+        n @ 0..=0xfc => {
+            *pos += 1;
+            return Ok(n as u64);
+        }
+        0xfd => 2,
+        0xfe => 4,
+        _ => 8,
+    };
+    let field = bytes
+        .get(*pos + 1..*pos + 1 + width)
+        .ok_or_else(truncated)?;
+    let mut le = [0u8; 8];
+    le[..width].copy_from_slice(field);
+    *pos += 1 + width;
+    Ok(u64::from_le_bytes(le))
+}
+
+/// Append `data` prefixed with its CompactSize length.
+pub fn write_var_bytes(out: &mut Vec<u8>, data: &[u8]) {
+    write_compact_size(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base58check_roundtrip_and_versioning() {
+        let payload = [0x30u8; 21];
+        let encoded = base58check_encode(&payload);
+        assert_eq!(base58check_decode(&encoded).unwrap(), payload);
+        assert_eq!(
+            base58check_decode_versioned(&encoded, 0x30, 20).unwrap(),
+            [0x30u8; 20]
+        );
+        assert!(base58check_decode_versioned(&encoded, 0x41, 20).is_err());
+    }
+
+    #[test]
+    fn segwit_bip173_vector() {
+        let address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let (version, program) =
+            segwit_decode("bc", "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(
+            hex::encode(&program),
+            "751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        assert_eq!(segwit_encode("bc", 0, &program), address);
+        assert!(segwit_decode("ltc", address).is_err());
+        assert!(segwit_decode("bc", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5").is_err());
+    }
+
+    #[test]
+    fn canonicalizes_high_s_per_policy() {
+        use k256::ecdsa::SigningKey;
+
+        let key = SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+        let prehash = sha256(b"malleable");
+        let (low, recid) = key.sign_prehash_recoverable(&prehash).unwrap();
+        let high = Signature::from_scalars(low.r(), -*low.s()).unwrap();
+        assert!(high.normalize_s().is_some());
+
+        let normalize = SignaturePolicy::default();
+        let der = high.to_der().as_bytes().to_vec();
+        let canonical = canonicalize_signature(&der, SignatureFormat::Der, &normalize).unwrap();
+        assert_eq!(canonical, low.to_der().as_bytes());
+
+        // The flipped recovery id still recovers the signer.
+        let recoverable = [&high.to_bytes()[..], &[recid.to_byte() ^ 1]].concat();
+        let canonical =
+            canonicalize_signature(&recoverable, SignatureFormat::Recoverable, &normalize).unwrap();
+        assert_eq!(canonical[..64], low.to_bytes()[..]);
+        assert_eq!(canonical[64], recid.to_byte());
+
+        let reject = SignaturePolicy {
+            high_s: HighS::Reject,
+            strict_der: true,
+        };
+        assert!(canonicalize_signature(&der, SignatureFormat::Der, &reject).is_err());
+        let allow = SignaturePolicy {
+            high_s: HighS::Allow,
+            strict_der: false,
+        };
+        assert_eq!(
+            canonicalize_signature(&der, SignatureFormat::Der, &allow).unwrap(),
+            der
+        );
+
+        // An integer padded with a redundant zero byte is valid BER, not DER.
+        let low_der = low.to_der().as_bytes().to_vec();
+        let r_len = low_der[3] as usize;
+        let mut padded = vec![0x30, low_der[1] + 1, 0x02, r_len as u8 + 1, 0x00];
+        padded.extend_from_slice(&low_der[4..]);
+        assert!(matches!(
+            canonicalize_signature(&padded, SignatureFormat::Der, &reject),
+            Err(ChainError::NonCanonicalSignature(_))
+        ));
+    }
+
+    #[test]
+    fn cashaddr_spec_vector() {
+        let hash: [u8; 20] = hex::decode("76a04053bda0a88bda5177b86a15c3b29f559873")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let address = "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a";
+        assert_eq!(
+            cashaddr_encode("bitcoincash", CASHADDR_P2PKH, &hash),
+            address
+        );
+        assert_eq!(
+            cashaddr_decode("bitcoincash", "QPM2QSZNHKS23Z7629MMS6S4CWEF74VCWVY22GDX6A").unwrap(),
+            (CASHADDR_P2PKH, hash)
+        );
+        assert!(cashaddr_decode("bchtest", address).is_err());
+        assert!(cashaddr_decode("bitcoincash", &address.replace("6a", "6b")).is_err());
+    }
+
+    #[test]
+    fn compact_size_boundaries() {
+        let encode = |n| {
+            let mut out = Vec::new();
+            write_compact_size(&mut out, n);
+            out
+        };
+        assert_eq!(encode(0xfc), [0xfc]);
+        assert_eq!(encode(0xfd), [0xfd, 0xfd, 0x00]);
+        assert_eq!(encode(0x1_0000), [0xfe, 0x00, 0x00, 0x01, 0x00]);
+    }
+}
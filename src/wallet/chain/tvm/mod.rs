@@ -0,0 +1,1093 @@
+pub mod proto;
+
+use prost::Message;
+
+use super::evm::{is_checksum_address, is_mixed_case, to_checksum_address};
+use super::sdk::{
+    base58check_decode_versioned, base58check_encode, be_bytes_to_decimal, expect_json, keccak256,
+    parse_recoverable_signature, recover_signer, sec1_compressed, sec1_uncompressed,
+};
+use super::{
+    Chain, ChainError, ChainFeatures, DecodedTransaction, DecodedTvmTx, Explorer, MessageFormat,
+    Network, SignatureFormat, TxPayment, TxSummary,
+};
+use crate::node::details::{TokenTransfer, TvmTxDetails};
+use crate::node::{RawTransaction, Transaction, TxDetails};
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::wallet::scheme::Secp256k1;
+use proto::{ContractType, TransactionRaw, TriggerSmartContract};
+
+/// Generic TVM-based chain implementation (e.g. Tron, Tron Testnet).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TvmChain {
+    pub name: &'static str,
+    pub address_prefix: u8,
+    /// Tron testnets share mainnet's address prefix, so only this tells them apart.
+    pub network: Network,
+    pub symbol: &'static str,
+    pub explorer: Option<Explorer>,
+}
+
+impl Chain for TvmChain {
+    type Scheme = Secp256k1;
+
+    fn id(&self) -> &'static str {
+        self.name
+    }
+
+    fn network(&self) -> Network {
+        self.network
+    }
+
+    /// 195 on mainnet and testnets alike, as TronLink derives.
+    fn coin_type(&self) -> u32 {
+        195
+    }
+
+    /// TRX counts in sun, 10^-6 TRX.
+    fn decimals(&self) -> u32 {
+        6
+    }
+
+    fn symbol(&self) -> &'static str {
+        self.symbol
+    }
+
+    fn explorer(&self) -> Option<Explorer> {
+        self.explorer
+    }
+
+    fn address_from_pubkey(&self, pubkey_sec1: &[u8]) -> Result<String, ChainError> {
+        tvm_address_from_pubkey(pubkey_sec1, self.address_prefix)
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), ChainError> {
+        base58check_decode_versioned(address, self.address_prefix, 20)
+            .map(|_| ())
+            .map_err(|e| ChainError::InvalidAddress(format!("{} ({}): {}", address, self.name, e)))
+    }
+
+    fn prehashed(&self) -> bool {
+        true
+    }
+
+    fn signature_format(&self) -> SignatureFormat {
+        SignatureFormat::Recoverable
+    }
+
+    /// Fees are paid in bandwidth and energy at network-wide prices, so there
+    /// is nothing to bid and nothing to replace.
+    fn features(&self) -> ChainFeatures {
+        ChainFeatures {
+            memos: true,
+            tokens: true,
+            multisig: true,
+            message_signing: true,
+            ..ChainFeatures::default()
+        }
+    }
+
+    /// `TransferContract` rejects an amount of zero; any positive amount also
+    /// activates a new recipient, the creation fee being the sender's.
+    fn min_transfer(&self) -> u64 {
+        1
+    }
+
+    /// Yields the transaction id, SHA-256 of `raw_data`, which is what Tron signs.
+    fn prepare_transaction(&self, raw_tx: &RawTransaction) -> Result<Vec<Vec<u8>>, ChainError> {
+        Ok(vec![self.raw_data(raw_tx)?.txid().to_vec()])
+    }
+
+    /// Attaches the signatures as the 65-byte `r || s || v` Tron nodes verify.
+    /// The first, of `pubkey`, may be recoverable or DER; those of co-signers
+    /// of a multi-signature permission must be recoverable, and each key may
+    /// sign once.
+    fn finalize_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+        signatures: &[Vec<u8>],
+        pubkey: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        let (signature, cosignatures) = signatures
+            .split_first()
+            .ok_or_else(|| ChainError::Other("No signatures provided".to_string()))?;
+        let tx_id = self.raw_data(raw_tx)?.txid();
+        let mut signers = vec![sec1_compressed(pubkey)?.to_vec()];
+        let mut signature_hexes = vec![tron_signature(signature, &tx_id, pubkey)?];
+        for cosignature in cosignatures {
+            let signer = recover_signer(cosignature, &tx_id)?;
+            if signers.contains(&signer) {
+                return Err(ChainError::Other(format!(
+                    "key {} signed more than once",
+                    hex::encode(&signer)
+                )));
+            }
+            signature_hexes.push(tron_signature(cosignature, &tx_id, &signer)?);
+            signers.push(signer);
+        }
+
+        let mut tx = expect_json(self.name, raw_tx)?.clone();
+        // Append to "signature" array
+        if let Some(sigs) = tx.get_mut("signature") {
+            if let Some(arr) = sigs.as_array_mut() {
+                arr.extend(signature_hexes.into_iter().map(serde_json::Value::String));
+            }
+        } else {
+            tx["signature"] = serde_json::json!(signature_hexes);
+        }
+        if tx.get("txID").is_none() {
+            tx["txID"] = serde_json::Value::String(hex::encode(tx_id));
+        }
+
+        Ok(RawTransaction::Json(tx))
+    }
+
+    fn message_format(&self) -> Option<MessageFormat> {
+        Some(MessageFormat::Tron)
+    }
+
+    /// Sets `Permission_id` on every contract, e.g. 2 for the first active
+    /// permission, and recomputes the transaction id the signatures cover.
+    fn apply_permission(
+        &self,
+        raw_tx: &RawTransaction,
+        permission_id: i32,
+    ) -> Result<RawTransaction, ChainError> {
+        self.rewrite_raw_data(raw_tx, "permission", |raw_data, tx| {
+            for contract in &mut raw_data.contract {
+                contract.permission_id = permission_id;
+            }
+            if let Some(contracts) = tx
+                .pointer_mut("/raw_data/contract")
+                .and_then(|v| v.as_array_mut())
+            {
+                for contract in contracts {
+                    contract["Permission_id"] = serde_json::json!(permission_id);
+                }
+            }
+        })
+    }
+
+    /// Puts `memo` in `raw_data.data`, where explorers and exchanges read
+    /// deposit memos from, and recomputes the transaction id.
+    fn apply_memo(
+        &self,
+        raw_tx: &RawTransaction,
+        memo: &[u8],
+    ) -> Result<RawTransaction, ChainError> {
+        self.rewrite_raw_data(raw_tx, "memo", |raw_data, tx| {
+            raw_data.data = memo.to_vec();
+            if let Some(json) = tx.get_mut("raw_data").and_then(|v| v.as_object_mut()) {
+                json.insert("data".to_string(), serde_json::json!(hex::encode(memo)));
+            }
+        })
+    }
+
+    /// Sets `expiration` to `lifetime` after the transaction's `timestamp`
+    /// and recomputes the transaction id. Nodes accept at most 24 hours.
+    fn extend_expiration(
+        &self,
+        raw_tx: &RawTransaction,
+        lifetime: Duration,
+    ) -> Result<RawTransaction, ChainError> {
+        if lifetime > MAX_EXPIRATION {
+            return Err(ChainError::Other(format!(
+                "expiration of {}s exceeds the 24 hours Tron nodes accept",
+                lifetime.as_secs()
+            )));
+        }
+        self.rewrite_raw_data(raw_tx, "expiration", |raw_data, tx| {
+            let built = if raw_data.timestamp > 0 {
+                raw_data.timestamp
+            } else {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as i64)
+                    .unwrap_or(0)
+            };
+            raw_data.expiration = built + lifetime.as_millis() as i64;
+            if let Some(json) = tx.get_mut("raw_data").and_then(|v| v.as_object_mut()) {
+                json.insert(
+                    "expiration".to_string(),
+                    serde_json::json!(raw_data.expiration),
+                );
+            }
+        })
+    }
+
+    /// Understands TRX transfers and TRC-20 `transfer` calls.
+    fn inspect_transaction(&self, raw_tx: &RawTransaction) -> Result<TxSummary, ChainError> {
+        let raw_data = self.raw_data(raw_tx)?;
+        let [contract] = raw_data.contract.as_slice() else {
+            return Err(ChainError::Other(
+                "expected exactly one contract".to_string(),
+            ));
+        };
+        let address = |bytes: &[u8]| {
+            self.hex_to_address(&hex::encode(bytes))
+                .ok_or_else(|| ChainError::InvalidAddress(hex::encode(bytes)))
+        };
+        let amount = |value: i64| {
+            u128::try_from(value)
+                .map_err(|_| ChainError::Other(format!("negative amount {}", value)))
+        };
+        match contract.kind() {
+            Some(ContractType::TransferContract) => {
+                let transfer = contract
+                    .unpack::<proto::TransferContract>()
+                    .ok_or_else(|| ChainError::Other("malformed TransferContract".to_string()))?;
+                Ok(TxSummary {
+                    payments: vec![TxPayment {
+                        to: address(&transfer.to_address)?,
+                        amount: amount(transfer.amount)?,
+                    }],
+                    token: None,
+                    max_fee: None,
+                })
+            }
+            Some(ContractType::TriggerSmartContract) => {
+                let call = contract.unpack::<TriggerSmartContract>().ok_or_else(|| {
+                    ChainError::Other("malformed TriggerSmartContract".to_string())
+                })?;
+                let (to, value) = decode_transfer_call(&hex::encode(&call.data))
+                    .filter(|_| call.call_value == 0)
+                    .ok_or_else(|| ChainError::Other("not a TRC-20 transfer".to_string()))?;
+                Ok(TxSummary {
+                    payments: vec![TxPayment {
+                        to: address(&hex::decode(&to).unwrap_or_default())?,
+                        amount: value.parse().map_err(|_| {
+                            ChainError::Other(format!("amount {} out of range", value))
+                        })?,
+                    }],
+                    token: Some(address(&call.contract_address)?),
+                    max_fee: Some(amount(raw_data.fee_limit)?),
+                })
+            }
+            _ => Err(ChainError::Other(format!(
+                "cannot inspect contract type {}",
+                contract.r#type
+            ))),
+        }
+    }
+
+    /// Reads the first contract from `raw_data_hex`, which is what gets
+    /// signed, not from the JSON rendering beside it.
+    fn decode_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<DecodedTransaction, ChainError> {
+        let raw_data = self.raw_data(raw_tx)?;
+        let contract = raw_data
+            .contract
+            .first()
+            .ok_or_else(|| ChainError::Other("raw_data has no contract".to_string()))?;
+        let address = |bytes: &[u8]| self.hex_to_address(&hex::encode(bytes));
+        let mut decoded = DecodedTvmTx {
+            contract_type: contract
+                .kind()
+                .map(|k| k.as_str_name().to_string())
+                .unwrap_or_else(|| format!("ContractType({})", contract.r#type)),
+            owner: contract
+                .unpack::<proto::ContractOwner>()
+                .and_then(|c| address(&c.owner_address)),
+            fee_limit: raw_data.fee_limit.max(0) as u64,
+            memo: (!raw_data.data.is_empty())
+                .then(|| String::from_utf8_lossy(&raw_data.data).into_owned()),
+            expiration: raw_data.expiration,
+            ..DecodedTvmTx::default()
+        };
+        match contract.kind() {
+            Some(ContractType::TransferContract) => {
+                if let Some(transfer) = contract.unpack::<proto::TransferContract>() {
+                    decoded.to = address(&transfer.to_address);
+                    decoded.amount = u128::try_from(transfer.amount).ok();
+                }
+            }
+            Some(ContractType::TriggerSmartContract) => {
+                if let Some(call) = contract.unpack::<TriggerSmartContract>() {
+                    let data = hex::encode(&call.data);
+                    match decode_transfer_call(&data).filter(|_| call.call_value == 0) {
+                        Some((to, value)) => {
+                            decoded.to = address(&hex::decode(&to).unwrap_or_default());
+                            decoded.amount = value.parse().ok();
+                            decoded.token = address(&call.contract_address);
+                        }
+                        None => {
+                            decoded.to = address(&call.contract_address);
+                            decoded.amount = u128::try_from(call.call_value).ok();
+                        }
+                    }
+                    decoded.data = Some(data);
+                }
+            }
+            Some(
+                ContractType::DelegateResourceContract | ContractType::UnDelegateResourceContract,
+            ) => {
+                if let Some(delegation) = contract.unpack::<proto::DelegateResourceContract>() {
+                    decoded.to = address(&delegation.receiver_address);
+                    decoded.amount = u128::try_from(delegation.balance).ok();
+                }
+            }
+            _ => {}
+        }
+        Ok(DecodedTransaction::Tvm(decoded))
+    }
+
+    /// The `txID`: SHA-256 of `raw_data`, which signatures do not change.
+    fn transaction_hash(&self, signed_tx: &RawTransaction) -> Result<String, ChainError> {
+        Ok(hex::encode(self.raw_data(signed_tx)?.txid()))
+    }
+
+    fn decode_provider_tx(&self, tx: &Transaction) -> Result<Option<TxDetails>, ChainError> {
+        let Some(raw) = &tx.raw else {
+            return Ok(None);
+        };
+        let contract = match raw.get("raw_data_hex").and_then(|v| v.as_str()) {
+            Some(raw_data_hex) => ContractFields::from_raw_data_hex(raw_data_hex)?,
+            None => ContractFields::from_json(
+                raw.pointer("/raw_data/contract/0")
+                    .ok_or_else(|| ChainError::Other("missing raw_data.contract".to_string()))?,
+            ),
+        };
+
+        let fee = match raw.pointer("/ret/0/fee").and_then(|v| v.as_u64()) {
+            Some(fee) => fee,
+            None => ["net_fee", "energy_fee"]
+                .iter()
+                .filter_map(|k| raw.get(*k).and_then(|v| v.as_u64()))
+                .sum(),
+        };
+
+        let mut token_transfers = Vec::new();
+        if let Some(logs) = raw.get("log").and_then(|v| v.as_array()) {
+            for log in logs {
+                if let Some(transfer) = self.decode_transfer_log(log) {
+                    token_transfers.push(transfer);
+                }
+            }
+        } else if let (Some(token), Some(owner), Some(data)) = (
+            contract.contract_address.as_deref(),
+            contract.owner_address.as_deref(),
+            contract.data.as_deref(),
+        ) && let Some((to, amount)) = decode_transfer_call(data)
+        {
+            token_transfers.push(TokenTransfer {
+                token: self
+                    .hex_to_address(token)
+                    .unwrap_or_else(|| token.to_string()),
+                from: self
+                    .hex_to_address(owner)
+                    .unwrap_or_else(|| owner.to_string()),
+                to: self.hex_to_address(&to).unwrap_or(to),
+                value: amount,
+            });
+        }
+
+        Ok(Some(TxDetails::Tvm(TvmTxDetails {
+            contract_type: contract.kind,
+            contract_address: contract
+                .contract_address
+                .as_deref()
+                .map(|a| self.hex_to_address(a).unwrap_or_else(|| a.to_string())),
+            fee,
+            energy_usage: raw.get("energy_usage_total").and_then(|v| v.as_u64()),
+            token_transfers,
+        })))
+    }
+}
+
+/// Longest a transaction may stay valid, as java-tron enforces.
+const MAX_EXPIRATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `keccak256("Transfer(address,address,uint256)")`
+const TRANSFER_EVENT_TOPIC: &str =
+    "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// Selector of `transfer(address,uint256)`.
+const TRANSFER_SELECTOR: &str = "a9059cbb";
+
+impl TvmChain {
+    /// Apply `edit` to the raw data of an unsigned transaction and to its JSON
+    /// form, then re-encode `raw_data_hex` and recompute `txID`.
+    fn rewrite_raw_data(
+        &self,
+        raw_tx: &RawTransaction,
+        what: &str,
+        edit: impl FnOnce(&mut TransactionRaw, &mut serde_json::Value),
+    ) -> Result<RawTransaction, ChainError> {
+        let mut raw_data = self.raw_data(raw_tx)?;
+        let mut tx = expect_json(self.name, raw_tx)?.clone();
+        if tx
+            .get("signature")
+            .and_then(|v| v.as_array())
+            .is_some_and(|sigs| !sigs.is_empty())
+        {
+            return Err(ChainError::Other(format!(
+                "cannot change the {} of a signed transaction",
+                what
+            )));
+        }
+        edit(&mut raw_data, &mut tx);
+        tx["raw_data_hex"] = serde_json::Value::String(hex::encode(raw_data.encode_to_vec()));
+        tx["txID"] = serde_json::Value::String(hex::encode(raw_data.txid()));
+        Ok(RawTransaction::Json(tx))
+    }
+
+    /// Decoded `raw_data_hex` of a provider transaction, checked against its `txID`.
+    fn raw_data(&self, raw_tx: &RawTransaction) -> Result<TransactionRaw, ChainError> {
+        let tx = expect_json(self.name, raw_tx)?;
+        let raw_data_hex = tx
+            .get("raw_data_hex")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ChainError::Other("Missing raw_data_hex".to_string()))?;
+
+        let raw_data_bytes = hex::decode(raw_data_hex)
+            .map_err(|e| ChainError::Other(format!("Invalid hex: {}", e)))?;
+
+        // Only sign bytes that parse as a Tron transaction, and whose id is the one
+        // the provider reported.
+        let raw_data = TransactionRaw::decode(raw_data_bytes.as_slice())
+            .map_err(|e| ChainError::Other(format!("Invalid raw_data: {}", e)))?;
+        if raw_data.contract.is_empty() {
+            return Err(ChainError::Other("raw_data has no contract".to_string()));
+        }
+        if let Some(tx_id) = tx.get("txID").and_then(|v| v.as_str())
+            && !tx_id.eq_ignore_ascii_case(&hex::encode(raw_data.txid()))
+        {
+            return Err(ChainError::Other(format!(
+                "txID {} does not match raw_data_hex",
+                tx_id
+            )));
+        }
+        Ok(raw_data)
+    }
+
+    /// `address` in the EIP-55 `0x` form that Tron's Ethereum-compatible
+    /// JSON-RPC and Solidity tooling use for the same account.
+    pub fn to_evm_address(&self, address: &str) -> Result<String, ChainError> {
+        self.validate_address(address)?;
+        let body = base58check_decode_versioned(address, self.address_prefix, 20)?;
+        Ok(to_checksum_address(&hex::encode(body)))
+    }
+
+    /// Base58 form of a `0x` address. Mixed case must match EIP-55, which
+    /// catches most mistyped characters.
+    pub fn from_evm_address(&self, address: &str) -> Result<String, ChainError> {
+        let invalid = |reason: &str| {
+            ChainError::InvalidAddress(format!("{} ({}): {}", address, self.name, reason))
+        };
+        let body = address
+            .strip_prefix("0x")
+            .ok_or_else(|| invalid("missing 0x prefix"))?;
+        if body.len() != 40 {
+            return Err(invalid("expected 40 hex characters"));
+        }
+        if is_mixed_case(body) && !is_checksum_address(address) {
+            return Err(invalid("bad checksum"));
+        }
+        self.hex_to_address(body)
+            .ok_or_else(|| invalid("expected 40 hex characters"))
+    }
+
+    /// Base58 address from a 20-byte (EVM-style) or 21-byte (prefixed) hex address.
+    fn hex_to_address(&self, hex_address: &str) -> Option<String> {
+        let bytes = hex::decode(hex_address.strip_prefix("0x").unwrap_or(hex_address)).ok()?;
+        match bytes.len() {
+            20 => {
+                let mut payload = [0u8; 21];
+                payload[0] = self.address_prefix;
+                payload[1..].copy_from_slice(&bytes);
+                Some(base58check_encode(&payload))
+            }
+            21 if bytes[0] == self.address_prefix => Some(base58check_encode(&bytes)),
+            _ => None,
+        }
+    }
+
+    fn decode_transfer_log(&self, log: &serde_json::Value) -> Option<TokenTransfer> {
+        let topics = log.get("topics")?.as_array()?;
+        if topics.len() != 3 || topics[0].as_str()? != TRANSFER_EVENT_TOPIC {
+            return None;
+        }
+        let data = hex::decode(log.get("data")?.as_str()?).ok()?;
+        Some(TokenTransfer {
+            token: self.hex_to_address(log.get("address")?.as_str()?)?,
+            from: self.hex_to_address(topic_address(&topics[1])?)?,
+            to: self.hex_to_address(topic_address(&topics[2])?)?,
+            value: be_bytes_to_decimal(data.get(..32)?),
+        })
+    }
+}
+
+/// Hex of the `r || s || v` form of `signature` Tron nodes verify.
+fn tron_signature(signature: &[u8], tx_id: &[u8; 32], pubkey: &[u8]) -> Result<String, ChainError> {
+    let (signature, parity) = parse_recoverable_signature(signature, tx_id, pubkey)?;
+    let mut bytes = signature.to_bytes().to_vec();
+    bytes.push(parity + 27);
+    Ok(hex::encode(bytes))
+}
+
+/// The first contract of a transaction, with addresses and call data as hex.
+struct ContractFields {
+    kind: String,
+    owner_address: Option<String>,
+    contract_address: Option<String>,
+    data: Option<String>,
+}
+
+impl ContractFields {
+    /// From the signed protobuf bytes, which are authoritative.
+    fn from_raw_data_hex(raw_data_hex: &str) -> Result<Self, ChainError> {
+        let bytes = hex::decode(raw_data_hex)
+            .map_err(|e| ChainError::Other(format!("Invalid hex: {}", e)))?;
+        let raw_data = TransactionRaw::decode(bytes.as_slice())
+            .map_err(|e| ChainError::Other(format!("Invalid raw_data: {}", e)))?;
+        let contract = raw_data
+            .contract
+            .first()
+            .ok_or_else(|| ChainError::Other("raw_data has no contract".to_string()))?;
+
+        let mut fields = Self {
+            kind: contract
+                .kind()
+                .map(|k| k.as_str_name().to_string())
+                .unwrap_or_else(|| format!("ContractType({})", contract.r#type)),
+            owner_address: None,
+            contract_address: None,
+            data: None,
+        };
+        match contract.kind() {
+            Some(ContractType::TriggerSmartContract) => {
+                if let Some(call) = contract.unpack::<TriggerSmartContract>() {
+                    fields.owner_address = Some(hex::encode(call.owner_address));
+                    fields.contract_address = Some(hex::encode(call.contract_address));
+                    fields.data = Some(hex::encode(call.data));
+                }
+            }
+            Some(ContractType::TransferContract) => {
+                fields.owner_address = contract
+                    .unpack::<proto::TransferContract>()
+                    .map(|t| hex::encode(t.owner_address));
+            }
+            _ => {}
+        }
+        Ok(fields)
+    }
+
+    /// From TronGrid's JSON rendering, for entries without `raw_data_hex`.
+    fn from_json(contract: &serde_json::Value) -> Self {
+        let value = contract.pointer("/parameter/value");
+        let field = |name: &str| {
+            value
+                .and_then(|v| v.get(name))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            kind: contract
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            owner_address: field("owner_address"),
+            contract_address: field("contract_address"),
+            data: field("data"),
+        }
+    }
+}
+
+/// Address held in the low 20 bytes of an indexed event topic.
+fn topic_address(topic: &serde_json::Value) -> Option<&str> {
+    topic.as_str().filter(|s| s.len() == 64).map(|s| &s[24..])
+}
+
+/// Recipient (20-byte hex) and amount of a `transfer(address,uint256)` call.
+fn decode_transfer_call(data: &str) -> Option<(String, String)> {
+    let body = data.strip_prefix(TRANSFER_SELECTOR)?;
+    let bytes = hex::decode(body).ok()?;
+    if bytes.len() != 64 {
+        return None;
+    }
+    Some((
+        hex::encode(&bytes[12..32]),
+        be_bytes_to_decimal(&bytes[32..]),
+    ))
+}
+
+/// Tron Mainnet configuration.
+pub const TRON: TvmChain = TvmChain {
+    name: "tron",
+    address_prefix: 0x41,
+    network: Network::Mainnet,
+    symbol: "TRX",
+    explorer: Some(Explorer::new(
+        "https://tronscan.org/#/transaction/{}",
+        "https://tronscan.org/#/address/{}",
+    )),
+};
+
+/// Tron's Nile testnet: mainnet addresses, test TRX.
+pub const TRON_NILE: TvmChain = TvmChain {
+    name: "tron-nile",
+    address_prefix: 0x41,
+    network: Network::Testnet,
+    symbol: "TRX",
+    explorer: Some(Explorer::new(
+        "https://nile.tronscan.org/#/transaction/{}",
+        "https://nile.tronscan.org/#/address/{}",
+    )),
+};
+
+/// Tron's Shasta testnet: mainnet addresses, test TRX.
+pub const TRON_SHASTA: TvmChain = TvmChain {
+    name: "tron-shasta",
+    address_prefix: 0x41,
+    network: Network::Testnet,
+    symbol: "TRX",
+    explorer: Some(Explorer::new(
+        "https://shasta.tronscan.org/#/transaction/{}",
+        "https://shasta.tronscan.org/#/address/{}",
+    )),
+};
+
+/// Derive TVM base58check address from a compressed SEC1 public key.
+pub fn tvm_address_from_pubkey(pubkey_sec1: &[u8], prefix: u8) -> Result<String, ChainError> {
+    // Uncompressed SEC1: 0x04 || X(32) || Y(32)
+    let uncompressed = sec1_uncompressed(pubkey_sec1)?;
+
+    let keccak = keccak256(&uncompressed[1..]);
+    let last20 = &keccak[keccak.len() - 20..];
+
+    // Tron base58check: prefix (e.g. 0x41) + 20-byte payload, double SHA256 checksum (first 4 bytes)
+    let mut payload = [0u8; 21];
+    payload[0] = prefix;
+    payload[1..].copy_from_slice(last20);
+
+    Ok(base58check_encode(&payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[test]
+    fn tron_address_matches_known_vector() {
+        // Deterministic secret for test.
+        let sk = [1u8; 32];
+        let signer = LocalSigner::from_bytes(sk).expect("key");
+        let pk = signer.public_key();
+
+        // Tron prefix 0x41
+        let addr = tvm_address_from_pubkey(&pk, 0x41).expect("addr");
+        // Precomputed once via same algorithm; guards against regressions.
+        assert_eq!(addr, "TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7");
+
+        // Via chain object
+        let chain = TRON;
+        let addr2 = chain.address_from_pubkey(&pk).unwrap();
+        assert_eq!(addr, addr2);
+    }
+
+    #[test]
+    fn validate_address_checks_checksum_and_prefix() {
+        assert!(
+            TRON.validate_address("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7")
+                .is_ok()
+        );
+        // Flipped last character breaks the checksum.
+        assert!(
+            TRON.validate_address("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr8")
+                .is_err()
+        );
+        // Valid base58check, but a Litecoin version byte.
+        assert!(
+            TRON.validate_address("LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn converts_to_and_from_checksummed_evm_addresses() {
+        let base58 = "TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7";
+        let evm = TRON.to_evm_address(base58).unwrap();
+        assert!(is_checksum_address(&evm));
+        assert_eq!(TRON.from_evm_address(&evm).unwrap(), base58);
+        assert_eq!(TRON.from_evm_address(&evm.to_lowercase()).unwrap(), base58);
+
+        // Swapping the case of one letter breaks the checksum.
+        let i = evm.find(|c: char| c.is_ascii_uppercase()).unwrap();
+        let mut corrupted = evm.clone();
+        corrupted.replace_range(i..=i, &evm[i..=i].to_lowercase());
+        assert!(TRON.from_evm_address(&corrupted).is_err());
+    }
+
+    #[test]
+    fn prepare_rejects_non_json_transactions() {
+        let err = TRON
+            .prepare_transaction(&RawTransaction::Bytes(vec![0x0a]))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ChainError::Other("tron expects a JSON transaction, got bytes".into())
+        );
+    }
+
+    #[test]
+    fn prepare_checks_raw_data_against_txid() {
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract::default(),
+            )],
+            ..Default::default()
+        };
+        let raw_data_hex = hex::encode(raw_data.encode_to_vec());
+        let tx = |tx_id: String| {
+            RawTransaction::Json(serde_json::json!({ "txID": tx_id, "raw_data_hex": raw_data_hex }))
+        };
+
+        assert!(
+            TRON.prepare_transaction(&tx(hex::encode(raw_data.txid())))
+                .is_ok()
+        );
+        assert!(TRON.prepare_transaction(&tx("00".repeat(32))).is_err());
+        let garbage = RawTransaction::Json(serde_json::json!({ "raw_data_hex": "ffff" }));
+        assert!(TRON.prepare_transaction(&garbage).is_err());
+    }
+
+    #[test]
+    fn memos_are_part_of_what_is_signed() {
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract::default(),
+            )],
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "txID": hex::encode(raw_data.txid()),
+            "raw_data": {},
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+        let with_memo = TRON.apply_memo(&raw, b"12345").unwrap();
+        assert_eq!(
+            with_memo.as_json().unwrap()["raw_data"]["data"],
+            "3132333435"
+        );
+        let signed_data = TRON.raw_data(&with_memo).unwrap();
+        assert_eq!(signed_data.data, b"12345");
+        assert_ne!(signed_data.txid(), raw_data.txid());
+    }
+
+    #[tokio::test]
+    async fn expired_sends_are_rebuilt_with_a_longer_expiration() {
+        use crate::node::NodeError;
+        use crate::testing::{MockCall, MockProvider, MockSigner};
+        use crate::wallet::{SendOptions, Wallet};
+
+        let build = |to: &str, amount: u64| {
+            let mut recipient = vec![0x41];
+            recipient.extend(base58check_decode_versioned(to, 0x41, 20).unwrap());
+            let raw_data = TransactionRaw {
+                contract: vec![proto::Contract::pack(
+                    ContractType::TransferContract,
+                    &proto::TransferContract {
+                        owner_address: Vec::new(),
+                        to_address: recipient,
+                        amount: amount as i64,
+                    },
+                )],
+                timestamp: 1_700_000_000_000,
+                expiration: 1_700_000_060_000,
+                ..Default::default()
+            };
+            RawTransaction::Json(serde_json::json!({
+                "txID": hex::encode(raw_data.txid()),
+                "raw_data": { "expiration": raw_data.expiration },
+                "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+            }))
+        };
+        let to = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let raw = build(to, 1);
+        let extended = TRON
+            .extend_expiration(&raw, Duration::from_secs(600))
+            .unwrap();
+        assert_eq!(
+            TRON.raw_data(&extended).unwrap().expiration,
+            1_700_000_600_000
+        );
+        assert_eq!(
+            extended.as_json().unwrap()["raw_data"]["expiration"],
+            1_700_000_600_000i64
+        );
+        assert_ne!(
+            extended.as_json().unwrap()["txID"],
+            raw.as_json().unwrap()["txID"]
+        );
+        assert!(
+            TRON.extend_expiration(&raw, Duration::from_secs(25 * 3600))
+                .is_err()
+        );
+
+        let signer = MockSigner::default();
+        let wallet = Wallet::new(signer.clone(), TRON);
+        let provider = MockProvider::new(6).on_create(move |_, to, amount| Ok(build(to, amount)));
+        provider.set_balance(&wallet.address().unwrap(), 10_000_000);
+        provider.fail_next(
+            MockCall::BroadcastTransaction,
+            NodeError::Api("Broadcast failed: {\"code\":\"TRANSACTION_EXPIRATION_ERROR\"}".into()),
+        );
+        let options = SendOptions {
+            expiration: Some(Duration::from_secs(600)),
+            ..SendOptions::default()
+        };
+        wallet
+            .send_coins_with_options(&provider, to, 1_500_000, &options)
+            .await
+            .unwrap();
+        assert_eq!(provider.calls(MockCall::BroadcastTransaction), 2);
+        assert_eq!(signer.requests().len(), 2);
+        let [sent] = provider.broadcasts().try_into().unwrap();
+        assert_eq!(TRON.raw_data(&sent).unwrap().expiration, 1_700_000_600_000);
+    }
+
+    #[tokio::test]
+    async fn cosigners_sign_under_an_active_permission() {
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract::default(),
+            )],
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "txID": hex::encode(raw_data.txid()),
+            "raw_data": { "contract": [{ "type": "TransferContract" }] },
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+        let raw = TRON.apply_permission(&raw, 2).unwrap();
+        let tx = raw.as_json().unwrap();
+        assert_eq!(tx["raw_data"]["contract"][0]["Permission_id"], 2);
+        let [payload] = TRON.prepare_transaction(&raw).unwrap().try_into().unwrap();
+        assert_ne!(payload, raw_data.txid().to_vec());
+
+        let owner = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let cosigner = LocalSigner::from_bytes([2u8; 32]).unwrap();
+        let own = owner.sign_recoverable(&payload).await.unwrap();
+        let other = cosigner.sign_recoverable(&payload).await.unwrap();
+        let signed = TRON
+            .finalize_transaction(&raw, &[own.clone(), other], &owner.public_key())
+            .unwrap();
+        assert_eq!(
+            signed.as_json().unwrap()["signature"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(TRON.apply_permission(&signed, 3).is_err());
+        // One key cannot count twice towards the threshold.
+        assert!(
+            TRON.finalize_transaction(&raw, &[own.clone(), own], &owner.public_key())
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn finalize_produces_recoverable_65_byte_signature() {
+        use crate::wallet::verify::{Signed, SignedBy, verify};
+
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract::default(),
+            )],
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+        let signer = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let pubkey = signer.public_key();
+
+        let payloads = TRON.prepare_transaction(&raw).unwrap();
+        assert_eq!(payloads, vec![raw_data.txid().to_vec()]);
+        let der = signer.sign_prehash(&payloads[0]).await.unwrap();
+        let signed = TRON.finalize_transaction(&raw, &[der], &pubkey).unwrap();
+
+        let tx = signed.as_json().unwrap();
+        assert_eq!(tx["txID"], hex::encode(raw_data.txid()));
+        let sig = hex::decode(tx["signature"][0].as_str().unwrap()).unwrap();
+        assert_eq!(sig.len(), 65);
+        assert!(matches!(sig[64], 27 | 28));
+        let address = TRON.address_from_pubkey(&pubkey).unwrap();
+        assert!(
+            verify(
+                &TRON,
+                SignedBy::Address(&address),
+                Signed::Prehash(&raw_data.txid()),
+                &sig
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn decodes_trc20_transfer_from_call_data() {
+        let raw = serde_json::json!({
+            "ret": [{ "contractRet": "SUCCESS", "fee": 345000 }],
+            "energy_usage_total": 14650,
+            "raw_data": { "contract": [{
+                "type": "TriggerSmartContract",
+                "parameter": { "value": {
+                    "owner_address": "411ad6c1b6bcb2b0e5ba0bb4e67e1c12e8c0f5d21b",
+                    "contract_address": "41a614f803b6fd780986a42c78ec9c7f77e6ded13c",
+                    "data": "a9059cbb000000000000000000000000a614f803b6fd780986a42c78ec9c7f77e6ded13c00000000000000000000000000000000000000000000000000000000000f4240"
+                }}
+            }]}
+        });
+        let tx = Transaction {
+            hash: "h".into(),
+            from: String::new(),
+            to: String::new(),
+            value: "0".into(),
+            block_number: 1,
+            timestamp: 0,
+            status: "SUCCESS".into(),
+            raw: Some(raw),
+            details: None,
+        };
+
+        let Some(TxDetails::Tvm(details)) = TRON.decode_provider_tx(&tx).unwrap() else {
+            panic!("expected TVM details");
+        };
+        assert_eq!(details.contract_type, "TriggerSmartContract");
+        assert_eq!(details.fee, 345000);
+        assert_eq!(details.energy_usage, Some(14650));
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        assert_eq!(details.contract_address.as_deref(), Some(usdt));
+        assert_eq!(details.token_transfers.len(), 1);
+        assert_eq!(details.token_transfers[0].token, usdt);
+        assert_eq!(details.token_transfers[0].to, usdt);
+        assert_eq!(details.token_transfers[0].value, "1000000");
+    }
+
+    #[test]
+    fn decodes_what_a_trc20_transfer_would_sign() {
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let mut call_data = hex::decode(TRANSFER_SELECTOR).unwrap();
+        call_data.extend_from_slice(&[0; 12]);
+        call_data.extend_from_slice(&base58check_decode_versioned(to, 0x41, 20).unwrap());
+        call_data.extend_from_slice(&[0; 28]);
+        call_data.extend_from_slice(&2_500_000u32.to_be_bytes());
+        let tron_address = |address: &str| {
+            let mut bytes = vec![0x41];
+            bytes.extend_from_slice(&base58check_decode_versioned(address, 0x41, 20).unwrap());
+            bytes
+        };
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TriggerSmartContract,
+                &TriggerSmartContract {
+                    owner_address: tron_address(to),
+                    contract_address: tron_address(usdt),
+                    call_value: 0,
+                    data: call_data,
+                },
+            )],
+            data: b"deposit 42".to_vec(),
+            fee_limit: 30_000_000,
+            expiration: 1_700_000_060_000,
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+
+        let DecodedTransaction::Tvm(decoded) = TRON.decode_transaction(&raw).unwrap() else {
+            panic!("expected a TVM transaction");
+        };
+        assert_eq!(decoded.contract_type, "TriggerSmartContract");
+        assert_eq!(decoded.owner.as_deref(), Some(to));
+        assert_eq!(decoded.to.as_deref(), Some(to));
+        assert_eq!(decoded.amount, Some(2_500_000));
+        assert_eq!(decoded.token.as_deref(), Some(usdt));
+        assert_eq!(decoded.fee_limit, 30_000_000);
+        assert_eq!(decoded.memo.as_deref(), Some("deposit 42"));
+        assert_eq!(decoded.expiration, 1_700_000_060_000);
+    }
+
+    #[test]
+    fn capped_prepare_checks_what_the_provider_built() {
+        use crate::wallet::chain::TxCaps;
+
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let to_address = base58check_decode_versioned(usdt, 0x41, 20).unwrap();
+        let mut recipient = vec![0x41];
+        recipient.extend_from_slice(&to_address);
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract {
+                    owner_address: Vec::new(),
+                    to_address: recipient,
+                    amount: 1_500_000,
+                },
+            )],
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+
+        let caps = TxCaps::new().recipient(usdt).amount(1_500_000).max_fee(1);
+        assert!(TRON.prepare_transaction_capped(&raw, &caps).is_ok());
+        assert!(matches!(
+            TRON.prepare_transaction_capped(&raw, &caps.clone().amount(1_000_000)),
+            Err(ChainError::CapViolation(_))
+        ));
+        assert!(matches!(
+            TRON.prepare_transaction_capped(&raw, &caps.token(usdt)),
+            Err(ChainError::CapViolation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn finalize_accepts_recoverable_and_der_signatures() {
+        use crate::wallet::signer::recoverable_from_der;
+
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract::default(),
+            )],
+            ..Default::default()
+        };
+        let raw = RawTransaction::Json(serde_json::json!({
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+        let signer = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let pubkey = signer.public_key();
+        let prehash = TRON.prepare_transaction(&raw).unwrap().remove(0);
+
+        let recoverable = signer.sign_recoverable(&prehash).await.unwrap();
+        let der = signer.sign_prehash(&prehash).await.unwrap();
+        assert_eq!(recoverable.len(), 65);
+        assert_eq!(
+            recoverable_from_der(&prehash, &der, &pubkey).unwrap(),
+            recoverable
+        );
+        assert_eq!(
+            TRON.finalize_transaction(&raw, std::slice::from_ref(&recoverable), &pubkey),
+            TRON.finalize_transaction(&raw, &[der], &pubkey)
+        );
+
+        let other = LocalSigner::from_bytes([2u8; 32]).unwrap().public_key();
+        assert!(
+            TRON.finalize_transaction(&raw, &[recoverable], &other)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,1233 @@
+//! Local construction and sighash computation for UTXO transactions.
+//!
+//! Lets the wallet sign digests it computed itself instead of whatever a REST
+//! backend put in `tosign`. Spends P2PKH (legacy sighash), P2WPKH (BIP-143)
+//! and Taproot key-path (BIP-341) outputs, with `SIGHASH_ALL` unless an input
+//! asks for another [`SighashType`].
+
+use serde_json::{Value, json};
+
+use super::UtxoChain;
+use super::selection::{CoinSelection, SelectionTarget};
+use crate::node::RawTransaction;
+use crate::wallet::chain::ChainError;
+use crate::wallet::chain::sdk::{
+    double_sha256, hash160, parse_der_signature, read_compact_size, sec1_compressed, sha256,
+    tagged_hash, taproot_output_key, write_compact_size, write_var_bytes,
+};
+
+pub(super) const DEFAULT_SEQUENCE: u32 = 0xffff_ffff;
+/// Highest input sequence that opts a transaction into replacement (BIP-125).
+pub const RBF_SEQUENCE: u32 = 0xffff_fffd;
+/// Most bytes an `OP_RETURN` output may carry and still be relayed.
+pub const MAX_OP_RETURN: usize = 80;
+/// Relay fee rate nodes require a replacement to pay on top of the fee it
+/// replaces, in base units per kilobyte.
+pub const MIN_RELAY_FEE_PER_KB: u64 = 1_000;
+/// Change below this many base units is left to the fee instead of creating an output.
+pub const DUST_LIMIT: u64 = 546;
+
+/// An unspent output being spent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoInput {
+    /// Id of the funding transaction, in the byte order explorers display.
+    pub prev_hash: [u8; 32],
+    pub output_index: u32,
+    pub value: u64,
+    /// Locking script of the output; P2PKH, P2WPKH and P2TR are supported,
+    /// and multisig P2SH and P2WSH through [`MultisigSpend`](super::multisig::MultisigSpend).
+    pub script_pubkey: Vec<u8>,
+    pub sequence: u32,
+    /// Which parts of the transaction this input's signature commits to.
+    pub sighash: SighashType,
+}
+
+/// Signature hash type of one input.
+///
+/// Anything but [`All`](Self::All) lets others change parts of the
+/// transaction after signing; see [`UnsignedUtxoTx::sighash_warnings`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SighashType {
+    /// Commit to every input and output.
+    #[default]
+    All,
+    /// Commit to no outputs: whoever relays the transaction can redirect the funds.
+    None,
+    /// Commit only to the output at the input's own index.
+    Single,
+    /// `ALL`, but other parties may add inputs (crowdfunding, coinjoin contributions).
+    AllAnyoneCanPay,
+    NoneAnyoneCanPay,
+    /// `SINGLE`, but other parties may add inputs (atomic swap offers).
+    SingleAnyoneCanPay,
+}
+
+const ANYONECANPAY: u8 = 0x80;
+
+impl SighashType {
+    /// The byte appended to signatures and hashed into the preimage.
+    pub fn byte(self) -> u8 {
+        match self {
+            SighashType::All => 0x01,
+            SighashType::None => 0x02,
+            SighashType::Single => 0x03,
+            SighashType::AllAnyoneCanPay => 0x01 | ANYONECANPAY,
+            SighashType::NoneAnyoneCanPay => 0x02 | ANYONECANPAY,
+            SighashType::SingleAnyoneCanPay => 0x03 | ANYONECANPAY,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, ChainError> {
+        Ok(match byte {
+            0x01 => SighashType::All,
+            0x02 => SighashType::None,
+            0x03 => SighashType::Single,
+            0x81 => SighashType::AllAnyoneCanPay,
+            0x82 => SighashType::NoneAnyoneCanPay,
+            0x83 => SighashType::SingleAnyoneCanPay,
+            _ => {
+                return Err(ChainError::Other(format!(
+                    "unsupported sighash type 0x{:02x}",
+                    byte
+                )));
+            }
+        })
+    }
+
+    fn anyone_can_pay(self) -> bool {
+        self.byte() & ANYONECANPAY != 0
+    }
+
+    pub(super) fn base(self) -> u8 {
+        self.byte() & !ANYONECANPAY
+    }
+
+    /// The byte a Taproot signature commits to: `SIGHASH_DEFAULT` (0x00) for
+    /// [`All`](Self::All), which keeps the signature at 64 bytes.
+    fn taproot_byte(self) -> u8 {
+        match self {
+            SighashType::All => 0x00,
+            other => other.byte(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoOutput {
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// A transaction whose inputs and outputs are fully known, ready to be signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedUtxoTx {
+    pub version: u32,
+    pub inputs: Vec<UtxoInput>,
+    pub outputs: Vec<UtxoOutput>,
+    pub lock_time: u32,
+}
+
+enum SpendKind {
+    P2pkh,
+    P2wpkh,
+    /// Taproot, spent by key path.
+    P2tr,
+}
+
+impl UtxoInput {
+    /// Sign this input with `sighash` instead of `SIGHASH_ALL`.
+    pub fn with_sighash(mut self, sighash: SighashType) -> Self {
+        self.sighash = sighash;
+        self
+    }
+
+    /// Virtual size this input adds once signed with a compressed key.
+    pub(super) fn vsize(&self) -> Result<u64, ChainError> {
+        Ok(match self.kind()? {
+            SpendKind::P2pkh => 148,
+            SpendKind::P2wpkh => 68,
+            SpendKind::P2tr => 58,
+        })
+    }
+
+    fn kind(&self) -> Result<SpendKind, ChainError> {
+        match self.script_pubkey.as_slice() {
+            [0x76, 0xa9, 0x14, .., 0x88, 0xac] if self.script_pubkey.len() == 25 => {
+                Ok(SpendKind::P2pkh)
+            }
+            [0x00, 0x14, ..] if self.script_pubkey.len() == 22 => Ok(SpendKind::P2wpkh),
+            [0x51, 0x20, ..] if self.script_pubkey.len() == 34 => Ok(SpendKind::P2tr),
+            _ => Err(ChainError::Other(format!(
+                "unsupported input script {}",
+                hex::encode(&self.script_pubkey)
+            ))),
+        }
+    }
+
+    /// The key hash, or for Taproot the x-only output key, the locking
+    /// script commits to.
+    fn key_commitment(&self) -> Result<&[u8], ChainError> {
+        Ok(match self.kind()? {
+            SpendKind::P2pkh => &self.script_pubkey[3..23],
+            SpendKind::P2wpkh => &self.script_pubkey[2..22],
+            SpendKind::P2tr => &self.script_pubkey[2..34],
+        })
+    }
+
+    fn write_outpoint(&self, out: &mut Vec<u8>) {
+        out.extend(self.prev_hash.iter().rev());
+        out.extend_from_slice(&self.output_index.to_le_bytes());
+    }
+}
+
+impl UtxoOutput {
+    /// Unspendable zero-value output carrying `data`, e.g. a memo.
+    pub fn op_return(data: &[u8]) -> Result<Self, ChainError> {
+        let mut script_pubkey = vec![0x6a];
+        match data.len() {
+            0..=75 => script_pubkey.push(data.len() as u8),
+            76..=MAX_OP_RETURN => script_pubkey.extend_from_slice(&[0x4c, data.len() as u8]),
+            len => {
+                return Err(ChainError::Other(format!(
+                    "OP_RETURN data is {} bytes; at most {} are relayed",
+                    len, MAX_OP_RETURN
+                )));
+            }
+        }
+        script_pubkey.extend_from_slice(data);
+        Ok(Self {
+            value: 0,
+            script_pubkey,
+        })
+    }
+
+    /// Data an `OP_RETURN` output carries, if this is one.
+    pub fn op_return_data(&self) -> Option<&[u8]> {
+        match self.script_pubkey.as_slice() {
+            [0x6a] => Some(&[]),
+            [0x6a, 0x4c, len, data @ ..] | [0x6a, len, data @ ..]
+                if *len as usize == data.len() =>
+            {
+                Some(data)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this is a zero-value data carrier rather than a payment.
+    pub fn is_data(&self) -> bool {
+        self.value == 0 && self.script_pubkey.first() == Some(&0x6a)
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value.to_le_bytes());
+        write_var_bytes(out, &self.script_pubkey);
+    }
+}
+
+impl UnsignedUtxoTx {
+    /// Whether nodes let a higher-fee transaction spending the same coins
+    /// replace this one: true if any input signals it.
+    pub fn signals_rbf(&self) -> bool {
+        self.inputs.iter().any(|i| i.sequence <= RBF_SEQUENCE)
+    }
+
+    /// Opt every input into replacement, keeping lower sequences (relative
+    /// lock times) as they are.
+    pub fn signal_rbf(&mut self) {
+        for input in &mut self.inputs {
+            input.sequence = input.sequence.min(RBF_SEQUENCE);
+        }
+    }
+
+    /// What the inputs bring in minus what the outputs pay out.
+    pub fn fee(&self) -> Result<u64, ChainError> {
+        let spent: u64 = self.inputs.iter().map(|i| i.value).sum();
+        let paid: u64 = self.outputs.iter().map(|o| o.value).sum();
+        spent.checked_sub(paid).ok_or_else(|| {
+            ChainError::Other(format!("outputs pay {} from inputs of {}", paid, spent))
+        })
+    }
+
+    /// Virtual size once signed, in bytes, assuming one compressed key per input.
+    pub fn estimated_vsize(&self) -> Result<u64, ChainError> {
+        let mut segwit = false;
+        let mut size = 10;
+        for input in &self.inputs {
+            segwit |= matches!(input.kind()?, SpendKind::P2wpkh | SpendKind::P2tr);
+            size += input.vsize()?;
+        }
+        for output in &self.outputs {
+            size += 9 + output.script_pubkey.len() as u64;
+        }
+        Ok(size + segwit as u64)
+    }
+
+    /// A replacement (BIP-125) of this transaction paying `fee_rate` base
+    /// units per kilobyte. It spends the same coins to the same outputs; the
+    /// extra fee comes out of the output to `change_script`, which is dropped
+    /// if what remains of it would be dust.
+    pub fn replace_by_fee(&self, change_script: &[u8], fee_rate: u64) -> Result<Self, ChainError> {
+        if !self.signals_rbf() {
+            return Err(ChainError::Other(
+                "transaction does not signal replace-by-fee".to_string(),
+            ));
+        }
+        let old_fee = self.fee()?;
+        let change = self
+            .outputs
+            .iter()
+            .rposition(|o| o.script_pubkey == change_script)
+            .ok_or_else(|| {
+                ChainError::Other("no change output to pay the higher fee from".to_string())
+            })?;
+        let vsize = self.estimated_vsize()?;
+        let fee = fee_rate.saturating_mul(vsize) / 1000;
+        let minimum = old_fee + MIN_RELAY_FEE_PER_KB * vsize / 1000;
+        if fee < minimum {
+            return Err(ChainError::Other(format!(
+                "a fee rate of {} pays {}, but replacing a fee of {} needs at least {}",
+                fee_rate, fee, old_fee, minimum
+            )));
+        }
+
+        let mut replacement = self.clone();
+        let available = replacement.outputs[change].value;
+        let remaining = available.checked_sub(fee - old_fee).ok_or_else(|| {
+            ChainError::Other(format!(
+                "change of {} cannot cover {} more fee",
+                available,
+                fee - old_fee
+            ))
+        })?;
+        if remaining >= DUST_LIMIT {
+            replacement.outputs[change].value = remaining;
+        } else if replacement.outputs.len() > 1 {
+            replacement.outputs.remove(change);
+        } else {
+            return Err(ChainError::Other(
+                "the higher fee would consume the only output".to_string(),
+            ));
+        }
+        replacement.signal_rbf();
+        Ok(replacement)
+    }
+
+    /// Digest for every input under its [`SighashType`], in input order.
+    ///
+    /// `SINGLE` on an input without an output at the same index is refused
+    /// rather than signing the constant legacy nodes substitute for it.
+    pub fn sighashes(&self) -> Result<Vec<[u8; 32]>, ChainError> {
+        let mut segwit_hashes = None;
+        let mut taproot_hashes = None;
+        (0..self.inputs.len())
+            .map(|i| {
+                let sighash = self.inputs[i].sighash;
+                if sighash.base() == SighashType::Single.byte() && i >= self.outputs.len() {
+                    return Err(ChainError::Other(format!(
+                        "input {} uses SIGHASH_SINGLE but there is no output {}",
+                        i, i
+                    )));
+                }
+                match self.inputs[i].kind()? {
+                    SpendKind::P2pkh => Ok(self.legacy_digest(i, &self.inputs[i].script_pubkey)),
+                    SpendKind::P2wpkh => {
+                        let shared = segwit_hashes.get_or_insert_with(|| self.bip143_shared());
+                        Ok(self.segwit_v0_sighash(i, shared))
+                    }
+                    SpendKind::P2tr => {
+                        let shared = taproot_hashes.get_or_insert_with(|| self.bip341_shared());
+                        Ok(self.taproot_sighash(i, shared))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Inputs whose sighash type leaves part of the transaction open to change.
+    pub fn sighash_warnings(&self) -> Vec<String> {
+        self.inputs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, input)| {
+                let risk = match input.sighash {
+                    SighashType::All => return None,
+                    SighashType::None | SighashType::NoneAnyoneCanPay => {
+                        "commits to no outputs, so anyone can redirect its value"
+                    }
+                    SighashType::Single | SighashType::SingleAnyoneCanPay => {
+                        "commits to one output only; the rest can be changed"
+                    }
+                    SighashType::AllAnyoneCanPay => "lets others add inputs",
+                };
+                Some(format!("input {} ({:?}) {}", i, input.sighash, risk))
+            })
+            .collect()
+    }
+
+    /// Pre-SegWit digest: the transaction with only input `index` carrying
+    /// `script_code` (its prevout script, or a P2SH redeem script), trimmed as
+    /// its sighash type asks.
+    pub(super) fn legacy_digest(&self, index: usize, script_code: &[u8]) -> [u8; 32] {
+        let sighash = self.inputs[index].sighash;
+        let base = sighash.base();
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&self.version.to_le_bytes());
+        let signed: Vec<usize> = if sighash.anyone_can_pay() {
+            vec![index]
+        } else {
+            (0..self.inputs.len()).collect()
+        };
+        write_compact_size(&mut preimage, signed.len() as u64);
+        for i in signed {
+            let input = &self.inputs[i];
+            input.write_outpoint(&mut preimage);
+            let script: &[u8] = if i == index { script_code } else { &[] };
+            write_var_bytes(&mut preimage, script);
+            // NONE and SINGLE let the other inputs be replaced.
+            let sequence = if i != index && base != SighashType::All.byte() {
+                0
+            } else {
+                input.sequence
+            };
+            preimage.extend_from_slice(&sequence.to_le_bytes());
+        }
+        match base {
+            0x02 => write_compact_size(&mut preimage, 0),
+            0x03 => {
+                write_compact_size(&mut preimage, index as u64 + 1);
+                for _ in 0..index {
+                    // Blank outputs: value -1 and an empty script.
+                    preimage.extend_from_slice(&u64::MAX.to_le_bytes());
+                    write_var_bytes(&mut preimage, &[]);
+                }
+                self.outputs[index].write(&mut preimage);
+            }
+            _ => self.write_outputs(&mut preimage),
+        }
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&(sighash.byte() as u32).to_le_bytes());
+        double_sha256(&preimage)
+    }
+
+    /// `hashPrevouts`, `hashSequence` and `hashOutputs` as `SIGHASH_ALL` uses
+    /// them, common to every BIP-143 input.
+    pub(super) fn bip143_shared(&self) -> [[u8; 32]; 3] {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in &self.inputs {
+            input.write_outpoint(&mut prevouts);
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        let mut outputs = Vec::new();
+        for output in &self.outputs {
+            output.write(&mut outputs);
+        }
+        [
+            double_sha256(&prevouts),
+            double_sha256(&sequences),
+            double_sha256(&outputs),
+        ]
+    }
+
+    /// BIP-143 digest for a P2WPKH input.
+    fn segwit_v0_sighash(&self, index: usize, shared: &[[u8; 32]; 3]) -> [u8; 32] {
+        // scriptCode of P2WPKH is the equivalent P2PKH script.
+        let mut script_code = vec![0x76, 0xa9, 0x14];
+        script_code.extend_from_slice(&self.inputs[index].script_pubkey[2..22]);
+        script_code.extend_from_slice(&[0x88, 0xac]);
+        self.segwit_v0_digest(index, shared, &script_code)
+    }
+
+    /// BIP-143 digest of input `index` executing `script_code`.
+    pub(super) fn segwit_v0_digest(
+        &self,
+        index: usize,
+        shared: &[[u8; 32]; 3],
+        script_code: &[u8],
+    ) -> [u8; 32] {
+        let [hash_prevouts, hash_sequence, hash_outputs] = shared;
+        let input = &self.inputs[index];
+        let sighash = input.sighash;
+        let base = sighash.base();
+        let zero = [0u8; 32];
+
+        let hash_prevouts = if sighash.anyone_can_pay() {
+            &zero
+        } else {
+            hash_prevouts
+        };
+        let hash_sequence = if sighash.anyone_can_pay() || base != SighashType::All.byte() {
+            &zero
+        } else {
+            hash_sequence
+        };
+        let single_output;
+        let hash_outputs = match base {
+            0x02 => &zero,
+            0x03 => {
+                let mut output = Vec::new();
+                self.outputs[index].write(&mut output);
+                single_output = double_sha256(&output);
+                &single_output
+            }
+            _ => hash_outputs,
+        };
+
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&self.version.to_le_bytes());
+        preimage.extend_from_slice(hash_prevouts);
+        preimage.extend_from_slice(hash_sequence);
+        input.write_outpoint(&mut preimage);
+        write_var_bytes(&mut preimage, script_code);
+        preimage.extend_from_slice(&input.value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(hash_outputs);
+        preimage.extend_from_slice(&self.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&(sighash.byte() as u32).to_le_bytes());
+        double_sha256(&preimage)
+    }
+
+    /// `sha_prevouts`, `sha_amounts`, `sha_scriptpubkeys`, `sha_sequences`
+    /// and `sha_outputs`, common to every BIP-341 input. Unlike BIP-143 they
+    /// commit to every input's amount and script, so a signer cannot be lied
+    /// to about the fee.
+    fn bip341_shared(&self) -> [[u8; 32]; 5] {
+        let mut prevouts = Vec::new();
+        let mut amounts = Vec::new();
+        let mut scripts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in &self.inputs {
+            input.write_outpoint(&mut prevouts);
+            amounts.extend_from_slice(&input.value.to_le_bytes());
+            write_var_bytes(&mut scripts, &input.script_pubkey);
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        let mut outputs = Vec::new();
+        for output in &self.outputs {
+            output.write(&mut outputs);
+        }
+        [
+            sha256(&prevouts),
+            sha256(&amounts),
+            sha256(&scripts),
+            sha256(&sequences),
+            sha256(&outputs),
+        ]
+    }
+
+    /// BIP-341 digest for a key-path spend of a P2TR input, without an annex.
+    fn taproot_sighash(&self, index: usize, shared: &[[u8; 32]; 5]) -> [u8; 32] {
+        let [prevouts, amounts, scripts, sequences, outputs] = shared;
+        let input = &self.inputs[index];
+        let sighash = input.sighash;
+        let base = sighash.base();
+
+        // Epoch 0, then the signature message.
+        let mut message = vec![0x00, sighash.taproot_byte()];
+        message.extend_from_slice(&self.version.to_le_bytes());
+        message.extend_from_slice(&self.lock_time.to_le_bytes());
+        if !sighash.anyone_can_pay() {
+            message.extend_from_slice(prevouts);
+            message.extend_from_slice(amounts);
+            message.extend_from_slice(scripts);
+            message.extend_from_slice(sequences);
+        }
+        if base != SighashType::None.byte() && base != SighashType::Single.byte() {
+            message.extend_from_slice(outputs);
+        }
+        // Key path, no annex.
+        message.push(0x00);
+        if sighash.anyone_can_pay() {
+            input.write_outpoint(&mut message);
+            message.extend_from_slice(&input.value.to_le_bytes());
+            write_var_bytes(&mut message, &input.script_pubkey);
+            message.extend_from_slice(&input.sequence.to_le_bytes());
+        } else {
+            message.extend_from_slice(&(index as u32).to_le_bytes());
+        }
+        if base == SighashType::Single.byte() {
+            let mut output = Vec::new();
+            self.outputs[index].write(&mut output);
+            message.extend_from_slice(&sha256(&output));
+        }
+        tagged_hash("TapSighash", &message)
+    }
+
+    fn write_outputs(&self, out: &mut Vec<u8>) {
+        write_compact_size(out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            output.write(out);
+        }
+    }
+
+    /// Serialize the signed transaction from one signature per input: DER,
+    /// or 64-byte Schnorr for Taproot inputs.
+    pub fn to_signed_bytes(
+        &self,
+        signatures: &[Vec<u8>],
+        pubkey: &[u8],
+    ) -> Result<Vec<u8>, ChainError> {
+        if signatures.len() != self.inputs.len() {
+            return Err(ChainError::Other(format!(
+                "Signature count mismatch: expected {}, got {}",
+                self.inputs.len(),
+                signatures.len()
+            )));
+        }
+        let pubkey = sec1_compressed(pubkey)?;
+        let key_hash = hash160(&pubkey);
+        let output_key = taproot_output_key(&pubkey)?;
+
+        let mut script_sigs = Vec::with_capacity(self.inputs.len());
+        let mut witnesses = Vec::with_capacity(self.inputs.len());
+        for (i, (input, signature)) in self.inputs.iter().zip(signatures).enumerate() {
+            let kind = input.kind()?;
+            let ours: &[u8] = match kind {
+                SpendKind::P2tr => &output_key,
+                _ => &key_hash,
+            };
+            if input.key_commitment()? != ours {
+                return Err(ChainError::Other(format!(
+                    "input {} is not spendable by this key",
+                    i
+                )));
+            }
+
+            match kind {
+                SpendKind::P2pkh | SpendKind::P2wpkh => {
+                    // Nodes reject high-s signatures as non-standard.
+                    let mut sig = parse_der_signature(signature)?.to_der().as_bytes().to_vec();
+                    sig.push(input.sighash.byte());
+                    if let SpendKind::P2pkh = kind {
+                        // Below 76 bytes the length prefix doubles as the push opcode.
+                        let mut script = Vec::with_capacity(sig.len() + pubkey.len() + 2);
+                        write_var_bytes(&mut script, &sig);
+                        write_var_bytes(&mut script, &pubkey);
+                        script_sigs.push(script);
+                        witnesses.push(vec![]);
+                    } else {
+                        script_sigs.push(vec![]);
+                        witnesses.push(vec![sig, pubkey.to_vec()]);
+                    }
+                }
+                SpendKind::P2tr => {
+                    if signature.len() != 64 {
+                        return Err(ChainError::Other(format!(
+                            "input {} needs a 64-byte Schnorr signature",
+                            i
+                        )));
+                    }
+                    let mut sig = signature.clone();
+                    if input.sighash != SighashType::All {
+                        sig.push(input.sighash.byte());
+                    }
+                    script_sigs.push(vec![]);
+                    witnesses.push(vec![sig]);
+                }
+            }
+        }
+
+        Ok(self.serialize(&script_sigs, &witnesses))
+    }
+
+    /// Wire encoding with the given scriptSig and witness stack per input.
+    pub(super) fn serialize(&self, script_sigs: &[Vec<u8>], witnesses: &[Vec<Vec<u8>>]) -> Vec<u8> {
+        let segwit = witnesses.iter().any(|w| !w.is_empty());
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.version.to_le_bytes());
+        if segwit {
+            // Marker and flag.
+            out.extend_from_slice(&[0x00, 0x01]);
+        }
+        write_compact_size(&mut out, self.inputs.len() as u64);
+        for (input, script_sig) in self.inputs.iter().zip(script_sigs) {
+            input.write_outpoint(&mut out);
+            write_var_bytes(&mut out, script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        self.write_outputs(&mut out);
+        if segwit {
+            for witness in witnesses {
+                write_compact_size(&mut out, witness.len() as u64);
+                for item in witness {
+                    write_var_bytes(&mut out, item);
+                }
+            }
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+
+    /// Wrap for [`UtxoChain`]: signing it yields [`RawTransaction::Bytes`].
+    pub fn into_raw(self) -> RawTransaction {
+        RawTransaction::Json(json!({ "utxo_tx": self.to_json() }))
+    }
+
+    /// Read back a transaction made with [`into_raw`](Self::into_raw).
+    pub fn from_raw(raw_tx: &RawTransaction) -> Result<Self, ChainError> {
+        let tx = raw_tx
+            .as_json()
+            .and_then(|json| json.get("utxo_tx"))
+            .ok_or_else(|| {
+                ChainError::Other(format!(
+                    "expected a locally built transaction, got {}",
+                    raw_tx.kind()
+                ))
+            })?;
+        Self::from_json(tx)
+    }
+
+    pub(super) fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "lock_time": self.lock_time,
+            "inputs": self.inputs.iter().map(|i| json!({
+                "prev_hash": hex::encode(i.prev_hash),
+                "output_index": i.output_index,
+                "value": i.value,
+                "script": hex::encode(&i.script_pubkey),
+                "sequence": i.sequence,
+                "sighash": i.sighash.byte(),
+            })).collect::<Vec<_>>(),
+            "outputs": self.outputs.iter().map(|o| json!({
+                "value": o.value,
+                "script": hex::encode(&o.script_pubkey),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    pub(super) fn from_json(tx: &Value) -> Result<Self, ChainError> {
+        let inputs = array(tx, "inputs")?
+            .iter()
+            .map(|input| {
+                Ok(UtxoInput {
+                    prev_hash: prev_hash(input)?,
+                    output_index: u32_field(input, "output_index")?,
+                    value: u64_field(input, "value")?,
+                    script_pubkey: hex_field(input, "script")?,
+                    sequence: u32_field(input, "sequence")?,
+                    // Absent in transactions saved before sighash types were selectable.
+                    sighash: match input.get("sighash") {
+                        Some(_) => SighashType::from_byte(u32_field(input, "sighash")? as u8)?,
+                        None => SighashType::All,
+                    },
+                })
+            })
+            .collect::<Result<_, ChainError>>()?;
+        let outputs = array(tx, "outputs")?
+            .iter()
+            .map(|output| {
+                Ok(UtxoOutput {
+                    value: u64_field(output, "value")?,
+                    script_pubkey: hex_field(output, "script")?,
+                })
+            })
+            .collect::<Result<_, ChainError>>()?;
+        Ok(Self {
+            version: u32_field(tx, "version")?,
+            inputs,
+            outputs,
+            lock_time: u32_field(tx, "lock_time")?,
+        })
+    }
+
+    /// Rebuild the transaction a Blockcypher `txs/new` skeleton describes.
+    pub(super) fn from_blockcypher(chain: &UtxoChain, tx: &Value) -> Result<Self, ChainError> {
+        let script_for = |io: &Value| match io.get("script").and_then(|v| v.as_str()) {
+            Some(script) => {
+                hex::decode(script).map_err(|e| ChainError::Other(format!("Invalid hex: {}", e)))
+            }
+            None => {
+                let address = io
+                    .pointer("/addresses/0")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ChainError::Other("missing addresses".to_string()))?;
+                chain.script_pubkey(address)
+            }
+        };
+
+        let inputs = array(tx, "inputs")?
+            .iter()
+            .map(|input| {
+                Ok(UtxoInput {
+                    prev_hash: prev_hash(input)?,
+                    output_index: u32_field(input, "output_index")?,
+                    value: u64_field(input, "output_value")?,
+                    // Input `script` is the scriptSig; the prevout script comes from the address.
+                    script_pubkey: chain.script_pubkey(
+                        input
+                            .pointer("/addresses/0")
+                            .and_then(|v| v.as_str())
+                            .ok_or_else(|| ChainError::Other("missing addresses".to_string()))?,
+                    )?,
+                    sequence: input
+                        .get("sequence")
+                        .and_then(|v| v.as_u64())
+                        .map_or(DEFAULT_SEQUENCE, |s| s as u32),
+                    sighash: SighashType::All,
+                })
+            })
+            .collect::<Result<_, ChainError>>()?;
+        let outputs = array(tx, "outputs")?
+            .iter()
+            .map(|output| {
+                Ok(UtxoOutput {
+                    value: u64_field(output, "value")?,
+                    script_pubkey: script_for(output)?,
+                })
+            })
+            .collect::<Result<_, ChainError>>()?;
+        Ok(Self {
+            version: tx.get("ver").and_then(|v| v.as_u64()).unwrap_or(1) as u32,
+            inputs,
+            outputs,
+            lock_time: tx.get("lock_time").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+        })
+    }
+}
+
+/// Where a [`UtxoTxBuilder`] sends what is left after outputs and fee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeStrategy {
+    /// Back to the first input's script, i.e. the sending address.
+    SameAddress,
+    /// A specific address: a custom destination, or a fresh internal-chain address
+    /// from [`WalletManager::next_change`](crate::wallet::manager::WalletManager::next_change).
+    Address(String),
+}
+
+/// Assembles an [`UnsignedUtxoTx`] from coins the caller selected.
+pub struct UtxoTxBuilder<'a> {
+    chain: &'a UtxoChain,
+    inputs: Vec<UtxoInput>,
+    outputs: Vec<UtxoOutput>,
+    change: Option<ChangeStrategy>,
+    fee: u64,
+    replaceable: bool,
+}
+
+impl<'a> UtxoTxBuilder<'a> {
+    pub fn new(chain: &'a UtxoChain) -> Self {
+        Self {
+            chain,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            change: None,
+            fee: 0,
+            replaceable: false,
+        }
+    }
+
+    pub fn input(mut self, input: UtxoInput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Pay `value` base units to `address`.
+    pub fn pay(mut self, address: &str, value: u64) -> Result<Self, ChainError> {
+        self.outputs.push(UtxoOutput {
+            value,
+            script_pubkey: self.chain.script_pubkey(address)?,
+        });
+        Ok(self)
+    }
+
+    /// Send whatever is left after outputs and fee to `address`.
+    pub fn change_to(self, address: &str) -> Result<Self, ChainError> {
+        self.change(ChangeStrategy::Address(address.to_string()))
+    }
+
+    /// Send whatever is left after outputs and fee according to `strategy`.
+    pub fn change(mut self, strategy: ChangeStrategy) -> Result<Self, ChainError> {
+        if let ChangeStrategy::Address(address) = &strategy {
+            self.chain.script_pubkey(address)?;
+        }
+        self.change = Some(strategy);
+        Ok(self)
+    }
+
+    /// Absolute fee in base units.
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Fund the outputs added so far from `utxos` as `strategy` picks, paying
+    /// `fee_rate` base units per kilobyte. Replaces any inputs and fee set
+    /// before; drops the change output when the selection needs none.
+    pub fn select_coins(
+        mut self,
+        utxos: &[UtxoInput],
+        strategy: &CoinSelection,
+        fee_rate: u64,
+    ) -> Result<Self, ChainError> {
+        let change_script = match &self.change {
+            Some(ChangeStrategy::Address(address)) => Some(self.chain.script_pubkey(address)?),
+            Some(ChangeStrategy::SameAddress) => utxos.first().map(|u| u.script_pubkey.clone()),
+            None => None,
+        };
+        let target = SelectionTarget {
+            amount: self.outputs.iter().map(|o| o.value).sum(),
+            fee_rate,
+            base_size: 10
+                + self
+                    .outputs
+                    .iter()
+                    .map(|o| 9 + o.script_pubkey.len() as u64)
+                    .sum::<u64>(),
+            change_size: change_script.map_or(0, |script| 9 + script.len() as u64),
+        };
+        let selection = strategy.select(utxos, &target)?;
+        if !selection.change {
+            self.change = None;
+        }
+        self.inputs = selection.inputs;
+        self.fee = selection.fee;
+        Ok(self)
+    }
+
+    /// Signal replace-by-fee on every input, so the transaction can later be
+    /// sped up with [`UnsignedUtxoTx::replace_by_fee`].
+    pub fn replaceable(mut self) -> Self {
+        self.replaceable = true;
+        self
+    }
+
+    pub fn build(self) -> Result<UnsignedUtxoTx, ChainError> {
+        if self.inputs.is_empty() || self.outputs.is_empty() {
+            return Err(ChainError::Other(
+                "transaction needs at least one input and one output".to_string(),
+            ));
+        }
+        let available: u64 = self.inputs.iter().map(|i| i.value).sum();
+        let spent = self
+            .outputs
+            .iter()
+            .map(|o| o.value)
+            .sum::<u64>()
+            .saturating_add(self.fee);
+        if available < spent {
+            return Err(ChainError::Other(format!(
+                "inputs total {} but outputs and fee need {}",
+                available, spent
+            )));
+        }
+
+        let mut outputs = self.outputs;
+        if let Some(strategy) = self.change {
+            let script_pubkey = match strategy {
+                ChangeStrategy::SameAddress => self.inputs[0].script_pubkey.clone(),
+                ChangeStrategy::Address(address) => self.chain.script_pubkey(&address)?,
+            };
+            let change = available - spent;
+            if change >= DUST_LIMIT {
+                outputs.push(UtxoOutput {
+                    value: change,
+                    script_pubkey,
+                });
+            }
+        }
+        let mut tx = UnsignedUtxoTx {
+            version: 2,
+            inputs: self.inputs,
+            outputs,
+            lock_time: 0,
+        };
+        if self.replaceable {
+            tx.signal_rbf();
+        }
+        Ok(tx)
+    }
+}
+
+fn array<'v>(value: &'v Value, key: &str) -> Result<&'v Vec<Value>, ChainError> {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| ChainError::Other(format!("Missing {} array", key)))
+}
+
+fn u64_field(value: &Value, key: &str) -> Result<u64, ChainError> {
+    value
+        .get(key)
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| ChainError::Other(format!("Missing {}", key)))
+}
+
+fn u32_field(value: &Value, key: &str) -> Result<u32, ChainError> {
+    u32::try_from(u64_field(value, key)?)
+        .map_err(|_| ChainError::Other(format!("{} out of range", key)))
+}
+
+fn hex_field(value: &Value, key: &str) -> Result<Vec<u8>, ChainError> {
+    let text = value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ChainError::Other(format!("Missing {}", key)))?;
+    hex::decode(text).map_err(|e| ChainError::Other(format!("Invalid hex in {}: {}", key, e)))
+}
+
+fn prev_hash(input: &Value) -> Result<[u8; 32], ChainError> {
+    hex_field(input, "prev_hash")?
+        .try_into()
+        .map_err(|_| ChainError::Other("prev_hash must be 32 bytes".to_string()))
+}
+
+/// Id of a serialized transaction in the byte order explorers display: the
+/// double SHA-256 of it without any witness data.
+pub fn txid(signed: &[u8]) -> Result<String, ChainError> {
+    let malformed = || ChainError::Other("malformed transaction".to_string());
+    let segwit = signed.get(4..6) == Some(&[0, 1]);
+    let stripped;
+    let legacy = if segwit {
+        // Skip the inputs and outputs to find where the witnesses start.
+        let mut pos = 6;
+        for _ in 0..read_compact_size(signed, &mut pos)? {
+            pos += 36;
+            let script = read_compact_size(signed, &mut pos)?;
+            pos = pos
+                .checked_add(usize::try_from(script).map_err(|_| malformed())?)
+                .and_then(|p| p.checked_add(4))
+                .ok_or_else(malformed)?;
+        }
+        for _ in 0..read_compact_size(signed, &mut pos)? {
+            pos += 8;
+            let script = read_compact_size(signed, &mut pos)?;
+            pos = pos
+                .checked_add(usize::try_from(script).map_err(|_| malformed())?)
+                .ok_or_else(malformed)?;
+        }
+        if pos + 4 > signed.len() {
+            return Err(malformed());
+        }
+        stripped = [&signed[..4], &signed[6..pos], &signed[signed.len() - 4..]].concat();
+        &stripped
+    } else {
+        signed
+    };
+    let mut hash = double_sha256(legacy);
+    hash.reverse();
+    Ok(hex::encode(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(hex_str: &str) -> [u8; 32] {
+        hex::decode(hex_str).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn bip143_native_p2wpkh_sighash() {
+        // Second input of the BIP-143 "Native P2WPKH" example.
+        let tx = UnsignedUtxoTx {
+            version: 1,
+            inputs: vec![
+                UtxoInput {
+                    prev_hash: hash(
+                        "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff",
+                    ),
+                    output_index: 0,
+                    value: 625_000_000,
+                    script_pubkey: hex::decode(
+                        "76a914000000000000000000000000000000000000000088ac",
+                    )
+                    .unwrap(),
+                    sequence: 0xffff_ffee,
+                    sighash: SighashType::All,
+                },
+                UtxoInput {
+                    prev_hash: hash(
+                        "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef",
+                    ),
+                    output_index: 1,
+                    value: 600_000_000,
+                    script_pubkey: hex::decode("00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1")
+                        .unwrap(),
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                },
+            ],
+            outputs: vec![
+                UtxoOutput {
+                    value: 112_340_000,
+                    script_pubkey: hex::decode(
+                        "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac",
+                    )
+                    .unwrap(),
+                },
+                UtxoOutput {
+                    value: 223_450_000,
+                    script_pubkey: hex::decode(
+                        "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac",
+                    )
+                    .unwrap(),
+                },
+            ],
+            lock_time: 0x11,
+        };
+        assert_eq!(
+            hex::encode(tx.sighashes().unwrap()[1]),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+        assert_eq!(UnsignedUtxoTx::from_json(&tx.to_json()).unwrap(), tx);
+
+        // Witnesses do not change the id.
+        let script_sigs = [vec![0x51], Vec::new()];
+        let legacy = tx.serialize(&script_sigs, &[Vec::new(), Vec::new()]);
+        let segwit = tx.serialize(&script_sigs, &[Vec::new(), vec![vec![1; 71], vec![2; 33]]]);
+        assert_ne!(legacy, segwit);
+        assert_eq!(txid(&segwit).unwrap(), txid(&legacy).unwrap());
+        assert!(txid(&segwit[..60]).is_err());
+    }
+
+    #[test]
+    fn change_strategies_pick_the_change_script() {
+        let chain = crate::wallet::chain::LITECOIN;
+        let sender = "LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc";
+        let other = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+        let builder = || {
+            UtxoTxBuilder::new(&chain)
+                .input(UtxoInput {
+                    prev_hash: [1; 32],
+                    output_index: 0,
+                    value: 100_000,
+                    script_pubkey: chain.script_pubkey(sender).unwrap(),
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                })
+                .pay(other, 50_000)
+                .unwrap()
+                .fee(1_000)
+        };
+
+        let same = builder()
+            .change(ChangeStrategy::SameAddress)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(same.outputs[1].value, 49_000);
+        assert_eq!(
+            same.outputs[1].script_pubkey,
+            chain.script_pubkey(sender).unwrap()
+        );
+
+        let custom = builder().change_to(other).unwrap().build().unwrap();
+        assert_eq!(
+            custom.outputs[1].script_pubkey,
+            chain.script_pubkey(other).unwrap()
+        );
+        assert!(builder().change_to("not-an-address").is_err());
+    }
+
+    #[test]
+    fn replacements_raise_the_fee_out_of_change() {
+        let chain = crate::wallet::chain::LITECOIN;
+        let sender = "LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc";
+        let other = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+        let change = chain.script_pubkey(sender).unwrap();
+        let build = |change_value: u64| {
+            UtxoTxBuilder::new(&chain)
+                .input(UtxoInput {
+                    prev_hash: [1; 32],
+                    output_index: 0,
+                    value: 50_200 + change_value,
+                    script_pubkey: change.clone(),
+                    sequence: DEFAULT_SEQUENCE,
+                    sighash: SighashType::All,
+                })
+                .pay(other, 50_000)
+                .unwrap()
+                .fee(200)
+                .change(ChangeStrategy::SameAddress)
+                .unwrap()
+        };
+
+        let final_tx = build(49_800).build().unwrap();
+        assert!(!final_tx.signals_rbf());
+        assert!(final_tx.replace_by_fee(&change, 5_000).is_err());
+
+        let tx = build(49_800).replaceable().build().unwrap();
+        assert_eq!(tx.inputs[0].sequence, RBF_SEQUENCE);
+        assert_eq!(tx.estimated_vsize().unwrap(), 226);
+        let bumped = tx.replace_by_fee(&change, 5_000).unwrap();
+        assert_eq!(bumped.fee().unwrap(), 1_130);
+        assert_eq!(bumped.outputs[0], tx.outputs[0]);
+        assert_eq!(bumped.outputs[1].value, 48_870);
+        // The new fee must beat the old one by the relay fee for its own size.
+        assert!(tx.replace_by_fee(&change, 1_000).is_err());
+
+        // Change that would turn into dust goes to the fee entirely.
+        let small = build(700).replaceable().build().unwrap();
+        let bumped = small.replace_by_fee(&change, 3_000).unwrap();
+        assert_eq!(bumped.outputs.len(), 1);
+        assert_eq!(bumped.fee().unwrap(), 900);
+        assert!(small.replace_by_fee(&change, 4_000).is_err());
+    }
+
+    #[test]
+    fn sighash_types_commit_to_what_they_promise() {
+        let input = |n: u8, script: &str| UtxoInput {
+            prev_hash: [n; 32],
+            output_index: 0,
+            value: 10_000,
+            script_pubkey: hex::decode(script).unwrap(),
+            sequence: 0xffff_ffff,
+            sighash: SighashType::All,
+        };
+        let output = |value| UtxoOutput {
+            value,
+            script_pubkey: hex::decode("76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac")
+                .unwrap(),
+        };
+        let legacy = "76a914000000000000000000000000000000000000000088ac";
+        let segwit = "00141d0f172a0ecb48aee1be1f2687d2963ae33f71a1";
+
+        for script in [legacy, segwit] {
+            let digest = |sighash, extra_input: bool, second_output: u64| {
+                let mut inputs = vec![input(1, script).with_sighash(sighash)];
+                if extra_input {
+                    inputs.push(input(2, script));
+                }
+                let tx = UnsignedUtxoTx {
+                    version: 2,
+                    inputs,
+                    outputs: vec![output(5_000), output(second_output)],
+                    lock_time: 0,
+                };
+                tx.sighashes().unwrap()[0]
+            };
+            use SighashType::*;
+            assert_ne!(digest(All, false, 1), digest(All, false, 2));
+            assert_ne!(digest(All, false, 1), digest(All, true, 1));
+            assert_eq!(digest(None, false, 1), digest(None, false, 2));
+            assert_eq!(digest(Single, false, 1), digest(Single, false, 2));
+            assert_ne!(digest(Single, false, 1), digest(Single, true, 1));
+            assert_eq!(
+                digest(SingleAnyoneCanPay, false, 1),
+                digest(SingleAnyoneCanPay, true, 2)
+            );
+            assert_eq!(
+                digest(AllAnyoneCanPay, false, 1),
+                digest(AllAnyoneCanPay, true, 1)
+            );
+            assert_ne!(digest(All, false, 1), digest(AllAnyoneCanPay, false, 1));
+        }
+
+        // SINGLE without a matching output is refused, and non-ALL types are flagged.
+        let tx = UnsignedUtxoTx {
+            version: 2,
+            inputs: vec![
+                input(1, segwit),
+                input(2, legacy).with_sighash(SighashType::Single),
+            ],
+            outputs: vec![output(5_000)],
+            lock_time: 0,
+        };
+        assert!(tx.sighashes().is_err());
+        assert_eq!(tx.sighash_warnings().len(), 1);
+        assert_eq!(UnsignedUtxoTx::from_json(&tx.to_json()).unwrap(), tx);
+    }
+}
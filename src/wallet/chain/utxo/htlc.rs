@@ -0,0 +1,362 @@
+//! Hash-time-locked outputs for cross-chain swaps.
+//!
+//! The output is P2WSH over
+//! `IF SIZE 32 EQUALVERIFY SHA256 <hash> EQUALVERIFY DUP HASH160 <recipient>
+//! ELSE <lock_time> CHECKLOCKTIMEVERIFY DROP DUP HASH160 <refund> ENDIF
+//! EQUALVERIFY CHECKSIG`: the recipient takes it with the preimage of `hash`,
+//! or the sender takes it back once `lock_time` has passed.
+
+use serde_json::{Value, json};
+
+use super::UtxoChain;
+use super::builder::{SighashType, UnsignedUtxoTx, UtxoInput, UtxoOutput};
+use crate::node::RawTransaction;
+use crate::wallet::chain::ChainError;
+use crate::wallet::chain::sdk::{
+    hash160, parse_der_signature, sec1_compressed, segwit_encode, sha256,
+};
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA_32: u8 = 0x20;
+const OP_PUSHDATA_20: u8 = 0x14;
+const OP_IF: u8 = 0x63;
+const OP_ELSE: u8 = 0x67;
+const OP_ENDIF: u8 = 0x68;
+const OP_DROP: u8 = 0x75;
+const OP_DUP: u8 = 0x76;
+const OP_SIZE: u8 = 0x82;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_SHA256: u8 = 0xa8;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+/// Enables the lock time without opting into replace-by-fee.
+const LOCKTIME_SEQUENCE: u32 = 0xffff_fffe;
+
+/// SHA-256 of a swap secret, as both the UTXO script and the swap contracts check it.
+pub fn secret_hash(preimage: &[u8]) -> [u8; 32] {
+    sha256(preimage)
+}
+
+/// Terms of a hash-time-locked output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Htlc {
+    /// SHA-256 of the 32-byte secret.
+    pub secret_hash: [u8; 32],
+    /// HASH160 of the key that may claim with the secret.
+    pub recipient: [u8; 20],
+    /// HASH160 of the key that may reclaim after `lock_time`.
+    pub refund: [u8; 20],
+    /// Block height (below 500 000 000) or Unix time the refund opens at.
+    pub lock_time: u32,
+}
+
+/// Which branch of the script a spend takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtlcPath {
+    /// Claim with the secret; publishing the spend reveals it to the sender.
+    Redeem { preimage: [u8; 32] },
+    /// Reclaim after the lock time.
+    Refund,
+}
+
+impl Htlc {
+    /// Terms between two SEC1 public keys.
+    pub fn new(
+        secret_hash: [u8; 32],
+        recipient_pubkey: &[u8],
+        refund_pubkey: &[u8],
+        lock_time: u32,
+    ) -> Result<Self, ChainError> {
+        Ok(Self {
+            secret_hash,
+            recipient: hash160(&sec1_compressed(recipient_pubkey)?),
+            refund: hash160(&sec1_compressed(refund_pubkey)?),
+            lock_time,
+        })
+    }
+
+    /// The witness script.
+    pub fn script(&self) -> Vec<u8> {
+        let mut script = vec![OP_IF, OP_SIZE, 0x01, 0x20, OP_EQUALVERIFY, OP_SHA256];
+        script.push(OP_PUSHDATA_32);
+        script.extend_from_slice(&self.secret_hash);
+        script.extend_from_slice(&[OP_EQUALVERIFY, OP_DUP, OP_HASH160, OP_PUSHDATA_20]);
+        script.extend_from_slice(&self.recipient);
+        script.push(OP_ELSE);
+        push_number(&mut script, self.lock_time);
+        script.extend_from_slice(&[OP_CHECKLOCKTIMEVERIFY, OP_DROP]);
+        script.extend_from_slice(&[OP_DUP, OP_HASH160, OP_PUSHDATA_20]);
+        script.extend_from_slice(&self.refund);
+        script.extend_from_slice(&[OP_ENDIF, OP_EQUALVERIFY, OP_CHECKSIG]);
+        script
+    }
+
+    /// P2WSH locking script to fund.
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        let mut script = vec![OP_0, OP_PUSHDATA_32];
+        script.extend_from_slice(&sha256(&self.script()));
+        script
+    }
+
+    /// Bech32 address of the output on `chain`, for funding it with
+    /// [`UtxoTxBuilder::pay`](super::UtxoTxBuilder::pay).
+    pub fn address(&self, chain: &UtxoChain) -> String {
+        segwit_encode(chain.bech32_hrp, 0, &sha256(&self.script()))
+    }
+
+    /// Spend the funded output `prev_hash:output_index` of `value` to
+    /// `destination` (a locking script), paying `fee`.
+    pub fn spend(
+        &self,
+        prev_hash: [u8; 32],
+        output_index: u32,
+        value: u64,
+        path: HtlcPath,
+        destination: Vec<u8>,
+        fee: u64,
+    ) -> Result<HtlcSpend, ChainError> {
+        let remaining = value
+            .checked_sub(fee)
+            .filter(|&v| v > 0)
+            .ok_or_else(|| ChainError::Other(format!("fee {} consumes the output", fee)))?;
+        let lock_time = match path {
+            HtlcPath::Redeem { .. } => 0,
+            HtlcPath::Refund => self.lock_time,
+        };
+        Ok(HtlcSpend {
+            htlc: self.clone(),
+            path,
+            tx: UnsignedUtxoTx {
+                version: 2,
+                inputs: vec![UtxoInput {
+                    prev_hash,
+                    output_index,
+                    value,
+                    script_pubkey: self.script_pubkey(),
+                    sequence: LOCKTIME_SEQUENCE,
+                    sighash: SighashType::All,
+                }],
+                outputs: vec![UtxoOutput {
+                    value: remaining,
+                    script_pubkey: destination,
+                }],
+                lock_time,
+            },
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "secret_hash": hex::encode(self.secret_hash),
+            "recipient": hex::encode(self.recipient),
+            "refund": hex::encode(self.refund),
+            "lock_time": self.lock_time,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, ChainError> {
+        Ok(Self {
+            secret_hash: fixed_hex(value, "secret_hash")?,
+            recipient: fixed_hex(value, "recipient")?,
+            refund: fixed_hex(value, "refund")?,
+            lock_time: value
+                .get("lock_time")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| ChainError::Other("Missing lock_time".to_string()))?,
+        })
+    }
+}
+
+/// A one-input transaction spending an [`Htlc`] output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtlcSpend {
+    pub htlc: Htlc,
+    pub path: HtlcPath,
+    pub tx: UnsignedUtxoTx,
+}
+
+impl HtlcSpend {
+    /// BIP-143 digest to sign, with the witness script as scriptCode.
+    pub fn sighash(&self) -> [u8; 32] {
+        self.tx
+            .segwit_v0_digest(0, &self.tx.bip143_shared(), &self.htlc.script())
+    }
+
+    /// Serialize with the witness for the chosen path.
+    pub fn to_signed_bytes(&self, signature: &[u8], pubkey: &[u8]) -> Result<Vec<u8>, ChainError> {
+        let pubkey = sec1_compressed(pubkey)?;
+        let (expected, selector) = match &self.path {
+            HtlcPath::Redeem { preimage } => {
+                (&self.htlc.recipient, vec![preimage.to_vec(), vec![1]])
+            }
+            HtlcPath::Refund => (&self.htlc.refund, vec![vec![]]),
+        };
+        if hash160(&pubkey) != *expected {
+            return Err(ChainError::Other(
+                "key does not match this HTLC path".to_string(),
+            ));
+        }
+        if let HtlcPath::Redeem { preimage } = &self.path
+            && secret_hash(preimage) != self.htlc.secret_hash
+        {
+            return Err(ChainError::Other(
+                "preimage does not match the HTLC hash".to_string(),
+            ));
+        }
+        let mut sig = parse_der_signature(signature)?.to_der().as_bytes().to_vec();
+        sig.push(SighashType::All.byte());
+
+        let mut witness = vec![sig, pubkey.to_vec()];
+        witness.extend(selector);
+        witness.push(self.htlc.script());
+        Ok(self.tx.serialize(&[vec![]], &[witness]))
+    }
+
+    /// Wrap for [`UtxoChain`]: signing it yields [`RawTransaction::Bytes`].
+    pub fn into_raw(self) -> RawTransaction {
+        let (path, preimage) = match &self.path {
+            HtlcPath::Redeem { preimage } => ("redeem", Some(hex::encode(preimage))),
+            HtlcPath::Refund => ("refund", None),
+        };
+        RawTransaction::Json(json!({
+            "htlc_spend": {
+                "htlc": self.htlc.to_json(),
+                "path": path,
+                "preimage": preimage,
+                "tx": self.tx.to_json(),
+            }
+        }))
+    }
+
+    pub(super) fn from_json(value: &Value) -> Result<Self, ChainError> {
+        let path = match value.get("path").and_then(|v| v.as_str()) {
+            Some("redeem") => HtlcPath::Redeem {
+                preimage: fixed_hex(value, "preimage")?,
+            },
+            Some("refund") => HtlcPath::Refund,
+            _ => return Err(ChainError::Other("Missing HTLC path".to_string())),
+        };
+        let tx = value
+            .get("tx")
+            .ok_or_else(|| ChainError::Other("Missing tx".to_string()))?;
+        let htlc = value
+            .get("htlc")
+            .ok_or_else(|| ChainError::Other("Missing htlc".to_string()))?;
+        Ok(Self {
+            htlc: Htlc::from_json(htlc)?,
+            path,
+            tx: UnsignedUtxoTx::from_json(tx)?,
+        })
+    }
+}
+
+/// Push `n` as a minimal script number.
+pub(super) fn push_number(script: &mut Vec<u8>, n: u32) {
+    match n {
+        0 => script.push(OP_0),
+        1..=16 => script.push(0x50 + n as u8),
+        _ => {
+            let mut bytes: Vec<u8> = n.to_le_bytes().into_iter().collect();
+            while bytes.last() == Some(&0) {
+                bytes.pop();
+            }
+            // The top bit is the sign; keep the number positive.
+            if bytes.last().is_some_and(|b| b & 0x80 != 0) {
+                bytes.push(0);
+            }
+            script.push(bytes.len() as u8);
+            script.extend_from_slice(&bytes);
+        }
+    }
+}
+
+fn fixed_hex<const N: usize>(value: &Value, key: &str) -> Result<[u8; N], ChainError> {
+    let text = value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ChainError::Other(format!("Missing {}", key)))?;
+    hex::decode(text)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ChainError::Other(format!("{} must be {} hex bytes", key, N)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+    use crate::wallet::chain::{Chain, LITECOIN};
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[test]
+    fn encodes_lock_times_as_minimal_numbers() {
+        let mut script = Vec::new();
+        push_number(&mut script, 16);
+        push_number(&mut script, 128);
+        push_number(&mut script, 800_000);
+        assert_eq!(hex::encode(script), "600280000300350c");
+    }
+
+    #[tokio::test]
+    async fn redeems_through_the_signing_pipeline() {
+        let recipient = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let sender = LocalSigner::from_bytes([2u8; 32]).unwrap();
+        let preimage = [7u8; 32];
+        let htlc = Htlc::new(
+            secret_hash(&preimage),
+            &recipient.public_key(),
+            &sender.public_key(),
+            2_700_000,
+        )
+        .unwrap();
+        assert!(htlc.address(&LITECOIN).starts_with("ltc1q"));
+
+        let destination = vec![
+            0x00, 0x14, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+            0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab, 0xab,
+        ];
+        let raw = htlc
+            .spend(
+                [9; 32],
+                0,
+                50_000,
+                HtlcPath::Redeem { preimage },
+                destination.clone(),
+                1_000,
+            )
+            .unwrap()
+            .into_raw();
+        let digests = LITECOIN.prepare_transaction(&raw).unwrap();
+        let signature = recipient.sign_prehash(&digests[0]).await.unwrap();
+        let signed = LITECOIN
+            .finalize_transaction(
+                &raw,
+                std::slice::from_ref(&signature),
+                &recipient.public_key(),
+            )
+            .unwrap();
+        let RawTransaction::Bytes(bytes) = signed else {
+            panic!("expected wire bytes");
+        };
+        // The preimage and witness script are revealed in the witness.
+        assert!(bytes.windows(32).any(|w| w == preimage));
+        assert!(
+            bytes
+                .windows(htlc.script().len())
+                .any(|w| w == htlc.script())
+        );
+
+        // Only the refund key can take the refund path.
+        let refund = htlc
+            .spend([9; 32], 0, 50_000, HtlcPath::Refund, destination, 1_000)
+            .unwrap();
+        assert_eq!(refund.tx.lock_time, 2_700_000);
+        assert!(
+            refund
+                .to_signed_bytes(&signature, &recipient.public_key())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,575 @@
+//! m-of-n multisig outputs, spent with signatures from several signers.
+//!
+//! The script is `OP_m <key>... OP_n OP_CHECKMULTISIG` over the keys sorted
+//! as BIP-67 sorts them, so cosigners derive the same address whatever order
+//! they list the keys in. It is locked as P2SH (legacy sighash) or P2WSH
+//! (BIP-143). A [`MultisigSpend`] is the partially-signed transaction the
+//! cosigners pass around as JSON: each adds signatures from its own
+//! [`Signer`] (local, hardware or remote), copies are merged, and once `m`
+//! keys have signed every input it serializes to the final transaction.
+
+use std::collections::BTreeMap;
+
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use serde_json::{Value, json};
+
+use super::UtxoAddressType;
+use super::UtxoChain;
+use super::builder::{DEFAULT_SEQUENCE, SighashType, UnsignedUtxoTx, UtxoInput};
+use super::htlc::push_number;
+use crate::WalletError;
+use crate::wallet::Signer;
+use crate::wallet::chain::sdk::{
+    hash160, parse_der_signature, parse_sec1, sec1_compressed, sha256, write_compact_size,
+};
+use crate::wallet::chain::{Chain, ChainError};
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_EQUAL: u8 = 0x87;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// How the multisig script is locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultisigKind {
+    /// Pay-to-script-hash: base58 (or CashAddr) addresses any chain accepts.
+    P2sh,
+    /// Pay-to-witness-script-hash: bech32 addresses, cheaper to spend, on
+    /// chains with SegWit.
+    P2wsh,
+}
+
+impl MultisigKind {
+    /// Most keys a standard script of this kind may hold: a P2SH redeem
+    /// script must fit in 520 bytes, P2WSH is bound by `OP_CHECKMULTISIG`.
+    pub fn max_keys(self) -> usize {
+        match self {
+            MultisigKind::P2sh => 15,
+            MultisigKind::P2wsh => 20,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            MultisigKind::P2sh => "p2sh",
+            MultisigKind::P2wsh => "p2wsh",
+        }
+    }
+}
+
+/// Keys and threshold of an m-of-n multisig output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigWallet {
+    threshold: usize,
+    /// Compressed, in BIP-67 order.
+    pubkeys: Vec<[u8; 33]>,
+    kind: MultisigKind,
+}
+
+impl MultisigWallet {
+    /// `threshold` of `pubkeys` (SEC1, in any order) must sign to spend.
+    pub fn new(
+        threshold: usize,
+        pubkeys: &[&[u8]],
+        kind: MultisigKind,
+    ) -> Result<Self, ChainError> {
+        if threshold == 0 || threshold > pubkeys.len() || pubkeys.len() > kind.max_keys() {
+            return Err(ChainError::Other(format!(
+                "cannot make a {}-of-{} {} multisig",
+                threshold,
+                pubkeys.len(),
+                kind.as_str()
+            )));
+        }
+        let mut keys = pubkeys
+            .iter()
+            .map(|key| sec1_compressed(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        keys.sort();
+        if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(ChainError::Other(
+                "a key appears twice in the multisig".to_string(),
+            ));
+        }
+        Ok(Self {
+            threshold,
+            pubkeys: keys,
+            kind,
+        })
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The compressed keys, in script order.
+    pub fn pubkeys(&self) -> &[[u8; 33]] {
+        &self.pubkeys
+    }
+
+    pub fn kind(&self) -> MultisigKind {
+        self.kind
+    }
+
+    /// The redeem (P2SH) or witness (P2WSH) script.
+    pub fn script(&self) -> Vec<u8> {
+        let mut script = Vec::with_capacity(3 + 34 * self.pubkeys.len());
+        push_number(&mut script, self.threshold as u32);
+        for key in &self.pubkeys {
+            script.push(key.len() as u8);
+            script.extend_from_slice(key);
+        }
+        push_number(&mut script, self.pubkeys.len() as u32);
+        script.push(OP_CHECKMULTISIG);
+        script
+    }
+
+    /// Locking script to fund.
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        let script = self.script();
+        match self.kind {
+            MultisigKind::P2sh => {
+                [&[OP_HASH160, 0x14][..], &hash160(&script), &[OP_EQUAL]].concat()
+            }
+            MultisigKind::P2wsh => [&[OP_0, 0x20][..], &sha256(&script)].concat(),
+        }
+    }
+
+    /// Address of the output on `chain`, for funding it with
+    /// [`UtxoTxBuilder::pay`](super::UtxoTxBuilder::pay).
+    pub fn address(&self, chain: &UtxoChain) -> Result<String, ChainError> {
+        let cashaddr = chain.address_type == UtxoAddressType::CashAddr;
+        let unsupported = match self.kind {
+            MultisigKind::P2sh => chain.p2sh_prefix.is_none() && !cashaddr,
+            MultisigKind::P2wsh => cashaddr,
+        };
+        if unsupported {
+            return Err(ChainError::Other(format!(
+                "{} has no {} addresses",
+                chain.id(),
+                self.kind.as_str()
+            )));
+        }
+        Ok(chain.address_from_script(&self.script_pubkey()))
+    }
+
+    /// The output `prev_hash:output_index` of `value` this wallet was paid,
+    /// as an input for [`UtxoTxBuilder::input`](super::UtxoTxBuilder::input).
+    pub fn input(&self, prev_hash: [u8; 32], output_index: u32, value: u64) -> UtxoInput {
+        UtxoInput {
+            prev_hash,
+            output_index,
+            value,
+            script_pubkey: self.script_pubkey(),
+            sequence: DEFAULT_SEQUENCE,
+            sighash: SighashType::All,
+        }
+    }
+
+    /// Virtual size one of this wallet's inputs adds once `threshold`
+    /// signatures are on it, for working out a fee to build with.
+    pub fn input_vsize(&self) -> u64 {
+        // A DER signature of at most 72 bytes and its sighash byte, pushed.
+        let signatures = self.threshold as u64 * 74;
+        let script = self.script().len() as u64;
+        match self.kind {
+            MultisigKind::P2sh => {
+                let script_sig = 1 + signatures + push_size(script as usize) + script;
+                let mut prefix = Vec::new();
+                write_compact_size(&mut prefix, script_sig);
+                40 + prefix.len() as u64 + script_sig
+            }
+            MultisigKind::P2wsh => {
+                // Item count, the empty item CHECKMULTISIG pops, signatures, script.
+                let witness = 2 + signatures + 1 + script;
+                41 + witness.div_ceil(4)
+            }
+        }
+    }
+
+    /// Start collecting signatures for `tx`, every input of which must spend
+    /// one of this wallet's outputs.
+    pub fn spend(&self, tx: UnsignedUtxoTx) -> Result<MultisigSpend, ChainError> {
+        let script_pubkey = self.script_pubkey();
+        if let Some(i) = tx
+            .inputs
+            .iter()
+            .position(|input| input.script_pubkey != script_pubkey)
+        {
+            return Err(ChainError::Other(format!(
+                "input {} does not spend this multisig",
+                i
+            )));
+        }
+        Ok(MultisigSpend {
+            wallet: self.clone(),
+            signatures: vec![BTreeMap::new(); tx.inputs.len()],
+            tx,
+        })
+    }
+
+    fn key_index(&self, pubkey: &[u8]) -> Result<usize, ChainError> {
+        let pubkey = sec1_compressed(pubkey)?;
+        self.pubkeys
+            .iter()
+            .position(|key| *key == pubkey)
+            .ok_or_else(|| ChainError::Other("key is not part of this multisig".to_string()))
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "threshold": self.threshold,
+            "kind": self.kind.as_str(),
+            "pubkeys": self.pubkeys.iter().map(hex::encode).collect::<Vec<_>>(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, ChainError> {
+        let missing = |key: &str| ChainError::Other(format!("Missing {}", key));
+        let kind = match value.get("kind").and_then(|v| v.as_str()) {
+            Some("p2sh") => MultisigKind::P2sh,
+            Some("p2wsh") => MultisigKind::P2wsh,
+            _ => return Err(missing("kind")),
+        };
+        let threshold = value
+            .get("threshold")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| missing("threshold"))?;
+        let pubkeys = value
+            .get("pubkeys")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| missing("pubkeys"))?
+            .iter()
+            .map(|key| {
+                key.as_str()
+                    .and_then(|key| hex::decode(key).ok())
+                    .ok_or_else(|| ChainError::Other("pubkeys must be hex".to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let pubkeys: Vec<&[u8]> = pubkeys.iter().map(Vec::as_slice).collect();
+        Self::new(threshold as usize, &pubkeys, kind)
+    }
+}
+
+/// A transaction spending [`MultisigWallet`] outputs, with the signatures
+/// collected so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigSpend {
+    pub wallet: MultisigWallet,
+    pub tx: UnsignedUtxoTx,
+    /// Per input, normalized DER signatures by index of the key that made them.
+    signatures: Vec<BTreeMap<usize, Vec<u8>>>,
+}
+
+impl MultisigSpend {
+    /// Digest every cosigner signs for each input, with the multisig script
+    /// as scriptCode.
+    pub fn sighashes(&self) -> Result<Vec<[u8; 32]>, ChainError> {
+        let script = self.wallet.script();
+        let shared = self.tx.bip143_shared();
+        (0..self.tx.inputs.len())
+            .map(|i| {
+                let sighash = self.tx.inputs[i].sighash;
+                if sighash.base() == SighashType::Single.byte() && i >= self.tx.outputs.len() {
+                    return Err(ChainError::Other(format!(
+                        "input {} uses SIGHASH_SINGLE but there is no output {}",
+                        i, i
+                    )));
+                }
+                Ok(match self.wallet.kind {
+                    MultisigKind::P2sh => self.tx.legacy_digest(i, &script),
+                    MultisigKind::P2wsh => self.tx.segwit_v0_digest(i, &shared, &script),
+                })
+            })
+            .collect()
+    }
+
+    /// Add `pubkey`'s DER signature of input `input`, made elsewhere (an
+    /// air-gapped device, another service). It is checked against the
+    /// input's digest before it is kept.
+    pub fn add_signature(
+        &mut self,
+        input: usize,
+        pubkey: &[u8],
+        signature: &[u8],
+    ) -> Result<(), ChainError> {
+        let key = self.wallet.key_index(pubkey)?;
+        let digest = *self
+            .sighashes()?
+            .get(input)
+            .ok_or_else(|| ChainError::Other(format!("transaction has no input {}", input)))?;
+        let signature = parse_der_signature(signature)?;
+        parse_sec1(pubkey)?
+            .verify_prehash(&digest, &signature)
+            .map_err(|_| ChainError::Other(format!("signature does not sign input {}", input)))?;
+        self.signatures[input].insert(key, signature.to_der().as_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Sign every input `signer`'s key has not signed yet and return how
+    /// many signatures were added.
+    pub async fn sign_with<S: Signer + ?Sized>(
+        &mut self,
+        signer: &S,
+    ) -> Result<usize, WalletError> {
+        let pubkey = signer.public_key();
+        let key = self.wallet.key_index(&pubkey)?;
+        let mut added = 0;
+        for (i, digest) in self.sighashes()?.into_iter().enumerate() {
+            if self.signatures[i].contains_key(&key) {
+                continue;
+            }
+            let signature = signer.sign_prehash(&digest).await.map_err(|source| {
+                WalletError::SigningFailed {
+                    context: format!("multisig input {}", i),
+                    source,
+                }
+            })?;
+            self.add_signature(i, &pubkey, &signature)?;
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Take in the signatures of another cosigner's copy of this spend.
+    pub fn merge(&mut self, other: &MultisigSpend) -> Result<(), ChainError> {
+        if other.wallet != self.wallet || other.tx != self.tx {
+            return Err(ChainError::Other(
+                "cannot merge signatures for a different spend".to_string(),
+            ));
+        }
+        for (ours, theirs) in self.signatures.iter_mut().zip(&other.signatures) {
+            for (key, signature) in theirs {
+                ours.entry(*key).or_insert_with(|| signature.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys that have signed input `input`.
+    pub fn signed_by(&self, input: usize) -> Vec<[u8; 33]> {
+        self.signatures
+            .get(input)
+            .into_iter()
+            .flat_map(|sigs| sigs.keys().map(|&key| self.wallet.pubkeys[key]))
+            .collect()
+    }
+
+    /// Whether every input has `threshold` signatures.
+    pub fn is_complete(&self) -> bool {
+        self.signatures
+            .iter()
+            .all(|sigs| sigs.len() >= self.wallet.threshold)
+    }
+
+    /// Serialize the finished transaction, with the first `threshold`
+    /// signatures of each input in key order as `OP_CHECKMULTISIG` wants them.
+    pub fn to_signed_bytes(&self) -> Result<Vec<u8>, ChainError> {
+        if let Some(i) = self
+            .signatures
+            .iter()
+            .position(|sigs| sigs.len() < self.wallet.threshold)
+        {
+            return Err(ChainError::Other(format!(
+                "input {} has {} of {} signatures",
+                i,
+                self.signatures[i].len(),
+                self.wallet.threshold
+            )));
+        }
+        let script = self.wallet.script();
+        let mut script_sigs = Vec::with_capacity(self.tx.inputs.len());
+        let mut witnesses = Vec::with_capacity(self.tx.inputs.len());
+        for (input, sigs) in self.tx.inputs.iter().zip(&self.signatures) {
+            let sigs = sigs.values().take(self.wallet.threshold).map(|der| {
+                let mut sig = der.clone();
+                sig.push(input.sighash.byte());
+                sig
+            });
+            match self.wallet.kind {
+                MultisigKind::P2sh => {
+                    // OP_CHECKMULTISIG pops one item more than it uses.
+                    let mut script_sig = vec![OP_0];
+                    for sig in sigs {
+                        push_data(&mut script_sig, &sig);
+                    }
+                    push_data(&mut script_sig, &script);
+                    script_sigs.push(script_sig);
+                    witnesses.push(vec![]);
+                }
+                MultisigKind::P2wsh => {
+                    let mut witness = vec![vec![]];
+                    witness.extend(sigs);
+                    witness.push(script.clone());
+                    script_sigs.push(vec![]);
+                    witnesses.push(witness);
+                }
+            }
+        }
+        Ok(self.tx.serialize(&script_sigs, &witnesses))
+    }
+
+    /// This crate's exchange format for cosigners (not PSBT): the wallet,
+    /// the transaction and the signatures so far.
+    pub fn to_json(&self) -> Value {
+        let signatures: Vec<Value> = self
+            .signatures
+            .iter()
+            .map(|sigs| {
+                sigs.iter()
+                    .map(|(&key, der)| {
+                        (
+                            hex::encode(self.wallet.pubkeys[key]),
+                            Value::from(hex::encode(der)),
+                        )
+                    })
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()
+            })
+            .collect();
+        json!({
+            "multisig": self.wallet.to_json(),
+            "tx": self.tx.to_json(),
+            "signatures": signatures,
+        })
+    }
+
+    /// Read back [`to_json`](Self::to_json), checking every signature.
+    pub fn from_json(value: &Value) -> Result<Self, ChainError> {
+        let wallet = value
+            .get("multisig")
+            .ok_or_else(|| ChainError::Other("Missing multisig".to_string()))?;
+        let tx = value
+            .get("tx")
+            .ok_or_else(|| ChainError::Other("Missing tx".to_string()))?;
+        let mut spend = MultisigWallet::from_json(wallet)?.spend(UnsignedUtxoTx::from_json(tx)?)?;
+        let inputs = value
+            .get("signatures")
+            .and_then(|v| v.as_array())
+            .map_or(&[][..], Vec::as_slice);
+        for (i, sigs) in inputs.iter().enumerate() {
+            for (pubkey, der) in sigs.as_object().into_iter().flatten() {
+                let decode = |text: Option<&str>| {
+                    text.and_then(|text| hex::decode(text).ok())
+                        .ok_or_else(|| ChainError::Other("signatures must be hex".to_string()))
+                };
+                spend.add_signature(i, &decode(Some(pubkey))?, &decode(der.as_str())?)?;
+            }
+        }
+        Ok(spend)
+    }
+}
+
+/// Bytes the push of `len` bytes of data adds in front of them.
+fn push_size(len: usize) -> u64 {
+    match len {
+        0..=75 => 1,
+        76..=255 => 2,
+        _ => 3,
+    }
+}
+
+/// Push `data` with the smallest opcode that fits it.
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        len @ 0..=75 => script.push(len as u8),
+        len @ 76..=255 => script.extend_from_slice(&[OP_PUSHDATA1, len as u8]),
+        len => {
+            script.push(OP_PUSHDATA2);
+            script.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::chain::{BITCOIN, LITECOIN, UtxoOutput};
+    use crate::wallet::signer::local::LocalSigner;
+
+    fn signers() -> Vec<LocalSigner> {
+        (1..=3)
+            .map(|i| LocalSigner::from_bytes([i; 32]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn addresses_do_not_depend_on_key_order() {
+        let keys: Vec<Vec<u8>> = signers().iter().map(|s| s.public_key()).collect();
+        let forward: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+        let backward: Vec<&[u8]> = keys.iter().rev().map(Vec::as_slice).collect();
+
+        let p2sh = MultisigWallet::new(2, &forward, MultisigKind::P2sh).unwrap();
+        assert_eq!(
+            p2sh,
+            MultisigWallet::new(2, &backward, MultisigKind::P2sh).unwrap()
+        );
+        let script = p2sh.script();
+        assert_eq!((script[0], script[script.len() - 2]), (0x52, 0x53));
+        let address = p2sh.address(&BITCOIN).unwrap();
+        assert!(address.starts_with('3'));
+        assert_eq!(
+            BITCOIN.script_pubkey(&address).unwrap(),
+            p2sh.script_pubkey()
+        );
+        assert!(p2sh.address(&LITECOIN).unwrap().starts_with('M'));
+
+        let p2wsh = MultisigWallet::new(2, &forward, MultisigKind::P2wsh).unwrap();
+        let address = p2wsh.address(&BITCOIN).unwrap();
+        assert!(address.starts_with("bc1q") && address.len() == 62);
+
+        assert!(MultisigWallet::new(4, &forward, MultisigKind::P2sh).is_err());
+        assert!(MultisigWallet::new(1, &[&keys[0], &keys[0]], MultisigKind::P2sh).is_err());
+    }
+
+    #[tokio::test]
+    async fn cosigners_sign_apart_and_merge() {
+        let signers = signers();
+        let keys: Vec<Vec<u8>> = signers.iter().map(|s| s.public_key()).collect();
+        let keys: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+
+        for kind in [MultisigKind::P2sh, MultisigKind::P2wsh] {
+            let wallet = MultisigWallet::new(2, &keys, kind).unwrap();
+            let tx = UnsignedUtxoTx {
+                version: 2,
+                inputs: vec![
+                    wallet.input([1; 32], 0, 60_000),
+                    wallet.input([2; 32], 1, 40_000),
+                ],
+                outputs: vec![UtxoOutput {
+                    value: 99_000,
+                    script_pubkey: [&[0x00, 0x14][..], &[0xab; 20]].concat(),
+                }],
+                lock_time: 0,
+            };
+            let mut first = wallet.spend(tx.clone()).unwrap();
+            assert_eq!(first.sign_with(&signers[2]).await.unwrap(), 2);
+            assert_eq!(first.sign_with(&signers[2]).await.unwrap(), 0);
+            assert!(!first.is_complete() && first.to_signed_bytes().is_err());
+
+            // The second cosigner works from the JSON the first sent.
+            let mut second = MultisigSpend::from_json(&first.to_json()).unwrap();
+            assert_eq!(second, first);
+            second.sign_with(&signers[0]).await.unwrap();
+            first.merge(&second).unwrap();
+            assert!(first.is_complete());
+            assert_eq!(first.signed_by(1).len(), 2);
+
+            let signed = first.to_signed_bytes().unwrap();
+            let script = wallet.script();
+            assert!(signed.windows(script.len()).any(|w| w == script));
+            assert_eq!(
+                signed.get(4..6) == Some(&[0, 1]),
+                kind == MultisigKind::P2wsh
+            );
+
+            // A signature of the wrong digest is refused.
+            let digest = first.sighashes().unwrap()[0];
+            let wrong = signers[1].sign_prehash(&digest).await.unwrap();
+            assert!(first.add_signature(1, keys[1], &wrong).is_err());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+
+use super::memory::SecureBuffer;
+
+const NONCE_LEN: usize = 12;
+
+/// AES-256-GCM for records written to disk (seen-sets, caches).
+///
+/// Each sealed record is `nonce || ciphertext || tag` with a fresh random nonce,
+/// so records can be appended independently.
+pub struct AtRestCipher {
+    cipher: Aes256Gcm,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum AtRestError {
+    #[error("encryption key must be 32 bytes, got {0}")]
+    KeyLength(usize),
+    #[error("record could not be decrypted (wrong key or corrupted data)")]
+    Decrypt,
+}
+
+impl AtRestCipher {
+    /// Build a cipher from a 32-byte key held in a [`SecureBuffer`].
+    pub fn new(key: &SecureBuffer) -> Result<Self, AtRestError> {
+        let cipher =
+            Aes256Gcm::new_from_slice(key).map_err(|_| AtRestError::KeyLength(key.len()))?;
+        Ok(Self { cipher })
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(&Nonce::from(nonce), plaintext)
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+        let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        out
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, AtRestError> {
+        let (nonce, ciphertext) = sealed
+            .split_first_chunk::<NONCE_LEN>()
+            .ok_or(AtRestError::Decrypt)?;
+        self.cipher
+            .decrypt(&Nonce::from(*nonce), ciphertext)
+            .map_err(|_| AtRestError::Decrypt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_and_wrong_key() {
+        let cipher = AtRestCipher::new(&SecureBuffer::new(vec![7u8; 32])).unwrap();
+        let sealed = cipher.seal(b"tron\tabcd\t-");
+        assert_ne!(cipher.seal(b"tron\tabcd\t-"), sealed, "nonces are fresh");
+        assert_eq!(cipher.open(&sealed).unwrap(), b"tron\tabcd\t-");
+
+        let other = AtRestCipher::new(&SecureBuffer::new(vec![8u8; 32])).unwrap();
+        assert_eq!(other.open(&sealed), Err(AtRestError::Decrypt));
+        assert!(AtRestCipher::new(&SecureBuffer::new(vec![0u8; 16])).is_err());
+    }
+}
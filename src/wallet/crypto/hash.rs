@@ -9,6 +9,16 @@ pub fn sha256(data: &[u8]) -> [u8; 32] {
     hasher.finalize().into()
 }
 
+/// BIP-340 tagged hash, `sha256(sha256(tag) || sha256(tag) || data)`.
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag = sha256(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
 pub fn double_sha256(data: &[u8]) -> [u8; 32] {
     sha256(&sha256(data))
 }
@@ -58,6 +68,61 @@ mod tests {
         );
     }
 
+    /// Keccak-256 (the pre-SHA-3 padding Ethereum uses) known answers,
+    /// including messages either side of the 136-byte rate.
+    #[cfg(feature = "exhaustive-tests")]
+    #[test]
+    fn keccak256_reference_suite() {
+        let million = vec![b'a'; 1_000_000];
+        let a = |n: usize| &million[..n];
+        let vectors: [(&[u8], &str); 9] = [
+            (
+                b"",
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                b"abc",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                b"The quick brown fox jumps over the lazy dog",
+                "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "45d3b367a6904e6e8d502ee04999a7c27647f91fa845d456525fd352ae3d7371",
+            ),
+            (
+                b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+                "f519747ed599024f3882238e5ab43960132572b7345fbeb9a90769dafd21ad67",
+            ),
+            (
+                a(135),
+                "34367dc248bbd832f4e3e69dfaac2f92638bd0bbd18f2912ba4ef454919cf446",
+            ),
+            (
+                a(136),
+                "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e",
+            ),
+            (
+                a(137),
+                "d869f639c7046b4929fc92a4d988a8b22c55fbadb802c0c66ebcd484f1915f39",
+            ),
+            (
+                &million,
+                "fadae6b49f129bbb812be8407b7b2894f34aecf6dbd1f9b0f0c7e9853098fc96",
+            ),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(
+                to_hex(&keccak256(message)),
+                digest,
+                "{} bytes",
+                message.len()
+            );
+        }
+    }
+
     #[test]
     fn hash160_known_vector() {
         let out = hash160(b"abc");
@@ -1,21 +1,31 @@
 use std::fmt;
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 
-#[cfg(unix)]
-use libc;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
-/// A buffer that zeroizes its content on drop and prevents swapping (on Unix).
+/// A buffer that zeroizes its content on drop and is kept out of swap
+/// (`mlock` on Unix, `VirtualLock` on Windows).
 /// Used for storing sensitive data like private keys and mnemonics.
-#[derive(Clone)]
+///
+/// The bytes sit in one fixed-size allocation for the buffer's whole life, so
+/// no copy is left behind by a reallocation; build a secret of unknown length
+/// in a [`SecureBufferMut`] instead of a `Vec`.
 pub struct SecureBuffer {
-    data: Vec<u8>,
+    data: Box<[u8]>,
 }
 
 impl SecureBuffer {
+    /// Take `data`. If it has spare capacity it is copied into an exact-size
+    /// allocation and the original, spare capacity included, is zeroized.
     pub fn new(data: Vec<u8>) -> Self {
-        let buffer = Self { data };
-        buffer.lock_memory();
-        buffer
+        Self::from_boxed(into_exact(data))
+    }
+
+    /// Take `data` without copying it.
+    pub fn from_boxed(data: Box<[u8]>) -> Self {
+        lock(data.as_ptr(), data.len());
+        Self { data }
     }
 
     pub fn from_string(s: String) -> Self {
@@ -26,54 +36,194 @@ impl SecureBuffer {
         std::str::from_utf8(&self.data)
     }
 
-    #[cfg(unix)]
-    fn lock_memory(&self) {
-        if self.data.is_empty() {
-            return;
-        }
+    /// Whether the buffer holds exactly `other`, in time that does not depend
+    /// on where the two differ. Use it to check PINs, MACs and the like.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        self.data.ct_eq(other).into()
+    }
 
-        unsafe {
-            let ptr = self.data.as_ptr() as *const libc::c_void;
-            let len = self.data.len();
-            libc::mlock(ptr, len);
+    /// Turn the buffer back into a growable one, in the same allocation.
+    pub fn into_mut(mut self) -> SecureBufferMut {
+        SecureBufferMut {
+            data: std::mem::take(&mut self.data).into_vec(),
         }
     }
+}
+
+impl Clone for SecureBuffer {
+    fn clone(&self) -> Self {
+        Self::from_boxed(self.data.clone())
+    }
+}
+
+impl Drop for SecureBuffer {
+    fn drop(&mut self) {
+        self.data.zeroize();
+        unlock(self.data.as_ptr(), self.data.len());
+    }
+}
+
+/// A growable secret, e.g. a passphrase read a character at a time.
+///
+/// Growing moves the contents to a new locked allocation and zeroizes the old
+/// one, which a `Vec` would hand back to the allocator as it is. Dropping the
+/// guard zeroizes everything it held; [`freeze`](Self::freeze) turns it into
+/// a [`SecureBuffer`] instead.
+#[derive(Default)]
+pub struct SecureBufferMut {
+    data: Vec<u8>,
+}
+
+impl SecureBufferMut {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    #[cfg(not(unix))]
-    fn lock_memory(&self) {
-        // No-op on non-Unix systems
+    pub fn with_capacity(capacity: usize) -> Self {
+        let data = Vec::with_capacity(capacity);
+        lock(data.as_ptr(), data.capacity());
+        Self { data }
     }
 
-    #[cfg(unix)]
-    fn unlock_memory(&self) {
-        if self.data.is_empty() {
+    /// Make room for `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.data.len() + additional;
+        if needed <= self.data.capacity() {
             return;
         }
+        let mut grown = Vec::with_capacity(needed.max(self.data.capacity() * 2));
+        lock(grown.as_ptr(), grown.capacity());
+        grown.extend_from_slice(&self.data);
+        wipe(std::mem::replace(&mut self.data, grown));
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.reserve(1);
+        self.data.push(byte);
+    }
+
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.reserve(bytes.len());
+        self.data.extend_from_slice(bytes);
+    }
 
-        unsafe {
-            let ptr = self.data.as_ptr() as *const libc::c_void;
-            let len = self.data.len();
-            libc::munlock(ptr, len);
+    /// Zeroize and drop the bytes past `len`.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.data.len() {
+            self.data[len..].zeroize();
+            self.data.truncate(len);
         }
     }
 
-    #[cfg(not(unix))]
-    fn unlock_memory(&self) {
-        // No-op on non-Unix systems
+    /// The contents as a [`SecureBuffer`], copied (and this allocation
+    /// zeroized) only if it has spare capacity.
+    pub fn freeze(mut self) -> SecureBuffer {
+        let data = std::mem::take(&mut self.data);
+        if data.len() == data.capacity() {
+            return SecureBuffer {
+                data: data.into_boxed_slice(),
+            };
+        }
+        let frozen = SecureBuffer::from_boxed(Box::from(&data[..]));
+        wipe(data);
+        frozen
     }
 }
 
-impl Drop for SecureBuffer {
+impl Drop for SecureBufferMut {
     fn drop(&mut self) {
-        unsafe {
-            for byte in self.data.iter_mut() {
-                std::ptr::write_volatile(byte, 0x00);
-            }
-        }
-        self.unlock_memory();
+        wipe(std::mem::take(&mut self.data));
+    }
+}
+
+impl Deref for SecureBufferMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
     }
 }
 
+impl DerefMut for SecureBufferMut {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+impl fmt::Debug for SecureBufferMut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecureBufferMut(***REDACTED***)")
+    }
+}
+
+/// `data` in an allocation of exactly its length. `Vec::into_boxed_slice`
+/// would shrink in place or copy and free the original unzeroized.
+fn into_exact(mut data: Vec<u8>) -> Box<[u8]> {
+    if data.len() == data.capacity() {
+        return data.into_boxed_slice();
+    }
+    let exact = Box::from(&data[..]);
+    data.zeroize();
+    exact
+}
+
+/// Zeroize all of `data`'s allocation, spare capacity included, and unlock it.
+fn wipe(mut data: Vec<u8>) {
+    let (ptr, capacity) = (data.as_ptr(), data.capacity());
+    data.zeroize();
+    unlock(ptr, capacity);
+}
+
+#[cfg(unix)]
+fn lock(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    unsafe {
+        libc::mlock(ptr as *const libc::c_void, len);
+    }
+}
+
+#[cfg(unix)]
+fn unlock(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    unsafe {
+        libc::munlock(ptr as *const libc::c_void, len);
+    }
+}
+
+#[cfg(windows)]
+fn lock(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    unsafe {
+        windows_sys::Win32::System::Memory::VirtualLock(ptr.cast(), len);
+    }
+}
+
+#[cfg(windows)]
+fn unlock(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    unsafe {
+        windows_sys::Win32::System::Memory::VirtualUnlock(ptr.cast(), len);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(_ptr: *const u8, _len: usize) {
+    // No page locking on this platform
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_ptr: *const u8, _len: usize) {
+    // No page locking on this platform
+}
+
 impl Deref for SecureBuffer {
     type Target = [u8];
 
@@ -88,6 +238,12 @@ impl AsRef<[u8]> for SecureBuffer {
     }
 }
 
+impl AsMut<[u8]> for SecureBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
 impl fmt::Debug for SecureBuffer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SecureBuffer(***REDACTED***)")
@@ -130,6 +286,31 @@ mod tests {
         assert_eq!(buffer.as_str().unwrap(), s);
     }
 
+    #[test]
+    fn compares_in_constant_time() {
+        let pin = SecureBuffer::from("4921");
+        assert!(pin.ct_eq(b"4921"));
+        assert!(!pin.ct_eq(b"4920"));
+        assert!(!pin.ct_eq(b"49210"));
+    }
+
+    #[test]
+    fn grows_and_freezes_without_losing_bytes() {
+        let mut buffer = SecureBufferMut::with_capacity(2);
+        buffer.extend_from_slice(b"correct ");
+        for byte in b"horse" {
+            buffer.push(*byte);
+        }
+        buffer[0] = b'C';
+        buffer.truncate(12);
+
+        let frozen = buffer.freeze();
+        assert_eq!(frozen.as_str().unwrap(), "Correct hors");
+        let mut again = frozen.into_mut();
+        again.push(b'e');
+        assert_eq!(&*again.freeze(), b"Correct horse");
+    }
+
     #[test]
     fn test_debug_redaction() {
         let buffer = SecureBuffer::from("secret");
@@ -1,3 +1,4 @@
+pub mod at_rest;
 pub mod hash;
 pub mod memory;
 pub mod ripemd160;
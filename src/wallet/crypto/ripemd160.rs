@@ -137,4 +137,52 @@ mod tests {
         let out = ripemd160(b"");
         assert_eq!(to_hex(&out), "9c1185a5c5e9fc54612808977ee8f548b2258d31");
     }
+
+    /// Every vector published with the algorithm, plus lengths either side
+    /// of the padding and block boundaries.
+    #[cfg(feature = "exhaustive-tests")]
+    #[test]
+    fn ripemd160_reference_suite() {
+        let million = vec![b'a'; 1_000_000];
+        let a = |n: usize| &million[..n];
+        let vectors: [(&[u8], &str); 14] = [
+            (b"", "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+            (b"a", "0bdc9d2d256b3ee9daae347be6f4dc835a467ffe"),
+            (b"abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+            (
+                b"message digest",
+                "5d0689ef49d2fae572b881b123a85ffa21595f36",
+            ),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "f71c27109c692c1b56bbdceb5b9d2865b3708dbc",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "12a053384a9c0c88e405a06c27dcf49ada62eb2b",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "b0e20b6e3116640286ed3a87a5713079b21f5189",
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "9b752e45573d4b39f4dbd3323cab82bf63326bfb",
+            ),
+            (a(55), "0d8a8c9063a48576a7c97e9f95253a6e53ff6765"),
+            (a(56), "e72334b46c83cc70bef979e15453706c95b888be"),
+            (a(63), "e640041293fe663b9bf3f8c21ffecac03819e6b2"),
+            (a(64), "9dfb7d374ad924f3f88de96291c33e9abed53e32"),
+            (a(65), "99724bb11811e7166af38f671b6a082d8ab4960b"),
+            (&million, "52783243c1697bdbe16d37f97f68f08325dc1528"),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(
+                to_hex(&ripemd160(message)),
+                digest,
+                "{} bytes",
+                message.len()
+            );
+        }
+    }
 }
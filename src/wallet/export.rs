@@ -0,0 +1,345 @@
+//! Transaction history written out for accounting imports.
+//!
+//! [`Wallet::export_history`] follows the provider's pages of the wallet's
+//! history and writes one row per transaction, with amounts in whole units
+//! of the coin or token moved rather than base units, rendered by an
+//! [`AmountFormatter`].
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::monitor::dedup::EventKey;
+use crate::node::token::by_contract;
+use crate::node::utils::{AmountFormatter, AmountStyle};
+use crate::node::{ReadProvider, Transaction, TxDetails, TxQuery};
+use crate::wallet::chain::Chain;
+use crate::wallet::{Signer, Wallet};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("cannot write export: {0}")]
+    Write(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated, with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// Which way a transaction moved funds, seen from the exported address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+    /// From the address to itself.
+    #[serde(rename = "self")]
+    SelfTransfer,
+}
+
+impl Direction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+            Direction::SelfTransfer => "self",
+        }
+    }
+}
+
+/// One line of an export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportRow {
+    pub hash: String,
+    pub direction: Direction,
+    /// The other side: the recipient of an outgoing transfer, the sender of
+    /// an incoming one.
+    pub counterparty: String,
+    /// Symbol of the coin or token moved; a token's contract if it has no
+    /// known symbol.
+    pub asset: String,
+    /// In whole units; in base units for a token of unknown decimals.
+    pub amount: String,
+    /// Native fee in whole units, for transactions the address paid for and
+    /// whose fee the provider reported.
+    pub fee: Option<String>,
+    /// As the provider reports it (milliseconds on Tron).
+    pub timestamp: u64,
+    pub block_number: u64,
+    pub status: String,
+}
+
+/// Native coin of the exported chain, as the provider describes it.
+struct Native {
+    symbol: String,
+    decimals: u32,
+    network: Option<String>,
+}
+
+impl ExportRow {
+    /// The row for `tx` seen from `address`; `None` if it moved nothing of
+    /// the address's.
+    fn new(
+        tx: &Transaction,
+        address: &str,
+        native: &Native,
+        formatter: &dyn AmountFormatter,
+    ) -> Option<Self> {
+        let transfer = match &tx.details {
+            Some(TxDetails::Tvm(tvm)) if tx.token().is_some() => tvm
+                .token_transfers
+                .iter()
+                .find(|t| t.from == address || t.to == address),
+            _ => None,
+        };
+        let (from, to, value, asset, decimals) = match transfer {
+            Some(t) => {
+                let preset = native
+                    .network
+                    .as_deref()
+                    .and_then(|network| by_contract(network, &t.token));
+                (
+                    t.from.as_str(),
+                    t.to.as_str(),
+                    t.value.as_str(),
+                    preset.map_or(t.token.clone(), |p| p.symbol.to_string()),
+                    preset.map_or(0, |p| p.decimals),
+                )
+            }
+            None => (
+                tx.from.as_str(),
+                tx.to.as_str(),
+                tx.value.as_str(),
+                native.symbol.clone(),
+                native.decimals,
+            ),
+        };
+
+        let (direction, counterparty) = match (from == address, to == address) {
+            (true, true) => (Direction::SelfTransfer, to),
+            (true, false) => (Direction::Out, to),
+            (false, true) => (Direction::In, from),
+            (false, false) => return None,
+        };
+        let fee = tx
+            .fee()
+            .filter(|_| from == address || tx.from == address)
+            .map(|fee| formatter.format_amount(&fee.to_string(), native.decimals, None));
+
+        Some(Self {
+            hash: tx.hash.clone(),
+            direction,
+            counterparty: counterparty.to_string(),
+            asset,
+            amount: formatter.format_amount(value, decimals, None),
+            fee,
+            timestamp: tx.timestamp,
+            block_number: tx.block_number,
+            status: tx.status.clone(),
+        })
+    }
+
+    fn write(&self, format: ExportFormat, out: &mut dyn Write) -> Result<(), ExportError> {
+        match format {
+            ExportFormat::Csv => writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&self.hash),
+                self.direction.as_str(),
+                csv_field(&self.counterparty),
+                csv_field(&self.asset),
+                csv_field(&self.amount),
+                csv_field(self.fee.as_deref().unwrap_or("")),
+                self.timestamp,
+                self.block_number,
+                csv_field(&self.status),
+            )?,
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut *out, self).map_err(std::io::Error::from)?;
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const CSV_HEADER: &str =
+    "hash,direction,counterparty,asset,amount,fee,timestamp,block_number,status";
+
+/// `value` quoted if it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// Write this wallet's history within `query`'s time range to `out`,
+    /// following every page, and return the number of rows written. Entries
+    /// listed more than once are written once; details are decoded as in
+    /// [`history`](Self::history), which is where fees come from. Amounts
+    /// have the digits of [`AmountStyle::default`].
+    pub async fn export_history(
+        &self,
+        provider: &dyn ReadProvider,
+        query: &TxQuery,
+        format: ExportFormat,
+        out: &mut dyn Write,
+    ) -> Result<usize, WalletError> {
+        self.export_history_with(provider, query, format, &AmountStyle::default(), out)
+            .await
+    }
+
+    /// [`export_history`](Self::export_history) with amounts and fees
+    /// rendered by `formatter`, e.g. in the locale the accounting tool
+    /// imports. CSV fields holding a separator are quoted.
+    pub async fn export_history_with(
+        &self,
+        provider: &dyn ReadProvider,
+        query: &TxQuery,
+        format: ExportFormat,
+        formatter: &dyn AmountFormatter,
+        out: &mut dyn Write,
+    ) -> Result<usize, WalletError> {
+        let address = self.address()?;
+        let info = provider.chain_info().await?;
+        let native = Native {
+            symbol: info
+                .symbol
+                .unwrap_or_else(|| self.chain.id().to_uppercase()),
+            decimals: info.decimals,
+            network: info.chain_id,
+        };
+
+        if format == ExportFormat::Csv {
+            writeln!(out, "{}", CSV_HEADER).map_err(ExportError::from)?;
+        }
+        let mut query = query.clone();
+        let mut seen = HashSet::new();
+        let mut rows = 0;
+        loop {
+            let page = provider.get_transactions_page(&address, &query).await?;
+            for mut tx in page.transactions {
+                if !seen.insert(EventKey::for_transaction(self.chain.id(), &tx)) {
+                    continue;
+                }
+                tx.details = self
+                    .chain
+                    .decode_provider_tx(&tx)
+                    .ok()
+                    .flatten()
+                    .or(tx.details.take());
+                if let Some(row) = ExportRow::new(&tx, &address, &native, formatter) {
+                    row.write(format, out)?;
+                    rows += 1;
+                }
+            }
+            match page.next {
+                Some(cursor) => query = query.after(cursor),
+                None => break,
+            }
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::details::{TokenTransfer, TvmTxDetails};
+    use crate::node::token::USDT_TRON;
+    use crate::testing::{MockProvider, MockSigner};
+    use crate::wallet::chain::TRON;
+
+    #[tokio::test]
+    async fn exports_native_and_token_rows_in_whole_units() {
+        let wallet = Wallet::new(MockSigner::default(), TRON);
+        let me = wallet.address().unwrap();
+        let provider = MockProvider::new(6);
+        let tx = |hash: &str, from: &str, to: &str, value: &str, timestamp| Transaction {
+            hash: hash.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            value: value.to_string(),
+            block_number: timestamp / 3,
+            timestamp,
+            status: "SUCCESS".into(),
+            raw: None,
+            details: None,
+        };
+        provider.add_transaction(tx("aa", "TPayer", &me, "1500000", 3_000));
+        let mut usdt = tx("bb", &me, "TShop,Inc", "25000000", 6_000);
+        usdt.details = Some(TxDetails::Tvm(TvmTxDetails {
+            contract_type: "TriggerSmartContract".into(),
+            contract_address: Some(USDT_TRON.contract.into()),
+            fee: 345_000,
+            token_transfers: vec![TokenTransfer {
+                token: USDT_TRON.contract.into(),
+                from: me.clone(),
+                to: "TShop,Inc".into(),
+                value: "25000000".into(),
+            }],
+            ..TvmTxDetails::default()
+        }));
+        provider.add_transaction(usdt);
+
+        let mut csv = Vec::new();
+        let rows = wallet
+            .export_history(&provider, &TxQuery::new(), ExportFormat::Csv, &mut csv)
+            .await
+            .unwrap();
+        assert_eq!(rows, 2);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        // MockProvider's chain_info names no chain, so USDT goes by contract.
+        assert!(lines[1].starts_with(&format!(
+            "bb,out,\"TShop,Inc\",{},25000000,0.345000,",
+            USDT_TRON.contract
+        )));
+        assert!(lines[2].starts_with("aa,in,TPayer,TRON,1.500000,,3000,1000,SUCCESS"));
+
+        let mut json = Vec::new();
+        wallet
+            .export_history(
+                &provider,
+                &TxQuery::new(),
+                ExportFormat::JsonLines,
+                &mut json,
+            )
+            .await
+            .unwrap();
+        let first: ExportRow =
+            serde_json::from_str(String::from_utf8(json).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!(first.direction, Direction::Out);
+        assert_eq!(first.fee.as_deref(), Some("0.345000"));
+
+        let mut csv = Vec::new();
+        wallet
+            .export_history_with(
+                &provider,
+                &TxQuery::new(),
+                ExportFormat::Csv,
+                &AmountStyle::continental(),
+                &mut csv,
+            )
+            .await
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(
+            csv.lines()
+                .nth(2)
+                .unwrap()
+                .starts_with("aa,in,TPayer,TRON,\"1,5\",,")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Speeding up stuck UTXO sends by replace-by-fee (BIP-125).
+//!
+//! A send made with [`SendOptions::replaceable`] can be replaced while it is
+//! unconfirmed by a version spending the same coins with a higher fee. The
+//! replacement pays the same recipients; the extra fee comes out of the change.
+
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::node::Provider;
+use crate::node::utils::format_units;
+use crate::wallet::chain::{Chain, ChainError, UtxoChain};
+use crate::wallet::options::SendOptions;
+use crate::wallet::{Signer, Wallet};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeeBumpError {
+    #[error("transaction {0} not found")]
+    NotFound(String),
+    #[error("transaction {0} is already confirmed")]
+    Confirmed(String),
+    #[error("transaction {txid} was sent by {from}, not this wallet")]
+    NotSender { txid: String, from: String },
+    #[error("the provider returned no raw form of transaction {0}")]
+    NoRawTransaction(String),
+}
+
+impl<T: Signer> Wallet<UtxoChain, T> {
+    /// Replace the wallet's unconfirmed send `txid` with one paying
+    /// `new_fee_rate` base units per kilobyte, and broadcast it. Returns the
+    /// replacement's hash; once it confirms, `txid` never will.
+    pub async fn bump_fee(
+        &self,
+        provider: &dyn Provider,
+        txid: &str,
+        new_fee_rate: u64,
+    ) -> Result<String, WalletError> {
+        let from = self.address()?;
+        let original = provider
+            .get_transaction(txid)
+            .await?
+            .ok_or_else(|| FeeBumpError::NotFound(txid.to_string()))?;
+        if original.block_number > 0 {
+            return Err(FeeBumpError::Confirmed(txid.to_string()).into());
+        }
+        if original.from != from {
+            return Err(FeeBumpError::NotSender {
+                txid: txid.to_string(),
+                from: original.from,
+            }
+            .into());
+        }
+        let raw = original
+            .raw
+            .as_ref()
+            .ok_or_else(|| FeeBumpError::NoRawTransaction(txid.to_string()))?;
+
+        let replacement = self.chain.replacement(raw, &from, new_fee_rate)?;
+        let fee = replacement.fee()?;
+        let options = SendOptions {
+            fee_limit: Some(fee),
+            summary: Some(format!(
+                "Replace {} on {} with a fee of {}",
+                txid,
+                self.chain.id(),
+                format_units(&fee.to_string(), self.chain.decimals())
+            )),
+            ..SendOptions::default()
+        };
+        let amount = original.value.parse().map_err(|_| {
+            ChainError::Other(format!(
+                "transaction {} has an unreadable value {:?}",
+                txid, original.value
+            ))
+        })?;
+        self.check_spend(&[self.spend_request(&from, &original.to, amount, &options)])
+            .await?;
+        let signed = self
+            .sign_send(
+                &replacement.into_raw(),
+                &from,
+                &original.to,
+                amount,
+                &options,
+                &[],
+            )
+            .await?;
+        let tx_hash = provider.broadcast_transaction(&signed).await?;
+        self.watch(&tx_hash, signed);
+        Ok(tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, RawTransaction, ReadProvider, Transaction, TransactionProvider};
+    use crate::wallet::chain::LITECOIN;
+    use crate::wallet::chain::utxo::RBF_SEQUENCE;
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+
+    /// Knows one transaction from `from` at `block_height`, and accepts
+    /// broadcasts of raw bytes only.
+    struct Node {
+        from: String,
+        block_height: i64,
+        value: &'static str,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Node {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(Vec::new())
+        }
+        async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+            let raw = serde_json::json!({
+                "hash": hash,
+                "block_height": self.block_height,
+                "inputs": [{
+                    "prev_hash": "11".repeat(32),
+                    "output_index": 0,
+                    "output_value": 100_000,
+                    "addresses": [self.from],
+                    "sequence": RBF_SEQUENCE,
+                }],
+                "outputs": [
+                    { "value": 50_000, "addresses": [TO] },
+                    { "value": 49_800, "addresses": [self.from] },
+                ],
+            });
+            Ok(Some(Transaction {
+                hash: hash.to_string(),
+                from: self.from.clone(),
+                to: TO.to_string(),
+                value: self.value.into(),
+                block_number: self.block_height.max(0) as u64,
+                timestamp: 0,
+                status: "PENDING".into(),
+                raw: Some(raw),
+                details: None,
+            }))
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Node {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            Err(NodeError::Api("not used".into()))
+        }
+        async fn broadcast_transaction(&self, tx: &RawTransaction) -> Result<String, NodeError> {
+            match tx {
+                RawTransaction::Bytes(_) => Ok("replacement".into()),
+                _ => Err(NodeError::Api("expected raw bytes".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn replaces_unconfirmed_sends_of_the_wallet() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), LITECOIN);
+        let from = wallet.address().unwrap();
+        let pending = Node {
+            from: from.clone(),
+            block_height: -1,
+            value: "50000",
+        };
+        assert_eq!(
+            wallet.bump_fee(&pending, "stuck", 5_000).await.unwrap(),
+            "replacement"
+        );
+        // 226 bytes at 1000 per kB cannot outbid the 200 already paid by the relay fee.
+        assert!(matches!(
+            wallet.bump_fee(&pending, "stuck", 1_000).await,
+            Err(WalletError::Chain(_))
+        ));
+
+        let confirmed = Node {
+            from: from.clone(),
+            block_height: 7,
+            value: "50000",
+        };
+        assert!(matches!(
+            wallet.bump_fee(&confirmed, "stuck", 5_000).await,
+            Err(WalletError::FeeBump(FeeBumpError::Confirmed(_)))
+        ));
+        let foreign = Node {
+            from: TO.to_string(),
+            block_height: -1,
+            value: "50000",
+        };
+        assert!(matches!(
+            wallet.bump_fee(&foreign, "stuck", 5_000).await,
+            Err(WalletError::FeeBump(FeeBumpError::NotSender { .. }))
+        ));
+        // Policies and caps must not see an unreadable value as a free send.
+        let garbled = Node {
+            from,
+            block_height: -1,
+            value: "lots",
+        };
+        assert!(matches!(
+            wallet.bump_fee(&garbled, "stuck", 5_000).await,
+            Err(WalletError::Chain(ChainError::Other(_)))
+        ));
+    }
+}
@@ -0,0 +1,569 @@
+#[cfg(feature = "network")]
+use std::collections::HashMap;
+#[cfg(feature = "network")]
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::WalletError;
+#[cfg(feature = "network")]
+use crate::monitor::confirmations::WaitOptions;
+#[cfg(feature = "network")]
+use crate::node::network::tron::{TronProvider, TronResource};
+#[cfg(feature = "network")]
+use crate::wallet::chain::TvmChain;
+#[cfg(feature = "network")]
+use crate::wallet::options::SendOptions;
+#[cfg(feature = "network")]
+use crate::wallet::{BuiltSend, Signer, Wallet};
+
+/// A funded account that covers network fees on behalf of the sender.
+///
+/// The send pipeline calls [`FeePayer::sponsor`] before creating the user's
+/// transaction, so the sponsor can top up whatever the chain charges fees in,
+/// and [`FeePayer::release`] with what it returned once the send is over. A
+/// send whose transaction expired and was rebuilt is sponsored again, and
+/// each sponsorship released on its own.
+#[async_trait]
+pub trait FeePayer: Send + Sync {
+    /// Whether this payer can sponsor transactions on the given chain.
+    fn supports(&self, chain_id: &str) -> bool;
+
+    /// Make `beneficiary` able to pay for a transfer of `amount`.
+    async fn sponsor(&self, beneficiary: &str, amount: u64) -> Result<Sponsorship, WalletError>;
+
+    /// Called once per `sponsorship` after the send it was for: with the
+    /// hash that send was broadcast as, or `None` if it failed before
+    /// reaching the chain. The send is over, so there is nothing left to
+    /// abort.
+    async fn release(&self, beneficiary: &str, sponsorship: &Sponsorship, tx_hash: Option<&str>) {
+        let _ = (beneficiary, sponsorship, tx_hash);
+    }
+}
+
+/// What one [`FeePayer::sponsor`] call did, for [`FeePayer::release`] to
+/// undo without touching the payer's other sponsorships.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sponsorship {
+    /// Hash of the sponsoring transaction, if one was sent.
+    pub tx_hash: Option<String>,
+    /// The payer's own id for what it has to undo; `None` if nothing.
+    pub id: Option<u64>,
+}
+
+/// Smallest delegation the network accepts, in Sun.
+#[cfg(feature = "network")]
+const MIN_DELEGATION: u64 = 1_000_000;
+
+/// Fee payer for TVM chains that delegates staked bandwidth/energy from a gas tank account.
+///
+/// Each sponsored send gets just the stake its sender lacks for `units` of
+/// the resource, delegated and confirmed before the send is built. Once the
+/// send confirms, or fails, that delegation is undelegated again; others
+/// to the same sender, for sends still in flight, stay.
+#[cfg(feature = "network")]
+pub struct TronResourceSponsor<S: Signer> {
+    tank: Arc<Wallet<TvmChain, S>>,
+    provider: Arc<TronProvider>,
+    resource: TronResource,
+    /// Units of `resource` a sponsored send uses.
+    units: u64,
+    wait: WaitOptions,
+    delegations: Arc<Mutex<Delegations>>,
+}
+
+/// Delegations made and not yet undelegated, by sponsorship id.
+#[cfg(feature = "network")]
+#[derive(Debug, Default)]
+struct Delegations {
+    next_id: u64,
+    /// Beneficiary and staked Sun of each.
+    open: HashMap<u64, (String, u64)>,
+}
+
+#[cfg(feature = "network")]
+impl Delegations {
+    fn record(&mut self, beneficiary: &str, balance: u64) -> u64 {
+        self.next_id += 1;
+        self.open
+            .insert(self.next_id, (beneficiary.to_string(), balance));
+        self.next_id
+    }
+
+    fn to(&self, beneficiary: &str) -> u64 {
+        self.open
+            .values()
+            .filter(|(to, _)| to == beneficiary)
+            .map(|(_, balance)| balance)
+            .sum()
+    }
+
+    /// Remove every delegation to `beneficiary`, by id.
+    fn take_all(&mut self, beneficiary: &str) -> Vec<(u64, u64)> {
+        let ids: Vec<u64> = self
+            .open
+            .iter()
+            .filter(|(_, (to, _))| to == beneficiary)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .map(|id| (id, self.open.remove(&id).expect("listed above").1))
+            .collect()
+    }
+}
+
+/// Delegation of `shortfall_stake` Sun, raised to the network's minimum.
+#[cfg(feature = "network")]
+fn delegation_for(shortfall_stake: u64) -> u64 {
+    shortfall_stake.max(MIN_DELEGATION)
+}
+
+#[cfg(feature = "network")]
+impl<S: Signer + 'static> TronResourceSponsor<S> {
+    pub fn new(
+        tank: Wallet<TvmChain, S>,
+        provider: Arc<TronProvider>,
+        resource: TronResource,
+        units: u64,
+    ) -> Self {
+        Self {
+            tank: Arc::new(tank),
+            provider,
+            resource,
+            units,
+            wait: WaitOptions::default(),
+            delegations: Arc::default(),
+        }
+    }
+
+    /// How long to wait for delegations, and for sponsored sends, to confirm.
+    pub fn with_wait(mut self, wait: WaitOptions) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Staked Sun currently delegated to `beneficiary` by this sponsor.
+    pub fn delegated_to(&self, beneficiary: &str) -> u64 {
+        self.delegations.lock().unwrap().to(beneficiary)
+    }
+
+    /// Reclaim everything this sponsor still has delegated to `beneficiary`,
+    /// including delegations for sends still in flight. Returns the hash of
+    /// the undelegating transaction, if one was sent.
+    pub async fn reclaim(&self, beneficiary: &str) -> Result<Option<String>, WalletError> {
+        let taken = self.delegations.lock().unwrap().take_all(beneficiary);
+        undelegate(
+            &self.tank,
+            &self.provider,
+            &self.delegations,
+            beneficiary,
+            taken,
+            self.resource,
+        )
+        .await
+    }
+}
+
+/// Undelegate the delegations `taken` from `beneficiary` in one
+/// transaction, recording them again if that fails.
+#[cfg(feature = "network")]
+async fn undelegate<S: Signer>(
+    tank: &Wallet<TvmChain, S>,
+    provider: &TronProvider,
+    delegations: &Mutex<Delegations>,
+    beneficiary: &str,
+    taken: Vec<(u64, u64)>,
+    resource: TronResource,
+) -> Result<Option<String>, WalletError> {
+    if taken.is_empty() {
+        return Ok(None);
+    }
+    let balance = taken.iter().map(|(_, balance)| balance).sum();
+    let result = async {
+        let owner = tank.address()?;
+        let send = BuiltSend {
+            to: beneficiary.to_string(),
+            amount: 0,
+            token: None,
+            summary: format!("Take back {:?} lent to {}", resource, beneficiary),
+        };
+        tank.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider
+                .create_undelegate_resource(&owner, beneficiary, balance, resource)
+                .await?)
+        })
+        .await
+    }
+    .await;
+    if result.is_err() {
+        let mut delegations = delegations.lock().unwrap();
+        for (id, balance) in taken {
+            delegations
+                .open
+                .insert(id, (beneficiary.to_string(), balance));
+        }
+    }
+    result.map(Some)
+}
+
+#[cfg(feature = "network")]
+#[async_trait]
+impl<S: Signer + 'static> FeePayer for TronResourceSponsor<S> {
+    fn supports(&self, chain_id: &str) -> bool {
+        chain_id == self.tank.chain.name
+    }
+
+    async fn sponsor(&self, beneficiary: &str, _amount: u64) -> Result<Sponsorship, WalletError> {
+        let available = self.provider.account_resources(beneficiary).await?;
+        let available = match self.resource {
+            TronResource::Energy => available.energy,
+            _ => available.bandwidth,
+        };
+        let shortfall = self.units.saturating_sub(available);
+        if shortfall == 0 {
+            return Ok(Sponsorship::default());
+        }
+
+        let owner = self.tank.address()?;
+        let balance = delegation_for(
+            self.provider
+                .stake_for_resource(&owner, self.resource, shortfall)
+                .await?,
+        );
+        let send = BuiltSend {
+            to: beneficiary.to_string(),
+            amount: balance,
+            token: None,
+            summary: format!("Lend {:?} to {}", self.resource, beneficiary),
+        };
+        let provider = &*self.provider;
+        let tx_hash = self
+            .tank
+            .send_built(provider, send, &SendOptions::default(), |_| async move {
+                Ok(provider
+                    .create_delegate_resource(&owner, beneficiary, balance, self.resource)
+                    .await?)
+            })
+            .await?;
+        let id = self
+            .delegations
+            .lock()
+            .unwrap()
+            .record(beneficiary, balance);
+        // The sender's transaction is built next and must find the resources there.
+        self.tank
+            .wait_for_receipt(&*self.provider, &tx_hash, 1, &self.wait)
+            .await?;
+        Ok(Sponsorship {
+            tx_hash: Some(tx_hash),
+            id: Some(id),
+        })
+    }
+
+    /// Undelegate `sponsorship`'s stake once `tx_hash` confirms, on a task of
+    /// its own so the send returns without waiting. If undelegating fails,
+    /// the stake stays recorded for [`reclaim`](TronResourceSponsor::reclaim).
+    async fn release(&self, beneficiary: &str, sponsorship: &Sponsorship, tx_hash: Option<&str>) {
+        let Some(id) = sponsorship.id else {
+            return;
+        };
+        let tank = self.tank.clone();
+        let provider = self.provider.clone();
+        let delegations = self.delegations.clone();
+        let resource = self.resource;
+        let wait = self.wait;
+        let beneficiary = beneficiary.to_string();
+        let tx_hash = tx_hash.map(str::to_string);
+        tokio::spawn(async move {
+            if let Some(tx_hash) = &tx_hash {
+                // A failed or unconfirmed send no longer needs the stake either.
+                let _ = tank.wait_for_receipt(&*provider, tx_hash, 1, &wait).await;
+            }
+            // Taken only now: `reclaim` may have undelegated it meanwhile.
+            let taken = delegations.lock().unwrap().open.remove(&id);
+            let taken = taken.map(|(_, balance)| vec![(id, balance)]);
+            let _ = undelegate(
+                &tank,
+                &provider,
+                &delegations,
+                &beneficiary,
+                taken.unwrap_or_default(),
+                resource,
+            )
+            .await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::node::NodeError;
+    use crate::test_utils::{OfflineProvider, tron_transfer};
+    use crate::testing::{MockCall, MockProvider};
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::options::SendOptions;
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[derive(Default)]
+    struct RecordingPayer {
+        sponsored: Mutex<Vec<(String, u64)>>,
+        released: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl FeePayer for RecordingPayer {
+        fn supports(&self, chain_id: &str) -> bool {
+            chain_id == "tron"
+        }
+
+        async fn sponsor(
+            &self,
+            beneficiary: &str,
+            amount: u64,
+        ) -> Result<Sponsorship, WalletError> {
+            self.sponsored
+                .lock()
+                .unwrap()
+                .push((beneficiary.to_string(), amount));
+            Ok(Sponsorship::default())
+        }
+
+        async fn release(&self, beneficiary: &str, _: &Sponsorship, tx_hash: Option<&str>) {
+            self.released
+                .lock()
+                .unwrap()
+                .push((beneficiary.to_string(), tx_hash.map(str::to_string)));
+        }
+    }
+
+    #[tokio::test]
+    async fn sponsor_runs_before_transaction_creation() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let payer = Arc::new(RecordingPayer::default());
+        let options = SendOptions {
+            fee_payer: Some(payer.clone()),
+            ..Default::default()
+        };
+
+        // Creation fails offline, but the payer must already have been asked to sponsor.
+        let result = wallet
+            .send_coins_with_options(
+                &OfflineProvider,
+                "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT",
+                5,
+                &options,
+            )
+            .await;
+        assert!(matches!(result, Err(WalletError::Node(_))));
+        assert_eq!(
+            *payer.sponsored.lock().unwrap(),
+            vec![(wallet.address().unwrap(), 5)]
+        );
+        // The failed send is over, so the payer may take its resources back.
+        assert_eq!(
+            *payer.released.lock().unwrap(),
+            vec![(wallet.address().unwrap(), None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn release_gets_the_hash_of_the_sponsored_send() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let payer = Arc::new(RecordingPayer::default());
+        let options = SendOptions {
+            fee_payer: Some(payer.clone()),
+            ..Default::default()
+        };
+        let provider = MockProvider::new(6).on_create(|_, _, _| Ok(tron_transfer()));
+        let tx_hash = wallet
+            .send_coins_with_options(&provider, "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT", 5, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            *payer.released.lock().unwrap(),
+            vec![(wallet.address().unwrap(), Some(tx_hash))]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_rebuilt_send_releases_each_sponsorship() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let payer = Arc::new(RecordingPayer::default());
+        let options = SendOptions {
+            fee_payer: Some(payer.clone()),
+            ..Default::default()
+        };
+        let provider = MockProvider::new(6).on_create(|_, _, _| Ok(tron_transfer()));
+        provider.fail_next(
+            MockCall::BroadcastTransaction,
+            NodeError::Api("TRANSACTION_EXPIRATION_ERROR".into()),
+        );
+        let tx_hash = wallet
+            .send_coins_with_options(&provider, "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT", 5, &options)
+            .await
+            .unwrap();
+
+        let from = wallet.address().unwrap();
+        assert_eq!(payer.sponsored.lock().unwrap().len(), 2);
+        // The expired build never reached the chain; only the second did.
+        assert_eq!(
+            *payer.released.lock().unwrap(),
+            vec![(from.clone(), None), (from, Some(tx_hash))]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_chain_is_rejected() {
+        let wallet = Wallet::new(
+            LocalSigner::from_bytes([1u8; 32]).unwrap(),
+            crate::wallet::chain::LITECOIN,
+        );
+        let options = SendOptions {
+            fee_payer: Some(Arc::new(RecordingPayer::default())),
+            ..Default::default()
+        };
+        let result = wallet
+            .send_coins_with_options(
+                &OfflineProvider,
+                "LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc",
+                5_000,
+                &options,
+            )
+            .await;
+        assert!(matches!(result, Err(WalletError::FeePayer(_))));
+    }
+
+    #[cfg(feature = "network")]
+    mod tron {
+        use super::*;
+        use crate::test_utils::http::{Reply, serve};
+
+        const BENEFICIARY: &str = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+        fn sponsor(url: String, units: u64) -> TronResourceSponsor<LocalSigner> {
+            TronResourceSponsor::new(
+                Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON),
+                Arc::new(TronProvider::with_url(url)),
+                TronResource::Energy,
+                units,
+            )
+        }
+
+        fn resources(energy: u64) -> Reply {
+            Reply::new(
+                200,
+                serde_json::json!({
+                    "EnergyLimit": energy,
+                    "TotalEnergyLimit": 90_000_000_000u64,
+                    "TotalEnergyWeight": 19_000_000_000u64,
+                })
+                .to_string(),
+            )
+        }
+
+        /// A transaction to sign that also reads as an accepted broadcast,
+        /// so replies fit requests in any order.
+        fn accepted() -> Reply {
+            let mut tx = tron_transfer().as_json().unwrap().clone();
+            tx["result"] = true.into();
+            tx["txid"] = "undelegated".into();
+            Reply::new(200, tx.to_string())
+        }
+
+        /// Staked Sun the request of `endpoint` among `requests` moved.
+        fn balances(requests: &[String], endpoint: &str) -> Vec<u64> {
+            let mut balances: Vec<u64> = requests
+                .iter()
+                .filter(|request| request.contains(endpoint))
+                .map(|request| {
+                    let body = request.split("\r\n\r\n").nth(1).unwrap();
+                    serde_json::from_str::<serde_json::Value>(body).unwrap()["balance"]
+                        .as_u64()
+                        .unwrap()
+                })
+                .collect();
+            balances.sort();
+            balances
+        }
+
+        #[test]
+        fn delegations_are_at_least_the_network_minimum() {
+            assert_eq!(delegation_for(211_112), MIN_DELEGATION);
+            assert_eq!(delegation_for(10_555_555_556), 10_555_555_556);
+        }
+
+        #[tokio::test]
+        async fn nothing_is_delegated_without_a_shortfall() {
+            let (url, server) = serve(vec![resources(65_000)]).await;
+            let sponsor = sponsor(url, 65_000);
+
+            let sponsorship = sponsor.sponsor(BENEFICIARY, 5).await.unwrap();
+            assert_eq!(sponsorship, Sponsorship::default());
+            assert_eq!(sponsor.delegated_to(BENEFICIARY), 0);
+            assert_eq!(server.await.unwrap().len(), 1);
+        }
+
+        #[tokio::test]
+        async fn delegates_the_stake_for_the_shortfall() {
+            // 50,000 energy short at 19e9 / 90e9 Sun per unit, and 1 short,
+            // which the network minimum rounds up.
+            for (units, delegated) in [(65_000, 10_555_555_556), (15_001, MIN_DELEGATION)] {
+                let refused = Reply::new(200, r#"{"Error":"refused"}"#);
+                let (url, server) = serve(vec![resources(15_000), resources(0), refused]).await;
+                let sponsor = sponsor(url, units);
+
+                assert!(sponsor.sponsor(BENEFICIARY, 5).await.is_err());
+                let requests = server.await.unwrap();
+                assert_eq!(balances(&requests, "delegateresource"), [delegated]);
+                assert_eq!(sponsor.delegated_to(BENEFICIARY), 0);
+            }
+        }
+
+        #[tokio::test]
+        async fn release_undelegates_only_its_own_sponsorship() {
+            let (url, server) = serve(vec![accepted(), accepted(), accepted(), accepted()]).await;
+            let sponsor = sponsor(url, 65_000);
+            let (first, second) = {
+                let mut delegations = sponsor.delegations.lock().unwrap();
+                (
+                    delegations.record(BENEFICIARY, 2_000_000),
+                    delegations.record(BENEFICIARY, 3_000_000),
+                )
+            };
+
+            let first = Sponsorship {
+                tx_hash: None,
+                id: Some(first),
+            };
+            sponsor.release(BENEFICIARY, &first, None).await;
+            while sponsor.delegations.lock().unwrap().open.len() > 1 {
+                tokio::task::yield_now().await;
+            }
+            // The send the second delegation is for is still in flight.
+            assert_eq!(sponsor.delegated_to(BENEFICIARY), 3_000_000);
+            assert!(
+                sponsor
+                    .delegations
+                    .lock()
+                    .unwrap()
+                    .open
+                    .contains_key(&second)
+            );
+
+            assert_eq!(
+                sponsor.reclaim(BENEFICIARY).await.unwrap().as_deref(),
+                Some("undelegated")
+            );
+            assert_eq!(sponsor.delegated_to(BENEFICIARY), 0);
+            let requests = server.await.unwrap();
+            assert_eq!(
+                balances(&requests, "undelegateresource"),
+                [2_000_000, 3_000_000]
+            );
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Interception points around the send pipeline.
+//!
+//! A [`TransactionHook`] added with
+//! [`Wallet::with_hook`](crate::wallet::Wallet::with_hook) is called before
+//! the transaction is created, again with the exact bytes about to be
+//! signed, and once the transaction is broadcast. Hooks run in the order
+//! they were added; the first to abort stops the send.
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::node::RawTransaction;
+use crate::wallet::options::SendOptions;
+use crate::wallet::signer::SigningContext;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HookError {
+    #[error("send aborted by hook: {0}")]
+    Aborted(String),
+}
+
+/// Observer, and possibly gatekeeper, of a wallet's sends. Every method
+/// does nothing by default, so a hook implements only what it needs.
+#[async_trait]
+pub trait TransactionHook: Send + Sync {
+    /// Called before the provider builds the transaction. Changes to
+    /// `options`, e.g. raising [`SendOptions::fee_priority`], apply to the
+    /// rest of the send.
+    async fn pre_create(
+        &self,
+        send: &SigningContext,
+        options: &mut SendOptions,
+    ) -> Result<(), HookError> {
+        let _ = (send, options);
+        Ok(())
+    }
+
+    /// Called with `raw_tx` and the payloads the chain prepared from it,
+    /// just before they go to the signer.
+    async fn pre_sign(
+        &self,
+        send: &SigningContext,
+        raw_tx: &RawTransaction,
+        bytes_to_sign: &[Vec<u8>],
+    ) -> Result<(), HookError> {
+        let _ = (send, raw_tx, bytes_to_sign);
+        Ok(())
+    }
+
+    /// Called once the node accepted the transaction as `tx_hash`. The
+    /// send has happened, so there is nothing left to abort.
+    async fn post_broadcast(&self, send: &SigningContext, tx_hash: &str) {
+        let _ = (send, tx_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::WalletError;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+    /// Records the fee rate each send is created with, and aborts sends
+    /// above `limit`.
+    struct Recorder {
+        limit: u64,
+        seen: Mutex<Vec<Option<u64>>>,
+    }
+
+    #[async_trait]
+    impl TransactionHook for Recorder {
+        async fn pre_create(
+            &self,
+            send: &SigningContext,
+            options: &mut SendOptions,
+        ) -> Result<(), HookError> {
+            if send.amount > self.limit {
+                return Err(HookError::Aborted(format!("{} is too much", send.amount)));
+            }
+            options.fee_rate = Some(42);
+            Ok(())
+        }
+    }
+
+    struct SeesFeeRate(Arc<Recorder>);
+
+    #[async_trait]
+    impl TransactionHook for SeesFeeRate {
+        async fn pre_create(
+            &self,
+            _: &SigningContext,
+            options: &mut SendOptions,
+        ) -> Result<(), HookError> {
+            self.0.seen.lock().unwrap().push(options.fee_rate);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_see_each_others_changes_and_can_abort() {
+        let recorder = Arc::new(Recorder {
+            limit: 1_000,
+            seen: Mutex::new(Vec::new()),
+        });
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_hook(recorder.clone())
+            .with_hook(Arc::new(SeesFeeRate(recorder.clone())));
+
+        // Let through to the offline provider, which cannot build it.
+        assert!(matches!(
+            wallet.send_coins(&OfflineProvider, TO, 500).await,
+            Err(WalletError::Node(_))
+        ));
+        assert!(matches!(
+            wallet.send_coins(&OfflineProvider, TO, 5_000).await,
+            Err(WalletError::Hook(HookError::Aborted(_)))
+        ));
+        assert_eq!(*recorder.seen.lock().unwrap(), [Some(42)]);
+    }
+}
@@ -0,0 +1,339 @@
+//! Sends that are safe to retry.
+//!
+//! A send made with [`SendOptions::idempotency_key`] is recorded, signed, in
+//! the wallet's [`IdempotencyStore`] before it is broadcast. Sending again
+//! with the same key never builds a second transaction: the recorded one is
+//! looked up on chain, and broadcast again only if the network does not know
+//! it, so a broadcast that timed out cannot turn into two payments.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::node::{Provider, RawTransaction};
+use crate::persist::{self, Persist};
+use crate::wallet::chain::Chain;
+use crate::wallet::options::SendOptions;
+use crate::wallet::{Signer, Wallet};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdempotencyError {
+    #[error("idempotency key {0} was already used for a different send")]
+    KeyReused(String),
+    #[error("idempotency store unavailable: {0}")]
+    Storage(String),
+}
+
+/// A signed send recorded under its idempotency key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotentSend {
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the chain's base unit.
+    pub amount: u64,
+    /// Hash of `signed`, once known: worked out locally where the chain
+    /// can, else as the provider reported it at broadcast.
+    pub tx_hash: Option<String>,
+    pub signed: RawTransaction,
+}
+
+/// Where a wallet records its idempotent sends; see
+/// [`Wallet::with_idempotency_store`](crate::wallet::Wallet::with_idempotency_store).
+/// Shared by several processes, it must make a `put` visible to every later `get`.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<IdempotentSend>, IdempotencyError>;
+    async fn put(&self, key: &str, send: &IdempotentSend) -> Result<(), IdempotencyError>;
+}
+
+/// Sends of a [`MemoryIdempotencyStore`] by key, as kept in its state file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyLog {
+    pub sends: BTreeMap<String, IdempotentSend>,
+}
+
+impl Persist for IdempotencyLog {
+    const KIND: &'static str = "idempotency_log";
+    const VERSION: u16 = 1;
+}
+
+/// In-process [`IdempotencyStore`], optionally saved to a file so retries
+/// after a restart are recognised too.
+#[derive(Default)]
+pub struct MemoryIdempotencyStore {
+    log: Mutex<IdempotencyLog>,
+    state_file: Option<PathBuf>,
+}
+
+impl MemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the log in `path`, loading it if the file exists.
+    pub fn with_state_file(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(log) = persist::load::<IdempotencyLog>(&path)? {
+            self.log = Mutex::new(log);
+        }
+        self.state_file = Some(path);
+        Ok(self)
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyStore {
+    async fn get(&self, key: &str) -> Result<Option<IdempotentSend>, IdempotencyError> {
+        Ok(self.log.lock().unwrap().sends.get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, send: &IdempotentSend) -> Result<(), IdempotencyError> {
+        let mut log = self.log.lock().unwrap();
+        log.sends.insert(key.to_string(), send.clone());
+        match &self.state_file {
+            Some(path) => persist::save(path, &*log).map_err(|e| {
+                IdempotencyError::Storage(format!("cannot save {}: {}", path.display(), e))
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// The outcome of the send already recorded under `options`' idempotency
+    /// key, if there is one: its hash, after broadcasting it again should the
+    /// network not know it. An `amount` of `None`, as for a sweep, accepts
+    /// whatever amount was recorded.
+    pub(super) async fn resume_send(
+        &self,
+        provider: &dyn Provider,
+        from: &str,
+        to: &str,
+        amount: Option<u64>,
+        options: &SendOptions,
+    ) -> Result<Option<String>, WalletError> {
+        let (Some(store), Some(key)) = (&self.idempotency, &options.idempotency_key) else {
+            return Ok(None);
+        };
+        let Some(mut send) = store.get(key).await? else {
+            return Ok(None);
+        };
+        if send.chain != self.chain.id()
+            || send.from != from
+            || send.to != to
+            || amount.is_some_and(|amount| send.amount != amount)
+        {
+            return Err(IdempotencyError::KeyReused(key.clone()).into());
+        }
+        if let Some(tx_hash) = &send.tx_hash
+            && provider.get_transaction(tx_hash).await?.is_some()
+        {
+            return Ok(Some(tx_hash.clone()));
+        }
+
+        let tx_hash = self.broadcast_checked(provider, &send.signed).await?;
+        if send.tx_hash.is_none() {
+            send.tx_hash = Some(tx_hash.clone());
+            store.put(key, &send).await?;
+        }
+        self.watch(&tx_hash, send.signed);
+        Ok(Some(tx_hash))
+    }
+
+    /// Step 5 of a send: record `signed_tx` under `options`' idempotency key,
+    /// if any, then broadcast it.
+    pub(super) async fn broadcast_send(
+        &self,
+        provider: &dyn Provider,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        signed_tx: RawTransaction,
+    ) -> Result<String, WalletError> {
+        let record = match (&self.idempotency, &options.idempotency_key) {
+            (Some(store), Some(key)) => {
+                let send = IdempotentSend {
+                    chain: self.chain.id().to_string(),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    amount,
+                    tx_hash: self.chain.transaction_hash(&signed_tx).ok(),
+                    signed: signed_tx.clone(),
+                };
+                store.put(key, &send).await?;
+                Some((store, key, send))
+            }
+            _ => None,
+        };
+
+        let tx_hash = self.broadcast_checked(provider, &signed_tx).await?;
+        if let Some((store, key, mut send)) = record
+            && send.tx_hash.is_none()
+        {
+            send.tx_hash = Some(tx_hash.clone());
+            store.put(key, &send).await?;
+        }
+        self.watch(&tx_hash, signed_tx);
+        Ok(tx_hash)
+    }
+
+    /// Broadcast `signed_tx`. Should the broadcast fail but the network know
+    /// the transaction after all, as when only the response was lost, that
+    /// is success.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "broadcast", skip_all))]
+    pub(super) async fn broadcast_checked(
+        &self,
+        provider: &dyn Provider,
+        signed_tx: &RawTransaction,
+    ) -> Result<String, WalletError> {
+        let result = match provider.broadcast_transaction(signed_tx).await {
+            Ok(tx_hash) => Ok(tx_hash),
+            Err(e) => match self.chain.transaction_hash(signed_tx) {
+                Ok(tx_hash) if matches!(provider.get_transaction(&tx_hash).await, Ok(Some(_))) => {
+                    Ok(tx_hash)
+                }
+                _ => Err(e.into()),
+            },
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.broadcast(self.chain.id(), result.is_ok());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::node::{NodeError, ReadProvider, Transaction, TransactionProvider};
+    use crate::wallet::chain::LITECOIN;
+    use crate::wallet::chain::utxo::{SighashType, UtxoInput, UtxoTxBuilder, txid};
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+
+    /// Accepts every broadcast but times out answering it.
+    struct Node {
+        from: String,
+        created: AtomicUsize,
+        known: Mutex<HashSet<String>>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Node {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(Vec::new())
+        }
+        async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+            Ok(self
+                .known
+                .lock()
+                .unwrap()
+                .contains(hash)
+                .then(|| Transaction {
+                    hash: hash.to_string(),
+                    from: self.from.clone(),
+                    to: TO.to_string(),
+                    value: "0".into(),
+                    block_number: 0,
+                    timestamp: 0,
+                    status: "PENDING".into(),
+                    raw: None,
+                    details: None,
+                }))
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("100000".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Node {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            to: &str,
+            amount: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            self.created.fetch_add(1, Ordering::SeqCst);
+            let tx = UtxoTxBuilder::new(&LITECOIN)
+                .input(UtxoInput {
+                    prev_hash: [7; 32],
+                    output_index: 0,
+                    value: 100_000,
+                    script_pubkey: LITECOIN.script_pubkey(&self.from).unwrap(),
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                })
+                .pay(to, amount)
+                .unwrap()
+                .build()
+                .unwrap();
+            Ok(tx.into_raw())
+        }
+        async fn broadcast_transaction(&self, tx: &RawTransaction) -> Result<String, NodeError> {
+            let RawTransaction::Bytes(bytes) = tx else {
+                return Err(NodeError::Api("expected raw bytes".into()));
+            };
+            self.known.lock().unwrap().insert(txid(bytes).unwrap());
+            Err(NodeError::Network("timed out".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_with_the_same_key_send_once() {
+        let store = Arc::new(MemoryIdempotencyStore::new());
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), LITECOIN)
+            .with_idempotency_store(store.clone());
+        let node = Node {
+            from: wallet.address().unwrap(),
+            created: AtomicUsize::new(0),
+            known: Mutex::new(HashSet::new()),
+        };
+        let options = SendOptions {
+            idempotency_key: Some("withdrawal-17".into()),
+            ..SendOptions::default()
+        };
+
+        // The broadcast timed out, but the network has the transaction.
+        let tx_hash = wallet
+            .send_coins_with_options(&node, TO, 50_000, &options)
+            .await
+            .unwrap();
+        assert!(node.known.lock().unwrap().contains(&tx_hash));
+        let retried = wallet
+            .send_coins_with_options(&node, TO, 50_000, &options)
+            .await
+            .unwrap();
+        assert_eq!(retried, tx_hash);
+        assert_eq!(node.created.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            store.get("withdrawal-17").await.unwrap().unwrap().tx_hash,
+            Some(tx_hash)
+        );
+
+        assert!(matches!(
+            wallet
+                .send_coins_with_options(&node, TO, 60_000, &options)
+                .await,
+            Err(WalletError::Idempotency(IdempotencyError::KeyReused(_)))
+        ));
+    }
+}
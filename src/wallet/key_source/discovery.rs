@@ -0,0 +1,271 @@
+//! BIP-44 account and address discovery, for restoring a wallet from a
+//! mnemonic or an account xpub.
+//!
+//! Addresses are derived in order on the external and internal chains and
+//! checked for transaction history; a chain ends after [`GAP_LIMIT`] unused
+//! addresses in a row, and accounts are scanned until one has no used
+//! external address.
+
+use crate::WalletError;
+use crate::node::{NodeError, ReadProvider};
+use crate::wallet::chain::Chain;
+use crate::wallet::scheme::Secp256k1;
+
+use super::{Bip44Path, KeySource};
+
+/// Unused addresses in a row after which a chain is taken to end (BIP-44).
+pub const GAP_LIMIT: u32 = 20;
+
+/// An address with transaction history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredAddress {
+    /// 0 for receiving addresses, 1 for change.
+    pub change: u32,
+    pub index: u32,
+    pub path: String,
+    pub address: String,
+    /// Balance in base units.
+    pub balance: u128,
+}
+
+/// An account with at least one used address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredAccount {
+    pub account: u32,
+    pub addresses: Vec<DiscoveredAddress>,
+}
+
+impl DiscoveredAccount {
+    /// Sum of the used addresses' balances, in base units.
+    pub fn balance(&self) -> u128 {
+        self.addresses.iter().map(|a| a.balance).sum()
+    }
+
+    /// First unused index on `change`, where new addresses should start.
+    pub fn next_index(&self, change: u32) -> u32 {
+        self.addresses
+            .iter()
+            .filter(|a| a.change == change)
+            .map(|a| a.index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Finds the used accounts and addresses of one chain through a provider.
+pub struct Discovery<'a, C> {
+    chain: &'a C,
+    provider: &'a dyn ReadProvider,
+    gap_limit: u32,
+    max_accounts: u32,
+}
+
+impl<'a, C: Chain<Scheme = Secp256k1>> Discovery<'a, C> {
+    pub fn new(chain: &'a C, provider: &'a dyn ReadProvider) -> Self {
+        Self {
+            chain,
+            provider,
+            gap_limit: GAP_LIMIT,
+            max_accounts: 100,
+        }
+    }
+
+    /// Stop a chain after `gap_limit` unused addresses instead of [`GAP_LIMIT`].
+    pub fn with_gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit.max(1);
+        self
+    }
+
+    /// Scan at most `max_accounts` accounts.
+    pub fn with_max_accounts(mut self, max_accounts: u32) -> Self {
+        self.max_accounts = max_accounts;
+        self
+    }
+
+    /// Scan the chain's BIP-44 accounts under a root key source, such as a
+    /// [`MnemonicKeySource`](super::MnemonicKeySource), in order until one is
+    /// unused.
+    pub async fn accounts<K: KeySource + ?Sized>(
+        &self,
+        source: &K,
+    ) -> Result<Vec<DiscoveredAccount>, WalletError> {
+        let mut accounts = Vec::new();
+        for account in 0..self.max_accounts {
+            let root = Bip44Path::for_chain(self.chain).with_account(account);
+            let found = self
+                .account(source, account, |change, index| {
+                    Bip44Path {
+                        change,
+                        index,
+                        ..root
+                    }
+                    .to_string()
+                })
+                .await?;
+            if !found.addresses.iter().any(|a| a.change == 0) {
+                break;
+            }
+            accounts.push(found);
+        }
+        Ok(accounts)
+    }
+
+    /// Scan the one account an account-level xpub
+    /// ([`XPubKeySource`](super::XPubKeySource)) stands for, deriving
+    /// `m/<change>/<index>` below it. Returned as `account`.
+    pub async fn xpub_account<K: KeySource + ?Sized>(
+        &self,
+        source: &K,
+        account: u32,
+    ) -> Result<DiscoveredAccount, WalletError> {
+        self.account(source, account, |change, index| {
+            format!("m/{}/{}", change, index)
+        })
+        .await
+    }
+
+    async fn account<K: KeySource + ?Sized>(
+        &self,
+        source: &K,
+        account: u32,
+        path: impl Fn(u32, u32) -> String,
+    ) -> Result<DiscoveredAccount, WalletError> {
+        let mut addresses = Vec::new();
+        for change in [0, 1] {
+            let mut unused = 0;
+            let mut index = 0;
+            while unused < self.gap_limit {
+                let path = path(change, index);
+                let address = self.address(source, &path).await?;
+                if self.provider.get_transactions(&address).await?.is_empty() {
+                    unused += 1;
+                } else {
+                    unused = 0;
+                    addresses.push(DiscoveredAddress {
+                        change,
+                        index,
+                        path,
+                        address,
+                        balance: 0,
+                    });
+                }
+                index += 1;
+            }
+        }
+
+        let used: Vec<String> = addresses.iter().map(|a| a.address.clone()).collect();
+        let balances = self.provider.get_balances_bulk(&used).await;
+        for (found, balance) in addresses.iter_mut().zip(balances) {
+            let balance = balance?;
+            found.balance = balance.parse().map_err(|_| {
+                NodeError::Parse(format!(
+                    "balance of {} is not an integer: {}",
+                    found.address, balance
+                ))
+            })?;
+        }
+        Ok(DiscoveredAccount { account, addresses })
+    }
+
+    async fn address<K: KeySource + ?Sized>(
+        &self,
+        source: &K,
+        path: &str,
+    ) -> Result<String, WalletError> {
+        let signer = source.derive_signer(path).await?;
+        Ok(self.chain.address_from_pubkey(&signer.public_key())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{ReadProvider, Transaction};
+    use crate::wallet::chain::LITECOIN;
+    use crate::wallet::key_source::MnemonicKeySource;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Addresses in `used` have one transaction and a balance of 5.
+    struct History {
+        used: HashSet<String>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for History {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, address: &str) -> Result<Vec<Transaction>, NodeError> {
+            if !self.used.contains(address) {
+                return Ok(vec![]);
+            }
+            Ok(vec![Transaction {
+                hash: format!("tx-{}", address),
+                from: String::new(),
+                to: address.to_string(),
+                value: "5".into(),
+                block_number: 1,
+                timestamp: 0,
+                status: "SUCCESS".into(),
+                raw: None,
+                details: None,
+            }])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("5".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_gap_limit() {
+        let source = MnemonicKeySource::new(PHRASE, None).unwrap();
+        let address = |path: Bip44Path| {
+            let source = &source;
+            async move {
+                let signer = source.derive_signer(&path.to_string()).await.unwrap();
+                LITECOIN.address_from_pubkey(&signer.public_key()).unwrap()
+            }
+        };
+        let base = Bip44Path::for_chain(&LITECOIN);
+        let used = [
+            address(base.with_index(0)).await,
+            // Within the gap of 3 after index 0.
+            address(base.with_index(3)).await,
+            address(base.internal().with_index(1)).await,
+            // Past the gap after index 3; never reached.
+            address(base.with_index(8)).await,
+            // Account 1 has no receiving history, so scanning stops before it.
+            address(base.with_account(1).internal()).await,
+        ];
+        let provider = History {
+            used: used.iter().cloned().collect(),
+        };
+
+        let accounts = Discovery::new(&LITECOIN, &provider)
+            .with_gap_limit(3)
+            .accounts(&source)
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), 1);
+        let found: Vec<_> = accounts[0]
+            .addresses
+            .iter()
+            .map(|a| (a.change, a.index))
+            .collect();
+        assert_eq!(found, [(0, 0), (0, 3), (1, 1)]);
+        assert_eq!(
+            accounts[0].addresses[1].path,
+            base.with_index(3).to_string()
+        );
+        assert_eq!(accounts[0].balance(), 15);
+        assert_eq!(accounts[0].next_index(0), 4);
+    }
+}
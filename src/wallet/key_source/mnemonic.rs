@@ -1,64 +1,311 @@
 use async_trait::async_trait;
-use bip32::XPrv;
+use bip32::{ChildNumber, Prefix, XPrv};
 use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
 use rand::RngCore;
+use sha2::Sha512;
+use std::ops::Range;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use super::{KeySource, KeySourceError};
+use super::{Bip44Path, KeySource, KeySourceError, parse_hardened_path, parse_path};
 use crate::wallet::Signer;
+use crate::wallet::chain::{Chain, Network};
 use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::scheme::{Ed25519, Secp256k1};
+use crate::wallet::secrets::{KeyStore, SecretStore};
+use crate::wallet::signer::ed25519::Ed25519Signer;
 use crate::wallet::signer::local::LocalSigner;
 
+pub use bip39::Language;
+
+/// Length of a generated phrase; longer phrases carry more entropy (128 bits
+/// for 12 words up to 256 bits for 24).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WordCount {
+    #[default]
+    Twelve,
+    Fifteen,
+    Eighteen,
+    TwentyOne,
+    TwentyFour,
+}
+
+impl WordCount {
+    pub fn words(self) -> usize {
+        match self {
+            WordCount::Twelve => 12,
+            WordCount::Fifteen => 15,
+            WordCount::Eighteen => 18,
+            WordCount::TwentyOne => 21,
+            WordCount::TwentyFour => 24,
+        }
+    }
+
+    /// Every 3 words encode 32 bits of entropy (plus a checksum bit each).
+    fn entropy_bytes(self) -> usize {
+        self.words() / 3 * 4
+    }
+}
+
+/// Where generated phrases get their entropy, e.g. an HSM's RNG.
+pub trait EntropySource: Send + Sync {
+    /// Fill `dest` with uniformly random bytes.
+    fn fill(&self, dest: &mut [u8]) -> Result<(), KeySourceError>;
+}
+
+/// The operating system's RNG.
+pub struct OsEntropy;
+
+impl EntropySource for OsEntropy {
+    fn fill(&self, dest: &mut [u8]) -> Result<(), KeySourceError> {
+        rand::rng().fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// How [`MnemonicKeySource::generate`] makes a phrase. Defaults to 12 English
+/// words from [`OsEntropy`].
+#[derive(Clone)]
+pub struct MnemonicOptions {
+    words: WordCount,
+    language: Language,
+    entropy: Arc<dyn EntropySource>,
+}
+
+impl Default for MnemonicOptions {
+    fn default() -> Self {
+        Self {
+            words: WordCount::default(),
+            language: Language::English,
+            entropy: Arc::new(OsEntropy),
+        }
+    }
+}
+
+impl MnemonicOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_words(mut self, words: WordCount) -> Self {
+        self.words = words;
+        self
+    }
+
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: Arc<dyn EntropySource>) -> Self {
+        self.entropy = entropy;
+        self
+    }
+}
+
+/// A receiving address from [`MnemonicKeySource::derive_addresses`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedAddress {
+    pub path: Bip44Path,
+    pub address: String,
+}
+
 /// Local HD Wallet key source based on BIP-39 mnemonic.
 pub struct MnemonicKeySource {
     seed: SecureBuffer,
     phrase: SecureBuffer,
+    network: Option<Network>,
 }
 
 impl MnemonicKeySource {
-    /// Create a new source from a BIP-39 mnemonic phrase.
+    /// Create a new source from a BIP-39 mnemonic phrase in any of the
+    /// BIP-39 wordlist languages.
     pub fn new(phrase: &str, passphrase: Option<&str>) -> Result<Self, KeySourceError> {
         let mnemonic = Mnemonic::from_str(phrase)
             .map_err(|e| KeySourceError::InvalidMnemonic(e.to_string()))?;
-        let seed = mnemonic.to_seed(passphrase.unwrap_or(""));
-        Ok(Self {
-            seed: SecureBuffer::new(seed.to_vec()),
-            phrase: SecureBuffer::from(phrase),
-        })
+        Ok(Self::from_mnemonic(&mnemonic, passphrase))
     }
 
-    /// Generate a new random mnemonic (12 words).
+    /// Generate a new random mnemonic (12 English words).
     pub fn random(passphrase: Option<&str>) -> Self {
-        let mut entropy = [0u8; 16]; // 128 bits = 12 words
-        rand::rng().fill_bytes(&mut entropy);
+        Self::generate(&MnemonicOptions::default(), passphrase)
+            .expect("OS entropy is always available")
+    }
+
+    /// Generate a new random mnemonic as `options` asks.
+    pub fn generate(
+        options: &MnemonicOptions,
+        passphrase: Option<&str>,
+    ) -> Result<Self, KeySourceError> {
+        let mut entropy = SecureBuffer::new(vec![0u8; options.words.entropy_bytes()]);
+        options.entropy.fill(entropy.as_mut())?;
+        let mnemonic = Mnemonic::from_entropy_in(options.language, &entropy)
+            .map_err(|e| KeySourceError::InvalidMnemonic(e.to_string()))?;
+        Ok(Self::from_mnemonic(&mnemonic, passphrase))
+    }
 
-        let mnemonic = Mnemonic::from_entropy(&entropy).expect("valid entropy");
-        let phrase = mnemonic.to_string();
+    fn from_mnemonic(mnemonic: &Mnemonic, passphrase: Option<&str>) -> Self {
         let seed = mnemonic.to_seed(passphrase.unwrap_or(""));
         Self {
             seed: SecureBuffer::new(seed.to_vec()),
-            phrase: SecureBuffer::from(phrase),
+            phrase: SecureBuffer::from_string(mnemonic.to_string()),
+            network: None,
         }
     }
 
+    /// Tag every derived signer with `network`, so a seed used for testnet
+    /// development cannot sign mainnet transactions (or the other way round).
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
     /// Get the mnemonic phrase.
     pub fn phrase(&self) -> &str {
         self.phrase.as_str().unwrap_or("")
     }
+
+    /// Load the phrase stored as `alias` in `store`, e.g. an
+    /// [`OsKeyring`](crate::wallet::keyring::OsKeyring), so it is only in
+    /// process memory while the source is alive.
+    pub fn from_store(
+        store: &dyn SecretStore,
+        alias: &str,
+        passphrase: Option<&str>,
+    ) -> Result<Self, KeySourceError> {
+        let phrase = store
+            .get(alias)?
+            .ok_or_else(|| KeySourceError::MissingSecret(alias.to_string()))?;
+        let phrase = phrase
+            .as_str()
+            .map_err(|e| KeySourceError::InvalidMnemonic(e.to_string()))?;
+        Self::new(phrase, passphrase)
+    }
+
+    /// Store the phrase (not the passphrase) as `alias` in `store`, for
+    /// [`from_store`](Self::from_store) to load later.
+    pub fn save_to(&self, store: &dyn KeyStore, alias: &str) -> Result<(), KeySourceError> {
+        Ok(store.put(alias, &self.phrase)?)
+    }
+
+    /// Extended public key at `path`, usually an account (`m/44'/0'/0'`), for
+    /// an [`XPubKeySource`](super::XPubKeySource) on a machine that must not
+    /// hold the mnemonic. `tpub` if the source is tagged for testnet.
+    pub fn derive_xpub(&self, path: &str) -> Result<String, KeySourceError> {
+        Ok(self.xprv(path)?.public_key().to_string(self.prefix(false)))
+    }
+
+    /// Extended *private* key at `path`. Anyone holding it can spend from
+    /// every address below `path`; prefer [`derive_xpub`](Self::derive_xpub)
+    /// unless the key is going straight into another signer.
+    pub fn dangerous_export_xprv(&self, path: &str) -> Result<SecureBuffer, KeySourceError> {
+        let xprv = self.xprv(path)?.to_string(self.prefix(true));
+        Ok(SecureBuffer::from(xprv.as_str()))
+    }
+
+    /// Receiving addresses `range` of `account` on `chain`, in order.
+    ///
+    /// Deriving each address's full path from the seed repeats the five
+    /// hardened and unhardened steps every time; here the account's
+    /// receiving chain key is derived once and every address is one public
+    /// child of it, which is what makes batches of thousands cheap.
+    pub fn derive_addresses<C: Chain<Scheme = Secp256k1> + ?Sized>(
+        &self,
+        chain: &C,
+        account: u32,
+        range: Range<u32>,
+    ) -> Result<Vec<DerivedAddress>, KeySourceError> {
+        let root = Bip44Path::for_chain(chain).with_account(account);
+        let receiving = self
+            .xprv(&format!(
+                "m/{}'/{}'/{}'/{}",
+                root.purpose, root.coin_type, root.account, root.change
+            ))?
+            .public_key();
+        range
+            .map(|index| {
+                let child = ChildNumber::new(index, false)
+                    .and_then(|number| receiving.derive_child(number))
+                    .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+                let pubkey = child.public_key().to_encoded_point(true);
+                let address = chain
+                    .address_from_pubkey(pubkey.as_bytes())
+                    .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+                Ok(DerivedAddress {
+                    path: root.external(index),
+                    address,
+                })
+            })
+            .collect()
+    }
+
+    fn xprv(&self, path: &str) -> Result<XPrv, KeySourceError> {
+        XPrv::derive_from_path(&self.seed, &parse_path(path)?)
+            .map_err(|e| KeySourceError::Derivation(e.to_string()))
+    }
+
+    fn prefix(&self, private: bool) -> Prefix {
+        match (self.network, private) {
+            (Some(Network::Testnet), false) => Prefix::TPUB,
+            (Some(Network::Testnet), true) => Prefix::TPRV,
+            (_, false) => Prefix::XPUB,
+            (_, true) => Prefix::XPRV,
+        }
+    }
 }
 
 #[async_trait]
 impl KeySource for MnemonicKeySource {
     async fn derive_signer(&self, path: &str) -> Result<Box<dyn Signer>, KeySourceError> {
-        let xprv = XPrv::derive_from_path(&self.seed, &path.parse().unwrap())
-            .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+        let xprv = self.xprv(path)?;
 
         let secret_key_bytes = xprv.private_key().to_bytes();
-        let signer = LocalSigner::from_slice(&secret_key_bytes)
+        let mut signer = LocalSigner::from_slice(&secret_key_bytes)
             .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+        if let Some(network) = self.network {
+            signer = signer.with_network(network);
+        }
 
         Ok(Box::new(signer))
     }
+
+    /// SLIP-10 derivation; ed25519 only has hardened children, so every
+    /// path segment must be hardened (`m/44'/501'/0'/0'`).
+    async fn derive_ed25519_signer(
+        &self,
+        path: &str,
+    ) -> Result<Box<dyn Signer<Ed25519>>, KeySourceError> {
+        let secret_key = slip10_ed25519(&self.seed, path)?;
+        let mut signer = Ed25519Signer::from_bytes(
+            secret_key[..]
+                .try_into()
+                .expect("SLIP-10 keys are 32 bytes"),
+        );
+        if let Some(network) = self.network {
+            signer = signer.with_network(network);
+        }
+        Ok(Box::new(signer))
+    }
+}
+
+/// SLIP-10 ed25519 private key at `path` below `seed`.
+fn slip10_ed25519(seed: &[u8], path: &str) -> Result<SecureBuffer, KeySourceError> {
+    let path = parse_hardened_path(path)?;
+    let hmac = |key: &[u8], data: &[&[u8]]| {
+        let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes any key length");
+        for part in data {
+            mac.update(part);
+        }
+        SecureBuffer::new(mac.finalize().into_bytes().to_vec())
+    };
+
+    let mut node = hmac(b"ed25519 seed", &[seed]);
+    for child in path.iter() {
+        let (key, chain_code) = node.split_at(32);
+        node = hmac(chain_code, &[&[0], key, &u32::from(child).to_be_bytes()]);
+    }
+    Ok(SecureBuffer::new(node[..32].to_vec()))
 }
 
 #[cfg(test)]
@@ -84,6 +331,104 @@ mod tests {
         assert_eq!(pk.len(), 33);
     }
 
+    #[tokio::test]
+    async fn generates_longer_phrases_in_other_languages() {
+        /// Stands in for an HSM returning fixed bytes.
+        struct Fixed;
+        impl EntropySource for Fixed {
+            fn fill(&self, dest: &mut [u8]) -> Result<(), KeySourceError> {
+                dest.fill(0x7f);
+                Ok(())
+            }
+        }
+
+        let options = MnemonicOptions::new()
+            .with_words(WordCount::TwentyFour)
+            .with_language(Language::Spanish)
+            .with_entropy(Arc::new(Fixed));
+        let source = MnemonicKeySource::generate(&options, None).unwrap();
+        let mnemonic = Mnemonic::from_str(source.phrase()).unwrap();
+        assert_eq!(mnemonic.word_count(), 24);
+        assert_eq!(mnemonic.language(), Language::Spanish);
+        assert_eq!(mnemonic.to_entropy(), vec![0x7f; 32]);
+
+        let restored = MnemonicKeySource::new(source.phrase(), None).unwrap();
+        assert_eq!(
+            restored
+                .derive_signer("m/44'/0'/0'/0/0")
+                .await
+                .unwrap()
+                .public_key(),
+            source
+                .derive_signer("m/44'/0'/0'/0/0")
+                .await
+                .unwrap()
+                .public_key()
+        );
+        for words in [
+            WordCount::Fifteen,
+            WordCount::Eighteen,
+            WordCount::TwentyOne,
+        ] {
+            let source =
+                MnemonicKeySource::generate(&MnemonicOptions::new().with_words(words), None)
+                    .unwrap();
+            assert_eq!(source.phrase().split(' ').count(), words.words());
+        }
+    }
+
+    #[tokio::test]
+    async fn loads_a_phrase_stored_by_alias() {
+        use crate::wallet::secrets::MemorySecretStore;
+
+        let store = MemorySecretStore::new();
+        let source = MnemonicKeySource::random(None);
+        source.save_to(&store, "treasury").unwrap();
+
+        let loaded = MnemonicKeySource::from_store(&store, "treasury", None).unwrap();
+        assert_eq!(loaded.phrase(), source.phrase());
+        assert_eq!(
+            loaded.derive_xpub("m/44'/0'/0'").unwrap(),
+            source.derive_xpub("m/44'/0'/0'").unwrap()
+        );
+
+        assert!(store.delete("treasury").unwrap());
+        assert!(matches!(
+            MnemonicKeySource::from_store(&store, "treasury", None),
+            Err(KeySourceError::MissingSecret(alias)) if alias == "treasury"
+        ));
+    }
+
+    #[tokio::test]
+    async fn bulk_addresses_match_per_path_derivation() {
+        use crate::wallet::chain::{LITECOIN, TRON, UtxoAddressType};
+
+        let source = MnemonicKeySource::random(None);
+        let segwit = LITECOIN.with_address_type(UtxoAddressType::P2wpkh);
+        let bulk = source.derive_addresses(&segwit, 2, 5..8).unwrap();
+        assert_eq!(bulk.len(), 3);
+        for derived in &bulk {
+            let signer = source.derive_signer_at(&derived.path).await.unwrap();
+            assert_eq!(
+                derived.address,
+                segwit.address_from_pubkey(&signer.public_key()).unwrap()
+            );
+        }
+        assert_eq!(bulk[0].path.to_string(), "m/84'/2'/2'/0/5");
+
+        let tron = source.derive_addresses(&TRON, 0, 0..1).unwrap();
+        let signer = source.derive_signer("m/44'/195'/0'/0/0").await.unwrap();
+        assert_eq!(
+            tron[0].address,
+            TRON.address_from_pubkey(&signer.public_key()).unwrap()
+        );
+        assert!(
+            source
+                .derive_addresses(&TRON, 0, 0x8000_0000..0x8000_0001)
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn test_passphrase_derivation() {
         let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
@@ -105,4 +450,91 @@ mod tests {
         // Keys should be different
         assert_ne!(signer1.public_key(), signer2.public_key());
     }
+
+    #[tokio::test]
+    async fn exported_xpub_derives_the_same_addresses() {
+        use crate::wallet::key_source::{Bip44Path, CoinType, XPubKeySource};
+
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let source = MnemonicKeySource::new(phrase, None).unwrap();
+        let xpub = source.derive_xpub("m/44'/0'/0'").unwrap();
+        assert_eq!(
+            xpub,
+            "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj"
+        );
+        let watch_only = XPubKeySource::new(&xpub).unwrap();
+        assert_eq!(
+            watch_only
+                .derive_signer("m/0/5")
+                .await
+                .unwrap()
+                .public_key(),
+            source
+                .derive_signer("m/44'/0'/0'/0/5")
+                .await
+                .unwrap()
+                .public_key()
+        );
+
+        let typed = Bip44Path::coin(CoinType::Bitcoin).external(5);
+        assert_eq!(
+            watch_only
+                .derive_signer_at(&typed)
+                .await
+                .unwrap()
+                .public_key(),
+            source.derive_signer_at(&typed).await.unwrap().public_key()
+        );
+
+        let xprv = source.dangerous_export_xprv("m/44'/0'/0'").unwrap();
+        assert!(xprv.as_str().unwrap().starts_with("xprv"));
+        let testnet = MnemonicKeySource::new(phrase, None)
+            .unwrap()
+            .with_network(Network::Testnet);
+        assert!(
+            testnet
+                .derive_xpub("m/44'/1'/0'")
+                .unwrap()
+                .starts_with("tpub")
+        );
+    }
+
+    #[tokio::test]
+    async fn derives_ed25519_keys_per_slip10() {
+        use crate::wallet::chain::{Chain, ChainError, TRON};
+        use crate::wallet::scheme::KeyType;
+
+        // SLIP-10 test vector 1 for ed25519.
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let source = MnemonicKeySource::random(None);
+        assert!(matches!(
+            source.derive_signer("m/44'/oops").await,
+            Err(KeySourceError::InvalidPath { .. })
+        ));
+        assert_eq!(
+            hex::encode(&slip10_ed25519(&seed, "m").unwrap()[..]),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(&slip10_ed25519(&seed, "m/0'").unwrap()[..]),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert!(slip10_ed25519(&seed, "m/0'/1").is_err());
+
+        let signer = source
+            .derive_ed25519_signer("m/44'/501'/0'/0'")
+            .await
+            .unwrap();
+        let key = signer.tagged_public_key();
+        assert_eq!(key.key_type, KeyType::Ed25519);
+        assert_eq!(KeyType::of_public_key(&key.bytes), Some(KeyType::Ed25519));
+        assert_eq!(signer.sign(b"hello").await.unwrap().len(), 64);
+        assert_eq!(
+            TRON.address_from_pubkey(&key.bytes),
+            Err(ChainError::KeyTypeMismatch {
+                expected: KeyType::Secp256k1,
+                actual: KeyType::Ed25519,
+            })
+        );
+    }
 }
@@ -2,13 +2,23 @@ use async_trait::async_trait;
 use thiserror::Error;
 
 use crate::wallet::Signer;
+use crate::wallet::scheme::{Ed25519, KeyType};
 
+pub mod discovery;
 pub mod mnemonic;
 pub mod mpc;
+pub mod path;
+pub mod slip39;
 pub mod xpub;
 
-pub use mnemonic::MnemonicKeySource;
+pub use discovery::{DiscoveredAccount, DiscoveredAddress, Discovery, GAP_LIMIT};
+pub use mnemonic::{
+    DerivedAddress, EntropySource, Language, MnemonicKeySource, MnemonicOptions, OsEntropy,
+    WordCount,
+};
 pub use mpc::MpcKeySource;
+pub use path::{Bip44Path, CoinType, parse_hardened_path, parse_path, parse_unhardened_path};
+pub use slip39::{ShareGroup, ShareOptions, Slip39Error};
 pub use xpub::XPubKeySource;
 
 #[derive(Debug, Error)]
@@ -17,6 +27,16 @@ pub enum KeySourceError {
     InvalidMnemonic(String),
     #[error("derivation failed: {0}")]
     Derivation(String),
+    #[error("invalid derivation path {input:?}: {reason}")]
+    InvalidPath { input: String, reason: String },
+    #[error("{0} keys are not supported by this key source")]
+    UnsupportedKeyType(KeyType),
+    #[error("SLIP-39: {0}")]
+    Shares(#[from] Slip39Error),
+    #[error("no secret stored as {0:?}")]
+    MissingSecret(String),
+    #[error("secret store: {0}")]
+    SecretStore(#[from] std::io::Error),
 }
 
 /// Abstract source of keys.
@@ -27,4 +47,19 @@ pub trait KeySource: Send + Sync {
     /// For local mnemonics, this derives the private key.
     /// For MPC, this might prepare a session for that path.
     async fn derive_signer(&self, path: &str) -> Result<Box<dyn Signer>, KeySourceError>;
+
+    /// [`derive_signer`](Self::derive_signer) for a typed path.
+    async fn derive_signer_at(&self, path: &Bip44Path) -> Result<Box<dyn Signer>, KeySourceError> {
+        self.derive_signer(&path.to_string()).await
+    }
+
+    /// Derive an ed25519 signer for a specific path, for chains such as Solana.
+    /// Sources that only hold secp256k1 keys keep the default, which refuses.
+    async fn derive_ed25519_signer(
+        &self,
+        path: &str,
+    ) -> Result<Box<dyn Signer<Ed25519>>, KeySourceError> {
+        let _ = path;
+        Err(KeySourceError::UnsupportedKeyType(KeyType::Ed25519))
+    }
 }
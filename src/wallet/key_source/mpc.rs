@@ -1,39 +1,375 @@
 use async_trait::async_trait;
-use std::sync::Arc;
+use bip32::{ChildNumber, DerivationPath, ExtendedKeyAttrs, Prefix, XPub};
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::PrimeField;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{ProjectivePoint, PublicKey, Scalar};
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::{Arc, Mutex};
 
 use crate::wallet::Signer;
+use crate::wallet::chain::Network;
+use crate::wallet::signer::mpc::protocol::{self, KeygenOptions, MpcError};
 use crate::wallet::signer::mpc::signer::{KeyShare, MpcSigner};
-use crate::wallet::signer::mpc::transport::MpcTransport;
+use crate::wallet::signer::mpc::transport::{MpcTransport, PartyId};
 
-use super::{KeySource, KeySourceError};
+use super::{KeySource, KeySourceError, parse_hardened_path, parse_path};
 
 /// MPC-based key source.
+///
+/// The key from keygen stands in for a BIP-44 *account* key: the hardened
+/// segments of a path (`m/44'/195'/0'`) would need the whole private key, so
+/// they only select it, and the unhardened rest (`0/5`) is derived below it
+/// as BIP-32 public derivation would, by tweaking each party's share. Run one
+/// keygen per account: the share records the account's hardened path, and
+/// paths under any other account are refused rather than mapped onto the same
+/// key. A share without a recorded account is bound to the first account a
+/// path names.
 pub struct MpcKeySource {
     share: KeyShare,
     transport: Arc<dyn MpcTransport>,
+    network: Option<Network>,
+    refresh: KeygenOptions,
+    account: Mutex<Option<String>>,
 }
 
 impl MpcKeySource {
     pub fn new(share: KeyShare, transport: Arc<dyn MpcTransport>) -> Self {
-        Self { share, transport }
+        Self {
+            account: Mutex::new(share.account.clone()),
+            share,
+            transport,
+            network: None,
+            refresh: KeygenOptions::default(),
+        }
+    }
+
+    /// Run [`keygen`](protocol::keygen) with `peer` for the account at the
+    /// hardened path `account`, e.g. `m/44'/195'/0'`, and record it in the share.
+    pub async fn keygen(
+        transport: Arc<dyn MpcTransport>,
+        peer: PartyId,
+        account: &str,
+        options: &KeygenOptions,
+    ) -> Result<Self, KeySourceError> {
+        let account = parse_hardened_path(account)?.to_string();
+        let mut share = protocol::keygen(transport.as_ref(), peer, options)
+            .await
+            .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+        share.account = Some(account);
+        Ok(Self::new(share, transport).with_refresh_options(*options))
+    }
+
+    /// Tag every derived signer with `network`.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Paillier size and timeout for [`refresh_shares`](Self::refresh_shares).
+    pub fn with_refresh_options(mut self, options: KeygenOptions) -> Self {
+        self.refresh = options;
+        self
+    }
+
+    /// The share, e.g. to persist after a refresh.
+    pub fn share(&self) -> &KeyShare {
+        &self.share
+    }
+
+    /// Rotate the shares among `new_parties`: this party and its current
+    /// co-signer, or this party and one that took over the co-signer's share
+    /// through [`hand_off_share`](Self::hand_off_share) (the transport must
+    /// then reach the new party). The public key, and so every address,
+    /// stays; shares from before no longer sign with shares from after.
+    pub async fn refresh_shares(&mut self, new_parties: [PartyId; 2]) -> Result<(), MpcError> {
+        // Carry an account bound since keygen over to the new share.
+        self.share.account = self.account.lock().unwrap().clone();
+        let me = self.share.party;
+        let peer = match new_parties {
+            [a, b] if a == me && b != me => b,
+            [a, b] if b == me && a != me => a,
+            _ => {
+                return Err(MpcError::InvalidShare(format!(
+                    "parties {:?} must be party {} and one other; a party leaving hands its share off",
+                    new_parties, me
+                )));
+            }
+        };
+        self.share =
+            protocol::refresh(self.transport.as_ref(), &self.share, peer, &self.refresh).await?;
+        Ok(())
+    }
+
+    /// Chain code of the joint key, fixed by its public key so both parties
+    /// agree on it without another round.
+    pub fn chain_code(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"flow-wallet/mpc/chain-code");
+        hasher.update(&self.share.public_key);
+        hasher.finalize().into()
+    }
+
+    /// The joint key as an account-level xpub (depth 3), from which an
+    /// [`XPubKeySource`](super::XPubKeySource) watches the same addresses.
+    /// Its parent fingerprint and child number are unknown and left zero.
+    pub fn account_xpub(&self) -> Result<String, KeySourceError> {
+        let public_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&self.share.public_key)
+            .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+        let attrs = ExtendedKeyAttrs {
+            depth: 3,
+            parent_fingerprint: [0; 4],
+            child_number: ChildNumber(0),
+            chain_code: self.chain_code(),
+        };
+        let prefix = match self.network {
+            Some(Network::Testnet) => Prefix::TPUB,
+            _ => Prefix::XPUB,
+        };
+        Ok(XPub::new(public_key, attrs).to_string(prefix))
+    }
+
+    /// Leave the key to party `to`, who receives the share with
+    /// [`receive_hand_off`](protocol::receive_hand_off) and then refreshes
+    /// with the co-signer, which invalidates this copy.
+    pub async fn hand_off_share(mut self, to: PartyId) -> Result<(), MpcError> {
+        self.share.account = self.account.lock().unwrap().clone();
+        protocol::hand_off(self.transport.as_ref(), &self.share, to).await
+    }
+
+    /// Refuse `path` unless its hardened prefix `account` is the key's
+    /// account, binding an unrecorded one to it.
+    fn check_account(&self, path: &str, account: &str) -> Result<(), KeySourceError> {
+        let mut bound = self.account.lock().unwrap();
+        match bound.as_deref() {
+            Some(own) if own != account => Err(KeySourceError::InvalidPath {
+                input: path.to_string(),
+                reason: format!(
+                    "account {} is not this MPC key's {}; run a keygen per account",
+                    account, own
+                ),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                *bound = Some(account.to_string());
+                Ok(())
+            }
+        }
     }
 }
 
 #[async_trait]
 impl KeySource for MpcKeySource {
-    async fn derive_signer(&self, _path: &str) -> Result<Box<dyn Signer>, KeySourceError> {
-        // TODO:
-        // In a real MPC, derivation might involve communication or just using the share for that path.
-        // For this skeleton, we assume the share is already for the target key.
-        // We clone the share data for the new signer instance.
-        let signer_share = KeyShare {
-            public_key: self.share.public_key.clone(),
-            share_data: self.share.share_data.clone(),
-        };
+    async fn derive_signer(&self, path: &str) -> Result<Box<dyn Signer>, KeySourceError> {
+        let parsed = parse_path(path)?;
+        let mut account = DerivationPath::default();
+        for child in parsed.iter().take_while(|child| child.is_hardened()) {
+            account.push(child);
+        }
+        let children: Vec<ChildNumber> = parsed.iter().skip(account.len()).collect();
+        if !account.is_empty() {
+            self.check_account(path, &account.to_string())?;
+        }
+        if let Some(depth) = children.iter().position(|child| child.is_hardened()) {
+            return Err(KeySourceError::InvalidPath {
+                input: path.to_string(),
+                reason: format!(
+                    "hardened segment after {} unhardened ones; MPC keys only derive \
+                     unhardened children below the account",
+                    depth
+                ),
+            });
+        }
+        let offset = child_offset(&self.share.public_key, self.chain_code(), &children)?;
+        let share = protocol::tweak_share(&self.share, &offset)
+            .map_err(|e| KeySourceError::Derivation(e.to_string()))?;
+        let mut signer = MpcSigner::new(share, self.transport.clone());
+        if let Some(network) = self.network {
+            signer = signer.with_network(network);
+        }
+        Ok(Box::new(signer))
+    }
+}
+
+/// Sum of the BIP-32 CKDpub tweaks from `public_key` down `children`, so the
+/// child key is `public_key + offset·G`.
+fn child_offset(
+    public_key: &[u8],
+    mut chain_code: [u8; 32],
+    children: &[ChildNumber],
+) -> Result<Scalar, KeySourceError> {
+    let invalid = || KeySourceError::Derivation("child key is invalid; skip this index".into());
+    let mut point = PublicKey::from_sec1_bytes(public_key)
+        .map_err(|e| KeySourceError::Derivation(e.to_string()))?
+        .to_projective();
+    let mut offset = Scalar::ZERO;
+    for child in children {
+        let mut mac = Hmac::<Sha512>::new_from_slice(&chain_code).expect("any key length");
+        mac.update(point.to_affine().to_encoded_point(true).as_bytes());
+        mac.update(&child.0.to_be_bytes());
+        let i = mac.finalize().into_bytes();
+        let (il, ir) = i.split_at(32);
+        let il: [u8; 32] = il.try_into().expect("32-byte half");
+        let tweak: Scalar = Option::from(Scalar::from_repr(il.into())).ok_or_else(invalid)?;
+        point += ProjectivePoint::GENERATOR * tweak;
+        if point == ProjectivePoint::IDENTITY {
+            return Err(invalid());
+        }
+        chain_code.copy_from_slice(ir);
+        offset += tweak;
+    }
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::wallet::signer::SignerError;
+    use crate::wallet::signer::mpc::transport::LocalTransport;
+
+    const FAST: KeygenOptions = KeygenOptions {
+        paillier_bits: 1024,
+        timeout: Duration::from_secs(30),
+    };
+
+    /// Sign one digest with both sources' signers, each checking the result
+    /// against the joint key.
+    async fn sign_together(a: &MpcKeySource, b: &MpcKeySource) -> Result<Vec<u8>, SignerError> {
+        let prehash = Sha256::digest(b"after the refresh");
+        let (a, b) = (
+            a.derive_signer("m/44'/195'/0'/0/0").await.unwrap(),
+            b.derive_signer("m/44'/195'/0'/0/0").await.unwrap(),
+        );
+        let (sig_a, sig_b) = tokio::join!(a.sign_prehash(&prehash), b.sign_prehash(&prehash));
+        sig_b?;
+        sig_a
+    }
+
+    #[tokio::test]
+    async fn derives_the_unhardened_subtree_of_one_keygen() {
+        let (t1, t2) = LocalTransport::pair(1, 2);
+        let (s1, s2) = tokio::join!(
+            protocol::keygen(t1.as_ref(), 2, &FAST),
+            protocol::keygen(t2.as_ref(), 1, &FAST)
+        );
+        let first = MpcKeySource::new(s1.unwrap(), t1);
+        let second = MpcKeySource::new(s2.unwrap(), t2);
+
+        let path = "m/44'/195'/0'/0/3";
+        let (a, b) = (
+            first.derive_signer(path).await.unwrap(),
+            second.derive_signer(path).await.unwrap(),
+        );
+        assert_eq!(a.public_key(), b.public_key());
+        assert_ne!(a.public_key(), first.share().public_key);
+        // The same child as public derivation from the account xpub.
+        let watch = super::super::XPubKeySource::new(&first.account_xpub().unwrap()).unwrap();
+        assert_eq!(
+            watch.derive_signer("m/0/3").await.unwrap().public_key(),
+            a.public_key()
+        );
+
+        let prehash = Sha256::digest(b"child key");
+        let (sig_a, sig_b) = tokio::join!(a.sign_prehash(&prehash), b.sign_prehash(&prehash));
+        // Each side checks the joint signature against the child key.
+        sig_a.unwrap();
+        sig_b.unwrap();
+
+        assert!(matches!(
+            first.derive_signer("m/44'/195'/0'/0/3'").await,
+            Err(KeySourceError::InvalidPath { .. })
+        ));
+        // Bound to the first account asked for: another coin type is not
+        // mapped onto the same key.
+        assert!(matches!(
+            first.derive_signer("m/44'/60'/0'/0/3").await,
+            Err(KeySourceError::InvalidPath { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_account_recorded_at_keygen() {
+        let (t1, t2) = LocalTransport::pair(1, 2);
+        let (first, second) = tokio::join!(
+            MpcKeySource::keygen(t1, 2, "m/44h/195h/0h", &FAST),
+            MpcKeySource::keygen(t2, 1, "m/44'/195'/0'", &FAST)
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.share().account.as_deref(), Some("m/44'/195'/0'"));
+
+        for other in [
+            "m/44'/60'/0'/0/3",
+            "m/44'/195'/1'/0/3",
+            "m/44'/195'/0'/0'/3",
+        ] {
+            assert!(
+                matches!(
+                    first.derive_signer(other).await,
+                    Err(KeySourceError::InvalidPath { .. })
+                ),
+                "{} was derived",
+                other
+            );
+        }
+        let tron = first.derive_signer("m/44'/195'/0'/0/3").await.unwrap();
+        assert_eq!(
+            first.derive_signer("m/0/3").await.unwrap().public_key(),
+            tron.public_key()
+        );
+
+        // The account survives a round trip through the persisted form.
+        let bytes = crate::persist::to_bytes(second.share()).unwrap();
+        let restored: KeyShare = crate::persist::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.account, second.share().account);
+    }
+
+    #[tokio::test]
+    async fn refreshes_keep_the_key_and_move_it_to_a_new_party() {
+        let (t1, t2) = LocalTransport::pair(1, 2);
+        let (s1, s2) = tokio::join!(
+            protocol::keygen(t1.as_ref(), 2, &FAST),
+            protocol::keygen(t2.as_ref(), 1, &FAST)
+        );
+        let public_key = s1.as_ref().unwrap().public_key.clone();
+        let old_second = s2.as_ref().unwrap().clone();
+        let mut first = MpcKeySource::new(s1.unwrap(), t1).with_refresh_options(FAST);
+        let mut second = MpcKeySource::new(s2.unwrap(), t2.clone()).with_refresh_options(FAST);
+
+        let (a, b) = tokio::join!(first.refresh_shares([1, 2]), second.refresh_shares([1, 2]));
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(first.share().public_key, public_key);
+        assert!(sign_together(&first, &second).await.is_ok());
+        // The stolen copy of the old second share no longer pairs up.
+        let stale = MpcKeySource::new(old_second, t2);
+        assert!(sign_together(&first, &stale).await.is_err());
 
-        Ok(Box::new(MpcSigner::new(
-            signer_share,
-            self.transport.clone(),
-        )))
+        // Party 2 retires in favour of party 0, which becomes the Paillier
+        // holder since roles follow the ids.
+        let (t2_out, t0_in) = LocalTransport::pair(2, 0);
+        let (t1_new, t0) = LocalTransport::pair(1, 0);
+        let mut first = MpcKeySource::new(first.share().clone(), t1_new).with_refresh_options(FAST);
+        MpcKeySource::new(second.share().clone(), t2_out)
+            .hand_off_share(0)
+            .await
+            .unwrap();
+        let handed = protocol::receive_hand_off(t0_in.as_ref(), 2, FAST.timeout)
+            .await
+            .unwrap();
+        let mut newcomer = MpcKeySource::new(handed, t0).with_refresh_options(FAST);
+        assert!(first.refresh_shares([2, 3]).await.is_err());
+        let (a, b) = tokio::join!(
+            first.refresh_shares([0, 1]),
+            newcomer.refresh_shares([0, 1])
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(newcomer.share().public_key, public_key);
+        // Both sides check the joint signature against the unchanged key.
+        assert!(sign_together(&newcomer, &first).await.is_ok());
     }
 }
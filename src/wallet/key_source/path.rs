@@ -0,0 +1,288 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bip32::{ChildNumber, DerivationPath};
+
+use super::KeySourceError;
+use crate::wallet::chain::Chain;
+
+/// Registered SLIP-44 coin types of the chains this crate knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoinType {
+    Bitcoin,
+    /// Shared by every testnet.
+    Testnet,
+    Litecoin,
+    Ethereum,
+    Tron,
+    Solana,
+    Other(u32),
+}
+
+impl CoinType {
+    pub fn index(self) -> u32 {
+        match self {
+            CoinType::Bitcoin => 0,
+            CoinType::Testnet => 1,
+            CoinType::Litecoin => 2,
+            CoinType::Ethereum => 60,
+            CoinType::Tron => 195,
+            CoinType::Solana => 501,
+            CoinType::Other(index) => index,
+        }
+    }
+}
+
+/// `m / purpose' / coin_type' / account' / change / index`, as used by BIP-44 and BIP-84.
+///
+/// Build one rather than writing the string by hand:
+/// `Bip44Path::coin(CoinType::Tron).with_account(0).external(0)` is
+/// `m/44'/195'/0'/0/0`. Every [`KeySource`](super::KeySource) takes it through
+/// [`derive_signer_at`](super::KeySource::derive_signer_at).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bip44Path {
+    pub purpose: u32,
+    pub coin_type: u32,
+    pub account: u32,
+    /// 0 for receiving addresses, 1 for change.
+    pub change: u32,
+    pub index: u32,
+}
+
+impl Bip44Path {
+    /// First receiving address of account 0 on `chain`.
+    pub fn for_chain<C: Chain + ?Sized>(chain: &C) -> Self {
+        Self {
+            purpose: chain.purpose(),
+            coin_type: chain.coin_type(),
+            account: 0,
+            change: 0,
+            index: 0,
+        }
+    }
+
+    /// BIP-44 (legacy addresses) on `coin`, account 0.
+    pub fn coin(coin: CoinType) -> Self {
+        Self::with_purpose(44, coin)
+    }
+
+    /// BIP-49 (P2SH-wrapped SegWit addresses) on `coin`, account 0.
+    pub fn bip49(coin: CoinType) -> Self {
+        Self::with_purpose(49, coin)
+    }
+
+    /// BIP-84 (native SegWit addresses) on `coin`, account 0.
+    pub fn bip84(coin: CoinType) -> Self {
+        Self::with_purpose(84, coin)
+    }
+
+    fn with_purpose(purpose: u32, coin: CoinType) -> Self {
+        Self {
+            purpose,
+            coin_type: coin.index(),
+            account: 0,
+            change: 0,
+            index: 0,
+        }
+    }
+
+    pub fn with_account(self, account: u32) -> Self {
+        Self { account, ..self }
+    }
+
+    pub fn with_index(self, index: u32) -> Self {
+        Self { index, ..self }
+    }
+
+    /// Same position on the internal (change) chain.
+    pub fn internal(self) -> Self {
+        Self { change: 1, ..self }
+    }
+
+    /// Receiving address `index`.
+    pub fn external(self, index: u32) -> Self {
+        Self {
+            change: 0,
+            index,
+            ..self
+        }
+    }
+}
+
+impl FromStr for Bip44Path {
+    type Err = KeySourceError;
+
+    /// Parse `m/purpose'/coin'/account'/change/index`, with the first three
+    /// segments hardened and the last two not.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let path = parse_path(input)?;
+        let children: Vec<ChildNumber> = path.iter().collect();
+        let shape = children.len() == 5
+            && children[..3].iter().all(|c| c.is_hardened())
+            && !children[3].is_hardened()
+            && !children[4].is_hardened();
+        if !shape {
+            return Err(KeySourceError::InvalidPath {
+                input: input.to_string(),
+                reason: "expected m/purpose'/coin'/account'/change/index".to_string(),
+            });
+        }
+        Ok(Self {
+            purpose: children[0].index(),
+            coin_type: children[1].index(),
+            account: children[2].index(),
+            change: children[3].index(),
+            index: children[4].index(),
+        })
+    }
+}
+
+impl fmt::Display for Bip44Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "m/{}'/{}'/{}'/{}/{}",
+            self.purpose, self.coin_type, self.account, self.change, self.index
+        )
+    }
+}
+
+/// Parse a BIP-32 path such as `m/44'/195'/0'/0/0`. Hardened segments may be
+/// marked with `'`, `h` or `H`; errors name the offending segment.
+pub fn parse_path(input: &str) -> Result<DerivationPath, KeySourceError> {
+    let invalid = |reason: String| KeySourceError::InvalidPath {
+        input: input.to_string(),
+        reason,
+    };
+    let mut segments = input.split('/');
+    if segments.next() != Some("m") {
+        return Err(invalid("must start with `m`".to_string()));
+    }
+    let mut path = DerivationPath::default();
+    for (depth, segment) in segments.enumerate() {
+        let digits = segment.strip_suffix(['\'', 'h', 'H']);
+        let hardened = digits.is_some();
+        let digits = digits.unwrap_or(segment);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid(format!(
+                "segment {} `{}` is not an index",
+                depth + 1,
+                segment
+            )));
+        }
+        let child = digits
+            .parse()
+            .ok()
+            .and_then(|index| ChildNumber::new(index, hardened).ok())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "segment {} `{}` is out of range; indices stop at 2147483647",
+                    depth + 1,
+                    segment
+                ))
+            })?;
+        path.push(child);
+    }
+    Ok(path)
+}
+
+/// [`parse_path`], requiring every segment to be hardened, as ed25519
+/// (SLIP-10) derivation does.
+pub fn parse_hardened_path(input: &str) -> Result<DerivationPath, KeySourceError> {
+    let path = parse_path(input)?;
+    if let Some((depth, child)) = path.iter().enumerate().find(|(_, c)| !c.is_hardened()) {
+        return Err(KeySourceError::InvalidPath {
+            input: input.to_string(),
+            reason: format!(
+                "segment {} must be hardened (`{}'`); this key type has no unhardened children",
+                depth + 1,
+                child.index()
+            ),
+        });
+    }
+    Ok(path)
+}
+
+/// [`parse_path`], refusing hardened segments, which cannot be derived from a
+/// public key alone.
+pub fn parse_unhardened_path(input: &str) -> Result<DerivationPath, KeySourceError> {
+    let path = parse_path(input)?;
+    if let Some(depth) = path.iter().position(|c| c.is_hardened()) {
+        return Err(KeySourceError::InvalidPath {
+            input: input.to_string(),
+            reason: format!(
+                "segment {} is hardened, which needs the private key; \
+                 derive the account xpub at that depth instead",
+                depth + 1
+            ),
+        });
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::chain::{ETHEREUM, LITECOIN, TRON, UtxoAddressType};
+
+    #[test]
+    fn standard_paths_per_chain() {
+        assert_eq!(Bip44Path::for_chain(&TRON).to_string(), "m/44'/195'/0'/0/0");
+        assert_eq!(
+            Bip44Path::for_chain(&LITECOIN).to_string(),
+            "m/44'/2'/0'/0/0"
+        );
+        assert_eq!(
+            Bip44Path::for_chain(&ETHEREUM)
+                .with_account(3)
+                .with_index(7)
+                .to_string(),
+            "m/44'/60'/3'/0/7"
+        );
+        let segwit = LITECOIN.with_address_type(UtxoAddressType::P2wpkh);
+        assert_eq!(
+            Bip44Path::for_chain(&segwit).internal().to_string(),
+            "m/84'/2'/0'/1/0"
+        );
+
+        let built = Bip44Path::coin(CoinType::Tron).with_account(0).external(0);
+        assert_eq!(built, Bip44Path::for_chain(&TRON));
+        assert_eq!(
+            Bip44Path::bip84(CoinType::Litecoin),
+            Bip44Path::for_chain(&segwit)
+        );
+        assert_eq!(
+            Bip44Path::bip49(CoinType::Bitcoin)
+                .with_account(2)
+                .external(5)
+                .to_string(),
+            "m/49'/0'/2'/0/5"
+        );
+        assert_eq!(
+            "m/49'/0'/2'/0/5".parse::<Bip44Path>().unwrap(),
+            Bip44Path::bip49(CoinType::Bitcoin)
+                .with_account(2)
+                .external(5)
+        );
+        assert!("m/44'/0'/0'".parse::<Bip44Path>().is_err());
+    }
+
+    #[test]
+    fn path_errors_name_the_bad_segment() {
+        let reason = |result: Result<DerivationPath, KeySourceError>| match result {
+            Err(KeySourceError::InvalidPath { reason, .. }) => reason,
+            other => panic!("expected an invalid path, got {:?}", other),
+        };
+        assert_eq!(parse_path("m/44h/0H/0'/0/1").unwrap().len(), 5);
+        assert_eq!(reason(parse_path("44'/0'")), "must start with `m`");
+        assert_eq!(
+            reason(parse_path("m/44'/x/0")),
+            "segment 2 `x` is not an index"
+        );
+        assert!(reason(parse_path("m/2147483648")).contains("out of range"));
+        assert!(
+            reason(parse_hardened_path("m/44'/501'/0")).starts_with("segment 3 must be hardened")
+        );
+        assert!(reason(parse_unhardened_path("m/0/1'")).starts_with("segment 2 is hardened"));
+    }
+}
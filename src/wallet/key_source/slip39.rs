@@ -0,0 +1,684 @@
+//! SLIP-39 Shamir backup of a mnemonic.
+//!
+//! Instead of one phrase that loses everything when it is stolen or burnt,
+//! the secret is split into share mnemonics of which only a quorum is needed
+//! again, e.g. any 2 of 3 held by different people. Shares can be organised
+//! in groups ("2 of the 3 family members, plus 1 of the 2 safe deposit
+//! boxes"): recovery takes `group_threshold` groups, each with its own
+//! member threshold met.
+//!
+//! The shared secret is the BIP-39 entropy of the phrase, so recovery gives
+//! back the very same phrase; a BIP-39 passphrase, if the wallet uses one, is
+//! not part of the backup and still has to be supplied. The SLIP-39
+//! passphrase given to [`MnemonicKeySource::to_shares`] encrypts the shares
+//! themselves. Wallets that use the SLIP-39 master secret as the BIP-32 seed
+//! directly (Trezor's "Shamir backup") derive different keys from the same
+//! shares; use [`combine`] for those.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::KeySourceError;
+use super::mnemonic::{EntropySource, Language, MnemonicKeySource, OsEntropy};
+use crate::wallet::crypto::memory::SecureBuffer;
+
+static WORDLIST: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    include_str!("slip39_english.txt")
+        .split_whitespace()
+        .collect()
+});
+
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+const CHECKSUM_WORDS: usize = 3;
+/// Identifier, iteration exponent and group/member parameters.
+const HEADER_WORDS: usize = 4;
+const MIN_SECRET_BYTES: usize = 16;
+const MAX_SHARES: u8 = 16;
+const BASE_ITERATIONS: u32 = 10_000;
+const ROUNDS: u8 = 4;
+const DIGEST_BYTES: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Slip39Error {
+    #[error("invalid share: {0}")]
+    InvalidShare(String),
+    #[error("invalid sharing scheme: {0}")]
+    InvalidScheme(String),
+    #[error("shares do not belong to the same backup")]
+    Mismatch,
+    #[error("not enough shares: {0}")]
+    NotEnoughShares(String),
+    #[error("share digest does not match; a share is corrupt or from another backup")]
+    Digest,
+}
+
+/// One group of shares: `count` shares, any `threshold` of which recover it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareGroup {
+    pub threshold: u8,
+    pub count: u8,
+}
+
+/// How [`split`] divides a secret. Passphrase stretching defaults to
+/// iteration exponent 1 (20 000 PBKDF2 iterations in all).
+#[derive(Clone)]
+pub struct ShareOptions {
+    group_threshold: u8,
+    groups: Vec<ShareGroup>,
+    iteration_exponent: u8,
+    entropy: Arc<dyn EntropySource>,
+}
+
+impl ShareOptions {
+    /// A single group of `count` shares, any `threshold` of which recover
+    /// the secret.
+    pub fn new(threshold: u8, count: u8) -> Self {
+        Self::groups(1, vec![ShareGroup { threshold, count }])
+    }
+
+    /// Shares in `groups`, any `group_threshold` of which recover the secret.
+    pub fn groups(group_threshold: u8, groups: Vec<ShareGroup>) -> Self {
+        Self {
+            group_threshold,
+            groups,
+            iteration_exponent: 1,
+            entropy: Arc::new(OsEntropy),
+        }
+    }
+
+    /// Stretch the passphrase with `10 000 << exponent` iterations.
+    pub fn with_iteration_exponent(mut self, exponent: u8) -> Self {
+        self.iteration_exponent = exponent;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: Arc<dyn EntropySource>) -> Self {
+        self.entropy = entropy;
+        self
+    }
+}
+
+impl MnemonicKeySource {
+    /// Split the phrase into SLIP-39 share mnemonics, one list per group,
+    /// encrypted under `passphrase` (may be empty).
+    pub fn to_shares(
+        &self,
+        options: &ShareOptions,
+        passphrase: &str,
+    ) -> Result<Vec<Vec<SecureBuffer>>, KeySourceError> {
+        let mnemonic = Mnemonic::parse(self.phrase())
+            .map_err(|e| KeySourceError::InvalidMnemonic(e.to_string()))?;
+        let entropy = SecureBuffer::new(mnemonic.to_entropy());
+        split(&entropy, options, passphrase)
+    }
+
+    /// Rebuild the source from a quorum of shares made by
+    /// [`to_shares`](Self::to_shares). `language` is that of the original
+    /// phrase: the seed is derived from its words, not only its entropy.
+    pub fn from_shares(
+        shares: &[&str],
+        passphrase: &str,
+        language: Language,
+        bip39_passphrase: Option<&str>,
+    ) -> Result<Self, KeySourceError> {
+        let entropy = combine(shares, passphrase)?;
+        let mnemonic = Mnemonic::from_entropy_in(language, &entropy)
+            .map_err(|e| KeySourceError::InvalidMnemonic(e.to_string()))?;
+        Self::new(&mnemonic.to_string(), bip39_passphrase)
+    }
+}
+
+/// Split `secret` (at least 16 bytes, even length) into share mnemonics, one
+/// list per group of `options`. Shares are written in the original,
+/// non-extendable format; [`combine`] reads both.
+pub fn split(
+    secret: &[u8],
+    options: &ShareOptions,
+    passphrase: &str,
+) -> Result<Vec<Vec<SecureBuffer>>, KeySourceError> {
+    if secret.len() < MIN_SECRET_BYTES || !secret.len().is_multiple_of(2) {
+        return Err(scheme("secret must be an even number of bytes, at least 16").into());
+    }
+    if options.iteration_exponent > 15 {
+        return Err(scheme("iteration exponent must be at most 15").into());
+    }
+    let group_count = options.groups.len();
+    if options.group_threshold == 0
+        || group_count > MAX_SHARES as usize
+        || options.group_threshold as usize > group_count
+    {
+        return Err(scheme("group threshold must be between 1 and the number of groups").into());
+    }
+    for group in &options.groups {
+        if group.threshold == 0 || group.threshold > group.count || group.count > MAX_SHARES {
+            return Err(scheme("member threshold must be between 1 and the share count").into());
+        }
+        // Several copies of one share would pass for a threshold they do not meet.
+        if group.threshold == 1 && group.count > 1 {
+            return Err(scheme("a group with threshold 1 must have exactly one share").into());
+        }
+    }
+    check_passphrase(passphrase)?;
+
+    let random = |len: usize| -> Result<Vec<u8>, KeySourceError> {
+        let mut bytes = vec![0u8; len];
+        options.entropy.fill(&mut bytes)?;
+        Ok(bytes)
+    };
+    let mut id = [0u8; 2];
+    options.entropy.fill(&mut id)?;
+    let id = u16::from_be_bytes(id) & 0x7fff;
+    let encrypted = encrypt(secret, passphrase, options.iteration_exponent, id, false);
+
+    let group_secrets = split_secret(
+        options.group_threshold,
+        group_count as u8,
+        &encrypted,
+        &random,
+    )?;
+    options
+        .groups
+        .iter()
+        .zip(group_secrets)
+        .map(|(group, (group_index, group_secret))| {
+            split_secret(group.threshold, group.count, &group_secret, &random)?
+                .into_iter()
+                .map(|(member_index, value)| {
+                    let share = Share {
+                        id,
+                        extendable: false,
+                        iteration_exponent: options.iteration_exponent,
+                        group_index,
+                        group_threshold: options.group_threshold,
+                        group_count: group_count as u8,
+                        member_index,
+                        member_threshold: group.threshold,
+                        value: SecureBuffer::new(value),
+                    };
+                    Ok(SecureBuffer::from_string(share.to_mnemonic()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Recover the secret from share mnemonics: at least the member threshold of
+/// shares from each of at least the group threshold of groups.
+pub fn combine(shares: &[&str], passphrase: &str) -> Result<SecureBuffer, KeySourceError> {
+    let shares = shares
+        .iter()
+        .map(|mnemonic| Share::parse(mnemonic))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = shares
+        .first()
+        .ok_or_else(|| Slip39Error::NotEnoughShares("no shares given".into()))?;
+    if shares.iter().any(|s| {
+        (s.id, s.extendable, s.iteration_exponent)
+            != (first.id, first.extendable, first.iteration_exponent)
+            || (s.group_threshold, s.group_count) != (first.group_threshold, first.group_count)
+    }) {
+        return Err(Slip39Error::Mismatch.into());
+    }
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        let members = groups.entry(share.group_index).or_default();
+        if members
+            .iter()
+            .any(|m| m.member_threshold != share.member_threshold)
+        {
+            return Err(Slip39Error::Mismatch.into());
+        }
+        match members
+            .iter()
+            .find(|m| m.member_index == share.member_index)
+        {
+            Some(m) if *m.value != *share.value => return Err(Slip39Error::Mismatch.into()),
+            Some(_) => {}
+            None => members.push(share),
+        }
+    }
+
+    let mut group_secrets = Vec::new();
+    for (group_index, members) in &groups {
+        let threshold = members[0].member_threshold;
+        if members.len() < threshold as usize {
+            continue;
+        }
+        let points: Vec<_> = members
+            .iter()
+            .take(threshold as usize)
+            .map(|m| (m.member_index, m.value.to_vec()))
+            .collect();
+        group_secrets.push((*group_index, recover_secret(threshold, &points)?));
+        if group_secrets.len() == first.group_threshold as usize {
+            break;
+        }
+    }
+    if group_secrets.len() < first.group_threshold as usize {
+        return Err(Slip39Error::NotEnoughShares(format!(
+            "{} of {} required groups complete",
+            group_secrets.len(),
+            first.group_threshold
+        ))
+        .into());
+    }
+
+    check_passphrase(passphrase)?;
+    let encrypted = recover_secret(first.group_threshold, &group_secrets)?;
+    Ok(SecureBuffer::new(decrypt(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.id,
+        first.extendable,
+    )))
+}
+
+fn scheme(reason: &str) -> Slip39Error {
+    Slip39Error::InvalidScheme(reason.to_string())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), Slip39Error> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(scheme("passphrase must be printable ASCII"))
+    }
+}
+
+/// One decoded share mnemonic.
+struct Share {
+    id: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: SecureBuffer,
+}
+
+impl Share {
+    fn to_mnemonic(&self) -> String {
+        let mut bits = Bits::default();
+        bits.push(self.id as u32, 15);
+        bits.push(self.extendable as u32, 1);
+        bits.push(self.iteration_exponent as u32, 4);
+        bits.push(self.group_index as u32, 4);
+        bits.push(self.group_threshold as u32 - 1, 4);
+        bits.push(self.group_count as u32 - 1, 4);
+        bits.push(self.member_index as u32, 4);
+        bits.push(self.member_threshold as u32 - 1, 4);
+        // The value is left-padded with zero bits to a whole number of words.
+        bits.push(0, (10 - self.value.len() * 8 % 10) % 10);
+        for byte in self.value.iter() {
+            bits.push(*byte as u32, 8);
+        }
+        let mut words = bits.words();
+        words.extend(checksum(customization(self.extendable), &words));
+        words
+            .iter()
+            .map(|&w| WORDLIST[w as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn parse(mnemonic: &str) -> Result<Self, Slip39Error> {
+        let invalid = |reason: &str| Slip39Error::InvalidShare(reason.to_string());
+        let words = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                WORDLIST
+                    .binary_search(&word.to_lowercase().as_str())
+                    .map(|i| i as u16)
+                    .map_err(|_| invalid(&format!("unknown word {:?}", word)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let value_words = words
+            .len()
+            .checked_sub(HEADER_WORDS + CHECKSUM_WORDS)
+            .filter(|&n| n * 10 >= MIN_SECRET_BYTES * 8)
+            .ok_or_else(|| invalid("too few words"))?;
+        let padding = value_words * 10 % 16;
+        if padding > 8 {
+            return Err(invalid("invalid length"));
+        }
+        // Bit 15 of the header, after the identifier.
+        let extendable = words[1] >> 4 & 1 == 1;
+        if polymod(customization(extendable), &words) != 1 {
+            return Err(invalid("checksum mismatch"));
+        }
+
+        let mut bits = Bits::default();
+        for word in &words[..words.len() - CHECKSUM_WORDS] {
+            bits.push(*word as u32, 10);
+        }
+        let mut reader = bits.reader();
+        let id = reader.take(15) as u16;
+        reader.take(1);
+        let iteration_exponent = reader.take(4) as u8;
+        let group_index = reader.take(4) as u8;
+        let group_threshold = reader.take(4) as u8 + 1;
+        let group_count = reader.take(4) as u8 + 1;
+        let member_index = reader.take(4) as u8;
+        let member_threshold = reader.take(4) as u8 + 1;
+        if reader.take(padding) != 0 {
+            return Err(invalid("padding bits are not zero"));
+        }
+        let value = (0..(value_words * 10 - padding) / 8)
+            .map(|_| reader.take(8) as u8)
+            .collect();
+        if group_threshold > group_count {
+            return Err(invalid("group threshold exceeds group count"));
+        }
+        Ok(Self {
+            id,
+            extendable,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value: SecureBuffer::new(value),
+        })
+    }
+}
+
+/// Bit string packed into 10-bit words, most significant bit first.
+#[derive(Default)]
+struct Bits(Vec<bool>);
+
+impl Bits {
+    fn push(&mut self, value: u32, len: usize) {
+        self.0.extend((0..len).rev().map(|i| value >> i & 1 == 1));
+    }
+
+    fn words(&self) -> Vec<u16> {
+        self.0
+            .chunks(10)
+            .map(|chunk| chunk.iter().fold(0, |acc, &bit| acc << 1 | bit as u16))
+            .collect()
+    }
+
+    fn reader(&self) -> BitReader<'_> {
+        BitReader(&self.0)
+    }
+}
+
+struct BitReader<'a>(&'a [bool]);
+
+impl BitReader<'_> {
+    fn take(&mut self, len: usize) -> u32 {
+        let (head, rest) = self.0.split_at(len.min(self.0.len()));
+        self.0 = rest;
+        head.iter().fold(0, |acc, &bit| acc << 1 | bit as u32)
+    }
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_EXTENDABLE
+    } else {
+        CUSTOMIZATION
+    }
+}
+
+/// RS1024 checksum over `words`, salted with the customization string.
+fn polymod(customization: &[u8], words: &[u16]) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+        0x21b1f890, 0x3f3f120,
+    ];
+    let values = customization
+        .iter()
+        .map(|&b| b as u32)
+        .chain(words.iter().map(|&w| w as u32));
+    values.fold(1, |chk, value| {
+        let top = chk >> 20;
+        let chk = (chk & 0xfffff) << 10 ^ value;
+        (0..10)
+            .filter(|i| top >> i & 1 == 1)
+            .fold(chk, |chk, i| chk ^ GEN[i])
+    })
+}
+
+fn checksum(customization: &[u8], words: &[u16]) -> [u16; CHECKSUM_WORDS] {
+    let mut padded = words.to_vec();
+    padded.extend([0; CHECKSUM_WORDS]);
+    let chk = polymod(customization, &padded) ^ 1;
+    [
+        (chk >> 20) as u16,
+        (chk >> 10 & 0x3ff) as u16,
+        (chk & 0x3ff) as u16,
+    ]
+}
+
+/// Four-round Feistel network keyed by PBKDF2 of the passphrase.
+fn encrypt(secret: &[u8], passphrase: &str, exponent: u8, id: u16, extendable: bool) -> Vec<u8> {
+    feistel(secret, passphrase, exponent, id, extendable, 0..ROUNDS)
+}
+
+fn decrypt(secret: &[u8], passphrase: &str, exponent: u8, id: u16, extendable: bool) -> Vec<u8> {
+    feistel(
+        secret,
+        passphrase,
+        exponent,
+        id,
+        extendable,
+        (0..ROUNDS).rev(),
+    )
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &str,
+    exponent: u8,
+    id: u16,
+    extendable: bool,
+    rounds: impl Iterator<Item = u8>,
+) -> Vec<u8> {
+    let (left, right) = input.split_at(input.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    let mut salt = Vec::new();
+    if !extendable {
+        salt.extend(CUSTOMIZATION);
+        salt.extend(id.to_be_bytes());
+    }
+    let iterations = (BASE_ITERATIONS << exponent) / ROUNDS as u32;
+    for round in rounds {
+        let mut password = vec![round];
+        password.extend(passphrase.as_bytes());
+        let mut round_salt = salt.clone();
+        round_salt.extend(&right);
+        let mut f = vec![0u8; right.len()];
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password, &round_salt, iterations, &mut f);
+        let next = left.iter().zip(&f).map(|(l, f)| l ^ f).collect();
+        left = std::mem::replace(&mut right, next);
+    }
+    right.extend(left);
+    right
+}
+
+/// Shamir-split `secret` into `count` points over GF(256), any `threshold` of
+/// which recover it. Points 254 and 255 carry a digest of the secret and the
+/// secret itself, so a wrong quorum is detected on recovery.
+fn split_secret(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    random: &dyn Fn(usize) -> Result<Vec<u8>, KeySourceError>,
+) -> Result<Vec<(u8, Vec<u8>)>, KeySourceError> {
+    if threshold == 1 {
+        return Ok((0..count).map(|i| (i, secret.to_vec())).collect());
+    }
+    let random_points = threshold - 2;
+    let mut points = (0..random_points)
+        .map(|i| Ok((i, random(secret.len())?)))
+        .collect::<Result<Vec<_>, KeySourceError>>()?;
+    let random_part = random(secret.len() - DIGEST_BYTES)?;
+    let mut digest = share_digest(&random_part, secret).to_vec();
+    digest.extend(&random_part);
+
+    let mut base = points.clone();
+    base.push((DIGEST_INDEX, digest));
+    base.push((SECRET_INDEX, secret.to_vec()));
+    for i in random_points..count {
+        points.push((i, interpolate(&base, i)));
+    }
+    Ok(points)
+}
+
+fn recover_secret(threshold: u8, points: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Slip39Error> {
+    if threshold == 1 {
+        return Ok(points[0].1.clone());
+    }
+    let secret = interpolate(points, SECRET_INDEX);
+    let digest = interpolate(points, DIGEST_INDEX);
+    if digest[..DIGEST_BYTES] != share_digest(&digest[DIGEST_BYTES..], &secret) {
+        return Err(Slip39Error::Digest);
+    }
+    Ok(secret)
+}
+
+fn share_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_BYTES] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random_part).expect("HMAC takes any key size");
+    mac.update(secret);
+    mac.finalize().into_bytes()[..DIGEST_BYTES]
+        .try_into()
+        .expect("4 bytes")
+}
+
+/// Value at `x` of the polynomials through `points`, byte by byte.
+fn interpolate(points: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = points.iter().find(|(px, _)| *px == x) {
+        return value.clone();
+    }
+    let mut result = vec![0u8; points[0].1.len()];
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let basis = points
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |acc, (_, (xj, _))| gf_mul(acc, gf_div(x ^ xj, xi ^ xj)));
+        for (r, y) in result.iter_mut().zip(yi) {
+            *r ^= gf_mul(*y, basis);
+        }
+    }
+    result
+}
+
+/// Exponent and logarithm tables of GF(256) with the AES polynomial and
+/// generator 3.
+static GF: LazyLock<([u8; 255], [u8; 256])> = LazyLock::new(|| {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    for (i, e) in exp.iter_mut().enumerate() {
+        *e = poly as u8;
+        log[poly as usize] = i as u8;
+        poly ^= poly << 1;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+    }
+    (exp, log)
+});
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (exp, log) = &*GF;
+    exp[(log[a as usize] as usize + log[b as usize] as usize) % 255]
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    let (exp, log) = &*GF;
+    exp[(log[a as usize] as usize + 255 - log[b as usize] as usize) % 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_the_reference_vector() {
+        let share = "duckling enlarge academic academic agency result length solution fridge \
+                     kidney coal piece deal husband erode duke ajar critical decision keyboard";
+        let secret = combine(&[share], "TREZOR").unwrap();
+        assert_eq!(hex::encode(&*secret), "bb54aac4b89dc868ba37d9cc21b2cece");
+
+        let corrupt = share.replace("keyboard", "kidney");
+        assert!(matches!(
+            combine(&[&corrupt], "TREZOR"),
+            Err(KeySourceError::Shares(Slip39Error::InvalidShare(_)))
+        ));
+    }
+
+    #[test]
+    fn splits_a_mnemonic_into_groups_and_back() {
+        let source = MnemonicKeySource::random(None);
+        let options = ShareOptions::groups(
+            2,
+            vec![
+                ShareGroup {
+                    threshold: 2,
+                    count: 3,
+                },
+                ShareGroup {
+                    threshold: 1,
+                    count: 1,
+                },
+                ShareGroup {
+                    threshold: 3,
+                    count: 5,
+                },
+            ],
+        )
+        .with_iteration_exponent(0);
+        let groups = source.to_shares(&options, "share pass").unwrap();
+        let words = |group: usize, member: usize| groups[group][member].as_str().unwrap();
+        assert_eq!(words(0, 0).split_whitespace().count(), 20);
+
+        let quorum = [
+            words(0, 2),
+            words(2, 4),
+            words(0, 0),
+            words(2, 1),
+            words(2, 0),
+        ];
+        let recovered =
+            MnemonicKeySource::from_shares(&quorum, "share pass", Language::English, None).unwrap();
+        assert_eq!(recovered.phrase(), source.phrase());
+        let alone = [words(1, 0), words(0, 1), words(0, 2)];
+        let recovered =
+            MnemonicKeySource::from_shares(&alone, "share pass", Language::English, None).unwrap();
+        assert_eq!(recovered.phrase(), source.phrase());
+
+        // One member short in the second group.
+        assert!(matches!(
+            combine(
+                &[words(0, 0), words(0, 1), words(2, 0), words(2, 3)],
+                "share pass"
+            ),
+            Err(KeySourceError::Shares(Slip39Error::NotEnoughShares(_)))
+        ));
+        // A wrong passphrase yields a different phrase rather than an error.
+        let wrong = MnemonicKeySource::from_shares(&alone, "guess", Language::English, None);
+        assert_ne!(wrong.unwrap().phrase(), source.phrase());
+    }
+}
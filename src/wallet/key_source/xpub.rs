@@ -3,8 +3,9 @@ use bip32::XPub;
 use k256::ecdsa::VerifyingKey;
 use std::str::FromStr;
 
-use super::{KeySource, KeySourceError};
+use super::{Bip44Path, KeySource, KeySourceError, parse_unhardened_path};
 use crate::wallet::Signer;
+use crate::wallet::signer::SignerError;
 
 /// A signer that can only provide public keys but cannot sign.
 /// Used for watch-only wallets.
@@ -20,14 +21,21 @@ impl WatchOnlySigner {
 
 #[async_trait]
 impl Signer for WatchOnlySigner {
-    async fn sign(&self, _message: &[u8]) -> Result<Vec<u8>, ()> {
-        // Watch-only wallets cannot sign.
-        Err(())
+    async fn sign(&self, _message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        Err(SignerError::WatchOnly)
+    }
+
+    async fn sign_prehash(&self, _prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        Err(SignerError::WatchOnly)
     }
 
     fn public_key(&self) -> Vec<u8> {
         self.public_key.to_encoded_point(true).as_bytes().to_vec()
     }
+
+    fn can_sign(&self) -> bool {
+        false
+    }
 }
 
 /// Key source based on an Extended Public Key (xpub).
@@ -57,13 +65,9 @@ impl KeySource for XPubKeySource {
         // We need to handle the "m/" prefix or relative paths.
         // If the xpub is already at "m/44'/0'/0'", then deriving "0/0" gives the first address.
 
-        let derivation_path: bip32::DerivationPath = path
-            .parse()
-            .map_err(|e| KeySourceError::Derivation(format!("Invalid path: {}", e)))?;
+        let derivation_path = parse_unhardened_path(path)?;
 
-        // Wait, `derive_child` only takes one index. `derive_from_path` is for XPrv usually.
-        // For XPub, we need to iterate over the path components.
-        // Also, XPub cannot derive hardened indices.
+        // `derive_child` only takes one index, so walk the path components.
 
         let mut current_xpub = self.xpub.clone();
         for child_index in derivation_path {
@@ -74,6 +78,22 @@ impl KeySource for XPubKeySource {
 
         Ok(Box::new(WatchOnlySigner::new(*current_xpub.public_key())))
     }
+
+    /// Treats the xpub as the account key of `path` (depth 3, as exported by
+    /// [`MnemonicKeySource::derive_xpub`](super::MnemonicKeySource::derive_xpub))
+    /// and derives `m/<change>/<index>` below it. The xpub cannot show which
+    /// purpose, coin or account it was made for, so those are not checked.
+    async fn derive_signer_at(&self, path: &Bip44Path) -> Result<Box<dyn Signer>, KeySourceError> {
+        let depth = self.xpub.attrs().depth;
+        if depth != 3 {
+            return Err(KeySourceError::Derivation(format!(
+                "xpub at depth {} is not an account key; derive {} from a string path",
+                depth, path
+            )));
+        }
+        self.derive_signer(&format!("m/{}/{}", path.change, path.index))
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -102,6 +122,6 @@ mod tests {
 
         // Ensure signing fails
         let res = signer.sign(b"test").await;
-        assert!(res.is_err());
+        assert!(matches!(res, Err(SignerError::WatchOnly)));
     }
 }
@@ -0,0 +1,57 @@
+//! Key material in the operating system's credential store (feature `keyring`).
+
+use std::io;
+
+use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::secrets::{KeyStore, SecretStore};
+
+/// [`KeyStore`] over the OS credential store: the macOS Keychain, the
+/// Windows Credential Manager, or a Secret Service (GNOME Keyring, KWallet)
+/// on Linux. Each alias is one credential of `service`, so the wallets of
+/// different applications on one machine do not see each other's keys.
+///
+/// Calls block on the platform API; from async code, make them on a
+/// blocking thread.
+#[derive(Debug, Clone)]
+pub struct OsKeyring {
+    service: String,
+}
+
+impl OsKeyring {
+    /// Credentials filed under `service`, e.g. `com.example.wallet`.
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    fn entry(&self, alias: &str) -> io::Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, alias).map_err(io::Error::other)
+    }
+}
+
+impl SecretStore for OsKeyring {
+    fn get(&self, name: &str) -> io::Result<Option<SecureBuffer>> {
+        match self.entry(name)?.get_secret() {
+            Ok(secret) => Ok(Some(SecureBuffer::new(secret))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+}
+
+impl KeyStore for OsKeyring {
+    fn put(&self, alias: &str, secret: &SecureBuffer) -> io::Result<()> {
+        self.entry(alias)?
+            .set_secret(secret.as_ref())
+            .map_err(io::Error::other)
+    }
+
+    fn delete(&self, alias: &str) -> io::Result<bool> {
+        match self.entry(alias)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use crate::time::{SystemTime, UNIX_EPOCH};
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::persist::PRODUCER;
+use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::key_source::MnemonicKeySource;
+use crate::wallet::signer::local::LocalSigner;
+
+/// Current keystore file format. Version 2 records when and by which release
+/// the file was made, and authenticates every field, not just the kind.
+pub const KEYSTORE_VERSION: u32 = 2;
+
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "scrypt";
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+/// Costs past which a file's scrypt parameters are refused rather than
+/// derived, so a crafted keystore cannot exhaust memory or CPU on load.
+const MAX_LOG_N: u8 = 20;
+const MAX_KDF_MEMORY: u64 = 1 << 30;
+const MAX_P: u32 = 16;
+
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("unsupported {0}")]
+    Unsupported(String),
+    #[error("keystore holds a {found}, not a {expected}")]
+    WrongKind {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("invalid scrypt parameters: {0}")]
+    Kdf(String),
+    #[error("wrong password or corrupted keystore")]
+    Decrypt,
+    #[error("invalid key material: {0}")]
+    InvalidKey(String),
+    #[error("malformed keystore: {0}")]
+    Format(String),
+    #[error("keystore file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What a keystore protects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeystoreKind {
+    /// A BIP-39 phrase. The optional BIP-39 passphrase is never stored.
+    Mnemonic,
+    /// A raw 32-byte secp256k1 secret key.
+    PrivateKey,
+    /// A [`Backup`](crate::wallet::backup::Backup) bundle.
+    Backup,
+}
+
+impl KeystoreKind {
+    fn name(self) -> &'static str {
+        match self {
+            KeystoreKind::Mnemonic => "mnemonic",
+            KeystoreKind::PrivateKey => "private key",
+            KeystoreKind::Backup => "wallet backup",
+        }
+    }
+}
+
+/// scrypt cost, stored in the file so it can be raised for new keystores
+/// without breaking old ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// scrypt's recommended interactive cost (N = 2^17, about 128 MiB).
+    fn default() -> Self {
+        Self {
+            log_n: scrypt::Params::RECOMMENDED_LOG_N,
+            r: scrypt::Params::RECOMMENDED_R,
+            p: scrypt::Params::RECOMMENDED_P,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    #[serde(flatten)]
+    scrypt: ScryptParams,
+    salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CryptoSection {
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: KdfParams,
+}
+
+/// Password-encrypted key material, serialized as JSON.
+///
+/// The password is stretched with scrypt into an AES-256-GCM key. The header
+/// and KDF parameters are bound into the authentication tag, so editing any
+/// field makes the file fail to decrypt rather than load as something else.
+/// Version 1 files, which only bound the version and kind, still load; call
+/// [`upgrade`](Self::upgrade) to rewrite them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub kind: KeystoreKind,
+    /// Unix seconds; absent in version 1 files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Release that wrote the file; absent in version 1 files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer: Option<String>,
+    crypto: CryptoSection,
+}
+
+impl Keystore {
+    /// Encrypt the phrase of `source`.
+    pub fn from_mnemonic(
+        source: &MnemonicKeySource,
+        password: &str,
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        Self::encrypt(
+            KeystoreKind::Mnemonic,
+            source.phrase().as_bytes(),
+            password,
+            params,
+        )
+    }
+
+    /// Encrypt a 32-byte secp256k1 secret key.
+    pub fn from_private_key(
+        secret_key: &[u8],
+        password: &str,
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        signer_from_secret(secret_key)?;
+        Self::encrypt(KeystoreKind::PrivateKey, secret_key, password, params)
+    }
+
+    /// Decrypt a mnemonic keystore. `passphrase` is the BIP-39 passphrase, if
+    /// the wallet uses one.
+    pub fn to_mnemonic(
+        &self,
+        password: &str,
+        passphrase: Option<&str>,
+    ) -> Result<MnemonicKeySource, KeystoreError> {
+        let phrase = self.decrypt(KeystoreKind::Mnemonic, password)?;
+        let phrase = phrase
+            .as_str()
+            .map_err(|e| KeystoreError::InvalidKey(e.to_string()))?;
+        MnemonicKeySource::new(phrase, passphrase)
+            .map_err(|e| KeystoreError::InvalidKey(e.to_string()))
+    }
+
+    /// Decrypt a private-key keystore into a signer.
+    pub fn to_signer(&self, password: &str) -> Result<LocalSigner, KeystoreError> {
+        let secret = self.decrypt(KeystoreKind::PrivateKey, password)?;
+        signer_from_secret(&secret)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("keystore serializes to JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        let keystore: Self =
+            serde_json::from_str(json).map_err(|e| KeystoreError::Format(e.to_string()))?;
+        if !(1..=KEYSTORE_VERSION).contains(&keystore.version) {
+            return Err(KeystoreError::UnsupportedVersion(keystore.version));
+        }
+        Ok(keystore)
+    }
+
+    /// Re-encrypt under the current format, keeping the password and KDF cost.
+    /// Returns the keystore unchanged if it is already current.
+    pub fn upgrade(&self, password: &str) -> Result<Self, KeystoreError> {
+        if self.version == KEYSTORE_VERSION {
+            return Ok(self.clone());
+        }
+        let secret = self.decrypt(self.kind, password)?;
+        Self::encrypt(self.kind, &secret, password, self.crypto.kdfparams.scrypt)
+    }
+
+    /// Write the keystore to `path`, readable only by the owner on Unix.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KeystoreError> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        std::io::Write::write_all(&mut file, self.to_json().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn encrypt(
+        kind: KeystoreKind,
+        secret: &[u8],
+        password: &str,
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        let salt: [u8; SALT_LEN] = rand::random();
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut keystore = Self {
+            version: KEYSTORE_VERSION,
+            kind,
+            created_at: Some(created_at),
+            producer: Some(PRODUCER.to_string()),
+            crypto: CryptoSection {
+                cipher: CIPHER.to_string(),
+                nonce: String::new(),
+                ciphertext: String::new(),
+                kdf: KDF.to_string(),
+                kdfparams: KdfParams {
+                    scrypt: params,
+                    salt: hex::encode(salt),
+                },
+            },
+        };
+        keystore.reseal(secret, password)?;
+        Ok(keystore)
+    }
+
+    /// Encrypt `secret` under the current header with a fresh nonce.
+    fn reseal(&mut self, secret: &[u8], password: &str) -> Result<(), KeystoreError> {
+        let salt = decode_hex("salt", &self.crypto.kdfparams.salt)?;
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let key = derive_key(password, &salt, self.crypto.kdfparams.scrypt)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).expect("derived key is 32 bytes");
+        let aad = self.associated_data();
+        let ciphertext = cipher
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: secret,
+                    aad: &aad,
+                },
+            )
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+        self.crypto.nonce = hex::encode(nonce);
+        self.crypto.ciphertext = hex::encode(ciphertext);
+        Ok(())
+    }
+
+    /// Everything but the nonce and ciphertext, as authenticated by the tag.
+    fn associated_data(&self) -> Vec<u8> {
+        if self.version == 1 {
+            return format!("flow-wallet keystore v1 {}", self.kind.name()).into_bytes();
+        }
+        let params = &self.crypto.kdfparams;
+        format!(
+            "flow-wallet keystore v{} {} created={} producer={} cipher={} kdf={} log_n={} r={} p={} salt={}",
+            self.version,
+            self.kind.name(),
+            self.created_at.unwrap_or_default(),
+            self.producer.as_deref().unwrap_or_default(),
+            self.crypto.cipher,
+            self.crypto.kdf,
+            params.scrypt.log_n,
+            params.scrypt.r,
+            params.scrypt.p,
+            params.salt,
+        )
+        .into_bytes()
+    }
+
+    pub(crate) fn decrypt(
+        &self,
+        expected: KeystoreKind,
+        password: &str,
+    ) -> Result<SecureBuffer, KeystoreError> {
+        if self.kind != expected {
+            return Err(KeystoreError::WrongKind {
+                expected: expected.name(),
+                found: self.kind.name(),
+            });
+        }
+        let crypto = &self.crypto;
+        if crypto.cipher != CIPHER {
+            return Err(KeystoreError::Unsupported(format!(
+                "cipher {}",
+                crypto.cipher
+            )));
+        }
+        if crypto.kdf != KDF {
+            return Err(KeystoreError::Unsupported(format!("kdf {}", crypto.kdf)));
+        }
+        let salt = decode_hex("salt", &crypto.kdfparams.salt)?;
+        let nonce: [u8; NONCE_LEN] = decode_hex("nonce", &crypto.nonce)?
+            .try_into()
+            .map_err(|_| KeystoreError::Format("nonce must be 12 bytes".into()))?;
+        let ciphertext = decode_hex("ciphertext", &crypto.ciphertext)?;
+
+        let key = derive_key(password, &salt, crypto.kdfparams.scrypt)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).expect("derived key is 32 bytes");
+        let aad = self.associated_data();
+        cipher
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map(SecureBuffer::new)
+            .map_err(|_| KeystoreError::Decrypt)
+    }
+}
+
+fn derive_key(
+    password: &str,
+    salt: &[u8],
+    params: ScryptParams,
+) -> Result<SecureBuffer, KeystoreError> {
+    check_cost(params)?;
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LEN)
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    let mut key = SecureBuffer::new(vec![0u8; KEY_LEN]);
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, key.as_mut())
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    Ok(key)
+}
+
+fn check_cost(params: ScryptParams) -> Result<(), KeystoreError> {
+    if params.log_n > MAX_LOG_N {
+        return Err(KeystoreError::Kdf(format!(
+            "log_n {} exceeds {}",
+            params.log_n, MAX_LOG_N
+        )));
+    }
+    // scrypt needs 128 * r * N bytes.
+    let memory = (128 * u64::from(params.r)) << params.log_n;
+    if memory > MAX_KDF_MEMORY {
+        return Err(KeystoreError::Kdf(format!(
+            "r {} with log_n {} needs {} MiB",
+            params.r,
+            params.log_n,
+            memory >> 20
+        )));
+    }
+    if params.p > MAX_P {
+        return Err(KeystoreError::Kdf(format!(
+            "p {} exceeds {}",
+            params.p, MAX_P
+        )));
+    }
+    Ok(())
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|e| KeystoreError::Format(format!("{}: {}", field, e)))
+}
+
+fn signer_from_secret(secret: &[u8]) -> Result<LocalSigner, KeystoreError> {
+    if secret.len() != 32 {
+        return Err(KeystoreError::InvalidKey(format!(
+            "secret key must be 32 bytes, got {}",
+            secret.len()
+        )));
+    }
+    LocalSigner::from_slice(secret).map_err(|e| KeystoreError::InvalidKey(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+
+    /// Cheap enough for debug-build tests.
+    const FAST: ScryptParams = ScryptParams {
+        log_n: 10,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn mnemonic_roundtrip_through_json() {
+        let source = MnemonicKeySource::random(None);
+        let json = Keystore::from_mnemonic(&source, "hunter2", FAST)
+            .unwrap()
+            .to_json();
+        assert!(!json.contains(source.phrase().split(' ').next().unwrap()));
+
+        let keystore = Keystore::from_json(&json).unwrap();
+        let restored = keystore.to_mnemonic("hunter2", None).unwrap();
+        assert_eq!(restored.phrase(), source.phrase());
+
+        assert!(matches!(
+            keystore.to_mnemonic("hunter3", None),
+            Err(KeystoreError::Decrypt)
+        ));
+        assert!(matches!(
+            keystore.to_signer("hunter2"),
+            Err(KeystoreError::WrongKind { .. })
+        ));
+    }
+
+    #[test]
+    fn private_key_roundtrip_and_tamper_detection() {
+        let secret = [0x11u8; 32];
+        let keystore = Keystore::from_private_key(&secret, "pw", FAST).unwrap();
+        let expected = LocalSigner::from_bytes(secret).unwrap().public_key();
+        assert_eq!(keystore.to_signer("pw").unwrap().public_key(), expected);
+
+        // Relabelling the file is caught by the authenticated header.
+        let mut relabelled = keystore.clone();
+        relabelled.kind = KeystoreKind::Mnemonic;
+        assert!(matches!(
+            relabelled.to_mnemonic("pw", None),
+            Err(KeystoreError::Decrypt)
+        ));
+
+        assert!(Keystore::from_private_key(&[0u8; 16], "pw", FAST).is_err());
+    }
+
+    #[test]
+    fn version_1_files_load_and_upgrade() {
+        let secret = [0x22u8; 32];
+        let mut legacy = Keystore::from_private_key(&secret, "pw", FAST).unwrap();
+        legacy.version = 1;
+        legacy.created_at = None;
+        legacy.producer = None;
+        legacy.reseal(&secret, "pw").unwrap();
+        let legacy = Keystore::from_json(&legacy.to_json()).unwrap();
+        let expected = legacy.to_signer("pw").unwrap().public_key();
+
+        let upgraded = legacy.upgrade("pw").unwrap();
+        assert_eq!(upgraded.version, KEYSTORE_VERSION);
+        assert!(upgraded.created_at.is_some());
+        assert_eq!(upgraded.to_signer("pw").unwrap().public_key(), expected);
+
+        // Version 2 authenticates the metadata too.
+        let mut backdated = upgraded.clone();
+        backdated.created_at = Some(0);
+        assert!(matches!(
+            backdated.to_signer("pw"),
+            Err(KeystoreError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn refuses_excessive_scrypt_costs_before_deriving() {
+        let secret = [0x33u8; 32];
+        for params in [
+            ScryptParams { log_n: 30, ..FAST },
+            ScryptParams { r: 1 << 20, ..FAST },
+            ScryptParams { p: 1 << 20, ..FAST },
+        ] {
+            assert!(matches!(
+                Keystore::from_private_key(&secret, "pw", params),
+                Err(KeystoreError::Kdf(_))
+            ));
+        }
+
+        let mut crafted = Keystore::from_private_key(&secret, "pw", FAST).unwrap();
+        crafted.crypto.kdfparams.scrypt.log_n = 24;
+        let crafted = Keystore::from_json(&crafted.to_json()).unwrap();
+        assert!(matches!(
+            crafted.to_signer("pw"),
+            Err(KeystoreError::Kdf(_))
+        ));
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OwnedMutexGuard;
+
+use crate::WalletError;
+use crate::node::Provider;
+use crate::node::price::PriceProvider;
+use crate::wallet::backup::{AccountIndex, Backup};
+use crate::wallet::chain::{Chain, ChangeStrategy};
+use crate::wallet::key_source::{Bip44Path, KeySource, KeySourceError};
+use crate::wallet::keystore::{Keystore, ScryptParams};
+use crate::wallet::portfolio::Portfolio;
+use crate::wallet::scheme::Secp256k1;
+use crate::wallet::{Signer, Wallet};
+
+/// A registered chain, shared so wallets for it can be built by chain id.
+pub type DynChain = Arc<dyn Chain<Scheme = Secp256k1>>;
+
+/// Derives wallets for several chains from one [`KeySource`].
+///
+/// Each chain is registered once; its derivation path comes from the chain's own
+/// coin type and purpose, so callers only pick the account and address index.
+/// Chains given a provider with [`set_provider`](Self::set_provider) can also
+/// be read and sent from by chain id, e.g. [`balances`](Self::balances) across
+/// the whole portfolio.
+///
+/// Sends from one funding address are serialized (see
+/// [`lock_source`](Self::lock_source)): two concurrent sends would otherwise
+/// select the same UTXOs, or build Tron transactions on the same reference
+/// block that the node rejects as duplicates.
+pub struct WalletManager<K: KeySource> {
+    source: K,
+    accounts: HashMap<&'static str, Account>,
+    providers: HashMap<&'static str, Arc<dyn Provider>>,
+    spend_locks: Mutex<HashMap<SpendKey, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Chain id and funding address.
+type SpendKey = (String, String);
+
+/// Held while spending from one address; see [`WalletManager::lock_source`].
+pub type SpendGuard = OwnedMutexGuard<()>;
+
+/// A registered chain's account and the next unused index on its change chain.
+struct Account {
+    chain: DynChain,
+    path: Bip44Path,
+    next_change: AtomicU32,
+}
+
+impl<K: KeySource> WalletManager<K> {
+    pub fn new(source: K) -> Self {
+        Self {
+            source,
+            accounts: HashMap::new(),
+            providers: HashMap::new(),
+            spend_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register `chain` with its standard path, account 0.
+    pub fn register<C: Chain<Scheme = Secp256k1> + Clone + 'static>(
+        &mut self,
+        chain: &C,
+    ) -> &mut Self {
+        self.register_account(chain, 0)
+    }
+
+    /// Register `chain` deriving under `account`.
+    pub fn register_account<C: Chain<Scheme = Secp256k1> + Clone + 'static>(
+        &mut self,
+        chain: &C,
+        account: u32,
+    ) -> &mut Self {
+        self.accounts.insert(
+            chain.id(),
+            Account {
+                chain: Arc::new(chain.clone()),
+                path: Bip44Path::for_chain(chain).with_account(account),
+                next_change: AtomicU32::new(0),
+            },
+        );
+        self
+    }
+
+    /// Derivation path of address `index` on a registered chain.
+    pub fn path<C: Chain>(&self, chain: &C, index: u32) -> Result<Bip44Path, KeySourceError> {
+        Ok(self.account(chain)?.path.with_index(index))
+    }
+
+    /// Change to a fresh address on the chain's internal (BIP-44 change) branch.
+    ///
+    /// Each call hands out the next index, so change never lands on an address
+    /// that was shown to a payer, and a restore that scans the internal branch
+    /// finds it again.
+    pub async fn next_change<C: Chain<Scheme = Secp256k1>>(
+        &self,
+        chain: &C,
+    ) -> Result<ChangeStrategy, WalletError> {
+        let account = self.account(chain)?;
+        let index = account.next_change.fetch_add(1, Ordering::Relaxed);
+        let path = account.path.internal().with_index(index);
+        let signer = self.source.derive_signer(&path.to_string()).await?;
+        let address = chain.address_from_pubkey(&signer.public_key())?;
+        Ok(ChangeStrategy::Address(address))
+    }
+
+    /// Index [`next_change`](Self::next_change) will use next on `chain`.
+    pub fn next_change_index<C: Chain>(&self, chain: &C) -> Result<u32, KeySourceError> {
+        Ok(self.account(chain)?.next_change.load(Ordering::Relaxed))
+    }
+
+    /// Resume change derivation at `index`, e.g. one past the last used change
+    /// address found when restoring from a mnemonic.
+    pub fn set_next_change_index<C: Chain>(
+        &self,
+        chain: &C,
+        index: u32,
+    ) -> Result<(), KeySourceError> {
+        self.account(chain)?
+            .next_change
+            .store(index, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Wallet for address `index` on a registered chain.
+    pub async fn wallet<C: Chain<Scheme = Secp256k1>>(
+        &self,
+        chain: C,
+        index: u32,
+    ) -> Result<Wallet<C, Box<dyn Signer>>, KeySourceError> {
+        let path = self.path(&chain, index)?;
+        let signer = self.source.derive_signer(&path.to_string()).await?;
+        Ok(Wallet::new(signer, chain))
+    }
+
+    pub fn key_source(&self) -> &K {
+        &self.source
+    }
+
+    /// Read and send on `chain` through `provider`.
+    pub fn set_provider<C: Chain>(&mut self, chain: &C, provider: Arc<dyn Provider>) -> &mut Self {
+        self.providers.insert(chain.id(), provider);
+        self
+    }
+
+    pub fn provider(&self, chain_id: &str) -> Option<&Arc<dyn Provider>> {
+        self.providers.get(chain_id)
+    }
+
+    /// Ids of the registered chains, sorted.
+    pub fn chains(&self) -> Vec<&'static str> {
+        let mut ids: Vec<_> = self.accounts.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Wallet for address `index` on the registered chain `chain_id`, for
+    /// callers that pick the chain at runtime. Configure it (audit log,
+    /// approval policy) before sending, as with [`wallet`](Self::wallet).
+    pub async fn wallet_by_id(
+        &self,
+        chain_id: &str,
+        index: u32,
+    ) -> Result<Wallet<DynChain, Box<dyn Signer>>, KeySourceError> {
+        let account = self.account_by_id(chain_id)?;
+        let path = account.path.with_index(index);
+        let signer = self.source.derive_signer(&path.to_string()).await?;
+        Ok(Wallet::new(signer, account.chain.clone()))
+    }
+
+    /// First receiving address (index 0) of every registered chain.
+    pub async fn addresses(&self) -> Result<BTreeMap<&'static str, String>, WalletError> {
+        let mut addresses = BTreeMap::new();
+        for chain_id in self.chains() {
+            let wallet = self.wallet_by_id(chain_id, 0).await?;
+            addresses.insert(chain_id, wallet.address()?);
+        }
+        Ok(addresses)
+    }
+
+    /// Native balance of the first receiving address on every registered
+    /// chain, in base units. Each chain succeeds or fails on its own, so one
+    /// unreachable provider does not hide the rest of the portfolio.
+    pub async fn balances(&self) -> BTreeMap<&'static str, Result<String, WalletError>> {
+        let mut balances = BTreeMap::new();
+        for chain_id in self.chains() {
+            let balance = async {
+                let provider = self.require_provider(chain_id)?;
+                let address = self.wallet_by_id(chain_id, 0).await?.address()?;
+                Ok(provider.get_balance(&address).await?)
+            }
+            .await;
+            balances.insert(chain_id, balance);
+        }
+        balances
+    }
+
+    /// Balances of the first receiving address on every registered chain,
+    /// tokens included, valued in `fiat` at `prices`' rates. Each chain
+    /// succeeds or fails on its own, as in [`balances`](Self::balances).
+    pub async fn portfolio(
+        &self,
+        prices: &dyn PriceProvider,
+        fiat: &str,
+    ) -> BTreeMap<&'static str, Result<Portfolio, WalletError>> {
+        let mut portfolio = BTreeMap::new();
+        for chain_id in self.chains() {
+            let valued = async {
+                let provider = self.require_provider(chain_id)?;
+                let wallet = self.wallet_by_id(chain_id, 0).await?;
+                wallet.portfolio(provider.as_ref(), prices, fiat).await
+            }
+            .await;
+            portfolio.insert(chain_id, valued);
+        }
+        portfolio
+    }
+
+    /// Send `amount` base units from the first receiving address on
+    /// `chain_id` to `to`, through the chain's provider. Sends that need an
+    /// audit log or approval policy go through [`wallet_by_id`](Self::wallet_by_id).
+    pub async fn send(&self, chain_id: &str, to: &str, amount: u64) -> Result<String, WalletError> {
+        let provider = self.require_provider(chain_id)?;
+        let wallet = self.wallet_by_id(chain_id, 0).await?;
+        let _guard = self.lock_source(chain_id, &wallet.address()?).await;
+        wallet.send_coins(provider.as_ref(), to, amount).await
+    }
+
+    /// Wait until no other spend from `address` on `chain_id` is in flight,
+    /// and hold off new ones until the guard is dropped.
+    ///
+    /// [`send`](Self::send) takes it itself; take it around sends made
+    /// through [`wallet_by_id`](Self::wallet_by_id) or [`wallet`](Self::wallet)
+    /// so they queue behind the manager's own.
+    pub async fn lock_source(&self, chain_id: &str, address: &str) -> SpendGuard {
+        let lock = {
+            let mut locks = self.spend_locks.lock().unwrap();
+            // Drop the locks nobody holds or waits on.
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks
+                .entry((chain_id.to_string(), address.to_string()))
+                .or_default()
+                .clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Seal `backup` under `password` together with every registered chain's
+    /// account and change index. Key material goes in `backup.keystores`; the
+    /// manager only knows its [`KeySource`], which may not be exportable.
+    pub fn export_backup(
+        &self,
+        mut backup: Backup,
+        password: &str,
+        params: ScryptParams,
+    ) -> Result<Keystore, WalletError> {
+        backup.accounts = self
+            .chains()
+            .into_iter()
+            .map(|chain_id| {
+                let account = &self.accounts[chain_id];
+                AccountIndex {
+                    chain: chain_id.to_string(),
+                    account: account.path.account,
+                    next_change: account.next_change.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        backup.seal(password, params)
+    }
+
+    /// Open a bundle from [`export_backup`](Self::export_backup) and resume
+    /// each chain's account and change index from it. Every chain in the
+    /// bundle must be registered first; nothing is changed otherwise, so
+    /// change addresses handed out on the old host are never reused.
+    /// Returns the bundle for restoring the rest (monitors, watchdog, labels).
+    pub fn import_backup(
+        &mut self,
+        sealed: &Keystore,
+        password: &str,
+    ) -> Result<Backup, WalletError> {
+        let backup = Backup::open(sealed, password)?;
+        for index in &backup.accounts {
+            self.account_by_id(&index.chain)?;
+        }
+        for index in &backup.accounts {
+            let account = self
+                .accounts
+                .get_mut(index.chain.as_str())
+                .expect("checked above");
+            account.path = account.path.with_account(index.account);
+            *account.next_change.get_mut() = index.next_change;
+        }
+        Ok(backup)
+    }
+
+    fn require_provider(&self, chain_id: &str) -> Result<&Arc<dyn Provider>, WalletError> {
+        self.provider(chain_id)
+            .ok_or_else(|| WalletError::NoProvider(chain_id.to_string()))
+    }
+
+    fn account<C: Chain>(&self, chain: &C) -> Result<&Account, KeySourceError> {
+        self.account_by_id(chain.id())
+    }
+
+    fn account_by_id(&self, chain_id: &str) -> Result<&Account, KeySourceError> {
+        self.accounts.get(chain_id).ok_or_else(|| {
+            KeySourceError::Derivation(format!("chain {} is not registered", chain_id))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::chain::{LITECOIN, TRON};
+    use crate::wallet::key_source::MnemonicKeySource;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[tokio::test]
+    async fn derives_registered_chains_on_their_standard_paths() {
+        let source = MnemonicKeySource::new(PHRASE, None).unwrap();
+        let expected = source
+            .derive_signer("m/44'/2'/5'/0/1")
+            .await
+            .unwrap()
+            .public_key();
+
+        let mut manager = WalletManager::new(source);
+        manager.register(&TRON).register_account(&LITECOIN, 5);
+
+        assert_eq!(
+            manager.path(&TRON, 4).unwrap().to_string(),
+            "m/44'/195'/0'/0/4"
+        );
+        let ltc = manager.wallet(LITECOIN, 1).await.unwrap();
+        assert_eq!(ltc.signer.public_key(), expected);
+
+        let unregistered = crate::wallet::chain::ETHEREUM;
+        assert!(manager.wallet(unregistered, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn change_walks_the_internal_branch() {
+        let source = MnemonicKeySource::new(PHRASE, None).unwrap();
+        let internal = |i: u32| format!("m/44'/2'/0'/1/{}", i);
+        let mut expected = Vec::new();
+        for i in [0, 1, 7] {
+            let signer = source.derive_signer(&internal(i)).await.unwrap();
+            expected.push(ChangeStrategy::Address(
+                LITECOIN.address_from_pubkey(&signer.public_key()).unwrap(),
+            ));
+        }
+
+        let mut manager = WalletManager::new(source);
+        manager.register(&LITECOIN);
+        assert_eq!(manager.next_change(&LITECOIN).await.unwrap(), expected[0]);
+        assert_eq!(manager.next_change(&LITECOIN).await.unwrap(), expected[1]);
+        assert_eq!(manager.next_change_index(&LITECOIN).unwrap(), 2);
+
+        manager.set_next_change_index(&LITECOIN, 7).unwrap();
+        assert_eq!(manager.next_change(&LITECOIN).await.unwrap(), expected[2]);
+    }
+
+    #[tokio::test]
+    async fn sends_from_one_address_take_turns() {
+        use std::time::Duration;
+
+        use async_trait::async_trait;
+
+        use crate::node::{
+            NodeError, RawTransaction, ReadProvider, Transaction, TransactionProvider,
+        };
+
+        #[derive(Default)]
+        struct Busy {
+            in_flight: AtomicU32,
+            most: AtomicU32,
+        }
+
+        #[async_trait]
+        impl ReadProvider for Busy {
+            fn get_decimals(&self) -> u32 {
+                6
+            }
+            async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+                Ok(vec![])
+            }
+            async fn get_block_number(&self) -> Result<u64, NodeError> {
+                Ok(0)
+            }
+            async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+                Ok("0".into())
+            }
+        }
+
+        #[async_trait]
+        impl TransactionProvider for Busy {
+            async fn create_transaction(
+                &self,
+                _: &str,
+                _: &str,
+                _: u64,
+            ) -> Result<RawTransaction, NodeError> {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.most.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Err(NodeError::Api("not today".into()))
+            }
+            async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+                unimplemented!()
+            }
+        }
+
+        let provider = Arc::new(Busy::default());
+        let mut manager = WalletManager::new(MnemonicKeySource::new(PHRASE, None).unwrap());
+        manager
+            .register(&TRON)
+            .set_provider(&TRON, provider.clone());
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let (a, b, c) = tokio::join!(
+            manager.send("tron", to, 1),
+            manager.send("tron", to, 2),
+            manager.send("tron", to, 3)
+        );
+        assert!(a.is_err() && b.is_err() && c.is_err());
+        assert_eq!(provider.most.load(Ordering::SeqCst), 1);
+
+        // Another address is not held up.
+        let own = manager.wallet(TRON, 0).await.unwrap().address().unwrap();
+        let _guard = manager.lock_source("tron", &own).await;
+        let other = manager.lock_source("tron", "Tother");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), other)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn portfolio_by_chain_id() {
+        let source = MnemonicKeySource::new(PHRASE, None).unwrap();
+        let mut manager = WalletManager::new(source);
+        manager
+            .register(&TRON)
+            .register(&LITECOIN)
+            .set_provider(&TRON, Arc::new(OfflineProvider));
+
+        let addresses = manager.addresses().await.unwrap();
+        assert_eq!(manager.chains(), ["litecoin", "tron"]);
+        assert_eq!(
+            addresses["tron"],
+            manager.wallet(TRON, 0).await.unwrap().address().unwrap()
+        );
+
+        let balances = manager.balances().await;
+        assert_eq!(balances["tron"].as_ref().unwrap(), "0");
+        assert!(matches!(
+            balances["litecoin"],
+            Err(WalletError::NoProvider(_))
+        ));
+        assert!(matches!(
+            manager.send("ethereum", "0x00", 1).await,
+            Err(WalletError::NoProvider(_))
+        ));
+        // The offline provider refuses to build the transaction.
+        assert!(matches!(
+            manager
+                .send("tron", "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT", 1)
+                .await,
+            Err(WalletError::Node(_))
+        ));
+    }
+}
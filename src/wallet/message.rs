@@ -0,0 +1,121 @@
+//! Signing and verifying arbitrary messages, as wallets do to prove control
+//! of an address. See [`MessageFormat`] for the scheme of each chain family.
+
+use crate::wallet::chain::caps::same_address;
+use crate::wallet::chain::sdk::parse_recoverable_signature;
+use crate::wallet::chain::{Chain, ChainError, MessageFormat};
+use crate::wallet::scheme::Secp256k1;
+use crate::wallet::{Signer, Wallet};
+
+impl<C: Chain<Scheme = Secp256k1>, T: Signer> Wallet<C, T> {
+    /// Sign `message` the way the chain's own wallets do (TronLink,
+    /// MetaMask, Bitcoin Core's `signmessage`), so that any of them verifies it.
+    pub async fn sign_message(&self, message: &[u8]) -> Result<String, crate::WalletError> {
+        self.check_can_sign()?;
+        let format = message_format(&self.chain)?;
+        let digest = format.digest(message);
+        let signature = self
+            .signer
+            .sign_recoverable(&digest)
+            .await
+            .map_err(|source| crate::WalletError::SigningFailed {
+                context: format!("message on {}", self.chain.id()),
+                source,
+            })?;
+        let (signature, parity) =
+            parse_recoverable_signature(&signature, &digest, &self.signer.public_key())?;
+        Ok(format.encode(&signature, parity))
+    }
+
+    /// Whether `signature` is this wallet's signature of `message`.
+    pub fn verify_message(&self, message: &[u8], signature: &str) -> Result<bool, ChainError> {
+        verify_message(&self.chain, &self.address()?, message, signature)
+    }
+}
+
+/// Address whose key produced `signature` over `message` on `chain`.
+pub fn recover_message_signer(
+    chain: &(impl Chain + ?Sized),
+    message: &[u8],
+    signature: &str,
+) -> Result<String, ChainError> {
+    let pubkey = message_format(chain)?.recover(message, signature)?;
+    chain.address_from_pubkey(&pubkey)
+}
+
+/// Whether `signature` over `message` was made by the key of `address`.
+/// Malformed signatures are errors; well-formed ones by another key are
+/// `false`. EVM addresses match in any casing, checksummed or not.
+pub fn verify_message(
+    chain: &(impl Chain + ?Sized),
+    address: &str,
+    message: &[u8],
+    signature: &str,
+) -> Result<bool, ChainError> {
+    let signer = recover_message_signer(chain, message, signature)?;
+    Ok(same_address(&signer, address))
+}
+
+fn message_format(chain: &(impl Chain + ?Sized)) -> Result<MessageFormat, ChainError> {
+    chain
+        .message_format()
+        .ok_or_else(|| ChainError::Other(format!("{} cannot sign messages", chain.id())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::chain::{BITCOIN, ETHEREUM, LITECOIN, TRON};
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[tokio::test]
+    async fn signatures_recover_the_signing_address_on_each_chain() {
+        let signer = || LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let message = b"I own this address";
+
+        let tron = Wallet::new(signer(), TRON);
+        let signature = tron.sign_message(message).await.unwrap();
+        assert!(signature.starts_with("0x") && signature.len() == 132);
+        assert!(tron.verify_message(message, &signature).unwrap());
+        assert!(!tron.verify_message(b"something else", &signature).unwrap());
+
+        let eth = Wallet::new(signer(), ETHEREUM);
+        let signature = eth.sign_message(message).await.unwrap();
+        assert_eq!(
+            recover_message_signer(&ETHEREUM, message, &signature).unwrap(),
+            eth.address().unwrap()
+        );
+        let lowercase = eth.address().unwrap().to_lowercase();
+        assert_ne!(lowercase, eth.address().unwrap());
+        assert!(verify_message(&ETHEREUM, &lowercase, message, &signature).unwrap());
+        // Same key and hash function, different prefix.
+        assert!(!tron.verify_message(message, &signature).unwrap());
+
+        for chain in [LITECOIN, BITCOIN] {
+            let wallet = Wallet::new(signer(), chain);
+            let signature = wallet.sign_message(message).await.unwrap();
+            assert_eq!(signature.len(), 88);
+            assert!(wallet.verify_message(message, &signature).unwrap());
+        }
+        assert!(verify_message(&LITECOIN, "x", message, "not base64!").is_err());
+    }
+
+    #[test]
+    fn ethereum_digest_matches_personal_sign() {
+        // keccak256("\x19Ethereum Signed Message:\n5hello")
+        assert_eq!(
+            hex::encode(MessageFormat::Ethereum.digest(b"hello")),
+            "50b2c43fd39106bafbba0da34fc430e1f91e3c96ea2acee2bc34119f92b37750"
+        );
+    }
+
+    #[test]
+    fn tron_digest_matches_sign_message_v2() {
+        // keccak256("\x19TRON Signed Message:\n11hello world"), the length
+        // being that of the message rather than a fixed 32.
+        assert_eq!(
+            hex::encode(MessageFormat::Tron.digest(b"hello world")),
+            "cf02daeb2bea196ed5692322a66ed50080ce74ff8cb711199f1b04f3c13bc10d"
+        );
+    }
+}
@@ -1,35 +1,454 @@
+#[cfg(feature = "erc4337")]
+pub mod aa;
+pub mod approval;
+pub mod audit;
+pub mod backup;
+pub mod bip38;
 pub mod chain;
 pub mod crypto;
+pub mod export;
+pub mod fee_bump;
+pub mod fee_payer;
+pub mod hooks;
+pub mod idempotency;
 pub mod key_source;
+#[cfg(feature = "keyring")]
+pub mod keyring;
+pub mod keystore;
+pub mod manager;
+pub mod message;
+#[cfg(feature = "network")]
+pub mod nft;
+pub mod offline;
+pub mod options;
+pub mod ownership;
+pub mod payout;
+#[cfg(feature = "network")]
+pub mod permissions;
+pub mod policy;
+pub mod portfolio;
+pub mod scheme;
+pub mod screening;
+pub mod secrets;
 pub mod signer;
+pub mod simulate;
+#[cfg(feature = "network")]
+pub mod staking;
+#[cfg(feature = "network")]
+pub mod token;
+pub mod verify;
+pub mod watch;
 
-use crate::wallet::chain::{Chain, ChainError};
+use crate::time::Instant;
+use std::sync::Arc;
+
+use crate::correlation::CorrelationId;
+use crate::metrics::Metrics;
+use crate::monitor::confirmations::{
+    Confirmation, ConfirmationPolicy, NATIVE, Receipt, WaitOptions, confirmations,
+};
+use crate::monitor::dedup::dedup_transactions;
+use crate::monitor::watchdog::SendWatchdog;
+use crate::node::resolver::NameResolver;
+use crate::node::utils::format_units;
+use crate::node::{RawTransaction, TxStatus};
+use crate::shutdown::Shutdown;
+use crate::wallet::approval::{ApprovalPolicy, ApprovalRequest};
+use crate::wallet::audit::{AuditKind, AuditRecord, AuditSink};
+use crate::wallet::chain::sdk::taproot_tweak;
+use crate::wallet::chain::{Chain, ChainError, Network, SignatureFormat, SignaturePolicy, TxCaps};
+use crate::wallet::fee_payer::Sponsorship;
+use crate::wallet::hooks::TransactionHook;
+use crate::wallet::idempotency::IdempotencyStore;
+use crate::wallet::key_source::xpub::WatchOnlySigner;
+use crate::wallet::options::SendOptions;
+use crate::wallet::policy::{PolicyError, SpendPolicy, SpendRequest};
+use crate::wallet::scheme::{KeyType, PublicKey, Secp256k1, SignatureScheme};
+use crate::wallet::screening::{FailMode, Screening, ScreeningError};
+use crate::wallet::signer::{SignMode, SignerError, SigningContext, recoverable_from_der};
 use async_trait::async_trait;
+use futures_util::StreamExt;
 
+/// Produces signatures under scheme `S` (secp256k1 unless stated otherwise).
 #[async_trait]
-pub trait Signer: Send + Sync {
-    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, ()>;
+pub trait Signer<S: SignatureScheme = Secp256k1>: Send + Sync {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError>;
+    /// Sign a 32-byte digest the chain has already hashed (e.g. keccak256 on EVM chains).
+    /// Signers that can only hash-then-sign keep the default, which refuses.
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        let _ = prehash;
+        Err(SignerError::Unsupported("prehashed signing"))
+    }
+    /// [`sign`](Self::sign) with a description of the transaction, for signers
+    /// that show it to a person before signing. The default ignores it.
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        let _ = context;
+        self.sign(message).await
+    }
+    /// [`sign_prehash`](Self::sign_prehash) with a description of the transaction.
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        let _ = context;
+        self.sign_prehash(prehash).await
+    }
+    /// Sign a 32-byte prehash, returning the 65-byte `r || s || recid` that Tron
+    /// and EVM chains need. The default signs with
+    /// [`sign_prehash`](Self::sign_prehash) and recovers the id against
+    /// [`public_key`](Self::public_key).
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        let der = self.sign_prehash(prehash).await?;
+        recoverable_from_der(prehash, &der, &self.public_key())
+    }
+    /// BIP-340 Schnorr signature (64 bytes) over a 32-byte prehash. With a
+    /// `tweak`, signs for the tweaked key `P + tweak·G` of the even-y form of
+    /// this key, as Taproot key-path spends do. Signers without Schnorr
+    /// support keep the default, which refuses.
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        let _ = (prehash, tweak);
+        Err(SignerError::Unsupported("Schnorr signing"))
+    }
+    /// [`sign_recoverable`](Self::sign_recoverable) with a description of the transaction.
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        let der = self.sign_prehash_with_context(prehash, context).await?;
+        recoverable_from_der(prehash, &der, &self.public_key())
+    }
+    /// Sign each of `payloads` as `mode` says, with `context` if given, and
+    /// return one result per payload, in order. The default makes up to
+    /// `concurrency` single-payload calls at a time; backends that can sign
+    /// a batch in one round (e.g. MPC) override it.
+    async fn sign_many(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        concurrency: usize,
+    ) -> Vec<Result<Vec<u8>, SignerError>> {
+        let calls: Vec<_> = payloads
+            .iter()
+            .map(|payload| sign_as(self, payload, mode, context))
+            .collect();
+        futures_util::stream::iter(calls)
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
     fn public_key(&self) -> Vec<u8>;
+    /// Curve of [`public_key`](Self::public_key); the scheme's unless a signer says otherwise.
+    fn key_type(&self) -> KeyType {
+        S::KEY_TYPE
+    }
+    /// [`public_key`](Self::public_key) tagged with its curve.
+    fn tagged_public_key(&self) -> PublicKey {
+        PublicKey::new(self.key_type(), self.public_key())
+    }
+    /// Network this key was derived for, if declared. Wallets refuse to sign
+    /// for a chain on the other network; `None` signs for either.
+    fn network(&self) -> Option<Network> {
+        None
+    }
+    /// Whether this signer holds a key to sign with. Wallets around one that
+    /// does not are watch-only: they query, build and export, but refuse to
+    /// sign with [`WalletError::WatchOnly`](crate::WalletError::WatchOnly).
+    fn can_sign(&self) -> bool {
+        true
+    }
 }
 
 #[async_trait]
-impl Signer for Box<dyn Signer> {
-    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, ()> {
+impl<S: SignatureScheme> Signer<S> for Box<dyn Signer<S>> {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
         (**self).sign(message).await
     }
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        (**self).sign_prehash(prehash).await
+    }
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        (**self).sign_with_context(message, context).await
+    }
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        (**self).sign_prehash_with_context(prehash, context).await
+    }
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        (**self).sign_recoverable(prehash).await
+    }
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        (**self)
+            .sign_recoverable_with_context(prehash, context)
+            .await
+    }
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        (**self).sign_schnorr(prehash, tweak).await
+    }
+    async fn sign_many(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        concurrency: usize,
+    ) -> Vec<Result<Vec<u8>, SignerError>> {
+        (**self)
+            .sign_many(payloads, mode, context, concurrency)
+            .await
+    }
     fn public_key(&self) -> Vec<u8> {
         (**self).public_key()
     }
+    fn key_type(&self) -> KeyType {
+        (**self).key_type()
+    }
+    fn network(&self) -> Option<Network> {
+        (**self).network()
+    }
+    fn can_sign(&self) -> bool {
+        (**self).can_sign()
+    }
+}
+
+/// A transfer the wallet builds with a provider call of its own rather than
+/// `create_transaction`: a token transfer or approval, an NFT transfer, a
+/// staking operation. `to` is its counterparty, the wallet itself for
+/// operations on its own stake; `amount` is zero for those moving nothing.
+#[cfg(feature = "network")]
+struct BuiltSend {
+    to: String,
+    amount: u64,
+    token: Option<String>,
+    /// What the signer and hooks are shown, unless the send's options
+    /// carry a summary of their own.
+    summary: String,
+}
+
+/// One payload signed by `signer` with the method `mode` and `context` call for.
+pub(crate) async fn sign_as<S: SignatureScheme, T: Signer<S> + ?Sized>(
+    signer: &T,
+    payload: &[u8],
+    mode: SignMode,
+    context: Option<&SigningContext>,
+) -> Result<Vec<u8>, SignerError> {
+    match (mode, context) {
+        (SignMode::Recoverable, Some(context)) => {
+            signer.sign_recoverable_with_context(payload, context).await
+        }
+        (SignMode::Recoverable, None) => signer.sign_recoverable(payload).await,
+        (SignMode::Prehash, Some(context)) => {
+            signer.sign_prehash_with_context(payload, context).await
+        }
+        (SignMode::Prehash, None) => signer.sign_prehash(payload).await,
+        (SignMode::Message, Some(context)) => signer.sign_with_context(payload, context).await,
+        (SignMode::Message, None) => signer.sign(payload).await,
+        (SignMode::Taproot, _) => {
+            let tweak = taproot_tweak(&signer.public_key())
+                .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+            signer.sign_schnorr(payload, Some(&tweak)).await
+        }
+    }
 }
 
-pub struct Wallet<C: Chain, T: Signer> {
+/// A [`Wallet`] over a public key alone: it derives addresses, queries
+/// balances and history, and builds unsigned transactions for export
+/// ([`create_unsigned`](Wallet::create_unsigned)), but every signing call
+/// fails with [`WalletError::WatchOnly`](crate::WalletError::WatchOnly).
+pub type WatchOnlyWallet<C> = Wallet<C, WatchOnlySigner>;
+
+pub struct Wallet<C: Chain, T: Signer<C::Scheme>> {
     pub signer: T,
     pub chain: C,
+    audit: Option<Arc<dyn AuditSink>>,
+    approval: Option<Arc<dyn ApprovalPolicy>>,
+    spend_policies: Vec<Arc<dyn SpendPolicy>>,
+    hooks: Vec<Arc<dyn TransactionHook>>,
+    idempotency: Option<Arc<dyn IdempotencyStore>>,
+    resolver: Option<Arc<dyn NameResolver>>,
+    shutdown: Option<Shutdown>,
+    partial_trust: bool,
+    signature_policy: Option<SignaturePolicy>,
+    watchdog: Option<Arc<SendWatchdog>>,
+    screening: Option<(Arc<dyn Screening>, FailMode)>,
+    metrics: Option<Arc<dyn Metrics>>,
+    signing_concurrency: usize,
+}
+
+impl<C: Chain<Scheme = Secp256k1>> WatchOnlyWallet<C> {
+    /// A watch-only wallet for `public_key`, e.g. a child of an account xpub.
+    pub fn watch_only(public_key: k256::ecdsa::VerifyingKey, chain: C) -> Self {
+        Wallet::new(WatchOnlySigner::new(public_key), chain)
+    }
 }
 
-impl<C: Chain, T: Signer> Wallet<C, T> {
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
     pub fn new(signer: T, chain: C) -> Self {
-        Self { signer, chain }
+        Self {
+            signer,
+            chain,
+            audit: None,
+            approval: None,
+            spend_policies: Vec::new(),
+            hooks: Vec::new(),
+            idempotency: None,
+            resolver: None,
+            shutdown: None,
+            partial_trust: false,
+            signature_policy: None,
+            watchdog: None,
+            screening: None,
+            metrics: None,
+            signing_concurrency: 1,
+        }
+    }
+
+    /// Record every send attempt made through this wallet to `sink`.
+    pub fn with_audit_log(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Ask `policy` to approve every send before it is built, e.g. a
+    /// [`TotpPolicy`](crate::wallet::approval::TotpPolicy) for large amounts.
+    pub fn with_approval_policy(mut self, policy: Arc<dyn ApprovalPolicy>) -> Self {
+        self.approval = Some(policy);
+        self
+    }
+
+    /// Hold every transfer this wallet signs to `policy`, after any added
+    /// before it; see [`policy`] for the built-in limits, destination lists
+    /// and approval callbacks.
+    pub fn with_spend_policy(mut self, policy: Arc<dyn SpendPolicy>) -> Self {
+        self.spend_policies.push(policy);
+        self
+    }
+
+    /// Report each signature the signer produces, with its latency, and
+    /// each broadcast to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Call `hook` around every send, after any hooks added before it.
+    pub fn with_hook(mut self, hook: Arc<dyn TransactionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Record sends made with [`SendOptions::idempotency_key`] in `store`,
+    /// so that sending again with the key cannot pay twice.
+    pub fn with_idempotency_store(mut self, store: Arc<dyn IdempotencyStore>) -> Self {
+        self.idempotency = Some(store);
+        self
+    }
+
+    /// Screen every recipient with `screening` before a send is built; `mode`
+    /// decides whether a screener that cannot answer blocks the send.
+    pub fn with_screening(mut self, screening: Arc<dyn Screening>, mode: FailMode) -> Self {
+        self.screening = Some((screening, mode));
+        self
+    }
+
+    /// Let `send_coins` accept names (e.g. `alice.eth`) that `resolver` supports.
+    pub fn with_name_resolver(mut self, resolver: Arc<dyn NameResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Refuse new sends once `shutdown` is triggered; sends already running complete
+    /// and hold the shutdown open until they have broadcast (or failed).
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Treat providers as partially trusted: before signing a transaction a
+    /// provider built for a send, check it pays exactly the requested amount
+    /// to the recipient (change back to the sender) and, when
+    /// [`SendOptions::fee_limit`] is set, cannot spend more on fees.
+    pub fn with_partial_trust(mut self) -> Self {
+        self.partial_trust = true;
+        self
+    }
+
+    /// Hold signatures to `policy` instead of the chain's
+    /// [`signature_policy`](Chain::signature_policy), e.g. with
+    /// [`HighS::Reject`](chain::HighS::Reject) to surface a signer that is not low-s.
+    pub fn with_signature_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.signature_policy = Some(policy);
+        self
+    }
+
+    /// Hand every transaction this wallet broadcasts to `watchdog`, which
+    /// rebroadcasts it if the network loses it.
+    pub fn with_send_watchdog(mut self, watchdog: Arc<SendWatchdog>) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Ask the signer for up to `limit` signatures at once when a transaction
+    /// has several payloads, e.g. a UTXO spend of many inputs. The default
+    /// of 1 signs one after another, which devices that handle one request
+    /// at a time need.
+    pub fn with_signing_concurrency(mut self, limit: usize) -> Self {
+        self.signing_concurrency = limit.max(1);
+        self
+    }
+
+    /// Whether the signer holds no private key (see [`Signer::can_sign`]).
+    pub fn is_watch_only(&self) -> bool {
+        !self.signer.can_sign()
+    }
+
+    /// Refuse to sign with a watch-only signer, before anything is built.
+    fn check_can_sign(&self) -> Result<(), crate::WalletError> {
+        match self.is_watch_only() {
+            true => Err(crate::WalletError::WatchOnly),
+            false => Ok(()),
+        }
+    }
+
+    /// Canonical form every signature of this wallet is brought into.
+    fn signature_policy(&self) -> SignaturePolicy {
+        self.signature_policy
+            .unwrap_or_else(|| self.chain.signature_policy())
+    }
+
+    /// Resolve `to` if it is a name the configured resolver handles, then check
+    /// the result is a valid address on this chain.
+    pub async fn resolve_recipient(&self, to: &str) -> Result<String, crate::WalletError> {
+        let address = match &self.resolver {
+            Some(resolver) if resolver.supports(to) => resolver.resolve(to).await?,
+            _ => to.to_string(),
+        };
+        self.chain.validate_address(&address)?;
+        Ok(address)
     }
 
     /// Derive the on-chain address for this wallet using the chain rules.
@@ -38,8 +457,39 @@ impl<C: Chain, T: Signer> Wallet<C, T> {
         self.chain.address_from_pubkey(&pk)
     }
 
-    /// Send coins to a destination address.
-    /// Orchestrates the flow: create (async) -> prepare (sync) -> sign (async) -> finalize (sync) -> broadcast (async).
+    /// Transaction history of this wallet's address, enriched with chain-specific details.
+    /// Entries a provider lists more than once are returned once.
+    /// Entries the chain cannot decode keep the details the provider gave, if any.
+    pub async fn history(
+        &self,
+        provider: &dyn crate::node::ReadProvider,
+    ) -> Result<Vec<crate::node::Transaction>, crate::WalletError> {
+        let address = self.address()?;
+        let transactions = provider.get_transactions(&address).await?;
+        let mut transactions = dedup_transactions(self.chain.id(), transactions);
+        for tx in &mut transactions {
+            tx.details = self
+                .chain
+                .decode_provider_tx(tx)
+                .ok()
+                .flatten()
+                .or(tx.details.take());
+        }
+        Ok(transactions)
+    }
+
+    /// Fee of sending `amount` to `to` at each priority, in the chain's base unit.
+    pub async fn estimate_fee(
+        &self,
+        provider: &dyn crate::node::TransactionProvider,
+        to: &str,
+        amount: u64,
+    ) -> Result<crate::node::FeeEstimate, crate::WalletError> {
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        Ok(provider.estimate_fee(&from, &to, amount).await?)
+    }
+
     /// Send coins to a destination address.
     /// Orchestrates the flow: create (async) -> prepare (sync) -> sign (async) -> finalize (sync) -> broadcast (async).
     pub async fn send_coins(
@@ -48,45 +498,933 @@ impl<C: Chain, T: Signer> Wallet<C, T> {
         to: &str,
         amount: u64,
     ) -> Result<String, crate::WalletError> {
+        self.send_coins_with_options(provider, to, amount, &SendOptions::default())
+            .await
+    }
+
+    /// [`send_coins`](Self::send_coins) for an [`Amount`], which must be in
+    /// the provider's decimals and fit the send APIs' `u64`.
+    pub async fn send_amount(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        amount: crate::node::Amount,
+    ) -> Result<String, crate::WalletError> {
+        let decimals = provider.get_decimals();
+        if amount.decimals() != decimals {
+            return Err(crate::node::AmountError::DecimalsMismatch {
+                left: amount.decimals(),
+                right: decimals,
+            }
+            .into());
+        }
+        self.send_coins(provider, to, amount.to_u64()?).await
+    }
+
+    /// Send coins, then poll until the transaction has the confirmations `policy`
+    /// requires for this chain and amount.
+    pub async fn send_and_wait(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        amount: u64,
+        policy: &ConfirmationPolicy,
+        wait: &WaitOptions,
+    ) -> Result<Confirmation, crate::WalletError> {
+        let tx_hash = self.send_coins(provider, to, amount).await?;
         let from = self.address()?;
+        let required = policy.required(self.chain.id(), NATIVE, amount as u128);
+        let deadline = Instant::now() + wait.timeout;
 
-        // 1. Create raw transaction (Async, Network)
-        let raw_tx = provider.create_transaction(&from, to, amount).await?;
+        let mut seen = 0;
+        loop {
+            let tip = provider.get_block_number().await?;
+            let history = provider.get_transactions(&from).await?;
+            if let Some(tx) = history.into_iter().find(|tx| tx.hash == tx_hash) {
+                if tx.is_failed() {
+                    return Err(crate::WalletError::TransactionFailed(tx_hash));
+                }
+                seen = confirmations(tip, tx.block_number);
+                if seen >= required {
+                    return Ok(Confirmation {
+                        tx_hash,
+                        block_number: tx.block_number,
+                        confirmations: seen,
+                    });
+                }
+            }
 
-        // 2. Prepare transaction for signing (Sync, Chain Logic)
-        let bytes_to_sign = self.chain.prepare_transaction(&raw_tx)?;
+            // Shutdown ends the wait early; the error still carries the hash.
+            let stopping = self.shutdown.as_ref().is_some_and(|s| s.is_triggered());
+            if stopping || Instant::now() >= deadline {
+                return Err(crate::WalletError::ConfirmationTimeout {
+                    tx_hash,
+                    confirmations: seen,
+                    required,
+                });
+            }
+            tokio::time::sleep(wait.poll_interval).await;
+        }
+    }
 
-        // 3. Sign the bytes (Async, Signer/MPC)
-        let mut signatures = Vec::new();
-        for bytes in bytes_to_sign {
-            let signature = self
-                .signer
-                .sign(&bytes)
+    /// Send coins and wait until the transaction has `confirmations`
+    /// confirmations, giving up after `timeout`.
+    pub async fn send_and_confirm(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        amount: u64,
+        confirmations: u64,
+        timeout: std::time::Duration,
+    ) -> Result<Receipt, crate::WalletError> {
+        let tx_hash = self.send_coins(provider, to, amount).await?;
+        let wait = WaitOptions {
+            timeout,
+            ..WaitOptions::default()
+        };
+        self.wait_for_receipt(provider, &tx_hash, confirmations, &wait)
+            .await
+    }
+
+    /// Poll `provider` until `tx_hash` has `required` confirmations.
+    ///
+    /// A hash the provider does not know yet is waited on like a pending one;
+    /// a failed transaction ends the wait with
+    /// [`TransactionFailed`](crate::WalletError::TransactionFailed).
+    pub async fn wait_for_receipt(
+        &self,
+        provider: &dyn crate::node::ReadProvider,
+        tx_hash: &str,
+        required: u64,
+        wait: &WaitOptions,
+    ) -> Result<Receipt, crate::WalletError> {
+        let deadline = Instant::now() + wait.timeout;
+        let mut seen = 0;
+        loop {
+            if let Some(transaction) = provider.get_transaction(tx_hash).await? {
+                let tip = provider.get_block_number().await?;
+                match TxStatus::of(&transaction, tip) {
+                    TxStatus::Failed { .. } => {
+                        return Err(crate::WalletError::TransactionFailed(tx_hash.to_string()));
+                    }
+                    TxStatus::Confirmed {
+                        block_number,
+                        confirmations,
+                    } => {
+                        seen = confirmations;
+                        if confirmations >= required {
+                            return Ok(Receipt {
+                                tx_hash: tx_hash.to_string(),
+                                block_number,
+                                timestamp: transaction.timestamp,
+                                confirmations,
+                                fee: transaction.fee(),
+                                transaction,
+                            });
+                        }
+                    }
+                    TxStatus::Pending | TxStatus::NotFound => {}
+                }
+            }
+
+            let stopping = self.shutdown.as_ref().is_some_and(|s| s.is_triggered());
+            if stopping || Instant::now() >= deadline {
+                return Err(crate::WalletError::ConfirmationTimeout {
+                    tx_hash: tx_hash.to_string(),
+                    confirmations: seen,
+                    required,
+                });
+            }
+            tokio::time::sleep(wait.poll_interval).await;
+        }
+    }
+
+    /// Same as [`send_coins`](Self::send_coins), with extra per-send options.
+    ///
+    /// The whole pipeline runs under one [`CorrelationId`] (taken from `options` or
+    /// generated), which providers forward as an HTTP header and audit records carry.
+    pub async fn send_coins_with_options(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> Result<String, crate::WalletError> {
+        self.send_coins_with_cosigners(provider, to, amount, options, &[])
+            .await
+    }
+
+    /// [`send_coins_with_options`](Self::send_coins_with_options) from an
+    /// account whose permission needs several keys: `cosigners` sign after the
+    /// wallet's own key. Set [`SendOptions::permission_id`] to the permission
+    /// the keys hold together.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "send",
+            skip_all,
+            fields(chain = self.chain.id(), to = %to, amount, correlation_id),
+            err
+        )
+    )]
+    pub async fn send_coins_with_cosigners(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        cosigners: &[&dyn Signer<C::Scheme>],
+    ) -> Result<String, crate::WalletError> {
+        self.check_can_sign()?;
+        let _in_flight = match &self.shutdown {
+            Some(shutdown) => Some(shutdown.enter().ok_or(crate::WalletError::ShuttingDown)?),
+            None => None,
+        };
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("correlation_id", correlation_id.as_str());
+        correlation_id
+            .clone()
+            .scope(async {
+                let from = self.address()?;
+                let to = &self.resolve_recipient(to).await?;
+                if let Some(tx_hash) = self
+                    .resume_send(provider, &from, to, Some(amount), options)
+                    .await?
+                {
+                    return Ok(tx_hash);
+                }
+                self.audited_send(&from, to, amount, options, || {
+                    self.execute_send(provider, &from, to, amount, options, cosigners, false)
+                })
+                .await
+            })
+            .await
+    }
+
+    /// Send everything the wallet holds to `to`, less the fee.
+    pub async fn sweep(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+    ) -> Result<String, crate::WalletError> {
+        self.sweep_with_options(provider, to, &SendOptions::default())
+            .await
+    }
+
+    /// [`sweep`](Self::sweep) with extra per-send options. The amount is
+    /// what the provider's [`max_sendable`](crate::node::TransactionProvider::max_sendable)
+    /// leaves after the fee at `options`' fee settings; screening, approval,
+    /// hooks and audit see it like any other send. With an idempotency key,
+    /// a sweep already made under it is resumed rather than repeated.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "sweep",
+            skip_all,
+            fields(chain = self.chain.id(), to = %to, correlation_id),
+            err
+        )
+    )]
+    pub async fn sweep_with_options(
+        &self,
+        provider: &dyn crate::node::Provider,
+        to: &str,
+        options: &SendOptions,
+    ) -> Result<String, crate::WalletError> {
+        self.check_can_sign()?;
+        let _in_flight = match &self.shutdown {
+            Some(shutdown) => Some(shutdown.enter().ok_or(crate::WalletError::ShuttingDown)?),
+            None => None,
+        };
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("correlation_id", correlation_id.as_str());
+        correlation_id
+            .clone()
+            .scope(async {
+                let from = self.address()?;
+                let to = &self.resolve_recipient(to).await?;
+                if let Some(tx_hash) = self.resume_send(provider, &from, to, None, options).await? {
+                    return Ok(tx_hash);
+                }
+                let amount = provider.max_sendable(&from, to, &options.fees()).await?;
+                self.audited_send(&from, to, amount, options, || {
+                    self.execute_send(provider, &from, to, amount, options, &[], true)
+                })
                 .await
-                .map_err(|_| crate::WalletError::SigningFailed)?;
-            signatures.push(signature);
+            })
+            .await
+    }
+
+    /// Audit a send and check it with the approval policy before running `execute`.
+    async fn audited_send<F, Fut>(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        execute: F,
+    ) -> Result<String, crate::WalletError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<String, crate::WalletError>>,
+    {
+        self.audited(from, to, amount, None, options, || async {
+            self.check_amount(amount)?;
+            self.check_screening(from, to, amount, None, options)
+                .await?;
+            self.check_approval(from, to, amount, options).await?;
+            execute().await
+        })
+        .await
+    }
+
+    /// Audit that a send of `amount` of `token`, the chain's coin for
+    /// `None`, was requested, then what `run` made of it.
+    async fn audited<F, Fut>(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        token: Option<&str>,
+        options: &SendOptions,
+        run: F,
+    ) -> Result<String, crate::WalletError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<String, crate::WalletError>>,
+    {
+        self.audit(AuditKind::SendRequested, from, to, amount, token, options);
+        let result = run().await;
+        let kind = match &result {
+            Ok(tx_hash) => AuditKind::SendBroadcast {
+                tx_hash: tx_hash.clone(),
+            },
+            Err(e) => AuditKind::SendFailed {
+                reason: e.to_string(),
+            },
+        };
+        self.audit(kind, from, to, amount, token, options);
+        result
+    }
+
+    fn audit(
+        &self,
+        kind: AuditKind,
+        from: &str,
+        to: &str,
+        amount: u64,
+        token: Option<&str>,
+        options: &SendOptions,
+    ) {
+        if let Some(sink) = &self.audit {
+            let mut record = AuditRecord::new(kind, self.chain.id(), from, to, amount);
+            record.token = token.map(str::to_string);
+            record.metadata = options.metadata.clone();
+            record.correlation_id = CorrelationId::current();
+            sink.record(&record);
         }
+    }
 
-        // 4. Finalize transaction (Sync, Chain Logic)
-        let pubkey = self.signer.public_key();
-        let signed_tx = self
+    fn check_amount(&self, amount: u64) -> Result<(), crate::WalletError> {
+        let minimum = self.chain.min_transfer();
+        if amount < minimum {
+            return Err(crate::WalletError::AmountTooSmall { minimum });
+        }
+        Ok(())
+    }
+
+    async fn check_screening(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        token: Option<&str>,
+        options: &SendOptions,
+    ) -> Result<(), crate::WalletError> {
+        let Some((screening, mode)) = &self.screening else {
+            return Ok(());
+        };
+        match screening.screen(self.chain.id(), to).await {
+            Err(e @ ScreeningError::Unavailable(_)) if *mode == FailMode::Open => {
+                self.audit(
+                    AuditKind::ScreeningBypassed {
+                        reason: e.to_string(),
+                    },
+                    from,
+                    to,
+                    amount,
+                    token,
+                    options,
+                );
+                Ok(())
+            }
+            result => Ok(result?),
+        }
+    }
+
+    async fn check_approval(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> Result<(), crate::WalletError> {
+        let Some(policy) = &self.approval else {
+            return Ok(());
+        };
+        let request = ApprovalRequest {
+            chain: self.chain.id().to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            code: options.approval_code.clone(),
+        };
+        Ok(policy.approve(&request).await?)
+    }
+
+    /// Build, sign and broadcast a transfer of `amount`, or with `sweep` one
+    /// that leaves `from` empty.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_send(
+        &self,
+        provider: &dyn crate::node::Provider,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        cosigners: &[&dyn Signer<C::Scheme>],
+        sweep: bool,
+    ) -> Result<String, crate::WalletError> {
+        let context = self.signing_context(from, to, amount, options);
+        let mut options = options.clone();
+        for hook in &self.hooks {
+            hook.pre_create(&context, &mut options).await?;
+        }
+        let options = &options;
+
+        // A transaction that expired while it was being signed never reaches
+        // the chain, so it is built and signed again, once.
+        let mut rebuilt = false;
+        let mut sponsorships = Vec::new();
+        let result = async {
+            loop {
+                sponsorships.extend(self.sponsor_send(from, amount, options).await?);
+                let raw_tx = self
+                    .build_send(provider, from, to, amount, options, sweep)
+                    .await?;
+                let signed_tx = async {
+                    // A rebuilt send is the one the policies already let through.
+                    if !rebuilt {
+                        let request = self.spend_request(from, to, amount, options);
+                        self.check_spend(&[request]).await?;
+                    }
+                    self.sign_send(&raw_tx, from, to, amount, options, cosigners)
+                        .await
+                }
+                .await;
+                let signed_tx = self.discard_unsent(provider, &raw_tx, signed_tx).await?;
+
+                // 5. Broadcast transaction (Async, Network)
+                match self
+                    .broadcast_send(provider, from, to, amount, options, signed_tx)
+                    .await
+                {
+                    Ok(tx_hash) => {
+                        self.after_broadcast(&context, &tx_hash).await;
+                        return Ok(tx_hash);
+                    }
+                    Err(crate::WalletError::Node(e)) if e.is_expired_transaction() && !rebuilt => {
+                        provider.discard_transaction(&raw_tx).await;
+                        rebuilt = true;
+                    }
+                    Err(e) => return self.discard_unsent(provider, &raw_tx, Err(e)).await,
+                }
+            }
+        }
+        .await;
+        if let Some(payer) = &options.fee_payer {
+            // Only the last build can have been broadcast.
+            let last = sponsorships.len().saturating_sub(1);
+            for (i, sponsorship) in sponsorships.iter().enumerate() {
+                let tx_hash = result.as_ref().ok().filter(|_| i == last);
+                payer
+                    .release(from, sponsorship, tx_hash.map(String::as_str))
+                    .await;
+            }
+        }
+        result
+    }
+
+    /// Pass `result` on, first handing `raw_tx` back to `provider` if the
+    /// error means it will never reach the chain: signing it failed, or the
+    /// node refused it. After a transient failure the node may still have it.
+    async fn discard_unsent<R>(
+        &self,
+        provider: &dyn crate::node::Provider,
+        raw_tx: &RawTransaction,
+        result: Result<R, crate::WalletError>,
+    ) -> Result<R, crate::WalletError> {
+        match &result {
+            Err(crate::WalletError::Node(e)) if e.is_transient() => {}
+            Err(_) => provider.discard_transaction(raw_tx).await,
+            Ok(_) => {}
+        }
+        result
+    }
+
+    async fn after_broadcast(&self, context: &SigningContext, tx_hash: &str) {
+        for hook in &self.hooks {
+            hook.post_broadcast(context, tx_hash).await;
+        }
+    }
+
+    /// What a transfer of `amount` from `from` to `to` is described as to
+    /// the signer and to hooks.
+    fn signing_context(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> SigningContext {
+        let summary = options.summary.clone().unwrap_or_else(|| {
+            format!(
+                "Send {} on {} to {}",
+                format_units(&amount.to_string(), self.chain.decimals()),
+                self.chain.id(),
+                to
+            )
+        });
+        SigningContext {
+            chain: self.chain.id().to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            summary,
+            metadata: options.metadata.clone(),
+        }
+    }
+
+    /// Step 0 of a send: let the fee payer, if one is configured, fund the
+    /// sender's fees. Returns what it did, to be released after the send.
+    async fn sponsor_send(
+        &self,
+        from: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> Result<Option<Sponsorship>, crate::WalletError> {
+        let Some(payer) = &options.fee_payer else {
+            return Ok(None);
+        };
+        if !payer.supports(self.chain.id()) {
+            return Err(crate::WalletError::FeePayer(format!(
+                "fee payer does not support chain {}",
+                self.chain.id()
+            )));
+        }
+        Ok(Some(payer.sponsor(from, amount).await?))
+    }
+
+    /// Step 1 of a send: have the provider build the unsigned transaction;
+    /// with `sweep`, one that leaves `from` empty.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "build", skip_all))]
+    async fn build_send(
+        &self,
+        provider: &dyn crate::node::Provider,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        sweep: bool,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        if options.check_resources {
+            provider.check_resources(from, to, amount).await?;
+        }
+
+        // 1. Create raw transaction (Async, Network)
+        let raw_tx = match &options.coin_selection {
+            _ if sweep => {
+                provider
+                    .create_sweep_transaction(from, to, amount, &options.fees())
+                    .await?
+            }
+            Some(selection) => {
+                provider
+                    .create_transaction_with_coins(from, to, amount, &options.fees(), selection)
+                    .await?
+            }
+            None => {
+                provider
+                    .create_transaction_with_fees(from, to, amount, &options.fees())
+                    .await?
+            }
+        };
+        let raw_tx = match &options.memo {
+            Some(memo) => self.chain.apply_memo(&raw_tx, memo.as_bytes())?,
+            None => raw_tx,
+        };
+        let raw_tx = match options.permission_id {
+            Some(permission_id) => self.chain.apply_permission(&raw_tx, permission_id)?,
+            None => raw_tx,
+        };
+        let raw_tx = match options.expiration {
+            Some(lifetime) => self.chain.extend_expiration(&raw_tx, lifetime)?,
+            None => raw_tx,
+        };
+        Ok(if options.replaceable {
+            self.chain.signal_replaceable(&raw_tx)?
+        } else {
+            raw_tx
+        })
+    }
+
+    /// The transfer of `amount` from `from` to `to` as the spend policies see it.
+    fn spend_request(
+        &self,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> SpendRequest {
+        SpendRequest {
+            chain: self.chain.id().to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            token: None,
+            metadata: options.metadata.clone(),
+        }
+    }
+
+    /// Hold each of `requests` to every spend policy.
+    async fn check_spend(&self, requests: &[SpendRequest]) -> Result<(), crate::WalletError> {
+        for request in requests {
+            for policy in &self.spend_policies {
+                policy.evaluate(request).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// What `raw_tx` pays, as the chain reads it, less change back to the
+    /// wallet; for raw transactions, which come with no account of their own.
+    fn spend_requests(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<Vec<SpendRequest>, crate::WalletError> {
+        let from = self.address()?;
+        let summary = self
             .chain
-            .finalize_transaction(&raw_tx, &signatures, &pubkey)?;
+            .inspect_transaction(raw_tx)
+            .map_err(|e| PolicyError::Uninspectable(e.to_string()))?;
+        Ok(summary
+            .payments
+            .into_iter()
+            .filter(|payment| payment.to != from)
+            .map(|payment| SpendRequest {
+                chain: self.chain.id().to_string(),
+                from: from.clone(),
+                to: payment.to,
+                amount: u64::try_from(payment.amount).unwrap_or(u64::MAX),
+                token: summary.token.clone(),
+                metadata: None,
+            })
+            .collect())
+    }
+
+    /// Steps 2 to 4 of a send: sign `raw_tx` as a transfer of `amount` from
+    /// `from` to `to`, which the caller has held to the spend policies,
+    /// described to the signer and, under partial trust, held to caps on
+    /// exactly that.
+    async fn sign_send(
+        &self,
+        raw_tx: &RawTransaction,
+        from: &str,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        cosigners: &[&dyn Signer<C::Scheme>],
+    ) -> Result<RawTransaction, crate::WalletError> {
+        let context = self.signing_context(from, to, amount, options);
+        let caps = self.partial_trust.then(|| {
+            let caps = TxCaps::new()
+                .recipient(to)
+                .amount(amount as u128)
+                .change(from);
+            match options.fee_limit {
+                Some(limit) => caps.max_fee(limit as u128),
+                None => caps,
+            }
+        });
+        self.sign_raw_with_cosigners(raw_tx, Some(&context), caps.as_ref(), cosigners, true)
+            .await
+    }
+
+    /// Sign and broadcast what `build` makes of the options: a transfer the
+    /// wallet builds with a provider call of its own. It is audited,
+    /// screened, shown to hooks and held to the spend policies as `send`
+    /// describes it, like a coin send. Operations moving nothing to anyone
+    /// else, such as reclaiming a delegation, are not screened.
+    #[cfg(feature = "network")]
+    async fn send_built<F, Fut>(
+        &self,
+        provider: &dyn crate::node::Provider,
+        send: BuiltSend,
+        options: &SendOptions,
+        build: F,
+    ) -> Result<String, crate::WalletError>
+    where
+        F: FnOnce(SendOptions) -> Fut,
+        Fut: std::future::Future<Output = Result<RawTransaction, crate::WalletError>>,
+    {
+        let _in_flight = match &self.shutdown {
+            Some(shutdown) => Some(shutdown.enter().ok_or(crate::WalletError::ShuttingDown)?),
+            None => None,
+        };
+        let from = self.address()?;
+        let token = send.token.as_deref();
+        self.audited(&from, &send.to, send.amount, token, options, || async {
+            if send.amount > 0 && send.to != from {
+                self.check_screening(&from, &send.to, send.amount, token, options)
+                    .await?;
+            }
+            // The context's amount is of the chain's coin, which a token send
+            // does not move.
+            let coins = if token.is_some() { 0 } else { send.amount };
+            let mut context = self.signing_context(&from, &send.to, coins, options);
+            if options.summary.is_none() {
+                context.summary = send.summary.clone();
+            }
+            let mut options = options.clone();
+            for hook in &self.hooks {
+                hook.pre_create(&context, &mut options).await?;
+            }
+
+            let raw_tx = build(options.clone()).await?;
+            let signed_tx = async {
+                let request = SpendRequest {
+                    token: send.token.clone(),
+                    ..self.spend_request(&from, &send.to, send.amount, &options)
+                };
+                self.check_spend(&[request]).await?;
+                self.sign_raw_with_cosigners(&raw_tx, Some(&context), None, &[], true)
+                    .await
+            }
+            .await;
+            let signed_tx = self.discard_unsent(provider, &raw_tx, signed_tx).await?;
+            let result = self.broadcast_checked(provider, &signed_tx).await;
+            let tx_hash = self.discard_unsent(provider, &raw_tx, result).await?;
+            self.after_broadcast(&context, &tx_hash).await;
+            self.watch(&tx_hash, signed_tx);
+            Ok(tx_hash)
+        })
+        .await
+    }
+
+    /// Sign a provider-built raw transaction and broadcast it.
+    /// Runs the prepare -> sign -> finalize -> broadcast half of the send flow.
+    pub async fn sign_and_broadcast(
+        &self,
+        provider: &dyn crate::node::Provider,
+        raw_tx: &RawTransaction,
+    ) -> Result<String, crate::WalletError> {
+        let signed_tx = self.sign_transaction(raw_tx).await?;
 
         // 5. Broadcast transaction (Async, Network)
         let tx_hash = provider.broadcast_transaction(&signed_tx).await?;
+        self.watch(&tx_hash, signed_tx);
 
         Ok(tx_hash)
     }
+
+    fn watch(&self, tx_hash: &str, signed_tx: RawTransaction) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.track(self.chain.id(), tx_hash, signed_tx);
+        }
+    }
+
+    /// Sign a raw transaction without broadcasting it, e.g. one imported with
+    /// [`persist::from_bytes`](crate::persist::from_bytes) on an offline machine.
+    pub async fn sign_transaction(
+        &self,
+        raw_tx: &RawTransaction,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.sign_raw(raw_tx, None, None).await
+    }
+
+    /// [`sign_transaction`](Self::sign_transaction), refusing a transaction that
+    /// does not stay within `caps` (see [`Chain::prepare_transaction_capped`]).
+    pub async fn sign_transaction_capped(
+        &self,
+        raw_tx: &RawTransaction,
+        caps: &TxCaps,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.sign_raw(raw_tx, None, Some(caps)).await
+    }
+
+    /// [`sign_transaction`](Self::sign_transaction), passing `context` to the
+    /// signer so it can show what is being signed.
+    pub async fn sign_transaction_with_context(
+        &self,
+        raw_tx: &RawTransaction,
+        context: &SigningContext,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.sign_raw(raw_tx, Some(context), None).await
+    }
+
+    /// Refuse to sign for this chain with a signer tagged for the other network.
+    fn check_network(&self) -> Result<(), crate::WalletError> {
+        match self.signer.network() {
+            Some(network) if network != self.chain.network() => {
+                Err(crate::WalletError::NetworkMismatch {
+                    signer: network,
+                    chain: self.chain.id().to_string(),
+                    network: self.chain.network(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// [`sign_transaction`](Self::sign_transaction) for an account whose
+    /// permission needs several keys (a Tron multi-signature permission):
+    /// after the wallet's own key, each of `cosigners` signs in turn.
+    pub async fn sign_transaction_with_cosigners(
+        &self,
+        raw_tx: &RawTransaction,
+        cosigners: &[&dyn Signer<C::Scheme>],
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.sign_raw_with_cosigners(raw_tx, None, None, cosigners, false)
+            .await
+    }
+
+    async fn sign_raw(
+        &self,
+        raw_tx: &RawTransaction,
+        context: Option<&SigningContext>,
+        caps: Option<&TxCaps>,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.sign_raw_with_cosigners(raw_tx, context, caps, &[], false)
+            .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sign", skip_all, fields(cosigners = cosigners.len()))
+    )]
+    /// Sign `raw_tx`; unless `policed`, the caller having held what it pays
+    /// to the spend policies, checking it against them first.
+    async fn sign_raw_with_cosigners(
+        &self,
+        raw_tx: &RawTransaction,
+        context: Option<&SigningContext>,
+        caps: Option<&TxCaps>,
+        cosigners: &[&dyn Signer<C::Scheme>],
+        policed: bool,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        self.check_can_sign()?;
+        self.check_network()?;
+        if !policed && !self.spend_policies.is_empty() {
+            let requests = self.spend_requests(raw_tx)?;
+            self.check_spend(&requests).await?;
+        }
+
+        // 2. Prepare transaction for signing (Sync, Chain Logic)
+        let bytes_to_sign = match caps {
+            Some(caps) => self.chain.prepare_transaction_capped(raw_tx, caps)?,
+            None => self.chain.prepare_transaction(raw_tx)?,
+        };
+        let count = bytes_to_sign.len();
+        if !cosigners.is_empty() && count != 1 {
+            return Err(ChainError::Other(format!(
+                "co-signing needs a single payload, {} has {}",
+                self.chain.id(),
+                count
+            ))
+            .into());
+        }
+        if let Some(context) = context {
+            for hook in &self.hooks {
+                hook.pre_sign(context, raw_tx, &bytes_to_sign).await?;
+            }
+        }
+
+        // 3. Sign the bytes (Async, Signer/MPC), with our key and then each co-signer's
+        let format = self.chain.signature_format();
+        let policy = self.signature_policy();
+        let mode = match (format, self.chain.prehashed()) {
+            (SignatureFormat::Recoverable, _) => SignMode::Recoverable,
+            (SignatureFormat::Schnorr, _) => SignMode::Taproot,
+            (SignatureFormat::Der, true) => SignMode::Prehash,
+            (SignatureFormat::Der, false) => SignMode::Message,
+        };
+        let own: &dyn Signer<C::Scheme> = &self.signer;
+        let mut signatures = Vec::with_capacity(count + cosigners.len());
+        for (position, signer) in std::iter::once(own)
+            .chain(cosigners.iter().copied())
+            .enumerate()
+        {
+            let started = Instant::now();
+            let results = signer
+                .sign_many(&bytes_to_sign, mode, context, self.signing_concurrency)
+                .await;
+            let latency = started.elapsed();
+            if results.len() != count {
+                return Err(ChainError::Other(format!(
+                    "signer returned {} signatures for {} payloads",
+                    results.len(),
+                    count
+                ))
+                .into());
+            }
+            for (index, signature) in results.into_iter().enumerate() {
+                if let Some(metrics) = &self.metrics {
+                    metrics.signature(self.chain.id(), latency, signature.is_ok());
+                }
+                let signature = signature.map_err(|source| {
+                    let payload = match context {
+                        Some(context) => format!("payload {}/{} of {}", index + 1, count, context),
+                        None => format!("payload {}/{} on {}", index + 1, count, self.chain.id()),
+                    };
+                    crate::WalletError::SigningFailed {
+                        context: match position {
+                            0 => payload,
+                            n => format!("{} by co-signer {}", payload, n),
+                        },
+                        source,
+                    }
+                })?;
+                signatures.push(C::Scheme::canonicalize(&signature, format, &policy)?);
+            }
+        }
+
+        // 4. Finalize transaction (Sync, Chain Logic)
+        let pubkey = self.signer.public_key();
+        Ok(self
+            .chain
+            .finalize_transaction(raw_tx, &signatures, &pubkey)?)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
     use k256::ecdsa::{Signature, VerifyingKey, signature::DigestVerifier};
     use sha2::{Digest, Sha256};
 
-    use crate::wallet::chain::TRON;
+    use crate::WalletError;
+    use crate::monitor::confirmations::WaitOptions;
+    use crate::node::details::{TvmTxDetails, TxDetails};
+    use crate::node::resolver::{NameResolver, ResolverError};
+    use crate::node::{NodeError, RawTransaction, ReadProvider, Transaction, TransactionProvider};
+    use crate::test_utils::tron_transfer;
+    use crate::wallet::chain::{Network, TRON, TRON_NILE};
     use crate::wallet::signer::local::LocalSigner;
+    use crate::wallet::signer::{SignerError, SigningContext};
     use crate::wallet::{Signer, Wallet};
 
     #[tokio::test]
@@ -133,4 +1471,432 @@ mod tests {
         let addr = wallet.address().expect("address");
         assert_eq!(addr, "TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7");
     }
+
+    struct FixedResolver;
+
+    #[async_trait]
+    impl NameResolver for FixedResolver {
+        fn supports(&self, name: &str) -> bool {
+            name.ends_with(".test")
+        }
+
+        async fn resolve(&self, name: &str) -> Result<String, ResolverError> {
+            match name {
+                "alice.test" => Ok("TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT".to_string()),
+                _ => Ok("0x00000000000000000000000000000000000a11ce".to_string()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_names_and_validates_result() {
+        let signer = LocalSigner::from_bytes([1u8; 32]).expect("valid key");
+        let wallet = Wallet::new(signer, TRON).with_name_resolver(Arc::new(FixedResolver));
+
+        assert_eq!(
+            wallet.resolve_recipient("alice.test").await.unwrap(),
+            "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT"
+        );
+        // Plain addresses bypass the resolver.
+        assert!(
+            wallet
+                .resolve_recipient("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7")
+                .await
+                .is_ok()
+        );
+        // A name resolving to an address of another chain is rejected.
+        assert!(matches!(
+            wallet.resolve_recipient("bob.test").await,
+            Err(WalletError::Chain(_))
+        ));
+    }
+
+    /// Unknown on the first lookup, then mined in block 10 while the tip
+    /// advances one block per poll.
+    #[derive(Default)]
+    struct Mining {
+        polls: std::sync::Mutex<u64>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Mining {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+
+        async fn get_transactions(&self, _address: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+
+        async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>, NodeError> {
+            let mut polls = self.polls.lock().unwrap();
+            *polls += 1;
+            Ok((*polls > 1).then(|| Transaction {
+                hash: hash.to_string(),
+                from: "a".into(),
+                to: "b".into(),
+                value: "5".into(),
+                block_number: 10,
+                timestamp: 1_700_000_000,
+                status: "SUCCESS".into(),
+                raw: None,
+                details: Some(TxDetails::Tvm(TvmTxDetails {
+                    fee: 1_100,
+                    ..Default::default()
+                })),
+            }))
+        }
+
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(9 + *self.polls.lock().unwrap())
+        }
+
+        async fn get_balance(&self, _address: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Mining {
+        async fn create_transaction(
+            &self,
+            _from: &str,
+            _to: &str,
+            _amount: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            Err(NodeError::Network("offline".into()))
+        }
+
+        async fn broadcast_transaction(
+            &self,
+            _raw_tx: &RawTransaction,
+        ) -> Result<String, NodeError> {
+            Err(NodeError::Network("offline".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_for_confirmations_and_builds_a_receipt() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let wait = WaitOptions {
+            poll_interval: std::time::Duration::from_millis(1),
+            timeout: std::time::Duration::from_secs(5),
+        };
+
+        let receipt = wallet
+            .wait_for_receipt(&Mining::default(), "ab", 3, &wait)
+            .await
+            .unwrap();
+        assert_eq!(
+            (receipt.block_number, receipt.confirmations, receipt.fee),
+            (10, 3, Some(1_100))
+        );
+
+        let impatient = WaitOptions {
+            timeout: std::time::Duration::ZERO,
+            ..wait
+        };
+        assert!(matches!(
+            wallet
+                .wait_for_receipt(&Mining::default(), "ab", 3, &impatient)
+                .await,
+            Err(WalletError::ConfirmationTimeout {
+                confirmations: 0,
+                ..
+            })
+        ));
+    }
+
+    /// Local signer that remembers the summaries it was asked to sign for.
+    struct Displaying {
+        inner: LocalSigner,
+        shown: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Signer for Displaying {
+        async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+            self.inner.sign(message).await
+        }
+        async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+            self.inner.sign_prehash(prehash).await
+        }
+        async fn sign_prehash_with_context(
+            &self,
+            prehash: &[u8],
+            context: &SigningContext,
+        ) -> Result<Vec<u8>, SignerError> {
+            self.shown.lock().unwrap().push(context.to_string());
+            self.inner.sign_prehash(prehash).await
+        }
+        fn public_key(&self) -> Vec<u8> {
+            self.inner.public_key()
+        }
+    }
+
+    #[tokio::test]
+    async fn signer_receives_the_signing_context() {
+        let raw_tx = tron_transfer();
+        let signer = Displaying {
+            inner: LocalSigner::from_bytes([1u8; 32]).unwrap(),
+            shown: Default::default(),
+        };
+        let wallet = Wallet::new(signer, TRON);
+
+        wallet.sign_transaction(&raw_tx).await.unwrap();
+        let context = SigningContext {
+            summary: "Send 1.5 on tron to alice".into(),
+            ..Default::default()
+        };
+        wallet
+            .sign_transaction_with_context(&raw_tx, &context)
+            .await
+            .unwrap();
+        assert_eq!(
+            *wallet.signer.shown.lock().unwrap(),
+            ["Send 1.5 on tron to alice"]
+        );
+    }
+
+    /// Leaves 0.9 TRX of its balance after the fee, and remembers what it
+    /// was asked to sweep.
+    #[derive(Default)]
+    struct Sweepable {
+        swept: std::sync::Mutex<Vec<u64>>,
+    }
+
+    #[async_trait]
+    impl ReadProvider for Sweepable {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(vec![])
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("1000000".into())
+        }
+        async fn get_transaction(&self, _: &str) -> Result<Option<Transaction>, NodeError> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Sweepable {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            Err(NodeError::Network("offline".into()))
+        }
+        async fn broadcast_transaction(&self, tx: &RawTransaction) -> Result<String, NodeError> {
+            Ok(tx.as_json().unwrap()["txID"].as_str().unwrap().to_string())
+        }
+        async fn max_sendable(
+            &self,
+            _: &str,
+            _: &str,
+            _: &crate::node::FeeOptions,
+        ) -> Result<u64, NodeError> {
+            Ok(900_000)
+        }
+        async fn create_sweep_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            amount: u64,
+            _: &crate::node::FeeOptions,
+        ) -> Result<RawTransaction, NodeError> {
+            self.swept.lock().unwrap().push(amount);
+            Ok(tron_transfer())
+        }
+    }
+
+    #[tokio::test]
+    async fn sweeps_what_the_provider_says_is_sendable() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let provider = Sweepable::default();
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        assert!(wallet.sweep(&provider, to).await.is_ok());
+        assert_eq!(*provider.swept.lock().unwrap(), [900_000]);
+
+        assert!(matches!(
+            wallet.sweep(&crate::test_utils::OfflineProvider, to).await,
+            Err(WalletError::Node(NodeError::Api(_)))
+        ));
+    }
+
+    #[derive(Default)]
+    struct CountsCreates(std::sync::atomic::AtomicUsize);
+
+    #[async_trait]
+    impl crate::wallet::hooks::TransactionHook for CountsCreates {
+        async fn pre_create(
+            &self,
+            _: &SigningContext,
+            _: &mut crate::wallet::options::SendOptions,
+        ) -> Result<(), crate::wallet::hooks::HookError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn sweeps_run_hooks_and_resume_under_their_idempotency_key() {
+        use crate::wallet::idempotency::MemoryIdempotencyStore;
+        use crate::wallet::options::SendOptions;
+
+        let hook = Arc::new(CountsCreates::default());
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_hook(hook.clone())
+            .with_idempotency_store(Arc::new(MemoryIdempotencyStore::new()));
+        let provider = Sweepable::default();
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let options = SendOptions {
+            idempotency_key: Some("close-account-3".into()),
+            ..SendOptions::default()
+        };
+
+        let tx_hash = wallet
+            .sweep_with_options(&provider, to, &options)
+            .await
+            .unwrap();
+        let retried = wallet
+            .sweep_with_options(&provider, to, &options)
+            .await
+            .unwrap();
+        assert_eq!(retried, tx_hash);
+        assert_eq!(*provider.swept.lock().unwrap(), [900_000]);
+        assert_eq!(hook.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_amounts_below_the_chain_minimum() {
+        use crate::test_utils::OfflineProvider;
+        use crate::wallet::chain::LITECOIN;
+
+        let key = || LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let ltc = Wallet::new(key(), LITECOIN);
+        assert!(matches!(
+            ltc.send_coins(&OfflineProvider, "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv", 545)
+                .await,
+            Err(WalletError::AmountTooSmall { minimum: 546 })
+        ));
+        let tron = Wallet::new(key(), TRON);
+        assert!(matches!(
+            tron.send_coins(&OfflineProvider, "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT", 0)
+                .await,
+            Err(WalletError::AmountTooSmall { minimum: 1 })
+        ));
+        // Above the minimum the send gets as far as the (offline) provider.
+        assert!(matches!(
+            tron.send_coins(&OfflineProvider, "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT", 1)
+                .await,
+            Err(WalletError::Node(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn testnet_signers_refuse_mainnet_transactions() {
+        let raw_tx = tron_transfer();
+        let signer = || {
+            LocalSigner::from_bytes([1u8; 32])
+                .unwrap()
+                .with_network(Network::Testnet)
+        };
+
+        let mainnet = Wallet::new(signer(), TRON);
+        assert!(matches!(
+            mainnet.sign_transaction(&raw_tx).await,
+            Err(WalletError::NetworkMismatch {
+                signer: Network::Testnet,
+                network: Network::Mainnet,
+                ..
+            })
+        ));
+        let nile = Wallet::new(signer(), TRON_NILE);
+        assert!(nile.sign_transaction(&raw_tx).await.is_ok());
+    }
+
+    /// Takes longer over the payloads asked for first, and counts how many
+    /// signatures it was asked for at once.
+    struct Slow {
+        key: LocalSigner,
+        calls: std::sync::atomic::AtomicU64,
+        in_flight: std::sync::atomic::AtomicUsize,
+        most: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Signer for Slow {
+        async fn sign(&self, _: &[u8]) -> Result<Vec<u8>, SignerError> {
+            Err(SignerError::Unsupported("messages"))
+        }
+        async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+            use std::sync::atomic::Ordering;
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(now, Ordering::SeqCst);
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let delay = 10 * 3u64.saturating_sub(call);
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.key.sign_prehash(prehash).await
+        }
+        fn public_key(&self) -> Vec<u8> {
+            self.key.public_key()
+        }
+    }
+
+    #[tokio::test]
+    async fn signs_inputs_concurrently_in_order() {
+        use crate::wallet::chain::LITECOIN;
+        use crate::wallet::chain::utxo::{SighashType, UtxoInput, UtxoTxBuilder};
+
+        let sequential = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), LITECOIN);
+        let from = sequential.address().unwrap();
+        let mut builder = UtxoTxBuilder::new(&LITECOIN);
+        for index in 0..3 {
+            builder = builder.input(UtxoInput {
+                prev_hash: [index as u8; 32],
+                output_index: index,
+                value: 100_000,
+                script_pubkey: LITECOIN.script_pubkey(&from).unwrap(),
+                sequence: 0xffff_ffff,
+                sighash: SighashType::All,
+            });
+        }
+        let raw_tx = builder
+            .pay("LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv", 250_000)
+            .unwrap()
+            .build()
+            .unwrap()
+            .into_raw();
+
+        let concurrent = Wallet::new(
+            Slow {
+                key: LocalSigner::from_bytes([1u8; 32]).unwrap(),
+                calls: Default::default(),
+                in_flight: Default::default(),
+                most: Default::default(),
+            },
+            LITECOIN,
+        )
+        .with_signing_concurrency(3);
+        assert_eq!(
+            concurrent.sign_transaction(&raw_tx).await.unwrap(),
+            sequential.sign_transaction(&raw_tx).await.unwrap()
+        );
+        assert_eq!(
+            concurrent
+                .signer
+                .most
+                .load(std::sync::atomic::Ordering::SeqCst),
+            3
+        );
+    }
 }
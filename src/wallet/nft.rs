@@ -0,0 +1,111 @@
+//! TRC-721 NFTs: listing what the wallet holds and sending a token on.
+//!
+//! Transfers are `transferFrom` calls run through the same
+//! create -> prepare -> sign -> finalize -> broadcast pipeline as a TRC-20
+//! transfer, after checking the wallet owns the token and can pay the
+//! energy the call burns.
+
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::node::network::tron::TronProvider;
+use crate::node::nft::Nft;
+use crate::wallet::chain::{Chain, TvmChain};
+use crate::wallet::options::SendOptions;
+use crate::wallet::{BuiltSend, Signer, Wallet};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NftError {
+    #[error("token {token_id} of {contract} belongs to {owner}, not this wallet")]
+    NotOwner {
+        contract: String,
+        token_id: String,
+        owner: String,
+    },
+}
+
+impl<T: Signer> Wallet<TvmChain, T> {
+    /// TRC-721 tokens the wallet holds; see [`TronProvider::trc721_tokens`]
+    /// for how they are found. Metadata is one
+    /// [`nft_metadata`](TronProvider::nft_metadata) call per token away.
+    pub async fn nfts(&self, provider: &TronProvider) -> Result<Vec<Nft>, WalletError> {
+        Ok(provider.trc721_tokens(&self.address()?).await?)
+    }
+
+    /// Send token `token_id` (decimal) of the TRC-721 `contract` to `to`.
+    pub async fn send_nft(
+        &self,
+        provider: &TronProvider,
+        contract: &str,
+        token_id: &str,
+        to: &str,
+    ) -> Result<String, WalletError> {
+        self.send_nft_with_options(provider, contract, token_id, to, &SendOptions::default())
+            .await
+    }
+
+    /// Same as [`send_nft`](Self::send_nft) with extra per-send options. Like
+    /// a coin send, the transfer is audited with `options.metadata`, screened
+    /// and shown to hooks; `options.fee_limit` caps the energy burned.
+    pub async fn send_nft_with_options(
+        &self,
+        provider: &TronProvider,
+        contract: &str,
+        token_id: &str,
+        to: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.transfer_trc721(provider, contract, token_id, to, options))
+            .await
+    }
+
+    async fn transfer_trc721(
+        &self,
+        provider: &TronProvider,
+        contract: &str,
+        token_id: &str,
+        to: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        // One token of the collection, for policies capping it.
+        let send = BuiltSend {
+            to: to.clone(),
+            amount: 1,
+            token: Some(contract.to_string()),
+            summary: format!(
+                "Send {} #{} on {} to {}",
+                contract,
+                token_id,
+                self.chain.id(),
+                to
+            ),
+        };
+        self.send_built(provider, send, options, |options| async move {
+            let owner = provider.trc721_owner_of(contract, token_id).await?;
+            if owner != from {
+                return Err(NftError::NotOwner {
+                    contract: contract.to_string(),
+                    token_id: token_id.to_string(),
+                    owner,
+                }
+                .into());
+            }
+
+            let energy_needed = provider
+                .estimate_trc721_transfer_energy(&from, &to, contract, token_id)
+                .await?;
+            let fee_limit = self
+                .affordable_fee_limit(provider, &from, energy_needed, options.fee_limit)
+                .await?;
+
+            Ok(provider
+                .create_trc721_transfer(&from, &to, contract, token_id, fee_limit)
+                .await?)
+        })
+        .await
+    }
+}
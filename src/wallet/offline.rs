@@ -0,0 +1,266 @@
+//! A send split into steps that can run on different machines: an online
+//! one builds the transaction, an air-gapped one signs it, and the online
+//! one broadcasts it later. Between machines the steps' outputs travel as
+//! [`persist`](crate::persist) records.
+
+use serde::{Deserialize, Serialize};
+
+use crate::WalletError;
+use crate::node::{Provider, RawTransaction};
+use crate::persist::Persist;
+use crate::wallet::chain::{Chain, ChainError};
+use crate::wallet::options::SendOptions;
+use crate::wallet::{Signer, Wallet};
+
+/// A transfer built by a provider, waiting for its signature. Carries what
+/// the transaction is meant to do, which the signing wallet shows to its
+/// signer and, under partial trust, checks the transaction against.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the chain's base unit.
+    pub amount: u64,
+    pub fee_limit: Option<u64>,
+    /// Line the signer shows instead of the generated one.
+    pub summary: Option<String>,
+    pub raw: RawTransaction,
+}
+
+impl Persist for UnsignedTransaction {
+    const KIND: &'static str = "unsigned_tx";
+    const VERSION: u16 = 1;
+}
+
+/// An [`UnsignedTransaction`] after signing, ready to broadcast.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub raw: RawTransaction,
+}
+
+impl Persist for SignedTransaction {
+    const KIND: &'static str = "signed_tx";
+    const VERSION: u16 = 1;
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// First step, online: check the send against the wallet's screening
+    /// and approval policies and have `provider` build it.
+    ///
+    /// A watch-only wallet of the offline key is enough here.
+    pub async fn create_unsigned(
+        &self,
+        provider: &dyn Provider,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+    ) -> Result<UnsignedTransaction, WalletError> {
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        self.check_amount(amount)?;
+        self.check_screening(&from, &to, amount, None, options)
+            .await?;
+        self.check_approval(&from, &to, amount, options).await?;
+        // The transaction is broadcast elsewhere, so nothing here can tell
+        // the payer when to release; it keeps the sponsorship to reclaim.
+        self.sponsor_send(&from, amount, options).await?;
+        let raw = self
+            .build_send(provider, &from, &to, amount, options, false)
+            .await?;
+        Ok(UnsignedTransaction {
+            chain: self.chain.id().to_string(),
+            from,
+            to,
+            amount,
+            fee_limit: options.fee_limit,
+            summary: options.summary.clone(),
+            raw,
+        })
+    }
+
+    /// Second step, offline: sign a transaction made by
+    /// [`create_unsigned`](Self::create_unsigned) for this wallet.
+    pub async fn sign_unsigned(
+        &self,
+        tx: &UnsignedTransaction,
+    ) -> Result<SignedTransaction, WalletError> {
+        self.check_chain(&tx.chain)?;
+        let from = self.address()?;
+        if tx.from != from {
+            return Err(ChainError::Other(format!(
+                "transaction spends from {}, this wallet is {}",
+                tx.from, from
+            ))
+            .into());
+        }
+        let options = SendOptions {
+            fee_limit: tx.fee_limit,
+            summary: tx.summary.clone(),
+            ..SendOptions::default()
+        };
+        self.check_spend(&[self.spend_request(&tx.from, &tx.to, tx.amount, &options)])
+            .await?;
+        let raw = self
+            .sign_send(&tx.raw, &tx.from, &tx.to, tx.amount, &options, &[])
+            .await?;
+        Ok(SignedTransaction {
+            chain: tx.chain.clone(),
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            amount: tx.amount,
+            raw,
+        })
+    }
+
+    /// Last step, online: broadcast a transaction signed by
+    /// [`sign_unsigned`](Self::sign_unsigned). Returns its hash.
+    pub async fn broadcast(
+        &self,
+        provider: &dyn Provider,
+        tx: &SignedTransaction,
+    ) -> Result<String, WalletError> {
+        self.check_chain(&tx.chain)?;
+        let tx_hash = provider.broadcast_transaction(&tx.raw).await?;
+        self.watch(&tx_hash, tx.raw.clone());
+        Ok(tx_hash)
+    }
+
+    fn check_chain(&self, chain: &str) -> Result<(), ChainError> {
+        if chain != self.chain.id() {
+            return Err(ChainError::Other(format!(
+                "transaction is for {}, this wallet is on {}",
+                chain,
+                self.chain.id()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, ReadProvider, Transaction, TransactionProvider};
+    use crate::persist;
+    use crate::wallet::chain::TRON;
+    use crate::wallet::chain::tvm::proto::{
+        Contract, ContractType, TransactionRaw, TransferContract,
+    };
+    use crate::wallet::key_source::xpub::WatchOnlySigner;
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+    /// Builds a bare TRX transfer and accepts any broadcast.
+    struct Node;
+
+    #[async_trait]
+    impl ReadProvider for Node {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(Vec::new())
+        }
+        async fn get_transaction(&self, _: &str) -> Result<Option<Transaction>, NodeError> {
+            Ok(None)
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("0".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Node {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            amount: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            let raw_data = TransactionRaw {
+                contract: vec![Contract::pack(
+                    ContractType::TransferContract,
+                    &TransferContract {
+                        amount: amount as i64,
+                        ..Default::default()
+                    },
+                )],
+                ..Default::default()
+            };
+            Ok(RawTransaction::Json(serde_json::json!({
+                "txID": hex::encode(raw_data.txid()),
+                "raw_data_hex": hex::encode(prost::Message::encode_to_vec(&raw_data)),
+            })))
+        }
+        async fn broadcast_transaction(&self, tx: &RawTransaction) -> Result<String, NodeError> {
+            assert_eq!(
+                tx.as_json().unwrap()["signature"].as_array().unwrap().len(),
+                1
+            );
+            Ok(tx.as_json().unwrap()["txID"].as_str().unwrap().to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn air_gapped_send_round_trips_through_records() {
+        let key = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let public_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key.public_key()).unwrap();
+        let online = Wallet::new(WatchOnlySigner::new(public_key), TRON);
+        let offline = Wallet::new(key, TRON);
+
+        let unsigned = online
+            .create_unsigned(&Node, TO, 1_500_000, &SendOptions::default())
+            .await
+            .unwrap();
+        let carried: UnsignedTransaction =
+            persist::from_bytes(&persist::to_bytes(&unsigned).unwrap()).unwrap();
+        assert_eq!(carried, unsigned);
+
+        let signed = offline.sign_unsigned(&carried).await.unwrap();
+        let carried: SignedTransaction =
+            persist::from_bytes(&persist::to_bytes(&signed).unwrap()).unwrap();
+        let tx_hash = online.broadcast(&Node, &carried).await.unwrap();
+        assert_eq!(tx_hash, signed.raw.as_json().unwrap()["txID"]);
+
+        // Only the wallet the transaction spends from signs it.
+        let other = Wallet::new(LocalSigner::from_bytes([2u8; 32]).unwrap(), TRON);
+        assert!(other.sign_unsigned(&unsigned).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn watch_only_wallets_build_but_never_sign() {
+        let key = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let public_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key.public_key()).unwrap();
+        let signer: Box<dyn Signer> = Box::new(WatchOnlySigner::new(public_key));
+        let wallet = Wallet::new(signer, TRON);
+        assert!(wallet.is_watch_only());
+        assert_eq!(
+            wallet.address().unwrap(),
+            Wallet::new(key, TRON).address().unwrap()
+        );
+
+        let unsigned = wallet
+            .create_unsigned(&Node, TO, 1_500_000, &SendOptions::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            wallet.sign_unsigned(&unsigned).await,
+            Err(WalletError::WatchOnly)
+        ));
+        assert!(matches!(
+            wallet.send_coins(&Node, TO, 1_500_000).await,
+            Err(WalletError::WatchOnly)
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::correlation::CorrelationId;
+use crate::node::{FeeOptions, FeePriority};
+use crate::time::Duration;
+use crate::wallet::chain::CoinSelection;
+use crate::wallet::fee_payer::FeePayer;
+
+/// Identity of one side of a transfer, as required by travel-rule regimes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TravelRuleParty {
+    pub name: String,
+    /// Account identifier at the institution (or the on-chain address).
+    pub account: Option<String>,
+    /// Name or LEI of the VASP servicing this party.
+    pub vasp: Option<String>,
+    /// ISO 3166 country code.
+    pub jurisdiction: Option<String>,
+}
+
+/// Compliance metadata threaded through a send.
+/// It is recorded off-chain only and never embedded in the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TravelRuleMetadata {
+    pub originator: TravelRuleParty,
+    pub beneficiary: TravelRuleParty,
+    /// Integrator-side reference, e.g. a withdrawal request id.
+    pub reference: Option<String>,
+}
+
+/// Optional knobs for [`Wallet::send_coins_with_options`](crate::wallet::Wallet::send_coins_with_options).
+#[derive(Clone, Default)]
+pub struct SendOptions {
+    pub metadata: Option<TravelRuleMetadata>,
+    /// Separate account that covers the network fees of this send.
+    pub fee_payer: Option<Arc<dyn FeePayer>>,
+    /// Id used to trace this send across systems; generated when unset.
+    pub correlation_id: Option<CorrelationId>,
+    /// Fee tier used when `fee_rate` is unset.
+    pub fee_priority: FeePriority,
+    /// Explicit fee rate, see [`FeeOptions::fee_rate`]; on EVM chains the
+    /// EIP-1559 `maxFeePerGas`.
+    pub fee_rate: Option<u64>,
+    /// EIP-1559 tip in wei per gas, see [`FeeOptions::priority_fee`];
+    /// suggested from recent blocks when unset.
+    pub priority_fee: Option<u64>,
+    /// Most the network may charge, in base units: the `fee_limit` of Tron contract
+    /// calls (estimated when unset), a cap on the fee elsewhere.
+    pub fee_limit: Option<u64>,
+    /// Send with this nonce rather than the next free one, e.g. to replace
+    /// a stuck EVM transaction with a better-paid one.
+    pub nonce: Option<u64>,
+    /// Ask the provider whether the sender can pay for the send (Tron
+    /// bandwidth, or the TRX burned for it) before building it, for a
+    /// descriptive [`NodeError::InsufficientResources`](crate::node::NodeError::InsufficientResources)
+    /// instead of the node's rejection at broadcast.
+    pub check_resources: bool,
+    /// Account permission the send acts under, for chains with account-level
+    /// permissions (Tron: 0 is the owner, 2 and up the active permissions);
+    /// the owner's when unset.
+    pub permission_id: Option<i32>,
+    /// Let the send be replaced by a higher-fee version later, on chains
+    /// where transactions must opt in (BIP-125 on UTXO chains); see
+    /// [`Wallet::bump_fee`](crate::wallet::Wallet::bump_fee).
+    pub replaceable: bool,
+    /// Note the transfer carries, e.g. the deposit memo an exchange asks
+    /// for: Tron's `raw_data.data`, an `OP_RETURN` output on UTXO chains.
+    pub memo: Option<String>,
+    /// How long the transaction stays valid after it is built, on chains
+    /// whose transactions expire (Tron: 60 seconds unless extended, at most
+    /// 24 hours), for signers slower than that.
+    pub expiration: Option<Duration>,
+    /// How to pick the inputs of a UTXO send, or which to spend; the
+    /// provider's own choice when unset.
+    pub coin_selection: Option<CoinSelection>,
+    /// Key under which the wallet's idempotency store records this send;
+    /// sending again with it returns the first send's hash instead of
+    /// paying twice. See [`Wallet::with_idempotency_store`](crate::wallet::Wallet::with_idempotency_store).
+    pub idempotency_key: Option<String>,
+    /// Let a token approval grant [`Allowance::Unlimited`](crate::node::Allowance::Unlimited),
+    /// which is refused otherwise.
+    pub allow_unlimited_approval: bool,
+    /// One-time code for the wallet's approval policy, e.g. from an authenticator app.
+    pub approval_code: Option<String>,
+    /// Line the signer shows for this send, replacing the generated
+    /// `Send <amount> on <chain> to <address>`.
+    pub summary: Option<String>,
+}
+
+impl SendOptions {
+    /// The fee controls handed to the provider.
+    pub fn fees(&self) -> FeeOptions {
+        FeeOptions {
+            priority: self.fee_priority,
+            fee_rate: self.fee_rate,
+            fee_limit: self.fee_limit,
+            priority_fee: self.priority_fee,
+            nonce: self.nonce,
+        }
+    }
+}
+
+impl fmt::Debug for SendOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendOptions")
+            .field("metadata", &self.metadata)
+            .field("fee_payer", &self.fee_payer.is_some())
+            .field("correlation_id", &self.correlation_id)
+            .field("fee_priority", &self.fee_priority)
+            .field("fee_rate", &self.fee_rate)
+            .field("priority_fee", &self.priority_fee)
+            .field("fee_limit", &self.fee_limit)
+            .field("nonce", &self.nonce)
+            .field("check_resources", &self.check_resources)
+            .field("permission_id", &self.permission_id)
+            .field("replaceable", &self.replaceable)
+            .field("memo", &self.memo)
+            .field("expiration", &self.expiration)
+            .field("coin_selection", &self.coin_selection)
+            .field("idempotency_key", &self.idempotency_key)
+            .field("allow_unlimited_approval", &self.allow_unlimited_approval)
+            .field("approval_code", &self.approval_code.is_some())
+            .field("summary", &self.summary)
+            .finish()
+    }
+}
@@ -0,0 +1,301 @@
+use crate::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::wallet::chain::Chain;
+use crate::wallet::message::verify_message;
+use crate::wallet::scheme::Secp256k1;
+use crate::wallet::{Signer, Wallet};
+
+const PROOF_DOMAIN: &str = "flow-wallet ownership proof";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OwnershipError {
+    #[error("challenge expired")]
+    Expired,
+    #[error("challenge was not issued by this verifier or was already used")]
+    Replayed,
+    #[error("proof is for another address")]
+    AddressMismatch,
+    #[error("invalid proof: {0}")]
+    InvalidProof(String),
+    #[error("signature verification failed")]
+    BadSignature,
+}
+
+/// Challenge issued by a verifier that the address owner must sign.
+/// The nonce and expiry make each proof single-use and short-lived.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipChallenge {
+    pub nonce: String,
+    pub expires_at: u64,
+}
+
+impl OwnershipChallenge {
+    /// Create a challenge with a random 128-bit nonce valid for `ttl_secs`.
+    pub fn new(ttl_secs: u64) -> Self {
+        let mut nonce = [0u8; 16];
+        rand::rng().fill_bytes(&mut nonce);
+        Self {
+            nonce: hex::encode(nonce),
+            expires_at: unix_now().saturating_add(ttl_secs),
+        }
+    }
+
+    /// The text the owner signs for this challenge and address, in the
+    /// chain's signed-message format (see [`Wallet::sign_message`]).
+    pub fn message(&self, address: &str) -> Vec<u8> {
+        format!(
+            "{}\naddress:{}\nnonce:{}\nexpires:{}",
+            PROOF_DOMAIN, address, self.nonce, self.expires_at
+        )
+        .into_bytes()
+    }
+}
+
+/// Signed answer to an [`OwnershipChallenge`]. Any wallet that signs
+/// messages for the chain (TronLink, MetaMask, Bitcoin Core's
+/// `signmessage`) can produce one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipProof {
+    pub address: String,
+    /// Signature over [`OwnershipChallenge::message`], encoded as the chain's
+    /// message signatures are; the signing key is recovered from it.
+    pub signature: String,
+}
+
+impl<C: Chain<Scheme = Secp256k1>, T: Signer> Wallet<C, T> {
+    /// Sign a verifier's challenge to prove control of this wallet's address.
+    pub async fn prove_ownership(
+        &self,
+        challenge: &OwnershipChallenge,
+    ) -> Result<OwnershipProof, crate::WalletError> {
+        let address = self.address()?;
+        let signature = self.sign_message(&challenge.message(&address)).await?;
+        Ok(OwnershipProof { address, signature })
+    }
+}
+
+/// Stateless check of a proof against a challenge.
+/// Does not track nonces; use [`OwnershipVerifier`] for replay protection.
+pub fn verify_ownership(
+    chain: &impl Chain<Scheme = Secp256k1>,
+    address: &str,
+    challenge: &OwnershipChallenge,
+    proof: &OwnershipProof,
+) -> Result<(), OwnershipError> {
+    if unix_now() > challenge.expires_at {
+        return Err(OwnershipError::Expired);
+    }
+    if proof.address != address {
+        return Err(OwnershipError::AddressMismatch);
+    }
+
+    match verify_message(
+        chain,
+        address,
+        &challenge.message(address),
+        &proof.signature,
+    ) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(OwnershipError::BadSignature),
+        Err(e) => Err(OwnershipError::InvalidProof(e.to_string())),
+    }
+}
+
+/// Verifier that issues challenges and accepts each one once, before it
+/// expires. Only outstanding challenges are kept: a nonce is forgotten
+/// once its proof is accepted or it expires.
+#[derive(Default)]
+pub struct OwnershipVerifier {
+    /// Expiry of each issued, unanswered challenge, by nonce.
+    outstanding: Mutex<HashMap<String, u64>>,
+}
+
+impl OwnershipVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a challenge valid for `ttl_secs`.
+    pub fn issue(&self, ttl_secs: u64) -> OwnershipChallenge {
+        let challenge = OwnershipChallenge::new(ttl_secs);
+        let mut outstanding = self.outstanding.lock().unwrap();
+        evict_expired(&mut outstanding);
+        outstanding.insert(challenge.nonce.clone(), challenge.expires_at);
+        challenge
+    }
+
+    /// Number of issued challenges still awaiting a proof.
+    pub fn outstanding(&self) -> usize {
+        let mut outstanding = self.outstanding.lock().unwrap();
+        evict_expired(&mut outstanding);
+        outstanding.len()
+    }
+
+    /// Verify a proof against a challenge this verifier issued, and retire
+    /// the challenge on success.
+    pub fn verify(
+        &self,
+        chain: &impl Chain<Scheme = Secp256k1>,
+        address: &str,
+        challenge: &OwnershipChallenge,
+        proof: &OwnershipProof,
+    ) -> Result<(), OwnershipError> {
+        let mut outstanding = self.outstanding.lock().unwrap();
+        evict_expired(&mut outstanding);
+        match outstanding.get(&challenge.nonce) {
+            Some(expires_at) if *expires_at == challenge.expires_at => {}
+            _ => return Err(OwnershipError::Replayed),
+        }
+        verify_ownership(chain, address, challenge, proof)?;
+        outstanding.remove(&challenge.nonce);
+        Ok(())
+    }
+}
+
+fn evict_expired(outstanding: &mut HashMap<String, u64>) {
+    let now = unix_now();
+    outstanding.retain(|_, expires_at| *expires_at >= now);
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::chain::{ETHEREUM, LITECOIN, TRON};
+    use crate::wallet::signer::local::LocalSigner;
+    use sha3::{Digest, Keccak256};
+
+    #[tokio::test]
+    async fn proof_roundtrip_and_replay() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let address = wallet.address().unwrap();
+        let verifier = OwnershipVerifier::new();
+        let challenge = verifier.issue(300);
+        let proof = wallet.prove_ownership(&challenge).await.expect("prove");
+        assert_eq!(verifier.outstanding(), 1);
+
+        verifier
+            .verify(&TRON, &address, &challenge, &proof)
+            .expect("valid proof");
+        assert_eq!(verifier.outstanding(), 0);
+        assert_eq!(
+            verifier.verify(&TRON, &address, &challenge, &proof),
+            Err(OwnershipError::Replayed)
+        );
+
+        // Challenges it did not issue, or whose expiry was changed, are refused.
+        let foreign = OwnershipChallenge::new(300);
+        let proof = wallet.prove_ownership(&foreign).await.unwrap();
+        assert_eq!(
+            verifier.verify(&TRON, &address, &foreign, &proof),
+            Err(OwnershipError::Replayed)
+        );
+        let mut extended = verifier.issue(300);
+        extended.expires_at += 3_600;
+        let proof = wallet.prove_ownership(&extended).await.unwrap();
+        assert_eq!(
+            verifier.verify(&TRON, &address, &extended, &proof),
+            Err(OwnershipError::Replayed)
+        );
+    }
+
+    #[test]
+    fn forgets_expired_challenges() {
+        let verifier = OwnershipVerifier::new();
+        verifier.outstanding.lock().unwrap().insert("00".into(), 0);
+        verifier.issue(300);
+        assert_eq!(verifier.outstanding(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_and_foreign_chain() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let address = wallet.address().unwrap();
+
+        let expired = OwnershipChallenge {
+            nonce: "00".into(),
+            expires_at: 0,
+        };
+        let proof = wallet.prove_ownership(&expired).await.unwrap();
+        assert_eq!(
+            verify_ownership(&TRON, &address, &expired, &proof),
+            Err(OwnershipError::Expired)
+        );
+
+        // A Litecoin verifier expects base64 signmessage signatures, an
+        // Ethereum one recovers an address other than the TRON one.
+        let challenge = OwnershipChallenge::new(300);
+        let proof = wallet.prove_ownership(&challenge).await.unwrap();
+        assert!(matches!(
+            verify_ownership(&LITECOIN, &address, &challenge, &proof),
+            Err(OwnershipError::InvalidProof(_))
+        ));
+        assert_eq!(
+            verify_ownership(&ETHEREUM, &address, &challenge, &proof),
+            Err(OwnershipError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn accepts_proofs_from_standard_message_signers() {
+        // personal_sign, as MetaMask does it: keccak256 over the EIP-191
+        // prefix and the message, then r || s || 27 + recovery id.
+        let key = k256::ecdsa::SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+        let wallet = Wallet::new(LocalSigner::from_bytes([7u8; 32]).unwrap(), ETHEREUM);
+        let address = wallet.address().unwrap();
+        let challenge = OwnershipChallenge {
+            nonce: "5f1c".into(),
+            expires_at: u64::MAX,
+        };
+        let message = challenge.message(&address);
+        let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        prefixed.extend_from_slice(&message);
+        let digest = Keccak256::digest(&prefixed);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&digest).unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        let proof = OwnershipProof {
+            address: address.clone(),
+            signature: format!("0x{}", hex::encode(bytes)),
+        };
+
+        verify_ownership(&ETHEREUM, &address, &challenge, &proof).expect("personal_sign proof");
+        assert_eq!(
+            verify_ownership(
+                &ETHEREUM,
+                &address,
+                &OwnershipChallenge {
+                    nonce: "5f1d".into(),
+                    ..challenge
+                },
+                &proof
+            ),
+            Err(OwnershipError::BadSignature)
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_only_wallets_report_why_they_cannot_prove() {
+        let key = LocalSigner::from_bytes([1u8; 32]).unwrap().public_key();
+        let public_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key).unwrap();
+        let wallet = Wallet::watch_only(public_key, TRON);
+        let error = wallet
+            .prove_ownership(&OwnershipChallenge::new(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, crate::WalletError::WatchOnly));
+        assert!(error.to_string().contains("watch-only"));
+    }
+}
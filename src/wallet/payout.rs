@@ -0,0 +1,853 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::node::utils::format_units;
+use crate::node::{Provider, RawTransaction};
+use crate::wallet::audit::AuditKind;
+use crate::wallet::chain::Chain;
+use crate::wallet::options::SendOptions;
+use crate::wallet::signer::SigningContext;
+use crate::wallet::{Signer, Wallet};
+
+/// Estimated virtual size of a batch transaction before its recipients'
+/// outputs: version and lock time, one P2PKH input and a change output.
+/// Coin selection may add inputs, so size limits are best kept with room
+/// to spare.
+pub const BATCH_OVERHEAD_VSIZE: u64 = 10 + 148 + 43;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PayoutError {
+    #[error("line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error("{}", describe_invalid(.0))]
+    Invalid(Vec<PayoutProblem>),
+}
+
+fn describe_invalid(problems: &[PayoutProblem]) -> String {
+    match problems.first() {
+        Some(first) => format!("{} problem(s), first {}", problems.len(), first),
+        None => "invalid rows".to_string(),
+    }
+}
+
+/// Something [`PayoutBatch::validate`] found wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutProblem {
+    /// Index into [`PayoutBatch::rows`] of the offending row; `None` when
+    /// the problem is with the batch as a whole, such as its total.
+    pub row: Option<usize>,
+    /// CSV line of that row, if it was parsed from one.
+    pub line: Option<usize>,
+    pub reason: String,
+}
+
+impl fmt::Display for PayoutProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.row, self.line) {
+            (_, Some(line)) => write!(f, "at line {}: {}", line, self.reason),
+            (Some(row), None) => write!(f, "at row {}: {}", row, self.reason),
+            (None, None) => write!(f, "in the batch: {}", self.reason),
+        }
+    }
+}
+
+/// One recipient of a payout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutRow {
+    pub address: String,
+    /// Amount in the chain's base unit (Sun, litoshi, ...).
+    pub amount: u64,
+    pub memo: Option<String>,
+    /// 1-based line of the CSV input the row was parsed from; `None` for
+    /// rows [`add`](PayoutBatch::add)ed directly.
+    pub line: Option<usize>,
+}
+
+/// Constraints applied when validating and splitting a batch.
+#[derive(Debug, Clone, Default)]
+pub struct PayoutLimits {
+    /// Largest amount moved by a single transaction; bigger rows are split.
+    pub max_amount_per_tx: Option<u64>,
+    /// Upper bound on the sum of all rows.
+    pub max_total: Option<u64>,
+    /// Most recipients one transaction pays, on chains whose transactions
+    /// can pay several (see [`Chain::output_vsize`]). One, the default,
+    /// sends every transfer on its own.
+    pub max_recipients_per_tx: Option<usize>,
+    /// Largest estimated virtual size of a transaction paying several
+    /// recipients; see [`BATCH_OVERHEAD_VSIZE`].
+    pub max_tx_vsize: Option<u64>,
+    /// Most fee a transaction may pay. Passed on as its `fee_limit`, and with
+    /// an explicit [`SendOptions::fee_rate`] it also bounds how many
+    /// recipients fit in one.
+    pub max_fee_per_tx: Option<u64>,
+}
+
+/// A single transfer planned for a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedTransfer {
+    /// Index into [`PayoutBatch::rows`].
+    pub row: usize,
+    pub address: String,
+    pub amount: u64,
+}
+
+/// One transaction of a plan, with one output per transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedTransaction {
+    pub transfers: Vec<PlannedTransfer>,
+}
+
+impl PlannedTransaction {
+    pub fn total(&self) -> u64 {
+        self.transfers.iter().map(|t| t.amount).sum()
+    }
+}
+
+/// Result of paying out one row.
+#[derive(Debug)]
+pub struct PayoutOutcome {
+    /// Index into [`PayoutBatch::rows`].
+    pub row: usize,
+    /// CSV line of the row, if it was parsed from one.
+    pub line: Option<usize>,
+    pub address: String,
+    pub amount: u64,
+    pub memo: Option<String>,
+    /// Hashes of the transactions broadcast for this row, in order. Rows
+    /// paid in the same transaction share its hash.
+    pub tx_hashes: Vec<String>,
+    /// Set if any transfer for this row failed; later transfers were skipped.
+    pub error: Option<String>,
+}
+
+impl PayoutOutcome {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Per-recipient outcomes of [`PayoutBatch::execute`].
+#[derive(Debug, Default)]
+pub struct PayoutReport {
+    pub outcomes: Vec<PayoutOutcome>,
+}
+
+impl PayoutReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &PayoutOutcome> {
+        self.outcomes.iter().filter(|o| o.is_success())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &PayoutOutcome> {
+        self.outcomes.iter().filter(|o| !o.is_success())
+    }
+}
+
+/// Builder for paying many recipients from one wallet (airdrops, payroll).
+#[derive(Debug, Clone, Default)]
+pub struct PayoutBatch {
+    rows: Vec<PayoutRow>,
+    limits: PayoutLimits,
+    options: SendOptions,
+}
+
+impl PayoutBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `address,amount[,memo]` lines.
+    /// Blank lines, `#` comments and a leading header row are skipped.
+    pub fn from_csv(input: &str) -> Result<Self, PayoutError> {
+        let mut batch = Self::new();
+        for (idx, line) in input.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(3, ',').map(str::trim);
+            let address = fields.next().unwrap_or_default();
+            let amount = fields.next().ok_or_else(|| PayoutError::Parse {
+                line: line_no,
+                reason: "missing amount column".into(),
+            })?;
+            let memo = fields.next().filter(|m| !m.is_empty());
+
+            let amount = match amount.parse::<u64>() {
+                Ok(amount) => amount,
+                Err(_) if batch.rows.is_empty() && address.eq_ignore_ascii_case("address") => {
+                    continue;
+                }
+                Err(e) => {
+                    return Err(PayoutError::Parse {
+                        line: line_no,
+                        reason: format!("invalid amount {:?}: {}", amount, e),
+                    });
+                }
+            };
+            batch.add(address, amount, memo);
+            batch.rows.last_mut().expect("just added").line = Some(line_no);
+        }
+        Ok(batch)
+    }
+
+    pub fn add(&mut self, address: &str, amount: u64, memo: Option<&str>) -> &mut Self {
+        self.rows.push(PayoutRow {
+            address: address.to_string(),
+            amount,
+            memo: memo.map(str::to_string),
+            line: None,
+        });
+        self
+    }
+
+    pub fn with_limits(mut self, limits: PayoutLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Options every transaction is sent with, e.g. its fee rate or
+    /// metadata. A row's memo replaces `memo`; `idempotency_key` is not
+    /// used, since one key cannot stand for several transactions.
+    pub fn with_options(mut self, options: SendOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn rows(&self) -> &[PayoutRow] {
+        &self.rows
+    }
+
+    pub fn total(&self) -> u128 {
+        self.rows.iter().map(|r| r.amount as u128).sum()
+    }
+
+    /// Validate every row up front and report all problems at once: bad
+    /// addresses, and amounts or split remainders below the chain's
+    /// [`min_transfer`](Chain::min_transfer).
+    pub fn validate(&self, chain: &impl Chain) -> Result<(), PayoutError> {
+        let minimum = chain.min_transfer();
+        let chunk = self.limits.max_amount_per_tx.filter(|max| *max > 0);
+        let mut problems = Vec::new();
+        let batch_problem = |reason: String| PayoutProblem {
+            row: None,
+            line: None,
+            reason,
+        };
+        if let Some(chunk) = chunk
+            && chunk < minimum
+        {
+            problems.push(batch_problem(format!(
+                "max_amount_per_tx {} is below the minimum transfer of {}",
+                chunk, minimum
+            )));
+        }
+        for (idx, row) in self.rows.iter().enumerate() {
+            let mut problem = |reason: String| {
+                problems.push(PayoutProblem {
+                    row: Some(idx),
+                    line: row.line,
+                    reason,
+                })
+            };
+            if row.amount == 0 {
+                problem("amount must be positive".to_string());
+            } else if row.amount < minimum {
+                problem(format!(
+                    "amount {} is below the minimum transfer of {}",
+                    row.amount, minimum
+                ));
+            } else if let Some(chunk) = chunk
+                && row.amount % chunk != 0
+                && row.amount % chunk < minimum
+            {
+                problem(format!(
+                    "splitting at {} per transaction leaves {}, below the minimum transfer of {}",
+                    chunk,
+                    row.amount % chunk,
+                    minimum
+                ));
+            }
+            if let Err(e) = chain.validate_address(&row.address) {
+                problem(e.to_string());
+            }
+        }
+        if let Some(max_total) = self.limits.max_total
+            && self.total() > max_total as u128
+        {
+            problems.push(batch_problem(format!(
+                "batch total {} exceeds limit {}",
+                self.total(),
+                max_total
+            )));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PayoutError::Invalid(problems))
+        }
+    }
+
+    /// Split rows into transfers honouring `max_amount_per_tx`, then group
+    /// transfers into transactions. Where `chain` allows several recipients
+    /// per transaction, consecutive transfers share one up to
+    /// `max_recipients_per_tx`, `max_tx_vsize`, the size `max_fee_per_tx`
+    /// pays for at the options' fee rate, and `max_amount_per_tx` in total.
+    /// Rows with a memo get transactions of their own, since a transaction
+    /// carries one memo, and so does every transfer when a fee payer is set.
+    pub fn plan(&self, chain: &impl Chain) -> Vec<PlannedTransaction> {
+        let max_recipients = self.limits.max_recipients_per_tx.unwrap_or(1).max(1);
+        let fee_vsize = match (self.limits.max_fee_per_tx, self.options.fee_rate) {
+            (Some(max_fee), Some(per_kb)) if per_kb > 0 => {
+                Some(max_fee.saturating_mul(1000) / per_kb)
+            }
+            _ => None,
+        };
+        let max_vsize = match (self.limits.max_tx_vsize, fee_vsize) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let max_amount = self.limits.max_amount_per_tx.filter(|max| *max > 0);
+
+        let mut transactions: Vec<PlannedTransaction> = Vec::new();
+        // Size of the last transaction, while more transfers may join it.
+        let mut open: Option<u64> = None;
+        for (idx, row) in self.rows.iter().enumerate() {
+            let chunk = max_amount.unwrap_or(row.amount);
+            let output_vsize = match row.memo {
+                None if max_recipients > 1 && self.options.fee_payer.is_none() => {
+                    chain.output_vsize(&row.address)
+                }
+                _ => None,
+            };
+            let mut remaining = row.amount;
+            while remaining > 0 {
+                let transfer = PlannedTransfer {
+                    row: idx,
+                    address: row.address.clone(),
+                    amount: remaining.min(chunk),
+                };
+                remaining -= transfer.amount;
+
+                let joined = match (open, output_vsize, transactions.last_mut()) {
+                    (Some(vsize), Some(output), Some(last))
+                        if last.transfers.len() < max_recipients
+                            && max_vsize.is_none_or(|max| vsize + output <= max)
+                            && max_amount
+                                .is_none_or(|max| last.total() + transfer.amount <= max) =>
+                    {
+                        last.transfers.push(transfer.clone());
+                        open = Some(vsize + output);
+                        true
+                    }
+                    _ => false,
+                };
+                if !joined {
+                    open = output_vsize.map(|output| BATCH_OVERHEAD_VSIZE + output);
+                    transactions.push(PlannedTransaction {
+                        transfers: vec![transfer],
+                    });
+                }
+            }
+        }
+        transactions
+    }
+
+    /// Validate, then send every planned transaction, continuing past failed
+    /// rows. A row's memo goes on each of its transfers. Transactions paying
+    /// several recipients go through the same screening, approval, spend
+    /// policies and audit as single sends, once per recipient; a wallet
+    /// under partial trust sends every transfer on its own.
+    pub async fn execute<C: Chain, T: Signer<C::Scheme>>(
+        &self,
+        wallet: &Wallet<C, T>,
+        provider: &dyn Provider,
+    ) -> Result<PayoutReport, PayoutError> {
+        self.validate(&wallet.chain)?;
+
+        let mut outcomes: Vec<PayoutOutcome> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(idx, row)| PayoutOutcome {
+                row: idx,
+                line: row.line,
+                address: row.address.clone(),
+                amount: row.amount,
+                memo: row.memo.clone(),
+                tx_hashes: Vec::new(),
+                error: None,
+            })
+            .collect();
+
+        let mut plan = self.plan(&wallet.chain);
+        if wallet.partial_trust {
+            plan = plan
+                .into_iter()
+                .flat_map(|tx| tx.transfers)
+                .map(|transfer| PlannedTransaction {
+                    transfers: vec![transfer],
+                })
+                .collect();
+        }
+        for tx in plan {
+            let transfers: Vec<PlannedTransfer> = tx
+                .transfers
+                .into_iter()
+                .filter(|transfer| outcomes[transfer.row].error.is_none())
+                .collect();
+            let Some(first) = transfers.first() else {
+                continue;
+            };
+            let mut options = self.options.clone();
+            options.memo = self.rows[first.row].memo.clone();
+            options.idempotency_key = None;
+            options.fee_limit = match (options.fee_limit, self.limits.max_fee_per_tx) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let result = if let [transfer] = transfers.as_slice() {
+                wallet
+                    .send_coins_with_options(provider, &transfer.address, transfer.amount, &options)
+                    .await
+            } else {
+                let outputs: Vec<(String, u64)> = transfers
+                    .iter()
+                    .map(|t| (t.address.clone(), t.amount))
+                    .collect();
+                wallet.send_batch(provider, &outputs, &options).await
+            };
+            for transfer in &transfers {
+                let outcome = &mut outcomes[transfer.row];
+                match &result {
+                    Ok(hash) => outcome.tx_hashes.push(hash.clone()),
+                    Err(e) => outcome.error = Some(e.to_string()),
+                }
+            }
+        }
+
+        Ok(PayoutReport { outcomes })
+    }
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// One transaction paying every `(address, amount)` of `outputs`, checked
+    /// and audited per recipient as [`send_coins_with_options`](Self::send_coins_with_options)
+    /// would check and audit a send to it. The signer sees the recipients
+    /// and the total.
+    async fn send_batch(
+        &self,
+        provider: &dyn Provider,
+        outputs: &[(String, u64)],
+        options: &SendOptions,
+    ) -> Result<String, crate::WalletError> {
+        self.check_can_sign()?;
+        let _in_flight = match &self.shutdown {
+            Some(shutdown) => Some(shutdown.enter().ok_or(crate::WalletError::ShuttingDown)?),
+            None => None,
+        };
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(async {
+                let from = self.address()?;
+                for (to, amount) in outputs {
+                    self.audit(AuditKind::SendRequested, &from, to, *amount, None, options);
+                }
+                let result = self.execute_batch(provider, &from, outputs, options).await;
+                for (to, amount) in outputs {
+                    let kind = match &result {
+                        Ok(tx_hash) => AuditKind::SendBroadcast {
+                            tx_hash: tx_hash.clone(),
+                        },
+                        Err(e) => AuditKind::SendFailed {
+                            reason: e.to_string(),
+                        },
+                    };
+                    self.audit(kind, &from, to, *amount, None, options);
+                }
+                result
+            })
+            .await
+    }
+
+    async fn execute_batch(
+        &self,
+        provider: &dyn Provider,
+        from: &str,
+        outputs: &[(String, u64)],
+        options: &SendOptions,
+    ) -> Result<String, crate::WalletError> {
+        for (to, amount) in outputs {
+            self.check_amount(*amount)?;
+            self.check_screening(from, to, *amount, None, options)
+                .await?;
+            self.check_approval(from, to, *amount, options).await?;
+        }
+        let total: u64 = outputs.iter().map(|(_, amount)| amount).sum();
+        let recipients: Vec<&str> = outputs.iter().map(|(to, _)| to.as_str()).collect();
+        let context = SigningContext {
+            chain: self.chain.id().to_string(),
+            from: from.to_string(),
+            to: recipients.join(","),
+            amount: total,
+            summary: options.summary.clone().unwrap_or_else(|| {
+                format!(
+                    "Send {} on {} to {} recipients",
+                    format_units(&total.to_string(), self.chain.decimals()),
+                    self.chain.id(),
+                    outputs.len()
+                )
+            }),
+            metadata: options.metadata.clone(),
+        };
+        let mut options = options.clone();
+        for hook in &self.hooks {
+            hook.pre_create(&context, &mut options).await?;
+        }
+
+        let raw_tx = provider
+            .create_batch_transaction(from, outputs, &options.fees())
+            .await?;
+        let raw_tx = self.prepare_batch(&raw_tx, &options)?;
+        let signed_tx = async {
+            let requests: Vec<_> = outputs
+                .iter()
+                .map(|(to, amount)| self.spend_request(from, to, *amount, &options))
+                .collect();
+            self.check_spend(&requests).await?;
+            self.sign_raw_with_cosigners(&raw_tx, Some(&context), None, &[], true)
+                .await
+        }
+        .await;
+        let signed_tx = self.discard_unsent(provider, &raw_tx, signed_tx).await?;
+        let result = self.broadcast_checked(provider, &signed_tx).await;
+        let tx_hash = self.discard_unsent(provider, &raw_tx, result).await?;
+        self.watch(&tx_hash, signed_tx);
+        self.after_broadcast(&context, &tx_hash).await;
+        Ok(tx_hash)
+    }
+
+    fn prepare_batch(
+        &self,
+        raw_tx: &RawTransaction,
+        options: &SendOptions,
+    ) -> Result<RawTransaction, crate::WalletError> {
+        let raw_tx = match &options.memo {
+            Some(memo) => self.chain.apply_memo(raw_tx, memo.as_bytes())?,
+            None => raw_tx.clone(),
+        };
+        Ok(if options.replaceable {
+            self.chain.signal_replaceable(&raw_tx)?
+        } else {
+            raw_tx
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::node::{FeeOptions, NodeError, ReadProvider, Transaction, TransactionProvider};
+    use crate::test_utils::{OfflineProvider, tron_transfer};
+    use crate::testing::MockProvider;
+    use crate::wallet::chain::utxo::{
+        ChangeStrategy, CoinSelection, SighashType, UtxoInput, UtxoTxBuilder,
+    };
+    use crate::wallet::chain::{LITECOIN, TRON};
+    use crate::wallet::signer::local::LocalSigner;
+
+    const CSV: &str = "address,amount,memo
+# payroll march
+TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7,1500,alice
+TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT, 250 ,
+";
+
+    #[test]
+    fn parses_csv_with_header_and_comments() {
+        let batch = PayoutBatch::from_csv(CSV).expect("parse");
+        assert_eq!(batch.rows().len(), 2);
+        assert_eq!(batch.rows()[0].memo.as_deref(), Some("alice"));
+        assert_eq!(batch.rows()[1].amount, 250);
+        assert_eq!(batch.rows()[1].memo, None);
+        assert!(batch.validate(&TRON).is_ok());
+
+        let err = PayoutBatch::from_csv("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7,abc").unwrap_err();
+        assert!(matches!(err, PayoutError::Parse { line: 1, .. }));
+    }
+
+    #[test]
+    fn validation_reports_every_bad_row() {
+        let mut batch = PayoutBatch::new();
+        batch
+            .add("not-an-address", 1, None)
+            .add("TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7", 0, None);
+        let PayoutError::Invalid(problems) = batch.validate(&TRON).unwrap_err() else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            problems.iter().map(|p| p.row).collect::<Vec<_>>(),
+            [Some(0), Some(1)]
+        );
+        assert_eq!(PayoutError::Invalid(vec![]).to_string(), "invalid rows");
+    }
+
+    #[test]
+    fn plan_splits_large_rows() {
+        let batch = PayoutBatch::from_csv(CSV)
+            .unwrap()
+            .with_limits(PayoutLimits {
+                max_amount_per_tx: Some(1000),
+                ..Default::default()
+            });
+        let amounts: Vec<Vec<(usize, u64)>> = batch
+            .plan(&TRON)
+            .iter()
+            .map(|tx| tx.transfers.iter().map(|t| (t.row, t.amount)).collect())
+            .collect();
+        assert_eq!(amounts, [vec![(0, 1000)], vec![(0, 500)], vec![(1, 250)]]);
+    }
+
+    #[test]
+    fn validation_cites_csv_lines_and_the_batch_total() {
+        let csv = format!(
+            "address,amount\n{},10000\n\n{},300\n",
+            ltc_address(2),
+            ltc_address(3)
+        );
+        let batch = PayoutBatch::from_csv(&csv)
+            .unwrap()
+            .with_limits(PayoutLimits {
+                max_amount_per_tx: Some(4800),
+                max_total: Some(10_000),
+                ..Default::default()
+            });
+        let PayoutError::Invalid(problems) = batch.validate(&LITECOIN).unwrap_err() else {
+            panic!("expected validation error");
+        };
+        let located: Vec<(Option<usize>, Option<usize>)> =
+            problems.iter().map(|p| (p.row, p.line)).collect();
+        // 10000 splits into 4800 + 4800 + 400, and 400 is dust; 300 is too.
+        assert_eq!(
+            located,
+            [(Some(0), Some(2)), (Some(1), Some(4)), (None, None)]
+        );
+        assert!(problems[0].reason.contains("leaves 400"));
+        assert_eq!(
+            problems[2].to_string(),
+            "in the batch: batch total 10300 exceeds limit 10000"
+        );
+        assert!(
+            PayoutError::Invalid(problems)
+                .to_string()
+                .starts_with("3 problem(s), first at line 2: ")
+        );
+    }
+
+    #[test]
+    fn plan_batches_utxo_recipients_within_size_and_fee() {
+        let mut batch = PayoutBatch::new();
+        for key in 2..7 {
+            batch.add(&ltc_address(key), 1000, None);
+        }
+        batch.add(&ltc_address(7), 1000, Some("invoice"));
+        let recipients = |batch: &PayoutBatch| -> Vec<usize> {
+            batch
+                .plan(&LITECOIN)
+                .iter()
+                .map(|tx| tx.transfers.len())
+                .collect()
+        };
+
+        assert_eq!(recipients(&batch), [1, 1, 1, 1, 1, 1]);
+        let by_count = batch.clone().with_limits(PayoutLimits {
+            max_recipients_per_tx: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(recipients(&by_count), [3, 2, 1]);
+        // P2PKH outputs are 34 bytes: two fit in 201 + 2 * 34.
+        let by_size = batch.clone().with_limits(PayoutLimits {
+            max_recipients_per_tx: Some(10),
+            max_tx_vsize: Some(BATCH_OVERHEAD_VSIZE + 2 * 34),
+            ..Default::default()
+        });
+        assert_eq!(recipients(&by_size), [2, 2, 1, 1]);
+        // 300 at 1000 per kB pays for 300 bytes: 201 + 2 * 34 again.
+        let by_fee = batch
+            .clone()
+            .with_limits(PayoutLimits {
+                max_recipients_per_tx: Some(10),
+                max_fee_per_tx: Some(300),
+                ..Default::default()
+            })
+            .with_options(SendOptions {
+                fee_rate: Some(1000),
+                ..Default::default()
+            });
+        assert_eq!(recipients(&by_fee), [2, 2, 1, 1]);
+        // Account chains pay one recipient per transaction regardless.
+        let tron = PayoutBatch::from_csv(CSV)
+            .unwrap()
+            .with_limits(PayoutLimits {
+                max_recipients_per_tx: Some(10),
+                ..Default::default()
+            });
+        assert_eq!(tron.plan(&TRON).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn execute_pays_batched_recipients_in_one_transaction() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), LITECOIN);
+        let provider = Utxos {
+            from: wallet.address().unwrap(),
+            outputs: Mutex::new(Vec::new()),
+        };
+        let mut batch = PayoutBatch::new();
+        for key in 2..5 {
+            batch.add(&ltc_address(key), 10_000, None);
+        }
+        let report = batch
+            .with_limits(PayoutLimits {
+                max_recipients_per_tx: Some(2),
+                ..Default::default()
+            })
+            .execute(&wallet, &provider)
+            .await
+            .expect("valid");
+
+        assert_eq!(report.succeeded().count(), 3);
+        assert_eq!(*provider.outputs.lock().unwrap(), [2]);
+        let hashes: Vec<&str> = report
+            .outcomes
+            .iter()
+            .map(|o| o.tx_hashes[0].as_str())
+            .collect();
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[1], hashes[2]);
+    }
+
+    fn ltc_address(key: u8) -> String {
+        Wallet::new(LocalSigner::from_bytes([key; 32]).unwrap(), LITECOIN)
+            .address()
+            .unwrap()
+    }
+
+    /// Funds transactions from one coin of `from`, recording how many
+    /// recipients each batch pays; single sends are built the same way.
+    struct Utxos {
+        from: String,
+        outputs: Mutex<Vec<usize>>,
+    }
+
+    impl Utxos {
+        fn build(&self, outputs: &[(String, u64)]) -> Result<RawTransaction, NodeError> {
+            let coin = UtxoInput {
+                prev_hash: [0x11; 32],
+                output_index: 0,
+                value: 1_000_000,
+                script_pubkey: LITECOIN.script_pubkey(&self.from).unwrap(),
+                sequence: u32::MAX,
+                sighash: SighashType::All,
+            };
+            outputs
+                .iter()
+                .try_fold(UtxoTxBuilder::new(&LITECOIN), |builder, (to, amount)| {
+                    builder.pay(to, *amount)
+                })
+                .and_then(|builder| builder.change(ChangeStrategy::SameAddress))
+                .and_then(|builder| builder.select_coins(&[coin], &CoinSelection::default(), 1000))
+                .and_then(|builder| builder.build())
+                .map(|tx| tx.into_raw())
+                .map_err(|e| NodeError::Api(e.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ReadProvider for Utxos {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(Vec::new())
+        }
+        async fn get_transaction(&self, _: &str) -> Result<Option<Transaction>, NodeError> {
+            Ok(None)
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("1000000".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Utxos {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            to: &str,
+            amount: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            self.build(&[(to.to_string(), amount)])
+        }
+        async fn create_batch_transaction(
+            &self,
+            _: &str,
+            outputs: &[(String, u64)],
+            _: &FeeOptions,
+        ) -> Result<RawTransaction, NodeError> {
+            self.outputs.lock().unwrap().push(outputs.len());
+            self.build(outputs)
+        }
+        async fn broadcast_transaction(&self, tx: &RawTransaction) -> Result<String, NodeError> {
+            match tx {
+                RawTransaction::Bytes(bytes) => Ok(hex::encode(Sha256::digest(bytes))),
+                _ => Err(NodeError::Api("expected raw bytes".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_reports_per_recipient_failures() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let batch = PayoutBatch::from_csv(CSV).unwrap();
+        let report = batch
+            .execute(&wallet, &OfflineProvider)
+            .await
+            .expect("valid");
+        assert_eq!(report.outcomes.len(), 2);
+        assert_eq!(report.failed().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn execute_sends_each_row_with_its_memo() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON);
+        let provider = MockProvider::new(6).on_create(|_, _, _| Ok(tron_transfer()));
+        let report = PayoutBatch::from_csv(CSV)
+            .unwrap()
+            .execute(&wallet, &provider)
+            .await
+            .expect("valid");
+        assert_eq!(report.succeeded().count(), 2);
+
+        let raw_data: Vec<String> = provider
+            .broadcasts()
+            .iter()
+            .map(|tx| match tx {
+                RawTransaction::Json(json) => json["raw_data_hex"].as_str().unwrap().to_string(),
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        assert!(raw_data[0].contains(&hex::encode("alice")));
+        assert_ne!(raw_data[0], raw_data[1]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Tron account permissions: letting several keys share an account, each
+//! with a weight, so that a transaction needs enough of them to reach the
+//! permission's threshold.
+//!
+//! Transactions under an active permission set
+//! [`SendOptions::permission_id`](crate::wallet::options::SendOptions::permission_id)
+//! and collect the other keys' signatures with
+//! [`Wallet::send_coins_with_cosigners`].
+
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::node::TransactionProvider;
+use crate::node::network::tron::{TronPermission, TronProvider};
+use crate::wallet::chain::TvmChain;
+use crate::wallet::{Signer, Wallet};
+
+/// Most keys one permission may hold.
+pub const MAX_PERMISSION_KEYS: usize = 5;
+/// Most active permissions one account may hold.
+pub const MAX_ACTIVE_PERMISSIONS: usize = 8;
+/// Operations of the active permission a new account gets: every contract
+/// type a wallet signs, as TronLink sets it.
+pub const DEFAULT_ACTIVE_OPERATIONS: &str =
+    "7fff1fc0033e0000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PermissionError {
+    #[error("permission {name} has {keys} keys; at most {max} are allowed")]
+    TooManyKeys {
+        name: String,
+        keys: usize,
+        max: usize,
+    },
+    #[error("permission {name} needs weight {threshold} but its keys add up to {total}")]
+    UnreachableThreshold {
+        name: String,
+        threshold: u64,
+        total: u64,
+    },
+    #[error("{count} active permissions given; at most {max} are allowed")]
+    TooManyActive { count: usize, max: usize },
+    #[error("active permission {0} lists no operations")]
+    NoOperations(String),
+}
+
+/// Check what the node would refuse in an `AccountPermissionUpdateContract`,
+/// above all a threshold the keys cannot reach, which would lock the account.
+pub fn validate_permissions(
+    owner: &TronPermission,
+    actives: &[TronPermission],
+) -> Result<(), PermissionError> {
+    if actives.len() > MAX_ACTIVE_PERMISSIONS {
+        return Err(PermissionError::TooManyActive {
+            count: actives.len(),
+            max: MAX_ACTIVE_PERMISSIONS,
+        });
+    }
+    for permission in std::iter::once(owner).chain(actives) {
+        if permission.keys.len() > MAX_PERMISSION_KEYS {
+            return Err(PermissionError::TooManyKeys {
+                name: permission.name.clone(),
+                keys: permission.keys.len(),
+                max: MAX_PERMISSION_KEYS,
+            });
+        }
+        let total: u64 = permission.keys.iter().map(|(_, weight)| weight).sum();
+        if permission.threshold == 0 || total < permission.threshold {
+            return Err(PermissionError::UnreachableThreshold {
+                name: permission.name.clone(),
+                threshold: permission.threshold,
+                total,
+            });
+        }
+    }
+    if let Some(active) = actives.iter().find(|active| active.operations.is_none()) {
+        return Err(PermissionError::NoOperations(active.name.clone()));
+    }
+    Ok(())
+}
+
+impl<T: Signer> Wallet<TvmChain, T> {
+    /// Replace the account's owner and active permissions. `cosigners` sign
+    /// after the wallet's key when the current owner permission needs more
+    /// than one.
+    pub async fn update_permissions(
+        &self,
+        provider: &TronProvider,
+        owner: &TronPermission,
+        actives: &[TronPermission],
+        cosigners: &[&dyn Signer],
+    ) -> Result<String, WalletError> {
+        validate_permissions(owner, actives)?;
+        let address = self.address()?;
+        let raw_tx = provider
+            .create_account_permission_update(&address, owner, actives)
+            .await?;
+        let signed_tx = self
+            .sign_transaction_with_cosigners(&raw_tx, cosigners)
+            .await?;
+        let tx_hash = provider.broadcast_transaction(&signed_tx).await?;
+        self.watch(&tx_hash, signed_tx);
+        Ok(tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permission(threshold: u64, weights: &[u64]) -> TronPermission {
+        TronPermission {
+            name: "treasury".into(),
+            threshold,
+            keys: weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| (format!("key{}", i), *weight))
+                .collect(),
+            operations: Some(DEFAULT_ACTIVE_OPERATIONS.into()),
+        }
+    }
+
+    #[test]
+    fn refuses_permissions_that_would_lock_the_account() {
+        let owner = permission(2, &[1, 1, 1]);
+        assert!(validate_permissions(&owner, &[permission(1, &[1])]).is_ok());
+        assert_eq!(
+            validate_permissions(&permission(3, &[1, 1]), &[]),
+            Err(PermissionError::UnreachableThreshold {
+                name: "treasury".into(),
+                threshold: 3,
+                total: 2,
+            })
+        );
+        assert!(matches!(
+            validate_permissions(&permission(1, &[1; 6]), &[]),
+            Err(PermissionError::TooManyKeys { keys: 6, .. })
+        ));
+        let mut active = permission(1, &[1]);
+        active.operations = None;
+        assert_eq!(
+            validate_permissions(&owner, &[active]),
+            Err(PermissionError::NoOperations("treasury".into()))
+        );
+    }
+}
@@ -0,0 +1,487 @@
+//! Spending policies enforced by the wallet itself, right before it signs.
+//!
+//! Unlike an [`ApprovalPolicy`](crate::wallet::approval::ApprovalPolicy),
+//! which the send flow consults before building, a [`SpendPolicy`] sits in
+//! front of the signer: every transfer the wallet signs passes it, whether
+//! it comes from `send_coins`, a sweep, a payout, a fee bump, an offline
+//! transaction, a token, NFT or staking operation, or a raw transaction
+//! handed to `sign_transaction` and its variants, so the guardrails hold
+//! however the calling application reaches the key.
+//!
+//! A raw transaction is checked by what the chain reads it to pay (change
+//! back to the wallet aside); one it cannot read is refused while any
+//! policy is set. A send is checked once, even if it is built and signed
+//! again after expiring.
+
+use crate::time::{Duration, Instant};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use thiserror::Error;
+
+use crate::wallet::options::TravelRuleMetadata;
+
+/// Window [`SpendLimits::daily`] is counted over.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolicyError {
+    #[error("{amount} exceeds the per-transaction limit of {limit}")]
+    TransactionLimit { amount: u64, limit: u64 },
+    #[error(
+        "{amount} on top of {spent} sent in the last 24 hours exceeds the daily limit of {limit}"
+    )]
+    DailyLimit {
+        amount: u64,
+        spent: u128,
+        limit: u128,
+    },
+    #[error("{0} is not an allowed destination")]
+    NotAllowed(String),
+    #[error("{0} is a denied destination")]
+    Denied(String),
+    #[error("approval refused: {0}")]
+    Refused(String),
+    #[error("cannot tell what the transaction pays: {0}")]
+    Uninspectable(String),
+}
+
+/// A transfer about to be signed, as seen by a [`SpendPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendRequest {
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in base units of the coin or token; zero for operations
+    /// that move none, such as voting or taking stake back. Token amounts
+    /// past `u64::MAX` are reported as `u64::MAX`.
+    pub amount: u64,
+    /// Contract of the token moved, `None` for the chain's coin.
+    pub token: Option<String>,
+    /// Compliance metadata the send was made with.
+    pub metadata: Option<TravelRuleMetadata>,
+}
+
+/// Rule a transfer must pass before the wallet signs it; see
+/// [`Wallet::with_spend_policy`](crate::wallet::Wallet::with_spend_policy).
+#[async_trait]
+pub trait SpendPolicy: Send + Sync {
+    async fn evaluate(&self, request: &SpendRequest) -> Result<(), PolicyError>;
+}
+
+/// Caps on single transfers and on the total over the last 24 hours.
+///
+/// Amounts of different assets do not add up, so the caps apply to one: the
+/// chain's coin, or the token set with [`for_token`](Self::for_token).
+/// Transfers of anything else pass.
+///
+/// A transfer counts towards the daily total once this policy lets it
+/// through, so one that a later policy refuses or that fails to broadcast
+/// still uses up the allowance until it ages out.
+pub struct SpendLimits {
+    token: Option<String>,
+    per_transaction: Option<u64>,
+    daily: Option<u128>,
+    spent: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl SpendLimits {
+    /// No limits yet.
+    pub fn new() -> Self {
+        Self {
+            token: None,
+            per_transaction: None,
+            daily: None,
+            spent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Cap transfers of the token at `contract` instead of the chain's coin.
+    pub fn for_token(mut self, contract: &str) -> Self {
+        self.token = Some(contract.to_string());
+        self
+    }
+
+    /// Refuse any single transfer of more than `limit` base units.
+    pub fn per_transaction(mut self, limit: u64) -> Self {
+        self.per_transaction = Some(limit);
+        self
+    }
+
+    /// Refuse a transfer that would take the total of the last 24 hours
+    /// above `limit` base units.
+    pub fn daily(mut self, limit: u128) -> Self {
+        self.daily = Some(limit);
+        self
+    }
+
+    fn check_at(&self, amount: u64, now: Instant) -> Result<(), PolicyError> {
+        if let Some(limit) = self.per_transaction
+            && amount > limit
+        {
+            return Err(PolicyError::TransactionLimit { amount, limit });
+        }
+        let mut spent = self.spent.lock().unwrap();
+        while spent
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= DAY)
+        {
+            spent.pop_front();
+        }
+        let total: u128 = spent.iter().map(|(_, a)| *a as u128).sum();
+        if let Some(limit) = self.daily
+            && total + amount as u128 > limit
+        {
+            return Err(PolicyError::DailyLimit {
+                amount,
+                spent: total,
+                limit,
+            });
+        }
+        spent.push_back((now, amount));
+        Ok(())
+    }
+}
+
+impl Default for SpendLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SpendPolicy for SpendLimits {
+    async fn evaluate(&self, request: &SpendRequest) -> Result<(), PolicyError> {
+        if request.token != self.token {
+            return Ok(());
+        }
+        self.check_at(request.amount, Instant::now())
+    }
+}
+
+/// Destinations a wallet may or may not pay. Denied addresses are refused
+/// even if also allowed; with an allowlist, everything else is refused too,
+/// except the wallet itself (staking, say).
+#[derive(Debug, Clone, Default)]
+pub struct Destinations {
+    allowed: Option<HashSet<String>>,
+    denied: HashSet<String>,
+}
+
+impl Destinations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only pay these addresses (and any allowed by other calls).
+    pub fn allow<'a>(mut self, addresses: impl IntoIterator<Item = &'a str>) -> Self {
+        self.allowed
+            .get_or_insert_with(HashSet::new)
+            .extend(addresses.into_iter().map(str::to_string));
+        self
+    }
+
+    /// Never pay these addresses.
+    pub fn deny<'a>(mut self, addresses: impl IntoIterator<Item = &'a str>) -> Self {
+        self.denied
+            .extend(addresses.into_iter().map(str::to_string));
+        self
+    }
+}
+
+#[async_trait]
+impl SpendPolicy for Destinations {
+    async fn evaluate(&self, request: &SpendRequest) -> Result<(), PolicyError> {
+        if self.denied.contains(&request.to) {
+            return Err(PolicyError::Denied(request.to.clone()));
+        }
+        match &self.allowed {
+            _ if request.to == request.from => Ok(()),
+            Some(allowed) if !allowed.contains(&request.to) => {
+                Err(PolicyError::NotAllowed(request.to.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+type ApproveFn = dyn Fn(SpendRequest) -> BoxFuture<'static, Result<(), String>> + Send + Sync;
+
+/// Hands transfers above a threshold to a callback, e.g. one asking an
+/// operator, and signs only once it returns `Ok`. Its `Err` is the reason
+/// given in [`PolicyError::Refused`].
+pub struct RequireApproval {
+    threshold: u64,
+    approve: Arc<ApproveFn>,
+}
+
+impl RequireApproval {
+    /// Ask `approve` about every transfer.
+    pub fn new<F>(approve: F) -> Self
+    where
+        F: Fn(SpendRequest) -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static,
+    {
+        Self {
+            threshold: 0,
+            approve: Arc::new(approve),
+        }
+    }
+
+    /// Only ask about transfers of more than `amount` base units.
+    pub fn above(mut self, amount: u64) -> Self {
+        self.threshold = amount;
+        self
+    }
+}
+
+#[async_trait]
+impl SpendPolicy for RequireApproval {
+    async fn evaluate(&self, request: &SpendRequest) -> Result<(), PolicyError> {
+        if request.amount <= self.threshold {
+            return Ok(());
+        }
+        (self.approve)(request.clone())
+            .await
+            .map_err(PolicyError::Refused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TO: &str = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+
+    fn request(to: &str, amount: u64) -> SpendRequest {
+        SpendRequest {
+            chain: "tron".into(),
+            from: "TLyqzVGLV1srkB7dToTAEqgDSfPtXRJZYH".into(),
+            to: to.into(),
+            amount,
+            token: None,
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_lists_and_approvals_gate_transfers() {
+        let limits = SpendLimits::new().per_transaction(600).daily(1_000);
+        let start = Instant::now();
+        assert!(limits.check_at(500, start).is_ok());
+        assert_eq!(
+            limits.check_at(700, start),
+            Err(PolicyError::TransactionLimit {
+                amount: 700,
+                limit: 600
+            })
+        );
+        assert!(limits.check_at(500, start).is_ok());
+        assert_eq!(
+            limits.check_at(1, start),
+            Err(PolicyError::DailyLimit {
+                amount: 1,
+                spent: 1_000,
+                limit: 1_000
+            })
+        );
+        assert!(limits.check_at(500, start + DAY).is_ok());
+
+        // Coin and token amounts are capped apart.
+        let usdt = SpendLimits::new().for_token("TUsdt").per_transaction(10);
+        let mut transfer = request(TO, 50);
+        assert!(usdt.evaluate(&transfer).await.is_ok());
+        transfer.token = Some("TUsdt".into());
+        assert!(matches!(
+            usdt.evaluate(&transfer).await,
+            Err(PolicyError::TransactionLimit { .. })
+        ));
+        assert!(limits.evaluate(&transfer).await.is_ok());
+
+        let destinations = Destinations::new().allow([TO]).deny(["TBlocked"]);
+        assert!(destinations.evaluate(&request(TO, 1)).await.is_ok());
+        assert!(matches!(
+            destinations.evaluate(&request("TOther", 1)).await,
+            Err(PolicyError::NotAllowed(_))
+        ));
+        let own = request(TO, 0);
+        assert!(
+            destinations
+                .evaluate(&SpendRequest {
+                    to: own.from.clone(),
+                    ..own
+                })
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            Destinations::new()
+                .deny(["TBlocked"])
+                .evaluate(&request("TBlocked", 1))
+                .await,
+            Err(PolicyError::Denied(_))
+        ));
+
+        let approval = RequireApproval::new(|request: SpendRequest| {
+            Box::pin(async move {
+                if request.amount < 5_000 {
+                    Ok(())
+                } else {
+                    Err("too much for one signature".to_string())
+                }
+            })
+        })
+        .above(100);
+        assert!(approval.evaluate(&request(TO, 50)).await.is_ok());
+        assert!(approval.evaluate(&request(TO, 1_000)).await.is_ok());
+        assert_eq!(
+            approval.evaluate(&request(TO, 9_000)).await,
+            Err(PolicyError::Refused("too much for one signature".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn policies_and_hooks_see_the_send_metadata() {
+        use crate::test_utils::tron_transfer;
+        use crate::testing::MockProvider;
+        use crate::wallet::Wallet;
+        use crate::wallet::chain::TRON;
+        use crate::wallet::hooks::{HookError, TransactionHook};
+        use crate::wallet::options::SendOptions;
+        use crate::wallet::signer::SigningContext;
+        use crate::wallet::signer::local::LocalSigner;
+
+        #[derive(Default)]
+        struct Seen(Mutex<Vec<Option<TravelRuleMetadata>>>);
+
+        #[async_trait]
+        impl TransactionHook for Seen {
+            async fn pre_create(
+                &self,
+                send: &SigningContext,
+                _: &mut SendOptions,
+            ) -> Result<(), HookError> {
+                self.0.lock().unwrap().push(send.metadata.clone());
+                Ok(())
+            }
+        }
+
+        let seen = Arc::new(Seen::default());
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_hook(seen.clone())
+            .with_spend_policy(Arc::new(RequireApproval::new(|request: SpendRequest| {
+                Box::pin(async move {
+                    match request.metadata.and_then(|m| m.reference) {
+                        Some(_) => Ok(()),
+                        None => Err("withdrawals need a reference".to_string()),
+                    }
+                })
+            })));
+        let provider = MockProvider::new(6).on_create(|_, _, _| Ok(tron_transfer()));
+        let metadata = TravelRuleMetadata {
+            reference: Some("withdrawal-17".into()),
+            ..Default::default()
+        };
+        let options = SendOptions {
+            metadata: Some(metadata.clone()),
+            ..SendOptions::default()
+        };
+
+        assert!(
+            wallet
+                .send_coins_with_options(&provider, TO, 5, &options)
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            wallet.send_coins(&provider, TO, 5).await,
+            Err(crate::WalletError::Policy(PolicyError::Refused(_)))
+        ));
+        assert_eq!(*seen.0.lock().unwrap(), [Some(metadata), None]);
+    }
+
+    #[tokio::test]
+    async fn a_send_rebuilt_after_expiring_is_counted_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::node::NodeError;
+        use crate::test_utils::tron_transfer;
+        use crate::testing::MockProvider;
+        use crate::wallet::Wallet;
+        use crate::wallet::chain::TRON;
+        use crate::wallet::signer::local::LocalSigner;
+
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_spend_policy(Arc::new(SpendLimits::new().daily(5)));
+        let broadcasts = AtomicUsize::new(0);
+        let provider = MockProvider::new(6)
+            .on_create(|_, _, _| Ok(tron_transfer()))
+            .on_broadcast(move |_| match broadcasts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(NodeError::Api("TRANSACTION_EXPIRATION_ERROR".into())),
+                _ => Ok("sent".into()),
+            });
+
+        assert_eq!(wallet.send_coins(&provider, TO, 5).await.unwrap(), "sent");
+        assert!(matches!(
+            wallet.send_coins(&provider, TO, 1).await,
+            Err(crate::WalletError::Policy(PolicyError::DailyLimit {
+                spent: 5,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn raw_transactions_are_checked_by_what_they_pay() {
+        use prost::Message;
+
+        use crate::node::RawTransaction;
+        use crate::test_utils::tron_transfer;
+        use crate::wallet::Wallet;
+        use crate::wallet::chain::TRON;
+        use crate::wallet::chain::tvm::proto::{self, ContractType, TransactionRaw};
+        use crate::wallet::signer::local::LocalSigner;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_spend_policy(Arc::new(RequireApproval::new(move |request| {
+                record.lock().unwrap().push(request);
+                Box::pin(async { Err("not today".to_string()) })
+            })));
+        let raw_data = TransactionRaw {
+            contract: vec![proto::Contract::pack(
+                ContractType::TransferContract,
+                &proto::TransferContract {
+                    to_address: [[0x41].as_slice(), &[7; 20]].concat(),
+                    amount: 250,
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        };
+        let raw_tx = RawTransaction::Json(serde_json::json!({
+            "txID": hex::encode(raw_data.txid()),
+            "raw_data_hex": hex::encode(raw_data.encode_to_vec()),
+        }));
+
+        assert_eq!(
+            wallet
+                .sign_transaction(&raw_tx)
+                .await
+                .unwrap_err()
+                .to_string(),
+            crate::WalletError::Policy(PolicyError::Refused("not today".into())).to_string()
+        );
+        let seen = seen.lock().unwrap().clone();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].amount, 250);
+        assert_eq!(seen[0].from, wallet.address().unwrap());
+        // One paying nobody the chain can name is not signed at all.
+        assert!(matches!(
+            wallet.sign_transaction(&tron_transfer()).await,
+            Err(crate::WalletError::Policy(PolicyError::Uninspectable(_)))
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+//! Balances valued in a fiat currency.
+
+use serde::{Deserialize, Serialize};
+
+use crate::WalletError;
+use crate::node::price::PriceProvider;
+use crate::node::{Amount, NodeError, ReadProvider};
+use crate::wallet::chain::Chain;
+use crate::wallet::{Signer, Wallet};
+
+/// One coin or token an address holds, with its fiat value where a price is known.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub symbol: String,
+    /// Token contract; `None` for the chain's native coin.
+    pub contract: Option<String>,
+    pub amount: Amount,
+    /// Price of one whole unit.
+    pub price: Option<f64>,
+    pub value: Option<f64>,
+}
+
+/// What an address holds, valued in `fiat`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub address: String,
+    pub fiat: String,
+    /// The native coin first, then each token.
+    pub holdings: Vec<Holding>,
+    /// Sum of the holdings that have a value.
+    pub total: f64,
+    /// Why token balances are missing, when the provider could not list them
+    /// (many, e.g. Blockcypher's, list none).
+    #[serde(skip)]
+    pub token_error: Option<NodeError>,
+}
+
+impl Portfolio {
+    /// Holdings no price was found for, which [`total`](Self::total) leaves out.
+    pub fn unpriced(&self) -> impl Iterator<Item = &Holding> {
+        self.holdings.iter().filter(|h| h.value.is_none())
+    }
+}
+
+/// Value `address`'s native balance and token balances, as `provider` lists
+/// them, at `prices`' rates in `fiat`. Tokens the provider cannot name or
+/// give decimals for are left out: there is nothing to price them by.
+pub async fn portfolio_of(
+    provider: &dyn ReadProvider,
+    prices: &dyn PriceProvider,
+    address: &str,
+    fiat: &str,
+) -> Result<Portfolio, WalletError> {
+    let info = provider.chain_info().await?;
+    let native = Amount::from_base_units(&provider.get_balance(address).await?, info.decimals)?;
+    let mut holdings = vec![Holding {
+        symbol: info.symbol.unwrap_or_default(),
+        contract: None,
+        amount: native,
+        price: None,
+        value: None,
+    }];
+
+    let token_error = match provider.get_token_balances(address).await {
+        Ok(tokens) => {
+            for token in tokens {
+                let (Some(symbol), Some(decimals)) = (token.symbol, token.decimals) else {
+                    continue;
+                };
+                holdings.push(Holding {
+                    symbol,
+                    contract: Some(token.contract),
+                    amount: Amount::from_base_units(&token.balance, decimals)?,
+                    price: None,
+                    value: None,
+                });
+            }
+            None
+        }
+        Err(e) => Some(e),
+    };
+
+    let symbols: Vec<&str> = holdings
+        .iter()
+        .map(|h| h.symbol.as_str())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let rates = prices.get_prices(&symbols, fiat).await?;
+    let mut total = 0.0;
+    for holding in &mut holdings {
+        holding.price = rates.get(&holding.symbol.to_uppercase()).copied();
+        holding.value = holding
+            .price
+            .map(|price| whole_units(&holding.amount) * price);
+        total += holding.value.unwrap_or(0.0);
+    }
+
+    Ok(Portfolio {
+        address: address.to_string(),
+        fiat: fiat.to_uppercase(),
+        holdings,
+        total,
+        token_error,
+    })
+}
+
+fn whole_units(amount: &Amount) -> f64 {
+    amount.value() as f64 / 10f64.powi(amount.decimals() as i32)
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// This wallet's balances valued in `fiat`; see [`portfolio_of`].
+    pub async fn portfolio(
+        &self,
+        provider: &dyn ReadProvider,
+        prices: &dyn PriceProvider,
+        fiat: &str,
+    ) -> Result<Portfolio, WalletError> {
+        portfolio_of(provider, prices, &self.address()?, fiat).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::price::StaticPrices;
+    use crate::node::token::USDT_TRON;
+    use crate::node::{ChainInfo, ReadProvider, TokenBalance};
+    use crate::testing::MockProvider;
+
+    /// A Tron account holding USDT and a token nobody has labelled.
+    struct Node(MockProvider);
+
+    #[async_trait]
+    impl ReadProvider for Node {
+        fn get_decimals(&self) -> u32 {
+            6
+        }
+        async fn chain_info(&self) -> Result<ChainInfo, NodeError> {
+            Ok(ChainInfo {
+                symbol: Some("TRX".into()),
+                ..ChainInfo::with_decimals(6)
+            })
+        }
+        async fn get_transactions(
+            &self,
+            address: &str,
+        ) -> Result<Vec<crate::node::Transaction>, NodeError> {
+            self.0.get_transactions(address).await
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            self.0.get_block_number().await
+        }
+        async fn get_balance(&self, address: &str) -> Result<String, NodeError> {
+            self.0.get_balance(address).await
+        }
+        async fn get_token_balances(&self, _: &str) -> Result<Vec<TokenBalance>, NodeError> {
+            Ok(vec![
+                TokenBalance::new(Some("tron"), USDT_TRON.contract, "25000000".into()),
+                TokenBalance::new(Some("tron"), "TUnknownToken", "7".into()),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn values_native_and_token_balances() {
+        let node = Node(MockProvider::new(6));
+        node.0.set_balance("TA", 150_000_000);
+        let prices = StaticPrices::new()
+            .with_price("trx", "usd", 0.2)
+            .with_price("USDT", "USD", 1.0);
+
+        let portfolio = portfolio_of(&node, &prices, "TA", "usd").await.unwrap();
+        assert_eq!(portfolio.fiat, "USD");
+        assert_eq!(portfolio.holdings.len(), 2);
+        assert_eq!(portfolio.holdings[0].symbol, "TRX");
+        assert_eq!(portfolio.holdings[0].value, Some(30.0));
+        assert_eq!(
+            portfolio.holdings[1].contract.as_deref(),
+            Some(USDT_TRON.contract)
+        );
+        assert_eq!(portfolio.total, 55.0);
+        assert!(portfolio.token_error.is_none());
+
+        let unpriced = portfolio_of(&node, &prices, "TA", "eur").await.unwrap();
+        assert_eq!(unpriced.total, 0.0);
+        assert_eq!(unpriced.unpriced().count(), 2);
+    }
+}
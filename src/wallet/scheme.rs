@@ -0,0 +1,116 @@
+//! Signature schemes as types, so chains and signers are matched at compile time.
+//!
+//! A [`Chain`](crate::wallet::chain::Chain) names the scheme it verifies and a
+//! [`Signer`](crate::wallet::Signer) names the scheme it produces; `Wallet` only
+//! accepts pairs that agree.
+//!
+//! ```compile_fail
+//! use async_trait::async_trait;
+//! use flow_wallet::wallet::scheme::Ed25519;
+//! use flow_wallet::wallet::signer::SignerError;
+//! use flow_wallet::wallet::{Signer, Wallet, chain::TRON};
+//!
+//! struct EdSigner;
+//!
+//! #[async_trait]
+//! impl Signer<Ed25519> for EdSigner {
+//!     async fn sign(&self, _message: &[u8]) -> Result<Vec<u8>, SignerError> {
+//!         Err(SignerError::Unsupported("signing"))
+//!     }
+//!     fn public_key(&self) -> Vec<u8> { vec![] }
+//! }
+//!
+//! // TRON verifies secp256k1 signatures, so this does not compile.
+//! let _wallet = Wallet::new(EdSigner, TRON);
+//! ```
+
+use std::fmt;
+
+use crate::wallet::chain::{ChainError, SignatureFormat, SignaturePolicy};
+
+/// Curve a key lives on, for checks made at runtime (keys loaded from a
+/// store, a [`Box<dyn Signer>`](crate::wallet::Signer) of unknown origin).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyType {
+    Secp256k1,
+    Ed25519,
+}
+
+impl KeyType {
+    /// Curve of a public key, told apart by its encoding: 32 bytes for
+    /// ed25519, compressed or uncompressed SEC1 for secp256k1.
+    pub fn of_public_key(public_key: &[u8]) -> Option<Self> {
+        match public_key {
+            [0x02 | 0x03, rest @ ..] if rest.len() == 32 => Some(Self::Secp256k1),
+            [0x04, rest @ ..] if rest.len() == 64 => Some(Self::Secp256k1),
+            key if key.len() == 32 => Some(Self::Ed25519),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Secp256k1 => Secp256k1::NAME,
+            Self::Ed25519 => Ed25519::NAME,
+        })
+    }
+}
+
+/// A public key together with the curve it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublicKey {
+    pub key_type: KeyType,
+    /// SEC1 for secp256k1, the 32-byte point for ed25519.
+    pub bytes: Vec<u8>,
+}
+
+impl PublicKey {
+    pub fn new(key_type: KeyType, bytes: Vec<u8>) -> Self {
+        Self { key_type, bytes }
+    }
+}
+
+/// Marker for a signature algorithm / curve combination.
+pub trait SignatureScheme: Send + Sync + 'static {
+    const NAME: &'static str;
+    const KEY_TYPE: KeyType;
+
+    /// Bring a signer's output into the canonical form `policy` requires.
+    /// Schemes without malleable signatures return it unchanged.
+    fn canonicalize(
+        signature: &[u8],
+        format: SignatureFormat,
+        policy: &SignaturePolicy,
+    ) -> Result<Vec<u8>, ChainError> {
+        let _ = (format, policy);
+        Ok(signature.to_vec())
+    }
+}
+
+/// ECDSA over secp256k1 (Bitcoin, Litecoin, Tron, EVM).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Secp256k1;
+
+impl SignatureScheme for Secp256k1 {
+    const NAME: &'static str = "secp256k1";
+    const KEY_TYPE: KeyType = KeyType::Secp256k1;
+
+    fn canonicalize(
+        signature: &[u8],
+        format: SignatureFormat,
+        policy: &SignaturePolicy,
+    ) -> Result<Vec<u8>, ChainError> {
+        crate::wallet::chain::sdk::canonicalize_signature(signature, format, policy)
+    }
+}
+
+/// EdDSA over Curve25519 (Solana, Aptos, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ed25519;
+
+impl SignatureScheme for Ed25519 {
+    const NAME: &'static str = "ed25519";
+    const KEY_TYPE: KeyType = KeyType::Ed25519;
+}
@@ -0,0 +1,204 @@
+//! Destination screening against sanctions lists and denylists.
+//!
+//! A wallet [configured](crate::wallet::Wallet::with_screening) with a
+//! [`Screening`] asks it about every recipient before anything is built or
+//! signed: of coins, tokens and NFTs, and the spender of a token approval or
+//! receiver of delegated resources. Whether a screener that cannot answer (a sanctions API timing out)
+//! blocks the send is the [`FailMode`]'s call; sends let through that way are
+//! recorded as [`AuditKind::ScreeningBypassed`](crate::wallet::audit::AuditKind::ScreeningBypassed).
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScreeningError {
+    #[error("destination {address} on {chain} is denied: {reason}")]
+    Denied {
+        chain: String,
+        address: String,
+        reason: String,
+    },
+    #[error("screening unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// What to do when a [`Screening`] fails with [`ScreeningError::Unavailable`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailMode {
+    /// Refuse the send.
+    #[default]
+    Closed,
+    /// Send anyway and record that screening was skipped.
+    Open,
+}
+
+/// Check on a destination before a send to it is signed.
+#[async_trait]
+pub trait Screening: Send + Sync {
+    /// `Ok` if `address` on `chain` may receive funds.
+    async fn screen(&self, chain: &str, address: &str) -> Result<(), ScreeningError>;
+}
+
+/// Fixed set of denied addresses, e.g. loaded from a sanctions list export.
+///
+/// `0x` addresses match case-insensitively; others, whose encodings are case
+/// sensitive, match exactly.
+#[derive(Debug, Clone, Default)]
+pub struct Denylist {
+    /// (chain, address); a `None` chain matches every chain.
+    entries: HashSet<(Option<String>, String)>,
+}
+
+impl Denylist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deny `address` on every chain.
+    pub fn deny(mut self, address: &str) -> Self {
+        self.entries.insert((None, normalize(address)));
+        self
+    }
+
+    /// Deny `address` on `chain` only.
+    pub fn deny_on(mut self, chain: &str, address: &str) -> Self {
+        self.entries
+            .insert((Some(chain.to_string()), normalize(address)));
+        self
+    }
+
+    /// Deny every address in `list`, one per line; blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn from_lines(list: &str) -> Self {
+        list.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .fold(Self::new(), Self::deny)
+    }
+
+    pub fn contains(&self, chain: &str, address: &str) -> bool {
+        let address = normalize(address);
+        self.entries.contains(&(None, address.clone()))
+            || self.entries.contains(&(Some(chain.to_string()), address))
+    }
+}
+
+fn normalize(address: &str) -> String {
+    if address.starts_with("0x") {
+        address.to_ascii_lowercase()
+    } else {
+        address.to_string()
+    }
+}
+
+#[async_trait]
+impl Screening for Denylist {
+    async fn screen(&self, chain: &str, address: &str) -> Result<(), ScreeningError> {
+        if self.contains(chain, address) {
+            return Err(ScreeningError::Denied {
+                chain: chain.to_string(),
+                address: address.to_string(),
+                reason: "on denylist".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::WalletError;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::Wallet;
+    use crate::wallet::audit::{AuditKind, MemoryAuditLog};
+    use crate::wallet::chain::TRON;
+    use crate::wallet::signer::local::LocalSigner;
+
+    struct Down;
+
+    #[async_trait]
+    impl Screening for Down {
+        async fn screen(&self, _: &str, _: &str) -> Result<(), ScreeningError> {
+            Err(ScreeningError::Unavailable("timed out".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn denied_and_unavailable_screening() {
+        let denied = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let other = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let list = Denylist::from_lines(&format!("# OFAC export\n\n{}\n", denied))
+            .deny_on("ethereum", "0xAbC0000000000000000000000000000000000001");
+        assert!(list.contains("ethereum", "0xabc0000000000000000000000000000000000001"));
+        assert!(!list.contains("sepolia", "0xabc0000000000000000000000000000000000001"));
+
+        let signer = || LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let wallet = Wallet::new(signer(), TRON).with_screening(Arc::new(list), FailMode::Closed);
+        let refused = wallet.send_coins(&OfflineProvider, denied, 1).await;
+        assert!(matches!(
+            refused,
+            Err(WalletError::Screening(ScreeningError::Denied { .. }))
+        ));
+
+        let closed = Wallet::new(signer(), TRON).with_screening(Arc::new(Down), FailMode::Closed);
+        assert!(matches!(
+            closed.send_coins(&OfflineProvider, other, 1).await,
+            Err(WalletError::Screening(ScreeningError::Unavailable(_)))
+        ));
+
+        // Fail-open carries on to the (offline) provider and leaves a trace.
+        let log = Arc::new(MemoryAuditLog::new());
+        let open = Wallet::new(signer(), TRON)
+            .with_screening(Arc::new(Down), FailMode::Open)
+            .with_audit_log(log.clone());
+        assert!(matches!(
+            open.send_coins(&OfflineProvider, other, 1).await,
+            Err(WalletError::Node(_))
+        ));
+        assert!(log.records().iter().any(|r| r.kind
+            == AuditKind::ScreeningBypassed {
+                reason: "screening unavailable: timed out".into()
+            }));
+    }
+
+    #[cfg(feature = "network")]
+    #[tokio::test]
+    async fn tokens_approvals_and_delegations_are_screened() {
+        use crate::node::network::tron::{TronProvider, TronResource};
+        use crate::node::token::{Allowance, USDT_TRON};
+        use crate::wallet::token::TokenWallet;
+
+        let denied = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), TRON)
+            .with_screening(Arc::new(Denylist::new().deny(denied)), FailMode::Closed);
+        // Refused before the provider is asked anything.
+        let provider = TronProvider::with_url("http://127.0.0.1:9".to_string());
+        let is_denied = |result: Result<String, WalletError>| {
+            matches!(
+                result,
+                Err(WalletError::Screening(ScreeningError::Denied { .. }))
+            )
+        };
+
+        let usdt = TokenWallet::new(&wallet, &provider, USDT_TRON);
+        assert!(is_denied(usdt.transfer(denied, "1").await));
+        assert!(is_denied(usdt.approve(denied, Allowance::Limited(1)).await));
+        assert!(is_denied(
+            wallet
+                .delegate_resource(&provider, denied, 1, TronResource::Energy)
+                .await
+        ));
+        // Taking resources back moves nothing to the denied address.
+        assert!(matches!(
+            wallet
+                .undelegate_resource(&provider, denied, 1, TronResource::Energy)
+                .await,
+            Err(WalletError::Node(_))
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::wallet::crypto::memory::SecureBuffer;
+
+/// Named secrets (TOTP seeds, API tokens) kept out of the wallet's
+/// configuration, e.g. in a vault, the environment or memory.
+pub trait SecretStore: Send + Sync {
+    /// The secret called `name`, or `None` if the store has no such entry.
+    fn get(&self, name: &str) -> std::io::Result<Option<SecureBuffer>>;
+}
+
+/// A [`SecretStore`] that can also keep secrets, for key material
+/// (mnemonics, private keys) stored by alias outside the process and read
+/// back only when a key source is built from it, e.g. with
+/// [`MnemonicKeySource::from_store`](crate::wallet::key_source::MnemonicKeySource::from_store).
+///
+/// Not to be confused with a [`Keystore`](crate::wallet::keystore::Keystore),
+/// which is one password-encrypted key file.
+pub trait KeyStore: SecretStore {
+    /// Store `secret` as `alias`, replacing any secret already there.
+    fn put(&self, alias: &str, secret: &SecureBuffer) -> std::io::Result<()>;
+
+    /// Remove `alias`; `false` if there was nothing to remove.
+    fn delete(&self, alias: &str) -> std::io::Result<bool>;
+}
+
+/// Secrets held in process memory, zeroized on drop.
+#[derive(Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<String, SecureBuffer>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, name: &str, secret: impl Into<SecureBuffer>) {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), secret.into());
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn get(&self, name: &str) -> std::io::Result<Option<SecureBuffer>> {
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+}
+
+impl KeyStore for MemorySecretStore {
+    fn put(&self, alias: &str, secret: &SecureBuffer) -> std::io::Result<()> {
+        self.insert(alias, secret.clone());
+        Ok(())
+    }
+
+    fn delete(&self, alias: &str) -> std::io::Result<bool> {
+        Ok(self.secrets.lock().unwrap().remove(alias).is_some())
+    }
+}
+
+/// Secrets read from environment variables named `{prefix}{name}`.
+pub struct EnvSecretStore {
+    prefix: String,
+}
+
+impl EnvSecretStore {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+impl SecretStore for EnvSecretStore {
+    fn get(&self, name: &str) -> std::io::Result<Option<SecureBuffer>> {
+        match std::env::var(format!("{}{}", self.prefix, name)) {
+            Ok(value) => Ok(Some(SecureBuffer::from_string(value))),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use futures_util::future::BoxFuture;
+use futures_util::stream::FuturesUnordered;
+
+use crate::wallet::chain::Network;
+use crate::wallet::scheme::{KeyType, Secp256k1, SignatureScheme};
+use crate::wallet::signer::{SignMode, SignerError, SigningContext};
+use crate::wallet::{Signer, sign_as};
+
+/// Signatures a [`CompositeSigner`] is about to make, as its approvers see them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureRequest {
+    /// Every payload of the transaction, signed together.
+    pub payloads: Vec<Vec<u8>>,
+    pub mode: SignMode,
+    /// What is being signed, when the wallet described it.
+    pub context: Option<SigningContext>,
+}
+
+/// One of the parties a [`CompositeSigner`] asks before it signs.
+#[async_trait]
+pub trait Approver: Send + Sync {
+    /// `Ok` to approve; an error says why not.
+    async fn approve(&self, request: &SignatureRequest) -> Result<(), SignerError>;
+}
+
+/// A second [`Signer`] as an approver: it approves by signing the same
+/// payloads, which for a hardware wallet or a remote co-signing service
+/// means a person or policy engine confirmed them. Its signatures are
+/// thrown away.
+pub struct Cosigner<T, S: SignatureScheme = Secp256k1> {
+    signer: T,
+    _scheme: PhantomData<fn() -> S>,
+}
+
+impl<T, S: SignatureScheme> Cosigner<T, S> {
+    pub fn new(signer: T) -> Self {
+        Self {
+            signer,
+            _scheme: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: SignatureScheme, T: Signer<S>> Approver for Cosigner<T, S> {
+    async fn approve(&self, request: &SignatureRequest) -> Result<(), SignerError> {
+        for payload in &request.payloads {
+            sign_as(
+                &self.signer,
+                payload,
+                request.mode,
+                request.context.as_ref(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+type ApproveFn = dyn Fn(SignatureRequest) -> BoxFuture<'static, Result<(), String>> + Send + Sync;
+
+/// An async callback as an approver, e.g. one that waits for someone to
+/// press a button in another service. Its `Err` is the reason given in
+/// [`SignerError::Rejected`].
+pub struct ApprovalCallback {
+    approve: Arc<ApproveFn>,
+}
+
+impl ApprovalCallback {
+    pub fn new<F>(approve: F) -> Self
+    where
+        F: Fn(SignatureRequest) -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static,
+    {
+        Self {
+            approve: Arc::new(approve),
+        }
+    }
+}
+
+#[async_trait]
+impl Approver for ApprovalCallback {
+    async fn approve(&self, request: &SignatureRequest) -> Result<(), SignerError> {
+        (self.approve)(request.clone())
+            .await
+            .map_err(SignerError::Rejected)
+    }
+}
+
+/// Signer that signs with `inner` only once `threshold` of its approvers
+/// have approved, adding approval factors to a custodial key without
+/// touching the [`Wallet`](crate::wallet::Wallet) that uses it.
+///
+/// Approvers are asked all at once. Signing goes ahead as soon as enough
+/// approve and fails with [`SignerError::Rejected`], listing the reasons,
+/// as soon as too many refuse for the threshold to be reached. A batch
+/// from [`sign_many`](Signer::sign_many) is approved as a whole.
+pub struct CompositeSigner<T> {
+    inner: T,
+    approvers: Vec<Arc<dyn Approver>>,
+    threshold: Option<usize>,
+}
+
+impl<T> CompositeSigner<T> {
+    /// Signs with `inner` once every approver added has approved.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            approvers: Vec::new(),
+            threshold: None,
+        }
+    }
+
+    pub fn with_approver(mut self, approver: Arc<dyn Approver>) -> Self {
+        self.approvers.push(approver);
+        self
+    }
+
+    /// Sign once `threshold` of the approvers approve, rather than all of them.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    async fn approve(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+    ) -> Result<(), SignerError> {
+        let needed = self.threshold.unwrap_or(self.approvers.len());
+        if needed > self.approvers.len() {
+            return Err(SignerError::Rejected(format!(
+                "{} approvals required but only {} approvers configured",
+                needed,
+                self.approvers.len()
+            )));
+        }
+        if needed == 0 {
+            return Ok(());
+        }
+        let request = SignatureRequest {
+            payloads: payloads.to_vec(),
+            mode,
+            context: context.cloned(),
+        };
+        let mut pending: FuturesUnordered<_> = self
+            .approvers
+            .iter()
+            .map(|approver| approver.approve(&request))
+            .collect();
+        let mut approved = 0;
+        let mut refusals = Vec::new();
+        while let Some(result) = pending.next().await {
+            match result {
+                Ok(()) => approved += 1,
+                Err(e) => refusals.push(e.to_string()),
+            }
+            if approved >= needed {
+                return Ok(());
+            }
+            if self.approvers.len() - refusals.len() < needed {
+                break;
+            }
+        }
+        Err(SignerError::Rejected(format!(
+            "{} of {} required approvals: {}",
+            approved,
+            needed,
+            refusals.join("; ")
+        )))
+    }
+}
+
+#[async_trait]
+impl<S: SignatureScheme, T: Signer<S>> Signer<S> for CompositeSigner<T> {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[message.to_vec()], SignMode::Message, None)
+            .await?;
+        self.inner.sign(message).await
+    }
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[prehash.to_vec()], SignMode::Prehash, None)
+            .await?;
+        self.inner.sign_prehash(prehash).await
+    }
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[message.to_vec()], SignMode::Message, Some(context))
+            .await?;
+        self.inner.sign_with_context(message, context).await
+    }
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[prehash.to_vec()], SignMode::Prehash, Some(context))
+            .await?;
+        self.inner.sign_prehash_with_context(prehash, context).await
+    }
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[prehash.to_vec()], SignMode::Recoverable, None)
+            .await?;
+        self.inner.sign_recoverable(prehash).await
+    }
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[prehash.to_vec()], SignMode::Recoverable, Some(context))
+            .await?;
+        self.inner
+            .sign_recoverable_with_context(prehash, context)
+            .await
+    }
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.approve(&[prehash.to_vec()], SignMode::Taproot, None)
+            .await?;
+        self.inner.sign_schnorr(prehash, tweak).await
+    }
+    async fn sign_many(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        concurrency: usize,
+    ) -> Vec<Result<Vec<u8>, SignerError>> {
+        if let Err(e) = self.approve(payloads, mode, context).await {
+            let reason = e.to_string();
+            return payloads
+                .iter()
+                .map(|_| Err(SignerError::Rejected(reason.clone())))
+                .collect();
+        }
+        self.inner
+            .sign_many(payloads, mode, context, concurrency)
+            .await
+    }
+    fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key()
+    }
+    fn key_type(&self) -> KeyType {
+        self.inner.key_type()
+    }
+    fn network(&self) -> Option<Network> {
+        self.inner.network()
+    }
+    fn can_sign(&self) -> bool {
+        self.inner.can_sign()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::wallet::Wallet;
+    use crate::wallet::chain::{Chain, TRON};
+    use crate::wallet::signer::LocalSigner;
+
+    fn callback(answer: Result<(), &'static str>, calls: Arc<AtomicUsize>) -> Arc<dyn Approver> {
+        Arc::new(ApprovalCallback::new(move |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { answer.map_err(String::from) })
+        }))
+    }
+
+    #[tokio::test]
+    async fn signs_once_enough_approvers_agree() {
+        let key = || LocalSigner::from_bytes([1; 32]).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let signer = CompositeSigner::new(key())
+            .with_approver(Arc::new(Cosigner::<_, Secp256k1>::new(
+                LocalSigner::from_bytes([2; 32]).unwrap(),
+            )))
+            .with_approver(callback(Err("not today"), calls.clone()))
+            .with_approver(callback(Ok(()), calls.clone()))
+            .with_threshold(2);
+        let wallet = Wallet::new(signer, TRON);
+        assert_eq!(
+            wallet.address().unwrap(),
+            TRON.address_from_pubkey(&key().public_key()).unwrap()
+        );
+        let digest = [7u8; 32];
+        assert_eq!(
+            wallet.signer.sign_prehash(&digest).await.unwrap(),
+            key().sign_prehash(&digest).await.unwrap()
+        );
+
+        let strict = CompositeSigner::new(key())
+            .with_approver(callback(Ok(()), calls.clone()))
+            .with_approver(callback(Err("not today"), calls.clone()));
+        let error = strict
+            .sign_many(&vec![digest.to_vec(); 3], SignMode::Prehash, None, 2)
+            .await;
+        assert_eq!(error.len(), 3);
+        assert!(
+            matches!(&error[0], Err(SignerError::Rejected(reason)) if reason.contains("not today"))
+        );
+        // One approval round per batch.
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
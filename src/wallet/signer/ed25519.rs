@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use ed25519_dalek::{Signer as _, SigningKey};
+
+use crate::wallet::Signer;
+use crate::wallet::chain::Network;
+use crate::wallet::scheme::Ed25519;
+use crate::wallet::signer::SignerError;
+
+/// Local software signer backed by an in-memory ed25519 key, for chains such
+/// as Solana. Signs whole messages; ed25519 has no prehashed form.
+pub struct Ed25519Signer {
+    signing_key: SigningKey,
+    network: Option<Network>,
+}
+
+impl Ed25519Signer {
+    /// Create a signer from a 32-byte ed25519 secret key (any value is valid).
+    pub fn from_bytes(secret_key: [u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(&secret_key),
+            network: None,
+        }
+    }
+
+    /// Only sign for chains on `network`.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+}
+
+#[async_trait]
+impl Signer<Ed25519> for Ed25519Signer {
+    /// The 64-byte `R || S` signature.
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        Ok(self.signing_key.sign(message).to_bytes().to_vec())
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        self.signing_key.verifying_key().to_bytes().to_vec()
+    }
+
+    fn network(&self) -> Option<Network> {
+        self.network
+    }
+}
@@ -1,12 +1,21 @@
 use async_trait::async_trait;
-use k256::ecdsa::{Signature, SigningKey, VerifyingKey, signature::DigestSigner};
+use k256::ecdsa::signature::{DigestSigner, hazmat::PrehashSigner};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::PrimeField;
+use k256::{Scalar, schnorr};
+use rand::RngCore;
 use sha2::{Digest, Sha256};
 
 use crate::wallet::Signer;
+use crate::wallet::chain::Network;
+use crate::wallet::signer::SignerError;
 
 /// Local software signer backed by an in-memory secp256k1 private key.
 pub struct LocalSigner {
     signing_key: SigningKey,
+    /// Compressed SEC1 public key, encoded once at construction.
+    public_key: [u8; 33],
+    network: Option<Network>,
 }
 
 impl LocalSigner {
@@ -18,28 +27,86 @@ impl LocalSigner {
     /// Create a signer from a secret scalar slice.
     pub fn from_slice(secret_key: &[u8]) -> Result<Self, k256::ecdsa::Error> {
         let signing_key = SigningKey::from_bytes(secret_key.into())?;
-        Ok(Self { signing_key })
+        let mut public_key = [0u8; 33];
+        public_key.copy_from_slice(
+            VerifyingKey::from(&signing_key)
+                .to_encoded_point(true)
+                .as_bytes(),
+        );
+        Ok(Self {
+            signing_key,
+            public_key,
+            network: None,
+        })
     }
 
-    /// Return the compressed public key (33 bytes, SEC1).
-    fn compressed_public_key(&self) -> VerifyingKey {
-        self.signing_key.verifying_key().to_owned()
+    /// Only sign for chains on `network`.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
     }
 }
 
 #[async_trait]
 impl Signer for LocalSigner {
-    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, ()> {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
         // Hash the message to 32 bytes; required size for secp256k1 signing.
         let digest = Sha256::new().chain_update(message);
         let signature: Signature = self.signing_key.sign_digest(digest);
         Ok(signature.to_der().as_bytes().to_vec())
     }
 
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        let signature: Signature = self
+            .signing_key
+            .sign_prehash(prehash)
+            .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+        Ok(signature.to_der().as_bytes().to_vec())
+    }
+
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        let (signature, recid) = self
+            .signing_key
+            .sign_prehash_recoverable(prehash)
+            .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+        let mut out = signature.to_bytes().to_vec();
+        out.push(recid.to_byte());
+        Ok(out)
+    }
+
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        let prehash: &[u8; 32] = prehash
+            .try_into()
+            .map_err(|_| SignerError::InvalidInput("prehash must be 32 bytes".to_string()))?;
+        let mut secret: Scalar = **self.signing_key.as_nonzero_scalar();
+        if let Some(tweak) = tweak {
+            // The tweak applies to the even-y key; the Schnorr key below
+            // negates the sum again if the tweaked key has an odd y.
+            if self.public_key[0] == 0x03 {
+                secret = -secret;
+            }
+            secret += Option::<Scalar>::from(Scalar::from_repr((*tweak).into()))
+                .ok_or_else(|| SignerError::InvalidInput("tweak out of range".to_string()))?;
+        }
+        let key = schnorr::SigningKey::from_bytes(&secret.to_bytes())
+            .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+        let mut aux_rand = [0u8; 32];
+        rand::rng().fill_bytes(&mut aux_rand);
+        let signature = key
+            .sign_prehash_with_aux_rand(prehash, &aux_rand)
+            .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+        Ok(signature.to_bytes().to_vec())
+    }
+
     fn public_key(&self) -> Vec<u8> {
-        self.compressed_public_key()
-            .to_encoded_point(true)
-            .as_bytes()
-            .to_vec()
+        self.public_key.to_vec()
+    }
+
+    fn network(&self) -> Option<Network> {
+        self.network
     }
 }
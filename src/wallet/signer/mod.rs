@@ -1,4 +1,124 @@
+pub mod composite;
+pub mod ed25519;
 pub mod local;
 pub mod mpc;
+#[cfg(feature = "network")]
+pub mod remote;
+pub mod retry;
 
+pub use composite::{ApprovalCallback, Approver, CompositeSigner, Cosigner, SignatureRequest};
+pub use ed25519::Ed25519Signer;
 pub use local::LocalSigner;
+#[cfg(feature = "network")]
+pub use remote::{RemoteSigner, RemoteSignerConfig};
+pub use retry::RetryingSigner;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::wallet::options::TravelRuleMetadata;
+
+use crate::wallet::chain::sdk::{parse_der_signature, recovery_parity};
+
+/// Why a [`Signer`](crate::wallet::Signer) produced no signature.
+#[derive(Debug, Clone, Error)]
+pub enum SignerError {
+    #[error("signing was rejected: {0}")]
+    Rejected(String),
+
+    #[error("signing device disconnected: {0}")]
+    Disconnected(String),
+
+    #[error("signer transport failed: {0}")]
+    Transport(String),
+
+    #[error("signing device busy: {0}")]
+    Busy(String),
+
+    #[error("signer timed out: {0}")]
+    Timeout(String),
+
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("watch-only signer holds no private key")]
+    WatchOnly,
+
+    #[error("signer does not support {0}")]
+    Unsupported(&'static str),
+
+    #[error("invalid signing input: {0}")]
+    InvalidInput(String),
+}
+
+impl SignerError {
+    /// Whether asking again may succeed: a busy device, a peer that did not
+    /// answer in time, a dropped connection. Rejections, bad keys and bad
+    /// input fail the same way every time.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            SignerError::Busy(_) | SignerError::Timeout(_) | SignerError::Transport(_)
+        )
+    }
+}
+
+/// Which of a [`Signer`](crate::wallet::Signer)'s methods a payload is for,
+/// as the chain requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignMode {
+    /// A message the signer hashes itself ([`sign`](crate::wallet::Signer::sign)).
+    Message,
+    /// A digest the chain hashed ([`sign_prehash`](crate::wallet::Signer::sign_prehash)).
+    Prehash,
+    /// A digest signed as `r || s || recid`
+    /// ([`sign_recoverable`](crate::wallet::Signer::sign_recoverable)).
+    Recoverable,
+    /// A Taproot sighash, signed with Schnorr by the key's BIP-86 output key
+    /// ([`sign_schnorr`](crate::wallet::Signer::sign_schnorr)).
+    Taproot,
+}
+
+/// What a signature is for, handed to the signer next to the bytes so that
+/// hardware wallets and approval apps can show it instead of an opaque hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningContext {
+    /// `Chain::id` of the chain the transaction is for.
+    pub chain: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the chain's base unit.
+    pub amount: u64,
+    /// One line for a person to confirm, e.g. `Send 1.5 on tron to T...`.
+    pub summary: String,
+    /// Compliance metadata the send was made with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<TravelRuleMetadata>,
+}
+
+/// `r || s || recid` for a DER signature over `prehash` by `pubkey`, for
+/// signers that cannot report the recovery id themselves.
+pub fn recoverable_from_der(
+    prehash: &[u8],
+    der: &[u8],
+    pubkey: &[u8],
+) -> Result<Vec<u8>, SignerError> {
+    let prehash: &[u8; 32] = prehash
+        .try_into()
+        .map_err(|_| SignerError::InvalidInput("prehash must be 32 bytes".to_string()))?;
+    let signature =
+        parse_der_signature(der).map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+    let parity = recovery_parity(prehash, &signature, pubkey)
+        .map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+    let mut out = signature.to_bytes().to_vec();
+    out.push(parity);
+    Ok(out)
+}
+
+impl fmt::Display for SigningContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary)
+    }
+}
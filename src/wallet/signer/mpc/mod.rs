@@ -1,2 +1,4 @@
+mod paillier;
+pub mod protocol;
 pub mod signer;
 pub mod transport;
@@ -0,0 +1,393 @@
+//! Paillier encryption, the additively homomorphic scheme two-party ECDSA uses
+//! to combine key shares without revealing them.
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Miller-Rabin rounds; error probability below 2^-128 for random candidates.
+const MR_ROUNDS: usize = 64;
+
+/// A well-formed modulus has no prime factor below this bound.
+const FACTOR_BOUND: u32 = 6370;
+
+/// Rounds of the modulus proof; each catches a malformed modulus with
+/// probability at least 1/2.
+const MODULUS_ROUNDS: usize = 128;
+
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaillierPublicKey {
+    pub n: BigUint,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PaillierSecretKey {
+    p: BigUint,
+    q: BigUint,
+}
+
+impl PaillierSecretKey {
+    /// Fresh key with a modulus of `bits` bits.
+    pub fn generate(bits: u64) -> Self {
+        loop {
+            let p = random_prime(bits / 2);
+            let q = random_prime(bits - bits / 2);
+            if p != q && (&p * &q).bits() == bits {
+                return Self { p, q };
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> PaillierPublicKey {
+        PaillierPublicKey {
+            n: &self.p * &self.q,
+        }
+    }
+
+    /// Non-interactive proof that the modulus is a Paillier-Blum integer:
+    /// `n = p * q` for primes `p, q = 3 (mod 4)` with `n` coprime to `phi(n)`.
+    /// Fourth roots of challenges derived from `context` show two prime
+    /// factors, `n`-th roots show the coprimality; after Canetti et al., "UC
+    /// Non-Interactive, Proactive, Threshold ECDSA with Identifiable Aborts"
+    /// (2021), figure 16.
+    pub fn prove_modulus(&self, context: &[u8]) -> ModulusProof {
+        let n = &self.p * &self.q;
+        let phi = (&self.p - 1u32) * (&self.q - 1u32);
+        let exponent = n
+            .modinv(&phi)
+            .expect("n is coprime to phi(n) for primes of equal size");
+        // A non-residue modulo exactly one prime, so Jacobi symbol -1.
+        let w = loop {
+            let w = random_below(&n);
+            if w.gcd(&n).is_one() && is_residue(&w, &self.p) != is_residue(&w, &self.q) {
+                break w;
+            }
+        };
+        let rounds = (0..MODULUS_ROUNDS)
+            .map(|i| {
+                let y = modulus_challenge(context, &n, &w, i);
+                let z = y.modpow(&exponent, &n);
+                // Exactly one of +-y, +-wy is a square modulo both primes.
+                let (negate, times_w, square) =
+                    [(false, false), (true, false), (false, true), (true, true)]
+                        .into_iter()
+                        .map(|(negate, times_w)| {
+                            (negate, times_w, adjust(&y, negate, times_w, &w, &n))
+                        })
+                        .find(|(_, _, v)| is_residue(v, &self.p) && is_residue(v, &self.q))
+                        .expect("challenge is a unit modulo n");
+                ModulusRound {
+                    x: self.fourth_root(&square),
+                    negate,
+                    times_w,
+                    z,
+                }
+            })
+            .collect();
+        ModulusProof { w, rounds }
+    }
+
+    /// The fourth root of the square `v` that is itself a square, by CRT.
+    fn fourth_root(&self, v: &BigUint) -> BigUint {
+        let root = |p: &BigUint| {
+            // For p = 3 (mod 4), v^((p+1)/4) is the square root that is a square.
+            let e = (p + 1u32) >> 2;
+            v.modpow(&(&e * &e), p)
+        };
+        let (xp, xq) = (root(&self.p), root(&self.q));
+        let p_inv = self.p.modinv(&self.q).expect("distinct primes are coprime");
+        let diff = (&xq + &self.q - &xp % &self.q) % &self.q;
+        xp + &self.p * (diff * p_inv % &self.q)
+    }
+
+    /// Plaintext of `c`, in `[0, n)`.
+    pub fn decrypt(&self, c: &BigUint) -> BigUint {
+        let n = &self.p * &self.q;
+        let nn = &n * &n;
+        let lambda = (&self.p - 1u32).lcm(&(&self.q - 1u32));
+        // With g = n + 1, L(g^lambda mod n^2) = lambda, so mu = lambda^-1 mod n.
+        let mu = lambda
+            .modinv(&n)
+            .expect("lambda is invertible modulo n for distinct primes");
+        let u = c.modpow(&lambda, &nn);
+        ((u - 1u32) / &n * mu) % n
+    }
+}
+
+impl PaillierPublicKey {
+    fn nn(&self) -> BigUint {
+        &self.n * &self.n
+    }
+
+    pub fn encrypt(&self, m: &BigUint) -> BigUint {
+        self.encrypt_with(m, &self.random_nonce())
+    }
+
+    /// Encryption of `m` with the randomness `r`, a unit modulo `n`.
+    pub fn encrypt_with(&self, m: &BigUint, r: &BigUint) -> BigUint {
+        let nn = self.nn();
+        // (1 + n)^m = 1 + m*n (mod n^2)
+        let gm = (BigUint::one() + m * &self.n) % &nn;
+        gm * r.modpow(&self.n, &nn) % nn
+    }
+
+    /// Fresh encryption randomness.
+    pub fn random_nonce(&self) -> BigUint {
+        loop {
+            let r = random_below(&self.n);
+            if !r.is_zero() && r.gcd(&self.n).is_one() {
+                return r;
+            }
+        }
+    }
+
+    /// Check a [`PaillierSecretKey::prove_modulus`] proof made with `context`,
+    /// and that `n` has no prime factor below [`FACTOR_BOUND`].
+    pub fn verify_modulus(&self, context: &[u8], proof: &ModulusProof) -> bool {
+        let n = &self.n;
+        if n.is_even() || proof.rounds.len() != MODULUS_ROUNDS || is_probable_prime(n) {
+            return false;
+        }
+        if has_small_factor(n) {
+            return false;
+        }
+        if proof.w >= *n || jacobi(&proof.w, n) != -1 {
+            return false;
+        }
+        proof.rounds.iter().enumerate().all(|(i, round)| {
+            let y = modulus_challenge(context, n, &proof.w, i);
+            round.x < *n
+                && round.z < *n
+                && !y.is_zero()
+                && round.z.modpow(n, n) == y
+                && round.x.modpow(&BigUint::from(4u32), n)
+                    == adjust(&y, round.negate, round.times_w, &proof.w, n)
+        })
+    }
+
+    /// Encryption of the sum of the plaintexts.
+    pub fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % self.nn()
+    }
+
+    /// Encryption of the plaintext times `k`.
+    pub fn mul(&self, c: &BigUint, k: &BigUint) -> BigUint {
+        c.modpow(k, &self.nn())
+    }
+}
+
+/// Roots proving a Paillier modulus well formed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModulusProof {
+    w: BigUint,
+    rounds: Vec<ModulusRound>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModulusRound {
+    /// Fourth root of `(-1)^negate * w^times_w * y`.
+    x: BigUint,
+    negate: bool,
+    times_w: bool,
+    /// `n`-th root of `y`.
+    z: BigUint,
+}
+
+/// The `index`-th challenge `y`, uniform modulo `n` up to 2^-128.
+fn modulus_challenge(context: &[u8], n: &BigUint, w: &BigUint, index: usize) -> BigUint {
+    let blocks = (n.bits() + 128).div_ceil(256);
+    let mut bytes = Vec::new();
+    for block in 0..blocks {
+        bytes.extend(
+            Sha256::new()
+                .chain_update(b"flow-wallet/paillier/modulus")
+                .chain_update(context)
+                .chain_update(n.to_bytes_be())
+                .chain_update(w.to_bytes_be())
+                .chain_update((index as u64).to_be_bytes())
+                .chain_update(block.to_be_bytes())
+                .finalize(),
+        );
+    }
+    BigUint::from_bytes_be(&bytes) % n
+}
+
+/// `(-1)^negate * w^times_w * y` modulo `n`.
+fn adjust(y: &BigUint, negate: bool, times_w: bool, w: &BigUint, n: &BigUint) -> BigUint {
+    let v = if times_w { y * w % n } else { y.clone() };
+    if negate && !v.is_zero() { n - v } else { v }
+}
+
+/// Whether `v` is a non-zero square modulo the odd prime `p` (Euler's criterion).
+fn is_residue(v: &BigUint, p: &BigUint) -> bool {
+    v.modpow(&((p - 1u32) >> 1), p).is_one()
+}
+
+/// Jacobi symbol `(a / n)` for odd `n`.
+fn jacobi(a: &BigUint, n: &BigUint) -> i8 {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        let twos = a.trailing_zeros().unwrap_or(0);
+        a >>= twos;
+        let n_mod_8 = (&n % 8u32).to_u32_digits().first().copied().unwrap_or(0);
+        if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        std::mem::swap(&mut a, &mut n);
+        if (&a % 4u32) == BigUint::from(3u32) && (&n % 4u32) == BigUint::from(3u32) {
+            result = -result;
+        }
+        a %= &n;
+    }
+    if n.is_one() { result } else { 0 }
+}
+
+fn has_small_factor(n: &BigUint) -> bool {
+    primes_below(FACTOR_BOUND).any(|p| (n % p).is_zero())
+}
+
+fn primes_below(bound: u32) -> impl Iterator<Item = u32> {
+    let mut composite = vec![false; bound as usize];
+    (2..bound).filter(move |&p| {
+        if composite[p as usize] {
+            return false;
+        }
+        for multiple in (p as usize * p as usize..bound as usize).step_by(p as usize) {
+            composite[multiple] = true;
+        }
+        true
+    })
+}
+
+/// Uniform in `[0, bound)`.
+pub(crate) fn random_below(bound: &BigUint) -> BigUint {
+    let len = bound.bits().div_ceil(8) as usize;
+    let excess = len as u64 * 8 - bound.bits();
+    loop {
+        let mut bytes = vec![0u8; len];
+        rand::fill(&mut bytes[..]);
+        bytes[0] &= 0xff >> excess;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if &candidate < bound {
+            return candidate;
+        }
+    }
+}
+
+fn random_prime(bits: u64) -> BigUint {
+    let len = bits.div_ceil(8) as usize;
+    let excess = len as u64 * 8 - bits;
+    loop {
+        let mut bytes = vec![0u8; len];
+        rand::fill(&mut bytes[..]);
+        bytes[0] &= 0xff >> excess;
+        // Top two bits set so p*q has exactly 2*bits bits; 3 (mod 4) so p*q
+        // is a Blum integer.
+        bytes[0] |= 0xc0 >> excess;
+        bytes[len - 1] |= 3;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+fn is_probable_prime(n: &BigUint) -> bool {
+    for &p in &SMALL_PRIMES {
+        if (n % p).is_zero() {
+            return *n == BigUint::from(p);
+        }
+    }
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+    let two = BigUint::from(2u32);
+    'witness: for _ in 0..MR_ROUNDS {
+        let a = random_below(&(n - 3u32)) + &two;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn homomorphic_add_and_scalar_mul() {
+        let sk = PaillierSecretKey::generate(512);
+        let pk = sk.public_key();
+        assert_eq!(pk.n.bits(), 512);
+
+        let a = pk.encrypt(&BigUint::from(1234u32));
+        let b = pk.encrypt(&BigUint::from(66u32));
+        assert_ne!(a, pk.encrypt(&BigUint::from(1234u32)), "randomized");
+        assert_eq!(sk.decrypt(&pk.add(&a, &b)), BigUint::from(1300u32));
+        assert_eq!(
+            sk.decrypt(&pk.mul(&a, &BigUint::from(3u32))),
+            BigUint::from(3702u32)
+        );
+    }
+
+    #[test]
+    fn modulus_proofs_accept_only_well_formed_moduli() {
+        let sk = PaillierSecretKey::generate(512);
+        let pk = sk.public_key();
+        let proof = sk.prove_modulus(b"ctx");
+        assert!(pk.verify_modulus(b"ctx", &proof));
+        assert!(!pk.verify_modulus(b"other", &proof), "bound to its context");
+
+        let other = PaillierSecretKey::generate(512).public_key();
+        assert!(!other.verify_modulus(b"ctx", &proof));
+
+        let mut tampered = proof.clone();
+        tampered.rounds[7].negate ^= true;
+        assert!(!pk.verify_modulus(b"ctx", &tampered));
+
+        // Trial division catches small factors before any root is checked.
+        assert!(has_small_factor(&(&pk.n * 6367u32)));
+        assert!(!has_small_factor(&pk.n));
+    }
+
+    #[test]
+    fn jacobi_matches_euler_for_primes() {
+        let p = random_prime(64);
+        for a in [1u32, 2, 3, 5, 12345, 99991] {
+            let a = BigUint::from(a);
+            let expected = if is_residue(&a, &p) { 1 } else { -1 };
+            assert_eq!(jacobi(&a, &p), expected);
+        }
+        assert_eq!(jacobi(&BigUint::from(6u32), &BigUint::from(15u32)), 0);
+        assert_eq!(jacobi(&BigUint::from(2u32), &BigUint::from(15u32)), 1);
+    }
+
+    #[test]
+    fn primes_below_sieves() {
+        assert_eq!(
+            primes_below(30).collect::<Vec<_>>(),
+            [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(primes_below(FACTOR_BOUND).count(), 830);
+    }
+}
@@ -0,0 +1,1229 @@
+//! Two-party ECDSA after Lindell, "Fast Secure Two-Party ECDSA Signing" (2017).
+//!
+//! The key is split additively, `x = x1 + x2`; neither party ever holds `x`.
+//! Party 1 (the lower [`PartyId`]) owns a Paillier key and hands party 2 an
+//! encryption of `x1` at key generation. To sign, both contribute a nonce share
+//! with a proof of knowledge, party 2 folds its shares into an encrypted partial
+//! signature, and party 1 decrypts it and checks the result before releasing it.
+//!
+//! Every step that can fail (a bad proof, an opened commitment that does not
+//! match, a signature that does not verify, a silent peer) aborts the session
+//! and tells the peer why.
+//!
+//! [`refresh`] rotates both shares by a jointly chosen offset, `x1 + d` and
+//! `x2 - d`, under a fresh Paillier key: the public key stays, and a share
+//! stolen before the refresh is useless next to one from after it.
+//! [`hand_off`] passes a share to a party taking over from its holder, who
+//! then refreshes with the remaining co-signer.
+//!
+//! Party 2 takes party 1's Paillier key and encrypted share only with proofs
+//! that the modulus is a Paillier-Blum integer without small factors and that
+//! the share decrypts to the discrete log of `X1`: a modulus of party 1's
+//! choosing or a ciphertext of something else would otherwise let it read
+//! party 2's share out of the partial signatures.
+
+use std::time::Duration;
+
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use k256::ecdsa::{Signature, VerifyingKey};
+use k256::elliptic_curve::PrimeField;
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::point::AffineCoordinates;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{FieldBytes, ProjectivePoint, PublicKey, Scalar, U256};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::paillier::{ModulusProof, PaillierPublicKey, PaillierSecretKey, random_below};
+use super::transport::{MpcTransport, PartyId, TransportError};
+use crate::wallet::crypto::memory::SecureBuffer;
+
+/// Smallest Paillier modulus that fits party 2's masked partial signature
+/// (below `q^3 + q^2 + q`, about 2^769) without wrapping.
+pub const MIN_PAILLIER_BITS: u64 = 1024;
+
+/// Rounds of the encrypted share proof; a ciphertext of anything but the
+/// share passes each with probability at most 1/2.
+const SHARE_PROOF_ROUNDS: usize = 128;
+
+/// Order of the secp256k1 group.
+const CURVE_ORDER: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+#[derive(Debug, Error)]
+pub enum MpcError {
+    #[error("transport: {0}")]
+    Transport(#[from] TransportError),
+    #[error("no message from party {0} before the timeout")]
+    Timeout(PartyId),
+    #[error("protocol violation: {0}")]
+    Protocol(String),
+    #[error("party {party} aborted: {reason}")]
+    Aborted { party: PartyId, reason: String },
+    #[error("invalid key share: {0}")]
+    InvalidShare(String),
+}
+
+/// Options for [`keygen`].
+#[derive(Debug, Clone, Copy)]
+pub struct KeygenOptions {
+    /// Paillier modulus size for party 1, at least [`MIN_PAILLIER_BITS`].
+    pub paillier_bits: u64,
+    /// How long to wait for each message from the peer.
+    pub timeout: Duration,
+}
+
+impl Default for KeygenOptions {
+    fn default() -> Self {
+        Self {
+            paillier_bits: 2048,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Secret half of a two-party key, kept encoded inside a [`SecureBuffer`].
+#[derive(Serialize, Deserialize)]
+pub(crate) enum ShareSecret {
+    First {
+        x: [u8; 32],
+        paillier: PaillierSecretKey,
+    },
+    Second {
+        x: [u8; 32],
+        paillier: PaillierPublicKey,
+        /// Paillier encryption of party 1's share.
+        encrypted_share: BigUint,
+    },
+    /// Received through [`hand_off`]; must be [`refresh`]ed before it signs.
+    Handed { x: [u8; 32] },
+}
+
+impl ShareSecret {
+    fn x(&self) -> &[u8; 32] {
+        match self {
+            ShareSecret::First { x, .. }
+            | ShareSecret::Second { x, .. }
+            | ShareSecret::Handed { x } => x,
+        }
+    }
+
+    pub(crate) fn encode(&self) -> SecureBuffer {
+        let mut out = Vec::new();
+        ciborium::into_writer(self, &mut out).expect("share encodes to an in-memory buffer");
+        SecureBuffer::new(out)
+    }
+
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, MpcError> {
+        ciborium::from_reader(data).map_err(|e| MpcError::InvalidShare(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DlogProof {
+    commitment: Vec<u8>,
+    response: [u8; 32],
+}
+
+/// Proof that a Paillier ciphertext holds the discrete log of a point: per
+/// round, an encryption `a` of a mask and the mask times `G`, opened either
+/// as is or plus the share depending on one challenge bit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShareProof {
+    rounds: Vec<ShareRound>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShareRound {
+    ciphertext: BigUint,
+    point: Vec<u8>,
+    response: BigUint,
+    nonce: BigUint,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Message {
+    KeygenCommit {
+        commitment: [u8; 32],
+    },
+    KeygenShare {
+        point: Vec<u8>,
+        proof: DlogProof,
+    },
+    KeygenOpen {
+        point: Vec<u8>,
+        proof: DlogProof,
+        salt: [u8; 32],
+        paillier: PaillierPublicKey,
+        encrypted_share: BigUint,
+        modulus_proof: ModulusProof,
+        share_proof: ShareProof,
+    },
+    NonceCommit {
+        commitment: [u8; 32],
+    },
+    NonceShare {
+        point: Vec<u8>,
+        proof: DlogProof,
+    },
+    NonceOpen {
+        point: Vec<u8>,
+        proof: DlogProof,
+        salt: [u8; 32],
+    },
+    PartialSignature {
+        ciphertext: BigUint,
+    },
+    Signature {
+        der: Vec<u8>,
+    },
+    RefreshCommit {
+        commitment: [u8; 32],
+    },
+    RefreshOffset {
+        offset: [u8; 32],
+    },
+    RefreshOpen {
+        offset: [u8; 32],
+        salt: [u8; 32],
+        point: Vec<u8>,
+        proof: DlogProof,
+        paillier: PaillierPublicKey,
+        encrypted_share: BigUint,
+        modulus_proof: ModulusProof,
+        share_proof: ShareProof,
+    },
+    RefreshDone {
+        point: Vec<u8>,
+        proof: DlogProof,
+    },
+    HandOff {
+        x: [u8; 32],
+        public_key: Vec<u8>,
+        peer: PartyId,
+        #[serde(default)]
+        account: Option<String>,
+    },
+    Abort {
+        reason: String,
+    },
+}
+
+/// One protocol run between this party and `peer`.
+pub(crate) struct Session<'a> {
+    pub transport: &'a dyn MpcTransport,
+    pub peer: PartyId,
+    pub timeout: Duration,
+}
+
+impl Session<'_> {
+    fn me(&self) -> PartyId {
+        self.transport.my_party_id()
+    }
+
+    fn is_first(&self) -> bool {
+        self.me() < self.peer
+    }
+
+    async fn send(&self, message: &Message) -> Result<(), MpcError> {
+        let mut data = Vec::new();
+        ciborium::into_writer(message, &mut data).expect("message encodes to an in-memory buffer");
+        Ok(self.transport.send(self.peer, &data).await?)
+    }
+
+    async fn receive(&self) -> Result<Message, MpcError> {
+        let (from, data) = tokio::time::timeout(self.timeout, self.transport.receive())
+            .await
+            .map_err(|_| MpcError::Timeout(self.peer))??;
+        if from != self.peer {
+            return Err(MpcError::Protocol(format!(
+                "message from party {}, expected {}",
+                from, self.peer
+            )));
+        }
+        match ciborium::from_reader(&data[..]) {
+            Ok(Message::Abort { reason }) => Err(MpcError::Aborted {
+                party: from,
+                reason,
+            }),
+            Ok(message) => Ok(message),
+            Err(e) => Err(MpcError::Protocol(format!("undecodable message: {}", e))),
+        }
+    }
+
+    /// Tell the peer why the session ends here, then fail with `error`.
+    async fn abort<T>(&self, error: MpcError) -> Result<T, MpcError> {
+        if !matches!(error, MpcError::Aborted { .. }) {
+            let _ = self
+                .send(&Message::Abort {
+                    reason: error.to_string(),
+                })
+                .await;
+        }
+        Err(error)
+    }
+
+    /// Run `step`, aborting the session if it fails.
+    async fn run<T>(&self, step: impl Future<Output = Result<T, MpcError>>) -> Result<T, MpcError> {
+        match step.await {
+            Ok(value) => Ok(value),
+            Err(error) => self.abort(error).await,
+        }
+    }
+}
+
+/// Run distributed key generation with `peer`; both parties call this at the
+/// same time and each gets its own share of the same public key.
+pub async fn keygen(
+    transport: &dyn MpcTransport,
+    peer: PartyId,
+    options: &KeygenOptions,
+) -> Result<super::signer::KeyShare, MpcError> {
+    if options.paillier_bits < MIN_PAILLIER_BITS {
+        return Err(MpcError::InvalidShare(format!(
+            "Paillier modulus of {} bits is below the minimum of {}",
+            options.paillier_bits, MIN_PAILLIER_BITS
+        )));
+    }
+    let session = Session {
+        transport,
+        peer,
+        timeout: options.timeout,
+    };
+    let (secret, public) = if session.is_first() {
+        // Primes first: the peer's timeout only starts once we have spoken.
+        let paillier = PaillierSecretKey::generate(options.paillier_bits);
+        session.run(keygen_first(&session, paillier)).await?
+    } else {
+        session.run(keygen_second(&session)).await?
+    };
+    Ok(super::signer::KeyShare {
+        party: session.me(),
+        peer,
+        public_key: encode_point(&public),
+        share_data: secret.encode(),
+        account: None,
+    })
+}
+
+async fn keygen_first(
+    session: &Session<'_>,
+    paillier: PaillierSecretKey,
+) -> Result<(ShareSecret, ProjectivePoint), MpcError> {
+    let context = keygen_context(session.me());
+    let x1 = random_scalar();
+    let x1_point = ProjectivePoint::GENERATOR * x1;
+    let proof = prove_dlog(&context, &x1, &x1_point);
+    let salt: [u8; 32] = rand::random();
+    session
+        .send(&Message::KeygenCommit {
+            commitment: commit(&x1_point, &proof, &salt),
+        })
+        .await?;
+
+    let Message::KeygenShare {
+        point,
+        proof: peer_proof,
+    } = session.receive().await?
+    else {
+        return Err(unexpected("KeygenShare"));
+    };
+    let x2_point = decode_point(&point)?;
+    verify_dlog(&keygen_context(session.peer), &x2_point, &peer_proof)?;
+
+    let public_key = paillier.public_key();
+    let nonce = public_key.random_nonce();
+    let encrypted_share = public_key.encrypt_with(&scalar_to_biguint(&x1), &nonce);
+    session
+        .send(&Message::KeygenOpen {
+            point: encode_point(&x1_point),
+            proof,
+            salt,
+            modulus_proof: paillier.prove_modulus(&context),
+            share_proof: prove_share(
+                &context,
+                &public_key,
+                &x1,
+                &x1_point,
+                &encrypted_share,
+                &nonce,
+            ),
+            paillier: public_key,
+            encrypted_share,
+        })
+        .await?;
+
+    let secret = ShareSecret::First {
+        x: x1.to_bytes().into(),
+        paillier,
+    };
+    Ok((secret, x1_point + x2_point))
+}
+
+async fn keygen_second(session: &Session<'_>) -> Result<(ShareSecret, ProjectivePoint), MpcError> {
+    let Message::KeygenCommit { commitment } = session.receive().await? else {
+        return Err(unexpected("KeygenCommit"));
+    };
+    let x2 = random_scalar();
+    let x2_point = ProjectivePoint::GENERATOR * x2;
+    session
+        .send(&Message::KeygenShare {
+            point: encode_point(&x2_point),
+            proof: prove_dlog(&keygen_context(session.me()), &x2, &x2_point),
+        })
+        .await?;
+
+    let Message::KeygenOpen {
+        point,
+        proof,
+        salt,
+        paillier,
+        encrypted_share,
+        modulus_proof,
+        share_proof,
+    } = session.receive().await?
+    else {
+        return Err(unexpected("KeygenOpen"));
+    };
+    let x1_point = decode_point(&point)?;
+    if commit(&x1_point, &proof, &salt) != commitment {
+        return Err(MpcError::Protocol(
+            "key share does not match its commitment".into(),
+        ));
+    }
+    let context = keygen_context(session.peer);
+    verify_dlog(&context, &x1_point, &proof)?;
+    check_paillier(
+        &context,
+        &x1_point,
+        &paillier,
+        &encrypted_share,
+        &modulus_proof,
+        &share_proof,
+    )?;
+
+    let secret = ShareSecret::Second {
+        x: x2.to_bytes().into(),
+        paillier,
+        encrypted_share,
+    };
+    Ok((secret, x1_point + x2_point))
+}
+
+/// Accept party 1's Paillier key and its encryption of the share behind
+/// `x1_point` only with proofs for both, made under `context`.
+fn check_paillier(
+    context: &[u8],
+    x1_point: &ProjectivePoint,
+    paillier: &PaillierPublicKey,
+    encrypted_share: &BigUint,
+    modulus_proof: &ModulusProof,
+    share_proof: &ShareProof,
+) -> Result<(), MpcError> {
+    if paillier.n.bits() < MIN_PAILLIER_BITS {
+        return Err(MpcError::Protocol(format!(
+            "peer's Paillier modulus has only {} bits",
+            paillier.n.bits()
+        )));
+    }
+    if *encrypted_share >= &paillier.n * &paillier.n {
+        return Err(MpcError::Protocol("encrypted share out of range".into()));
+    }
+    if !paillier.verify_modulus(context, modulus_proof) {
+        return Err(MpcError::Protocol(
+            "peer's Paillier modulus is not well formed".into(),
+        ));
+    }
+    verify_share(context, paillier, x1_point, encrypted_share, share_proof)
+}
+
+/// Rotate `share` with `peer`, who runs this at the same time with the other
+/// share of the same key: the peer `share` was made with, or a party that
+/// took over from it through [`hand_off`]. Returns this party's new share;
+/// keep the old one until the new pair has signed, as a session cut short
+/// can leave only one side refreshed.
+pub async fn refresh(
+    transport: &dyn MpcTransport,
+    share: &super::signer::KeyShare,
+    peer: PartyId,
+    options: &KeygenOptions,
+) -> Result<super::signer::KeyShare, MpcError> {
+    if options.paillier_bits < MIN_PAILLIER_BITS {
+        return Err(MpcError::InvalidShare(format!(
+            "Paillier modulus of {} bits is below the minimum of {}",
+            options.paillier_bits, MIN_PAILLIER_BITS
+        )));
+    }
+    if transport.my_party_id() != share.party || peer == share.party {
+        return Err(MpcError::InvalidShare(format!(
+            "share of party {} cannot be refreshed by party {} with party {}",
+            share.party,
+            transport.my_party_id(),
+            peer
+        )));
+    }
+    let x = decode_scalar(ShareSecret::decode(&share.share_data)?.x())?;
+    let public_key = decode_point(&share.public_key)?;
+    let session = Session {
+        transport,
+        peer,
+        timeout: options.timeout,
+    };
+    // Roles follow the party ids, so a new peer may swap them.
+    let secret = if session.is_first() {
+        let paillier = PaillierSecretKey::generate(options.paillier_bits);
+        session
+            .run(refresh_first(&session, &x, &public_key, paillier))
+            .await?
+    } else {
+        session
+            .run(refresh_second(&session, &x, &public_key))
+            .await?
+    };
+    Ok(super::signer::KeyShare {
+        party: session.me(),
+        peer,
+        public_key: share.public_key.clone(),
+        share_data: secret.encode(),
+        account: share.account.clone(),
+    })
+}
+
+async fn refresh_first(
+    session: &Session<'_>,
+    x: &Scalar,
+    public_key: &ProjectivePoint,
+    paillier: PaillierSecretKey,
+) -> Result<ShareSecret, MpcError> {
+    // Commit to our half of the offset before seeing the peer's, so neither
+    // side picks the offset alone.
+    let own_offset = random_scalar();
+    let salt: [u8; 32] = rand::random();
+    session
+        .send(&Message::RefreshCommit {
+            commitment: commit_offset(&own_offset, &salt),
+        })
+        .await?;
+    let Message::RefreshOffset { offset } = session.receive().await? else {
+        return Err(unexpected("RefreshOffset"));
+    };
+
+    let x1 = *x + own_offset + decode_scalar(&offset)?;
+    let x1_point = ProjectivePoint::GENERATOR * x1;
+    let context = refresh_context(session.me());
+    let paillier_public = paillier.public_key();
+    let nonce = paillier_public.random_nonce();
+    let encrypted_share = paillier_public.encrypt_with(&scalar_to_biguint(&x1), &nonce);
+    session
+        .send(&Message::RefreshOpen {
+            offset: own_offset.to_bytes().into(),
+            salt,
+            point: encode_point(&x1_point),
+            proof: prove_dlog(&context, &x1, &x1_point),
+            modulus_proof: paillier.prove_modulus(&context),
+            share_proof: prove_share(
+                &context,
+                &paillier_public,
+                &x1,
+                &x1_point,
+                &encrypted_share,
+                &nonce,
+            ),
+            paillier: paillier_public,
+            encrypted_share,
+        })
+        .await?;
+
+    let Message::RefreshDone { point, proof } = session.receive().await? else {
+        return Err(unexpected("RefreshDone"));
+    };
+    let x2_point = decode_point(&point)?;
+    verify_dlog(&refresh_context(session.peer), &x2_point, &proof)?;
+    if x1_point + x2_point != *public_key {
+        return Err(MpcError::Protocol(
+            "refreshed shares do not add up to the key".into(),
+        ));
+    }
+    Ok(ShareSecret::First {
+        x: x1.to_bytes().into(),
+        paillier,
+    })
+}
+
+async fn refresh_second(
+    session: &Session<'_>,
+    x: &Scalar,
+    public_key: &ProjectivePoint,
+) -> Result<ShareSecret, MpcError> {
+    let Message::RefreshCommit { commitment } = session.receive().await? else {
+        return Err(unexpected("RefreshCommit"));
+    };
+    let own_offset = random_scalar();
+    session
+        .send(&Message::RefreshOffset {
+            offset: own_offset.to_bytes().into(),
+        })
+        .await?;
+
+    let Message::RefreshOpen {
+        offset,
+        salt,
+        point,
+        proof,
+        paillier,
+        encrypted_share,
+        modulus_proof,
+        share_proof,
+    } = session.receive().await?
+    else {
+        return Err(unexpected("RefreshOpen"));
+    };
+    let peer_offset = decode_scalar(&offset)?;
+    if commit_offset(&peer_offset, &salt) != commitment {
+        return Err(MpcError::Protocol(
+            "offset does not match its commitment".into(),
+        ));
+    }
+    let x2 = *x - (peer_offset + own_offset);
+    let x2_point = ProjectivePoint::GENERATOR * x2;
+    let x1_point = decode_point(&point)?;
+    let context = refresh_context(session.peer);
+    verify_dlog(&context, &x1_point, &proof)?;
+    if x1_point + x2_point != *public_key {
+        return Err(MpcError::Protocol(
+            "refreshed shares do not add up to the key".into(),
+        ));
+    }
+    check_paillier(
+        &context,
+        &x1_point,
+        &paillier,
+        &encrypted_share,
+        &modulus_proof,
+        &share_proof,
+    )?;
+
+    session
+        .send(&Message::RefreshDone {
+            point: encode_point(&x2_point),
+            proof: prove_dlog(&refresh_context(session.me()), &x2, &x2_point),
+        })
+        .await?;
+    Ok(ShareSecret::Second {
+        x: x2.to_bytes().into(),
+        paillier,
+        encrypted_share,
+    })
+}
+
+/// Send `share` to `to`, who takes this party's place next to its peer and
+/// picks it up with [`receive_hand_off`]. The share travels as is, so the
+/// transport must be confidential (in-process, or TLS with `mpc-tls`); the
+/// refresh the new holder runs next makes the copy left here worthless.
+pub async fn hand_off(
+    transport: &dyn MpcTransport,
+    share: &super::signer::KeyShare,
+    to: PartyId,
+) -> Result<(), MpcError> {
+    if to == share.peer {
+        return Err(MpcError::InvalidShare(
+            "cannot hand a share to the party holding the other one".into(),
+        ));
+    }
+    let secret = ShareSecret::decode(&share.share_data)?;
+    let session = Session {
+        transport,
+        peer: to,
+        timeout: Duration::ZERO,
+    };
+    session
+        .send(&Message::HandOff {
+            x: *secret.x(),
+            public_key: share.public_key.clone(),
+            peer: share.peer,
+            account: share.account.clone(),
+        })
+        .await
+}
+
+/// Take over the share `from` hands over with [`hand_off`]. It cannot sign
+/// until [`refresh`]ed with the peer it names.
+pub async fn receive_hand_off(
+    transport: &dyn MpcTransport,
+    from: PartyId,
+    timeout: Duration,
+) -> Result<super::signer::KeyShare, MpcError> {
+    let session = Session {
+        transport,
+        peer: from,
+        timeout,
+    };
+    let Message::HandOff {
+        x,
+        public_key,
+        peer,
+        account,
+    } = session.receive().await?
+    else {
+        return Err(unexpected("HandOff"));
+    };
+    decode_scalar(&x)?;
+    decode_point(&public_key)?;
+    if peer == session.me() {
+        return Err(MpcError::Protocol(
+            "handed a share whose peer is this party".into(),
+        ));
+    }
+    Ok(super::signer::KeyShare {
+        party: session.me(),
+        peer,
+        public_key,
+        share_data: ShareSecret::Handed { x }.encode(),
+        account,
+    })
+}
+
+/// `share` of the key `public_key + offset·G`, as BIP-32 public derivation
+/// makes children: party 1 adds `offset` to its share, party 2 adds it to its
+/// encryption of party 1's, so the parties stay in step without a session.
+pub(crate) fn tweak_share(
+    share: &super::signer::KeyShare,
+    offset: &Scalar,
+) -> Result<super::signer::KeyShare, MpcError> {
+    let public_key = decode_point(&share.public_key)? + ProjectivePoint::GENERATOR * offset;
+    let secret = match ShareSecret::decode(&share.share_data)? {
+        ShareSecret::First { x, paillier } => ShareSecret::First {
+            x: (decode_scalar(&x)? + offset).to_bytes().into(),
+            paillier,
+        },
+        ShareSecret::Second {
+            x,
+            paillier,
+            encrypted_share,
+        } => {
+            let encrypted_share = paillier.add(
+                &encrypted_share,
+                &paillier.encrypt(&scalar_to_biguint(offset)),
+            );
+            ShareSecret::Second {
+                x,
+                paillier,
+                encrypted_share,
+            }
+        }
+        ShareSecret::Handed { .. } => {
+            return Err(MpcError::InvalidShare(
+                "handed-off share must be refreshed before deriving from it".into(),
+            ));
+        }
+    };
+    Ok(super::signer::KeyShare {
+        party: share.party,
+        peer: share.peer,
+        public_key: encode_point(&public_key),
+        share_data: secret.encode(),
+        account: share.account.clone(),
+    })
+}
+
+/// Jointly sign `prehash` under `public_key`; the peer must be signing the
+/// same prehash with its share of the same key.
+pub(crate) async fn sign(
+    session: &Session<'_>,
+    secret: &ShareSecret,
+    public_key: &[u8],
+    prehash: &[u8; 32],
+) -> Result<Signature, MpcError> {
+    let verifying_key = VerifyingKey::from_sec1_bytes(public_key)
+        .map_err(|e| MpcError::InvalidShare(e.to_string()))?;
+    let step = async {
+        match secret {
+            ShareSecret::First { paillier, .. } if session.is_first() => {
+                sign_first(session, paillier, &verifying_key, prehash).await
+            }
+            ShareSecret::Second {
+                x,
+                paillier,
+                encrypted_share,
+            } if !session.is_first() => {
+                let x2 = decode_scalar(x)?;
+                sign_second(
+                    session,
+                    &x2,
+                    paillier,
+                    encrypted_share,
+                    &verifying_key,
+                    prehash,
+                )
+                .await
+            }
+            ShareSecret::Handed { .. } => Err(MpcError::InvalidShare(
+                "handed-off share must be refreshed before it signs".into(),
+            )),
+            _ => Err(MpcError::InvalidShare(
+                "share was generated for the other role".into(),
+            )),
+        }
+    };
+    session.run(step).await
+}
+
+async fn sign_first(
+    session: &Session<'_>,
+    paillier: &PaillierSecretKey,
+    verifying_key: &VerifyingKey,
+    prehash: &[u8; 32],
+) -> Result<Signature, MpcError> {
+    let context = sign_context(session.me(), prehash);
+    let k1 = random_scalar();
+    let r1 = ProjectivePoint::GENERATOR * k1;
+    let proof = prove_dlog(&context, &k1, &r1);
+    let salt: [u8; 32] = rand::random();
+    session
+        .send(&Message::NonceCommit {
+            commitment: commit(&r1, &proof, &salt),
+        })
+        .await?;
+
+    let Message::NonceShare {
+        point,
+        proof: peer_proof,
+    } = session.receive().await?
+    else {
+        return Err(unexpected("NonceShare"));
+    };
+    let r2 = decode_point(&point)?;
+    // The peer's proof is bound to its prehash, so a co-signer asked to sign
+    // something else is caught here.
+    verify_dlog(&sign_context(session.peer, prehash), &r2, &peer_proof)?;
+    session
+        .send(&Message::NonceOpen {
+            point: encode_point(&r1),
+            proof,
+            salt,
+        })
+        .await?;
+
+    let Message::PartialSignature { ciphertext } = session.receive().await? else {
+        return Err(unexpected("PartialSignature"));
+    };
+    let r = x_coordinate(&(r2 * k1));
+    let s_partial = biguint_to_scalar(&paillier.decrypt(&ciphertext));
+    let k1_inv = Option::<Scalar>::from(k1.invert()).expect("nonce is non-zero");
+    let s = k1_inv * s_partial;
+    let signature = Signature::from_scalars(r.to_bytes(), s.to_bytes())
+        .map_err(|_| MpcError::Protocol("partial signature produced a zero scalar".into()))?;
+    let signature = signature.normalize_s().unwrap_or(signature);
+    verifying_key
+        .verify_prehash(prehash, &signature)
+        .map_err(|_| MpcError::Protocol("partial signature does not verify".into()))?;
+
+    session
+        .send(&Message::Signature {
+            der: signature.to_der().as_bytes().to_vec(),
+        })
+        .await?;
+    Ok(signature)
+}
+
+async fn sign_second(
+    session: &Session<'_>,
+    x2: &Scalar,
+    paillier: &PaillierPublicKey,
+    encrypted_share: &BigUint,
+    verifying_key: &VerifyingKey,
+    prehash: &[u8; 32],
+) -> Result<Signature, MpcError> {
+    let Message::NonceCommit { commitment } = session.receive().await? else {
+        return Err(unexpected("NonceCommit"));
+    };
+    let k2 = random_scalar();
+    let r2 = ProjectivePoint::GENERATOR * k2;
+    session
+        .send(&Message::NonceShare {
+            point: encode_point(&r2),
+            proof: prove_dlog(&sign_context(session.me(), prehash), &k2, &r2),
+        })
+        .await?;
+
+    let Message::NonceOpen { point, proof, salt } = session.receive().await? else {
+        return Err(unexpected("NonceOpen"));
+    };
+    let r1 = decode_point(&point)?;
+    if commit(&r1, &proof, &salt) != commitment {
+        return Err(MpcError::Protocol(
+            "nonce does not match its commitment".into(),
+        ));
+    }
+    verify_dlog(&sign_context(session.peer, prehash), &r1, &proof)?;
+
+    // c3 = Enc(rho*q + k2^-1*(m + r*x2)) + Enc(x1) * k2^-1*r, so party 1 decrypts
+    // k2^-1*(m + r*x) mod q; rho hides anything else about the sum.
+    let r = x_coordinate(&(r1 * k2));
+    let m = <Scalar as Reduce<U256>>::reduce_bytes(&FieldBytes::from(*prehash));
+    let k2_inv = Option::<Scalar>::from(k2.invert()).expect("nonce is non-zero");
+    let q = curve_order();
+    let rho = random_below(&(&q * &q));
+    let masked = rho * &q + scalar_to_biguint(&(k2_inv * (m + r * x2)));
+    let c1 = paillier.encrypt(&masked);
+    let c2 = paillier.mul(encrypted_share, &scalar_to_biguint(&(k2_inv * r)));
+    session
+        .send(&Message::PartialSignature {
+            ciphertext: paillier.add(&c1, &c2),
+        })
+        .await?;
+
+    let Message::Signature { der } = session.receive().await? else {
+        return Err(unexpected("Signature"));
+    };
+    let signature = Signature::from_der(&der)
+        .map_err(|e| MpcError::Protocol(format!("invalid signature encoding: {}", e)))?;
+    verifying_key
+        .verify_prehash(prehash, &signature)
+        .map_err(|_| MpcError::Protocol("peer returned a signature that does not verify".into()))?;
+    Ok(signature)
+}
+
+fn unexpected(expected: &str) -> MpcError {
+    MpcError::Protocol(format!("expected {} message", expected))
+}
+
+fn keygen_context(prover: PartyId) -> Vec<u8> {
+    let mut context = b"flow-wallet/2p-ecdsa/keygen".to_vec();
+    context.extend_from_slice(&prover.to_be_bytes());
+    context
+}
+
+fn refresh_context(prover: PartyId) -> Vec<u8> {
+    let mut context = b"flow-wallet/2p-ecdsa/refresh".to_vec();
+    context.extend_from_slice(&prover.to_be_bytes());
+    context
+}
+
+fn sign_context(prover: PartyId, prehash: &[u8; 32]) -> Vec<u8> {
+    let mut context = b"flow-wallet/2p-ecdsa/sign".to_vec();
+    context.extend_from_slice(&prover.to_be_bytes());
+    context.extend_from_slice(prehash);
+    context
+}
+
+/// Schnorr proof of knowledge of `secret` with `point = secret * G`.
+fn prove_dlog(context: &[u8], secret: &Scalar, point: &ProjectivePoint) -> DlogProof {
+    let k = random_scalar();
+    let commitment = ProjectivePoint::GENERATOR * k;
+    let challenge = dlog_challenge(context, point, &commitment);
+    DlogProof {
+        commitment: encode_point(&commitment),
+        response: (k + challenge * secret).to_bytes().into(),
+    }
+}
+
+fn verify_dlog(context: &[u8], point: &ProjectivePoint, proof: &DlogProof) -> Result<(), MpcError> {
+    let commitment = decode_point(&proof.commitment)?;
+    let response = decode_scalar(&proof.response)?;
+    let challenge = dlog_challenge(context, point, &commitment);
+    if ProjectivePoint::GENERATOR * response == commitment + *point * challenge {
+        Ok(())
+    } else {
+        Err(MpcError::Protocol(
+            "proof of knowledge does not verify".into(),
+        ))
+    }
+}
+
+fn dlog_challenge(context: &[u8], point: &ProjectivePoint, commitment: &ProjectivePoint) -> Scalar {
+    let digest = Sha256::new()
+        .chain_update(context)
+        .chain_update(encode_point(point))
+        .chain_update(encode_point(commitment))
+        .finalize();
+    <Scalar as Reduce<U256>>::reduce_bytes(&digest)
+}
+
+/// Proof that `ciphertext`, made with `nonce`, encrypts `secret` with
+/// `point = secret * G`. Challenges are single bits so that the plaintext a
+/// cheating prover could be extracted for is the share itself, not a
+/// fraction of it modulo `n`.
+fn prove_share(
+    context: &[u8],
+    paillier: &PaillierPublicKey,
+    secret: &Scalar,
+    point: &ProjectivePoint,
+    ciphertext: &BigUint,
+    nonce: &BigUint,
+) -> ShareProof {
+    // Masks 128 bits wider than the curve order hide the share statistically.
+    let mask_bound = curve_order() << 128;
+    let masks: Vec<(BigUint, BigUint)> = (0..SHARE_PROOF_ROUNDS)
+        .map(|_| (random_below(&mask_bound), paillier.random_nonce()))
+        .collect();
+    let commitments: Vec<(BigUint, Vec<u8>)> = masks
+        .iter()
+        .map(|(mask, mask_nonce)| {
+            let mask_point = ProjectivePoint::GENERATOR * biguint_to_scalar(mask);
+            (
+                paillier.encrypt_with(mask, mask_nonce),
+                encode_point(&mask_point),
+            )
+        })
+        .collect();
+    let challenge = share_challenge(context, paillier, point, ciphertext, &commitments);
+    let secret = scalar_to_biguint(secret);
+    let rounds = masks
+        .into_iter()
+        .zip(commitments)
+        .enumerate()
+        .map(|(i, ((mask, mask_nonce), (ciphertext, point)))| {
+            let (response, nonce) = if challenge_bit(&challenge, i) {
+                (mask + &secret, mask_nonce * nonce % &paillier.n)
+            } else {
+                (mask, mask_nonce)
+            };
+            ShareRound {
+                ciphertext,
+                point,
+                response,
+                nonce,
+            }
+        })
+        .collect();
+    ShareProof { rounds }
+}
+
+fn verify_share(
+    context: &[u8],
+    paillier: &PaillierPublicKey,
+    point: &ProjectivePoint,
+    ciphertext: &BigUint,
+    proof: &ShareProof,
+) -> Result<(), MpcError> {
+    let invalid = || MpcError::Protocol("encrypted share does not match the key share".into());
+    if proof.rounds.len() != SHARE_PROOF_ROUNDS {
+        return Err(invalid());
+    }
+    let commitments: Vec<(BigUint, Vec<u8>)> = proof
+        .rounds
+        .iter()
+        .map(|round| (round.ciphertext.clone(), round.point.clone()))
+        .collect();
+    let challenge = share_challenge(context, paillier, point, ciphertext, &commitments);
+    let nn = &paillier.n * &paillier.n;
+    let response_bound = curve_order() << 129;
+    for (i, round) in proof.rounds.iter().enumerate() {
+        let bit = challenge_bit(&challenge, i);
+        if round.response >= response_bound
+            || round.nonce.is_zero()
+            || round.nonce >= paillier.n
+            || round.ciphertext >= nn
+        {
+            return Err(invalid());
+        }
+        let mask_point = decode_point(&round.point)?;
+        let (expected_ciphertext, expected_point) = if bit {
+            (&round.ciphertext * ciphertext % &nn, mask_point + point)
+        } else {
+            (round.ciphertext.clone(), mask_point)
+        };
+        if paillier.encrypt_with(&round.response, &round.nonce) != expected_ciphertext
+            || ProjectivePoint::GENERATOR * biguint_to_scalar(&round.response) != expected_point
+        {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+fn share_challenge(
+    context: &[u8],
+    paillier: &PaillierPublicKey,
+    point: &ProjectivePoint,
+    ciphertext: &BigUint,
+    commitments: &[(BigUint, Vec<u8>)],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new()
+        .chain_update(b"flow-wallet/2p-ecdsa/share")
+        .chain_update(context)
+        .chain_update(paillier.n.to_bytes_be())
+        .chain_update(encode_point(point))
+        .chain_update(ciphertext.to_bytes_be());
+    for (ciphertext, point) in commitments {
+        let bytes = ciphertext.to_bytes_be();
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+        hasher.update(point);
+    }
+    hasher.finalize().into()
+}
+
+fn challenge_bit(challenge: &[u8; 32], index: usize) -> bool {
+    challenge[index / 8] >> (index % 8) & 1 == 1
+}
+
+/// Hiding commitment to a point and its proof.
+fn commit(point: &ProjectivePoint, proof: &DlogProof, salt: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"flow-wallet/2p-ecdsa/commit")
+        .chain_update(encode_point(point))
+        .chain_update(&proof.commitment)
+        .chain_update(proof.response)
+        .chain_update(salt)
+        .finalize()
+        .into()
+}
+
+/// Hiding commitment to one party's half of a refresh offset.
+fn commit_offset(offset: &Scalar, salt: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"flow-wallet/2p-ecdsa/refresh-commit")
+        .chain_update(offset.to_bytes())
+        .chain_update(salt)
+        .finalize()
+        .into()
+}
+
+fn random_scalar() -> Scalar {
+    loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Some(scalar) = Option::<Scalar>::from(Scalar::from_repr(bytes.into()))
+            && scalar != Scalar::ZERO
+        {
+            return scalar;
+        }
+    }
+}
+
+fn decode_scalar(bytes: &[u8; 32]) -> Result<Scalar, MpcError> {
+    Option::from(Scalar::from_repr((*bytes).into()))
+        .ok_or_else(|| MpcError::Protocol("scalar out of range".into()))
+}
+
+fn encode_point(point: &ProjectivePoint) -> Vec<u8> {
+    point.to_affine().to_encoded_point(true).as_bytes().to_vec()
+}
+
+fn decode_point(bytes: &[u8]) -> Result<ProjectivePoint, MpcError> {
+    // Rejects the identity, which would let a party cancel the other's share.
+    PublicKey::from_sec1_bytes(bytes)
+        .map(|key| key.to_projective())
+        .map_err(|_| MpcError::Protocol("invalid curve point".into()))
+}
+
+fn x_coordinate(point: &ProjectivePoint) -> Scalar {
+    <Scalar as Reduce<U256>>::reduce_bytes(&point.to_affine().x())
+}
+
+fn curve_order() -> BigUint {
+    BigUint::parse_bytes(CURVE_ORDER.as_bytes(), 16).expect("valid constant")
+}
+
+fn scalar_to_biguint(scalar: &Scalar) -> BigUint {
+    BigUint::from_bytes_be(&scalar.to_bytes())
+}
+
+fn biguint_to_scalar(value: &BigUint) -> Scalar {
+    let reduced = (value % curve_order()).to_bytes_be();
+    let mut bytes = [0u8; 32];
+    bytes[32 - reduced.len()..].copy_from_slice(&reduced);
+    <Scalar as Reduce<U256>>::reduce_bytes(&bytes.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::signer::mpc::transport::LocalTransport;
+
+    const FAST: KeygenOptions = KeygenOptions {
+        paillier_bits: 1024,
+        timeout: Duration::from_secs(30),
+    };
+
+    /// Party 2's keygen against a party 1 that opens with `tamper` applied to
+    /// an honest `KeygenOpen`.
+    async fn keygen_against(tamper: impl FnOnce(&mut Message)) -> Result<(), MpcError> {
+        let (first, second) = LocalTransport::pair(1, 2);
+        let session = Session {
+            transport: first.as_ref(),
+            peer: 2,
+            timeout: FAST.timeout,
+        };
+        let cheat = async {
+            let context = keygen_context(1);
+            let x1 = random_scalar();
+            let x1_point = ProjectivePoint::GENERATOR * x1;
+            let proof = prove_dlog(&context, &x1, &x1_point);
+            let salt: [u8; 32] = rand::random();
+            session
+                .send(&Message::KeygenCommit {
+                    commitment: commit(&x1_point, &proof, &salt),
+                })
+                .await?;
+            session.receive().await?;
+
+            let paillier = PaillierSecretKey::generate(FAST.paillier_bits);
+            let public_key = paillier.public_key();
+            let nonce = public_key.random_nonce();
+            let encrypted_share = public_key.encrypt_with(&scalar_to_biguint(&x1), &nonce);
+            let mut open = Message::KeygenOpen {
+                point: encode_point(&x1_point),
+                proof,
+                salt,
+                modulus_proof: paillier.prove_modulus(&context),
+                share_proof: prove_share(
+                    &context,
+                    &public_key,
+                    &x1,
+                    &x1_point,
+                    &encrypted_share,
+                    &nonce,
+                ),
+                paillier: public_key,
+                encrypted_share,
+            };
+            tamper(&mut open);
+            session.send(&open).await
+        };
+        let (_, result) = tokio::join!(cheat, keygen(second.as_ref(), 1, &FAST));
+        result.map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn second_party_checks_the_paillier_key_and_share() {
+        keygen_against(|_| {}).await.unwrap();
+
+        // A modulus with a small factor, passed off with the honest key's proof.
+        let result = keygen_against(|open| {
+            let Message::KeygenOpen { paillier, .. } = open else {
+                unreachable!()
+            };
+            paillier.n = &paillier.n * 3u32;
+        })
+        .await;
+        assert!(
+            matches!(&result, Err(MpcError::Protocol(reason)) if reason.contains("not well formed")),
+            "{:?}",
+            result
+        );
+
+        // An encryption of something other than the share behind X1.
+        let result = keygen_against(|open| {
+            let Message::KeygenOpen {
+                paillier,
+                encrypted_share,
+                ..
+            } = open
+            else {
+                unreachable!()
+            };
+            *encrypted_share =
+                paillier.add(encrypted_share, &paillier.encrypt(&BigUint::from(1u32)));
+        })
+        .await;
+        assert!(
+            matches!(&result, Err(MpcError::Protocol(reason)) if reason.contains("does not match the key share")),
+            "{:?}",
+            result
+        );
+    }
+}
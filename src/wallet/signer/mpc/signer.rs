@@ -1,116 +1,268 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
+use std::time::Duration;
 
+use super::protocol::{self, MpcError, Session, ShareSecret};
 use super::transport::{MpcTransport, PartyId};
+use crate::persist::{Persist, PersistError};
 use crate::wallet::Signer;
+use crate::wallet::chain::Network;
 use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::signer::{SignerError, recoverable_from_der};
 
-/// Placeholder for MPC key share data.
-/// In a real implementation, this would contain the mathematical share.
+/// One party's share of a two-party ECDSA key, produced by
+/// [`keygen`](super::protocol::keygen).
+#[derive(Clone)]
 pub struct KeyShare {
+    pub party: PartyId,
+    /// The co-signer holding the other share.
+    pub peer: PartyId,
+    /// Compressed SEC1 joint public key.
     pub public_key: Vec<u8>,
+    /// Encoded secret share (and Paillier material); never leaves this buffer
+    /// unencrypted except inside a [`Persist`] record.
     pub share_data: SecureBuffer,
+    /// Hardened path of the BIP-44 account the joint key stands in for,
+    /// e.g. `m/44'/195'/0'`, as recorded by
+    /// [`MpcKeySource::keygen`](crate::wallet::key_source::MpcKeySource::keygen);
+    /// `None` for shares from a bare [`keygen`](super::protocol::keygen).
+    pub account: Option<String>,
 }
 
-/// Signer that uses Multi-Party Computation to generate signatures.
+/// Wire shape of a [`KeyShare`]. Encrypt the encoded record (e.g. with
+/// [`AtRestCipher`](crate::wallet::crypto::at_rest::AtRestCipher)) before it
+/// touches disk.
+#[derive(Serialize)]
+struct KeyShareRef<'a> {
+    party: PartyId,
+    peer: PartyId,
+    #[serde(with = "serde_bytes")]
+    public_key: &'a [u8],
+    #[serde(with = "serde_bytes")]
+    share_data: &'a [u8],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct KeyShareOwned {
+    party: PartyId,
+    peer: PartyId,
+    public_key: ByteBuf,
+    share_data: ByteBuf,
+    #[serde(default)]
+    account: Option<String>,
+}
+
+impl Serialize for KeyShare {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        KeyShareRef {
+            party: self.party,
+            peer: self.peer,
+            public_key: &self.public_key,
+            share_data: &self.share_data,
+            account: self.account.as_deref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyShare {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let owned = KeyShareOwned::deserialize(deserializer)?;
+        Ok(Self {
+            party: owned.party,
+            peer: owned.peer,
+            public_key: owned.public_key.into_vec(),
+            share_data: SecureBuffer::new(owned.share_data.into_vec()),
+            account: owned.account,
+        })
+    }
+}
+
+impl Persist for KeyShare {
+    const KIND: &'static str = "key_share";
+    /// Version 2 adds the party ids of real two-party shares.
+    const VERSION: u16 = 2;
+
+    fn migrate(_: ciborium::Value, version: u16) -> Result<ciborium::Value, PersistError> {
+        // Version 1 shares came from the single-key placeholder signer and hold
+        // no threshold material to carry over.
+        Err(PersistError::Migration {
+            kind: Self::KIND,
+            from: version,
+            reason: "placeholder shares cannot sign; run keygen again".into(),
+        })
+    }
+}
+
+/// Signer holding one share of a two-party key.
+///
+/// Each signature is a protocol run with the co-signer over the transport, so
+/// the peer must be signing the same message at the same time.
 pub struct MpcSigner {
     share: KeyShare,
-    _transport: Arc<dyn MpcTransport>,
-    _party_id: PartyId,
+    transport: Arc<dyn MpcTransport>,
+    timeout: Duration,
+    network: Option<Network>,
 }
 
 impl MpcSigner {
     pub fn new(share: KeyShare, transport: Arc<dyn MpcTransport>) -> Self {
-        // In a real implementation, we would derive the party_id from the transport or config
-        let party_id = 1;
         Self {
             share,
-            _transport: transport,
-            _party_id: party_id,
+            transport,
+            timeout: Duration::from_secs(30),
+            network: None,
+        }
+    }
+
+    /// How long to wait for each message from the co-signer (default 30s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Only sign for chains on `network`.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Jointly sign a 32-byte prehash, reporting why a session failed.
+    /// Returns a DER signature, like [`Signer::sign_prehash`].
+    pub async fn try_sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, MpcError> {
+        let prehash: &[u8; 32] = prehash
+            .try_into()
+            .map_err(|_| MpcError::Protocol("prehash must be 32 bytes".into()))?;
+        if self.transport.my_party_id() != self.share.party {
+            return Err(MpcError::InvalidShare(format!(
+                "share belongs to party {}, transport is party {}",
+                self.share.party,
+                self.transport.my_party_id()
+            )));
+        }
+        let secret = ShareSecret::decode(&self.share.share_data)?;
+        let session = Session {
+            transport: self.transport.as_ref(),
+            peer: self.share.peer,
+            timeout: self.timeout,
+        };
+        let signature = protocol::sign(&session, &secret, &self.share.public_key, prehash).await?;
+        Ok(signature.to_der().as_bytes().to_vec())
+    }
+
+    /// [`try_sign_prehash`](Self::try_sign_prehash) returning `r || s || recid`,
+    /// like [`Signer::sign_recoverable`]. The recovery id is found against the
+    /// joint public key, which neither party's nonce share reveals on its own.
+    pub async fn try_sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, MpcError> {
+        let der = self.try_sign_prehash(prehash).await?;
+        recoverable_from_der(prehash, &der, &self.share.public_key)
+            .map_err(|e| MpcError::Protocol(e.to_string()))
+    }
+}
+
+impl From<MpcError> for SignerError {
+    fn from(error: MpcError) -> Self {
+        match error {
+            MpcError::Transport(e) => SignerError::Transport(e.to_string()),
+            MpcError::Timeout(party) => {
+                SignerError::Timeout(format!("no message from party {}", party))
+            }
+            MpcError::Aborted { party, reason } => {
+                SignerError::Rejected(format!("party {} aborted: {}", party, reason))
+            }
+            MpcError::InvalidShare(reason) => SignerError::InvalidKey(reason),
+            MpcError::Protocol(reason) => SignerError::Transport(format!("protocol: {}", reason)),
         }
     }
 }
 
 #[async_trait]
 impl Signer for MpcSigner {
-    async fn sign(&self, _message: &[u8]) -> Result<Vec<u8>, ()> {
-        // TODO: Implement actual MPC signing protocol
-        // For now, we just sign with the local key share to simulate success in tests
-        // In reality, this would involve multiple rounds of communication via self.transport
-
-        // Simulating MPC delay
-        // tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-
-        // Placeholder: just sign with the share's private key part (which is a full key in this mock)
-        let _payload = &self.share.share_data;
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.sign_prehash(&Sha256::digest(message)).await
+    }
 
-        // For the prototype, we can't easily sign without the full key.
-        // But KeyShare in this mock might actually hold the full key for simplicity?
-        // Let's assume KeyShare::share_data holds the private key bytes for this "Local MPC" mock.
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        Ok(self.try_sign_prehash(prehash).await?)
+    }
 
-        // This is a HACK for the prototype to allow "MPC" signer to work in basic flow tests
-        // without implementing a full GG18/CMP protocol.
-        let secret_key_bytes = &self.share.share_data;
-        let signer =
-            crate::wallet::signer::local::LocalSigner::from_slice(secret_key_bytes.as_ref())
-                .map_err(|_| ())?;
-        signer.sign(_message).await
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        Ok(self.try_sign_recoverable(prehash).await?)
     }
 
     fn public_key(&self) -> Vec<u8> {
         self.share.public_key.clone()
     }
+
+    fn network(&self) -> Option<Network> {
+        self.network
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::wallet::signer::mpc::transport::{MpcTransport, TransportError};
-    use std::sync::Mutex;
-
-    struct MockTransport {
-        id: PartyId,
-        sent_messages: Arc<Mutex<Vec<(PartyId, Vec<u8>)>>>,
-    }
+    use crate::persist;
+    use crate::wallet::signer::mpc::protocol::{KeygenOptions, keygen};
+    use crate::wallet::signer::mpc::transport::LocalTransport;
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+    use k256::ecdsa::{Signature, VerifyingKey};
 
-    #[async_trait]
-    impl MpcTransport for MockTransport {
-        async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError> {
-            self.sent_messages.lock().unwrap().push((to, data.to_vec()));
-            Ok(())
-        }
+    const FAST: KeygenOptions = KeygenOptions {
+        paillier_bits: 1024,
+        timeout: Duration::from_secs(30),
+    };
 
-        async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError> {
-            // Dummy receive
-            Ok((0, vec![]))
-        }
+    async fn two_party_signers() -> (MpcSigner, MpcSigner) {
+        let (a, b) = LocalTransport::pair(1, 2);
+        let (share_a, share_b) =
+            tokio::join!(keygen(a.as_ref(), 2, &FAST), keygen(b.as_ref(), 1, &FAST));
+        let (share_a, share_b) = (share_a.unwrap(), share_b.unwrap());
+        assert_eq!(share_a.public_key, share_b.public_key);
 
-        fn my_party_id(&self) -> PartyId {
-            self.id
-        }
+        // Shares survive a persistence roundtrip.
+        let share_b: KeyShare = persist::from_bytes(&persist::to_bytes(&share_b).unwrap()).unwrap();
+        (MpcSigner::new(share_a, a), MpcSigner::new(share_b, b))
     }
 
     #[tokio::test]
-    async fn test_mpc_signer_creation() {
-        let sent = Arc::new(Mutex::new(Vec::new()));
-        let transport = Arc::new(MockTransport {
-            id: 1,
-            sent_messages: sent.clone(),
-        });
-
-        let share = KeyShare {
-            public_key: vec![1, 2, 3],
-            share_data: SecureBuffer::new(vec![1u8; 32]),
-        };
+    async fn two_parties_produce_a_valid_signature() {
+        let (first, second) = two_party_signers().await;
+        let prehash = Sha256::digest(b"transfer 1 LTC");
 
-        let signer = MpcSigner::new(share, transport);
+        let (sig_a, sig_b) = tokio::join!(
+            first.try_sign_prehash(&prehash),
+            second.try_sign_prehash(&prehash)
+        );
+        let (sig_a, sig_b) = (sig_a.unwrap(), sig_b.unwrap());
+        assert_eq!(sig_a, sig_b);
 
-        // Test public key retrieval
-        assert_eq!(signer.public_key(), vec![1, 2, 3]);
+        let key = VerifyingKey::from_sec1_bytes(&first.public_key()).unwrap();
+        let signature = Signature::from_der(&sig_a).unwrap();
+        assert!(key.verify_prehash(&prehash, &signature).is_ok());
+        assert!(signature.normalize_s().is_none(), "low-s");
+    }
+
+    #[tokio::test]
+    async fn disagreement_aborts_both_sides() {
+        let (first, second) = two_party_signers().await;
+        let (a, b) = tokio::join!(
+            first.try_sign_prehash(&[1u8; 32]),
+            second.try_sign_prehash(&[2u8; 32])
+        );
+        assert!(matches!(a, Err(MpcError::Protocol(_))));
+        assert!(matches!(b, Err(MpcError::Aborted { party: 1, .. })));
 
-        // Test signing (skeleton)
-        let sig = signer.sign(b"test").await.expect("sign");
-        // assert_eq!(sig, vec![0xde, 0xad, 0xbe, 0xef]);
-        assert!(!sig.is_empty()); // Just check it produces something valid-ish
+        // A co-signer that never shows up times out instead of hanging.
+        let lonely = first.with_timeout(Duration::from_millis(50));
+        assert!(matches!(
+            lonely.try_sign_prehash(&[1u8; 32]).await,
+            Err(MpcError::Timeout(2))
+        ));
     }
 }
@@ -0,0 +1,436 @@
+//! Reliable, ordered per-peer links shared by the network transports.
+//!
+//! Every payload gets a sequence number and stays in the sender's outbox until
+//! the peer acknowledges it. When a connection is (re)established both sides
+//! exchange a `Hello` carrying how far they have read, so the sender can drop
+//! what already arrived and resend the rest in order; the receiver discards
+//! anything it has seen. A party that restarts picks a new session id, which
+//! tells the other side to forget the old numbering.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::{AbortHandle, JoinHandle};
+
+use super::{PartyId, TransportError};
+
+const PROTOCOL_VERSION: u8 = 1;
+/// Upper bound on a single frame; MPC messages are a few kilobytes.
+pub(super) const MAX_FRAME: usize = 16 * 1024 * 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+const TAG_HELLO: u8 = 0;
+const TAG_DATA: u8 = 1;
+const TAG_ACK: u8 = 2;
+
+/// Sending half of a connection, moving whole frames.
+#[async_trait]
+pub(super) trait FrameWrite: Send {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()>;
+}
+
+/// Receiving half of a connection; an error means the connection is gone.
+#[async_trait]
+pub(super) trait FrameRead: Send {
+    async fn read_frame(&mut self) -> io::Result<Vec<u8>>;
+}
+
+pub(super) type Connection = (Box<dyn FrameRead>, Box<dyn FrameWrite>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Hello {
+    from: PartyId,
+    to: PartyId,
+    /// Random id of the sender's link, fresh on every restart.
+    session: u64,
+    /// The session of ours the sender last saw, 0 if none.
+    peer_session: u64,
+    /// First sequence number the sender has not received from us yet.
+    next_expected: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Frame {
+    Hello(Hello),
+    Data { seq: u64, payload: Vec<u8> },
+    Ack { next: u64 },
+}
+
+impl Frame {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Frame::Hello(hello) => {
+                let mut out = vec![TAG_HELLO, PROTOCOL_VERSION];
+                out.extend_from_slice(&hello.from.to_be_bytes());
+                out.extend_from_slice(&hello.to.to_be_bytes());
+                out.extend_from_slice(&hello.session.to_be_bytes());
+                out.extend_from_slice(&hello.peer_session.to_be_bytes());
+                out.extend_from_slice(&hello.next_expected.to_be_bytes());
+                out
+            }
+            Frame::Data { seq, payload } => {
+                let mut out = Vec::with_capacity(9 + payload.len());
+                out.push(TAG_DATA);
+                out.extend_from_slice(&seq.to_be_bytes());
+                out.extend_from_slice(payload);
+                out
+            }
+            Frame::Ack { next } => {
+                let mut out = vec![TAG_ACK];
+                out.extend_from_slice(&next.to_be_bytes());
+                out
+            }
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let (&tag, rest) = bytes.split_first()?;
+        let u64_at = |at: usize| Some(u64::from_be_bytes(rest.get(at..at + 8)?.try_into().ok()?));
+        match tag {
+            TAG_HELLO if rest.len() == 29 && rest[0] == PROTOCOL_VERSION => {
+                Some(Frame::Hello(Hello {
+                    from: u16::from_be_bytes([rest[1], rest[2]]),
+                    to: u16::from_be_bytes([rest[3], rest[4]]),
+                    session: u64_at(5)?,
+                    peer_session: u64_at(13)?,
+                    next_expected: u64_at(21)?,
+                }))
+            }
+            TAG_DATA => Some(Frame::Data {
+                seq: u64_at(0)?,
+                payload: rest.get(8..)?.to_vec(),
+            }),
+            TAG_ACK if rest.len() == 8 => Some(Frame::Ack { next: u64_at(0)? }),
+            _ => None,
+        }
+    }
+}
+
+async fn write(writer: &mut Box<dyn FrameWrite>, frame: &Frame) -> io::Result<()> {
+    tokio::time::timeout(WRITE_TIMEOUT, writer.write_frame(&frame.encode()))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "write timed out"))?
+}
+
+async fn read_hello(reader: &mut Box<dyn FrameRead>) -> io::Result<Hello> {
+    let bytes = tokio::time::timeout(HANDSHAKE_TIMEOUT, reader.read_frame())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no handshake from peer"))??;
+    match Frame::decode(&bytes) {
+        Some(Frame::Hello(hello)) => Ok(hello),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected a handshake",
+        )),
+    }
+}
+
+struct LinkState {
+    next_seq: u64,
+    /// Sent but not yet acknowledged, oldest first.
+    outbox: VecDeque<(u64, Vec<u8>)>,
+    writer: Option<Box<dyn FrameWrite>>,
+    /// Bumped per connection so a stale reader cannot detach a newer one.
+    generation: u64,
+    next_expected: u64,
+    peer_session: Option<u64>,
+}
+
+/// Ordered, resending channel to one peer over successive connections.
+pub(super) struct Link {
+    me: PartyId,
+    peer: PartyId,
+    session: u64,
+    state: Mutex<LinkState>,
+    reader: StdMutex<Option<AbortHandle>>,
+    inbox: mpsc::UnboundedSender<(PartyId, Vec<u8>)>,
+}
+
+impl Link {
+    fn new(me: PartyId, peer: PartyId, inbox: mpsc::UnboundedSender<(PartyId, Vec<u8>)>) -> Self {
+        Self {
+            me,
+            peer,
+            session: rand::random::<u64>() | 1,
+            state: Mutex::new(LinkState {
+                next_seq: 0,
+                outbox: VecDeque::new(),
+                writer: None,
+                generation: 0,
+                next_expected: 0,
+                peer_session: None,
+            }),
+            reader: StdMutex::new(None),
+            inbox,
+        }
+    }
+
+    fn hello(&self, state: &LinkState) -> Hello {
+        Hello {
+            from: self.me,
+            to: self.peer,
+            session: self.session,
+            peer_session: state.peer_session.unwrap_or(0),
+            next_expected: state.next_expected,
+        }
+    }
+
+    /// Queue `payload`, writing it straight away if connected.
+    async fn send(&self, payload: &[u8]) {
+        let mut state = self.state.lock().await;
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.outbox.push_back((seq, payload.to_vec()));
+        let frame = Frame::Data {
+            seq,
+            payload: payload.to_vec(),
+        };
+        if let Some(writer) = state.writer.as_mut()
+            && write(writer, &frame).await.is_err()
+        {
+            self.drop_connection(&mut state);
+        }
+    }
+
+    fn drop_connection(&self, state: &mut LinkState) {
+        state.writer = None;
+        if let Some(reader) = self.reader.lock().unwrap().take() {
+            reader.abort();
+        }
+    }
+
+    /// Dialer side: introduce ourselves, then take the connection over.
+    async fn dial(
+        self: &Arc<Self>,
+        (mut reader, mut writer): Connection,
+    ) -> io::Result<JoinHandle<()>> {
+        let hello = self.hello(&*self.state.lock().await);
+        write(&mut writer, &Frame::Hello(hello)).await?;
+        let reply = read_hello(&mut reader).await?;
+        if reply.from != self.peer || reply.to != self.me {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("expected party {}, reached party {}", self.peer, reply.from),
+            ));
+        }
+        self.attach(&reply, reader, writer, false).await
+    }
+
+    /// Make this connection current: apply the peer's `hello`, resend what it
+    /// is missing and start reading.
+    async fn attach(
+        self: &Arc<Self>,
+        hello: &Hello,
+        reader: Box<dyn FrameRead>,
+        mut writer: Box<dyn FrameWrite>,
+        reply: bool,
+    ) -> io::Result<JoinHandle<()>> {
+        let mut state = self.state.lock().await;
+        self.drop_connection(&mut state);
+
+        if state.peer_session != Some(hello.session) {
+            // The peer restarted: what we queued for its old session is moot.
+            if state.peer_session.is_some() {
+                state.outbox.clear();
+                state.next_seq = 0;
+            }
+            state.peer_session = Some(hello.session);
+            state.next_expected = 0;
+        }
+        let received = if hello.peer_session == self.session {
+            hello.next_expected
+        } else {
+            0
+        };
+        state.outbox.retain(|(seq, _)| *seq >= received);
+
+        if reply {
+            write(&mut writer, &Frame::Hello(self.hello(&state))).await?;
+        }
+        for (seq, payload) in &state.outbox {
+            let frame = Frame::Data {
+                seq: *seq,
+                payload: payload.clone(),
+            };
+            write(&mut writer, &frame).await?;
+        }
+
+        state.generation += 1;
+        state.writer = Some(writer);
+        let generation = state.generation;
+        let link = self.clone();
+        let handle = tokio::spawn(async move {
+            link.read_loop(reader).await;
+            let mut state = link.state.lock().await;
+            if state.generation == generation {
+                state.writer = None;
+            }
+        });
+        *self.reader.lock().unwrap() = Some(handle.abort_handle());
+        Ok(handle)
+    }
+
+    async fn read_loop(&self, mut reader: Box<dyn FrameRead>) {
+        while let Ok(bytes) = reader.read_frame().await {
+            match Frame::decode(&bytes) {
+                Some(Frame::Data { seq, payload }) => {
+                    let mut state = self.state.lock().await;
+                    if seq > state.next_expected {
+                        // A gap means the stream is broken; a new handshake resyncs.
+                        return;
+                    }
+                    if seq == state.next_expected {
+                        state.next_expected += 1;
+                        let _ = self.inbox.send((self.peer, payload));
+                    }
+                    let ack = Frame::Ack {
+                        next: state.next_expected,
+                    };
+                    if let Some(writer) = state.writer.as_mut()
+                        && write(writer, &ack).await.is_err()
+                    {
+                        return;
+                    }
+                }
+                Some(Frame::Ack { next }) => {
+                    let mut state = self.state.lock().await;
+                    while state.outbox.front().is_some_and(|(seq, _)| *seq < next) {
+                        state.outbox.pop_front();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+/// The set of links of one party, plus the inbox they all deliver to.
+pub(super) struct Router {
+    me: PartyId,
+    links: StdMutex<HashMap<PartyId, Arc<Link>>>,
+    deliver: mpsc::UnboundedSender<(PartyId, Vec<u8>)>,
+    inbox: Mutex<mpsc::UnboundedReceiver<(PartyId, Vec<u8>)>>,
+    tasks: StdMutex<Vec<AbortHandle>>,
+}
+
+impl Router {
+    pub(super) fn new(me: PartyId) -> Arc<Self> {
+        let (deliver, inbox) = mpsc::unbounded_channel();
+        Arc::new(Self {
+            me,
+            links: StdMutex::new(HashMap::new()),
+            deliver,
+            inbox: Mutex::new(inbox),
+            tasks: StdMutex::new(Vec::new()),
+        })
+    }
+
+    pub(super) fn me(&self) -> PartyId {
+        self.me
+    }
+
+    fn link(&self, peer: PartyId) -> Option<Arc<Link>> {
+        self.links.lock().unwrap().get(&peer).cloned()
+    }
+
+    /// Register `peer`, keeping its link (and queued messages) if known.
+    pub(super) fn add_peer(&self, peer: PartyId) -> Arc<Link> {
+        self.links
+            .lock()
+            .unwrap()
+            .entry(peer)
+            .or_insert_with(|| Arc::new(Link::new(self.me, peer, self.deliver.clone())))
+            .clone()
+    }
+
+    /// Run `task` until the owning transport is dropped.
+    pub(super) fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let handle = tokio::spawn(task).abort_handle();
+        self.tasks.lock().unwrap().push(handle);
+    }
+
+    /// Keep a connection to `peer` up, redialing with backoff when it drops.
+    pub(super) fn dial<F, Fut>(&self, peer: PartyId, connect: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<Connection>> + Send,
+    {
+        let link = self.add_peer(peer);
+        self.spawn(async move {
+            let mut backoff = MIN_BACKOFF;
+            loop {
+                if let Ok(connection) = connect().await
+                    && let Ok(reader) = link.dial(connection).await
+                {
+                    backoff = MIN_BACKOFF;
+                    let _ = reader.await;
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+    }
+
+    /// Acceptor side of the handshake for an inbound connection.
+    pub(super) async fn accept(&self, (mut reader, writer): Connection) -> io::Result<()> {
+        let hello = read_hello(&mut reader).await?;
+        let link = match self.link(hello.from) {
+            Some(link) if hello.to == self.me => link,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("party {} is not a peer", hello.from),
+                ));
+            }
+        };
+        link.attach(&hello, reader, writer, true).await.map(drop)
+    }
+
+    pub(super) async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError> {
+        let link = self
+            .link(to)
+            .ok_or_else(|| TransportError::SendError(format!("unknown party {}", to)))?;
+        link.send(data).await;
+        Ok(())
+    }
+
+    pub(super) async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError> {
+        self.inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| TransportError::ReceiveError("transport closed".into()))
+    }
+
+    /// Drop the current connection to `peer`, as if the network had.
+    #[cfg(test)]
+    pub(super) async fn disconnect(&self, peer: PartyId) {
+        if let Some(link) = self.link(peer) {
+            let mut state = link.state.lock().await;
+            link.drop_connection(&mut state);
+        }
+    }
+
+    /// Stop every background task and close all connections.
+    pub(super) fn shutdown(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        for link in self.links.lock().unwrap().values() {
+            if let Some(reader) = link.reader.lock().unwrap().take() {
+                reader.abort();
+            }
+            if let Ok(mut state) = link.state.try_lock() {
+                state.writer = None;
+            }
+        }
+    }
+}
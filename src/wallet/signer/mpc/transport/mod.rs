@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::{Mutex, mpsc};
+
+// Browsers have no sockets; parties there bring their own `MpcTransport`.
+#[cfg(all(feature = "network", not(target_arch = "wasm32")))]
+mod link;
+#[cfg(all(feature = "network", not(target_arch = "wasm32")))]
+pub mod tcp;
+#[cfg(all(feature = "mpc-ws", not(target_arch = "wasm32")))]
+pub mod ws;
+
+#[cfg(all(feature = "network", not(target_arch = "wasm32")))]
+pub use tcp::TcpTransport;
+#[cfg(all(feature = "mpc-tls", not(target_arch = "wasm32")))]
+pub use tcp::TlsConfig;
+#[cfg(all(feature = "mpc-ws", not(target_arch = "wasm32")))]
+pub use ws::WebSocketTransport;
+
+pub type PartyId = u16;
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error("send failed: {0}")]
+    SendError(String),
+    #[error("receive failed: {0}")]
+    ReceiveError(String),
+}
+
+/// Abstract transport for MPC communication.
+#[async_trait]
+pub trait MpcTransport: Send + Sync {
+    /// Send data to a specific party.
+    async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError>;
+
+    /// Receive data from any party.
+    /// Returns (sender_id, data).
+    async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError>;
+
+    /// Get the ID of this party.
+    fn my_party_id(&self) -> PartyId;
+}
+
+/// In-process transport between two parties, for co-signers running in the
+/// same process and for tests.
+pub struct LocalTransport {
+    id: PartyId,
+    peer: PartyId,
+    outbox: mpsc::UnboundedSender<(PartyId, Vec<u8>)>,
+    inbox: Mutex<mpsc::UnboundedReceiver<(PartyId, Vec<u8>)>>,
+}
+
+impl LocalTransport {
+    /// Two connected endpoints for parties `a` and `b`.
+    pub fn pair(a: PartyId, b: PartyId) -> (Arc<Self>, Arc<Self>) {
+        let (to_a, inbox_a) = mpsc::unbounded_channel();
+        let (to_b, inbox_b) = mpsc::unbounded_channel();
+        let endpoint = |id, peer, outbox, inbox| {
+            Arc::new(Self {
+                id,
+                peer,
+                outbox,
+                inbox: Mutex::new(inbox),
+            })
+        };
+        (endpoint(a, b, to_b, inbox_a), endpoint(b, a, to_a, inbox_b))
+    }
+}
+
+#[async_trait]
+impl MpcTransport for LocalTransport {
+    async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError> {
+        if to != self.peer {
+            return Err(TransportError::SendError(format!("unknown party {}", to)));
+        }
+        self.outbox
+            .send((self.id, data.to_vec()))
+            .map_err(|_| TransportError::SendError("peer disconnected".into()))
+    }
+
+    async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError> {
+        self.inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| TransportError::ReceiveError("peer disconnected".into()))
+    }
+
+    fn my_party_id(&self) -> PartyId {
+        self.id
+    }
+}
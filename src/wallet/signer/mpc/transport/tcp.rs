@@ -0,0 +1,248 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use super::link::{Connection, FrameRead, FrameWrite, MAX_FRAME, Router};
+use super::{MpcTransport, PartyId, TransportError};
+
+/// Certificates for running [`TcpTransport`] over mutually authenticated TLS.
+///
+/// The acceptor should require client certificates: the party-ID handshake
+/// only routes connections, it does not prove who is on the other end.
+#[cfg(feature = "mpc-tls")]
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub acceptor: tokio_rustls::TlsAcceptor,
+    pub connector: tokio_rustls::TlsConnector,
+}
+
+trait Stream: AsyncRead + AsyncWrite + Send + Unpin {}
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Stream for T {}
+
+#[derive(Clone, Default)]
+struct Security {
+    #[cfg(feature = "mpc-tls")]
+    tls: Option<TlsConfig>,
+}
+
+impl Security {
+    async fn accept(&self, stream: TcpStream) -> io::Result<Box<dyn Stream>> {
+        #[cfg(feature = "mpc-tls")]
+        if let Some(tls) = &self.tls {
+            return Ok(Box::new(tls.acceptor.accept(stream).await?));
+        }
+        Ok(Box::new(stream))
+    }
+
+    async fn connect(&self, addr: &str) -> io::Result<Box<dyn Stream>> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        #[cfg(feature = "mpc-tls")]
+        if let Some(tls) = &self.tls {
+            let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+            let name = tokio_rustls::rustls::pki_types::ServerName::try_from(
+                host.trim_matches(['[', ']']).to_string(),
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            return Ok(Box::new(tls.connector.connect(name, stream).await?));
+        }
+        Ok(Box::new(stream))
+    }
+}
+
+/// Frames as a big-endian `u32` length followed by the bytes.
+struct LengthPrefixed<T>(T);
+
+#[async_trait]
+impl<W: AsyncWrite + Send + Unpin> FrameWrite for LengthPrefixed<W> {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(4 + frame.len());
+        buf.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        buf.extend_from_slice(frame);
+        self.0.write_all(&buf).await?;
+        self.0.flush().await
+    }
+}
+
+#[async_trait]
+impl<R: AsyncRead + Send + Unpin> FrameRead for LengthPrefixed<R> {
+    async fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.0.read_u32().await? as usize;
+        if len > MAX_FRAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {} bytes", len),
+            ));
+        }
+        let mut frame = vec![0u8; len];
+        self.0.read_exact(&mut frame).await?;
+        Ok(frame)
+    }
+}
+
+fn framed(stream: Box<dyn Stream>) -> Connection {
+    let (reader, writer) = tokio::io::split(stream);
+    (
+        Box::new(LengthPrefixed(reader)),
+        Box::new(LengthPrefixed(writer)),
+    )
+}
+
+/// MPC transport over TCP with length-prefixed frames, optionally wrapped in
+/// TLS (feature `mpc-tls`).
+///
+/// Each pair of parties shares one connection, opened by whichever side was
+/// given the other's address via [`dial_peer`](Self::dial_peer); the other
+/// side lists it with [`accept_peer`](Self::accept_peer). Messages are
+/// delivered exactly once and in order per peer: anything sent while the
+/// connection is down is queued and resent after the dialer reconnects.
+pub struct TcpTransport {
+    router: Arc<Router>,
+    local_addr: SocketAddr,
+    security: Security,
+}
+
+impl TcpTransport {
+    /// Listen on `addr` as party `me`, over plain TCP.
+    pub async fn bind(me: PartyId, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::bind_with(me, addr, Security::default()).await
+    }
+
+    /// Listen on `addr` as party `me`, with every connection over TLS.
+    #[cfg(feature = "mpc-tls")]
+    pub async fn bind_tls(
+        me: PartyId,
+        addr: impl ToSocketAddrs,
+        tls: TlsConfig,
+    ) -> io::Result<Self> {
+        Self::bind_with(me, addr, Security { tls: Some(tls) }).await
+    }
+
+    async fn bind_with(
+        me: PartyId,
+        addr: impl ToSocketAddrs,
+        security: Security,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let router = Router::new(me);
+
+        let accepting = (router.clone(), security.clone());
+        router.spawn(async move {
+            let (router, security) = accepting;
+            while let Ok((stream, _)) = listener.accept().await {
+                let (router, security) = (router.clone(), security.clone());
+                tokio::spawn(async move {
+                    let _ = stream.set_nodelay(true);
+                    if let Ok(stream) = security.accept(stream).await {
+                        let _ = router.accept(framed(stream)).await;
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            router,
+            local_addr,
+            security,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Connect to `peer` at `addr` (`host:port`) and keep reconnecting.
+    pub fn dial_peer(&self, peer: PartyId, addr: &str) {
+        let addr = addr.to_string();
+        let security = self.security.clone();
+        self.router.dial(peer, move || {
+            let (addr, security) = (addr.clone(), security.clone());
+            async move { Ok(framed(security.connect(&addr).await?)) }
+        });
+    }
+
+    /// Let `peer` connect to us; connections claiming any other party are
+    /// refused.
+    pub fn accept_peer(&self, peer: PartyId) {
+        self.router.add_peer(peer);
+    }
+}
+
+impl Drop for TcpTransport {
+    fn drop(&mut self) {
+        self.router.shutdown();
+    }
+}
+
+#[async_trait]
+impl MpcTransport for TcpTransport {
+    async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError> {
+        self.router.send(to, data).await
+    }
+
+    async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError> {
+        self.router.receive().await
+    }
+
+    fn my_party_id(&self) -> PartyId {
+        self.router.me()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    async fn recv(transport: &TcpTransport) -> (PartyId, Vec<u8>) {
+        tokio::time::timeout(Duration::from_secs(5), transport.receive())
+            .await
+            .expect("message within the timeout")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ordered_delivery_across_reconnects() {
+        let a = TcpTransport::bind(1, "127.0.0.1:0").await.unwrap();
+        let b = TcpTransport::bind(2, "127.0.0.1:0").await.unwrap();
+        a.accept_peer(2);
+        b.dial_peer(1, &a.local_addr().to_string());
+
+        // Sent before the connection exists: queued, then flushed in order.
+        for i in 0..3u8 {
+            b.send(1, &[i]).await.unwrap();
+        }
+        for i in 0..3u8 {
+            assert_eq!(recv(&a).await, (2, vec![i]));
+        }
+        a.send(2, b"pong").await.unwrap();
+        assert_eq!(recv(&b).await, (1, b"pong".to_vec()));
+
+        a.router.disconnect(2).await;
+        b.send(1, b"after").await.unwrap();
+        a.send(2, b"queued").await.unwrap();
+        assert_eq!(recv(&a).await, (2, b"after".to_vec()));
+        assert_eq!(recv(&b).await, (1, b"queued".to_vec()));
+
+        b.send(1, b"last").await.unwrap();
+        assert_eq!(recv(&a).await, (2, b"last".to_vec()), "no duplicates");
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_parties() {
+        let a = TcpTransport::bind(1, "127.0.0.1:0").await.unwrap();
+        let intruder = TcpTransport::bind(3, "127.0.0.1:0").await.unwrap();
+        a.accept_peer(2);
+        intruder.dial_peer(1, &a.local_addr().to_string());
+
+        intruder.send(1, b"hi").await.unwrap();
+        let received = tokio::time::timeout(Duration::from_millis(300), a.receive()).await;
+        assert!(received.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::link::{Connection, FrameRead, FrameWrite, MAX_FRAME, Router};
+use super::{MpcTransport, PartyId, TransportError};
+
+fn ws_error(e: tokio_tungstenite::tungstenite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> FrameWrite
+    for SplitSink<WebSocketStream<S>, Message>
+{
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.send(Message::binary(frame.to_vec()))
+            .await
+            .map_err(ws_error)
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> FrameRead for SplitStream<WebSocketStream<S>> {
+    async fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            match self.next().await {
+                Some(Ok(Message::Binary(data))) if data.len() <= MAX_FRAME => {
+                    return Ok(data.to_vec());
+                }
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Err(e)) => return Err(ws_error(e)),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "websocket closed",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn framed<S: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
+    stream: WebSocketStream<S>,
+) -> Connection {
+    let (writer, reader) = stream.split();
+    (Box::new(reader), Box::new(writer))
+}
+
+/// MPC transport over WebSocket binary messages, for co-signers that can
+/// only reach each other through HTTP infrastructure.
+///
+/// Same delivery guarantees as [`TcpTransport`](super::TcpTransport): one
+/// connection per pair of parties, opened by the side that calls
+/// [`dial_peer`](Self::dial_peer), with in-order, exactly-once delivery across
+/// reconnects. `wss://` is not built in; terminate TLS in front of the
+/// listener.
+pub struct WebSocketTransport {
+    router: Arc<Router>,
+    local_addr: Option<SocketAddr>,
+}
+
+impl WebSocketTransport {
+    /// Party `me`, only dialing out.
+    pub fn new(me: PartyId) -> Self {
+        Self {
+            router: Router::new(me),
+            local_addr: None,
+        }
+    }
+
+    /// Party `me`, also accepting WebSocket upgrades on `addr`.
+    pub async fn bind(me: PartyId, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let router = Router::new(me);
+
+        let accepting = router.clone();
+        router.spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let router = accepting.clone();
+                tokio::spawn(async move {
+                    let _ = stream.set_nodelay(true);
+                    if let Ok(stream) = tokio_tungstenite::accept_async(stream).await {
+                        let _ = router.accept(framed(stream)).await;
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            router,
+            local_addr: Some(local_addr),
+        })
+    }
+
+    /// Listening address, if [`bind`](Self::bind) was used.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Connect to `peer` at `url` (`ws://host:port/path`) and keep reconnecting.
+    pub fn dial_peer(&self, peer: PartyId, url: &str) {
+        let url = url.to_string();
+        self.router.dial(peer, move || {
+            let url = url.clone();
+            async move {
+                let (stream, _) = tokio_tungstenite::connect_async(url.as_str())
+                    .await
+                    .map_err(ws_error)?;
+                Ok(framed(stream))
+            }
+        });
+    }
+
+    /// Let `peer` connect to us; connections claiming any other party are
+    /// refused.
+    pub fn accept_peer(&self, peer: PartyId) {
+        self.router.add_peer(peer);
+    }
+}
+
+impl Drop for WebSocketTransport {
+    fn drop(&mut self) {
+        self.router.shutdown();
+    }
+}
+
+#[async_trait]
+impl MpcTransport for WebSocketTransport {
+    async fn send(&self, to: PartyId, data: &[u8]) -> Result<(), TransportError> {
+        self.router.send(to, data).await
+    }
+
+    async fn receive(&self) -> Result<(PartyId, Vec<u8>), TransportError> {
+        self.router.receive().await
+    }
+
+    fn my_party_id(&self) -> PartyId {
+        self.router.me()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    async fn recv(transport: &WebSocketTransport) -> (PartyId, Vec<u8>) {
+        tokio::time::timeout(Duration::from_secs(5), transport.receive())
+            .await
+            .expect("message within the timeout")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn exchanges_messages_over_websocket() {
+        let a = WebSocketTransport::bind(1, "127.0.0.1:0").await.unwrap();
+        let b = WebSocketTransport::new(2);
+        a.accept_peer(2);
+        b.dial_peer(1, &format!("ws://{}/mpc", a.local_addr().unwrap()));
+
+        b.send(1, b"one").await.unwrap();
+        b.send(1, b"two").await.unwrap();
+        a.send(2, b"back").await.unwrap();
+        assert_eq!(recv(&a).await, (2, b"one".to_vec()));
+        assert_eq!(recv(&a).await, (2, b"two".to_vec()));
+        assert_eq!(recv(&b).await, (1, b"back".to_vec()));
+    }
+}
@@ -0,0 +1,854 @@
+//! Signing through a separate signing service (feature `network`; gRPC
+//! needs `remote-grpc`).
+//!
+//! Over HTTP(S) the service answers two JSON endpoints under its base URL:
+//!
+//! - `GET /keys/{key_id}` returns `{"public_key": "<hex>", "key_type": "secp256k1"}`
+//!   (`key_type` may be left out for keys whose encoding tells the curve).
+//! - `POST /keys/{key_id}/sign` takes `{"mode": "prehash", "payloads": ["<hex>"],
+//!   "context": {..} | null, "tweak": "<hex>" | null}` and returns
+//!   `{"signatures": ["<hex>"]}`, one per payload, in order.
+//!
+//! `key_id` is percent-encoded as one path segment.
+//!
+//! The context leaves out the send's travel-rule `metadata`, which names the
+//! parties to the transfer, unless the service's policy needs it and the
+//! config [asks for it](RemoteSignerConfig::with_metadata); the gRPC
+//! messages have no field for it.
+//!
+//! `mode` is `message`, `prehash`, `recoverable` or `taproot`, after
+//! [`SignMode`]; `tweak` only comes with `taproot`. Signatures are in the
+//! format the matching [`Signer`] method returns. Failures are a non-2xx
+//! status, optionally with `{"error": "<reason>"}`.
+//!
+//! Over gRPC the same two calls are the unary methods `GetPublicKey` and
+//! `Sign` of `flow_wallet.signer.v1.RemoteSigner`, with the messages in
+//! [`proto`].
+//!
+//! A bearer token goes in `Authorization` (`authorization` metadata). With a
+//! request key, every request also carries `x-signature-timestamp`, in unix
+//! seconds, and `x-signature`, the hex HMAC-SHA256 under that key of
+//! `{timestamp}\n{path}\n` followed by the request body (the encoded
+//! protobuf message over gRPC), so the service can refuse requests that did
+//! not come from this client or are replayed late.
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::node::network::http::{DEFAULT_CONNECT_TIMEOUT, fetch};
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::wallet::Signer;
+use crate::wallet::chain::Network;
+use crate::wallet::chain::sdk::taproot_tweak;
+use crate::wallet::crypto::memory::SecureBuffer;
+use crate::wallet::scheme::{KeyType, SignatureScheme};
+use crate::wallet::signer::{SignMode, SignerError, SigningContext};
+
+#[cfg(feature = "remote-grpc")]
+const GET_PUBLIC_KEY: &str = "/flow_wallet.signer.v1.RemoteSigner/GetPublicKey";
+#[cfg(feature = "remote-grpc")]
+const SIGN: &str = "/flow_wallet.signer.v1.RemoteSigner/Sign";
+
+/// Longest a signing request may take by default; longer than a provider
+/// call, since the service may wait on a person or an HSM.
+pub const DEFAULT_SIGN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Http,
+    #[cfg(feature = "remote-grpc")]
+    Grpc,
+}
+
+/// Where a [`RemoteSigner`] finds its signing service, and how it
+/// authenticates to it.
+#[derive(Clone)]
+pub struct RemoteSignerConfig {
+    endpoint: String,
+    protocol: Protocol,
+    key_id: String,
+    auth_token: Option<SecureBuffer>,
+    request_key: Option<SecureBuffer>,
+    timeout: Duration,
+    connect_timeout: Duration,
+    metadata: bool,
+}
+
+impl RemoteSignerConfig {
+    /// The JSON API at base URL `url` (e.g. `https://signer.internal/v1`),
+    /// signing with the service's key `key_id`.
+    pub fn http(url: &str, key_id: &str) -> Self {
+        Self::for_protocol(url, key_id, Protocol::Http)
+    }
+
+    /// The gRPC service at `url`, e.g. `https://signer.internal:50051`.
+    #[cfg(feature = "remote-grpc")]
+    pub fn grpc(url: &str, key_id: &str) -> Self {
+        Self::for_protocol(url, key_id, Protocol::Grpc)
+    }
+
+    fn for_protocol(url: &str, key_id: &str, protocol: Protocol) -> Self {
+        Self {
+            endpoint: url.trim_end_matches('/').to_string(),
+            protocol,
+            key_id: key_id.to_string(),
+            auth_token: None,
+            request_key: None,
+            timeout: DEFAULT_SIGN_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            metadata: false,
+        }
+    }
+
+    /// Send `token` as a bearer token with every request.
+    pub fn with_auth_token(mut self, token: &str) -> Self {
+        self.auth_token = Some(SecureBuffer::from(token));
+        self
+    }
+
+    /// HMAC every request with `key`, shared with the service.
+    pub fn with_request_key(mut self, key: &[u8]) -> Self {
+        self.request_key = Some(SecureBuffer::new(key.to_vec()));
+        self
+    }
+
+    /// Longest one request may take, from connecting to the last byte.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Include the send's travel-rule metadata in the signing context, for
+    /// a service whose policy decides on it.
+    pub fn with_metadata(mut self) -> Self {
+        self.metadata = true;
+        self
+    }
+
+    /// `x-signature-timestamp` and `x-signature` for a request to `path`
+    /// with `body`, if requests are signed.
+    fn request_signature(&self, path: &str, body: &[u8]) -> Option<(String, String)> {
+        let key = self.request_key.as_ref()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            .to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+        mac.update(timestamp.as_bytes());
+        mac.update(b"\n");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(body);
+        Some((timestamp, hex::encode(mac.finalize().into_bytes())))
+    }
+
+    fn bearer(&self) -> Option<String> {
+        let token = self.auth_token.as_ref()?.as_str().ok()?;
+        Some(format!("Bearer {}", token))
+    }
+}
+
+impl std::fmt::Debug for RemoteSignerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSignerConfig")
+            .field("endpoint", &self.endpoint)
+            .field("protocol", &self.protocol)
+            .field("key_id", &self.key_id)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field(
+                "request_key",
+                &self.request_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+enum Client {
+    Http(reqwest::Client),
+    #[cfg(feature = "remote-grpc")]
+    Grpc(tonic::transport::Channel),
+}
+
+/// [`Signer`] whose key lives in a separate signing service, reached over
+/// HTTPS or gRPC as the [module docs](self) describe. The public key is
+/// fetched once, by [`connect`](Self::connect).
+///
+/// Service failures come back as [`SignerError`]s by kind: refused
+/// credentials or a policy refusal as `Rejected`, an overloaded service as
+/// `Busy`, a slow one as `Timeout`, an unknown key as `InvalidKey`, so
+/// [`RetryingSigner`](super::RetryingSigner) retries only what may pass
+/// the next time.
+pub struct RemoteSigner {
+    config: RemoteSignerConfig,
+    client: Client,
+    public_key: Vec<u8>,
+    key_type: KeyType,
+    network: Option<Network>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PublicKeyResponse {
+    public_key: String,
+    #[serde(default)]
+    key_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignRequest {
+    mode: String,
+    payloads: Vec<String>,
+    context: Option<SigningContext>,
+    tweak: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignResponse {
+    signatures: Vec<String>,
+}
+
+impl RemoteSigner {
+    /// Reach the service `config` names and fetch its key's public key.
+    pub async fn connect(config: RemoteSignerConfig) -> Result<Self, SignerError> {
+        let client = match config.protocol {
+            Protocol::Http => {
+                let builder = reqwest::Client::builder();
+                // `fetch` applies the browser's own timeouts.
+                #[cfg(not(target_arch = "wasm32"))]
+                let builder = builder
+                    .timeout(config.timeout)
+                    .connect_timeout(config.connect_timeout);
+                let client = builder
+                    .build()
+                    .map_err(|e| SignerError::Transport(e.to_string()))?;
+                Client::Http(client)
+            }
+            #[cfg(feature = "remote-grpc")]
+            Protocol::Grpc => {
+                let endpoint = tonic::transport::Endpoint::from_shared(config.endpoint.clone())
+                    .map_err(|e| {
+                        SignerError::Transport(format!(
+                            "invalid endpoint {}: {}",
+                            config.endpoint, e
+                        ))
+                    })?
+                    .timeout(config.timeout)
+                    .connect_timeout(config.connect_timeout);
+                Client::Grpc(endpoint.connect_lazy())
+            }
+        };
+        let mut signer = Self {
+            config,
+            client,
+            public_key: Vec::new(),
+            key_type: KeyType::Secp256k1,
+            network: None,
+        };
+        let (public_key, key_type) = signer.fetch_public_key().await?;
+        let key_type = match key_type.as_deref() {
+            Some("secp256k1") => KeyType::Secp256k1,
+            Some("ed25519") => KeyType::Ed25519,
+            Some(other) => {
+                return Err(SignerError::InvalidKey(format!(
+                    "service reports unknown key type {}",
+                    other
+                )));
+            }
+            None => KeyType::of_public_key(&public_key).ok_or_else(|| {
+                SignerError::InvalidKey(format!(
+                    "cannot tell the curve of a {}-byte public key",
+                    public_key.len()
+                ))
+            })?,
+        };
+        signer.public_key = public_key;
+        signer.key_type = key_type;
+        Ok(signer)
+    }
+
+    /// Tag the signer with `network`.
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.config.key_id
+    }
+
+    async fn fetch_public_key(&self) -> Result<(Vec<u8>, Option<String>), SignerError> {
+        match &self.client {
+            Client::Http(client) => {
+                let path = format!("/keys/{}", path_segment(&self.config.key_id));
+                let response: PublicKeyResponse =
+                    self.http(client, reqwest::Method::GET, &path, None).await?;
+                let public_key = hex::decode(&response.public_key).map_err(|e| {
+                    SignerError::InvalidKey(format!("public key is not hex: {}", e))
+                })?;
+                Ok((public_key, response.key_type))
+            }
+            #[cfg(feature = "remote-grpc")]
+            Client::Grpc(channel) => {
+                let request = proto::GetPublicKeyRequest {
+                    key_id: self.config.key_id.clone(),
+                };
+                let response: proto::GetPublicKeyResponse =
+                    self.grpc(channel, GET_PUBLIC_KEY, request).await?;
+                let key_type = Some(response.key_type).filter(|t| !t.is_empty());
+                Ok((response.public_key, key_type))
+            }
+        }
+    }
+
+    /// One request signing all of `payloads`.
+    async fn request_signatures(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<Vec<u8>>, SignerError> {
+        let signatures = match &self.client {
+            Client::Http(client) => {
+                let path = format!("/keys/{}/sign", path_segment(&self.config.key_id));
+                let request = SignRequest {
+                    mode: mode_name(mode).to_string(),
+                    payloads: payloads.iter().map(hex::encode).collect(),
+                    context: context.map(|context| SigningContext {
+                        metadata: context.metadata.clone().filter(|_| self.config.metadata),
+                        ..context.clone()
+                    }),
+                    tweak: tweak.map(hex::encode),
+                };
+                let body = serde_json::to_vec(&request)
+                    .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+                let response: SignResponse = self
+                    .http(client, reqwest::Method::POST, &path, Some(body))
+                    .await?;
+                response
+                    .signatures
+                    .iter()
+                    .map(|s| {
+                        hex::decode(s).map_err(|e| {
+                            SignerError::Transport(format!("signature is not hex: {}", e))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            #[cfg(feature = "remote-grpc")]
+            Client::Grpc(channel) => {
+                let request = proto::SignRequest {
+                    key_id: self.config.key_id.clone(),
+                    mode: mode_name(mode).to_string(),
+                    payloads: payloads.to_vec(),
+                    context: context.map(proto::SigningContext::from),
+                    tweak: tweak.map(|t| t.to_vec()),
+                };
+                let response: proto::SignResponse = self.grpc(channel, SIGN, request).await?;
+                response.signatures
+            }
+        };
+        if signatures.len() != payloads.len() {
+            return Err(SignerError::Transport(format!(
+                "service returned {} signatures for {} payloads",
+                signatures.len(),
+                payloads.len()
+            )));
+        }
+        Ok(signatures)
+    }
+
+    async fn sign_one(
+        &self,
+        payload: &[u8],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        let mut signatures = self
+            .request_signatures(&[payload.to_vec()], mode, context, tweak)
+            .await?;
+        Ok(signatures.remove(0))
+    }
+
+    async fn http<T: serde::de::DeserializeOwned>(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<T, SignerError> {
+        let mut request = client.request(method, format!("{}{}", self.config.endpoint, path));
+        if let Some(bearer) = self.config.bearer() {
+            request = request.header(reqwest::header::AUTHORIZATION, bearer);
+        }
+        let body = body.unwrap_or_default();
+        if let Some((timestamp, signature)) = self.config.request_signature(path, &body) {
+            request = request
+                .header("x-signature-timestamp", timestamp)
+                .header("x-signature", signature);
+        }
+        if !body.is_empty() {
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+
+        let response = fetch(request).await.map_err(|e| {
+            if e.is_timeout() {
+                SignerError::Timeout(e.to_string())
+            } else {
+                SignerError::Transport(e.to_string())
+            }
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text();
+            let reason = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|v| v["error"].as_str().map(str::to_string))
+                .unwrap_or(text);
+            return Err(status_error(status.as_u16(), reason));
+        }
+        serde_json::from_slice(response.body())
+            .map_err(|e| SignerError::Transport(format!("invalid response: {}", e)))
+    }
+
+    #[cfg(feature = "remote-grpc")]
+    async fn grpc<Req, Resp>(
+        &self,
+        channel: &tonic::transport::Channel,
+        path: &'static str,
+        message: Req,
+    ) -> Result<Resp, SignerError>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        use tonic::metadata::MetadataValue;
+
+        let mut grpc = tonic::client::Grpc::new(channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| SignerError::Transport(e.to_string()))?;
+
+        let signature = self
+            .config
+            .request_signature(path, &message.encode_to_vec());
+        let mut request = tonic::Request::new(message);
+        let metadata = request.metadata_mut();
+        if let Some(bearer) = self.config.bearer() {
+            let value = MetadataValue::try_from(bearer)
+                .map_err(|e| SignerError::InvalidInput(e.to_string()))?;
+            metadata.insert("authorization", value);
+        }
+        if let Some((timestamp, signature)) = signature {
+            let timestamp = MetadataValue::try_from(timestamp).expect("ASCII digits");
+            let signature = MetadataValue::try_from(signature).expect("ASCII hex");
+            metadata.insert("x-signature-timestamp", timestamp);
+            metadata.insert("x-signature", signature);
+        }
+
+        grpc.unary(
+            request,
+            tonic::codegen::http::uri::PathAndQuery::from_static(path),
+            tonic_prost::ProstCodec::default(),
+        )
+        .await
+        .map(tonic::Response::into_inner)
+        .map_err(|status| {
+            let reason = status.message().to_string();
+            match status.code() {
+                tonic::Code::Unauthenticated | tonic::Code::PermissionDenied => {
+                    SignerError::Rejected(reason)
+                }
+                tonic::Code::FailedPrecondition | tonic::Code::Aborted => {
+                    SignerError::Rejected(reason)
+                }
+                tonic::Code::NotFound => SignerError::InvalidKey(reason),
+                tonic::Code::InvalidArgument | tonic::Code::Unimplemented => {
+                    SignerError::InvalidInput(reason)
+                }
+                tonic::Code::ResourceExhausted => SignerError::Busy(reason),
+                tonic::Code::DeadlineExceeded => SignerError::Timeout(reason),
+                _ => SignerError::Transport(format!("{:?}: {}", status.code(), reason)),
+            }
+        })
+    }
+}
+
+fn mode_name(mode: SignMode) -> &'static str {
+    match mode {
+        SignMode::Message => "message",
+        SignMode::Prehash => "prehash",
+        SignMode::Recoverable => "recoverable",
+        SignMode::Taproot => "taproot",
+    }
+}
+
+/// The [`SignerError`] for an HTTP error `status` with `reason`.
+fn status_error(status: u16, reason: String) -> SignerError {
+    match status {
+        401 | 403 | 409 => SignerError::Rejected(reason),
+        404 => SignerError::InvalidKey(reason),
+        400 | 422 => SignerError::InvalidInput(reason),
+        408 | 504 => SignerError::Timeout(reason),
+        429 | 503 => SignerError::Busy(reason),
+        _ => SignerError::Transport(format!("HTTP {}: {}", status, reason)),
+    }
+}
+
+/// `segment` percent-encoded for use as one URL path segment.
+fn path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl<S: SignatureScheme> Signer<S> for RemoteSigner {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(message, SignMode::Message, None, None).await
+    }
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(prehash, SignMode::Prehash, None, None).await
+    }
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(message, SignMode::Message, Some(context), None)
+            .await
+    }
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(prehash, SignMode::Prehash, Some(context), None)
+            .await
+    }
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(prehash, SignMode::Recoverable, None, None)
+            .await
+    }
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(prehash, SignMode::Recoverable, Some(context), None)
+            .await
+    }
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.sign_one(prehash, SignMode::Taproot, None, tweak).await
+    }
+    /// The whole batch in one request; `concurrency` does not apply.
+    async fn sign_many(
+        &self,
+        payloads: &[Vec<u8>],
+        mode: SignMode,
+        context: Option<&SigningContext>,
+        _concurrency: usize,
+    ) -> Vec<Result<Vec<u8>, SignerError>> {
+        if payloads.is_empty() {
+            return Vec::new();
+        }
+        // Taproot key-path spends sign with the tweaked key, as `sign_as` does.
+        let tweak = match mode {
+            SignMode::Taproot => match taproot_tweak(&self.public_key) {
+                Ok(tweak) => Some(tweak),
+                Err(e) => {
+                    let error = SignerError::InvalidInput(e.to_string());
+                    return payloads.iter().map(|_| Err(error.clone())).collect();
+                }
+            },
+            _ => None,
+        };
+        match self
+            .request_signatures(payloads, mode, context, tweak.as_ref())
+            .await
+        {
+            Ok(signatures) => signatures.into_iter().map(Ok).collect(),
+            Err(e) => payloads.iter().map(|_| Err(e.clone())).collect(),
+        }
+    }
+    fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+    fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+    fn network(&self) -> Option<Network> {
+        self.network
+    }
+}
+
+/// Messages of the `flow_wallet.signer.v1.RemoteSigner` gRPC service, in
+/// the shape `prost-build` generates from:
+///
+/// ```proto
+/// service RemoteSigner {
+///   rpc GetPublicKey(GetPublicKeyRequest) returns (GetPublicKeyResponse);
+///   rpc Sign(SignRequest) returns (SignResponse);
+/// }
+/// ```
+#[cfg(feature = "remote-grpc")]
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetPublicKeyRequest {
+        #[prost(string, tag = "1")]
+        pub key_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetPublicKeyResponse {
+        #[prost(bytes = "vec", tag = "1")]
+        pub public_key: Vec<u8>,
+        /// `secp256k1` or `ed25519`; empty to go by the key's encoding.
+        #[prost(string, tag = "2")]
+        pub key_type: String,
+    }
+
+    /// [`SigningContext`](crate::wallet::signer::SigningContext).
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SigningContext {
+        #[prost(string, tag = "1")]
+        pub chain: String,
+        #[prost(string, tag = "2")]
+        pub from: String,
+        #[prost(string, tag = "3")]
+        pub to: String,
+        #[prost(uint64, tag = "4")]
+        pub amount: u64,
+        #[prost(string, tag = "5")]
+        pub summary: String,
+    }
+
+    impl From<&crate::wallet::signer::SigningContext> for SigningContext {
+        fn from(context: &crate::wallet::signer::SigningContext) -> Self {
+            Self {
+                chain: context.chain.clone(),
+                from: context.from.clone(),
+                to: context.to.clone(),
+                amount: context.amount,
+                summary: context.summary.clone(),
+            }
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SignRequest {
+        #[prost(string, tag = "1")]
+        pub key_id: String,
+        #[prost(string, tag = "2")]
+        pub mode: String,
+        #[prost(bytes = "vec", repeated, tag = "3")]
+        pub payloads: Vec<Vec<u8>>,
+        #[prost(message, optional, tag = "4")]
+        pub context: Option<SigningContext>,
+        #[prost(bytes = "vec", optional, tag = "5")]
+        pub tweak: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SignResponse {
+        #[prost(bytes = "vec", repeated, tag = "1")]
+        pub signatures: Vec<Vec<u8>>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::http::{Reply, serve};
+    use crate::wallet::scheme::Secp256k1;
+    use crate::wallet::signer::LocalSigner;
+
+    #[tokio::test]
+    async fn signs_over_http_and_maps_service_errors() {
+        let key = LocalSigner::from_bytes([3; 32]).unwrap();
+        let public_key = Signer::<Secp256k1>::public_key(&key);
+        let digest = [9u8; 32];
+        let signature = key.sign_prehash(&digest).await.unwrap();
+        let (url, server) = serve(vec![
+            Reply::new(
+                200,
+                serde_json::json!({ "public_key": hex::encode(&public_key) }).to_string(),
+            ),
+            Reply::new(
+                200,
+                serde_json::json!({ "signatures": [hex::encode(&signature)] }).to_string(),
+            ),
+            Reply::new(429, r#"{"error":"slow down"}"#),
+        ])
+        .await;
+
+        let config = RemoteSignerConfig::http(&url, "hot-1")
+            .with_auth_token("token")
+            .with_request_key(b"shared");
+        let signer = RemoteSigner::connect(config).await.unwrap();
+        assert_eq!(Signer::<Secp256k1>::public_key(&signer), public_key);
+        assert_eq!(Signer::<Secp256k1>::key_type(&signer), KeyType::Secp256k1);
+        assert_eq!(
+            Signer::<Secp256k1>::sign_prehash(&signer, &digest)
+                .await
+                .unwrap(),
+            signature
+        );
+        let batch = Signer::<Secp256k1>::sign_many(
+            &signer,
+            &[digest.to_vec(), digest.to_vec()],
+            SignMode::Prehash,
+            None,
+            4,
+        )
+        .await;
+        assert!(
+            batch
+                .iter()
+                .all(|r| matches!(r, Err(SignerError::Busy(reason)) if reason == "slow down"))
+        );
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /keys/hot-1 "));
+        let sign = &requests[1];
+        assert!(sign.starts_with("POST /keys/hot-1/sign "));
+        assert!(sign.to_lowercase().contains("authorization: bearer token"));
+        let body = sign.split("\r\n\r\n").nth(1).unwrap();
+        let request: SignRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(request.mode, "prehash");
+        assert_eq!(request.payloads, vec![hex::encode(digest)]);
+        // The HMAC covers timestamp, path and body.
+        let header = |name: &str| {
+            sign.lines()
+                .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+                .unwrap()
+                .to_string()
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"shared").unwrap();
+        mac.update(format!("{}\n/keys/hot-1/sign\n", header("x-signature-timestamp")).as_bytes());
+        mac.update(body.as_bytes());
+        assert_eq!(
+            header("x-signature"),
+            hex::encode(mac.finalize().into_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_the_metadata_only_when_asked() {
+        use crate::wallet::options::TravelRuleMetadata;
+
+        let key = LocalSigner::from_bytes([4; 32]).unwrap();
+        let public_key = Signer::<Secp256k1>::public_key(&key);
+        let digest = [6u8; 32];
+        let signature = key.sign_prehash(&digest).await.unwrap();
+        let context = SigningContext {
+            chain: "tron".into(),
+            summary: "Send 1 on tron".into(),
+            metadata: Some(TravelRuleMetadata {
+                reference: Some("wd-9".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        for share in [false, true] {
+            let (url, server) = serve(vec![
+                Reply::new(
+                    200,
+                    serde_json::json!({ "public_key": hex::encode(&public_key) }).to_string(),
+                ),
+                Reply::new(
+                    200,
+                    serde_json::json!({ "signatures": [hex::encode(&signature)] }).to_string(),
+                ),
+            ])
+            .await;
+            let config = RemoteSignerConfig::http(&url, "hot-1");
+            let config = if share {
+                config.with_metadata()
+            } else {
+                config
+            };
+            let signer = RemoteSigner::connect(config).await.unwrap();
+            Signer::<Secp256k1>::sign_prehash_with_context(&signer, &digest, &context)
+                .await
+                .unwrap();
+
+            let requests = server.await.unwrap();
+            let request: SignRequest =
+                serde_json::from_str(requests[1].split("\r\n\r\n").nth(1).unwrap()).unwrap();
+            let sent = request.context.unwrap();
+            assert_eq!(sent.summary, context.summary);
+            assert_eq!(sent.metadata.is_some(), share);
+        }
+    }
+
+    #[tokio::test]
+    async fn tweaks_taproot_batches_and_encodes_the_key_id() {
+        use crate::wallet::chain::sdk::taproot_output_key;
+        use k256::schnorr::signature::hazmat::PrehashVerifier;
+
+        let key = LocalSigner::from_bytes([5; 32]).unwrap();
+        let public_key = Signer::<Secp256k1>::public_key(&key);
+        let tweak = taproot_tweak(&public_key).unwrap();
+        let digest = [7u8; 32];
+        let signature = key.sign_schnorr(&digest, Some(&tweak)).await.unwrap();
+        let (url, server) = serve(vec![
+            Reply::new(
+                200,
+                serde_json::json!({ "public_key": hex::encode(&public_key) }).to_string(),
+            ),
+            Reply::new(
+                200,
+                serde_json::json!({ "signatures": [hex::encode(&signature)] }).to_string(),
+            ),
+        ])
+        .await;
+
+        let signer = RemoteSigner::connect(RemoteSignerConfig::http(&url, "hot/1 a"))
+            .await
+            .unwrap();
+        let batch =
+            Signer::<Secp256k1>::sign_many(&signer, &[digest.to_vec()], SignMode::Taproot, None, 1)
+                .await;
+        let returned = batch.into_iter().next().unwrap().unwrap();
+        let output =
+            k256::schnorr::VerifyingKey::from_bytes(&taproot_output_key(&public_key).unwrap())
+                .unwrap();
+        let returned = k256::schnorr::Signature::try_from(returned.as_slice()).unwrap();
+        assert!(output.verify_prehash(&digest, &returned).is_ok());
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /keys/hot%2F1%20a "));
+        let sign = &requests[1];
+        assert!(sign.starts_with("POST /keys/hot%2F1%20a/sign "));
+        let request: SignRequest =
+            serde_json::from_str(sign.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(request.mode, "taproot");
+        assert_eq!(request.tweak, Some(hex::encode(tweak)));
+    }
+}
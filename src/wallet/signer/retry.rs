@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::wallet::Signer;
+use crate::wallet::chain::Network;
+use crate::wallet::scheme::{KeyType, SignatureScheme};
+use crate::wallet::signer::{SignerError, SigningContext};
+
+/// Signer that asks `inner` again when it fails with a
+/// [transient](SignerError::is_transient) error, such as an HSM reporting it
+/// is busy or an MPC peer timing out.
+///
+/// Waits `delay` before the first retry and doubles it each time, up to
+/// `max_delay`; gives up after `retries` retries and returns the last error.
+/// Permanent failures, including a person rejecting the request, are returned
+/// at once.
+pub struct RetryingSigner<T> {
+    inner: T,
+    retries: u32,
+    delay: Duration,
+    max_delay: Duration,
+}
+
+impl<T> RetryingSigner<T> {
+    /// Three retries starting at 200ms, each wait capped at 5 seconds.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            retries: 3,
+            delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    async fn retry<'a, F, Fut>(&'a self, attempt: F) -> Result<Vec<u8>, SignerError>
+    where
+        F: Fn(&'a T) -> Fut,
+        Fut: Future<Output = Result<Vec<u8>, SignerError>>,
+    {
+        let mut delay = self.delay;
+        let mut retried = 0;
+        loop {
+            match attempt(&self.inner).await {
+                Err(e) if e.is_transient() && retried < self.retries => {
+                    retried += 1;
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(self.max_delay);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: SignatureScheme, T: Signer<S>> Signer<S> for RetryingSigner<T> {
+    async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign(message)).await
+    }
+    async fn sign_prehash(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_prehash(prehash)).await
+    }
+    async fn sign_with_context(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_with_context(message, context))
+            .await
+    }
+    async fn sign_prehash_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_prehash_with_context(prehash, context))
+            .await
+    }
+    async fn sign_recoverable(&self, prehash: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_recoverable(prehash)).await
+    }
+    async fn sign_recoverable_with_context(
+        &self,
+        prehash: &[u8],
+        context: &SigningContext,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_recoverable_with_context(prehash, context))
+            .await
+    }
+    async fn sign_schnorr(
+        &self,
+        prehash: &[u8],
+        tweak: Option<&[u8; 32]>,
+    ) -> Result<Vec<u8>, SignerError> {
+        self.retry(|inner| inner.sign_schnorr(prehash, tweak)).await
+    }
+    fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key()
+    }
+    fn key_type(&self) -> KeyType {
+        self.inner.key_type()
+    }
+    fn network(&self) -> Option<Network> {
+        self.inner.network()
+    }
+    fn can_sign(&self) -> bool {
+        self.inner.can_sign()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Fails with each scripted error in turn, then signs.
+    struct Flaky {
+        errors: Mutex<Vec<SignerError>>,
+        calls: Mutex<u32>,
+    }
+
+    impl Flaky {
+        fn new(mut errors: Vec<SignerError>) -> Self {
+            errors.reverse();
+            Self {
+                errors: Mutex::new(errors),
+                calls: Mutex::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Signer for Flaky {
+        async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, SignerError> {
+            *self.calls.lock().unwrap() += 1;
+            match self.errors.lock().unwrap().pop() {
+                Some(error) => Err(error),
+                None => Ok(message.to_vec()),
+            }
+        }
+        fn public_key(&self) -> Vec<u8> {
+            vec![2; 33]
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_blips_but_not_rejections() {
+        let signer = RetryingSigner::new(Flaky::new(vec![
+            SignerError::Busy("hsm".into()),
+            SignerError::Timeout("party 2".into()),
+        ]))
+        .with_retries(2, Duration::ZERO);
+        assert_eq!(signer.sign(b"tx").await.unwrap(), b"tx");
+        assert_eq!(*signer.inner().calls.lock().unwrap(), 3);
+
+        let capped = RetryingSigner::new(Flaky::new(vec![
+            SignerError::Busy("hsm".into()),
+            SignerError::Busy("hsm".into()),
+        ]))
+        .with_retries(1, Duration::ZERO);
+        assert!(matches!(
+            capped.sign(b"tx").await,
+            Err(SignerError::Busy(_))
+        ));
+
+        let rejected = RetryingSigner::new(Flaky::new(vec![SignerError::Rejected(
+            "user declined".into(),
+        )]))
+        .with_retries(5, Duration::ZERO);
+        assert!(matches!(
+            rejected.sign(b"tx").await,
+            Err(SignerError::Rejected(_))
+        ));
+        assert_eq!(*rejected.inner().calls.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+//! Dry runs of sends, for pre-flight checks and confirmation screens.
+//!
+//! A simulation goes through the first half of a send, building the
+//! transaction and preparing what the signer would be given, and stops
+//! there: nothing is signed or broadcast, no fee payer is asked to sponsor
+//! it and no approval or spend policy is consulted.
+
+use std::collections::BTreeMap;
+
+use crate::WalletError;
+use crate::node::{Provider, RawTransaction};
+use crate::wallet::chain::{Chain, TxSummary};
+use crate::wallet::options::SendOptions;
+use crate::wallet::signer::SigningContext;
+use crate::wallet::{Signer, Wallet};
+
+/// What a send would do, as found by [`Wallet::simulate_send`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendSimulation {
+    /// The unsigned transaction the provider built.
+    pub raw_tx: RawTransaction,
+    /// Exactly the payloads the signer would be asked to sign.
+    pub bytes_to_sign: Vec<Vec<u8>>,
+    /// What the signer would be shown.
+    pub context: SigningContext,
+    /// Payments and fee cap read back from `raw_tx`, where the chain can.
+    pub summary: Option<TxSummary>,
+    /// Estimated fee in the chain's base unit: the provider's dry run when
+    /// one ran, else the fee the transaction itself commits to, else the
+    /// provider's estimate at the send's priority.
+    pub fee: Option<u64>,
+    /// Resources the provider's dry run says the send would consume.
+    pub resources: BTreeMap<String, u64>,
+    /// Whether the provider dry-ran the transaction and found nothing wrong.
+    pub validated: bool,
+}
+
+impl<C: Chain, T: Signer<C::Scheme>> Wallet<C, T> {
+    /// Build the transaction a send of `amount` to `to` would broadcast and
+    /// report what it would cost and sign, without sending it.
+    pub async fn simulate_send(
+        &self,
+        provider: &dyn Provider,
+        to: &str,
+        amount: u64,
+    ) -> Result<SendSimulation, WalletError> {
+        self.simulate_send_with_options(provider, to, amount, &SendOptions::default(), false)
+            .await
+    }
+
+    /// [`simulate_send`](Self::simulate_send) with the options of the send.
+    /// With `validate`, the provider also dry-runs the transaction (e.g.
+    /// `triggerconstantcontract` on Tron), and the simulation fails where the
+    /// node would reject it.
+    pub async fn simulate_send_with_options(
+        &self,
+        provider: &dyn Provider,
+        to: &str,
+        amount: u64,
+        options: &SendOptions,
+        validate: bool,
+    ) -> Result<SendSimulation, WalletError> {
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        self.check_amount(amount)?;
+        let options = SendOptions {
+            fee_payer: None,
+            ..options.clone()
+        };
+
+        let raw_tx = self
+            .build_send(provider, &from, &to, amount, &options, false)
+            .await?;
+        // Nothing built here is sent, so nothing it reserved stays taken.
+        provider.discard_transaction(&raw_tx).await;
+        let bytes_to_sign = self.chain.prepare_transaction(&raw_tx)?;
+        let summary = self.chain.inspect_transaction(&raw_tx).ok();
+
+        let (fee, resources) = if validate {
+            let dry_run = provider.simulate_transaction(&raw_tx).await?;
+            (Some(dry_run.fee), dry_run.resources)
+        } else {
+            let fee = match summary.as_ref().and_then(|s| s.max_fee) {
+                Some(fee) => u64::try_from(fee).ok(),
+                None => provider
+                    .estimate_fee(&from, &to, amount)
+                    .await
+                    .ok()
+                    .map(|estimate| estimate.at(options.fee_priority)),
+            };
+            (fee, BTreeMap::new())
+        };
+
+        Ok(SendSimulation {
+            context: self.signing_context(&from, &to, amount, &options),
+            raw_tx,
+            bytes_to_sign,
+            summary,
+            fee,
+            resources,
+            validated: validate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::node::{NodeError, ReadProvider, Transaction, TransactionProvider, TxSimulation};
+    use crate::wallet::chain::LITECOIN;
+    use crate::wallet::chain::utxo::{SighashType, UnsignedUtxoTx, UtxoInput, UtxoTxBuilder};
+    use crate::wallet::signer::local::LocalSigner;
+
+    const TO: &str = "LNLS8Mt4ugdyRzn6yjAcD3312cbsX8R7xv";
+
+    /// Builds a local transaction spending one 100_000 coin of `from`, and
+    /// fails any broadcast.
+    struct Node {
+        from: String,
+    }
+
+    impl Node {
+        fn build(&self, amount: u64) -> UnsignedUtxoTx {
+            let script_pubkey = LITECOIN.script_pubkey(&self.from).unwrap();
+            UtxoTxBuilder::new(&LITECOIN)
+                .input(UtxoInput {
+                    prev_hash: [7; 32],
+                    output_index: 0,
+                    value: 100_000,
+                    script_pubkey,
+                    sequence: 0xffff_ffff,
+                    sighash: SighashType::All,
+                })
+                .pay(TO, amount)
+                .unwrap()
+                .pay(&self.from, 100_000 - amount - 2_000)
+                .unwrap()
+                .build()
+                .unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl ReadProvider for Node {
+        fn get_decimals(&self) -> u32 {
+            8
+        }
+        async fn get_transactions(&self, _: &str) -> Result<Vec<Transaction>, NodeError> {
+            Ok(Vec::new())
+        }
+        async fn get_block_number(&self) -> Result<u64, NodeError> {
+            Ok(1)
+        }
+        async fn get_balance(&self, _: &str) -> Result<String, NodeError> {
+            Ok("100000".into())
+        }
+    }
+
+    #[async_trait]
+    impl TransactionProvider for Node {
+        async fn create_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            amount: u64,
+        ) -> Result<RawTransaction, NodeError> {
+            Ok(self.build(amount).into_raw())
+        }
+        async fn broadcast_transaction(&self, _: &RawTransaction) -> Result<String, NodeError> {
+            Err(NodeError::Api("a simulation must not broadcast".into()))
+        }
+        async fn simulate_transaction(
+            &self,
+            raw_tx: &RawTransaction,
+        ) -> Result<TxSimulation, NodeError> {
+            let tx = UnsignedUtxoTx::from_raw(raw_tx).unwrap();
+            Ok(TxSimulation {
+                fee: tx.fee().unwrap(),
+                resources: [("vsize".to_string(), tx.estimated_vsize().unwrap())].into(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn simulations_build_and_prepare_without_sending() {
+        let wallet = Wallet::new(LocalSigner::from_bytes([1u8; 32]).unwrap(), LITECOIN);
+        let node = Node {
+            from: wallet.address().unwrap(),
+        };
+
+        let simulation = wallet.simulate_send(&node, TO, 50_000).await.unwrap();
+        assert_eq!(simulation.fee, Some(2_000));
+        assert_eq!(simulation.bytes_to_sign.len(), 1);
+        assert_eq!(
+            simulation.bytes_to_sign,
+            LITECOIN.prepare_transaction(&simulation.raw_tx).unwrap()
+        );
+        assert_eq!(simulation.summary.unwrap().payments[0].to, TO);
+        assert!(!simulation.validated);
+
+        let validated = wallet
+            .simulate_send_with_options(&node, TO, 50_000, &SendOptions::default(), true)
+            .await
+            .unwrap();
+        assert!(validated.validated);
+        assert_eq!(validated.resources["vsize"], 226);
+        assert!(matches!(
+            wallet.simulate_send(&node, TO, 1).await,
+            Err(WalletError::AmountTooSmall { .. })
+        ));
+    }
+}
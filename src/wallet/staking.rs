@@ -0,0 +1,296 @@
+//! Tron Stake 2.0: staking TRX for bandwidth or energy, lending those
+//! resources to other accounts and voting for super representatives.
+//!
+//! Every operation runs through the same create -> prepare -> sign ->
+//! finalize -> broadcast pipeline as a send, after checking the account can
+//! afford it so that the node's terse contract validation errors are rare.
+//! Spend policies see delegation as a transfer of the staked Sun to the
+//! receiver, taking it back as a zero-value one, and operations on the
+//! account's own stake and votes as zero-value transfers to itself. Only a
+//! delegation's receiver is screened.
+
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::node::ReadProvider;
+use crate::node::network::tron::{TronAccount, TronProvider, TronResource, TronVote};
+use crate::node::utils::format_units;
+use crate::wallet::chain::TvmChain;
+use crate::wallet::options::SendOptions;
+use crate::wallet::{BuiltSend, Signer, Wallet};
+
+/// Smallest amount `freezebalancev2` accepts, in Sun.
+pub const MIN_STAKE: u64 = 1_000_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StakingError {
+    #[error("cannot stake {amount} Sun: the minimum is {minimum} Sun")]
+    BelowMinimum { amount: u64, minimum: u64 },
+    #[error("insufficient TRX to stake: need {needed} Sun, have {available} Sun")]
+    InsufficientBalance { needed: u64, available: u64 },
+    #[error("insufficient {resource:?} stake: need {needed} Sun, have {available} Sun")]
+    InsufficientStake {
+        resource: TronResource,
+        needed: u64,
+        available: u64,
+    },
+    #[error("{0:?} cannot be delegated")]
+    NotDelegatable(TronResource),
+    #[error("cannot cast {needed} votes: voting power is {available}")]
+    InsufficientVotingPower { needed: u64, available: u64 },
+}
+
+impl<T: Signer> Wallet<TvmChain, T> {
+    /// Stake `amount` Sun for `resource`.
+    pub async fn freeze_balance(
+        &self,
+        provider: &TronProvider,
+        amount: u64,
+        resource: TronResource,
+    ) -> Result<String, WalletError> {
+        if amount < MIN_STAKE {
+            return Err(StakingError::BelowMinimum {
+                amount,
+                minimum: MIN_STAKE,
+            }
+            .into());
+        }
+        let owner = self.address()?;
+        let balance: u64 = provider.get_balance(&owner).await?.parse().unwrap_or(0);
+        if balance < amount {
+            return Err(StakingError::InsufficientBalance {
+                needed: amount,
+                available: balance,
+            }
+            .into());
+        }
+        let summary = format!("Stake {} TRX for {:?}", trx(amount), resource);
+        let send = own_operation(&owner, summary);
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider
+                .create_freeze_balance(&owner, amount, resource)
+                .await?)
+        })
+        .await
+    }
+
+    /// Unstake `amount` Sun of the account's own (undelegated) `resource`
+    /// stake. Withdraw it with [`withdraw_unfrozen`](Self::withdraw_unfrozen)
+    /// once its unlock time passes.
+    pub async fn unfreeze_balance(
+        &self,
+        provider: &TronProvider,
+        amount: u64,
+        resource: TronResource,
+    ) -> Result<String, WalletError> {
+        let owner = self.address()?;
+        let account = provider.get_account(&owner).await?;
+        check_own_stake(account.as_ref(), amount, resource)?;
+        let summary = format!("Unstake {} TRX of {:?}", trx(amount), resource);
+        let send = own_operation(&owner, summary);
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider
+                .create_unfreeze_balance(&owner, amount, resource)
+                .await?)
+        })
+        .await
+    }
+
+    /// Move every unstaked amount past its unlock time back to the balance.
+    pub async fn withdraw_unfrozen(&self, provider: &TronProvider) -> Result<String, WalletError> {
+        let owner = self.address()?;
+        let send = own_operation(&owner, "Withdraw unstaked TRX".to_string());
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider.create_withdraw_expire_unfreeze(&owner).await?)
+        })
+        .await
+    }
+
+    /// Lend the `resource` yielded by `balance` staked Sun to `receiver`.
+    pub async fn delegate_resource(
+        &self,
+        provider: &TronProvider,
+        receiver: &str,
+        balance: u64,
+        resource: TronResource,
+    ) -> Result<String, WalletError> {
+        if resource == TronResource::TronPower {
+            return Err(StakingError::NotDelegatable(resource).into());
+        }
+        let owner = self.address()?;
+        let receiver = self.resolve_recipient(receiver).await?;
+        let send = BuiltSend {
+            to: receiver.clone(),
+            amount: balance,
+            token: None,
+            summary: format!(
+                "Delegate {:?} of {} TRX to {}",
+                resource,
+                trx(balance),
+                receiver
+            ),
+        };
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            let account = provider.get_account(&owner).await?;
+            check_own_stake(account.as_ref(), balance, resource)?;
+            Ok(provider
+                .create_delegate_resource(&owner, &receiver, balance, resource)
+                .await?)
+        })
+        .await
+    }
+
+    /// Take back resources of `balance` staked Sun lent to `receiver`.
+    pub async fn undelegate_resource(
+        &self,
+        provider: &TronProvider,
+        receiver: &str,
+        balance: u64,
+        resource: TronResource,
+    ) -> Result<String, WalletError> {
+        let owner = self.address()?;
+        let receiver = self.resolve_recipient(receiver).await?;
+        let send = BuiltSend {
+            to: receiver.clone(),
+            amount: 0,
+            token: None,
+            summary: format!(
+                "Undelegate {:?} of {} TRX from {}",
+                resource,
+                trx(balance),
+                receiver
+            ),
+        };
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider
+                .create_undelegate_resource(&owner, &receiver, balance, resource)
+                .await?)
+        })
+        .await
+    }
+
+    /// Vote for super representatives, replacing the account's earlier votes.
+    pub async fn vote_witnesses(
+        &self,
+        provider: &TronProvider,
+        votes: &[TronVote],
+    ) -> Result<String, WalletError> {
+        let owner = self.address()?;
+        let account = provider.get_account(&owner).await?;
+        check_voting_power(account.as_ref(), votes)?;
+        let summary = format!("Vote for {} super representatives", votes.len());
+        let send = own_operation(&owner, summary);
+        self.send_built(provider, send, &SendOptions::default(), |_| async move {
+            Ok(provider.create_vote_witness(&owner, votes).await?)
+        })
+        .await
+    }
+}
+
+/// An operation on the account's own stake or votes, which moves nothing
+/// away from it.
+fn own_operation(owner: &str, summary: String) -> BuiltSend {
+    BuiltSend {
+        to: owner.to_string(),
+        amount: 0,
+        token: None,
+        summary,
+    }
+}
+
+/// `sun` in TRX, for summaries.
+fn trx(sun: u64) -> String {
+    format_units(&sun.to_string(), 6)
+}
+
+/// Whether `account` has `amount` Sun staked for `resource` for its own use,
+/// which is all it can unstake or delegate.
+fn check_own_stake(
+    account: Option<&TronAccount>,
+    amount: u64,
+    resource: TronResource,
+) -> Result<(), StakingError> {
+    let available = account
+        .map(|account| {
+            account
+                .staked
+                .iter()
+                .filter(|stake| stake.resource == resource)
+                .map(|stake| stake.amount)
+                .sum()
+        })
+        .unwrap_or(0);
+    if available < amount {
+        return Err(StakingError::InsufficientStake {
+            resource,
+            needed: amount,
+            available,
+        });
+    }
+    Ok(())
+}
+
+fn check_voting_power(
+    account: Option<&TronAccount>,
+    votes: &[TronVote],
+) -> Result<(), StakingError> {
+    let needed: u64 = votes.iter().map(|vote| vote.count).sum();
+    let available = account.map(TronAccount::voting_power).unwrap_or(0);
+    if available < needed {
+        return Err(StakingError::InsufficientVotingPower { needed, available });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::network::tron::TronStake;
+
+    fn stake(resource: TronResource, amount: u64) -> TronStake {
+        TronStake {
+            resource,
+            amount,
+            expire_time: None,
+        }
+    }
+
+    #[test]
+    fn only_own_stake_can_be_unstaked_delegated_or_voted() {
+        let account = TronAccount {
+            address: "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT".into(),
+            balance: 0,
+            create_time: 0,
+            staked: vec![stake(TronResource::Energy, 5_000_000)],
+            delegated: vec![stake(TronResource::Energy, 3_000_000)],
+            unstaking: Vec::new(),
+            votes: Vec::new(),
+            owner_permission: None,
+            active_permissions: Vec::new(),
+        };
+        assert!(check_own_stake(Some(&account), 5_000_000, TronResource::Energy).is_ok());
+        assert_eq!(
+            check_own_stake(Some(&account), 6_000_000, TronResource::Energy),
+            Err(StakingError::InsufficientStake {
+                resource: TronResource::Energy,
+                needed: 6_000_000,
+                available: 5_000_000,
+            })
+        );
+        assert!(check_own_stake(None, 1, TronResource::Bandwidth).is_err());
+
+        // Delegated stakes still vote.
+        let vote = |count| TronVote {
+            address: "TLyqzVGLV1srkB7dToTAEqgDSfPtXRJZYH".into(),
+            count,
+        };
+        assert!(check_voting_power(Some(&account), &[vote(8)]).is_ok());
+        assert_eq!(
+            check_voting_power(Some(&account), &[vote(8), vote(1)]),
+            Err(StakingError::InsufficientVotingPower {
+                needed: 9,
+                available: 8,
+            })
+        );
+    }
+}
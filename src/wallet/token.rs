@@ -0,0 +1,960 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::WalletError;
+use crate::monitor::filter::Transfer;
+use crate::node::network::eth::{EthFees, EthProvider};
+use crate::node::network::tron::{SUN_PER_BANDWIDTH_BYTE, TronProvider};
+use crate::node::token::{Allowance, Token, TokenPreset};
+use crate::node::utils::{format_units, parse_units};
+use crate::node::{Amount, FeeOptions, NodeError, Provider, ReadProvider, TxPage, TxQuery};
+use crate::wallet::chain::{Chain, ChainError, EvmChain, TvmChain};
+use crate::wallet::options::SendOptions;
+use crate::wallet::{BuiltSend, Signer, Wallet};
+
+/// Bandwidth of a typical TRC-20 transfer, in bytes.
+const TRC20_TRANSFER_BANDWIDTH: u64 = 345;
+/// Headroom on top of the estimated energy cost when setting `fee_limit`.
+const FEE_LIMIT_HEADROOM_PERCENT: u64 = 20;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TokenError {
+    #[error("{symbol} is not deployed on {network}")]
+    WrongNetwork { symbol: String, network: String },
+    #[error("insufficient {symbol} balance: need {needed}, have {available}")]
+    InsufficientBalance {
+        symbol: String,
+        needed: String,
+        available: String,
+    },
+    #[error("insufficient ETH for gas: need {needed} wei, have {available} wei")]
+    InsufficientGas { needed: u128, available: u128 },
+    #[error("fee_limit of {fee_limit} Sun is below the {needed} Sun of energy this call burns")]
+    FeeLimitTooLow { fee_limit: u64, needed: u64 },
+    #[error("refusing to let {spender} spend unlimited {symbol} without allow_unlimited_approval")]
+    UnlimitedApproval { symbol: String, spender: String },
+}
+
+/// What a TRC-20 transfer will cost the sender, in Sun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TronFeeEstimate {
+    pub energy_needed: u64,
+    pub energy_available: u64,
+    pub energy_price: u64,
+    pub bandwidth_available: u64,
+}
+
+impl TronFeeEstimate {
+    /// TRX burned once staked/delegated energy and bandwidth are used up.
+    pub fn trx_burn(&self) -> u64 {
+        let energy = self.energy_needed.saturating_sub(self.energy_available) * self.energy_price;
+        let bandwidth = if self.bandwidth_available >= TRC20_TRANSFER_BANDWIDTH {
+            0
+        } else {
+            TRC20_TRANSFER_BANDWIDTH * SUN_PER_BANDWIDTH_BYTE
+        };
+        energy + bandwidth
+    }
+
+    /// What the account lacks when it cannot burn [`trx_burn`](Self::trx_burn):
+    /// the energy, if staked energy does not cover the call, else the bandwidth.
+    pub fn shortfall(&self) -> NodeError {
+        if self.energy_available < self.energy_needed {
+            NodeError::InsufficientResources {
+                resource: "energy".to_string(),
+                needed: self.energy_needed,
+                available: self.energy_available,
+            }
+        } else {
+            NodeError::InsufficientResources {
+                resource: "bandwidth".to_string(),
+                needed: TRC20_TRANSFER_BANDWIDTH,
+                available: self.bandwidth_available,
+            }
+        }
+    }
+
+    /// `fee_limit` covering the whole energy cost plus headroom, independent of staked energy.
+    pub fn fee_limit(&self) -> u64 {
+        let full = self.energy_needed * self.energy_price;
+        full + full * FEE_LIMIT_HEADROOM_PERCENT / 100
+    }
+
+    /// `requested` if it covers the energy that will be burned, else the estimate.
+    /// A lower cap would only make the call run out of energy and still burn the fee.
+    pub fn resolve_fee_limit(&self, requested: Option<u64>) -> Result<u64, TokenError> {
+        let Some(fee_limit) = requested else {
+            return Ok(self.fee_limit());
+        };
+        let needed = self.energy_needed.saturating_sub(self.energy_available) * self.energy_price;
+        if fee_limit < needed {
+            return Err(TokenError::FeeLimitTooLow { fee_limit, needed });
+        }
+        Ok(fee_limit)
+    }
+}
+
+impl<T: Signer> Wallet<TvmChain, T> {
+    /// Send USDT on the provider's network. `amount` is in whole tokens, e.g. `"12.5"`.
+    pub async fn send_usdt(
+        &self,
+        provider: &TronProvider,
+        to: &str,
+        amount: &str,
+    ) -> Result<String, WalletError> {
+        let token = provider.usdt().ok_or_else(|| TokenError::WrongNetwork {
+            symbol: "USDT".to_string(),
+            network: provider.network().unwrap_or("custom").to_string(),
+        })?;
+        self.send_token(provider, &token, to, amount).await
+    }
+
+    /// Send a TRC-20 token after checking the token balance and that the account
+    /// can pay the energy and bandwidth the transfer will burn.
+    pub async fn send_token(
+        &self,
+        provider: &TronProvider,
+        token: &TokenPreset,
+        to: &str,
+        amount: &str,
+    ) -> Result<String, WalletError> {
+        self.send_token_with_options(provider, token, to, amount, &SendOptions::default())
+            .await
+    }
+
+    /// Same as [`send_token`](Self::send_token) with extra per-send options.
+    /// Like a coin send, the transfer is audited with `options.metadata`,
+    /// screened and shown to hooks; `options.fee_limit` caps the energy burned.
+    pub async fn send_token_with_options(
+        &self,
+        provider: &TronProvider,
+        token: &TokenPreset,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.transfer_trc20(provider, &token.into(), to, amount, options))
+            .await
+    }
+
+    async fn transfer_trc20(
+        &self,
+        provider: &TronProvider,
+        token: &Token,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        if let Some(network) = provider.network()
+            && network != token.chain_id
+        {
+            return Err(TokenError::WrongNetwork {
+                symbol: token.symbol.clone(),
+                network: network.to_string(),
+            }
+            .into());
+        }
+
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        let amount = parse_units(amount, token.decimals)?;
+        let send = BuiltSend {
+            to: to.clone(),
+            amount: u64::try_from(amount).unwrap_or(u64::MAX),
+            token: Some(token.contract.clone()),
+            summary: format!(
+                "Send {} {} on {} to {}",
+                format_units(&amount.to_string(), token.decimals),
+                token.symbol,
+                self.chain.id(),
+                to
+            ),
+        };
+        self.send_built(provider, send, options, |options| async move {
+            let balance = provider.trc20_balance(&from, &token.contract).await?;
+            if balance < amount {
+                return Err(TokenError::InsufficientBalance {
+                    symbol: token.symbol.clone(),
+                    needed: format_units(&amount.to_string(), token.decimals),
+                    available: format_units(&balance.to_string(), token.decimals),
+                }
+                .into());
+            }
+
+            let energy_needed = provider
+                .estimate_trc20_transfer_energy(&from, &to, &token.contract, amount)
+                .await?;
+            let fee_limit = self
+                .affordable_fee_limit(provider, &from, energy_needed, options.fee_limit)
+                .await?;
+
+            Ok(provider
+                .create_trc20_transfer(&from, &to, &token.contract, amount, fee_limit)
+                .await?)
+        })
+        .await
+    }
+
+    async fn approve_trc20(
+        &self,
+        provider: &TronProvider,
+        token: &Token,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        check_approval(token, spender, amount, options)?;
+        if let Some(network) = provider.network()
+            && network != token.chain_id
+        {
+            return Err(TokenError::WrongNetwork {
+                symbol: token.symbol.clone(),
+                network: network.to_string(),
+            }
+            .into());
+        }
+
+        let owner = self.address()?;
+        let spender = self.resolve_recipient(spender).await?;
+        let send = approval_send(self.chain.id(), token, &spender, amount);
+        self.send_built(provider, send, options, |options| async move {
+            let energy_needed = provider
+                .estimate_trc20_approve_energy(&owner, &spender, &token.contract, amount)
+                .await?;
+            let fee_limit = self
+                .affordable_fee_limit(provider, &owner, energy_needed, options.fee_limit)
+                .await?;
+
+            Ok(provider
+                .create_trc20_approve(&owner, &spender, &token.contract, amount, fee_limit)
+                .await?)
+        })
+        .await
+    }
+
+    /// `fee_limit` for a contract call of `from` using `energy_needed`,
+    /// once the account is known to afford the energy and bandwidth it burns.
+    pub(super) async fn affordable_fee_limit(
+        &self,
+        provider: &TronProvider,
+        from: &str,
+        energy_needed: u64,
+        fee_limit: Option<u64>,
+    ) -> Result<u64, WalletError> {
+        let resources = provider.account_resources(from).await?;
+        let estimate = TronFeeEstimate {
+            energy_needed,
+            energy_available: resources.energy,
+            energy_price: provider.energy_price().await?,
+            bandwidth_available: resources.bandwidth,
+        };
+        let fee_limit = estimate.resolve_fee_limit(fee_limit)?;
+        let trx: u64 = provider.get_balance(from).await?.parse().unwrap_or(0);
+        if trx < estimate.trx_burn() {
+            return Err(estimate.shortfall().into());
+        }
+        Ok(fee_limit)
+    }
+}
+
+impl<T: Signer> Wallet<EvmChain, T> {
+    /// Send USDT on the provider's network. `amount` is in whole tokens, e.g. `"12.5"`.
+    pub async fn send_usdt(
+        &self,
+        provider: &EthProvider,
+        to: &str,
+        amount: &str,
+    ) -> Result<String, WalletError> {
+        let token = provider
+            .usdt()
+            .await?
+            .ok_or_else(|| TokenError::WrongNetwork {
+                symbol: "USDT".to_string(),
+                network: self.chain.id().to_string(),
+            })?;
+        self.send_token(provider, &token, to, amount).await
+    }
+
+    /// Send an ERC-20 token after checking the token balance and that the account
+    /// holds enough ETH for the worst-case gas cost.
+    pub async fn send_token(
+        &self,
+        provider: &EthProvider,
+        token: &TokenPreset,
+        to: &str,
+        amount: &str,
+    ) -> Result<String, WalletError> {
+        self.send_token_with_options(provider, token, to, amount, &SendOptions::default())
+            .await
+    }
+
+    /// Same as [`send_token`](Self::send_token) with extra per-send options.
+    /// Like a coin send, the transfer is audited with `options.metadata`,
+    /// screened and shown to hooks; `options.fee_limit` caps the fee, in wei.
+    pub async fn send_token_with_options(
+        &self,
+        provider: &EthProvider,
+        token: &TokenPreset,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.transfer_erc20(provider, &token.into(), to, amount, options))
+            .await
+    }
+
+    async fn transfer_erc20(
+        &self,
+        provider: &EthProvider,
+        token: &Token,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        if token.chain_id != self.chain.id() {
+            return Err(TokenError::WrongNetwork {
+                symbol: token.symbol.clone(),
+                network: self.chain.id().to_string(),
+            }
+            .into());
+        }
+
+        let from = self.address()?;
+        let to = self.resolve_recipient(to).await?;
+        let amount = parse_units(amount, token.decimals)?;
+        let send = BuiltSend {
+            to: to.clone(),
+            amount: u64::try_from(amount).unwrap_or(u64::MAX),
+            token: Some(token.contract.clone()),
+            summary: format!(
+                "Send {} {} on {} to {}",
+                format_units(&amount.to_string(), token.decimals),
+                token.symbol,
+                self.chain.id(),
+                to
+            ),
+        };
+        self.send_built(provider, send, options, |options| async move {
+            let balance = provider.erc20_balance(&from, &token.contract).await?;
+            if balance < amount {
+                return Err(TokenError::InsufficientBalance {
+                    symbol: token.symbol.clone(),
+                    needed: format_units(&amount.to_string(), token.decimals),
+                    available: format_units(&balance.to_string(), token.decimals),
+                }
+                .into());
+            }
+
+            let fees = provider
+                .estimate_erc20_transfer(&from, &to, &token.contract, amount)
+                .await?;
+            check_gas(provider, &from, &fees, options.fee_limit).await?;
+
+            Ok(provider
+                .create_erc20_transfer(&from, &to, &token.contract, amount, &fees)
+                .await?)
+        })
+        .await
+    }
+
+    async fn approve_erc20(
+        &self,
+        provider: &EthProvider,
+        token: &Token,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        check_approval(token, spender, amount, options)?;
+        if token.chain_id != self.chain.id() {
+            return Err(TokenError::WrongNetwork {
+                symbol: token.symbol.clone(),
+                network: self.chain.id().to_string(),
+            }
+            .into());
+        }
+
+        let owner = self.address()?;
+        let spender = self.resolve_recipient(spender).await?;
+        let send = approval_send(self.chain.id(), token, &spender, amount);
+        self.send_built(provider, send, options, |options| async move {
+            let fees = provider
+                .estimate_erc20_approve(&owner, &spender, &token.contract, amount)
+                .await?;
+            check_gas(provider, &owner, &fees, options.fee_limit).await?;
+
+            Ok(provider
+                .create_erc20_approve(&owner, &spender, &token.contract, amount, &fees)
+                .await?)
+        })
+        .await
+    }
+}
+
+/// Fail unless `from` holds the ETH for the worst-case cost of `fees`, and
+/// that cost is within `fee_limit` wei.
+async fn check_gas(
+    provider: &EthProvider,
+    from: &str,
+    fees: &EthFees,
+    fee_limit: Option<u64>,
+) -> Result<(), WalletError> {
+    let fee_options = FeeOptions {
+        fee_limit,
+        ..FeeOptions::default()
+    };
+    fee_options.check_limit(u64::try_from(fees.max_cost()).unwrap_or(u64::MAX))?;
+    let eth: u128 = provider.get_balance(from).await?.parse().unwrap_or(0);
+    if eth < fees.max_cost() {
+        return Err(TokenError::InsufficientGas {
+            needed: fees.max_cost(),
+            available: eth,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// An approval of `amount` to `spender` on `chain`, as the spend policies
+/// see it: a transfer of that much of the token, all of it if unlimited.
+fn approval_send(chain: &str, token: &Token, spender: &str, amount: Allowance) -> BuiltSend {
+    let (amount, allowance) = match amount {
+        Allowance::Limited(amount) => (
+            u64::try_from(amount).unwrap_or(u64::MAX),
+            format_units(&amount.to_string(), token.decimals),
+        ),
+        Allowance::Unlimited => (u64::MAX, "unlimited".to_string()),
+    };
+    BuiltSend {
+        to: spender.to_string(),
+        amount,
+        token: Some(token.contract.clone()),
+        summary: format!(
+            "Approve {} to spend {} {} on {}",
+            spender, allowance, token.symbol, chain
+        ),
+    }
+}
+
+/// Refuse an unlimited approval unless `options` opt in to it: a spender
+/// holding one can drain the token from the account at any later time.
+fn check_approval(
+    token: &Token,
+    spender: &str,
+    amount: Allowance,
+    options: &SendOptions,
+) -> Result<(), TokenError> {
+    if amount == Allowance::Unlimited && !options.allow_unlimited_approval {
+        return Err(TokenError::UnlimitedApproval {
+            symbol: token.symbol.clone(),
+            spender: spender.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Wallets of chains with a token standard, as [`TokenWallet`] drives them.
+#[async_trait]
+pub trait TokenTransfer: Sync {
+    /// Provider the chain's token transfers are built with.
+    type Provider: Provider;
+
+    /// Address holding the wallet's tokens.
+    fn holder(&self) -> Result<String, ChainError>;
+
+    /// Send `amount` whole tokens of `token` to `to`, after the chain's
+    /// balance and fee checks.
+    async fn transfer_token(
+        &self,
+        provider: &Self::Provider,
+        token: &Token,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError>;
+
+    /// Let `spender` move up to `amount` of the wallet's `token`, replacing
+    /// any earlier approval. [`Allowance::Unlimited`] is refused unless
+    /// [`SendOptions::allow_unlimited_approval`] is set.
+    async fn approve_token(
+        &self,
+        provider: &Self::Provider,
+        token: &Token,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError>;
+
+    /// What `spender` may still move of `owner`'s `token`.
+    async fn token_allowance(
+        &self,
+        provider: &Self::Provider,
+        token: &Token,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Allowance, WalletError>;
+}
+
+/// TRC-20 transfers.
+#[async_trait]
+impl<T: Signer> TokenTransfer for Wallet<TvmChain, T> {
+    type Provider = TronProvider;
+
+    fn holder(&self) -> Result<String, ChainError> {
+        self.address()
+    }
+
+    async fn transfer_token(
+        &self,
+        provider: &TronProvider,
+        token: &Token,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.transfer_trc20(provider, token, to, amount, options))
+            .await
+    }
+
+    async fn approve_token(
+        &self,
+        provider: &TronProvider,
+        token: &Token,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.approve_trc20(provider, token, spender, amount, options))
+            .await
+    }
+
+    async fn token_allowance(
+        &self,
+        provider: &TronProvider,
+        token: &Token,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Allowance, WalletError> {
+        Ok(provider
+            .trc20_allowance(owner, spender, &token.contract)
+            .await?)
+    }
+}
+
+/// ERC-20 transfers.
+#[async_trait]
+impl<T: Signer> TokenTransfer for Wallet<EvmChain, T> {
+    type Provider = EthProvider;
+
+    fn holder(&self) -> Result<String, ChainError> {
+        self.address()
+    }
+
+    async fn transfer_token(
+        &self,
+        provider: &EthProvider,
+        token: &Token,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.transfer_erc20(provider, token, to, amount, options))
+            .await
+    }
+
+    async fn approve_token(
+        &self,
+        provider: &EthProvider,
+        token: &Token,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        let correlation_id = options.correlation_id.clone().unwrap_or_default();
+        correlation_id
+            .scope(self.approve_erc20(provider, token, spender, amount, options))
+            .await
+    }
+
+    async fn token_allowance(
+        &self,
+        provider: &EthProvider,
+        token: &Token,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Allowance, WalletError> {
+        Ok(provider
+            .erc20_allowance(owner, spender, &token.contract)
+            .await?)
+    }
+}
+
+/// One transfer of a [`TokenWallet`]'s token to or from its holder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHistoryEntry {
+    /// Hash of the transaction that moved the tokens.
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Amount,
+    /// As the provider reports it (milliseconds on Tron).
+    pub timestamp: u64,
+    pub block_number: u64,
+    pub status: String,
+}
+
+/// One page of [`TokenWallet::history`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenHistoryPage {
+    pub transfers: Vec<TokenHistoryEntry>,
+    /// Cursor of the following page of transactions; `None` on the last one.
+    pub next: Option<String>,
+}
+
+impl TokenHistoryPage {
+    /// The transfers of `token` to or from `holder` in `page`.
+    fn of(page: TxPage, holder: &str, token: &Token) -> Result<Self, NodeError> {
+        let mut transfers = Vec::new();
+        for tx in &page.transactions {
+            for transfer in Transfer::all(tx) {
+                let Some(contract) = &transfer.token else {
+                    continue;
+                };
+                if !token.is_contract(contract)
+                    || (transfer.from != holder && transfer.to != holder)
+                {
+                    continue;
+                }
+                transfers.push(TokenHistoryEntry {
+                    hash: tx.hash.clone(),
+                    amount: Amount::from_base_units(&transfer.value, token.decimals)?,
+                    from: transfer.from,
+                    to: transfer.to,
+                    timestamp: tx.timestamp,
+                    block_number: tx.block_number,
+                    status: tx.status.clone(),
+                });
+            }
+        }
+        Ok(Self {
+            transfers,
+            next: page.next,
+        })
+    }
+}
+
+/// One token of one wallet, the same on every chain: balances, transfers
+/// and history without the application knowing TRC-20 from ERC-20.
+///
+/// ```no_run
+/// # use flow_wallet::node::network::tron::TronProvider;
+/// # use flow_wallet::node::token::USDT_TRON;
+/// # use flow_wallet::wallet::{Wallet, signer::local::LocalSigner, token::TokenWallet};
+/// # async fn run(wallet: Wallet<flow_wallet::wallet::chain::TvmChain, LocalSigner>, provider: TronProvider) -> Result<(), flow_wallet::WalletError> {
+/// let usdt = TokenWallet::new(&wallet, &provider, USDT_TRON);
+/// println!("{}", usdt.balance().await?);
+/// usdt.transfer("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", "12.5").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TokenWallet<'a, W: TokenTransfer> {
+    wallet: &'a W,
+    provider: &'a W::Provider,
+    token: Token,
+}
+
+impl<'a, W: TokenTransfer> TokenWallet<'a, W> {
+    pub fn new(wallet: &'a W, provider: &'a W::Provider, token: impl Into<Token>) -> Self {
+        Self {
+            wallet,
+            provider,
+            token: token.into(),
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// What the wallet holds of the token.
+    pub async fn balance(&self) -> Result<Amount, WalletError> {
+        let balance = self
+            .provider
+            .get_token_balance(&self.wallet.holder()?, &self.token.contract)
+            .await?;
+        Ok(Amount::from_base_units(&balance, self.token.decimals)?)
+    }
+
+    /// Send `amount` whole tokens, e.g. `"12.5"`, to `to`.
+    pub async fn transfer(&self, to: &str, amount: &str) -> Result<String, WalletError> {
+        self.transfer_with_options(to, amount, &SendOptions::default())
+            .await
+    }
+
+    /// [`transfer`](Self::transfer) with extra per-send options, as
+    /// `send_token_with_options` takes them.
+    pub async fn transfer_with_options(
+        &self,
+        to: &str,
+        amount: &str,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        self.wallet
+            .transfer_token(self.provider, &self.token, to, amount, options)
+            .await
+    }
+
+    /// Let `spender` move up to `amount` of the wallet's tokens; see
+    /// [`TokenTransfer::approve_token`].
+    pub async fn approve(&self, spender: &str, amount: Allowance) -> Result<String, WalletError> {
+        self.approve_with_options(spender, amount, &SendOptions::default())
+            .await
+    }
+
+    /// [`approve`](Self::approve) with extra per-send options, among them
+    /// `options.allow_unlimited_approval`. The approval is audited and
+    /// screened like a transfer to the spender.
+    pub async fn approve_with_options(
+        &self,
+        spender: &str,
+        amount: Allowance,
+        options: &SendOptions,
+    ) -> Result<String, WalletError> {
+        self.wallet
+            .approve_token(self.provider, &self.token, spender, amount, options)
+            .await
+    }
+
+    /// What `spender` may still move of `owner`'s tokens.
+    pub async fn allowance(&self, owner: &str, spender: &str) -> Result<Allowance, WalletError> {
+        self.wallet
+            .token_allowance(self.provider, &self.token, owner, spender)
+            .await
+    }
+
+    /// Transfers of the token in the page of the wallet's history `query`
+    /// selects. Needs a provider that lists history, which a plain EVM
+    /// node does not.
+    pub async fn history(&self, query: &TxQuery) -> Result<TokenHistoryPage, WalletError> {
+        let holder = self.wallet.holder()?;
+        let page = self.provider.get_transactions_page(&holder, query).await?;
+        Ok(TokenHistoryPage::of(page, &holder, &self.token)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_holders_transfers_of_the_token() {
+        use crate::node::Transaction;
+        use crate::node::details::{TokenTransfer, TvmTxDetails, TxDetails};
+        use crate::node::token::USDT_TRON;
+
+        let usdt = Token::from(USDT_TRON);
+        assert_eq!(usdt.chain_id, "tron");
+        let transfer = |hash: &str, token: &str, from: &str, to: &str| Transaction {
+            hash: hash.to_string(),
+            from: from.to_string(),
+            to: token.to_string(),
+            value: "0".to_string(),
+            block_number: 7,
+            timestamp: 21_000,
+            status: "SUCCESS".to_string(),
+            raw: None,
+            details: Some(TxDetails::Tvm(TvmTxDetails {
+                contract_type: "TriggerSmartContract".into(),
+                contract_address: Some(token.to_string()),
+                token_transfers: vec![TokenTransfer {
+                    token: token.to_string(),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    value: "12500000".to_string(),
+                }],
+                ..TvmTxDetails::default()
+            })),
+        };
+        let page = TxPage {
+            transactions: vec![
+                transfer("aa", USDT_TRON.contract, "TPayer", "TMe"),
+                transfer("bb", "TOtherToken", "TPayer", "TMe"),
+                transfer("cc", USDT_TRON.contract, "TPayer", "TSomeoneElse"),
+            ],
+            next: Some("cursor".to_string()),
+        };
+        let history = TokenHistoryPage::of(page, "TMe", &usdt).unwrap();
+        let [entry] = history.transfers.try_into().unwrap();
+        assert_eq!(entry.hash, "aa");
+        assert_eq!(entry.amount, Amount::parse("12.5", 6).unwrap());
+        assert_eq!(history.next.as_deref(), Some("cursor"));
+    }
+
+    #[tokio::test]
+    async fn unlimited_approvals_need_an_explicit_opt_in() {
+        use crate::node::token::USDT_TRON;
+        use crate::testing::MockSigner;
+        use crate::wallet::chain::TRON;
+
+        let wallet = Wallet::new(MockSigner::default(), TRON);
+        // Refused before the provider is asked anything.
+        let provider = TronProvider::with_url("http://127.0.0.1:9".to_string());
+        let usdt = TokenWallet::new(&wallet, &provider, USDT_TRON);
+        let spender = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        assert!(matches!(
+            usdt.approve(spender, Allowance::Unlimited).await,
+            Err(WalletError::Token(TokenError::UnlimitedApproval { .. }))
+        ));
+        let opted_in = SendOptions {
+            allow_unlimited_approval: true,
+            ..SendOptions::default()
+        };
+        assert!(matches!(
+            usdt.approve_with_options(spender, Allowance::Unlimited, &opted_in)
+                .await,
+            Err(WalletError::Node(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn token_sends_are_audited_and_shown_to_hooks_with_their_metadata() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::node::token::USDT_TRON;
+        use crate::testing::MockSigner;
+        use crate::wallet::audit::{AuditKind, MemoryAuditLog};
+        use crate::wallet::chain::TRON;
+        use crate::wallet::hooks::{HookError, TransactionHook};
+        use crate::wallet::options::{TravelRuleMetadata, TravelRuleParty};
+        use crate::wallet::signer::SigningContext;
+
+        #[derive(Default)]
+        struct Seen(Mutex<Vec<SigningContext>>);
+
+        #[async_trait]
+        impl TransactionHook for Seen {
+            async fn pre_create(
+                &self,
+                send: &SigningContext,
+                _: &mut SendOptions,
+            ) -> Result<(), HookError> {
+                self.0.lock().unwrap().push(send.clone());
+                Ok(())
+            }
+        }
+
+        let log = Arc::new(MemoryAuditLog::new());
+        let seen = Arc::new(Seen::default());
+        let wallet = Wallet::new(MockSigner::default(), TRON)
+            .with_audit_log(log.clone())
+            .with_hook(seen.clone());
+        // Fails at the balance check, once audited and past the hooks.
+        let provider = TronProvider::with_url("http://127.0.0.1:9".to_string());
+        let usdt = TokenWallet::new(&wallet, &provider, USDT_TRON);
+        let to = "TT5iK8oqGEyRKJAnRwrLSZ4fM5y77F2LNT";
+        let metadata = TravelRuleMetadata {
+            originator: TravelRuleParty {
+                name: "Alice".into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let options = SendOptions {
+            metadata: Some(metadata.clone()),
+            ..SendOptions::default()
+        };
+        assert!(matches!(
+            usdt.transfer_with_options(to, "1.5", &options).await,
+            Err(WalletError::Node(_))
+        ));
+
+        let records = log.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].kind, AuditKind::SendRequested);
+        assert!(matches!(records[1].kind, AuditKind::SendFailed { .. }));
+        for record in &records {
+            assert_eq!(record.amount, 1_500_000);
+            assert_eq!(record.token.as_deref(), Some(USDT_TRON.contract));
+            assert_eq!(record.metadata, Some(metadata.clone()));
+        }
+        let seen = seen.0.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            seen[0].summary,
+            format!("Send 1.500000 USDT on tron to {}", to)
+        );
+        assert_eq!(seen[0].metadata, Some(metadata));
+    }
+
+    #[test]
+    fn staked_resources_reduce_the_burn() {
+        let mut estimate = TronFeeEstimate {
+            energy_needed: 65_000,
+            energy_available: 0,
+            energy_price: 420,
+            bandwidth_available: 0,
+        };
+        assert_eq!(estimate.trx_burn(), 65_000 * 420 + 345_000);
+        assert_eq!(estimate.fee_limit(), 65_000 * 420 * 12 / 10);
+
+        estimate.energy_available = 100_000;
+        estimate.bandwidth_available = 600;
+        assert_eq!(estimate.trx_burn(), 0);
+        // The limit does not depend on what is staked.
+        assert_eq!(estimate.fee_limit(), 65_000 * 420 * 12 / 10);
+    }
+
+    #[test]
+    fn shortfall_names_the_missing_resource() {
+        let mut estimate = TronFeeEstimate {
+            energy_needed: 65_000,
+            energy_available: 15_000,
+            energy_price: 420,
+            bandwidth_available: 100,
+        };
+        let error = estimate.shortfall();
+        assert_eq!(
+            error.to_string(),
+            "Insufficient energy: need 65000, have 15000 (50000 short)"
+        );
+
+        estimate.energy_available = 65_000;
+        assert!(matches!(
+            estimate.shortfall(),
+            NodeError::InsufficientResources { resource, needed: TRC20_TRANSFER_BANDWIDTH, available: 100 }
+                if resource == "bandwidth"
+        ));
+    }
+
+    #[test]
+    fn configured_fee_limit_must_cover_burned_energy() {
+        let estimate = TronFeeEstimate {
+            energy_needed: 65_000,
+            energy_available: 15_000,
+            energy_price: 420,
+            bandwidth_available: 0,
+        };
+        assert_eq!(estimate.resolve_fee_limit(None), Ok(estimate.fee_limit()));
+        assert_eq!(
+            estimate.resolve_fee_limit(Some(50_000 * 420)),
+            Ok(50_000 * 420)
+        );
+        assert_eq!(
+            estimate.resolve_fee_limit(Some(1_000_000)),
+            Err(TokenError::FeeLimitTooLow {
+                fee_limit: 1_000_000,
+                needed: 50_000 * 420,
+            })
+        );
+    }
+}
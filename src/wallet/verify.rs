@@ -0,0 +1,337 @@
+//! Checking signatures on a chain's terms, for deposit proofs, logins and
+//! co-signed transactions: over a transaction payload, a bare digest, or a
+//! message behind the chain's prefix (see [`MessageFormat`]), by a known
+//! public key or by whoever holds an address.
+//!
+//! ECDSA signatures may be DER, `r || s`, or `r || s || v` with `v` of 0/1
+//! or 27/28, in which case `v` must be right too. A high-s signature
+//! verifies like its low-s twin; whether the chain accepts one is for its
+//! [`SignaturePolicy`](super::chain::SignaturePolicy). Schnorr signatures of
+//! Taproot chains are checked against the key's output key, so they need
+//! the public key.
+//!
+//! For signed messages in the text form wallets pass around (hex, or
+//! BIP-137 base64), see [`message::verify_message`](super::message::verify_message).
+
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use k256::schnorr::signature::hazmat::PrehashVerifier;
+
+use crate::node::RawTransaction;
+use crate::wallet::chain::caps::same_address;
+use crate::wallet::chain::sdk::{parse_sec1, sha256, taproot_output_key};
+use crate::wallet::chain::{Chain, ChainError, MessageFormat, SignatureFormat};
+use crate::wallet::scheme::Secp256k1;
+
+/// Who a signature should be from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedBy<'a> {
+    /// The key, as SEC1 bytes.
+    PublicKey(&'a [u8]),
+    /// Whichever key the address belongs to, recovered from the signature.
+    /// EVM addresses match in any casing, checksummed or not.
+    Address(&'a str),
+}
+
+/// What a signature covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signed<'a> {
+    /// An arbitrary message, hashed behind the chain's message prefix.
+    Message(&'a [u8]),
+    /// A payload from [`Chain::prepare_transaction`], hashed with SHA-256
+    /// first unless the chain's payloads are [prehashed](Chain::prehashed).
+    Payload(&'a [u8]),
+    /// A 32-byte digest, signed as it is.
+    Prehash(&'a [u8; 32]),
+}
+
+/// Whether `signature` over `signed` is by `by` on `chain`. Malformed
+/// signatures, keys and addresses are errors; well-formed signatures by
+/// another key, or over something else, are `false`.
+pub fn verify(
+    chain: &(impl Chain<Scheme = Secp256k1> + ?Sized),
+    by: SignedBy<'_>,
+    signed: Signed<'_>,
+    signature: &[u8],
+) -> Result<bool, ChainError> {
+    let digest = digest(chain, signed)?;
+    if chain.signature_format() == SignatureFormat::Schnorr && !matches!(signed, Signed::Message(_))
+    {
+        return verify_schnorr(by, &digest, signature);
+    }
+
+    let (signature, parity) = parse_ecdsa(signature)?;
+    // Normalizing s negates the nonce point, flipping its y-parity.
+    let (signature, parity) = match signature.normalize_s() {
+        Some(normalized) => (normalized, parity.map(|p| p ^ 1)),
+        None => (signature, parity),
+    };
+    let expected = match by {
+        SignedBy::PublicKey(key) => Some(parse_sec1(key)?),
+        SignedBy::Address(address) => {
+            chain.validate_address(address)?;
+            None
+        }
+    };
+    let parities = match parity {
+        Some(parity) => vec![parity],
+        None => vec![0, 1],
+    };
+    for parity in parities {
+        let recid = RecoveryId::new(parity == 1, false);
+        let Ok(key) = VerifyingKey::recover_from_prehash(&digest, &signature, recid) else {
+            continue;
+        };
+        let matches = match by {
+            SignedBy::PublicKey(_) => expected == Some(key),
+            SignedBy::Address(address) => same_address(
+                &chain.address_from_pubkey(key.to_encoded_point(true).as_bytes())?,
+                address,
+            ),
+        };
+        if matches {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether `signatures`, one per payload of `raw_tx` as for
+/// [`Chain::finalize_transaction`], are all by `by`.
+pub fn verify_transaction(
+    chain: &(impl Chain<Scheme = Secp256k1> + ?Sized),
+    by: SignedBy<'_>,
+    raw_tx: &RawTransaction,
+    signatures: &[Vec<u8>],
+) -> Result<bool, ChainError> {
+    let payloads = chain.prepare_transaction(raw_tx)?;
+    if payloads.len() != signatures.len() {
+        return Err(ChainError::Other(format!(
+            "{} signatures for {} payloads",
+            signatures.len(),
+            payloads.len()
+        )));
+    }
+    for (payload, signature) in payloads.iter().zip(signatures) {
+        if !verify(chain, by, Signed::Payload(payload), signature)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn digest(
+    chain: &(impl Chain<Scheme = Secp256k1> + ?Sized),
+    signed: Signed<'_>,
+) -> Result<[u8; 32], ChainError> {
+    match signed {
+        Signed::Message(message) => {
+            let format: MessageFormat = chain.message_format().ok_or_else(|| {
+                ChainError::Other(format!("{} has no message signatures", chain.id()))
+            })?;
+            Ok(format.digest(message))
+        }
+        Signed::Payload(payload) if chain.prehashed() => payload.try_into().map_err(|_| {
+            ChainError::Other(format!(
+                "expected a 32-byte payload, got {} bytes",
+                payload.len()
+            ))
+        }),
+        Signed::Payload(payload) => Ok(sha256(payload)),
+        Signed::Prehash(digest) => Ok(*digest),
+    }
+}
+
+/// The signature, and its y-parity when it carries one.
+fn parse_ecdsa(signature: &[u8]) -> Result<(Signature, Option<u8>), ChainError> {
+    let invalid = |e: k256::ecdsa::Error| ChainError::Other(format!("invalid signature: {}", e));
+    match signature {
+        [0x30, len, ..] if *len as usize + 2 == signature.len() => {
+            Ok((Signature::from_der(signature).map_err(invalid)?, None))
+        }
+        [rs @ .., v @ (0 | 1 | 27 | 28)] if rs.len() == 64 => {
+            Ok((Signature::from_slice(rs).map_err(invalid)?, Some(v % 27)))
+        }
+        rs if rs.len() == 64 => Ok((Signature::from_slice(rs).map_err(invalid)?, None)),
+        _ => Err(ChainError::Other(format!(
+            "expected a DER, r || s or r || s || v signature, got {} bytes",
+            signature.len()
+        ))),
+    }
+}
+
+fn verify_schnorr(
+    by: SignedBy<'_>,
+    digest: &[u8; 32],
+    signature: &[u8],
+) -> Result<bool, ChainError> {
+    let SignedBy::PublicKey(key) = by else {
+        return Err(ChainError::Other(
+            "a Schnorr signature needs the signer's public key".to_string(),
+        ));
+    };
+    let output = k256::schnorr::VerifyingKey::from_bytes(&taproot_output_key(key)?)
+        .map_err(|_| ChainError::InvalidPublicKey)?;
+    let signature = k256::schnorr::Signature::try_from(signature)
+        .map_err(|e| ChainError::Other(format!("invalid Schnorr signature: {}", e)))?;
+    Ok(output.verify_prehash(digest, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Signer;
+    use crate::wallet::chain::sdk::taproot_tweak;
+    use crate::wallet::chain::utxo::UtxoAddressType;
+    use crate::wallet::chain::{BITCOIN, ETHEREUM, LITECOIN, TRON};
+    use crate::wallet::signer::local::LocalSigner;
+
+    #[tokio::test]
+    async fn accepts_each_encoding_by_key_or_address() {
+        let signer = LocalSigner::from_bytes([1u8; 32]).unwrap();
+        let other = LocalSigner::from_bytes([2u8; 32]).unwrap();
+        let key = Signer::<Secp256k1>::public_key(&signer);
+        let digest = [7u8; 32];
+
+        let der = signer.sign_prehash(&digest).await.unwrap();
+        let recoverable = signer.sign_recoverable(&digest).await.unwrap();
+        let mut eth_style = recoverable.clone();
+        eth_style[64] += 27;
+        let tron = TRON.address_from_pubkey(&key).unwrap();
+        for signature in [&der, &recoverable, &eth_style, &recoverable[..64].to_vec()] {
+            assert!(
+                verify(
+                    &TRON,
+                    SignedBy::PublicKey(&key),
+                    Signed::Prehash(&digest),
+                    signature
+                )
+                .unwrap()
+            );
+            assert!(
+                verify(
+                    &TRON,
+                    SignedBy::Address(&tron),
+                    Signed::Prehash(&digest),
+                    signature
+                )
+                .unwrap()
+            );
+            assert!(
+                !verify(
+                    &TRON,
+                    SignedBy::PublicKey(&key),
+                    Signed::Prehash(&[8; 32]),
+                    signature
+                )
+                .unwrap()
+            );
+        }
+        let mut wrong_v = recoverable.clone();
+        wrong_v[64] ^= 1;
+        assert!(
+            !verify(
+                &TRON,
+                SignedBy::PublicKey(&key),
+                Signed::Prehash(&digest),
+                &wrong_v
+            )
+            .unwrap()
+        );
+        let other_key = Signer::<Secp256k1>::public_key(&other);
+        assert!(
+            !verify(
+                &TRON,
+                SignedBy::PublicKey(&other_key),
+                Signed::Prehash(&digest),
+                &der
+            )
+            .unwrap()
+        );
+        assert!(
+            verify(
+                &TRON,
+                SignedBy::PublicKey(&key),
+                Signed::Prehash(&digest),
+                b"short"
+            )
+            .is_err()
+        );
+        assert!(
+            verify(
+                &TRON,
+                SignedBy::Address("not an address"),
+                Signed::Prehash(&digest),
+                &der
+            )
+            .is_err()
+        );
+
+        // Messages behind the chain's prefix; LTC payloads are sighashes.
+        let message = b"login 2026-10-16";
+        let eth = ETHEREUM.address_from_pubkey(&key).unwrap();
+        let signature = signer
+            .sign_recoverable(&ETHEREUM.message_format().unwrap().digest(message))
+            .await
+            .unwrap();
+        assert!(
+            verify(
+                &ETHEREUM,
+                SignedBy::Address(&eth),
+                Signed::Message(message),
+                &signature
+            )
+            .unwrap()
+        );
+        assert!(
+            verify(
+                &ETHEREUM,
+                SignedBy::Address(&eth.to_lowercase()),
+                Signed::Message(message),
+                &signature
+            )
+            .unwrap()
+        );
+        assert!(
+            !verify(
+                &TRON,
+                SignedBy::Address(&tron),
+                Signed::Message(message),
+                &signature
+            )
+            .unwrap()
+        );
+        assert!(
+            verify(
+                &LITECOIN,
+                SignedBy::PublicKey(&key),
+                Signed::Payload(&digest),
+                &der
+            )
+            .unwrap()
+        );
+
+        let taproot = BITCOIN.with_address_type(UtxoAddressType::P2tr);
+        let schnorr = signer
+            .sign_schnorr(&digest, Some(&taproot_tweak(&key).unwrap()))
+            .await
+            .unwrap();
+        assert!(
+            verify(
+                &taproot,
+                SignedBy::PublicKey(&key),
+                Signed::Payload(&digest),
+                &schnorr
+            )
+            .unwrap()
+        );
+        assert!(
+            !verify(
+                &taproot,
+                SignedBy::PublicKey(&other_key),
+                Signed::Payload(&digest),
+                &schnorr
+            )
+            .unwrap()
+        );
+    }
+}
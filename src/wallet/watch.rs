@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::WalletError;
+use crate::monitor::balance::{BalanceSource, BalanceWatcher};
+use crate::monitor::dedup::dedup_transactions;
+use crate::monitor::deposits::DepositTracker;
+use crate::node::{NodeError, ReadProvider, Transaction};
+use crate::wallet::chain::{Chain, ChainError};
+
+/// Watch-only view of addresses held elsewhere (cold wallets, counterparties).
+///
+/// Holds no key material, so it can read balances and history and feed the
+/// monitors, but never sign.
+pub struct WatchWallet<C: Chain> {
+    pub chain: C,
+    addresses: Vec<String>,
+}
+
+impl<C: Chain> WatchWallet<C> {
+    /// Watch `addresses`, each checked against `chain`'s address format.
+    pub fn from_addresses<I, S>(chain: C, addresses: I) -> Result<Self, ChainError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut watched: Vec<String> = Vec::new();
+        for address in addresses {
+            let address = address.into();
+            chain.validate_address(&address)?;
+            if !watched.contains(&address) {
+                watched.push(address);
+            }
+        }
+        Ok(Self {
+            chain,
+            addresses: watched,
+        })
+    }
+
+    pub fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    /// Balance of every watched address, in the provider's base unit, fetched
+    /// with [`ReadProvider::get_balances_bulk`]. A failed lookup only fails its own entry.
+    pub async fn balances(
+        &self,
+        provider: &dyn ReadProvider,
+    ) -> BTreeMap<String, Result<String, NodeError>> {
+        let balances = provider.get_balances_bulk(&self.addresses).await;
+        self.addresses.iter().cloned().zip(balances).collect()
+    }
+
+    /// History of all watched addresses, decoded like
+    /// [`Wallet::history`](crate::wallet::Wallet::history). Transfers between two
+    /// watched addresses appear once.
+    pub async fn history(
+        &self,
+        provider: &dyn ReadProvider,
+    ) -> Result<Vec<Transaction>, WalletError> {
+        let mut transactions = Vec::new();
+        for address in &self.addresses {
+            transactions.extend(provider.get_transactions(address).await?);
+        }
+        let mut transactions = dedup_transactions(self.chain.id(), transactions);
+        for tx in &mut transactions {
+            tx.details = self
+                .chain
+                .decode_provider_tx(tx)
+                .ok()
+                .flatten()
+                .or(tx.details.take());
+        }
+        Ok(transactions)
+    }
+
+    /// Add every watched address to `watcher`, labelled by its address.
+    pub fn watch_balances(
+        &self,
+        watcher: BalanceWatcher,
+        asset: &str,
+        source: Arc<dyn BalanceSource>,
+    ) -> BalanceWatcher {
+        self.addresses.iter().fold(watcher, |watcher, address| {
+            watcher.watch(address, address, asset, source.clone())
+        })
+    }
+
+    /// Add every watched address to `tracker`.
+    pub fn track_deposits(&self, tracker: DepositTracker) -> DepositTracker {
+        self.addresses
+            .iter()
+            .fold(tracker, |tracker, address| tracker.watch(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::OfflineProvider;
+    use crate::wallet::chain::TRON;
+
+    #[tokio::test]
+    async fn imports_and_reads_address_lists() {
+        let cold = "TCNkawTmcQgYSU8nP8cHswT1QPjharxJr7";
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+        let watch = WatchWallet::from_addresses(TRON, [cold, usdt, cold]).unwrap();
+        assert_eq!(watch.addresses(), [cold, usdt]);
+
+        let balances = watch.balances(&OfflineProvider).await;
+        assert_eq!(balances.get(usdt), Some(&Ok("0".to_string())));
+        assert!(watch.history(&OfflineProvider).await.unwrap().is_empty());
+
+        assert!(WatchWallet::from_addresses(TRON, ["LWKNsGErA9XxsrKVPimDAbuRXjCyyazZtc"]).is_err());
+    }
+}
@@ -1,8 +1,8 @@
-use flow_wallet::node::Provider;
+use flow_wallet::node::ReadProvider;
 use flow_wallet::node::network::ltc::LtcProvider;
 use flow_wallet::node::network::tron::TronProvider;
 use flow_wallet::wallet::Wallet;
-use flow_wallet::wallet::chain::{TRON, UtxoChain};
+use flow_wallet::wallet::chain::{AnyChain, LITECOIN_TESTNET, TRON};
 use flow_wallet::wallet::key_source::{KeySource, MnemonicKeySource};
 use std::env;
 
@@ -65,16 +65,17 @@ async fn test_ltc_send_coins_real() {
         .await
         .expect("derive");
 
-    let chain = UtxoChain {
-        name: "litecoin_testnet",
-        p2pkh_prefix: 0x6f, // LTC Testnet prefix (m or n) is 0x6f (111)
-    };
-    let wallet = Wallet::new(signer, chain);
+    let wallet = Wallet::new(signer, LITECOIN_TESTNET);
     let address = wallet.address().expect("address");
     println!("LTC Testnet Address: {}", address);
 
     // 2. Setup Real Provider (Blockcypher Testnet)
-    let provider = LtcProvider::with_url("https://api.blockcypher.com/v1/ltc/test3".to_string());
+    let provider = LtcProvider::with_url(
+        AnyChain::Utxo(LITECOIN_TESTNET)
+            .default_endpoint()
+            .expect("endpoint")
+            .to_string(),
+    );
 
     // 3. Check Balance
     let balance = provider.get_balance(&address).await.expect("get balance");
@@ -0,0 +1,66 @@
+//! Key derivation, signing and providers in a browser, where randomness,
+//! clocks and HTTP come from JavaScript:
+//!
+//! ```sh
+//! wasm-pack test --headless --firefox
+//! wasm-pack test --headless --firefox -- --no-default-features
+//! ```
+#![cfg(target_arch = "wasm32")]
+
+use flow_wallet::wallet::Wallet;
+use flow_wallet::wallet::chain::{ETHEREUM, TRON};
+use flow_wallet::wallet::key_source::{KeySource, MnemonicKeySource};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const PHRASE: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[wasm_bindgen_test]
+async fn derives_the_standard_addresses() {
+    let source = MnemonicKeySource::new(PHRASE, None).unwrap();
+
+    let tron = Wallet::new(
+        source.derive_signer("m/44'/195'/0'/0/0").await.unwrap(),
+        TRON,
+    );
+    assert_eq!(
+        tron.address().unwrap(),
+        "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH"
+    );
+    let eth = Wallet::new(
+        source.derive_signer("m/44'/60'/0'/0/0").await.unwrap(),
+        ETHEREUM,
+    );
+    assert_eq!(
+        eth.address().unwrap(),
+        "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+    );
+}
+
+#[wasm_bindgen_test]
+async fn signs_with_a_generated_mnemonic() {
+    let source = MnemonicKeySource::random(None);
+    let signer = source.derive_signer("m/44'/195'/0'/0/0").await.unwrap();
+
+    let recoverable = signer.sign_recoverable(&[7u8; 32]).await.unwrap();
+    assert_eq!(recoverable.len(), 65);
+    assert_ne!(source.phrase(), MnemonicKeySource::random(None).phrase());
+}
+
+/// Requests go through `fetch`; an unreachable endpoint is a network error
+/// rather than a hang or a panic.
+#[cfg(feature = "network")]
+#[wasm_bindgen_test]
+async fn providers_fetch_over_the_browser() {
+    use flow_wallet::node::network::tron::TronProvider;
+    use flow_wallet::node::{NodeError, ReadProvider};
+
+    let provider = TronProvider::with_url("http://127.0.0.1:9".to_string());
+    let error = provider
+        .get_balance("TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, NodeError::Network(_)), "{:?}", error);
+}